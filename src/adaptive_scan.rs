@@ -0,0 +1,82 @@
+use crate::{
+    state::{AlertRecord, AppState},
+    thresholds::Severity,
+};
+use serde::{Deserialize, Serialize};
+
+// --- ESCANEO ADAPTATIVO ---
+// Con adaptive_scan en la configuración, una turbina con alertas abiertas recibe en su
+// heartbeat una espera entre escaneos más corta (warning_wait_sec, o critical_wait_sec si
+// alguna es crítica), para concentrar la atención en las unidades sospechosas. Vuelve a la
+// normal en cuanto deja de tener alertas abiertas recientes: al resolverse (a mano o con
+// auto_resolve) o tras quiet_after_sec sin alertas nuevas. Nunca alarga la espera: si la
+// planificación o un refuerzo de nivel ya la dejan más corta, se queda esa.
+
+fn default_quiet_after_sec() -> u64 {
+    7200
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AdaptiveScanConfig {
+    // Espera entre escaneos con alguna alerta de aviso abierta
+    pub warning_wait_sec: u64,
+    // Con alguna crítica abierta (None = la de aviso)
+    #[serde(default)]
+    pub critical_wait_sec: Option<u64>,
+    // Segundos sin alertas nuevas tras los que se relaja aunque no se hayan resuelto
+    #[serde(default = "default_quiet_after_sec")]
+    pub quiet_after_sec: u64,
+}
+
+impl AdaptiveScanConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.warning_wait_sec == 0 || self.critical_wait_sec == Some(0) {
+            return Err("adaptive_scan: wait times must be positive".into());
+        }
+        if self.critical_wait_sec.is_some_and(|c| c > self.warning_wait_sec) {
+            return Err("adaptive_scan: critical_wait_sec must not exceed warning_wait_sec".into());
+        }
+        if self.quiet_after_sec == 0 {
+            return Err("adaptive_scan: quiet_after_sec must be positive".into());
+        }
+        Ok(())
+    }
+
+    // Espera que impone la política según las alertas de la turbina (None = ninguna abierta)
+    pub fn wait_for<'a>(&self, alerts: impl IntoIterator<Item = &'a AlertRecord>, turbine_token: &str, now: u64) -> Option<u64> {
+        let severity = alerts.into_iter()
+            .filter(|a| a.turbine_token == turbine_token && a.is_open(now))
+            .filter(|a| a.timestamp.saturating_add(self.quiet_after_sec) >= now)
+            .map(|a| a.severity)
+            .max()?;
+        Some(match severity {
+            Severity::Critical => self.critical_wait_sec.unwrap_or(self.warning_wait_sec),
+            Severity::Warning => self.warning_wait_sec,
+        })
+    }
+}
+
+impl AppState {
+    // Espera adaptativa de una turbina ahora mismo; registra en el log cuando se
+    // intensifica, cambia o se relaja
+    pub async fn adaptive_scan_wait(&self, config: Option<&AdaptiveScanConfig>, turbine_token: &str, now: u64) -> Option<u64> {
+        let wait = match config {
+            Some(config) => config.wait_for(self.alerts.read().await.iter(), turbine_token, now),
+            None => None,
+        };
+        let mut applied = self.adaptive_scans.write().await;
+        let previous = match wait {
+            Some(wait) => applied.insert(turbine_token.to_string(), wait),
+            None => applied.remove(turbine_token),
+        };
+        match (previous, wait) {
+            (None, Some(wait)) => tracing::info!(%turbine_token, scan_wait_time_sec = wait, "🎯 Escaneo intensificado: la turbina tiene alertas abiertas"),
+            (Some(before), Some(wait)) if before != wait => {
+                tracing::info!(%turbine_token, scan_wait_time_sec = wait, before, "🎯 Escaneo adaptativo ajustado");
+            }
+            (Some(_), None) => tracing::info!(%turbine_token, "😌 Escaneo relajado: sin alertas abiertas recientes"),
+            _ => {}
+        }
+        wait
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    i18n::Locale,
+    notify::{alert_summary, capture_link},
+    scada::OPEN_ALERT_WINDOW_SEC,
+    state::{AlertRecord, AppState},
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+// --- EXPORTACIÓN A ALERTMANAGER ---
+// Las alertas abiertas (disparadas en las últimas 24 h, igual que en el SCADA) en el
+// formato de la API v2 de Alertmanager, para que las alertas térmicas compartan silencios,
+// agrupación y rutas con el resto de alertas de infraestructura. GET /api/alertmanager/alerts
+// las devuelve tal cual para quien prefiera recogerlas; con --alertmanager-url se envían a
+// POST /api/v2/alerts al dispararse y se reenvían cada --alertmanager-interval-sec, como
+// hace Prometheus. endsAt es el final de la ventana de alerta abierta, así que Alertmanager
+// las resuelve solas aunque este servidor deje de enviarlas; las resueltas al normalizarse
+// la temperatura se envían una última vez con endsAt en el instante de la resolución.
+
+const PUSH_TIMEOUT: Duration = Duration::from_secs(15);
+const MIN_INTERVAL_SEC: u64 = 15;
+pub const ALERT_NAME: &str = "SentinelThermalAlert";
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertmanagerAlert {
+    pub labels: BTreeMap<String, String>,
+    pub annotations: BTreeMap<String, String>,
+    pub starts_at: String,
+    pub ends_at: String,
+    // Enlace a la captura; solo con --public-url (Alertmanager exige una URL válida)
+    #[serde(rename = "generatorURL", skip_serializing_if = "Option::is_none")]
+    pub generator_url: Option<String>,
+}
+
+fn rfc3339(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0).map(|d| d.to_rfc3339()).unwrap_or_default()
+}
+
+// Las etiquetas identifican la alerta en Alertmanager (una por alert_id); las que sirven
+// para agrupar y silenciar son turbine, site, severity y zone
+pub fn to_alertmanager(state: &AppState, alert: &AlertRecord, site: Option<&str>) -> AlertmanagerAlert {
+    let mut labels = BTreeMap::from([
+        ("alertname".to_string(), ALERT_NAME.to_string()),
+        ("alert_id".to_string(), alert.id.clone()),
+        ("turbine".to_string(), alert.turbine_token.clone()),
+        ("severity".to_string(), Locale::En.severity(alert.severity).to_string()),
+    ]);
+    let optional = [
+        ("site", site.map(str::to_string)),
+        ("zone", alert.zone.clone()),
+        ("level", alert.level.clone()),
+        ("incident", alert.incident_id.clone()),
+    ];
+    labels.extend(optional.into_iter().filter_map(|(name, value)| Some((name.to_string(), value?))));
+
+    let mut annotations = BTreeMap::from([
+        ("summary".to_string(), alert_summary(alert, Locale::En)),
+        ("max_temp".to_string(), format!("{:.1}", alert.max_temp)),
+        ("angle".to_string(), format!("{:.1}", alert.angle)),
+        ("dataset".to_string(), alert.dataset_path.clone()),
+    ]);
+    if !alert.rules.is_empty() {
+        annotations.insert("rules".to_string(), alert.rules.join(", "));
+    }
+    if let Some(links) = &alert.links {
+        annotations.insert("thumbnail".to_string(), links.thumbnail.clone());
+    }
+
+    AlertmanagerAlert {
+        labels,
+        annotations,
+        starts_at: rfc3339(alert.timestamp),
+        ends_at: rfc3339(match &alert.resolution {
+            Some(resolution) => resolution.timestamp,
+            None => alert.timestamp.saturating_add(OPEN_ALERT_WINDOW_SEC),
+        }),
+        generator_url: state.settings.public_url.as_ref().map(|_| capture_link(state, &alert.dataset_path)),
+    }
+}
+
+async fn turbine_site(state: &AppState, token: &str) -> Option<String> {
+    state.turbines.read().await.iter().find(|t| t.token == token).and_then(|t| t.site.clone())
+}
+
+// Alertas abiertas ahora mismo, de la más reciente a la más antigua
+pub async fn firing_alerts(state: &AppState) -> Vec<AlertmanagerAlert> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let sites: BTreeMap<String, String> = state.turbines.read().await.iter()
+        .filter_map(|t| Some((t.token.clone(), t.site.clone()?)))
+        .collect();
+    state.alerts.read().await.iter()
+        .filter(|a| a.is_open(now))
+        .map(|a| to_alertmanager(state, a, sites.get(&a.turbine_token).map(String::as_str)))
+        .collect()
+}
+
+async fn post_alerts(http: &reqwest::Client, url: &str, alerts: &[AlertmanagerAlert]) -> Result<(), String> {
+    http.post(format!("{}/api/v2/alerts", url.trim_end_matches('/')))
+        .timeout(PUSH_TIMEOUT)
+        .json(alerts)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// Envío inmediato de una alerta recién disparada (sin esperar al siguiente reenvío)
+pub async fn forward_alert(state: &AppState, alert: &AlertRecord) {
+    let Some(url) = state.settings.alertmanager_url.clone() else { return };
+    let site = turbine_site(state, &alert.turbine_token).await;
+    let payload = vec![to_alertmanager(state, alert, site.as_deref())];
+    let http = state.http.clone();
+    tokio::spawn(async move {
+        if let Err(e) = post_alerts(&http, &url, &payload).await {
+            tracing::warn!(error = %e, "⚠️ Error enviando la alerta a Alertmanager; se reenviará en la próxima sincronización");
+        }
+    });
+}
+
+pub fn spawn_alertmanager_sync(state: &Arc<AppState>) {
+    let Some(url) = state.settings.alertmanager_url.clone() else { return };
+    let interval = Duration::from_secs(state.settings.alertmanager_interval_sec.max(MIN_INTERVAL_SEC));
+    tracing::info!(%url, "📟 Alertas abiertas exportadas a Alertmanager");
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let alerts = firing_alerts(&state).await;
+            if alerts.is_empty() {
+                continue;
+            }
+            if let Err(e) = post_alerts(&state.http, &url, &alerts).await {
+                tracing::warn!(error = %e, alerts = alerts.len(), "⚠️ Error reenviando las alertas abiertas a Alertmanager");
+            }
+        }
+    });
+}
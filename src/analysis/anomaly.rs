@@ -0,0 +1,34 @@
+use super::CaptureStats;
+
+// --- PUNTUACIÓN DE ANOMALÍA ---
+// Z-score de la captura frente al historial reciente de la misma turbina y ángulo:
+// cuántas desviaciones típicas se aleja su máxima (o su media) de lo habitual. Saca a
+// la luz frames raros aunque no lleguen a ningún umbral de alerta.
+
+// Capturas anteriores que forman el historial
+pub const ANOMALY_WINDOW: usize = 30;
+// Por debajo de este historial no se puntúa
+pub const MIN_HISTORY: usize = 5;
+// Dos capturas son "del mismo ángulo" si difieren menos que esto (grados)
+pub const ANGLE_TOLERANCE: f32 = 0.25;
+// Puntuación a partir de la cual se deja constancia en el log
+pub const ANOMALY_NOTICE: f32 = 3.0;
+// Desviación mínima (°C) para no disparar la puntuación con historiales casi constantes
+const MIN_STD: f32 = 0.1;
+
+fn z_score(history: impl Iterator<Item = f32> + Clone, value: f32) -> f32 {
+    let n = history.clone().count() as f32;
+    let mean = history.clone().sum::<f32>() / n;
+    let variance = history.map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    (value - mean) / variance.sqrt().max(MIN_STD)
+}
+
+// Mayor de los z-scores de máxima y media (positivo = más caliente de lo habitual)
+pub fn anomaly_score(history: &[CaptureStats], stats: &CaptureStats) -> Option<f32> {
+    if history.len() < MIN_HISTORY {
+        return None;
+    }
+    let z_max = z_score(history.iter().map(|h| h.max_temp), stats.max_temp);
+    let z_avg = z_score(history.iter().map(|h| h.avg_temp), stats.avg_temp);
+    Some(z_max.max(z_avg))
+}
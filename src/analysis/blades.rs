@@ -0,0 +1,234 @@
+use super::{hottest_frame, with_frames, FrameError};
+use ndarray::{ArrayView2, Axis};
+use serde::{Deserialize, Serialize};
+
+// --- SEGMENTACIÓN POR PALA ---
+// Máscara geométrica del rotor visto de frente: un círculo de buje en el centro y
+// `blade_count` sectores estrechos (las palas) hasta el radio del rotor; el resto es
+// fondo. Los ángulos se miden en grados en sentido horario desde las 12 en punto; la
+// fase del rotor es el ángulo de la pala A en el momento de la captura.
+// Una pala claramente más caliente que las demás es la firma típica de un fallo de
+// rodamiento de pitch.
+
+fn default_blade_count() -> usize {
+    3
+}
+
+fn default_blade_width_deg() -> f32 {
+    20.0
+}
+
+fn default_imbalance_min_delta() -> f32 {
+    5.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BladeGeometry {
+    // Centro del buje como fracción del ancho/alto del frame (0..1)
+    pub center_x: f32,
+    pub center_y: f32,
+    // Radios como fracción del lado menor del frame
+    pub hub_radius: f32,
+    pub rotor_radius: f32,
+    #[serde(default = "default_blade_count")]
+    pub blade_count: usize,
+    // Anchura angular de cada pala en la máscara
+    #[serde(default = "default_blade_width_deg")]
+    pub blade_width_deg: f32,
+    // Fase por defecto cuando la captura no indica la suya
+    #[serde(default)]
+    pub rotor_phase_deg: f32,
+    // Diferencia mínima (°C) entre la pala más caliente y las demás para señalarla
+    #[serde(default = "default_imbalance_min_delta")]
+    pub imbalance_min_delta: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    Background,
+    Hub,
+    Blade(usize),
+}
+
+impl BladeGeometry {
+    pub fn validate(&self) -> Result<(), String> {
+        let unit = |v: f32| (0.0..=1.0).contains(&v);
+        if !unit(self.center_x) || !unit(self.center_y) {
+            return Err("blade_geometry: center must be within 0..=1".into());
+        }
+        if !(self.hub_radius > 0.0 && self.hub_radius < self.rotor_radius) {
+            return Err("blade_geometry: hub_radius must be positive and below rotor_radius".into());
+        }
+        if !(2..=6).contains(&self.blade_count) {
+            return Err("blade_geometry: blade_count must be within 2..=6".into());
+        }
+        let sector = 360.0 / self.blade_count as f32;
+        if !(self.blade_width_deg > 0.0 && self.blade_width_deg < sector) {
+            return Err("blade_geometry: blade_width_deg does not fit between blades".into());
+        }
+        if ![self.rotor_radius, self.rotor_phase_deg, self.imbalance_min_delta].iter().all(|v| v.is_finite()) {
+            return Err("blade_geometry: invalid values".into());
+        }
+        Ok(())
+    }
+
+    // Región a la que pertenece un píxel de un frame rows × cols
+    pub fn region(&self, row: usize, col: usize, rows: usize, cols: usize, phase_deg: f32) -> Region {
+        let scale = rows.min(cols) as f32;
+        let dx = col as f32 + 0.5 - self.center_x * cols as f32;
+        let dy = row as f32 + 0.5 - self.center_y * rows as f32;
+        let radius = (dx * dx + dy * dy).sqrt() / scale;
+        if radius <= self.hub_radius {
+            return Region::Hub;
+        }
+        if radius > self.rotor_radius {
+            return Region::Background;
+        }
+        // Horario desde arriba: las filas crecen hacia abajo
+        let angle = dx.atan2(-dy).to_degrees();
+        let sector = 360.0 / self.blade_count as f32;
+        let relative = (angle - phase_deg).rem_euclid(360.0);
+        let blade = (relative / sector).round() as usize % self.blade_count;
+        let diff = (relative - blade as f32 * sector).rem_euclid(360.0);
+        let offset = diff.min(360.0 - diff);
+        if offset <= self.blade_width_deg / 2.0 {
+            Region::Blade(blade)
+        } else {
+            Region::Background
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RegionStats {
+    pub pixels: usize,
+    pub max_temp: Option<f32>,
+    pub mean_temp: Option<f32>,
+}
+
+#[derive(Default)]
+struct Accumulator {
+    pixels: usize,
+    sum: f64,
+    max: f32,
+}
+
+impl Accumulator {
+    fn push(&mut self, value: f32) {
+        self.max = if self.pixels == 0 { value } else { self.max.max(value) };
+        self.pixels += 1;
+        self.sum += value as f64;
+    }
+
+    fn stats(&self) -> RegionStats {
+        RegionStats {
+            pixels: self.pixels,
+            max_temp: (self.pixels > 0).then_some(self.max),
+            mean_temp: (self.pixels > 0).then(|| (self.sum / self.pixels as f64) as f32),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BladeStats {
+    // A, B, C... en sentido horario desde la pala de referencia
+    pub name: String,
+    #[serde(flatten)]
+    pub stats: RegionStats,
+}
+
+// Pala significativamente más caliente que el resto
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BladeImbalance {
+    pub blade: String,
+    // Diferencia con la más caliente de las demás palas
+    pub delta_temp: f32,
+    pub summary: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BladeReport {
+    pub frame_index: usize,
+    pub rotor_phase_deg: f32,
+    pub hub: RegionStats,
+    pub background: RegionStats,
+    pub blades: Vec<BladeStats>,
+    pub imbalance: Option<BladeImbalance>,
+}
+
+fn blade_name(index: usize) -> String {
+    char::from(b'A' + index as u8).to_string()
+}
+
+pub fn segment_frame(
+    frame: ArrayView2<f32>,
+    frame_index: usize,
+    geometry: &BladeGeometry,
+    phase_deg: f32,
+) -> BladeReport {
+    let (rows, cols) = frame.dim();
+    let mut hub = Accumulator::default();
+    let mut background = Accumulator::default();
+    let mut blades: Vec<Accumulator> = (0..geometry.blade_count).map(|_| Accumulator::default()).collect();
+    for ((row, col), &value) in frame.indexed_iter() {
+        match geometry.region(row, col, rows, cols, phase_deg) {
+            Region::Hub => hub.push(value),
+            Region::Background => background.push(value),
+            Region::Blade(i) => blades[i].push(value),
+        }
+    }
+
+    let blades: Vec<BladeStats> = blades.iter().enumerate()
+        .map(|(i, acc)| BladeStats { name: blade_name(i), stats: acc.stats() })
+        .collect();
+    BladeReport {
+        frame_index,
+        rotor_phase_deg: phase_deg,
+        imbalance: find_imbalance(&blades, geometry.imbalance_min_delta),
+        hub: hub.stats(),
+        background: background.stats(),
+        blades,
+    }
+}
+
+fn find_imbalance(blades: &[BladeStats], min_delta: f32) -> Option<BladeImbalance> {
+    let maxima: Vec<(&str, f32)> = blades.iter()
+        .filter_map(|b| Some((b.name.as_str(), b.stats.max_temp?)))
+        .collect();
+    if maxima.len() < 2 {
+        return None;
+    }
+    let (hottest, hottest_temp) = *maxima.iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let others: Vec<&str> = maxima.iter().filter(|(n, _)| *n != hottest).map(|(n, _)| *n).collect();
+    let others_max = maxima.iter()
+        .filter(|(n, _)| *n != hottest)
+        .map(|(_, t)| *t)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let delta_temp = hottest_temp - others_max;
+    (delta_temp >= min_delta).then(|| BladeImbalance {
+        blade: hottest.to_string(),
+        delta_temp,
+        summary: format!("blade {} is {:.1}°C hotter than {}", hottest, delta_temp, others.join(" and ")),
+    })
+}
+
+// Segmentación de un frame concreto de la captura
+pub fn blade_report(
+    bytes: &[u8],
+    frame_index: usize,
+    geometry: &BladeGeometry,
+    phase_deg: f32,
+) -> Result<BladeReport, FrameError> {
+    with_frames(bytes, |frames| {
+        (frame_index < frames.len_of(Axis(0)))
+            .then(|| segment_frame(frames.index_axis(Axis(0), frame_index), frame_index, geometry, phase_deg))
+    })
+    .ok_or(FrameError::Unreadable)?
+    .ok_or(FrameError::OutOfRange)
+}
+
+// Segmentación del frame más caliente de la captura (el que dispara la alerta)
+pub fn hottest_frame_blades(bytes: &[u8], geometry: &BladeGeometry, phase_deg: f32) -> Option<BladeReport> {
+    let (frame_index, frame) = hottest_frame(bytes)?;
+    Some(segment_frame(frame.view(), frame_index, geometry, phase_deg))
+}
@@ -0,0 +1,105 @@
+use super::ThermalFrameData;
+use serde::{Deserialize, Serialize};
+
+// --- CODIFICACIÓN COMPACTA DE LOS FRAMES ---
+// Buena parte de un frame suele ser cielo o fondo casi uniforme. Con ?encoding= en
+// /api/matrix los píxeles se envían comprimidos en lugar del array `pixels` y el frontend
+// reconstruye el array completo (ancho × alto, por filas):
+// - rle: `runs` = [[valor, repeticiones], ...]; se expanden en orden.
+// - delta: `first` y `deltas` son enteros en unidades de `scale` (°C o °F):
+//   píxel[0] = first × scale y píxel[i] = píxel[i-1] + deltas[i-1] × scale.
+// `precision` (decimales) redondea los valores antes de codificar: en rle hace que el
+// ruido del sensor no rompa las series (sin ella se codifica el valor exacto); en delta
+// es la resolución de los enteros (2 por defecto, centésimas de grado).
+
+pub const DEFAULT_DELTA_PRECISION: u32 = 2;
+pub const MAX_PRECISION: u32 = 6;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatrixEncoding {
+    // El array `pixels` de siempre
+    Dense,
+    Rle,
+    Delta,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "encoding", rename_all = "lowercase")]
+pub enum EncodedPixels {
+    Rle { runs: Vec<(f32, u32)> },
+    Delta { scale: f32, first: i64, deltas: Vec<i64> },
+}
+
+// Frame con los píxeles codificados; el resto de campos, igual que ThermalFrameData
+#[derive(Serialize, Clone, Debug)]
+pub struct EncodedFrame {
+    pub width: usize,
+    pub height: usize,
+    pub min_temp: f32,
+    pub max_temp: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub angle: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+    #[serde(flatten)]
+    pub pixels: EncodedPixels,
+}
+
+// Lo que se envía de cada frame: tal cual o codificado
+#[derive(Serialize, Clone)]
+#[serde(untagged)]
+pub enum MatrixFrame {
+    Dense(ThermalFrameData),
+    Encoded(EncodedFrame),
+}
+
+fn round_to(value: f32, precision: u32) -> f32 {
+    let factor = 10f32.powi(precision as i32);
+    (value * factor).round() / factor
+}
+
+fn run_lengths(pixels: &[f32], precision: Option<u32>) -> Vec<(f32, u32)> {
+    let mut runs: Vec<(f32, u32)> = Vec::new();
+    for &pixel in pixels {
+        let value = precision.map_or(pixel, |p| round_to(pixel, p));
+        match runs.last_mut() {
+            // to_bits: NaN (píxel sin lectura) también forma series
+            Some((last, count)) if last.to_bits() == value.to_bits() && *count < u32::MAX => *count += 1,
+            _ => runs.push((value, 1)),
+        }
+    }
+    runs
+}
+
+// Los NaN no tienen representación entera: cuentan como 0
+fn deltas(pixels: &[f32], precision: u32) -> EncodedPixels {
+    let scale = 10f64.powi(-(precision as i32));
+    let quantized: Vec<i64> = pixels.iter()
+        .map(|&p| if p.is_finite() { (p as f64 / scale).round() as i64 } else { 0 })
+        .collect();
+    EncodedPixels::Delta {
+        scale: scale as f32,
+        first: quantized.first().copied().unwrap_or_default(),
+        deltas: quantized.windows(2).map(|w| w[1] - w[0]).collect(),
+    }
+}
+
+impl MatrixEncoding {
+    pub fn encode(self, frame: ThermalFrameData, precision: Option<u32>) -> MatrixFrame {
+        let pixels = match self {
+            MatrixEncoding::Dense => return MatrixFrame::Dense(frame),
+            MatrixEncoding::Rle => EncodedPixels::Rle { runs: run_lengths(&frame.pixels, precision) },
+            MatrixEncoding::Delta => deltas(&frame.pixels, precision.unwrap_or(DEFAULT_DELTA_PRECISION)),
+        };
+        MatrixFrame::Encoded(EncodedFrame {
+            width: frame.width,
+            height: frame.height,
+            min_temp: frame.min_temp,
+            max_temp: frame.max_temp,
+            angle: frame.angle,
+            timestamp: frame.timestamp,
+            pixels,
+        })
+    }
+}
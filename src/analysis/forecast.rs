@@ -0,0 +1,78 @@
+use serde::Serialize;
+
+// --- PRONÓSTICO DE TENDENCIA ---
+// Recta de mínimos cuadrados sobre la serie reciente de máximas de una turbina. Con
+// capturas a intervalos irregulares es más robusta que un Holt-Winters y basta para
+// estimar cuándo cruzará cada umbral si la tendencia se mantiene.
+
+// Mínimo de capturas para ajustar una tendencia
+pub const MIN_FORECAST_POINTS: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+pub struct LinearTrend {
+    // Valor ajustado en `origin` y pendiente en °C por segundo
+    origin: u64,
+    intercept: f64,
+    slope: f64,
+    // Desviación típica de los residuos, para la banda de confianza
+    residual_std: f64,
+}
+
+#[derive(Serialize)]
+pub struct ForecastPoint {
+    pub timestamp: u64,
+    pub max_temp: f32,
+    // Banda aproximada del 95%
+    pub lower: f32,
+    pub upper: f32,
+}
+
+pub fn fit_linear(series: &[(u64, f32)]) -> Option<LinearTrend> {
+    if series.len() < MIN_FORECAST_POINTS {
+        return None;
+    }
+    let origin = series.iter().map(|(t, _)| *t).max()?;
+    let n = series.len() as f64;
+    let xs: Vec<f64> = series.iter().map(|(t, _)| *t as f64 - origin as f64).collect();
+    let ys: Vec<f64> = series.iter().map(|(_, v)| *v as f64).collect();
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let sxx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    // Todas las capturas en el mismo instante: sin tendencia medible
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    let intercept = mean_y - slope * mean_x;
+    let residuals: f64 = xs.iter().zip(&ys).map(|(x, y)| (y - (intercept + slope * x)).powi(2)).sum();
+    let residual_std = (residuals / (n - 2.0).max(1.0)).sqrt();
+    Some(LinearTrend { origin, intercept, slope, residual_std })
+}
+
+impl LinearTrend {
+    pub fn slope_per_hour(&self) -> f32 {
+        (self.slope * 3600.0) as f32
+    }
+
+    pub fn predict(&self, timestamp: u64) -> ForecastPoint {
+        let value = self.intercept + self.slope * (timestamp as f64 - self.origin as f64);
+        let margin = 1.96 * self.residual_std;
+        ForecastPoint {
+            timestamp,
+            max_temp: value as f32,
+            lower: (value - margin) as f32,
+            upper: (value + margin) as f32,
+        }
+    }
+
+    // Primer instante a partir de `from` en que la tendencia alcanza `threshold`
+    // (None si nunca lo alcanza con la pendiente actual)
+    pub fn time_to_threshold(&self, threshold: f32, from: u64) -> Option<u64> {
+        if self.predict(from).max_temp >= threshold {
+            return Some(from);
+        }
+        if self.slope <= 0.0 {
+            return None;
+        }
+        let elapsed = (threshold as f64 - self.intercept) / self.slope;
+        Some((self.origin as f64 + elapsed).ceil() as u64)
+    }
+}
@@ -0,0 +1,154 @@
+use super::thumbnail::{ironbow, RgbImage};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+// --- FUSIÓN TÉRMICA / VISIBLE ---
+// El frame térmico coloreado con la paleta ironbow se superpone a la foto visible de la
+// misma posición (ver storage::photos) y se mezcla con opacidad `alpha`. Las dos cámaras
+// no ven lo mismo: la región de la foto que cubre el frame térmico sale de la calibración
+// de la cámara (photo_region); sin ella se supone que ambas encuadran igual.
+
+// Píxeles máximos de una foto que se decodifica (evita imágenes desmesuradas)
+const MAX_PHOTO_PIXELS: usize = 50_000_000;
+
+// Rectángulo de la foto, en fracción de su ancho y su alto, que cubre el frame térmico
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PhotoRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for PhotoRegion {
+    fn default() -> Self {
+        PhotoRegion { x: 0.0, y: 0.0, width: 1.0, height: 1.0 }
+    }
+}
+
+impl PhotoRegion {
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [self.x, self.y, self.width, self.height];
+        if values.iter().any(|v| !v.is_finite()) || self.width <= 0.0 || self.height <= 0.0 {
+            return Err("photo_region: width and height must be positive".into());
+        }
+        if self.x < 0.0 || self.y < 0.0 || self.x + self.width > 1.0 + f32::EPSILON || self.y + self.height > 1.0 + f32::EPSILON {
+            return Err("photo_region must lie within the photo (fractions between 0 and 1)".into());
+        }
+        Ok(())
+    }
+}
+
+fn too_large(width: usize, height: usize) -> Result<(), String> {
+    match width.saturating_mul(height) > MAX_PHOTO_PIXELS {
+        true => Err(format!("photo is too large ({}x{})", width, height)),
+        false => Ok(()),
+    }
+}
+
+// Foto JPEG o PNG decodificada a RGB de 8 bits
+pub fn decode_photo(bytes: &[u8]) -> Result<RgbImage, String> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(bytes));
+        decoder.read_info().map_err(|e| e.to_string())?;
+        let info = decoder.info().ok_or("missing JPEG header")?;
+        let (width, height) = (info.width as usize, info.height as usize);
+        too_large(width, height)?;
+        let data = decoder.decode().map_err(|e| e.to_string())?;
+        let pixels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::RGB24 => data,
+            jpeg_decoder::PixelFormat::L8 => data.iter().flat_map(|&l| [l; 3]).collect(),
+            jpeg_decoder::PixelFormat::L16 => data.chunks_exact(2).flat_map(|l| [l[0]; 3]).collect(),
+            jpeg_decoder::PixelFormat::CMYK32 => data.chunks_exact(4)
+                .flat_map(|p| [0, 1, 2].map(|c| ((255 - p[c] as u16) * (255 - p[3] as u16) / 255) as u8))
+                .collect(),
+        };
+        return Ok(RgbImage { pixels, width, height, scale: 1.0 });
+    }
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let (width, height) = (reader.info().width as usize, reader.info().height as usize);
+    too_large(width, height)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(|e| e.to_string())?;
+    let data = &buffer[..frame.buffer_size()];
+    let pixels = match frame.color_type {
+        png::ColorType::Rgb => data.to_vec(),
+        png::ColorType::Rgba => data.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect(),
+        png::ColorType::Grayscale => data.iter().flat_map(|&l| [l; 3]).collect(),
+        png::ColorType::GrayscaleAlpha => data.chunks_exact(2).flat_map(|p| [p[0]; 3]).collect(),
+        png::ColorType::Indexed => return Err("unsupported PNG color type".into()),
+    };
+    Ok(RgbImage { pixels, width, height, scale: 1.0 })
+}
+
+// Reduce la foto (vecino más próximo) para que el lado mayor no pase de `max_side`
+pub fn fit_photo(photo: RgbImage, max_side: usize) -> RgbImage {
+    let longest = photo.width.max(photo.height);
+    if longest <= max_side {
+        return photo;
+    }
+    let scale = max_side as f32 / longest as f32;
+    let width = ((photo.width as f32 * scale).round() as usize).max(1);
+    let height = ((photo.height as f32 * scale).round() as usize).max(1);
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for row in 0..height {
+        let source_row = (row * photo.height / height).min(photo.height - 1);
+        for col in 0..width {
+            let source = (source_row * photo.width + (col * photo.width / width).min(photo.width - 1)) * 3;
+            pixels.extend_from_slice(&photo.pixels[source..source + 3]);
+        }
+    }
+    RgbImage { pixels, width, height, scale }
+}
+
+// Valor del frame en una posición fraccionaria (interpolación bilineal)
+fn sample(frame: &Array2<f32>, row: f32, col: f32) -> f32 {
+    let (rows, cols) = frame.dim();
+    let (r0, c0) = (row.floor().max(0.0) as usize, col.floor().max(0.0) as usize);
+    let (r0, c0) = (r0.min(rows - 1), c0.min(cols - 1));
+    let (r1, c1) = ((r0 + 1).min(rows - 1), (c0 + 1).min(cols - 1));
+    let (fr, fc) = ((row - r0 as f32).clamp(0.0, 1.0), (col - c0 as f32).clamp(0.0, 1.0));
+    let top = frame[[r0, c0]] * (1.0 - fc) + frame[[r0, c1]] * fc;
+    let bottom = frame[[r1, c0]] * (1.0 - fc) + frame[[r1, c1]] * fc;
+    top * (1.0 - fr) + bottom * fr
+}
+
+// Mezcla el frame coloreado sobre la región de la foto: alpha 0 = solo la foto, 1 = solo
+// el térmico. Los píxeles no finitos del frame dejan ver la foto
+pub fn fuse(photo: &mut RgbImage, frame: &Array2<f32>, region: PhotoRegion, alpha: f32) {
+    let (rows, cols) = frame.dim();
+    if rows == 0 || cols == 0 {
+        return;
+    }
+    let finite = || frame.iter().copied().filter(|v| v.is_finite());
+    let min = finite().fold(f32::INFINITY, f32::min);
+    let max = finite().fold(f32::NEG_INFINITY, f32::max);
+    let span = if max > min { max - min } else { 1.0 };
+
+    let (width, height) = (photo.width as f32, photo.height as f32);
+    let left = (region.x * width).round() as usize;
+    let top = (region.y * height).round() as usize;
+    let right = (((region.x + region.width) * width).round() as usize).min(photo.width);
+    let bottom = (((region.y + region.height) * height).round() as usize).min(photo.height);
+    let region_width = right.saturating_sub(left).max(1) as f32;
+    let region_height = bottom.saturating_sub(top).max(1) as f32;
+    for y in top..bottom {
+        // Centro del píxel de la foto, llevado a coordenadas del frame
+        let row = ((y - top) as f32 + 0.5) / region_height * rows as f32 - 0.5;
+        for x in left..right {
+            let col = ((x - left) as f32 + 0.5) / region_width * cols as f32 - 0.5;
+            let value = sample(frame, row, col);
+            if !value.is_finite() {
+                continue;
+            }
+            let color = ironbow((value - min) / span);
+            let pixel = &mut photo.pixels[(y * photo.width + x) * 3..][..3];
+            for (channel, thermal) in pixel.iter_mut().zip(color) {
+                *channel = (*channel as f32 * (1.0 - alpha) + thermal as f32 * alpha).round() as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use super::hottest_frame;
+use crate::state::RemoteConfig;
+use ndarray::ArrayView2;
+use serde::{Deserialize, Serialize};
+
+// --- CALIDAD DE IMAGEN DE UNA CAPTURA ---
+// Puntuación de 0 a 1 del frame más caliente de cada captura, calculada al recibirla, para
+// apartar las tomas defectuosas (desenfocadas, saturadas, con el sensor dañado o movidas)
+// de las líneas base, las tendencias y el clasificador (ver RemoteConfig::min_capture_quality).
+// Son indicadores aproximados, sin referencia externa:
+// - contraste: desviación típica del frame (°C); un frame casi plano no muestra nada
+// - enfoque: media del laplaciano relativa al contraste; el desenfoque suaviza los bordes
+// - saturación: porcentaje de píxeles clavados en el mínimo o el máximo del frame
+// - píxeles muertos: no finitos o aislados (muy distintos de sus cuatro vecinos)
+// - movimiento: anisotropía de los gradientes; una toma movida pierde detalle en una dirección
+
+// Contraste (°C) a partir del cual no penaliza
+const CONTRAST_REFERENCE: f32 = 1.0;
+// Enfoque a partir del cual no penaliza
+const FOCUS_REFERENCE: f32 = 0.1;
+// Diferencia (°C) con todos sus vecinos para considerar un píxel muerto o caliente
+const DEAD_PIXEL_DELTA: f32 = 10.0;
+// Cuánto penaliza la proporción de píxeles muertos (un 10 % deja la puntuación en 0)
+const DEAD_PIXEL_WEIGHT: f32 = 10.0;
+// Peso del indicador de movimiento (la anisotropía también la tienen escenas nítidas)
+const MOTION_BLUR_WEIGHT: f32 = 0.5;
+// Tolerancia para contar un píxel como clavado en un extremo
+const SATURATION_TOLERANCE: f32 = 1e-3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CaptureQuality {
+    // 0 (inservible) a 1
+    pub score: f32,
+    pub contrast: f32,
+    pub focus: f32,
+    pub saturation_pct: f32,
+    pub dead_pixel_ratio: f32,
+    // 0 (gradientes iguales en ambas direcciones) a 1
+    pub motion_blur: f32,
+}
+
+fn gradient_energy(pairs: impl Iterator<Item = (f32, f32)>) -> f32 {
+    let (sum, count) = pairs.filter(|(a, b)| a.is_finite() && b.is_finite())
+        .fold((0.0f32, 0usize), |(sum, count), (a, b)| (sum + (a - b).powi(2), count + 1));
+    if count == 0 { 0.0 } else { (sum / count as f32).sqrt() }
+}
+
+// None si el frame es demasiado pequeño (menos de 3×3) para medir nada
+pub fn frame_quality(frame: ArrayView2<f32>) -> Option<CaptureQuality> {
+    let (rows, cols) = frame.dim();
+    if rows < 3 || cols < 3 {
+        return None;
+    }
+    let total = (rows * cols) as f32;
+    let finite: Vec<f32> = frame.iter().copied().filter(|v| v.is_finite()).collect();
+    if finite.len() < 2 {
+        return Some(CaptureQuality { score: 0.0, contrast: 0.0, focus: 0.0, saturation_pct: 0.0, dead_pixel_ratio: 1.0, motion_blur: 0.0 });
+    }
+    let n = finite.len() as f32;
+    let mean = finite.iter().sum::<f32>() / n;
+    let contrast = (finite.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n).sqrt();
+
+    // Píxeles en el mínimo o el máximo; uno solo en cada extremo es lo normal
+    let min = finite.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = finite.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let at = |value: f32| finite.iter().filter(|v| (**v - value).abs() <= SATURATION_TOLERANCE).count();
+    let clipped = |count: usize| if count > 1 { count } else { 0 };
+    let saturated = if max - min <= SATURATION_TOLERANCE { finite.len() } else { clipped(at(min)) + clipped(at(max)) };
+    let saturation_pct = saturated as f32 / n * 100.0;
+
+    let (mut laplacian, mut interior, mut dead) = (0.0f32, 0usize, frame.len() - finite.len());
+    for row in 1..rows - 1 {
+        for col in 1..cols - 1 {
+            let center = frame[[row, col]];
+            let neighbours = [frame[[row - 1, col]], frame[[row + 1, col]], frame[[row, col - 1]], frame[[row, col + 1]]];
+            if !center.is_finite() || neighbours.iter().any(|v| !v.is_finite()) {
+                continue;
+            }
+            let above = neighbours.iter().all(|v| center - v > DEAD_PIXEL_DELTA);
+            let below = neighbours.iter().all(|v| v - center > DEAD_PIXEL_DELTA);
+            if above || below {
+                dead += 1;
+                continue;
+            }
+            laplacian += (neighbours.iter().sum::<f32>() - 4.0 * center).abs();
+            interior += 1;
+        }
+    }
+    let focus = match (interior, contrast > 0.0) {
+        (0, _) | (_, false) => 0.0,
+        _ => laplacian / interior as f32 / contrast,
+    };
+    let dead_pixel_ratio = dead as f32 / total;
+
+    // Energía (media cuadrática) del gradiente en horizontal y en vertical: al contrario
+    // que la media de su valor absoluto, baja cuando un borde se emborrona
+    let horizontal = gradient_energy(frame.windows((1, 2)).into_iter().map(|w| (w[[0, 0]], w[[0, 1]])));
+    let vertical = gradient_energy(frame.windows((2, 1)).into_iter().map(|w| (w[[0, 0]], w[[1, 0]])));
+    let strongest = horizontal.max(vertical);
+    let motion_blur = if strongest > 0.0 { 1.0 - horizontal.min(vertical) / strongest } else { 0.0 };
+
+    let score = (contrast / CONTRAST_REFERENCE).min(1.0)
+        * (focus / FOCUS_REFERENCE).min(1.0)
+        * (1.0 - saturation_pct / 100.0)
+        * (1.0 - (dead_pixel_ratio * DEAD_PIXEL_WEIGHT).min(1.0))
+        * (1.0 - MOTION_BLUR_WEIGHT * motion_blur);
+    Some(CaptureQuality { score: score.clamp(0.0, 1.0), contrast, focus, saturation_pct, dead_pixel_ratio, motion_blur })
+}
+
+// Calidad de una captura: la de su frame más caliente
+pub fn capture_quality(bytes: &[u8]) -> Option<CaptureQuality> {
+    let (_, frame) = hottest_frame(bytes)?;
+    frame_quality(frame.view())
+}
+
+impl RemoteConfig {
+    // Si una captura cuenta para líneas base, tendencias y el clasificador; las que no
+    // tienen puntuación (anteriores a ella o ilegibles) cuentan siempre
+    pub fn usable_quality(&self, quality: Option<&CaptureQuality>) -> bool {
+        match (self.min_capture_quality, quality) {
+            (Some(min), Some(quality)) => quality.score >= min,
+            _ => true,
+        }
+    }
+}
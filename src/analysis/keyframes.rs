@@ -0,0 +1,172 @@
+use super::with_frames;
+use ndarray::{Array2, ArrayView2};
+use serde::Serialize;
+use std::collections::HashMap;
+
+// --- FOTOGRAMAS CLAVE ---
+// Una sesión larga tiene cientos de frames casi idénticos; a los informes y al análisis
+// con IA les basta con unos pocos: el más caliente, los que más cambian respecto a sus
+// vecinos y uno por cada punto caliente distinto (misma cámara, orientación y posición
+// del máximo en la imagen = mismo punto).
+
+// Distancia máxima entre dos máximos (en fracción de la diagonal) para ser el mismo punto
+const SAME_HOTSPOT_DISTANCE: f32 = 0.1;
+// Diferencia máxima de ángulo (grados) para ser el mismo punto
+const SAME_HOTSPOT_ANGLE: f32 = 2.0;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyframeReason {
+    // La máxima más alta de la sesión
+    Hottest,
+    // Un punto caliente que no aparece en los frames ya elegidos
+    Hotspot,
+    // Gran diferencia con el frame anterior o el siguiente de la misma cámara
+    Change,
+}
+
+// Rasgos de un frame para elegir los fotogramas clave
+#[derive(Clone, Debug)]
+pub struct FrameFeatures {
+    pub frame_index: usize,
+    pub max_temp: f32,
+    pub avg_temp: f32,
+    // Posición del máximo (fila, columna) en fracción del alto y del ancho
+    pub hotspot: (f32, f32),
+    // Diferencia media absoluta con el frame anterior de la secuencia (None en el primero
+    // o si cambia la resolución)
+    pub change_prev: Option<f32>,
+}
+
+fn mean_abs_diff(a: ArrayView2<f32>, b: ArrayView2<f32>) -> f32 {
+    let sum: f32 = a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).sum();
+    sum / a.len().max(1) as f32
+}
+
+// Rasgos de cada frame de una captura. `previous` es el último frame visto de la misma
+// cámara (de esta captura o de la anterior) y queda apuntando al último de esta.
+pub fn frame_features(bytes: &[u8], previous: &mut Option<Array2<f32>>) -> Option<Vec<FrameFeatures>> {
+    with_frames(bytes, |frames| {
+        frames.outer_iter()
+            .enumerate()
+            .map(|(frame_index, frame)| {
+                let (rows, cols) = frame.dim();
+                let (mut max_temp, mut at) = (f32::NEG_INFINITY, (0, 0));
+                for (position, &value) in frame.indexed_iter() {
+                    if value > max_temp {
+                        max_temp = value;
+                        at = position;
+                    }
+                }
+                let change_prev = previous.as_ref()
+                    .filter(|p| p.dim() == frame.dim())
+                    .map(|p| mean_abs_diff(p.view(), frame));
+                *previous = Some(frame.to_owned());
+                FrameFeatures {
+                    frame_index,
+                    max_temp,
+                    avg_temp: frame.mean().unwrap_or(0.0),
+                    hotspot: (at.0 as f32 / rows.max(1) as f32, at.1 as f32 / cols.max(1) as f32),
+                    change_prev,
+                }
+            })
+            .collect()
+    })
+}
+
+// Un frame de la sesión, en orden cronológico
+pub struct Candidate {
+    pub features: FrameFeatures,
+    pub angle: Option<f32>,
+    // Cámara (o secuencia) a la que pertenece: los vecinos y los puntos calientes se
+    // comparan solo dentro de la misma
+    pub sequence: usize,
+}
+
+fn same_hotspot(a: &Candidate, b: &Candidate) -> bool {
+    if a.sequence != b.sequence {
+        return false;
+    }
+    let same_angle = match (a.angle, b.angle) {
+        (Some(x), Some(y)) => (x - y).abs() <= SAME_HOTSPOT_ANGLE,
+        (None, None) => true,
+        _ => false,
+    };
+    let (dy, dx) = (a.features.hotspot.0 - b.features.hotspot.0, a.features.hotspot.1 - b.features.hotspot.1);
+    same_angle && (dx * dx + dy * dy).sqrt() / std::f32::consts::SQRT_2 <= SAME_HOTSPOT_DISTANCE
+}
+
+// Mayor diferencia de cada frame con su vecino anterior o siguiente de la misma secuencia
+fn neighbor_changes(candidates: &[Candidate]) -> Vec<Option<f32>> {
+    let mut changes: Vec<Option<f32>> = candidates.iter().map(|c| c.features.change_prev).collect();
+    let mut last: HashMap<usize, usize> = HashMap::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        if let Some(&previous) = last.get(&candidate.sequence)
+            && let Some(change) = candidate.features.change_prev
+        {
+            changes[previous] = Some(changes[previous].map_or(change, |c| c.max(change)));
+        }
+        last.insert(candidate.sequence, i);
+    }
+    changes
+}
+
+// Índices de hasta `count` fotogramas clave, en el orden de `candidates`, con el motivo
+// de cada uno. Se alternan puntos calientes (más de `hotspot_margin` sobre la mediana de
+// las máximas de la sesión) y cambios, tras el frame más caliente.
+pub fn select_keyframes(candidates: &[Candidate], count: usize, hotspot_margin: f32) -> Vec<(usize, Vec<KeyframeReason>)> {
+    let hottest = candidates.iter()
+        .enumerate()
+        .filter(|(_, c)| c.features.max_temp.is_finite())
+        .max_by(|(_, a), (_, b)| a.features.max_temp.total_cmp(&b.features.max_temp))
+        .map(|(i, _)| i);
+    let Some(hottest) = hottest.filter(|_| count > 0) else {
+        return Vec::new();
+    };
+
+    let mut maxima: Vec<f32> = candidates.iter().map(|c| c.features.max_temp).filter(|t| t.is_finite()).collect();
+    maxima.sort_by(f32::total_cmp);
+    let threshold = maxima[maxima.len() / 2] + hotspot_margin;
+    let mut by_temp: Vec<usize> = (0..candidates.len())
+        .filter(|&i| candidates[i].features.max_temp.is_finite() && candidates[i].features.max_temp >= threshold)
+        .collect();
+    by_temp.sort_by(|&a, &b| candidates[b].features.max_temp.total_cmp(&candidates[a].features.max_temp));
+    let mut hotspots: Vec<usize> = Vec::new();
+    for i in by_temp {
+        if !hotspots.iter().any(|&h| same_hotspot(&candidates[h], &candidates[i])) {
+            hotspots.push(i);
+        }
+    }
+
+    let changes = neighbor_changes(candidates);
+    let mut by_change: Vec<(usize, f32)> = changes.iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.filter(|c| c.is_finite() && *c > 0.0).map(|c| (i, c)))
+        .collect();
+    by_change.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    // Un frame elegido por otro motivo suma el motivo sin ocupar otro hueco
+    let mut selected: Vec<(usize, Vec<KeyframeReason>)> = Vec::new();
+    let mut add = |index: usize, reason: KeyframeReason| match selected.iter().position(|(i, _)| *i == index) {
+        Some(position) if !selected[position].1.contains(&reason) => selected[position].1.push(reason),
+        Some(_) => {}
+        None if selected.len() < count => selected.push((index, vec![reason])),
+        None => {}
+    };
+    add(hottest, KeyframeReason::Hottest);
+    let (mut hotspots, mut changed) = (hotspots.into_iter(), by_change.into_iter().map(|(i, _)| i));
+    loop {
+        let (hotspot, change) = (hotspots.next(), changed.next());
+        if hotspot.is_none() && change.is_none() {
+            break;
+        }
+        if let Some(i) = hotspot {
+            add(i, KeyframeReason::Hotspot);
+        }
+        if let Some(i) = change {
+            add(i, KeyframeReason::Change);
+        }
+    }
+    selected.sort_by_key(|(i, _)| *i);
+    selected
+}
@@ -0,0 +1,211 @@
+use ndarray::{s, Array2, Array3, ArrayD, ArrayView2, ArrayView3, ArrayViewD, Axis, Ix3};
+use ndarray_npy::{ReadNpyExt, ViewNpyExt};
+use npz::frame_axes;
+use serde::{Deserialize, Serialize};
+
+pub mod anomaly;
+pub mod blades;
+pub mod encoding;
+pub mod forecast;
+pub mod fusion;
+pub mod image_quality;
+pub mod keyframes;
+pub mod npy;
+pub mod npz;
+pub mod overlay;
+pub mod pixel_units;
+pub mod registration;
+pub mod thumbnail;
+pub mod tracking;
+
+// --- ANÁLISIS DE MATRICES TÉRMICAS ---
+
+// Punto de datos para evolución
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EvolutionPoint {
+    pub frame_index: usize,
+    pub max_temp: f32,
+    pub avg_temp: f32,
+    // Ángulo e instante propios del frame (solo en pilas npz con "angles"/"timestamps")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub angle: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+}
+
+// Estructura para devolver la Matriz Cruda (Heatmap)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThermalFrameData {
+    pub width: usize,
+    pub height: usize,
+    pub min_temp: f32,
+    pub max_temp: f32,
+    // Aplanamos la matriz 2D a un vector 1D para enviarla fácil por JSON
+    pub pixels: Vec<f32>,
+    // Ángulo e instante propios del frame (solo en pilas npz con "angles"/"timestamps")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub angle: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+}
+
+// Resultado de extraer un frame de una captura
+pub enum FrameError {
+    // El contenido no es una matriz npy f32 de 2 o 3 dimensiones
+    Unreadable,
+    OutOfRange,
+}
+
+// Vista 3D (frames × alto × ancho) sobre los bytes npy sin copiarlos.
+// Una matriz única (alto × ancho) se ve como una pila de 1 frame. De un archivo npz
+// (zip de np.savez) se extrae antes el array de frames.
+pub fn with_frames<R>(bytes: &[u8], f: impl FnOnce(ArrayView3<f32>) -> R) -> Option<R> {
+    let extracted;
+    let bytes = if npz::is_npz(bytes) {
+        extracted = npz::frames_bytes(bytes).ok()?;
+        &extracted[..]
+    } else {
+        bytes
+    };
+    let owned;
+    let view = match ArrayViewD::<f32>::view_npy(bytes) {
+        Ok(view) => view,
+        // Buffer sin alinear (p. ej. recién descomprimido): lectura completa como respaldo
+        Err(_) => {
+            owned = ArrayD::<f32>::read_npy(bytes).ok()?;
+            owned.view()
+        }
+    };
+    let frames = match view.ndim() {
+        2 => view.insert_axis(Axis(0)).into_dimensionality::<Ix3>().ok()?,
+        3 => view.into_dimensionality::<Ix3>().ok()?,
+        _ => return None,
+    };
+    Some(f(frames))
+}
+
+// Copia solo el frame pedido. El archivo puede ser una única matriz (H×W)
+// o una pila (N×H×W); con el mapeo en memoria no se lee el resto.
+pub fn frame_matrix(bytes: &[u8], frame_index: usize) -> Result<Array2<f32>, FrameError> {
+    with_frames(bytes, |frames| {
+        (frame_index < frames.len_of(Axis(0)))
+            .then(|| frames.index_axis(Axis(0), frame_index).to_owned())
+    })
+    .ok_or(FrameError::Unreadable)?
+    .ok_or(FrameError::OutOfRange)
+}
+
+// Copia los frames start, start+stride... anteriores a `end` (recortado al total).
+// Devuelve los índices copiados y la pila resultante (N×H×W).
+pub fn frame_range(bytes: &[u8], start: usize, end: usize, stride: usize) -> Result<(Vec<usize>, Array3<f32>), FrameError> {
+    with_frames(bytes, |frames| {
+        let end = end.min(frames.len_of(Axis(0)));
+        (start < end).then(|| {
+            let stack = frames.slice(s![start..end;stride.max(1), .., ..]).to_owned();
+            ((start..end).step_by(stride.max(1)).collect(), stack)
+        })
+    })
+    .ok_or(FrameError::Unreadable)?
+    .ok_or(FrameError::OutOfRange)
+}
+
+pub fn extract_frame(bytes: &[u8], frame_index: usize) -> Result<ThermalFrameData, FrameError> {
+    let matrix = frame_matrix(bytes, frame_index)?;
+    let axes = frame_axes(bytes);
+    Ok(ThermalFrameData {
+        angle: axes.angle(frame_index),
+        timestamp: axes.timestamp(frame_index),
+        ..frame_data(matrix.view())
+    })
+}
+
+pub fn frame_data(matrix: ArrayView2<f32>) -> ThermalFrameData {
+    let (rows, cols) = matrix.dim();
+
+    // Estadísticas rápidas para normalización en frontend
+    let min_temp = matrix.fold(f32::INFINITY, |a, &b| a.min(b));
+    let max_temp = matrix.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+
+    // Aplanar datos (convertir [[1,2],[3,4]] a [1,2,3,4])
+    // as_standard_layout asegura que estén ordenados fila por fila
+    let pixels = matrix.as_standard_layout().into_owned().into_raw_vec();
+
+    ThermalFrameData {
+        width: cols,
+        height: rows,
+        min_temp,
+        max_temp,
+        pixels,
+        angle: None,
+        timestamp: None,
+    }
+}
+
+// Frame con la temperatura máxima más alta de la captura
+pub fn hottest_frame(bytes: &[u8]) -> Option<(usize, Array2<f32>)> {
+    with_frames(bytes, |frames| {
+        let max = |m: &ArrayView2<f32>| m.fold(f32::NEG_INFINITY, |acc, &v| acc.max(v));
+        frames.outer_iter().enumerate()
+            .max_by(|(_, a), (_, b)| max(a).total_cmp(&max(b)))
+            .map(|(index, frame)| (index, frame.to_owned()))
+    })
+    .flatten()
+}
+
+// Un punto por frame, recorriendo la vista mapeada sin cargar la pila completa
+pub fn evolution_points(bytes: &[u8]) -> Vec<EvolutionPoint> {
+    let axes = frame_axes(bytes);
+    let mut points = Vec::new();
+    with_frames(bytes, |frames| {
+        for (frame_index, matrix) in frames.outer_iter().enumerate() {
+            let max_val = matrix.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+            let sum: f32 = matrix.sum();
+            let count = matrix.len() as f32;
+            let avg_val = if count > 0.0 { sum / count } else { 0.0 };
+
+            points.push(EvolutionPoint {
+                frame_index,
+                max_temp: max_val,
+                avg_temp: avg_val,
+                angle: axes.angle(frame_index),
+                timestamp: axes.timestamp(frame_index),
+            });
+        }
+    });
+    points
+}
+
+// Puntos por frame de las pilas que traen ángulo o instante por frame (vacío si no),
+// para guardarlos en el catálogo
+pub fn frame_points(bytes: &[u8]) -> Vec<EvolutionPoint> {
+    if frame_axes(bytes).is_empty() {
+        return Vec::new();
+    }
+    evolution_points(bytes)
+}
+
+// Estadísticas de toda la captura (todos los frames)
+#[derive(Clone, Copy, Debug)]
+pub struct CaptureStats {
+    pub max_temp: f32,
+    pub avg_temp: f32,
+}
+
+// Alto y ancho de los frames de una captura
+pub fn frame_shape(bytes: &[u8]) -> Option<(usize, usize)> {
+    with_frames(bytes, |frames| {
+        let (_, height, width) = frames.dim();
+        (height, width)
+    })
+}
+
+pub fn capture_stats(bytes: &[u8]) -> Option<CaptureStats> {
+    with_frames(bytes, |frames| {
+        let count = frames.len();
+        (count > 0).then(|| CaptureStats {
+            max_temp: frames.fold(f32::NEG_INFINITY, |a, &b| a.max(b)),
+            avg_temp: (frames.iter().map(|&v| v as f64).sum::<f64>() / count as f64) as f32,
+        })
+    })
+    .flatten()
+}
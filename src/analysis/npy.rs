@@ -0,0 +1,116 @@
+// --- VALIDACIÓN DE CAPTURAS NPY ---
+// Las subidas se comprueban antes de guardarlas: cabecera npy (versión 1 a 3), tipo
+// float32 little-endian, 2 dimensiones (alto × ancho) o 3 (frames × alto × ancho), sin
+// ejes vacíos, con exactamente los datos que anuncia la cabecera, con algún valor finito
+// y, si el robot tiene resolución declarada, con ese alto y ancho. Un archivo npz (zip de np.savez) se
+// valida por su array de frames; sus arrays por frame (ángulos, instantes), si los trae,
+// deben tener un valor por frame.
+
+use super::npz::{array_names, frames_bytes, is_npz, read_named_array};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+// Arrays opcionales de un npz con un valor por frame
+const PER_FRAME_ARRAYS: &[&str] = &["angles", "timestamps"];
+const F32_SIZE: usize = 4;
+
+// Forma de una captura válida
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NpyShape {
+    pub frames: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+// Comprueba la captura; el error describe el problema para devolverlo al robot
+pub fn validate_capture(bytes: &[u8], expected: Option<(usize, usize)>) -> Result<NpyShape, String> {
+    if !is_npz(bytes) {
+        return validate_npy(bytes, expected);
+    }
+    let shape = validate_npy(&frames_bytes(bytes)?, expected).map_err(|e| format!("frames array: {}", e))?;
+    let names = array_names(bytes)?;
+    for name in PER_FRAME_ARRAYS.iter().filter(|n| names.iter().any(|a| a == *n)) {
+        let array = read_named_array(bytes, name)?;
+        if array.shape != [shape.frames] {
+            return Err(format!("array '{}' must have one value per frame ({}), got shape {:?}", name, shape.frames, array.shape));
+        }
+    }
+    Ok(shape)
+}
+
+fn validate_npy(bytes: &[u8], expected: Option<(usize, usize)>) -> Result<NpyShape, String> {
+    if !bytes.starts_with(MAGIC) {
+        return Err("not an npy file (missing \\x93NUMPY magic)".into());
+    }
+    let (len_size, major) = match bytes.get(MAGIC.len()) {
+        Some(1) => (2, 1),
+        Some(major @ (2 | 3)) => (4, *major),
+        Some(major) => return Err(format!("unsupported npy version {}", major)),
+        None => return Err("truncated npy header".into()),
+    };
+    let len_start = MAGIC.len() + 2;
+    let len_bytes = bytes.get(len_start..len_start + len_size).ok_or("truncated npy header")?;
+    let header_len = len_bytes.iter().rev().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+    let data_start = len_start + len_size + header_len;
+    let header = bytes.get(len_start + len_size..data_start).ok_or("truncated npy header")?;
+    let header = match major {
+        1 | 2 => std::str::from_utf8(header).ok().filter(|h| h.is_ascii()),
+        _ => std::str::from_utf8(header).ok(),
+    }
+    .ok_or("npy header is not valid text")?;
+
+    let descr = header_value(header, "descr").ok_or("npy header has no 'descr'")?;
+    if descr.trim_matches(['\'', '"']) != "<f4" {
+        return Err(format!("dtype must be little-endian float32 ('<f4'), got {}", descr));
+    }
+    let shape = header_value(header, "shape").ok_or("npy header has no 'shape'")?;
+    let dims: Vec<usize> = shape.trim_matches(['(', ')']).split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse::<usize>().map_err(|_| format!("invalid shape {}", shape)))
+        .collect::<Result<_, _>>()?;
+    let shape = match dims[..] {
+        [height, width] => NpyShape { frames: 1, height, width },
+        [frames, height, width] => NpyShape { frames, height, width },
+        _ => return Err(format!("expected 2 (height × width) or 3 (frames × height × width) dimensions, got {}", dims.len())),
+    };
+    if shape.frames == 0 || shape.height == 0 || shape.width == 0 {
+        return Err(format!("empty capture (shape {:?})", dims));
+    }
+
+    let expected_bytes = shape.frames
+        .checked_mul(shape.height)
+        .and_then(|n| n.checked_mul(shape.width))
+        .and_then(|n| n.checked_mul(F32_SIZE))
+        .ok_or("shape is too large")?;
+    let data_bytes = bytes.len() - data_start;
+    if data_bytes != expected_bytes {
+        return Err(format!("shape {:?} needs {} bytes of data, got {}", dims, expected_bytes, data_bytes));
+    }
+    // Sin ninguna lectura real (todo NaN o infinito) no hay nada que analizar
+    let finite = bytes[data_start..].chunks_exact(F32_SIZE)
+        .any(|v| f32::from_le_bytes([v[0], v[1], v[2], v[3]]).is_finite());
+    if !finite {
+        return Err(format!("capture has no finite temperature values (all {} pixels are NaN or infinite)", expected_bytes / F32_SIZE));
+    }
+    if let Some((height, width)) = expected
+        && (shape.height, shape.width) != (height, width)
+    {
+        return Err(format!(
+            "frame is {}×{} (width × height) but the device declares {}×{}",
+            shape.width, shape.height, width, height
+        ));
+    }
+    Ok(shape)
+}
+
+// Valor en bruto de una clave del diccionario de la cabecera ("'<f4'", "(24, 32)")
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find([',', '}'])?
+    };
+    Some(rest[..end].trim())
+}
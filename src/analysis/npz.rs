@@ -0,0 +1,140 @@
+use ndarray::ArrayD;
+use ndarray_npy::ReadNpyExt;
+use serde::Serialize;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+// --- ARCHIVOS NPZ ---
+// Los robots guardan las capturas como un npy suelto con extensión .npz, pero las
+// herramientas de numpy (np.savez) generan un zip con varios arrays con nombre: los
+// frames y, a veces, los ángulos y los instantes de cada frame. Ambos formatos se
+// aceptan; en un zip los frames son el array "frames" o, si solo hay uno, ese.
+
+// Nombre del array con la pila de frames
+pub const FRAMES_ARRAY: &str = "frames";
+// Tamaño máximo de un array descomprimido (protege frente a zips maliciosos)
+const MAX_ARRAY_BYTES: u64 = 1 << 30;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+pub fn is_npz(bytes: &[u8]) -> bool {
+    bytes.starts_with(ZIP_MAGIC)
+}
+
+fn open(bytes: &[u8]) -> Result<ZipArchive<Cursor<&[u8]>>, String> {
+    ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("invalid npz archive: {}", e))
+}
+
+// Nombres de los arrays del archivo (sin la extensión .npy), en el orden del zip
+pub fn array_names(bytes: &[u8]) -> Result<Vec<String>, String> {
+    let archive = open(bytes)?;
+    Ok(archive.file_names().map(|name| name.strip_suffix(".npy").unwrap_or(name).to_string()).collect())
+}
+
+// Bytes npy del array `name`
+pub fn array_bytes(bytes: &[u8], name: &str) -> Result<Vec<u8>, String> {
+    let mut archive = open(bytes)?;
+    let with_extension = format!("{}.npy", name);
+    let entry_name = match archive.index_for_name(&with_extension) {
+        Some(_) => with_extension.as_str(),
+        None => name,
+    };
+    let mut entry = archive.by_name(entry_name).map_err(|_| format!("npz archive has no array '{}'", name))?;
+    if entry.size() > MAX_ARRAY_BYTES {
+        return Err(format!("array '{}' is too large ({} bytes)", name, entry.size()));
+    }
+    let mut data = Vec::with_capacity(entry.size() as usize);
+    entry.by_ref().take(MAX_ARRAY_BYTES).read_to_end(&mut data).map_err(|e| format!("array '{}': {}", name, e))?;
+    Ok(data)
+}
+
+// Nombre del array con la pila de frames: "frames" o el único array del archivo
+pub fn frames_array_name(bytes: &[u8]) -> Result<String, String> {
+    let mut names = array_names(bytes)?;
+    match names.iter().position(|n| n == FRAMES_ARRAY) {
+        Some(index) => Ok(names.swap_remove(index)),
+        None if names.len() == 1 => Ok(names.swap_remove(0)),
+        None => Err(format!("npz archive has no '{}' array (arrays: {})", FRAMES_ARRAY, names.join(", "))),
+    }
+}
+
+// Bytes npy de la pila de frames
+pub fn frames_bytes(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    array_bytes(bytes, &frames_array_name(bytes)?)
+}
+
+// Array con nombre de una captura, convertido a f64 para devolverlo por JSON
+#[derive(Serialize)]
+pub struct NamedArray {
+    pub name: String,
+    pub shape: Vec<usize>,
+    // Aplanado en orden de filas
+    pub values: Vec<f64>,
+}
+
+// Lee un array numérico (float o entero) de cualquier tipo habitual
+pub fn read_named_array(bytes: &[u8], name: &str) -> Result<NamedArray, String> {
+    let npy = array_bytes(bytes, name)?;
+    fn convert<T: Copy + Into<f64>>(array: ArrayD<T>) -> (Vec<usize>, Vec<f64>) {
+        (array.shape().to_vec(), array.iter().map(|&v| v.into()).collect())
+    }
+    let (shape, values) = if let Ok(array) = ArrayD::<f32>::read_npy(&npy[..]) {
+        convert(array)
+    } else if let Ok(array) = ArrayD::<f64>::read_npy(&npy[..]) {
+        convert(array)
+    } else if let Ok(array) = ArrayD::<i32>::read_npy(&npy[..]) {
+        convert(array)
+    } else if let Ok(array) = ArrayD::<u32>::read_npy(&npy[..]) {
+        convert(array)
+    } else if let Ok(array) = ArrayD::<i64>::read_npy(&npy[..]) {
+        (array.shape().to_vec(), array.iter().map(|&v| v as f64).collect())
+    } else if let Ok(array) = ArrayD::<u64>::read_npy(&npy[..]) {
+        (array.shape().to_vec(), array.iter().map(|&v| v as f64).collect())
+    } else {
+        return Err(format!("array '{}' is not a numeric npy array", name));
+    };
+    Ok(NamedArray { name: name.to_string(), shape, values })
+}
+
+// Ángulo (grados) e instante (segundos Unix, con decimales) de cada frame, de los
+// arrays "angles" y "timestamps" que el robot puede añadir a una pila de frames
+#[derive(Clone, Debug, Default)]
+pub struct FrameAxes {
+    pub angles: Option<Vec<f32>>,
+    pub timestamps: Option<Vec<f64>>,
+}
+
+impl FrameAxes {
+    pub fn is_empty(&self) -> bool {
+        self.angles.is_none() && self.timestamps.is_none()
+    }
+
+    pub fn angle(&self, frame_index: usize) -> Option<f32> {
+        self.angles.as_ref()?.get(frame_index).copied().filter(|a| a.is_finite())
+    }
+
+    pub fn timestamp(&self, frame_index: usize) -> Option<f64> {
+        self.timestamps.as_ref()?.get(frame_index).copied().filter(|t| t.is_finite() && *t >= 0.0)
+    }
+}
+
+// Vacío para un npy suelto o un npz sin esos arrays (o con arrays que no son 1D)
+pub fn frame_axes(bytes: &[u8]) -> FrameAxes {
+    if !is_npz(bytes) {
+        return FrameAxes::default();
+    }
+    let Ok(names) = array_names(bytes) else {
+        return FrameAxes::default();
+    };
+    let read = |name: &str| {
+        names.iter().any(|n| n == name)
+            .then(|| read_named_array(bytes, name).ok())
+            .flatten()
+            .filter(|array| array.shape.len() == 1)
+            .map(|array| array.values)
+    };
+    FrameAxes {
+        angles: read("angles").map(|values| values.iter().map(|&v| v as f32).collect()),
+        timestamps: read("timestamps"),
+    }
+}
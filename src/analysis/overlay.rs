@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::FRAC_PI_6;
+
+// --- ANOTACIONES SOBRE FRAMES ---
+// Geometría de las marcas que dibujan los usuarios al analizar un frame (rectángulos,
+// flechas y texto), en coordenadas de píxel del frame (x = columna, y = fila), y su
+// dibujo sobre una imagen RGB ya renderizada. El texto usa una fuente de 3×5 píxeles
+// (mayúsculas, dígitos y algo de puntuación); el resto de caracteres se deja en blanco.
+
+// Color por defecto: cian, que no aparece en la paleta ironbow
+pub const DEFAULT_COLOR: [u8; 3] = [0, 255, 255];
+// Longitud máxima del texto de una anotación
+pub const MAX_TEXT_LEN: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Shape {
+    Rect { x: f32, y: f32, width: f32, height: f32 },
+    // Punta de la flecha en (x2, y2)
+    Arrow { x1: f32, y1: f32, x2: f32, y2: f32 },
+    Text { x: f32, y: f32, text: String },
+}
+
+impl Shape {
+    pub fn validate(&self) -> Result<(), String> {
+        let coords: &[f32] = match self {
+            Shape::Rect { x, y, width, height } => {
+                if *width < 0.0 || *height < 0.0 {
+                    return Err("rect width and height must not be negative".into());
+                }
+                &[*x, *y, *width, *height]
+            }
+            Shape::Arrow { x1, y1, x2, y2 } => &[*x1, *y1, *x2, *y2],
+            Shape::Text { x, y, text } => {
+                if text.trim().is_empty() || text.chars().count() > MAX_TEXT_LEN {
+                    return Err(format!("text must have between 1 and {} characters", MAX_TEXT_LEN));
+                }
+                &[*x, *y]
+            }
+        };
+        if !coords.iter().all(|c| c.is_finite()) {
+            return Err("coordinates must be finite numbers".into());
+        }
+        Ok(())
+    }
+}
+
+// "#rrggbb"
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').filter(|h| h.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Filas de cada glifo (3 bits por fila, el bit alto a la izquierda)
+const FONT: &[(char, [u8; 5])] = &[
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('?', [0b110, 0b001, 0b010, 0b000, 0b010]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('%', [0b101, 0b001, 0b010, 0b100, 0b101]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]),
+    (')', [0b100, 0b010, 0b010, 0b010, 0b100]),
+    ('°', [0b010, 0b101, 0b010, 0b000, 0b000]),
+];
+
+// Imagen RGB en la que se dibuja
+pub struct Canvas<'a> {
+    pub pixels: &'a mut [u8],
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Canvas<'_> {
+    fn plot(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let i = (y as usize * self.width + x as usize) * 3;
+        self.pixels[i..i + 3].copy_from_slice(&color);
+    }
+
+    // Bresenham
+    fn line(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64), color: [u8; 3]) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        loop {
+            self.plot(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    // Texto con la esquina superior izquierda en (x, y); `size` píxeles por punto
+    fn text(&mut self, (x, y): (i64, i64), text: &str, size: i64, color: [u8; 3]) {
+        for (n, c) in text.chars().flat_map(char::to_uppercase).enumerate() {
+            let Some((_, rows)) = FONT.iter().find(|(g, _)| *g == c) else { continue };
+            let left = x + n as i64 * 4 * size;
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) == 0 {
+                        continue;
+                    }
+                    for (px, py) in (0..size).flat_map(|i| (0..size).map(move |j| (i, j))) {
+                        self.plot(left + col * size + px, y + row as i64 * size + py, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // Dibuja una anotación; `scale` pasa de píxeles del frame a píxeles de la imagen
+    pub fn draw(&mut self, shape: &Shape, color: [u8; 3], scale: f32) {
+        let at = |x: f32, y: f32| ((x * scale).round() as i64, (y * scale).round() as i64);
+        match shape {
+            Shape::Rect { x, y, width, height } => {
+                let (left, top) = at(*x, *y);
+                let (right, bottom) = at(x + width, y + height);
+                self.line((left, top), (right, top), color);
+                self.line((right, top), (right, bottom), color);
+                self.line((right, bottom), (left, bottom), color);
+                self.line((left, bottom), (left, top), color);
+            }
+            Shape::Arrow { x1, y1, x2, y2 } => {
+                let (start, tip) = (at(*x1, *y1), at(*x2, *y2));
+                self.line(start, tip, color);
+                // Punta: dos segmentos a ±30° del cuerpo
+                let (dx, dy) = ((start.0 - tip.0) as f32, (start.1 - tip.1) as f32);
+                let length = dx.hypot(dy);
+                if length > 0.0 {
+                    let head = (length * 0.3).clamp(2.0, 12.0);
+                    for angle in [FRAC_PI_6, -FRAC_PI_6] {
+                        let (sin, cos) = angle.sin_cos();
+                        let (hx, hy) = ((dx * cos - dy * sin) / length, (dx * sin + dy * cos) / length);
+                        let end = (tip.0 + (hx * head).round() as i64, tip.1 + (hy * head).round() as i64);
+                        self.line(tip, end, color);
+                    }
+                }
+            }
+            Shape::Text { x, y, text } => {
+                // Letras legibles sin tapar la imagen: un punto de fuente por cada 100 px de ancho
+                let size = (self.width / 100).max(1) as i64;
+                self.text(at(*x, *y), text, size, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use super::npz::{array_bytes, array_names, frames_array_name, is_npz};
+use ndarray::ArrayD;
+use ndarray_npy::{ReadNpyExt, WriteNpyExt};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Write};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+// --- UNIDAD DE LOS PÍXELES DE LAS SUBIDAS ---
+// Algunos sensores envían cuentas en bruto de 16 bits o kelvin en lugar de °C. La subida
+// lo indica en el campo pixel_unit (celsius por defecto, kelvin o raw_counts) y el
+// servidor convierte la captura a °C float32 antes de validarla y guardarla, de modo que
+// las estadísticas, las alertas, las matrices y las miniaturas ven siempre temperaturas.
+// Las cuentas se convierten con la recta de la calibración vigente de la cámara
+// (CameraCalibration::raw_counts); sin ella la subida se rechaza. En un npz solo se
+// convierte el array de frames; los ángulos y los instantes se copian tal cual.
+
+const KELVIN_OFFSET: f32 = 273.15;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelUnit {
+    #[default]
+    Celsius,
+    Kelvin,
+    RawCounts,
+}
+
+impl PixelUnit {
+    // Valor del campo de la subida (vacío = °C)
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "c" | "celsius" => Ok(PixelUnit::Celsius),
+            "k" | "kelvin" => Ok(PixelUnit::Kelvin),
+            "raw_counts" | "counts" => Ok(PixelUnit::RawCounts),
+            other => Err(format!("Invalid pixel_unit '{}' (celsius, kelvin or raw_counts)", other)),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PixelUnit::Celsius => "celsius",
+            PixelUnit::Kelvin => "kelvin",
+            PixelUnit::RawCounts => "raw_counts",
+        }
+    }
+}
+
+// Recta de conversión de cuentas a °C de una cámara: °C = cuentas × scale + offset
+// (p. ej. un sensor radiométrico en centikelvin: scale 0.01, offset -273.15)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RawCountConversion {
+    pub scale: f32,
+    pub offset: f32,
+}
+
+impl RawCountConversion {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.scale.is_finite() || self.scale <= 0.0 {
+            return Err("raw_counts scale must be a positive number".into());
+        }
+        if !self.offset.is_finite() {
+            return Err("raw_counts offset must be finite".into());
+        }
+        Ok(())
+    }
+}
+
+// Frames en float32 o, si son cuentas, también en enteros de 16 bits
+fn read_pixels(npy: &[u8], unit: PixelUnit) -> Result<ArrayD<f32>, String> {
+    if let Ok(pixels) = ArrayD::<f32>::read_npy(npy) {
+        return Ok(pixels);
+    }
+    if unit == PixelUnit::RawCounts {
+        if let Ok(counts) = ArrayD::<u16>::read_npy(npy) {
+            return Ok(counts.mapv(f32::from));
+        }
+        if let Ok(counts) = ArrayD::<i16>::read_npy(npy) {
+            return Ok(counts.mapv(f32::from));
+        }
+        return Err("raw_counts dtype must be float32 ('<f4') or 16-bit integers ('<u2', '<i2')".into());
+    }
+    Err("dtype must be little-endian float32 ('<f4')".into())
+}
+
+fn npy_to_celsius(npy: &[u8], unit: PixelUnit, conversion: Option<&RawCountConversion>) -> Result<Vec<u8>, String> {
+    let pixels = read_pixels(npy, unit)?;
+    let celsius = match (unit, conversion) {
+        (PixelUnit::Celsius, _) => pixels,
+        (PixelUnit::Kelvin, _) => pixels.mapv(|k| k - KELVIN_OFFSET),
+        (PixelUnit::RawCounts, Some(c)) => pixels.mapv(|counts| counts * c.scale + c.offset),
+        (PixelUnit::RawCounts, None) => return Err("raw_counts needs a conversion".into()),
+    };
+    let mut converted = Vec::new();
+    celsius.write_npy(&mut converted).map_err(|e| e.to_string())?;
+    Ok(converted)
+}
+
+// Captura npy o npz en `unit` convertida a °C float32 (en el pool bloqueante)
+pub fn capture_to_celsius(bytes: &[u8], unit: PixelUnit, conversion: Option<&RawCountConversion>) -> Result<Vec<u8>, String> {
+    if !is_npz(bytes) {
+        return npy_to_celsius(bytes, unit, conversion);
+    }
+    let frames = frames_array_name(bytes)?;
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    for name in array_names(bytes)? {
+        let npy = array_bytes(bytes, &name)?;
+        let npy = if name == frames { npy_to_celsius(&npy, unit, conversion)? } else { npy };
+        zip.start_file(format!("{}.npy", name), options).map_err(|e| e.to_string())?;
+        zip.write_all(&npy).map_err(|e| e.to_string())?;
+    }
+    Ok(zip.finish().map_err(|e| e.to_string())?.into_inner())
+}
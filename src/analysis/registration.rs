@@ -0,0 +1,99 @@
+use ndarray::{s, Array2, ArrayView2};
+use serde::Serialize;
+
+// --- REGISTRO ENTRE ESCANEOS ---
+// La deriva del pan/tilt entre dos escaneos al mismo ángulo desplaza la imagen unos
+// píxeles, y restar frames sin alinear produce bordes falsos. Antes de comparar se busca
+// la traslación entera (dx, dy) que minimiza la diferencia media absoluta entre ambos
+// frames (centrados en su media, para no confundir deriva con calentamiento global).
+
+// Desplazamiento máximo que se busca en cada eje, en píxeles
+pub const DEFAULT_MAX_SHIFT: usize = 8;
+
+// Fracción mínima del frame que debe solaparse para aceptar un desplazamiento
+const MIN_OVERLAP: f32 = 0.5;
+
+// El píxel (fila, col) de la referencia corresponde al (fila + dy, col + dx) del otro frame
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Alignment {
+    pub dx: i32,
+    pub dy: i32,
+    // Diferencia media absoluta en el solape tras alinear
+    pub score: f32,
+}
+
+// Rangos de filas/columnas solapadas de la referencia para un desplazamiento
+fn overlap(len: usize, shift: i32) -> (usize, usize) {
+    let start = (-shift).max(0) as usize;
+    let end = (len as i32 - shift.max(0)).max(start as i32) as usize;
+    (start, end)
+}
+
+fn window<'a, 'b>(
+    reference: &'a ArrayView2<f32>,
+    moving: &'b ArrayView2<f32>,
+    dx: i32,
+    dy: i32,
+) -> (ArrayView2<'a, f32>, ArrayView2<'b, f32>) {
+    let (rows, cols) = reference.dim();
+    let (r0, r1) = overlap(rows, dy);
+    let (c0, c1) = overlap(cols, dx);
+    let a = reference.slice(s![r0..r1, c0..c1]);
+    let b = moving.slice(s![
+        (r0 as i32 + dy) as usize..(r1 as i32 + dy) as usize,
+        (c0 as i32 + dx) as usize..(c1 as i32 + dx) as usize
+    ]);
+    (a, b)
+}
+
+pub fn estimate_translation(reference: ArrayView2<f32>, moving: ArrayView2<f32>, max_shift: usize) -> Alignment {
+    let (rows, cols) = reference.dim();
+    if moving.dim() != (rows, cols) || rows == 0 || cols == 0 {
+        return Alignment::default();
+    }
+    let mean = |m: &ArrayView2<f32>| m.mean().unwrap_or(0.0);
+    let offset = mean(&moving) - mean(&reference);
+    let max_shift = max_shift.min(rows / 2).min(cols / 2) as i32;
+    let min_pixels = (rows * cols) as f32 * MIN_OVERLAP;
+
+    let mut best: Option<Alignment> = None;
+    for dy in -max_shift..=max_shift {
+        for dx in -max_shift..=max_shift {
+            let (a, b) = window(&reference, &moving, dx, dy);
+            if (a.len() as f32) < min_pixels {
+                continue;
+            }
+            let total: f32 = a.iter().zip(b.iter()).map(|(x, y)| (y - x - offset).abs()).sum();
+            let score = total / a.len() as f32;
+            // Ante empate gana el desplazamiento más pequeño
+            let better = best.is_none_or(|b| {
+                score < b.score || (score == b.score && dx.abs() + dy.abs() < b.dx.abs() + b.dy.abs())
+            });
+            if better {
+                best = Some(Alignment { dx, dy, score });
+            }
+        }
+    }
+    best.unwrap_or_default()
+}
+
+// Diferencia (otro − referencia) recortada a la zona solapada
+pub fn aligned_difference(reference: ArrayView2<f32>, moving: ArrayView2<f32>, alignment: Alignment) -> Array2<f32> {
+    let (a, b) = window(&reference, &moving, alignment.dx, alignment.dy);
+    &b - &a
+}
+
+// Reduce un frame a (filas, columnas) promediando los píxeles de origen de cada celda,
+// para comparar frames de distinta resolución a la menor de las dos
+pub fn downsample(frame: ArrayView2<f32>, (rows, cols): (usize, usize)) -> Array2<f32> {
+    let (src_rows, src_cols) = frame.dim();
+    let span = |i: usize, out: usize, src: usize| {
+        let start = i * src / out;
+        (start, ((i + 1) * src / out).max(start + 1).min(src))
+    };
+    Array2::from_shape_fn((rows, cols), |(r, c)| {
+        let (r0, r1) = span(r, rows, src_rows);
+        let (c0, c1) = span(c, cols, src_cols);
+        frame.slice(s![r0..r1, c0..c1]).mean().unwrap_or(0.0)
+    })
+}
@@ -0,0 +1,76 @@
+use ndarray::Array2;
+
+// --- MINIATURAS ---
+// Imagen PNG diminuta de un frame (media por bloques y paleta "ironbow" entre la mínima
+// y la máxima del frame) para listados y la app móvil con mala cobertura.
+
+// Paleta de frío a caliente: negro, azul, magenta, naranja, amarillo, blanco
+const IRONBOW: [[u8; 3]; 6] = [
+    [0, 0, 0],
+    [32, 0, 140],
+    [204, 0, 119],
+    [255, 140, 0],
+    [255, 230, 0],
+    [255, 255, 255],
+];
+
+pub(crate) fn ironbow(t: f32) -> [u8; 3] {
+    let scaled = t.clamp(0.0, 1.0) * (IRONBOW.len() - 1) as f32;
+    let i = (scaled.floor() as usize).min(IRONBOW.len() - 2);
+    let f = scaled - i as f32;
+    let (a, b) = (IRONBOW[i], IRONBOW[i + 1]);
+    [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * f).round() as u8)
+}
+
+// Factor de reducción para que el lado mayor no pase de `max_side` píxeles
+fn downscale_factor(rows: usize, cols: usize, max_side: usize) -> usize {
+    rows.max(cols).div_ceil(max_side.max(1)).max(1)
+}
+
+// Reduce el frame promediando bloques de factor × factor píxeles
+fn downscale(frame: &Array2<f32>, factor: usize) -> Array2<f32> {
+    let (rows, cols) = frame.dim();
+    let (out_rows, out_cols) = (rows.div_ceil(factor), cols.div_ceil(factor));
+    Array2::from_shape_fn((out_rows, out_cols), |(r, c)| {
+        let block = frame.slice(ndarray::s![r * factor..((r + 1) * factor).min(rows), c * factor..((c + 1) * factor).min(cols)]);
+        block.mean().unwrap_or(0.0)
+    })
+}
+
+// Imagen RGB ya coloreada, antes de codificarla
+pub struct RgbImage {
+    pub pixels: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    // Píxeles de la imagen por píxel del frame (1 / factor de reducción)
+    pub scale: f32,
+}
+
+pub fn render_rgb(frame: &Array2<f32>, max_side: usize) -> RgbImage {
+    let (rows, cols) = frame.dim();
+    let factor = downscale_factor(rows, cols, max_side);
+    let small = downscale(frame, factor);
+    let (height, width) = small.dim();
+    let finite = || small.iter().copied().filter(|v| v.is_finite());
+    let min = finite().fold(f32::INFINITY, f32::min);
+    let max = finite().fold(f32::NEG_INFINITY, f32::max);
+    let span = if max > min { max - min } else { 1.0 };
+    let pixels: Vec<u8> = small.iter()
+        .flat_map(|&v| ironbow(if v.is_finite() { (v - min) / span } else { 0.0 }))
+        .collect();
+    RgbImage { pixels, width, height, scale: 1.0 / factor as f32 }
+}
+
+pub fn encode_png(image: &RgbImage) -> Result<Vec<u8>, png::EncodingError> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Best);
+    encoder.write_header()?.write_image_data(&image.pixels)?;
+    Ok(out)
+}
+
+pub fn thumbnail_png(frame: &Array2<f32>, max_side: usize) -> Result<Vec<u8>, png::EncodingError> {
+    encode_png(&render_rgb(frame, max_side))
+}
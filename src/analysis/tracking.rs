@@ -0,0 +1,142 @@
+use ndarray::ArrayView2;
+use serde::Serialize;
+use std::f32::consts::SQRT_2;
+
+// --- SEGUIMIENTO DE UN PUNTO CALIENTE ---
+// Desde un frame con punto caliente se sigue su mancha por los frames siguientes de la
+// misma cámara en la sesión. En cada frame las manchas son las zonas conexas (4-vecindad)
+// que superan la mediana del frame en MIN_BLOB_DELTA; la seguida es la de centroide más
+// cercano a su última posición, a lo sumo a `max_distance` (fracción de la diagonal). Si
+// no aparece en MAX_MISSES frames seguidos se da por perdida.
+// Un fallo en un componente se queda en su sitio de la imagen con una temperatura
+// estable; un reflejo se desplaza con el ángulo y el sol. El veredicto (fixed / moving)
+// es orientativo: compara el desplazamiento del centroide con FIXED_DRIFT.
+
+// Diferencia mínima (°C) con la mediana del frame para formar parte de una mancha
+pub const MIN_BLOB_DELTA: f32 = 5.0;
+pub const DEFAULT_MAX_DISTANCE: f32 = 0.15;
+pub const MAX_MISSES: usize = 5;
+// Deriva máxima del centroide (fracción de la diagonal) de un punto fijo
+const FIXED_DRIFT: f32 = 0.05;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Blob {
+    // Centroide (fila, columna) en fracción del alto y del ancho
+    pub centroid: (f32, f32),
+    pub pixels: usize,
+    pub max_temp: f32,
+    pub mean_temp: f32,
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt() / SQRT_2
+}
+
+// Manchas calientes de un frame
+pub fn frame_blobs(frame: ArrayView2<f32>) -> Vec<Blob> {
+    let (rows, cols) = frame.dim();
+    let mut values: Vec<f32> = frame.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return Vec::new();
+    }
+    values.sort_by(f32::total_cmp);
+    let threshold = values[values.len() / 2] + MIN_BLOB_DELTA;
+    let hot = |r: usize, c: usize| frame[(r, c)] >= threshold;
+
+    let mut visited = vec![false; rows * cols];
+    let mut stack = Vec::new();
+    let mut blobs = Vec::new();
+    for start in 0..rows * cols {
+        if visited[start] || !hot(start / cols, start % cols) {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start);
+        let (mut pixels, mut sum_row, mut sum_col, mut sum_temp, mut max_temp) = (0usize, 0f64, 0f64, 0f64, f32::NEG_INFINITY);
+        while let Some(index) = stack.pop() {
+            let (r, c) = (index / cols, index % cols);
+            let value = frame[(r, c)];
+            pixels += 1;
+            sum_row += r as f64;
+            sum_col += c as f64;
+            sum_temp += value as f64;
+            max_temp = max_temp.max(value);
+            // wrapping_sub deja fuera de rango a los vecinos de la fila o columna 0
+            for (nr, nc) in [(r.wrapping_sub(1), c), (r + 1, c), (r, c.wrapping_sub(1)), (r, c + 1)] {
+                if nr < rows && nc < cols && !visited[nr * cols + nc] && hot(nr, nc) {
+                    visited[nr * cols + nc] = true;
+                    stack.push(nr * cols + nc);
+                }
+            }
+        }
+        let n = pixels as f64;
+        blobs.push(Blob {
+            centroid: ((sum_row / n) as f32 / rows as f32, (sum_col / n) as f32 / cols as f32),
+            pixels,
+            max_temp,
+            mean_temp: (sum_temp / n) as f32,
+        });
+    }
+    blobs
+}
+
+// La mancha del punto más caliente del frame
+pub fn hottest_blob(blobs: &[Blob]) -> Option<&Blob> {
+    blobs.iter().max_by(|a, b| a.max_temp.total_cmp(&b.max_temp))
+}
+
+pub fn nearest_blob(blobs: &[Blob], at: (f32, f32), max_distance: f32) -> Option<&Blob> {
+    blobs.iter()
+        .map(|b| (b, distance(b.centroid, at)))
+        .filter(|(_, d)| *d <= max_distance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(b, _)| b)
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackKind {
+    Fixed,
+    Moving,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TrackSummary {
+    pub points: usize,
+    // Frames recorridos en los que no apareció
+    pub missed: usize,
+    // Mayor distancia del centroide a su posición media y del primero al último
+    // (fracción de la diagonal)
+    pub max_drift: f32,
+    pub net_displacement: f32,
+    pub min_temp: f32,
+    pub max_temp: f32,
+    pub mean_temp: f32,
+    pub temp_std: f32,
+    pub kind: TrackKind,
+}
+
+// Resumen de la trayectoria: sus blobs en orden
+pub fn track_summary(blobs: &[&Blob], missed: usize) -> Option<TrackSummary> {
+    let (first, last) = (blobs.first()?, blobs.last()?);
+    let n = blobs.len() as f32;
+    let center = (
+        blobs.iter().map(|b| b.centroid.0).sum::<f32>() / n,
+        blobs.iter().map(|b| b.centroid.1).sum::<f32>() / n,
+    );
+    let max_drift = blobs.iter().map(|b| distance(b.centroid, center)).fold(0.0, f32::max);
+    let temps: Vec<f32> = blobs.iter().map(|b| b.max_temp).collect();
+    let mean_temp = temps.iter().sum::<f32>() / n;
+    let temp_std = (temps.iter().map(|t| (t - mean_temp).powi(2)).sum::<f32>() / n).sqrt();
+    Some(TrackSummary {
+        points: blobs.len(),
+        missed,
+        max_drift,
+        net_displacement: distance(first.centroid, last.centroid),
+        min_temp: temps.iter().copied().fold(f32::INFINITY, f32::min),
+        max_temp: temps.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        mean_temp,
+        temp_std,
+        kind: if max_drift <= FIXED_DRIFT { TrackKind::Fixed } else { TrackKind::Moving },
+    })
+}
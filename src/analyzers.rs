@@ -0,0 +1,207 @@
+use crate::{
+    analysis::{
+        anomaly::{anomaly_score, ANOMALY_NOTICE},
+        capture_stats, frame_points, frame_shape, hottest_frame,
+        image_quality::capture_quality,
+    },
+    calibration::calibration_at,
+    error::AppError,
+    pipeline::{CaptureAnalysis, CaptureInput},
+    state::AppState,
+    storage::lineage::record_derivation,
+};
+use std::{future::Future, pin::Pin};
+
+// --- ANALIZADORES DE CAPTURAS ---
+// Lo que se calcula con cada captura recibida (o re-ingestada) es una cadena de
+// analizadores que se ejecutan en el orden de RemoteConfig::analyzers. Cada uno lee la
+// captura y lo que dejaron los anteriores en CaptureAnalysis, y deja lo suyo: los
+// incorporados rellenan los campos de siempre (estadísticas, anomalía) o añaden un
+// resultado con su nombre, que se guarda en el catálogo junto a la captura (`analyses`).
+// Un análisis nuevo es un tipo que implementa Analyzer y una entrada en BUILTIN.
+
+pub type AnalyzerFuture<'a> = Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + 'a>>;
+
+pub trait Analyzer: Send + Sync {
+    // Nombre en la configuración y clave de sus resultados en el catálogo
+    fn name(&self) -> &'static str;
+
+    fn analyze<'a>(&'a self, state: &'a AppState, input: &'a CaptureInput, analysis: &'a mut CaptureAnalysis) -> AnalyzerFuture<'a>;
+}
+
+static BUILTIN: &[&dyn Analyzer] = &[&Stats, &Anomaly, &Hotspots, &Triage];
+
+// Los de siempre: sin configurar, una captura se analiza igual que antes
+pub fn default_analyzers() -> Vec<String> {
+    vec![Stats.name().to_string(), Anomaly.name().to_string()]
+}
+
+pub fn find_analyzer(name: &str) -> Option<&'static dyn Analyzer> {
+    BUILTIN.iter().copied().find(|a| a.name() == name)
+}
+
+// Las estadísticas van primero: los demás analizadores y la evaluación de umbrales las usan
+pub fn validate_analyzers(names: &[String]) -> Result<(), String> {
+    if names.first().map(String::as_str) != Some(Stats.name()) {
+        return Err(format!("analyzers: '{}' must come first", Stats.name()));
+    }
+    for (i, name) in names.iter().enumerate() {
+        if find_analyzer(name).is_none() {
+            let known: Vec<&str> = BUILTIN.iter().map(|a| a.name()).collect();
+            return Err(format!("analyzers: unknown analyzer '{}' ({})", name, known.join("|")));
+        }
+        if names[..i].contains(name) {
+            return Err(format!("analyzers: '{}' is listed twice", name));
+        }
+    }
+    Ok(())
+}
+
+// Estadísticas de la captura y por frame, corregidas con la calibración de la cámara
+// vigente al tomarla. La curva es monótona, así que la máxima corregida es exacta; la
+// media corregida es una aproximación (la curva no es lineal en general). También la
+// calidad de imagen, en bruto (ver analysis::image_quality)
+pub struct Stats;
+
+impl Analyzer for Stats {
+    fn name(&self) -> &'static str {
+        "stats"
+    }
+
+    fn analyze<'a>(&'a self, state: &'a AppState, input: &'a CaptureInput, analysis: &'a mut CaptureAnalysis) -> AnalyzerFuture<'a> {
+        Box::pin(async move {
+            let data = input.data.clone();
+            let (mut stats, shape, mut frames, quality) = tokio::task::spawn_blocking(move || {
+                (capture_stats(&data), frame_shape(&data), frame_points(&data), capture_quality(&data))
+            })
+            .await?;
+            let usable = state.config.read().await.usable_quality(quality.as_ref());
+            if let Some(quality) = quality.filter(|_| !usable) {
+                tracing::info!(filename = %input.filename, score = quality.score, "🌫️ Captura de baja calidad: no cuenta para líneas base ni tendencias");
+            }
+            let calibrations = state.calibrations.read().await;
+            if let Some(calibration) = calibration_at(&calibrations, &input.turbine_token, input.camera_id.as_deref(), input.timestamp) {
+                if let Some(stats) = stats.as_mut() {
+                    stats.max_temp = calibration.correct(stats.max_temp);
+                    stats.avg_temp = calibration.correct(stats.avg_temp);
+                }
+                for point in frames.iter_mut() {
+                    point.max_temp = calibration.correct(point.max_temp);
+                    point.avg_temp = calibration.correct(point.avg_temp);
+                }
+                analysis.calibration_version = Some(calibration.version);
+            }
+            analysis.stats = stats;
+            analysis.shape = shape;
+            analysis.frames = frames;
+            analysis.quality = quality;
+            Ok(())
+        })
+    }
+}
+
+// Puntuación de anomalía frente al historial de la misma turbina, cámara y ángulo
+pub struct Anomaly;
+
+impl Analyzer for Anomaly {
+    fn name(&self) -> &'static str {
+        "anomaly"
+    }
+
+    fn analyze<'a>(&'a self, state: &'a AppState, input: &'a CaptureInput, analysis: &'a mut CaptureAnalysis) -> AnalyzerFuture<'a> {
+        Box::pin(async move {
+            let Some(stats) = analysis.stats else { return Ok(()) };
+            let history = state.capture_history(&input.turbine_token, input.camera_id.as_deref(), input.angle, analysis.shape, input.timestamp).await;
+            analysis.anomaly_score = anomaly_score(&history, &stats);
+            if let Some(score) = analysis.anomaly_score.filter(|s| *s >= ANOMALY_NOTICE) {
+                tracing::info!(filename = %input.filename, anomaly_score = score, angle = input.angle, "📈 Captura inusual para su turbina y ángulo");
+            }
+            Ok(())
+        })
+    }
+}
+
+// Diferencia mínima (°C) entre el máximo y la mediana del frame para señalar un punto caliente
+const HOTSPOT_MIN_DELTA: f32 = 5.0;
+
+// Punto caliente del frame más caliente: posición del máximo, cuánto destaca sobre la
+// mediana del frame y cuántos píxeles están en la mitad superior de esa diferencia (su
+// tamaño aproximado). En bruto, sin calibrar: lo que importa es el contraste
+pub struct Hotspots;
+
+impl Analyzer for Hotspots {
+    fn name(&self) -> &'static str {
+        "hotspots"
+    }
+
+    fn analyze<'a>(&'a self, _state: &'a AppState, input: &'a CaptureInput, analysis: &'a mut CaptureAnalysis) -> AnalyzerFuture<'a> {
+        Box::pin(async move {
+            let data = input.data.clone();
+            let hotspot = tokio::task::spawn_blocking(move || {
+                let (frame_index, frame) = hottest_frame(&data)?;
+                let (mut peak, mut at) = (f32::NEG_INFINITY, (0, 0));
+                for (position, &value) in frame.indexed_iter() {
+                    if value > peak {
+                        peak = value;
+                        at = position;
+                    }
+                }
+                let mut sorted: Vec<f32> = frame.iter().copied().collect();
+                if sorted.is_empty() {
+                    return None;
+                }
+                sorted.sort_by(f32::total_cmp);
+                let median = sorted[sorted.len() / 2];
+                let delta = peak - median;
+                let pixels = frame.iter().filter(|&&v| v >= peak - delta / 2.0).count();
+                (delta >= HOTSPOT_MIN_DELTA).then(|| serde_json::json!({
+                    "frame_index": frame_index,
+                    "row": at.0,
+                    "col": at.1,
+                    "delta": delta,
+                    "pixels": pixels,
+                }))
+            })
+            .await?;
+            analysis.results.insert(self.name().to_string(), serde_json::json!(hotspot.into_iter().collect::<Vec<_>>()));
+            Ok(())
+        })
+    }
+}
+
+// Tipo de fallo probable del frame más caliente según el clasificador ONNX configurado,
+// para cribar capturas aunque no disparen alerta. Un fallo del modelo no frena la subida
+pub struct Triage;
+
+impl Analyzer for Triage {
+    fn name(&self) -> &'static str {
+        "triage"
+    }
+
+    fn analyze<'a>(&'a self, state: &'a AppState, input: &'a CaptureInput, analysis: &'a mut CaptureAnalysis) -> AnalyzerFuture<'a> {
+        Box::pin(async move {
+            // Las capturas de baja calidad no se criban (ver RemoteConfig::min_capture_quality)
+            let classifier_config = {
+                let config = state.config.read().await;
+                config.classifier.clone().filter(|_| config.usable_quality(analysis.quality.as_ref()))
+            };
+            let Some(config) = classifier_config else { return Ok(()) };
+            let (data, classifier) = (input.data.clone(), state.classifier.clone());
+            let params = serde_json::json!({ "model_path": config.model_path, "analyzer": self.name() });
+            let result = tokio::task::spawn_blocking(move || {
+                let (frame_index, frame) = hottest_frame(&data).ok_or("unreadable capture")?;
+                classifier.classify(&config, &frame).map(|prediction| (frame_index, prediction))
+            })
+            .await?;
+            match result {
+                Ok((frame_index, prediction)) => {
+                    let inputs = [(input.filename.as_str(), Some(frame_index))];
+                    record_derivation(state, "fault_triage", &inputs, params, None).await;
+                    analysis.results.insert(self.name().to_string(), serde_json::json!(prediction));
+                }
+                Err(e) => tracing::warn!(filename = %input.filename, error = %e, "⚠️ Error en el cribado de la captura"),
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{
+    routes::ingest::{HeartbeatPayload, UploadResponse},
+    schedule::parse_duration,
+    simulate::{synthetic_frame, Hotspot},
+    state::LiveStatus,
+};
+use axum::body::Bytes;
+use ndarray::{Array3, Axis};
+use ndarray_npy::WriteNpyExt;
+use reqwest::{multipart::{Form, Part}, RequestBuilder};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{sync::Mutex, time::Instant};
+
+// --- BANCO DE CARGA (subcomando bench) ---
+// Castiga una instancia en marcha como lo haría un sitio entero: N turbinas falsas
+// (BENCH-001...) que envían heartbeats y suben capturas sintéticas del tamaño indicado,
+// más lectores que piden matrices de las capturas recién subidas como un dashboard. Al
+// terminar muestra por operación peticiones, errores, rendimiento y percentiles de
+// latencia (cuerpo de la respuesta incluido), para validar la capacidad antes de instalar.
+// Las capturas quedan guardadas de verdad: después se borran con
+// DELETE /api/turbines/:token/data.
+
+// Capturas recientes entre las que eligen los lectores
+const RECENT_CAPTURES: usize = 256;
+
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    // URL base de la instancia a medir
+    #[arg(long, default_value = "http://127.0.0.1:8080")]
+    pub url: String,
+    // Turbinas simuladas
+    #[arg(long, default_value_t = 50)]
+    pub turbines: usize,
+    // Duración de la prueba ("90s", "5m")
+    #[arg(long, default_value = "60s", value_parser = parse_duration)]
+    pub duration: u64,
+    #[arg(long, default_value = "1s", value_parser = parse_duration)]
+    pub heartbeat_interval: u64,
+    // Espera entre capturas de cada turbina (como scan_wait_time_sec)
+    #[arg(long, default_value = "5s", value_parser = parse_duration)]
+    pub upload_interval: u64,
+    // Tamaño de cada frame y frames por captura
+    #[arg(long, default_value_t = 120)]
+    pub frame_rows: usize,
+    #[arg(long, default_value_t = 160)]
+    pub frame_cols: usize,
+    #[arg(long, default_value_t = 1)]
+    pub frames: usize,
+    // Lectores de matrices en paralelo (0 = sin lecturas)
+    #[arg(long, default_value_t = 4)]
+    pub readers: usize,
+    // Clave de ingesta, si la instancia la exige
+    #[arg(long, env = "SENTINEL_BENCH_INGEST_KEY", hide_env_values = true)]
+    pub ingest_key: Option<String>,
+    // Prefijo de los tokens de las turbinas simuladas
+    #[arg(long, default_value = "BENCH")]
+    pub prefix: String,
+    // Informe en JSON en lugar de tabla
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Default)]
+struct OperationStats {
+    latencies_ms: Vec<f64>,
+    // Por código HTTP o tipo de fallo de red
+    errors: BTreeMap<String, u64>,
+    bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct OperationReport {
+    pub operation: &'static str,
+    pub requests: usize,
+    pub errors: BTreeMap<String, u64>,
+    pub per_sec: f64,
+    pub mb_per_sec: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+struct Bench {
+    args: BenchArgs,
+    http: reqwest::Client,
+    stats: Mutex<BTreeMap<&'static str, OperationStats>>,
+    recent: Mutex<VecDeque<String>>,
+}
+
+// Percentil por el método del rango más próximo (latencias ya ordenadas)
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        n => sorted[((p * n as f64).ceil() as usize).clamp(1, n) - 1],
+    }
+}
+
+impl Bench {
+    // Envía la petición y lee la respuesta entera; None si falla
+    async fn timed(&self, operation: &'static str, sent_bytes: u64, request: RequestBuilder) -> Option<Bytes> {
+        let request = match &self.args.ingest_key {
+            Some(key) => request.header(crate::routes::devices::INGEST_KEY_HEADER, key),
+            None => request,
+        };
+        let start = Instant::now();
+        let result = match request.send().await {
+            Ok(response) if response.status().is_success() => response.bytes().await.map_err(|e| e.to_string()),
+            Ok(response) => Err(response.status().as_u16().to_string()),
+            Err(e) if e.is_timeout() => Err("timeout".into()),
+            Err(e) if e.is_connect() => Err("connect".into()),
+            Err(e) => Err(e.to_string()),
+        };
+        let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let mut stats = self.stats.lock().await;
+        let entry = stats.entry(operation).or_default();
+        match result {
+            Ok(body) => {
+                entry.latencies_ms.push(elapsed_ms);
+                entry.bytes += sent_bytes + body.len() as u64;
+                Some(body)
+            }
+            Err(error) => {
+                *entry.errors.entry(error).or_insert(0) += 1;
+                None
+            }
+        }
+    }
+
+    async fn heartbeat(&self, token: &str) {
+        let heartbeat = HeartbeatPayload {
+            status: LiveStatus {
+                last_update: 0,
+                turbine_token: token.to_string(),
+                mode: "Scanning".into(),
+                current_angle: 0.0,
+                current_max_temp: 0.0,
+                is_online: true,
+            },
+            // Con versión el servidor responde solo el acuse, como a los robots actuales
+            config_version: Some(String::new()),
+            calibration_version: None,
+            robot_time: Some(chrono::Utc::now().timestamp() as u64),
+            reported: None,
+        };
+        let request = self.http.post(format!("{}/ingest/heartbeat", self.args.url)).json(&heartbeat);
+        self.timed("heartbeat", 0, request).await;
+    }
+
+    async fn upload(&self, token: &str, rng: &mut fastrand::Rng, angle: f32) {
+        let (rows, cols, frames) = (self.args.frame_rows, self.args.frame_cols, self.args.frames);
+        let seed = rng.u64(..);
+        let npy = tokio::task::spawn_blocking(move || {
+            let mut rng = fastrand::Rng::with_seed(seed);
+            let hotspot = Hotspot { row: rows as f32 / 2.0, col: cols as f32 / 2.0, delta: 10.0, sigma: 2.0 };
+            let mut stack = Array3::<f32>::zeros((frames, rows, cols));
+            for mut frame in stack.axis_iter_mut(Axis(0)) {
+                frame.assign(&synthetic_frame(&mut rng, rows, cols, 15.0, Some(&hotspot)));
+            }
+            let mut npy = Vec::new();
+            match frames {
+                1 => stack.index_axis(Axis(0), 0).write_npy(&mut npy),
+                _ => stack.write_npy(&mut npy),
+            }
+            .map(|_| npy)
+        })
+        .await;
+        let Ok(Ok(npy)) = npy else { return };
+        let size = npy.len() as u64;
+        let form = Form::new()
+            .text("turbine_token", token.to_string())
+            .text("angle", angle.to_string())
+            .part("dataset_file", Part::bytes(npy).file_name("capture.npy"));
+        let request = self.http.post(format!("{}/ingest/upload", self.args.url)).multipart(form);
+        let Some(body) = self.timed("upload", size, request).await else { return };
+        // Las apartadas a la cuarentena no tienen nombre de archivo
+        if let Ok(response) = serde_json::from_slice::<UploadResponse>(&body)
+            && !response.filename.is_empty()
+        {
+            let filename = response.filename;
+            let mut recent = self.recent.lock().await;
+            recent.push_front(filename);
+            recent.truncate(RECENT_CAPTURES);
+        }
+    }
+
+    async fn run_turbine(self: Arc<Self>, token: String, deadline: Instant) {
+        let mut rng = fastrand::Rng::new();
+        // Escalonar las turbinas para no sincronizar las ráfagas
+        let stagger = |period: u64, rng: &mut fastrand::Rng| Duration::from_millis(rng.u64(0..period.max(1) * 1000));
+        let mut heartbeats = tokio::time::interval_at(Instant::now() + stagger(self.args.heartbeat_interval, &mut rng), Duration::from_secs(self.args.heartbeat_interval.max(1)));
+        let mut uploads = tokio::time::interval_at(Instant::now() + stagger(self.args.upload_interval, &mut rng), Duration::from_secs(self.args.upload_interval.max(1)));
+        let mut angle = rng.f32() * 360.0;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => break,
+                _ = heartbeats.tick() => self.heartbeat(&token).await,
+                _ = uploads.tick() => {
+                    self.upload(&token, &mut rng, angle).await;
+                    angle = (angle + 0.5).rem_euclid(360.0);
+                }
+            }
+        }
+    }
+
+    async fn run_reader(self: Arc<Self>, deadline: Instant) {
+        let mut rng = fastrand::Rng::new();
+        while Instant::now() < deadline {
+            let filename = {
+                let recent = self.recent.lock().await;
+                (!recent.is_empty()).then(|| recent[rng.usize(..recent.len())].clone())
+            };
+            let Some(filename) = filename else {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                continue;
+            };
+            let request = self.http.get(format!("{}/api/matrix/{}/0", self.args.url, filename));
+            self.timed("matrix", 0, request).await;
+        }
+    }
+
+    async fn report(&self, elapsed_sec: f64) -> Vec<OperationReport> {
+        let mut stats = self.stats.lock().await;
+        stats.iter_mut()
+            .map(|(operation, stats)| {
+                stats.latencies_ms.sort_by(f64::total_cmp);
+                let sorted = &stats.latencies_ms;
+                OperationReport {
+                    operation,
+                    requests: sorted.len() + stats.errors.values().sum::<u64>() as usize,
+                    errors: stats.errors.clone(),
+                    per_sec: sorted.len() as f64 / elapsed_sec,
+                    mb_per_sec: stats.bytes as f64 / 1_048_576.0 / elapsed_sec,
+                    p50_ms: percentile(sorted, 0.5),
+                    p90_ms: percentile(sorted, 0.9),
+                    p99_ms: percentile(sorted, 0.99),
+                    max_ms: sorted.last().copied().unwrap_or(0.0),
+                }
+            })
+            .collect()
+    }
+}
+
+pub async fn run(args: BenchArgs) -> std::io::Result<()> {
+    if args.turbines == 0 || args.duration == 0 || args.frames == 0 || args.frame_rows < 2 || args.frame_cols < 2 {
+        return Err(std::io::Error::other("bench: turbines, duration and frames must be positive and frames at least 2x2"));
+    }
+    let url = args.url.trim_end_matches('/').to_string();
+    tracing::info!(
+        %url,
+        turbines = args.turbines,
+        duration_sec = args.duration,
+        frame = %format!("{}x{}x{}", args.frames, args.frame_rows, args.frame_cols),
+        readers = args.readers,
+        "🏋️ Banco de carga en marcha"
+    );
+    let (json, turbines, readers, prefix) = (args.json, args.turbines, args.readers, args.prefix.clone());
+    let bench = Arc::new(Bench {
+        args: BenchArgs { url, ..args },
+        http: reqwest::Client::builder().timeout(Duration::from_secs(60)).build().map_err(std::io::Error::other)?,
+        stats: Mutex::new(BTreeMap::new()),
+        recent: Mutex::new(VecDeque::new()),
+    });
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(bench.args.duration);
+    let mut tasks = Vec::new();
+    for i in 1..=turbines {
+        tasks.push(tokio::spawn(bench.clone().run_turbine(format!("{}-{:03}", prefix, i), deadline)));
+    }
+    for _ in 0..readers {
+        tasks.push(tokio::spawn(bench.clone().run_reader(deadline)));
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+    let report = bench.report(start.elapsed().as_secs_f64()).await;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).map_err(std::io::Error::other)?);
+        return Ok(());
+    }
+    println!("{:<10} {:>9} {:>7} {:>9} {:>8} {:>9} {:>9} {:>9} {:>9}", "operation", "requests", "errors", "req/s", "MB/s", "p50 ms", "p90 ms", "p99 ms", "max ms");
+    for op in &report {
+        let errors: u64 = op.errors.values().sum();
+        println!(
+            "{:<10} {:>9} {:>7} {:>9.1} {:>8.2} {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+            op.operation, op.requests, errors, op.per_sec, op.mb_per_sec, op.p50_ms, op.p90_ms, op.p99_ms, op.max_ms
+        );
+        for (error, count) in &op.errors {
+            println!("{:<10}   {} × {}", "", count, error);
+        }
+    }
+    tracing::info!(turbines = turbines, prefix = %prefix, "🧹 Las capturas del banco quedan guardadas: bórralas con DELETE /api/turbines/:token/data");
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use crate::analysis::{fusion::PhotoRegion, pixel_units::RawCountConversion};
+use serde::{Deserialize, Serialize};
+
+// --- CALIBRACIÓN DE CÁMARAS ---
+// Cada cámara de cada robot puede tener calibraciones versionadas: parámetros intrínsecos
+// (opacos para el servidor: se guardan y se entregan al robot en el heartbeat) y una
+// curva de corrección de temperatura, que el servidor aplica a las estadísticas de las
+// capturas antes de compararlas con los umbrales. Vale la última versión creada antes
+// de la captura. Con photo_region indica además qué parte de la foto visible cubre la
+// cámara térmica, para la fusión de ambas (ver analysis::fusion). Con raw_counts, cómo
+// pasar a °C las subidas de la cámara en cuentas en bruto (ver analysis::pixel_units).
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct CorrectionPoint {
+    // Temperatura que mide la cámara
+    pub raw: f32,
+    // Temperatura real de referencia (cuerpo negro...)
+    pub corrected: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CameraCalibration {
+    pub turbine_token: String,
+    // None = cámara única del robot
+    #[serde(default)]
+    pub camera_id: Option<String>,
+    // Correlativa por (turbina, cámara)
+    pub version: u64,
+    pub created: u64,
+    #[serde(default)]
+    pub author: Option<String>,
+    // Focal, centro óptico, distorsión... en el formato que use el robot
+    #[serde(default)]
+    pub intrinsics: Option<serde_json::Value>,
+    // Puntos ordenados por `raw`; se interpola linealmente y fuera del rango se prolonga
+    // el tramo extremo (vacía = sin corrección)
+    #[serde(default)]
+    pub correction: Vec<CorrectionPoint>,
+    // Región de la foto visible que cubre el frame térmico (None = toda la foto)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo_region: Option<PhotoRegion>,
+    // Conversión de las subidas con pixel_unit=raw_counts (None = la cámara envía °C)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_counts: Option<RawCountConversion>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl CameraCalibration {
+    // Curva con al menos dos puntos, `raw` estrictamente creciente y `corrected` no
+    // decreciente: así la máxima corregida es la corrección de la máxima
+    pub fn validate(&self) -> Result<(), String> {
+        let points = &self.correction;
+        if points.len() == 1 {
+            return Err("correction needs at least two points".into());
+        }
+        if points.iter().any(|p| !p.raw.is_finite() || !p.corrected.is_finite()) {
+            return Err("correction points must be finite".into());
+        }
+        if points.windows(2).any(|w| w[1].raw <= w[0].raw || w[1].corrected < w[0].corrected) {
+            return Err("correction points must be increasing".into());
+        }
+        if let Some(region) = &self.photo_region {
+            region.validate()?;
+        }
+        if let Some(conversion) = &self.raw_counts {
+            conversion.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn correct(&self, raw: f32) -> f32 {
+        let points = &self.correction;
+        if points.len() < 2 {
+            return raw;
+        }
+        // Tramo que contiene `raw` (o el extremo más cercano)
+        let segment = points.windows(2)
+            .position(|w| raw <= w[1].raw)
+            .unwrap_or(points.len() - 2);
+        let (a, b) = (points[segment], points[segment + 1]);
+        a.corrected + (raw - a.raw) * (b.corrected - a.corrected) / (b.raw - a.raw)
+    }
+}
+
+// Calibración vigente de una cámara en un instante
+pub fn calibration_at<'a>(
+    calibrations: &'a [CameraCalibration],
+    turbine_token: &str,
+    camera_id: Option<&str>,
+    timestamp: u64,
+) -> Option<&'a CameraCalibration> {
+    calibrations.iter()
+        .filter(|c| c.turbine_token == turbine_token && c.camera_id.as_deref() == camera_id)
+        .filter(|c| c.created <= timestamp)
+        .max_by_key(|c| c.version)
+}
+
+// Última versión de cada cámara de una turbina
+pub fn latest_calibrations(calibrations: &[CameraCalibration], turbine_token: &str) -> Vec<CameraCalibration> {
+    let mut latest: Vec<CameraCalibration> = Vec::new();
+    for calibration in calibrations.iter().filter(|c| c.turbine_token == turbine_token) {
+        match latest.iter_mut().find(|c| c.camera_id == calibration.camera_id) {
+            Some(current) if current.version >= calibration.version => {}
+            Some(current) => *current = calibration.clone(),
+            None => latest.push(calibration.clone()),
+        }
+    }
+    latest.sort_by(|a, b| a.camera_id.cmp(&b.camera_id));
+    latest
+}
@@ -0,0 +1,169 @@
+use reqwest::{
+    multipart::{Form, Part},
+    RequestBuilder, StatusCode,
+};
+use serde::de::DeserializeOwned;
+
+// Tipos de la API, los mismos que usa el servidor
+pub use crate::{
+    analysis::{pixel_units::PixelUnit, ThermalFrameData},
+    commands::RobotCommand,
+    routes::ingest::{HeartbeatAck, HeartbeatPayload, ReceiptStatus, RobotConfig, UploadResponse},
+    state::{AlertRecord, LiveStatus, RemoteConfig},
+    storage::catalog::CaptureRecord,
+    thresholds::Severity,
+};
+
+// --- CLIENTE TIPADO DE LA API (feature "client-types") ---
+// Para el firmware de los robots y las herramientas en Rust: en lugar de redeclarar las
+// estructuras de la API dependen de este crate con la feature client-types y usan las
+// mismas que el servidor, más este cliente reqwest mínimo para las rutas de ingesta y
+// las de lectura del dashboard. La clave de ingesta va en X-Ingest-Key y el token de
+// administración como Bearer. Las respuestas de error llevan el mensaje del servidor.
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("server responded {status}: {message}")]
+    Api { status: StatusCode, message: String },
+}
+
+// Una captura para /ingest/upload
+pub struct CaptureUpload {
+    pub turbine_token: String,
+    pub angle: f32,
+    pub rotor_phase: Option<f32>,
+    pub camera_id: Option<String>,
+    // Contenido .npy o .npz
+    pub data: Vec<u8>,
+    // Unidad de los píxeles de `data`; el servidor convierte a °C lo que no lo esté
+    pub pixel_unit: PixelUnit,
+    // Foto visible JPEG o PNG de la misma posición
+    pub photo: Option<Vec<u8>>,
+}
+
+#[derive(Clone)]
+pub struct SentinelClient {
+    base_url: String,
+    http: reqwest::Client,
+    ingest_key: Option<String>,
+    admin_token: Option<String>,
+}
+
+impl SentinelClient {
+    pub fn new(base_url: &str) -> Self {
+        SentinelClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            ingest_key: None,
+            admin_token: None,
+        }
+    }
+
+    pub fn with_http(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn with_ingest_key(mut self, key: &str) -> Self {
+        self.ingest_key = Some(key.to_string());
+        self
+    }
+
+    pub fn with_admin_token(mut self, token: &str) -> Self {
+        self.admin_token = Some(token.to_string());
+        self
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = match &self.ingest_key {
+            Some(key) => request.header(crate::routes::devices::INGEST_KEY_HEADER, key),
+            None => request,
+        };
+        match &self.admin_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+        let response = self.authorized(request).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(ClientError::Api { status, message });
+        }
+        Ok(response.json().await?)
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.http.get(format!("{}{}", self.base_url, path))
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.http.post(format!("{}{}", self.base_url, path))
+    }
+
+    // --- ROBOTS ---
+
+    // Con config_version (vacía la primera vez) el servidor responde con el acuse; si
+    // config_changed, hay que pedir la configuración con robot_config
+    pub async fn heartbeat(&self, mut heartbeat: HeartbeatPayload) -> Result<HeartbeatAck, ClientError> {
+        heartbeat.config_version.get_or_insert_with(String::new);
+        self.send(self.post("/ingest/heartbeat").json(&heartbeat)).await
+    }
+
+    pub async fn robot_config(&self, turbine_token: &str) -> Result<RobotConfig, ClientError> {
+        self.send(self.get(&format!("/ingest/config/{}", turbine_token))).await
+    }
+
+    pub async fn upload(&self, upload: CaptureUpload) -> Result<UploadResponse, ClientError> {
+        let mut form = Form::new()
+            .text("turbine_token", upload.turbine_token)
+            .text("angle", upload.angle.to_string())
+            .part("dataset_file", Part::bytes(upload.data).file_name("capture.npy"));
+        if let Some(rotor_phase) = upload.rotor_phase {
+            form = form.text("rotor_phase", rotor_phase.to_string());
+        }
+        if let Some(camera_id) = upload.camera_id {
+            form = form.text("camera_id", camera_id);
+        }
+        if upload.pixel_unit != PixelUnit::Celsius {
+            form = form.text("pixel_unit", upload.pixel_unit.as_str());
+        }
+        if let Some(photo) = upload.photo {
+            form = form.part("photo", Part::bytes(photo).file_name("photo"));
+        }
+        self.send(self.post("/ingest/upload").multipart(form)).await
+    }
+
+    // Un 404 (ClientError::Api) significa que el servidor no tiene la captura
+    pub async fn receipt(&self, receipt_id: &str) -> Result<ReceiptStatus, ClientError> {
+        self.send(self.get(&format!("/ingest/receipt/{}", receipt_id))).await
+    }
+
+    // Espera hasta `wait_sec` a que haya comandos pendientes
+    pub async fn poll_commands(&self, turbine_token: &str, wait_sec: u64) -> Result<Vec<RobotCommand>, ClientError> {
+        self.send(self.get(&format!("/ingest/commands/{}", turbine_token)).query(&[("wait", wait_sec)])).await
+    }
+
+    // --- DASHBOARD ---
+
+    pub async fn live(&self) -> Result<LiveStatus, ClientError> {
+        self.send(self.get("/api/live")).await
+    }
+
+    pub async fn config(&self) -> Result<RemoteConfig, ClientError> {
+        self.send(self.get("/api/config")).await
+    }
+
+    // Temperaturas en °C, sea cual sea display_units
+    pub async fn alerts(&self) -> Result<Vec<AlertRecord>, ClientError> {
+        self.send(self.get("/api/alerts").query(&[("units", "c")])).await
+    }
+
+    pub async fn matrix(&self, filename: &str, frame_index: usize) -> Result<ThermalFrameData, ClientError> {
+        self.send(self.get(&format!("/api/matrix/{}/{}", filename, frame_index)).query(&[("units", "c")])).await
+    }
+}
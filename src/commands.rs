@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+use tokio::sync::{Mutex, Notify};
+
+// --- CANAL DE COMANDOS A LOS ROBOTS ---
+// Los robots están detrás de NAT, así que no se les puede llamar: piden sus comandos con
+// un long-poll (GET /ingest/commands/:token?wait=30) que el servidor mantiene abierto
+// hasta que se encola algo para esa turbina o vence la espera. Cada comando se entrega
+// una sola vez; después el robot informa de su avance (acuse, completado o fallo) y el
+// dashboard lo consulta en el historial reciente de la turbina.
+
+// Espera máxima que puede pedir un robot
+pub const MAX_WAIT_SEC: u64 = 60;
+// Comandos recientes que se conservan por turbina; al superarlo se descartan los más antiguos
+pub const MAX_COMMANDS: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandStatus {
+    // Encolado, el robot aún no lo ha recogido
+    Pending,
+    // Entregado en un long-poll
+    Delivered,
+    // El robot lo aceptó y lo está ejecutando
+    Acked,
+    Completed,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RobotCommand {
+    pub id: String,
+    pub command: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+    pub created: u64,
+    pub status: CommandStatus,
+    // Última actualización de estado y mensaje del robot (p. ej. motivo del fallo)
+    #[serde(default)]
+    pub updated: Option<u64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl RobotCommand {
+    pub fn new(command: &str, params: serde_json::Value) -> Self {
+        RobotCommand {
+            id: uuid::Uuid::new_v4().to_string(),
+            command: command.to_string(),
+            params,
+            created: chrono::Utc::now().timestamp() as u64,
+            status: CommandStatus::Pending,
+            updated: None,
+            message: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CommandQueue {
+    // Comandos recientes de cada turbina, del más antiguo al más nuevo
+    commands: Mutex<HashMap<String, VecDeque<RobotCommand>>>,
+    // Despierta a todos los long-polls en espera; cada uno revisa su propia cola
+    notify: Notify,
+}
+
+impl CommandQueue {
+    pub async fn enqueue(&self, turbine_token: &str, command: RobotCommand) {
+        {
+            let mut commands = self.commands.lock().await;
+            let queue = commands.entry(turbine_token.to_string()).or_default();
+            queue.push_back(command);
+            while queue.len() > MAX_COMMANDS {
+                queue.pop_front();
+            }
+        }
+        self.notify.notify_waiters();
+    }
+
+    pub async fn recent(&self, turbine_token: &str) -> Vec<RobotCommand> {
+        self.commands.lock().await.get(turbine_token).map(|q| q.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub async fn get(&self, turbine_token: &str, id: &str) -> Option<RobotCommand> {
+        self.commands.lock().await.get(turbine_token)?.iter().find(|c| c.id == id).cloned()
+    }
+
+    // Entrega los comandos pendientes, que pasan a "delivered"
+    pub async fn take(&self, turbine_token: &str) -> Vec<RobotCommand> {
+        let mut commands = self.commands.lock().await;
+        let Some(queue) = commands.get_mut(turbine_token) else { return Vec::new() };
+        let now = chrono::Utc::now().timestamp() as u64;
+        queue.iter_mut()
+            .filter(|c| c.status == CommandStatus::Pending)
+            .map(|c| {
+                c.status = CommandStatus::Delivered;
+                c.updated = Some(now);
+                c.clone()
+            })
+            .collect()
+    }
+
+    // Estado informado por el robot; None si el comando no existe
+    pub async fn update(&self, turbine_token: &str, id: &str, status: CommandStatus, message: Option<String>) -> Option<RobotCommand> {
+        let mut commands = self.commands.lock().await;
+        let command = commands.get_mut(turbine_token)?.iter_mut().find(|c| c.id == id)?;
+        command.status = status;
+        command.updated = Some(chrono::Utc::now().timestamp() as u64);
+        command.message = message;
+        Some(command.clone())
+    }
+
+    // Espera hasta `wait` a que haya comandos para la turbina; vacío si vence la espera
+    pub async fn wait_for(&self, turbine_token: &str, wait: Duration) -> Vec<RobotCommand> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            // Registrarse antes de mirar la cola para no perder un aviso entre medias
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let commands = self.take(turbine_token).await;
+            if !commands.is_empty() {
+                return commands;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return Vec::new();
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    analysis::pixel_units::PixelUnit,
+    notify,
+    routes::ingest::{ingest_capture, CaptureUpload},
+    state::{AlertRecord, AppState, RemoteConfig},
+    thresholds::Severity,
+};
+use axum::body::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+// --- PASARELA DE CORREO PARA SENSORES ANTIGUOS ---
+// Algunos sensores solo saben enviar correos. Con --imap-server el servidor revisa
+// periódicamente la carpeta indicada y pasa cada mensaje no leído por las reglas de
+// correo de la configuración (email_rules): la primera que coincide con el remitente y
+// el asunto lo convierte en una alerta (temperatura leída del texto) o en capturas (los
+// adjuntos .npy, por el mismo camino que las subidas). Los mensajes procesados se marcan
+// como leídos, coincidan o no con alguna regla. Requiere la feature `imap`.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailAction {
+    Alert,
+    Capture,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EmailRule {
+    pub name: String,
+    // Texto que debe contener el remitente / el asunto (sin distinguir mayúsculas;
+    // ausente = cualquiera)
+    #[serde(default)]
+    pub from_contains: Option<String>,
+    #[serde(default)]
+    pub subject_contains: Option<String>,
+    // Turbina a la que se atribuye el mensaje
+    pub turbine_token: String,
+    pub action: EmailAction,
+    // Texto tras el que aparece la temperatura en el asunto o el cuerpo ("Temp:");
+    // obligatorio para las alertas
+    #[serde(default)]
+    pub temp_marker: Option<String>,
+    // Igual para el ángulo (por defecto 0)
+    #[serde(default)]
+    pub angle_marker: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+    // Nombres de canales de notificación (ver RemoteConfig::channels)
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+impl EmailRule {
+    fn matches(&self, message: &EmailMessage) -> bool {
+        let contains = |text: &str, pattern: &Option<String>| {
+            pattern.as_ref().is_none_or(|p| text.to_lowercase().contains(&p.to_lowercase()))
+        };
+        contains(&message.from, &self.from_contains) && contains(&message.subject, &self.subject_contains)
+    }
+
+    pub fn validate(&self, config: &RemoteConfig) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.turbine_token.trim().is_empty() {
+            return Err("email rule: name and turbine_token are required".into());
+        }
+        if self.action == EmailAction::Alert && self.temp_marker.as_deref().is_none_or(str::is_empty) {
+            return Err(format!("email rule '{}': alerts need a temp_marker", self.name));
+        }
+        if let Some(missing) = self.channels.iter().find(|c| !config.channels.iter().any(|ch| ch.name == **c)) {
+            return Err(format!("email rule '{}': unknown channel '{}'", self.name, missing));
+        }
+        Ok(())
+    }
+}
+
+// Mensaje ya decodificado (lo que necesitan las reglas)
+pub struct EmailMessage {
+    pub from: String,
+    pub subject: String,
+    pub body: String,
+    // (nombre de archivo, contenido)
+    pub attachments: Vec<(String, Vec<u8>)>,
+}
+
+// Número que sigue a `marker` en el asunto o, si no está, en el cuerpo ("Temp: 71.5 C")
+fn number_after(message: &EmailMessage, marker: &str) -> Option<f32> {
+    [&message.subject, &message.body].into_iter().find_map(|text| {
+        let rest = &text[text.find(marker)? + marker.len()..];
+        let rest = rest.trim_start();
+        let end = rest.find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))).unwrap_or(rest.len());
+        rest[..end].parse::<f32>().ok().filter(|v| v.is_finite())
+    })
+}
+
+// Aplica la primera regla que coincide; devuelve su nombre
+pub async fn process_message(state: &AppState, message: &EmailMessage) -> Option<String> {
+    let rule = state.config.read().await.email_rules.iter().find(|r| r.matches(message)).cloned()?;
+    let angle = rule.angle_marker.as_deref().and_then(|m| number_after(message, m)).unwrap_or(0.0);
+    match rule.action {
+        EmailAction::Alert => {
+            let Some(max_temp) = rule.temp_marker.as_deref().and_then(|m| number_after(message, m)) else {
+                tracing::warn!(rule = %rule.name, subject = %message.subject, "⚠️ Correo sin temperatura legible, se ignora");
+                return Some(rule.name);
+            };
+            let alert = AlertRecord {
+                id: uuid::Uuid::new_v4().to_string(),
+                timestamp: chrono::Utc::now().timestamp() as u64,
+                turbine_token: rule.turbine_token.clone(),
+                max_temp,
+                angle,
+                // Sin captura asociada
+                dataset_path: String::new(),
+                zone: None,
+                severity: rule.severity,
+                level: Some(format!("email:{}", rule.name)),
+                ambient: None,
+                blade_imbalance: None,
+                fault_prediction: None,
+                incident_id: None,
+                starred: false,
+                frame_index: None,
+                links: None,
+                rules: Vec::new(),
+                confirmation: None,
+                work_order: None,
+                resolution: None,
+            };
+            notify::raise_alert(state, alert, &rule.channels).await;
+        }
+        EmailAction::Capture => {
+            for (name, data) in message.attachments.iter().filter(|(name, _)| name.to_lowercase().ends_with(".npy")) {
+                let upload = CaptureUpload {
+                    turbine_token: rule.turbine_token.clone(),
+                    angle,
+                    rotor_phase: None,
+                    camera_id: None,
+                    source: Some("email".into()),
+                    captured_at: None,
+                    force: false,
+                    receipt_id: None,
+                    data: Bytes::from(data.clone()),
+                    photo: None,
+                    pixel_unit: PixelUnit::Celsius,
+                };
+                match ingest_capture(state, upload).await {
+                    Ok(response) => tracing::info!(rule = %rule.name, attachment = %name, filename = %response.filename, "📧 Captura recibida por correo"),
+                    Err(e) => tracing::warn!(rule = %rule.name, attachment = %name, error = %e, "⚠️ Adjunto de correo rechazado"),
+                }
+            }
+        }
+    }
+    Some(rule.name)
+}
+
+// Revisión periódica del buzón (solo con --imap-server)
+pub fn spawn_email_poller(state: &Arc<AppState>) {
+    let Some(server) = state.settings.imap_server.clone() else {
+        return;
+    };
+    if !imap::AVAILABLE {
+        tracing::error!(%server, "❌ Pasarela de correo no disponible: servidor compilado sin la feature `imap`");
+        return;
+    }
+    tracing::info!(%server, folder = %state.settings.imap_folder, "📧 Pasarela de correo activa");
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(state.settings.imap_poll_sec.max(10)));
+        loop {
+            interval.tick().await;
+            match imap::poll_mailbox(&state, &server).await {
+                Ok(0) => {}
+                Ok(processed) => tracing::info!(processed, "📧 Correos procesados"),
+                Err(e) => tracing::warn!(%server, error = %e, "⚠️ Error revisando el buzón"),
+            }
+        }
+    });
+}
+
+#[cfg(feature = "imap")]
+mod imap {
+    use super::{process_message, EmailMessage};
+    use crate::state::AppState;
+    use mail_parser::{MessageParser, MimeHeaders};
+    use std::sync::Arc;
+    use tokio::net::TcpStream;
+    use tokio_rustls::{
+        rustls::{pki_types::ServerName, ClientConfig, RootCertStore},
+        TlsConnector,
+    };
+    use tokio_stream::StreamExt;
+
+    pub const AVAILABLE: bool = true;
+    const DEFAULT_PORT: u16 = 993;
+
+    fn decode(raw: &[u8]) -> Option<EmailMessage> {
+        let message = MessageParser::default().parse(raw)?;
+        Some(EmailMessage {
+            from: message.from()
+                .and_then(|from| from.first())
+                .and_then(|addr| addr.address())
+                .unwrap_or_default()
+                .to_string(),
+            subject: message.subject().unwrap_or_default().to_string(),
+            body: message.body_text(0).map(|b| b.into_owned()).unwrap_or_default(),
+            attachments: message.attachments()
+                .map(|part| (part.attachment_name().unwrap_or_default().to_string(), part.contents().to_vec()))
+                .collect(),
+        })
+    }
+
+    // Una pasada por los mensajes no leídos; devuelve cuántos se procesaron
+    pub async fn poll_mailbox(state: &AppState, server: &str) -> Result<usize, String> {
+        let settings = &state.settings;
+        let (host, port) = match server.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| format!("invalid port in '{}'", server))?),
+            None => (server, DEFAULT_PORT),
+        };
+        let tcp = TcpStream::connect((host, port)).await.map_err(|e| e.to_string())?;
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let tls_config = ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+        let domain = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+        let tls = TlsConnector::from(Arc::new(tls_config)).connect(domain, tcp).await.map_err(|e| e.to_string())?;
+
+        let user = settings.imap_user.as_deref().unwrap_or_default();
+        let password = settings.imap_password.as_deref().unwrap_or_default();
+        let mut session = async_imap::Client::new(tls).login(user, password).await.map_err(|(e, _)| e.to_string())?;
+        session.select(&settings.imap_folder).await.map_err(|e| e.to_string())?;
+        let uids = session.uid_search("UNSEEN").await.map_err(|e| e.to_string())?;
+        let mut processed = 0;
+        for uid in uids {
+            let fetches: Vec<_> = session.uid_fetch(uid.to_string(), "RFC822").await.map_err(|e| e.to_string())?
+                .collect::<Vec<_>>()
+                .await;
+            for fetch in fetches.into_iter().flatten() {
+                let Some(message) = fetch.body().and_then(decode) else { continue };
+                match process_message(state, &message).await {
+                    Some(rule) => tracing::debug!(%rule, subject = %message.subject, "📧 Correo procesado"),
+                    None => tracing::debug!(from = %message.from, subject = %message.subject, "📧 Correo sin regla aplicable"),
+                }
+            }
+            let _: Vec<_> = session.uid_store(uid.to_string(), "+FLAGS (\\Seen)").await.map_err(|e| e.to_string())?
+                .collect::<Vec<_>>()
+                .await;
+            processed += 1;
+        }
+        let _ = session.logout().await;
+        Ok(processed)
+    }
+}
+
+#[cfg(not(feature = "imap"))]
+mod imap {
+    use crate::state::AppState;
+
+    pub const AVAILABLE: bool = false;
+
+    pub async fn poll_mailbox(_state: &AppState, _server: &str) -> Result<usize, String> {
+        Err("server built without the `imap` feature".into())
+    }
+}
@@ -0,0 +1,92 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+// Error del crate: cada variante sabe qué código HTTP devolver
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Unauthorized(&'static str),
+    #[error("{0}")]
+    Forbidden(&'static str),
+    // Cuerpo de la petición mayor que el límite
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    // El cliente dejó de enviar el cuerpo a mitad
+    #[error("{0}")]
+    RequestTimeout(String),
+    // Backpressure: el robot debe reintentar pasados `retry_after_sec` segundos
+    #[error("Upload queue full, retry later")]
+    Busy { retry_after_sec: u64 },
+    // Límite de peticiones superado (subidas por robot, página de estado)
+    #[error("Rate limit exceeded, retry later")]
+    TooManyRequests { retry_after_sec: u64 },
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AppError::Internal(format!("background task failed: {}", e))
+    }
+}
+
+impl From<axum::extract::multipart::MultipartError> for AppError {
+    fn from(e: axum::extract::multipart::MultipartError) -> Self {
+        match e.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(format!("Upload too large: {}", e.body_text())),
+            _ => AppError::BadRequest(format!("Malformed multipart body: {}", e.body_text())),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+            AppError::Busy { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Io(e) if e.kind() == std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            // Rutas de usuario rechazadas por storage::paths
+            AppError::Io(e) if e.kind() == std::io::ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+            AppError::Io(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        if status == StatusCode::INTERNAL_SERVER_ERROR {
+            tracing::error!(error = %self, "❌ Error interno");
+        }
+
+        match self {
+            AppError::Busy { retry_after_sec } => (
+                status,
+                [(header::RETRY_AFTER, retry_after_sec.to_string())],
+                "Upload queue full, retry later",
+            )
+                .into_response(),
+            AppError::TooManyRequests { retry_after_sec } => (
+                status,
+                [(header::RETRY_AFTER, retry_after_sec.to_string())],
+                "Rate limit exceeded, retry later",
+            )
+                .into_response(),
+            AppError::Io(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                (status, "File not found").into_response()
+            }
+            AppError::Io(e) if e.kind() == std::io::ErrorKind::InvalidInput => {
+                (status, format!("Invalid path: {}", e)).into_response()
+            }
+            other => (status, other.to_string()).into_response(),
+        }
+    }
+}
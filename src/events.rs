@@ -0,0 +1,298 @@
+use crate::{
+    commands::RobotCommand,
+    i18n::Locale,
+    push::{push_to_subscribers, PushMessage, PushTopic},
+    state::{AlertRecord, AppState},
+};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+// --- FLUJO DE EVENTOS (KAFKA / NATS) ---
+// Cada subida, alerta, respuesta del robot a un comando y cambio de conectividad de
+// una turbina se publica como un evento JSON:
+//  - con --kafka-brokers en --kafka-topic, con el token de la turbina como clave (sus
+//    eventos quedan en orden en una partición);
+//  - con --nats-url en el subject "<prefijo>.events.<tipo>.<turbina>", y opcionalmente
+//    se aceptan comandos para los robots en --nats-command-subject.
+// Ambos destinos pueden estar activos a la vez. La publicación no bloquea a quien genera
+// el evento: si la cola de un destino se llena (broker caído) sus eventos se descartan
+// con un aviso.
+
+// Eventos en cola hacia el productor
+const EVENT_QUEUE: usize = 10_000;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    Upload {
+        filename: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        camera_id: Option<String>,
+        angle: f32,
+        max_temp: Option<f32>,
+        size_bytes: u64,
+    },
+    Alert { alert: AlertRecord },
+    // Alerta resuelta sola al normalizarse la temperatura
+    AlertResolved { alert: AlertRecord },
+    // Acuse, finalización o fallo de un comando por parte del robot
+    Command { command: RobotCommand },
+    Offline { last_update: u64 },
+    Online { last_update: u64 },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SentinelEvent {
+    pub id: String,
+    pub timestamp: u64,
+    pub turbine_token: String,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl SentinelEvent {
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            EventKind::Upload { .. } => "upload",
+            EventKind::Alert { .. } => "alert",
+            EventKind::AlertResolved { .. } => "alert_resolved",
+            EventKind::Command { .. } => "command",
+            EventKind::Offline { .. } => "offline",
+            EventKind::Online { .. } => "online",
+        }
+    }
+
+    pub fn new(turbine_token: &str, kind: EventKind) -> Self {
+        SentinelEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            turbine_token: turbine_token.to_string(),
+            kind,
+        }
+    }
+}
+
+// Punto de publicación compartido; inactivo hasta que arrancan los destinos
+#[derive(Default)]
+pub struct EventSink {
+    destinations: OnceLock<Vec<(&'static str, mpsc::Sender<SentinelEvent>)>>,
+}
+
+impl EventSink {
+    pub fn publish(&self, turbine_token: &str, kind: EventKind) {
+        let Some(destinations) = self.destinations.get() else { return };
+        let event = SentinelEvent::new(turbine_token, kind);
+        for (name, tx) in destinations {
+            if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(event.clone()) {
+                tracing::warn!(destination = name, turbine_token = %event.turbine_token, "⚠️ Cola de eventos llena, se descarta el evento");
+            }
+        }
+    }
+}
+
+// Arranca los destinos configurados y, si alguno funciona, el vigilante de conectividad
+pub fn spawn_event_publisher(state: &Arc<AppState>) {
+    let settings = &state.settings;
+    let mut destinations = Vec::new();
+    if let Some(brokers) = settings.kafka_brokers.clone() {
+        let (tx, rx) = mpsc::channel(EVENT_QUEUE);
+        match kafka::start(&brokers, settings.kafka_topic.clone(), rx) {
+            Ok(()) => {
+                tracing::info!(%brokers, topic = %settings.kafka_topic, "📡 Publicando eventos en Kafka");
+                destinations.push(("kafka", tx));
+            }
+            Err(e) => tracing::error!(%brokers, error = %e, "❌ No se pudo crear el productor de Kafka"),
+        }
+    }
+    if let Some(url) = settings.nats_url.clone() {
+        let (tx, rx) = mpsc::channel(EVENT_QUEUE);
+        match nats::start(state.clone(), &url, rx) {
+            Ok(()) => {
+                tracing::info!(%url, prefix = %settings.nats_subject_prefix, "📡 Publicando eventos en NATS");
+                destinations.push(("nats", tx));
+            }
+            Err(e) => tracing::error!(%url, error = %e, "❌ No se pudo conectar a NATS"),
+        }
+    }
+    let publishing = !destinations.is_empty();
+    if publishing {
+        let _ = state.events.destinations.set(destinations);
+    }
+    if publishing || state.fcm.is_some() {
+        spawn_connectivity_watcher(state);
+    }
+}
+
+// Las turbinas no avisan al desconectarse: se detecta por la falta de heartbeats. Las
+// desconexiones también se avisan por push a la app móvil
+fn spawn_connectivity_watcher(state: &Arc<AppState>) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut offline: HashSet<String> = HashSet::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let now = chrono::Utc::now().timestamp() as u64;
+            let timeouts = state.offline_timeouts().await;
+            let statuses: Vec<(String, u64)> = state.turbine_status.read().await.iter()
+                .map(|(token, status)| (token.clone(), status.last_update))
+                .collect();
+            for (token, last_update) in statuses {
+                let stale = !timeouts.is_online(&token, last_update, now);
+                if stale && offline.insert(token.clone()) {
+                    state.events.publish(&token, EventKind::Offline { last_update });
+                    let since = chrono::DateTime::from_timestamp(last_update as i64, 0).map(|d| d.to_rfc3339()).unwrap_or_default();
+                    let message = |locale: Locale| PushMessage {
+                        title: locale.pick(format!("📴 Turbine offline · {}", token), format!("📴 Turbina desconectada · {}", token)),
+                        body: locale.pick(format!("No heartbeat since {}", since), format!("Sin heartbeat desde {}", since)),
+                        data: HashMap::from([
+                            ("type".to_string(), "offline".to_string()),
+                            ("turbine_token".to_string(), token.clone()),
+                        ]),
+                    };
+                    push_to_subscribers(&state, &token, PushTopic::Offline, message).await;
+                } else if !stale && offline.remove(&token) {
+                    state.events.publish(&token, EventKind::Online { last_update });
+                }
+            }
+        }
+    });
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use super::SentinelEvent;
+    use rdkafka::{
+        producer::{FutureProducer, FutureRecord},
+        ClientConfig,
+    };
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    pub fn start(brokers: &str, topic: String, mut rx: mpsc::Receiver<SentinelEvent>) -> Result<(), String> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "30000")
+            .create()
+            .map_err(|e| e.to_string())?;
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let Ok(payload) = serde_json::to_vec(&event) else { continue };
+                let record = FutureRecord::to(&topic).key(&event.turbine_token).payload(&payload);
+                if let Err((e, _)) = producer.send(record, Duration::from_secs(5)).await {
+                    tracing::warn!(id = %event.id, error = %e, "⚠️ Error publicando evento en Kafka");
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "kafka"))]
+mod kafka {
+    use super::SentinelEvent;
+    use tokio::sync::mpsc;
+
+    pub fn start(_brokers: &str, _topic: String, _rx: mpsc::Receiver<SentinelEvent>) -> Result<(), String> {
+        Err("server built without the `kafka` feature".into())
+    }
+}
+
+#[cfg(feature = "nats")]
+mod nats {
+    use super::SentinelEvent;
+    use crate::{commands::RobotCommand, state::AppState};
+    use serde::Deserialize;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+    use tokio_stream::StreamExt;
+
+    // Comando recibido por NATS; se responde con el comando encolado si hay reply
+    #[derive(Deserialize)]
+    struct CommandMessage {
+        turbine_token: String,
+        command: String,
+        #[serde(default)]
+        params: serde_json::Value,
+    }
+
+    // Los tokens van dentro del subject: sin separadores ni comodines de NATS
+    fn subject_token(token: &str) -> String {
+        token.chars().map(|c| if matches!(c, '.' | '*' | '>') || c.is_whitespace() { '_' } else { c }).collect()
+    }
+
+    pub fn start(state: Arc<AppState>, url: &str, mut rx: mpsc::Receiver<SentinelEvent>) -> Result<(), String> {
+        let url = url.to_string();
+        tokio::spawn(async move {
+            // Reintenta la conexión inicial y reconecta solo si se cae
+            let options = async_nats::ConnectOptions::new().retry_on_initial_connect();
+            let client = match options.connect(&url).await {
+                Ok(client) => client,
+                Err(e) => {
+                    tracing::error!(%url, error = %e, "❌ No se pudo conectar a NATS");
+                    return;
+                }
+            };
+            if let Some(subject) = state.settings.nats_command_subject.clone() {
+                tokio::spawn(consume_commands(state.clone(), client.clone(), subject));
+            }
+            let prefix = state.settings.nats_subject_prefix.clone();
+            while let Some(event) = rx.recv().await {
+                let Ok(payload) = serde_json::to_vec(&event) else { continue };
+                let subject = format!("{}.events.{}.{}", prefix, event.kind_name(), subject_token(&event.turbine_token));
+                if let Err(e) = client.publish(subject, payload.into()).await {
+                    tracing::warn!(id = %event.id, error = %e, "⚠️ Error publicando evento en NATS");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn consume_commands(state: Arc<AppState>, client: async_nats::Client, subject: String) {
+        let mut subscriber = match client.subscribe(subject.clone()).await {
+            Ok(subscriber) => subscriber,
+            Err(e) => {
+                tracing::error!(%subject, error = %e, "❌ No se pudo suscribir a los comandos de NATS");
+                return;
+            }
+        };
+        tracing::info!(%subject, "📮 Aceptando comandos por NATS");
+        while let Some(message) = subscriber.next().await {
+            let reply = match serde_json::from_slice::<CommandMessage>(&message.payload) {
+                Ok(request) if !request.command.trim().is_empty() => {
+                    let command = RobotCommand::new(request.command.trim(), request.params);
+                    state.commands.enqueue(&request.turbine_token, command.clone()).await;
+                    tracing::info!(turbine_token = %request.turbine_token, command = %command.command, id = %command.id, "📮 Comando encolado desde NATS");
+                    serde_json::to_vec(&command).unwrap_or_default()
+                }
+                Ok(_) => br#"{"error":"command must not be empty"}"#.to_vec(),
+                Err(e) => {
+                    tracing::warn!(error = %e, "⚠️ Mensaje de comando inválido en NATS");
+                    serde_json::to_vec(&serde_json::json!({ "error": e.to_string() })).unwrap_or_default()
+                }
+            };
+            if let Some(reply_to) = message.reply
+                && let Err(e) = client.publish(reply_to, reply.into()).await
+            {
+                tracing::warn!(error = %e, "⚠️ Error respondiendo a un comando de NATS");
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "nats"))]
+mod nats {
+    use super::SentinelEvent;
+    use crate::state::AppState;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    pub fn start(_state: Arc<AppState>, _url: &str, _rx: mpsc::Receiver<SentinelEvent>) -> Result<(), String> {
+        Err("server built without the `nats` feature".into())
+    }
+}
@@ -0,0 +1,624 @@
+use crate::{
+    routes::fleet::{live_map_status, MapStatus},
+    sessions::SESSION_GAP_SEC,
+    state::{AlertRecord, AppState, LiveStatus},
+    storage::{catalog::CaptureRecord, registry::TurbineInfo},
+    thresholds::Severity,
+};
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, Schema, SimpleObject,
+};
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse},
+    routing::get,
+    Json, Router,
+};
+use std::{collections::BTreeSet, sync::Arc};
+
+// --- API GRAPHQL PARA EL DASHBOARD ---
+// POST /api/graphql (GET abre GraphiQL): turbinas, capturas, alertas, sesiones de escaneo
+// y estadísticas con consultas anidadas y filtros, para que el dashboard pida en una sola
+// petición solo los campos que necesita. Es de solo lectura y devuelve los valores tal
+// como se guardan (temperaturas en °C, instantes en segundos Unix).
+
+// Máximo de elementos por lista y profundidad de anidamiento admitida
+const MAX_LIMIT: usize = 1000;
+const MAX_DEPTH: usize = 10;
+
+pub type SentinelSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Severity", remote = "crate::thresholds::Severity")]
+enum GqlSeverity {
+    Warning,
+    Critical,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "TurbineStatus", remote = "crate::routes::fleet::MapStatus")]
+enum GqlStatus {
+    Offline,
+    Normal,
+    Warning,
+    Critical,
+}
+
+fn app_state<'a>(ctx: &Context<'a>) -> &'a Arc<AppState> {
+    ctx.data_unchecked::<Arc<AppState>>()
+}
+
+fn page<T>(items: impl Iterator<Item = T>, offset: usize, limit: usize) -> Vec<T> {
+    items.skip(offset).take(limit.min(MAX_LIMIT)).collect()
+}
+
+#[derive(InputObject, Default)]
+pub struct CaptureFilter {
+    turbine_token: Option<String>,
+    camera: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    starred: Option<bool>,
+    archived: Option<bool>,
+    // Solo capturas cuya máxima alcanza este valor
+    min_max_temp: Option<f32>,
+}
+
+impl CaptureFilter {
+    fn matches(&self, r: &CaptureRecord) -> bool {
+        self.turbine_token.as_ref().is_none_or(|t| *t == r.turbine_token)
+            && (self.camera.is_none() || r.camera_id == self.camera)
+            && self.from.is_none_or(|from| r.timestamp >= from)
+            && self.to.is_none_or(|to| r.timestamp <= to)
+            && self.starred.is_none_or(|s| r.starred == s)
+            && self.archived.is_none_or(|a| r.archived == a)
+            && self.min_max_temp.is_none_or(|min| r.max_temp.is_some_and(|t| t >= min))
+    }
+}
+
+#[derive(InputObject, Default)]
+pub struct AlertFilter {
+    turbine_token: Option<String>,
+    severity: Option<GqlSeverity>,
+    from: Option<u64>,
+    to: Option<u64>,
+    starred: Option<bool>,
+    incident_id: Option<String>,
+}
+
+impl AlertFilter {
+    fn matches(&self, a: &AlertRecord) -> bool {
+        self.turbine_token.as_ref().is_none_or(|t| *t == a.turbine_token)
+            && self.severity.is_none_or(|s| Severity::from(s) == a.severity)
+            && self.from.is_none_or(|from| a.timestamp >= from)
+            && self.to.is_none_or(|to| a.timestamp <= to)
+            && self.starred.is_none_or(|s| a.starred == s)
+            && (self.incident_id.is_none() || a.incident_id == self.incident_id)
+    }
+}
+
+// Capturas que cumplen el filtro, de la más reciente a la más antigua
+async fn find_captures(state: &AppState, filter: &CaptureFilter, offset: usize, limit: usize) -> Vec<Capture> {
+    let mut records: Vec<CaptureRecord> = state.catalog.read().await.iter()
+        .filter(|r| filter.matches(r))
+        .cloned()
+        .collect();
+    records.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+    page(records.into_iter().map(Capture), offset, limit)
+}
+
+// Alertas que cumplen el filtro, de la más reciente a la más antigua (el orden en que
+// se guardan en memoria)
+async fn find_alerts(state: &AppState, filter: &AlertFilter, offset: usize, limit: usize) -> Vec<Alert> {
+    let alerts = state.alerts.read().await;
+    page(alerts.iter().filter(|a| filter.matches(a)).cloned().map(Alert), offset, limit)
+}
+
+async fn find_turbine(state: &AppState, token: &str) -> Option<Turbine> {
+    let info = state.turbines.read().await.iter().find(|t| t.token == token).cloned();
+    let known = info.is_some()
+        || state.turbine_status.read().await.contains_key(token)
+        || state.catalog.read().await.iter().any(|r| r.turbine_token == token);
+    known.then(|| Turbine { token: token.to_string(), info })
+}
+
+fn hottest(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+// Agrupa las capturas de cada turbina en sesiones de escaneo, de la más reciente a la
+// más antigua
+fn scan_sessions(records: &[CaptureRecord]) -> Vec<ScanSession> {
+    let mut sorted: Vec<&CaptureRecord> = records.iter().collect();
+    sorted.sort_by(|a, b| a.turbine_token.cmp(&b.turbine_token).then(a.timestamp.cmp(&b.timestamp)));
+    let mut sessions: Vec<ScanSession> = Vec::new();
+    for record in sorted {
+        match sessions.last_mut() {
+            Some(session)
+                if session.turbine_token == record.turbine_token
+                    && record.timestamp <= session.end.saturating_add(SESSION_GAP_SEC) =>
+            {
+                session.end = record.timestamp;
+                session.capture_count += 1;
+                session.max_temp = hottest(session.max_temp, record.max_temp);
+            }
+            _ => sessions.push(ScanSession {
+                turbine_token: record.turbine_token.clone(),
+                start: record.timestamp,
+                end: record.timestamp,
+                capture_count: 1,
+                max_temp: record.max_temp,
+            }),
+        }
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.start));
+    sessions
+}
+
+async fn find_sessions(state: &AppState, turbine_token: Option<String>, from: Option<u64>, to: Option<u64>, limit: usize) -> Vec<ScanSession> {
+    let filter = CaptureFilter { turbine_token, from, to, ..Default::default() };
+    let records: Vec<CaptureRecord> = state.catalog.read().await.iter()
+        .filter(|r| filter.matches(r))
+        .cloned()
+        .collect();
+    page(scan_sessions(&records).into_iter(), 0, limit)
+}
+
+async fn fleet_stats(state: &AppState, turbine_token: Option<&str>, from: Option<u64>, to: Option<u64>) -> Stats {
+    let in_range = |ts: u64| from.is_none_or(|f| ts >= f) && to.is_none_or(|t| ts <= t);
+    let of_turbine = |token: &str| turbine_token.is_none_or(|t| t == token);
+    let mut stats = Stats::default();
+    for record in state.catalog.read().await.iter().filter(|r| of_turbine(&r.turbine_token) && in_range(r.timestamp)) {
+        stats.captures += 1;
+        stats.storage_bytes += record.size_bytes;
+        stats.max_temp = hottest(stats.max_temp, record.max_temp);
+    }
+    for alert in state.alerts.read().await.iter().filter(|a| of_turbine(&a.turbine_token) && in_range(a.timestamp)) {
+        stats.alerts += 1;
+        match alert.severity {
+            Severity::Warning => stats.warning_alerts += 1,
+            Severity::Critical => stats.critical_alerts += 1,
+        }
+    }
+    let now = chrono::Utc::now();
+    let statuses = state.turbine_status.read().await.clone();
+    for token in all_turbine_tokens(state).await.iter().filter(|t| of_turbine(t)) {
+        stats.turbines += 1;
+        if !matches!(live_map_status(state, token, statuses.get(token), &now).await, MapStatus::Offline) {
+            stats.online_turbines += 1;
+        }
+    }
+    stats
+}
+
+// Registradas, con heartbeat o con capturas
+async fn all_turbine_tokens(state: &AppState) -> BTreeSet<String> {
+    let mut tokens: BTreeSet<String> = state.turbines.read().await.iter().map(|t| t.token.clone()).collect();
+    tokens.extend(state.turbine_status.read().await.keys().cloned());
+    tokens.extend(state.catalog.read().await.iter().map(|r| r.turbine_token.clone()));
+    tokens
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn turbines(&self, ctx: &Context<'_>, site: Option<String>) -> Vec<Turbine> {
+        let state = app_state(ctx);
+        let registered = state.turbines.read().await.clone();
+        all_turbine_tokens(state).await.into_iter()
+            .map(|token| {
+                let info = registered.iter().find(|t| t.token == token).cloned();
+                Turbine { token, info }
+            })
+            .filter(|t| site.is_none() || t.info.as_ref().and_then(|i| i.site.as_ref()) == site.as_ref())
+            .collect()
+    }
+
+    async fn turbine(&self, ctx: &Context<'_>, token: String) -> Option<Turbine> {
+        find_turbine(app_state(ctx), &token).await
+    }
+
+    async fn captures(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: CaptureFilter,
+        #[graphql(default = 100)] limit: usize,
+        #[graphql(default)] offset: usize,
+    ) -> Vec<Capture> {
+        find_captures(app_state(ctx), &filter, offset, limit).await
+    }
+
+    async fn capture(&self, ctx: &Context<'_>, filename: String) -> Option<Capture> {
+        app_state(ctx).catalog.read().await.iter().find(|r| r.filename == filename).cloned().map(Capture)
+    }
+
+    async fn alerts(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: AlertFilter,
+        #[graphql(default = 100)] limit: usize,
+        #[graphql(default)] offset: usize,
+    ) -> Vec<Alert> {
+        find_alerts(app_state(ctx), &filter, offset, limit).await
+    }
+
+    async fn sessions(
+        &self,
+        ctx: &Context<'_>,
+        turbine_token: Option<String>,
+        from: Option<u64>,
+        to: Option<u64>,
+        #[graphql(default = 20)] limit: usize,
+    ) -> Vec<ScanSession> {
+        find_sessions(app_state(ctx), turbine_token, from, to, limit).await
+    }
+
+    async fn stats(&self, ctx: &Context<'_>, turbine_token: Option<String>, from: Option<u64>, to: Option<u64>) -> Stats {
+        fleet_stats(app_state(ctx), turbine_token.as_deref(), from, to).await
+    }
+}
+
+pub struct Turbine {
+    token: String,
+    // None si no está en el registro (solo ha enviado heartbeats o capturas)
+    info: Option<TurbineInfo>,
+}
+
+#[Object]
+impl Turbine {
+    async fn token(&self) -> &str {
+        &self.token
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.info.as_ref().map(|i| i.name.as_str())
+    }
+
+    async fn latitude(&self) -> Option<f64> {
+        self.info.as_ref().map(|i| i.latitude)
+    }
+
+    async fn longitude(&self) -> Option<f64> {
+        self.info.as_ref().map(|i| i.longitude)
+    }
+
+    async fn model(&self) -> Option<&str> {
+        self.info.as_ref().and_then(|i| i.model.as_deref())
+    }
+
+    async fn hub_height_m(&self) -> Option<f32> {
+        self.info.as_ref().and_then(|i| i.hub_height_m)
+    }
+
+    async fn site(&self) -> Option<&str> {
+        self.info.as_ref().and_then(|i| i.site.as_deref())
+    }
+
+    async fn status(&self, ctx: &Context<'_>) -> GqlStatus {
+        let state = app_state(ctx);
+        let live = state.turbine_status.read().await.get(&self.token).cloned();
+        live_map_status(state, &self.token, live.as_ref(), &chrono::Utc::now()).await.into()
+    }
+
+    // Último heartbeat
+    async fn live(&self, ctx: &Context<'_>) -> Option<Live> {
+        app_state(ctx).turbine_status.read().await.get(&self.token).cloned().map(Live)
+    }
+
+    async fn captures(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: CaptureFilter,
+        #[graphql(default = 100)] limit: usize,
+        #[graphql(default)] offset: usize,
+    ) -> Vec<Capture> {
+        let filter = CaptureFilter { turbine_token: Some(self.token.clone()), ..filter };
+        find_captures(app_state(ctx), &filter, offset, limit).await
+    }
+
+    async fn alerts(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: AlertFilter,
+        #[graphql(default = 100)] limit: usize,
+        #[graphql(default)] offset: usize,
+    ) -> Vec<Alert> {
+        let filter = AlertFilter { turbine_token: Some(self.token.clone()), ..filter };
+        find_alerts(app_state(ctx), &filter, offset, limit).await
+    }
+
+    async fn sessions(&self, ctx: &Context<'_>, from: Option<u64>, to: Option<u64>, #[graphql(default = 20)] limit: usize) -> Vec<ScanSession> {
+        find_sessions(app_state(ctx), Some(self.token.clone()), from, to, limit).await
+    }
+
+    async fn stats(&self, ctx: &Context<'_>, from: Option<u64>, to: Option<u64>) -> Stats {
+        fleet_stats(app_state(ctx), Some(&self.token), from, to).await
+    }
+}
+
+pub struct Live(LiveStatus);
+
+#[Object]
+impl Live {
+    async fn last_update(&self) -> u64 {
+        self.0.last_update
+    }
+
+    async fn mode(&self) -> &str {
+        &self.0.mode
+    }
+
+    async fn current_angle(&self) -> f32 {
+        self.0.current_angle
+    }
+
+    async fn current_max_temp(&self) -> f32 {
+        self.0.current_max_temp
+    }
+}
+
+pub struct Capture(CaptureRecord);
+
+#[Object]
+impl Capture {
+    async fn filename(&self) -> &str {
+        &self.0.filename
+    }
+
+    async fn turbine_token(&self) -> &str {
+        &self.0.turbine_token
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+
+    async fn sha256(&self) -> &str {
+        &self.0.sha256
+    }
+
+    async fn size_bytes(&self) -> u64 {
+        self.0.size_bytes
+    }
+
+    async fn archived(&self) -> bool {
+        self.0.archived
+    }
+
+    async fn integrity_error(&self) -> Option<&str> {
+        self.0.integrity_error.as_deref()
+    }
+
+    async fn angle(&self) -> Option<f32> {
+        self.0.angle
+    }
+
+    async fn max_temp(&self) -> Option<f32> {
+        self.0.max_temp
+    }
+
+    async fn avg_temp(&self) -> Option<f32> {
+        self.0.avg_temp
+    }
+
+    async fn anomaly_score(&self) -> Option<f32> {
+        self.0.anomaly_score
+    }
+
+    async fn starred(&self) -> bool {
+        self.0.starred
+    }
+
+    async fn camera_id(&self) -> Option<&str> {
+        self.0.camera_id.as_deref()
+    }
+
+    async fn calibration_version(&self) -> Option<u64> {
+        self.0.calibration_version
+    }
+
+    async fn clock_skew_sec(&self) -> Option<i64> {
+        self.0.clock_skew_sec
+    }
+
+    // Fuente externa (dron, cámara de mano); None = el robot de la turbina
+    async fn source(&self) -> Option<&str> {
+        self.0.source.as_deref()
+    }
+
+    async fn width(&self) -> Option<u32> {
+        self.0.width.map(|w| w as u32)
+    }
+
+    async fn height(&self) -> Option<u32> {
+        self.0.height.map(|h| h as u32)
+    }
+
+    async fn turbine(&self, ctx: &Context<'_>) -> Option<Turbine> {
+        find_turbine(app_state(ctx), &self.0.turbine_token).await
+    }
+
+    // Alertas disparadas por esta captura, de la más reciente a la más antigua
+    async fn alerts(&self, ctx: &Context<'_>) -> Vec<Alert> {
+        let alerts = app_state(ctx).alerts.read().await;
+        alerts.iter().filter(|a| a.dataset_path == self.0.filename).cloned().map(Alert).collect()
+    }
+}
+
+pub struct Alert(AlertRecord);
+
+#[Object]
+impl Alert {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+
+    async fn turbine_token(&self) -> &str {
+        &self.0.turbine_token
+    }
+
+    async fn max_temp(&self) -> f32 {
+        self.0.max_temp
+    }
+
+    async fn angle(&self) -> f32 {
+        self.0.angle
+    }
+
+    async fn dataset_path(&self) -> &str {
+        &self.0.dataset_path
+    }
+
+    async fn zone(&self) -> Option<&str> {
+        self.0.zone.as_deref()
+    }
+
+    async fn severity(&self) -> GqlSeverity {
+        self.0.severity.into()
+    }
+
+    async fn level(&self) -> Option<&str> {
+        self.0.level.as_deref()
+    }
+
+    // Reglas del operador que se cumplieron
+    async fn rules(&self) -> &[String] {
+        &self.0.rules
+    }
+
+    async fn fault_label(&self) -> Option<&str> {
+        self.0.fault_prediction.as_ref().map(|p| p.label.as_str())
+    }
+
+    async fn fault_confidence(&self) -> Option<f32> {
+        self.0.fault_prediction.as_ref().map(|p| p.confidence)
+    }
+
+    async fn incident_id(&self) -> Option<&str> {
+        self.0.incident_id.as_deref()
+    }
+
+    async fn starred(&self) -> bool {
+        self.0.starred
+    }
+
+    async fn frame_index(&self) -> Option<u32> {
+        self.0.frame_index.map(|i| i as u32)
+    }
+
+    async fn thumbnail_url(&self) -> Option<&str> {
+        self.0.links.as_ref().map(|l| l.thumbnail.as_str())
+    }
+
+    async fn render_url(&self) -> Option<&str> {
+        self.0.links.as_ref().map(|l| l.render.as_str())
+    }
+
+    async fn turbine(&self, ctx: &Context<'_>) -> Option<Turbine> {
+        find_turbine(app_state(ctx), &self.0.turbine_token).await
+    }
+
+    async fn capture(&self, ctx: &Context<'_>) -> Option<Capture> {
+        let catalog = app_state(ctx).catalog.read().await;
+        catalog.iter().find(|r| r.filename == self.0.dataset_path).cloned().map(Capture)
+    }
+}
+
+// Capturas consecutivas de una turbina sin pausas mayores que SESSION_GAP_SEC
+pub struct ScanSession {
+    turbine_token: String,
+    start: u64,
+    end: u64,
+    capture_count: u32,
+    max_temp: Option<f32>,
+}
+
+#[Object]
+impl ScanSession {
+    async fn turbine_token(&self) -> &str {
+        &self.turbine_token
+    }
+
+    async fn start(&self) -> u64 {
+        self.start
+    }
+
+    async fn end(&self) -> u64 {
+        self.end
+    }
+
+    async fn capture_count(&self) -> u32 {
+        self.capture_count
+    }
+
+    async fn max_temp(&self) -> Option<f32> {
+        self.max_temp
+    }
+
+    async fn turbine(&self, ctx: &Context<'_>) -> Option<Turbine> {
+        find_turbine(app_state(ctx), &self.turbine_token).await
+    }
+
+    async fn captures(&self, ctx: &Context<'_>, #[graphql(default = 100)] limit: usize, #[graphql(default)] offset: usize) -> Vec<Capture> {
+        let filter = CaptureFilter {
+            turbine_token: Some(self.turbine_token.clone()),
+            from: Some(self.start),
+            to: Some(self.end),
+            ..Default::default()
+        };
+        find_captures(app_state(ctx), &filter, offset, limit).await
+    }
+
+    async fn alerts(&self, ctx: &Context<'_>) -> Vec<Alert> {
+        let filter = AlertFilter {
+            turbine_token: Some(self.turbine_token.clone()),
+            from: Some(self.start),
+            to: Some(self.end),
+            ..Default::default()
+        };
+        find_alerts(app_state(ctx), &filter, 0, MAX_LIMIT).await
+    }
+}
+
+#[derive(SimpleObject, Default)]
+pub struct Stats {
+    turbines: u32,
+    online_turbines: u32,
+    captures: u32,
+    storage_bytes: u64,
+    // Máxima de las capturas (None si ninguna la tiene)
+    max_temp: Option<f32>,
+    alerts: u32,
+    warning_alerts: u32,
+    critical_alerts: u32,
+}
+
+pub fn build_schema(state: Arc<AppState>) -> SentinelSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+async fn graphql_handler(State(schema): State<SentinelSchema>, Json(request): Json<async_graphql::Request>) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn graphiql_handler() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/api/graphql").finish())
+}
+
+// Rutas de la API GraphQL, con el esquema como estado propio
+pub fn router<S: Clone + Send + Sync + 'static>(state: Arc<AppState>) -> Router<S> {
+    Router::new()
+        .route("/api/graphql", get(graphiql_handler).post(graphql_handler))
+        .with_state(build_schema(state))
+}
@@ -0,0 +1,54 @@
+use crate::thresholds::Severity;
+use serde::{Deserialize, Serialize};
+
+// --- IDIOMA DE LOS MENSAJES ---
+// Los operadores trabajan en inglés o en español. Cada canal de notificación, cada
+// usuario de la app móvil (preferencias push) y cada informe programado elige su idioma
+// con `locale`: "en" por defecto, salvo los avisos push, que ya salían en español.
+// Afecta al texto pensado para personas (avisos push, variable {{summary}} de las
+// plantillas, mensajes de los avisos de dispositivo, informes y correos); los campos del
+// JSON y los errores de la API siguen en inglés.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+impl Locale {
+    // El texto del idioma; se construyen los dos para no repetir los argumentos
+    pub fn pick<T>(self, en: T, es: T) -> T {
+        match self {
+            Locale::En => en,
+            Locale::Es => es,
+        }
+    }
+
+    pub fn severity(self, severity: Severity) -> &'static str {
+        match (self, severity) {
+            (Locale::En, Severity::Warning) => "warning",
+            (Locale::En, Severity::Critical) => "critical",
+            (Locale::Es, Severity::Warning) => "aviso",
+            (Locale::Es, Severity::Critical) => "crítica",
+        }
+    }
+}
+
+// Mensaje ya redactado en ambos idiomas
+#[derive(Clone, Debug, Default)]
+pub struct Localized {
+    pub en: String,
+    pub es: String,
+}
+
+impl Localized {
+    pub fn new(en: String, es: String) -> Self {
+        Localized { en, es }
+    }
+
+    pub fn get(&self, locale: Locale) -> &str {
+        locale.pick(&self.en, &self.es)
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{
+    commands::CommandStatus,
+    state::{AlertRecord, AppState},
+    storage::{
+        alert_log::alert_history,
+        config_history::{redacted_history, ConfigChange},
+        quality::{load_quality_events, QualityEventKind},
+        timeline::load_mode_changes,
+    },
+    thresholds::Severity,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// --- CORRELACIÓN DE ALERTAS ENTRE TURBINAS ---
+// Si varias turbinas del mismo sitio alertan casi a la vez, lo más probable es un
+// problema de sensor o de ambiente y no N fallos independientes. Esas alertas se agrupan
+// en un incidente con id propio y una única notificación combinada. La primera alerta ya
+// se notificó sola (no se retrasan las notificaciones); al sumarse la segunda turbina se
+// envía el incidente y las siguientes se unen a él sin notificar.
+
+fn default_window_sec() -> u64 {
+    300
+}
+
+fn default_min_turbines() -> usize {
+    2
+}
+
+// Más allá de esto ya no son alertas simultáneas sino un mismo problema que se prolonga
+const MAX_WINDOW_SEC: u64 = 24 * 3600;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CorrelationConfig {
+    // Alertas separadas por menos de esto se consideran simultáneas
+    #[serde(default = "default_window_sec")]
+    pub window_sec: u64,
+    // Turbinas distintas necesarias para abrir un incidente
+    #[serde(default = "default_min_turbines")]
+    pub min_turbines: usize,
+}
+
+impl CorrelationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_sec == 0 || self.window_sec > MAX_WINDOW_SEC {
+            return Err(format!("correlation: window_sec must be between 1 and {}", MAX_WINDOW_SEC));
+        }
+        if self.min_turbines < 2 {
+            return Err("correlation: min_turbines must be at least 2".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Incident {
+    pub id: String,
+    pub site: String,
+    pub started: u64,
+    // Última alerta incorporada; el incidente sigue abierto durante window_sec
+    pub updated: u64,
+    pub turbines: Vec<String>,
+    pub alert_ids: Vec<String>,
+    pub severity: Severity,
+}
+
+// Incidentes que se conservan en memoria
+pub const MAX_INCIDENTS: usize = 50;
+
+pub enum Correlation {
+    // Alerta aislada: se notifica sola
+    Single,
+    // Abre un incidente: se notifica el incidente con todas sus alertas
+    Opened(Incident, Vec<AlertRecord>),
+    // Se une a un incidente ya notificado
+    Joined(Incident),
+}
+
+impl AppState {
+    // Sitio de una turbina: el del registro o, si no lo indica, el de su sitio meteorológico
+    pub async fn site_of(&self, turbine_token: &str) -> Option<String> {
+        let registered = self.turbines.read().await.iter()
+            .find(|t| t.token == turbine_token)
+            .and_then(|t| t.site.clone());
+        match registered {
+            Some(site) => Some(site),
+            None => self.config.read().await.site_for_turbine(turbine_token).map(|s| s.name.clone()),
+        }
+    }
+
+    async fn site_members(&self, site: &str) -> HashSet<String> {
+        let mut members: HashSet<String> = self.turbines.read().await.iter()
+            .filter(|t| t.site.as_deref() == Some(site))
+            .map(|t| t.token.clone())
+            .collect();
+        if let Some(weather_site) = self.config.read().await.weather_sites.iter().find(|s| s.name == site) {
+            members.extend(weather_site.turbines.iter().cloned());
+        }
+        members
+    }
+}
+
+// Decide si la alerta (aún no registrada) pertenece a un incidente y le asigna su id
+pub async fn correlate(state: &AppState, alert: &mut AlertRecord) -> Correlation {
+    let Some(config) = state.config.read().await.correlation.clone() else {
+        return Correlation::Single;
+    };
+    let Some(site) = state.site_of(&alert.turbine_token).await else {
+        return Correlation::Single;
+    };
+    let members = state.site_members(&site).await;
+    let now = alert.timestamp;
+
+    let mut incidents = state.incidents.write().await;
+    if let Some(incident) = incidents.iter_mut()
+        .find(|i| i.site == site && i.updated.saturating_add(config.window_sec) >= now)
+    {
+        alert.incident_id = Some(incident.id.clone());
+        incident.updated = now;
+        incident.alert_ids.push(alert.id.clone());
+        incident.severity = incident.severity.max(alert.severity);
+        if !incident.turbines.contains(&alert.turbine_token) {
+            incident.turbines.push(alert.turbine_token.clone());
+        }
+        return Correlation::Joined(incident.clone());
+    }
+
+    let mut alerts = state.alerts.write().await;
+    let mut related: Vec<&mut AlertRecord> = alerts.iter_mut()
+        .filter(|a| a.incident_id.is_none() && a.timestamp.saturating_add(config.window_sec) >= now)
+        .filter(|a| a.turbine_token != alert.turbine_token && members.contains(&a.turbine_token))
+        .collect();
+    let mut turbines: Vec<String> = vec![alert.turbine_token.clone()];
+    for a in &related {
+        if !turbines.contains(&a.turbine_token) {
+            turbines.push(a.turbine_token.clone());
+        }
+    }
+    if turbines.len() < config.min_turbines.max(2) {
+        return Correlation::Single;
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    for a in related.iter_mut() {
+        a.incident_id = Some(id.clone());
+    }
+    alert.incident_id = Some(id.clone());
+    let mut members_alerts: Vec<AlertRecord> = related.into_iter().map(|a| a.clone()).collect();
+    members_alerts.push(alert.clone());
+    let incident = Incident {
+        id,
+        site,
+        started: members_alerts.iter().map(|a| a.timestamp).min().unwrap_or(now),
+        updated: now,
+        turbines,
+        alert_ids: members_alerts.iter().map(|a| a.id.clone()).collect(),
+        severity: members_alerts.iter().map(|a| a.severity).max().unwrap_or_default(),
+    };
+    incidents.push_front(incident.clone());
+    incidents.truncate(MAX_INCIDENTS);
+    Correlation::Opened(incident, members_alerts)
+}
+
+// --- LÍNEA DE TIEMPO DE UN INCIDENTE ---
+// Todo lo ocurrido en las turbinas del incidente desde `margin` antes de su primera alerta
+// hasta `margin` después de la última, en orden cronológico: alertas, capturas, actividad
+// de los robots, comandos enviados y cambios de configuración. Los robots no envían líneas
+// de log propias; su actividad son los cambios de modo de sus heartbeats y los eventos de
+// calidad de datos (huecos, reloj desincronizado, subidas corruptas). Los comandos solo
+// están en memoria, así que de incidentes antiguos puede que ya no aparezcan.
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineItem {
+    Alert {
+        alert_id: String,
+        severity: Severity,
+        level: Option<String>,
+        max_temp: f32,
+        angle: f32,
+        dataset_path: String,
+        // false si es de una de las turbinas pero no forma parte del incidente
+        in_incident: bool,
+    },
+    Capture {
+        filename: String,
+        angle: Option<f32>,
+        max_temp: Option<f32>,
+        camera_id: Option<String>,
+    },
+    ModeChange {
+        mode: String,
+    },
+    QualityEvent(QualityEventKind),
+    Command {
+        command_id: String,
+        command: String,
+        status: CommandStatus,
+        message: Option<String>,
+    },
+    ConfigChange {
+        version: u64,
+        author: Option<String>,
+        source: String,
+        changes: Vec<ConfigChange>,
+    },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TimelineEntry {
+    pub timestamp: u64,
+    // Sin turbina en los cambios de configuración, que afectan a todas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turbine_token: Option<String>,
+    #[serde(flatten)]
+    pub item: TimelineItem,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct IncidentTimeline {
+    pub incident: Incident,
+    pub from: u64,
+    pub to: u64,
+    pub entries: Vec<TimelineEntry>,
+}
+
+// Los incidentes en memoria son los últimos MAX_INCIDENTS; los anteriores se reconstruyen
+// con las alertas del histórico que llevan su id
+async fn find_incident(state: &AppState, id: &str, alerts: &[AlertRecord]) -> Option<Incident> {
+    if let Some(incident) = state.incidents.read().await.iter().find(|i| i.id == id) {
+        return Some(incident.clone());
+    }
+    let members: Vec<&AlertRecord> = alerts.iter().filter(|a| a.incident_id.as_deref() == Some(id)).collect();
+    let first = members.iter().min_by_key(|a| a.timestamp)?;
+    let mut turbines: Vec<String> = Vec::new();
+    for a in &members {
+        if !turbines.contains(&a.turbine_token) {
+            turbines.push(a.turbine_token.clone());
+        }
+    }
+    Some(Incident {
+        id: id.to_string(),
+        site: state.site_of(&first.turbine_token).await.unwrap_or_default(),
+        started: first.timestamp,
+        updated: members.iter().map(|a| a.timestamp).max().unwrap_or(first.timestamp),
+        turbines,
+        alert_ids: members.iter().map(|a| a.id.clone()).collect(),
+        severity: members.iter().map(|a| a.severity).max().unwrap_or_default(),
+    })
+}
+
+// None si el incidente no existe
+pub async fn build_incident_timeline(state: &AppState, id: &str, margin_sec: u64) -> std::io::Result<Option<IncidentTimeline>> {
+    let alerts = alert_history(state).await?;
+    let Some(incident) = find_incident(state, id, &alerts).await else { return Ok(None) };
+    let (from, to) = (incident.started.saturating_sub(margin_sec), incident.updated.saturating_add(margin_sec));
+    let in_window = |timestamp: u64| (from..=to).contains(&timestamp);
+    let involved = |token: &str| incident.turbines.iter().any(|t| t == token);
+    let mut entries = Vec::new();
+
+    for a in alerts.iter().filter(|a| in_window(a.timestamp) && involved(&a.turbine_token)) {
+        entries.push(TimelineEntry {
+            timestamp: a.timestamp,
+            turbine_token: Some(a.turbine_token.clone()),
+            item: TimelineItem::Alert {
+                alert_id: a.id.clone(),
+                severity: a.severity,
+                level: a.level.clone(),
+                max_temp: a.max_temp,
+                angle: a.angle,
+                dataset_path: a.dataset_path.clone(),
+                in_incident: a.incident_id.as_deref() == Some(id),
+            },
+        });
+    }
+
+    for r in state.catalog.read().await.iter().filter(|r| in_window(r.timestamp) && involved(&r.turbine_token)) {
+        entries.push(TimelineEntry {
+            timestamp: r.timestamp,
+            turbine_token: Some(r.turbine_token.clone()),
+            item: TimelineItem::Capture {
+                filename: r.filename.clone(),
+                angle: r.angle,
+                max_temp: r.max_temp,
+                camera_id: r.camera_id.clone(),
+            },
+        });
+    }
+
+    let turbines = incident.turbines.clone();
+    let (mode_changes, quality_events) = tokio::task::spawn_blocking(move || {
+        let mut modes = Vec::new();
+        let mut events = Vec::new();
+        for token in &turbines {
+            modes.extend(load_mode_changes(token, from, to)?);
+            events.extend(load_quality_events(token, from, to)?);
+        }
+        Ok::<_, std::io::Error>((modes, events))
+    })
+    .await
+    .map_err(std::io::Error::other)??;
+    // load_mode_changes incluye el modo vigente al empezar la ventana, aunque sea anterior
+    for change in mode_changes {
+        entries.push(TimelineEntry {
+            timestamp: change.timestamp.max(from),
+            turbine_token: Some(change.turbine_token),
+            item: TimelineItem::ModeChange { mode: change.mode },
+        });
+    }
+    for event in quality_events {
+        entries.push(TimelineEntry {
+            timestamp: event.timestamp,
+            turbine_token: Some(event.turbine_token),
+            item: TimelineItem::QualityEvent(event.kind),
+        });
+    }
+
+    for token in &incident.turbines {
+        for command in state.commands.recent(token).await.into_iter().filter(|c| in_window(c.created)) {
+            entries.push(TimelineEntry {
+                timestamp: command.created,
+                turbine_token: Some(token.clone()),
+                item: TimelineItem::Command {
+                    command_id: command.id,
+                    command: command.command,
+                    status: command.status,
+                    message: command.message,
+                },
+            });
+        }
+    }
+
+    // Las diferencias salen a la API: sin secretos
+    for version in redacted_history(&state.config_history.read().await).into_iter().filter(|v| in_window(v.timestamp)) {
+        entries.push(TimelineEntry {
+            timestamp: version.timestamp,
+            turbine_token: None,
+            item: TimelineItem::ConfigChange {
+                version: version.version,
+                author: version.author,
+                source: version.source,
+                changes: version.changes,
+            },
+        });
+    }
+
+    // Orden estable: a igual instante se mantiene el orden por tipo de arriba
+    entries.sort_by_key(|e| e.timestamp);
+    Ok(Some(IncidentTimeline { incident, from, to, entries }))
+}
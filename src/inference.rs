@@ -0,0 +1,118 @@
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+// --- CLASIFICACIÓN DE PUNTOS CALIENTES (ONNX) ---
+// Si la configuración indica un modelo, el servidor lo ejecuta sobre el frame más
+// caliente de cada captura que dispara una alerta y adjunta a la alerta la clase
+// predicha (tipo de fallo) y su confianza.
+// Requiere compilar con la feature `onnx`. El runtime se carga en tiempo de ejecución:
+// libonnxruntime debe estar instalada (o indicada con ORT_DYLIB_PATH).
+// Contrato del modelo: entrada f32 [1, 1, alto, ancho] con temperaturas en °C (reescalada
+// a input_width × input_height si se indican); salida [1, N] de logits en el orden de
+// `labels`.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ClassifierConfig {
+    // Ruta del archivo .onnx en el servidor
+    pub model_path: String,
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub input_width: Option<usize>,
+    #[serde(default)]
+    pub input_height: Option<usize>,
+}
+
+impl ClassifierConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.model_path.trim().is_empty() {
+            return Err("classifier: model_path must not be empty".into());
+        }
+        if self.labels.is_empty() {
+            return Err("classifier: labels must not be empty".into());
+        }
+        if self.input_width == Some(0) || self.input_height == Some(0) {
+            return Err("classifier: input size must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FaultPrediction {
+    pub label: String,
+    pub confidence: f32,
+}
+
+// Sesión del modelo cargada la primera vez que se usa (y recargada si cambia la ruta)
+#[derive(Default)]
+pub struct Classifier {
+    #[cfg(feature = "onnx")]
+    session: std::sync::Mutex<Option<(String, ort::session::Session)>>,
+}
+
+#[cfg(feature = "onnx")]
+impl Classifier {
+    // Se llama desde el pool bloqueante
+    pub fn classify(&self, config: &ClassifierConfig, frame: &Array2<f32>) -> Result<FaultPrediction, String> {
+        use ort::{session::Session, value::Tensor};
+
+        let input = resize_nearest(frame, config.input_width, config.input_height);
+        let (height, width) = input.dim();
+        let tensor = Tensor::from_array((
+            vec![1i64, 1, height as i64, width as i64],
+            input.as_standard_layout().into_owned().into_raw_vec(),
+        ))
+        .map_err(|e| e.to_string())?;
+
+        let mut cached = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let (_, session) = match cached.take() {
+            Some((path, session)) if path == config.model_path => cached.insert((path, session)),
+            _ => {
+                let session = Session::builder()
+                    .and_then(|builder| builder.commit_from_file(&config.model_path))
+                    .map_err(|e| format!("cannot load {}: {}", config.model_path, e))?;
+                tracing::info!(model = %config.model_path, "🧠 Modelo ONNX cargado");
+                cached.insert((config.model_path.clone(), session))
+            }
+        };
+
+        let outputs = session.run(ort::inputs![tensor]).map_err(|e| e.to_string())?;
+        let (_, logits) = outputs[0].try_extract_tensor::<f32>().map_err(|e| e.to_string())?;
+        best_label(&config.labels, logits)
+    }
+}
+
+#[cfg(not(feature = "onnx"))]
+impl Classifier {
+    pub fn classify(&self, _config: &ClassifierConfig, _frame: &Array2<f32>) -> Result<FaultPrediction, String> {
+        Err("server built without the `onnx` feature".into())
+    }
+}
+
+// Reescalado por vecino más cercano al tamaño de entrada del modelo
+#[cfg(feature = "onnx")]
+fn resize_nearest(frame: &Array2<f32>, width: Option<usize>, height: Option<usize>) -> Array2<f32> {
+    let (rows, cols) = frame.dim();
+    let (out_rows, out_cols) = (height.unwrap_or(rows), width.unwrap_or(cols));
+    if (out_rows, out_cols) == (rows, cols) {
+        return frame.clone();
+    }
+    Array2::from_shape_fn((out_rows, out_cols), |(r, c)| {
+        frame[[r * rows / out_rows, c * cols / out_cols]]
+    })
+}
+
+// Clase con mayor probabilidad tras softmax
+#[cfg(feature = "onnx")]
+fn best_label(labels: &[String], logits: &[f32]) -> Result<FaultPrediction, String> {
+    if logits.len() != labels.len() {
+        return Err(format!("model returned {} scores for {} labels", logits.len(), labels.len()));
+    }
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exp: Vec<f32> = logits.iter().map(|l| (l - max).exp()).collect();
+    let total: f32 = exp.iter().sum();
+    let (index, best) = exp.iter().enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .ok_or("model returned no scores")?;
+    Ok(FaultPrediction { label: labels[index].clone(), confidence: best / total })
+}
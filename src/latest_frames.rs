@@ -0,0 +1,114 @@
+use crate::analysis::{extract_frame, with_frames, ThermalFrameData};
+use ndarray::Axis;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::RwLock;
+
+// --- ÚLTIMOS FRAMES EN MEMORIA ---
+// De cada turbina se guardan decodificados los frames de sus últimas capturas (el último
+// frame de cada una, el más reciente de la pila), en un búfer circular de tamaño fijo:
+// /api/live muestra las estadísticas térmicas del más nuevo, /api/live/frames/:token los
+// devuelve todos sin tocar el disco y /api/matrix los sirve desde aquí antes que desde la
+// caché LRU o el archivo. Temperaturas en bruto, igual que /api/matrix.
+
+#[derive(Clone)]
+pub struct LatestFrame {
+    pub filename: String,
+    pub frame_index: usize,
+    pub timestamp: u64,
+    pub frame: ThermalFrameData,
+}
+
+// Estadísticas del frame más reciente de una turbina, para /api/live
+#[derive(Serialize, Clone, Debug)]
+pub struct LatestFrameStats {
+    pub filename: String,
+    pub frame_index: usize,
+    pub timestamp: u64,
+    pub width: usize,
+    pub height: usize,
+    pub min_temp: f32,
+    pub max_temp: f32,
+    pub avg_temp: f32,
+}
+
+impl LatestFrame {
+    // Último frame de una captura (en el pool bloqueante); None si no es legible
+    pub fn decode(filename: &str, timestamp: u64, bytes: &[u8]) -> Option<Self> {
+        let count = with_frames(bytes, |frames| frames.len_of(Axis(0)))?;
+        let frame_index = count.checked_sub(1)?;
+        let frame = extract_frame(bytes, frame_index).ok()?;
+        Some(LatestFrame { filename: filename.to_string(), frame_index, timestamp, frame })
+    }
+
+    pub fn stats(&self) -> LatestFrameStats {
+        let finite: Vec<f32> = self.frame.pixels.iter().copied().filter(|v| v.is_finite()).collect();
+        let avg_temp = if finite.is_empty() { 0.0 } else { finite.iter().sum::<f32>() / finite.len() as f32 };
+        LatestFrameStats {
+            filename: self.filename.clone(),
+            frame_index: self.frame_index,
+            timestamp: self.timestamp,
+            width: self.frame.width,
+            height: self.frame.height,
+            min_temp: self.frame.min_temp,
+            max_temp: self.frame.max_temp,
+            avg_temp,
+        }
+    }
+}
+
+pub struct LatestFrames {
+    // Frames por turbina (0 = desactivado)
+    per_turbine: usize,
+    frames: RwLock<HashMap<String, VecDeque<LatestFrame>>>,
+}
+
+impl LatestFrames {
+    pub fn new(per_turbine: usize) -> Self {
+        LatestFrames { per_turbine, frames: RwLock::new(HashMap::new()) }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.per_turbine > 0
+    }
+
+    // Añade el frame de una captura nueva; las tomas con fecha anterior a la última del
+    // búfer (reenvíos, importaciones) no desplazan a las más recientes
+    pub async fn push(&self, turbine_token: &str, frame: LatestFrame) {
+        if !self.enabled() {
+            return;
+        }
+        let mut frames = self.frames.write().await;
+        let ring = frames.entry(turbine_token.to_string()).or_default();
+        if ring.front().is_some_and(|newest| newest.timestamp > frame.timestamp) {
+            return;
+        }
+        ring.push_front(frame);
+        ring.truncate(self.per_turbine);
+    }
+
+    // Frames de una turbina, del más nuevo al más antiguo
+    pub async fn for_turbine(&self, turbine_token: &str) -> Vec<LatestFrame> {
+        self.frames.read().await.get(turbine_token).map(|ring| ring.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub async fn newest(&self, turbine_token: &str) -> Option<LatestFrame> {
+        self.frames.read().await.get(turbine_token)?.front().cloned()
+    }
+
+    // Frame concreto de una captura, si está en algún búfer
+    pub async fn find(&self, filename: &str, frame_index: usize) -> Option<ThermalFrameData> {
+        self.frames.read().await.values()
+            .flatten()
+            .find(|f| f.filename == filename && f.frame_index == frame_index)
+            .map(|f| f.frame.clone())
+    }
+
+    pub async fn remove(&self, turbine_token: &str) {
+        self.frames.write().await.remove(turbine_token);
+    }
+
+    pub async fn clear(&self) {
+        self.frames.write().await.clear();
+    }
+}
@@ -0,0 +1,58 @@
+// GSU Sentinel Cloud: ingesta de capturas térmicas de los robots y API para el dashboard.
+// El binario (main.rs) solo parsea la línea de comandos y levanta el servidor;
+// toda la lógica vive en estos módulos para poder reutilizarla y probarla.
+
+pub mod adaptive_scan;
+pub mod alertmanager;
+pub mod analysis;
+pub mod analyzers;
+pub mod bench;
+pub mod calibration;
+// Tipos de la API y cliente reqwest para robots y herramientas en Rust
+#[cfg(feature = "client-types")]
+pub mod client;
+pub mod commands;
+pub mod email;
+pub mod error;
+pub mod events;
+pub mod graphql;
+pub mod i18n;
+pub mod incidents;
+pub mod inference;
+pub mod latest_frames;
+pub mod metrics;
+pub mod modbus;
+pub mod notify;
+pub mod objectstore;
+pub mod pipeline;
+pub mod plausibility;
+pub mod push;
+pub mod quality;
+pub mod replication;
+pub mod reports;
+pub mod resolution;
+pub mod response_cache;
+pub mod routes;
+pub mod rules;
+pub mod scada;
+pub mod schedule;
+pub mod secrets;
+pub mod server;
+pub mod sessions;
+pub mod settings;
+pub mod shadow;
+pub mod simulate;
+pub mod state;
+pub mod storage;
+pub mod telemetry;
+pub mod thresholds;
+pub mod timeline;
+pub mod units;
+pub mod watchdog;
+pub mod weather;
+pub mod workorders;
+
+pub use error::AppError;
+pub use routes::build_router;
+pub use settings::ServerSettings;
+pub use state::AppState;
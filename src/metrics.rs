@@ -0,0 +1,128 @@
+use crate::{state::AppState, watchdog::fault_counts};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{collections::BTreeMap, fmt::Write, sync::Arc, time::Instant};
+use tokio::sync::Mutex;
+
+// --- MÉTRICAS ---
+// Contadores e histogramas de latencia por ruta, expuestos en /metrics con el formato
+// de texto de Prometheus. La ruta es la plantilla (p. ej. /api/matrix/:filename/:frame_index)
+// para que la cardinalidad no crezca con cada archivo pedido.
+
+// Límites superiores de los buckets de latencia, en segundos
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Default)]
+struct LatencyHistogram {
+    // Conteo por bucket (no acumulado); el último elemento es +Inf
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum_seconds: f64,
+    count: u64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, seconds: f64) {
+        let index = LATENCY_BUCKETS.iter().position(|&le| seconds <= le).unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[index] += 1;
+        self.sum_seconds += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct MetricsInner {
+    // (método, ruta, código) -> peticiones
+    requests: BTreeMap<(String, String, u16), u64>,
+    // (método, ruta) -> latencias
+    latency: BTreeMap<(String, String), LatencyHistogram>,
+    // (ruta, tipo) -> subidas cuyo formulario no se pudo leer
+    upload_errors: BTreeMap<(&'static str, &'static str), u64>,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl Metrics {
+    pub async fn record_request(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let mut inner = self.inner.lock().await;
+        *inner.requests.entry((method.to_owned(), route.to_owned(), status)).or_insert(0) += 1;
+        inner.latency.entry((method.to_owned(), route.to_owned())).or_default().observe(seconds);
+    }
+
+    // Subida cuyo formulario multipart falló: "malformed", "timeout" o "too_large"
+    pub async fn record_upload_error(&self, route: &'static str, kind: &'static str) {
+        *self.inner.lock().await.upload_errors.entry((route, kind)).or_insert(0) += 1;
+    }
+
+    // Exposición en formato de texto de Prometheus
+    pub async fn render(&self) -> String {
+        let inner = self.inner.lock().await;
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP sentinel_http_requests_total Peticiones HTTP atendidas por ruta y código.");
+        let _ = writeln!(out, "# TYPE sentinel_http_requests_total counter");
+        for ((method, route, status), count) in &inner.requests {
+            let _ = writeln!(
+                out,
+                "sentinel_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                method, route, status, count
+            );
+        }
+
+        let _ = writeln!(out, "# HELP sentinel_http_request_duration_seconds Latencia de las peticiones HTTP por ruta.");
+        let _ = writeln!(out, "# TYPE sentinel_http_request_duration_seconds histogram");
+        for ((method, route), histogram) in &inner.latency {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, route);
+            let mut cumulative = 0;
+            for (le, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "sentinel_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, le, cumulative);
+            }
+            let _ = writeln!(out, "sentinel_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, histogram.count);
+            let _ = writeln!(out, "sentinel_http_request_duration_seconds_sum{{{}}} {}", labels, histogram.sum_seconds);
+            let _ = writeln!(out, "sentinel_http_request_duration_seconds_count{{{}}} {}", labels, histogram.count);
+        }
+
+        let _ = writeln!(out, "# HELP sentinel_upload_errors_total Subidas con el formulario mal formado, cortado o demasiado grande.");
+        let _ = writeln!(out, "# TYPE sentinel_upload_errors_total counter");
+        for ((route, kind), count) in &inner.upload_errors {
+            let _ = writeln!(out, "sentinel_upload_errors_total{{route=\"{}\",kind=\"{}\"}} {}", route, kind, count);
+        }
+
+        let _ = writeln!(out, "# HELP sentinel_internal_faults_total Fallos internos del servidor vistos por el watchdog.");
+        let _ = writeln!(out, "# TYPE sentinel_internal_faults_total counter");
+        for (fault, count) in fault_counts() {
+            let _ = writeln!(out, "sentinel_internal_faults_total{{kind=\"{}\"}} {}", fault.as_str(), count);
+        }
+        out
+    }
+}
+
+// Middleware que mide cada petición
+pub async fn track_metrics(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    // Las rutas no registradas se agrupan para no crear una serie por URL desconocida
+    let route = request.extensions().get::<MatchedPath>()
+        .map(|p| p.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".to_owned());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    state.metrics.record_request(&method, &route, response.status().as_u16(), elapsed).await;
+    response
+}
+
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render().await,
+    )
+}
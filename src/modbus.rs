@@ -0,0 +1,153 @@
+use crate::{
+    scada::{fleet_snapshot, TurbineSnapshot},
+    state::AppState,
+    thresholds::Severity,
+};
+use std::{collections::HashMap, sync::Arc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+// --- MODBUS TCP PARA LOS PLC DE PLANTA ---
+// Listener opcional (--modbus-listen) de solo lectura: el PLC lee holding registers
+// (función 0x03) y puede enclavar sobre las alertas sin pasarela intermedia.
+//
+// Mapa de registros:
+//   0            sistema habilitado (system_enabled, 0/1)
+//   1            turbinas con slot asignado
+//   2            segundos del reloj del servidor (mod 65536), para vigilar que responde
+//   100 + 10·s   bloque de la turbina con modbus_slot = s:
+//     +0  en línea (0/1)
+//     +1  temperatura máxima actual ×10 (°C, entero con signo)
+//     +2  flags: bit0 alerta abierta, bit1 aviso abierto, bit2 crítica abierta
+//     +3  alertas abiertas
+//     +4  segundos desde el último heartbeat (65535 = nunca o más)
+// Los bloques sin turbina se leen a cero.
+
+pub const TURBINE_BLOCK_BASE: u16 = 100;
+pub const TURBINE_BLOCK_SIZE: u16 = 10;
+// Slots admitidos: el último bloque termina por debajo de 65536
+pub const MAX_MODBUS_SLOT: u16 = (u16::MAX - TURBINE_BLOCK_BASE) / TURBINE_BLOCK_SIZE - 1;
+
+// Límite del protocolo para una lectura de registros
+const MAX_READ_REGISTERS: u16 = 125;
+
+const READ_HOLDING_REGISTERS: u8 = 0x03;
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+const ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+fn turbine_block(turbine: &TurbineSnapshot, now: u64) -> [u16; 5] {
+    let flags = match turbine.open_severity {
+        None => 0,
+        Some(Severity::Warning) => 0b011,
+        Some(Severity::Critical) => 0b101,
+    };
+    let temp = turbine.current_max_temp.map_or(0, |t| (t * 10.0).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16);
+    let age = turbine.last_update.map_or(u16::MAX, |t| now.saturating_sub(t).min(u16::MAX as u64) as u16);
+    [turbine.online as u16, temp as u16, flags, turbine.open_alerts.min(u16::MAX as u32) as u16, age]
+}
+
+// Valores de todos los registros distintos de cero en este instante
+async fn register_map(state: &AppState) -> HashMap<u16, u16> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let slots: HashMap<String, u16> = state.turbines.read().await.iter()
+        .filter_map(|t| Some((t.token.clone(), t.modbus_slot?)))
+        .collect();
+    let mut registers = HashMap::new();
+    registers.insert(0, state.config.read().await.system_enabled as u16);
+    registers.insert(1, slots.len() as u16);
+    registers.insert(2, (now % 65536) as u16);
+    for turbine in fleet_snapshot(state).await {
+        let Some(&slot) = slots.get(&turbine.token) else { continue };
+        let base = TURBINE_BLOCK_BASE + slot * TURBINE_BLOCK_SIZE;
+        for (offset, value) in turbine_block(&turbine, now).into_iter().enumerate() {
+            registers.insert(base + offset as u16, value);
+        }
+    }
+    registers
+}
+
+// Respuesta (PDU) a una petición (PDU)
+async fn handle_pdu(state: &AppState, pdu: &[u8]) -> Vec<u8> {
+    let Some(&function) = pdu.first() else { return vec![0x80, ILLEGAL_FUNCTION] };
+    let exception = |code: u8| vec![function | 0x80, code];
+    if function != READ_HOLDING_REGISTERS {
+        return exception(ILLEGAL_FUNCTION);
+    }
+    if pdu.len() != 5 {
+        return exception(ILLEGAL_DATA_VALUE);
+    }
+    let start = u16::from_be_bytes([pdu[1], pdu[2]]);
+    let count = u16::from_be_bytes([pdu[3], pdu[4]]);
+    if count == 0 || count > MAX_READ_REGISTERS {
+        return exception(ILLEGAL_DATA_VALUE);
+    }
+    if start.checked_add(count).is_none() {
+        return exception(ILLEGAL_DATA_ADDRESS);
+    }
+    let registers = register_map(state).await;
+    let mut response = vec![function, (count * 2) as u8];
+    for address in start..start + count {
+        response.extend(registers.get(&address).copied().unwrap_or(0).to_be_bytes());
+    }
+    response
+}
+
+// Atiende peticiones de un cliente hasta que cierra la conexión
+async fn serve_client(state: Arc<AppState>, mut stream: TcpStream) -> std::io::Result<()> {
+    loop {
+        // Cabecera MBAP: transacción, protocolo (0), longitud (unidad + PDU), unidad
+        let mut header = [0u8; 7];
+        match stream.read_exact(&mut header).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        let protocol = u16::from_be_bytes([header[2], header[3]]);
+        let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+        if protocol != 0 || !(2..=254).contains(&length) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid MBAP header"));
+        }
+        let mut pdu = vec![0u8; length - 1];
+        stream.read_exact(&mut pdu).await?;
+
+        let response = handle_pdu(&state, &pdu).await;
+        let mut frame = Vec::with_capacity(7 + response.len());
+        frame.extend_from_slice(&header[..4]);
+        frame.extend(((response.len() + 1) as u16).to_be_bytes());
+        frame.push(header[6]);
+        frame.extend(response);
+        stream.write_all(&frame).await?;
+    }
+}
+
+pub fn spawn_modbus_listener(state: &Arc<AppState>) {
+    let Some(addr) = state.settings.modbus_listen else { return };
+    let state = state.clone();
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!(%addr, error = %e, "❌ No se pudo abrir el listener Modbus TCP");
+                return;
+            }
+        };
+        tracing::info!(%addr, "🏭 Modbus TCP escuchando");
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    tracing::debug!(%peer, "🏭 Cliente Modbus conectado");
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve_client(state, stream).await {
+                            tracing::warn!(%peer, error = %e, "⚠️ Conexión Modbus cerrada con error");
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error aceptando conexión Modbus"),
+            }
+        }
+    });
+}
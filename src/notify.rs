@@ -0,0 +1,750 @@
+use crate::{
+    alertmanager,
+    i18n::{Locale, Localized},
+    incidents::{self, Correlation, Incident},
+    push::{push_to_subscribers, PushMessage, PushTopic},
+    schedule::{parse_timezone, CronSchedule},
+    state::{AlertRecord, AppState},
+    secrets::SecretVault,
+    storage::{deliveries::save_failed_deliveries, held::save_held_notifications},
+    thresholds::Severity,
+    watchdog::{record_fault, Fault},
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::RwLock;
+
+// --- NOTIFICACIONES ---
+// Punto único por el que pasa cada alerta nueva: la registra en el estado y
+// avisa por los canales que indique el nivel de umbral alcanzado. El log del
+// servidor recibe siempre todas las alertas. Las alertas que forman parte de un
+// incidente multi-turbina se notifican una sola vez, como incidente.
+
+// Canal de notificación configurable, referenciado por nombre desde los niveles
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NotificationChannel {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: ChannelKind,
+    // Mensaje propio del canal ("🔥 {{turbine}} a {{max_temp}} °C"); sin plantilla se
+    // envía la alerta completa en JSON
+    #[serde(default)]
+    pub template: Option<String>,
+    // Idioma de {{summary}} y del mensaje de los avisos de dispositivo
+    #[serde(default)]
+    pub locale: Locale,
+    // Horas en las que solo se envía lo crítico (ver HORAS DE SILENCIO)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChannelKind {
+    // Solo deja constancia en el log (útil para niveles informativos)
+    Log,
+    // POST con la alerta (o el incidente) en JSON; con `secret`, firmado (ver FIRMA DE
+    // LOS WEBHOOKS)
+    Webhook {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        secret: Option<String>,
+    },
+}
+
+pub async fn raise_alert(state: &AppState, mut alert: AlertRecord, channels: &[String]) {
+    let correlation = incidents::correlate(state, &mut alert).await;
+    tracing::warn!(
+        turbine_token = %alert.turbine_token,
+        severity = ?alert.severity,
+        level = alert.level.as_deref(),
+        max_temp = alert.max_temp,
+        angle = alert.angle,
+        dataset_path = %alert.dataset_path,
+        blades = alert.blade_imbalance.as_ref().map(|b| b.summary.as_str()),
+        incident_id = alert.incident_id.as_deref(),
+        "🚨 Alerta registrada"
+    );
+
+    match correlation {
+        Correlation::Single => {
+            send(state, channels, alert.severity, |locale| (serde_json::json!(alert), alert_vars(state, &alert, locale))).await;
+            if alert.severity == Severity::Critical {
+                let message = |locale| alert_push(&alert, &alert_vars(state, &alert, locale), locale);
+                push_to_subscribers(state, &alert.turbine_token, PushTopic::CriticalAlert, message).await;
+            }
+        }
+        Correlation::Opened(incident, alerts) => {
+            tracing::warn!(
+                incident_id = %incident.id,
+                site = %incident.site,
+                turbines = ?incident.turbines,
+                "🔗 Incidente multi-turbina abierto"
+            );
+            let payload = serde_json::json!({ "incident": incident, "alerts": alerts });
+            send(state, channels, incident.severity, |locale| (payload.clone(), incident_vars(state, &incident, &alerts, locale))).await;
+            if incident.severity == Severity::Critical {
+                let message = |locale| alert_push(&alert, &incident_vars(state, &incident, &alerts, locale), locale);
+                for turbine in &incident.turbines {
+                    push_to_subscribers(state, turbine, PushTopic::CriticalAlert, &message).await;
+                }
+            }
+        }
+        Correlation::Joined(incident) => {
+            tracing::info!(incident_id = %incident.id, turbines = incident.turbines.len(), "🔗 Alerta añadida a incidente ya notificado");
+        }
+    }
+
+    alertmanager::forward_alert(state, &alert).await;
+    state.push_alert(alert).await;
+}
+
+// Aviso de una alerta resuelta sola (ver crate::resolution); nunca es urgente
+pub async fn notify_resolution(state: &AppState, alert: &AlertRecord, channels: &[String]) {
+    send(state, channels, Severity::Warning, |locale| {
+        let mut vars = alert_vars(state, alert, locale);
+        if let Some(resolution) = &alert.resolution {
+            vars.insert("time", format_time(resolution.timestamp));
+            vars.insert("summary", locale.pick(
+                format!("[Resolved] {}: back to {:.1} °C at {:.1}°", alert.turbine_token, resolution.max_temp, alert.angle),
+                format!("[Resuelta] {}: de vuelta a {:.1} °C a {:.1}°", alert.turbine_token, resolution.max_temp, alert.angle),
+            ));
+        }
+        (serde_json::json!({ "resolved_alert": alert }), vars)
+    })
+    .await;
+}
+
+// Aviso push de una alerta crítica (o del incidente que abre)
+fn alert_push(alert: &AlertRecord, vars: &TemplateVars, locale: Locale) -> PushMessage {
+    let var = |name: &str| vars.get(name).cloned().unwrap_or_default();
+    PushMessage {
+        title: locale.pick(format!("🚨 Critical alert · {}", var("turbine")), format!("🚨 Alerta crítica · {}", var("turbine"))),
+        body: locale.pick(
+            format!("{} °C at {}°", var("max_temp"), var("angle")),
+            format!("{} °C a {}°", var("max_temp"), var("angle")),
+        ),
+        data: HashMap::from([
+            ("type".to_string(), "alert".to_string()),
+            ("alert_id".to_string(), alert.id.clone()),
+            ("turbine_token".to_string(), alert.turbine_token.clone()),
+            ("incident_id".to_string(), var("incident")),
+            ("thumbnail".to_string(), var("thumbnail")),
+        ]),
+    }
+}
+
+// Encola el payload (o el mensaje de la plantilla del canal) para los canales indicados,
+// preparados en el idioma de cada canal; los webhooks se entregan en segundo plano. Lo no
+// crítico para un canal en horas de silencio se retiene para su resumen
+async fn send(
+    state: &AppState,
+    channels: &[String],
+    severity: Severity,
+    content: impl Fn(Locale) -> (serde_json::Value, TemplateVars),
+) {
+    let targets: Vec<NotificationChannel> = state.config.read().await.channels.iter()
+        .filter(|c| channels.contains(&c.name))
+        .cloned()
+        .collect();
+    let now = chrono::Utc::now();
+    let mut held = Vec::new();
+    for channel in targets {
+        let (payload, vars) = content(channel.locale);
+        if severity != Severity::Critical && channel.quiet_hours.as_ref().is_some_and(|q| q.is_quiet(&now)) {
+            held.push(HeldNotification {
+                channel: channel.name.clone(),
+                timestamp: now.timestamp() as u64,
+                severity,
+                summary: vars.get("summary").cloned().unwrap_or_default(),
+                payload,
+            });
+            continue;
+        }
+        dispatch(state, &channel, payload, &vars).await;
+    }
+    if !held.is_empty() {
+        hold_notifications(state, held).await;
+    }
+}
+
+async fn dispatch(state: &AppState, channel: &NotificationChannel, payload: serde_json::Value, vars: &TemplateVars) {
+    let message = channel.template.as_deref().map(|t| render_template(t, vars));
+    match &channel.kind {
+        ChannelKind::Log => {
+            if let Some(message) = message {
+                tracing::warn!(channel = %channel.name, %message, "📣 Notificación");
+            }
+        }
+        ChannelKind::Webhook { url, .. } => {
+            // {"text": ...} es lo que esperan los webhooks de chat habituales
+            let body = match message {
+                Some(message) => serde_json::json!({ "text": message }),
+                None => payload,
+            };
+            spawn_delivery(state, Delivery::new(&channel.name, url, body)).await;
+        }
+    }
+}
+
+// --- HORAS DE SILENCIO ---
+// Un canal con quiet_hours solo recibe lo crítico durante esa ventana cron (en la zona
+// horaria indicada, UTC por defecto). Los avisos y las resoluciones se retienen, en
+// persistencia, y al acabar la ventana se envían en un único resumen: sin plantilla, el
+// JSON {"quiet_hours_digest": {...}} con los payloads retenidos; con plantilla, {{summary}}
+// es la lista de resúmenes de una línea. El log del servidor no se silencia nunca.
+
+// Avisos retenidos por canal como máximo; después se descartan los más antiguos
+const MAX_HELD_PER_CHANNEL: usize = 500;
+// Líneas del resumen en plantilla; el resto se cuenta
+const DIGEST_SUMMARY_LINES: usize = 20;
+const QUIET_HOURS_CHECK_SEC: u64 = 60;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QuietHours {
+    // Ventana cron ("* 22-23,0-6 * * *": de 22:00 a 06:59)
+    pub window: String,
+    // Zona de la ventana ("Europe/Madrid"); sin ella, UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl QuietHours {
+    pub fn validate(&self) -> Result<(), String> {
+        self.window.parse::<CronSchedule>()?;
+        if let Some(timezone) = &self.timezone {
+            parse_timezone(timezone)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_quiet(&self, at: &chrono::DateTime<chrono::Utc>) -> bool {
+        let Ok(window) = self.window.parse::<CronSchedule>() else { return false };
+        match self.timezone.as_deref().map(parse_timezone) {
+            Some(Ok(tz)) => window.matches(&at.with_timezone(&tz)),
+            _ => window.matches(at),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HeldNotification {
+    pub channel: String,
+    pub timestamp: u64,
+    pub severity: Severity,
+    // Resumen de una línea en el idioma del canal
+    pub summary: String,
+    // Lo que se habría enviado sin plantilla
+    pub payload: serde_json::Value,
+}
+
+async fn hold_notifications(state: &AppState, new: Vec<HeldNotification>) {
+    let mut held = state.held_notifications.write().await;
+    for notification in new {
+        tracing::info!(channel = %notification.channel, summary = %notification.summary, "🌙 Notificación retenida por las horas de silencio del canal");
+        held.push(notification);
+    }
+    // Se conservan los MAX_HELD_PER_CHANNEL más recientes de cada canal
+    let before = held.len();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    held.reverse();
+    held.retain(|h| {
+        let count = counts.entry(h.channel.clone()).or_default();
+        *count += 1;
+        *count <= MAX_HELD_PER_CHANNEL
+    });
+    held.reverse();
+    if held.len() < before {
+        tracing::warn!(discarded = before - held.len(), "⚠️ Demasiadas notificaciones retenidas; se descartan las más antiguas");
+    }
+    save_held_notifications(&held);
+}
+
+// Resumen de lo retenido para un canal cuyas horas de silencio han terminado
+async fn send_digest(state: &AppState, channel: &NotificationChannel, held: Vec<HeldNotification>) {
+    let (from, to) = (held.first().map_or(0, |h| h.timestamp), held.last().map_or(0, |h| h.timestamp));
+    let mut lines: Vec<String> = held.iter().take(DIGEST_SUMMARY_LINES).map(|h| format!("- {}", h.summary)).collect();
+    if held.len() > DIGEST_SUMMARY_LINES {
+        let more = held.len() - DIGEST_SUMMARY_LINES;
+        lines.push(channel.locale.pick(format!("… and {} more", more), format!("… y {} más", more)));
+    }
+    let header = channel.locale.pick(
+        format!("{} notifications held during quiet hours:", held.len()),
+        format!("{} notificaciones retenidas durante las horas de silencio:", held.len()),
+    );
+    let mut vars: TemplateVars = TEMPLATE_VARS.iter().map(|name| (*name, String::new())).collect();
+    vars.insert("severity", severity_name(Severity::Warning));
+    vars.insert("time", format_time(to));
+    vars.insert("summary", format!("{}\n{}", header, lines.join("\n")));
+    tracing::info!(channel = %channel.name, count = held.len(), "🌅 Fin de las horas de silencio: resumen de lo retenido");
+    let payload = serde_json::json!({
+        "quiet_hours_digest": {
+            "channel": channel.name,
+            "from": from,
+            "to": to,
+            "count": held.len(),
+            "notifications": held.into_iter().map(|h| h.payload).collect::<Vec<_>>(),
+        }
+    });
+    dispatch(state, channel, payload, &vars).await;
+}
+
+// Revisa cada minuto los canales con avisos retenidos y envía el resumen de los que ya
+// salieron de sus horas de silencio (o las perdieron al cambiar la configuración). Lo de
+// canales eliminados se descarta
+pub fn spawn_quiet_hours_digest(state: &Arc<AppState>) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(QUIET_HOURS_CHECK_SEC));
+        loop {
+            ticker.tick().await;
+            if state.held_notifications.read().await.is_empty() {
+                continue;
+            }
+            let channels = state.config.read().await.channels.clone();
+            let now = chrono::Utc::now();
+            let due = {
+                let mut held = state.held_notifications.write().await;
+                let (due, kept): (Vec<_>, Vec<_>) = held.drain(..).partition(|h| {
+                    match channels.iter().find(|c| c.name == h.channel) {
+                        Some(channel) => !channel.quiet_hours.as_ref().is_some_and(|q| q.is_quiet(&now)),
+                        None => true,
+                    }
+                });
+                *held = kept;
+                save_held_notifications(&held);
+                due
+            };
+            let mut by_channel: BTreeMap<String, Vec<HeldNotification>> = BTreeMap::new();
+            for notification in due {
+                by_channel.entry(notification.channel.clone()).or_default().push(notification);
+            }
+            for (name, held) in by_channel {
+                match channels.iter().find(|c| c.name == name) {
+                    Some(channel) => send_digest(&state, channel, held).await,
+                    None => tracing::warn!(channel = %name, discarded = held.len(), "⚠️ Notificaciones retenidas de un canal que ya no existe; se descartan"),
+                }
+            }
+        }
+    });
+}
+
+// --- AVISOS DE DISPOSITIVO ---
+// Problemas operativos de un robot (no térmicos), para los canales de
+// device_alert_channels. Sin plantilla se envía el aviso en JSON, con el mensaje en el
+// idioma del canal; con plantilla solo tienen valor turbine, severity, level (el tipo de
+// aviso), time y summary (el mensaje).
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DeviceAlert {
+    pub id: String,
+    pub timestamp: u64,
+    pub turbine_token: String,
+    // "ingest_flood"...
+    pub kind: String,
+    // En inglés; al enviarlo, en el idioma del canal
+    pub message: String,
+    #[serde(skip)]
+    pub messages: Localized,
+}
+
+impl DeviceAlert {
+    pub fn new(turbine_token: &str, kind: &str, message: Localized) -> Self {
+        DeviceAlert {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            turbine_token: turbine_token.to_string(),
+            kind: kind.to_string(),
+            message: message.en.clone(),
+            messages: message,
+        }
+    }
+
+    fn in_locale(&self, locale: Locale) -> DeviceAlert {
+        DeviceAlert { message: self.messages.get(locale).to_string(), ..self.clone() }
+    }
+}
+
+pub async fn raise_device_alert(state: &AppState, alert: DeviceAlert) {
+    tracing::warn!(turbine_token = %alert.turbine_token, kind = %alert.kind, message = %alert.message, "🤖 Aviso de dispositivo");
+    let channels = state.config.read().await.device_alert_channels.clone();
+    send(state, &channels, Severity::Warning, |locale| {
+        let mut vars: TemplateVars = TEMPLATE_VARS.iter().map(|name| (*name, String::new())).collect();
+        vars.insert("turbine", alert.turbine_token.clone());
+        vars.insert("severity", severity_name(Severity::Warning));
+        vars.insert("level", alert.kind.clone());
+        vars.insert("time", format_time(alert.timestamp));
+        vars.insert("summary", alert.messages.get(locale).to_string());
+        (serde_json::json!({ "device_alert": alert.in_locale(locale) }), vars)
+    })
+    .await;
+}
+
+// --- AVISOS DEL PROPIO SERVIDOR ---
+// Fallos internos detectados por crate::watchdog (y su recuperación), para los canales de
+// watchdog.channels. Como los avisos de dispositivo: sin plantilla se envía
+// {"health_alert": {...}}; con plantilla, level es el tipo de fallo y summary el mensaje.
+// Los fallos que pierden datos son críticos y no esperan a las horas de silencio.
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HealthAlert {
+    pub id: String,
+    pub timestamp: u64,
+    // "disk_write"...
+    pub fault: String,
+    pub severity: Severity,
+    pub recovered: bool,
+    // En inglés; al enviarlo, en el idioma del canal
+    pub message: String,
+    #[serde(skip)]
+    pub messages: Localized,
+}
+
+impl HealthAlert {
+    pub fn new(fault: &str, severity: Severity, recovered: bool, message: Localized) -> Self {
+        HealthAlert {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            fault: fault.to_string(),
+            severity,
+            recovered,
+            message: message.en.clone(),
+            messages: message,
+        }
+    }
+}
+
+pub async fn raise_health_alert(state: &AppState, channels: &[String], alert: HealthAlert) {
+    send(state, channels, alert.severity, |locale| {
+        let mut vars: TemplateVars = TEMPLATE_VARS.iter().map(|name| (*name, String::new())).collect();
+        vars.insert("severity", severity_name(alert.severity));
+        vars.insert("level", alert.fault.clone());
+        vars.insert("time", format_time(alert.timestamp));
+        vars.insert("summary", alert.messages.get(locale).to_string());
+        let localized = HealthAlert { message: alert.messages.get(locale).to_string(), ..alert.clone() };
+        (serde_json::json!({ "health_alert": localized }), vars)
+    })
+    .await;
+}
+
+// --- PLANTILLAS DE MENSAJE ---
+// Sintaxis mínima: {{variable}} se sustituye por su valor; no hay condicionales ni
+// bucles. Las variables desconocidas se rechazan al guardar la configuración.
+
+pub const TEMPLATE_VARS: &[&str] = &[
+    "turbine", "max_temp", "angle", "severity", "level", "zone", "time", "dataset", "alert_id", "incident", "site",
+    "link", "thumbnail", "rules", "summary",
+];
+
+pub type TemplateVars = HashMap<&'static str, String>;
+
+fn severity_name(severity: Severity) -> String {
+    serde_json::to_value(severity).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+pub fn capture_link(state: &AppState, dataset: &str) -> String {
+    let base = state.settings.public_url.as_deref().unwrap_or("").trim_end_matches('/');
+    format!("{}/api/download/{}?inline=true", base, dataset)
+}
+
+fn format_time(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0).map(|d| d.to_rfc3339()).unwrap_or_default()
+}
+
+// Resumen de una línea en el idioma del canal
+pub fn alert_summary(alert: &AlertRecord, locale: Locale) -> String {
+    locale.pick(
+        format!("[{}] {}: {:.1} °C at {:.1}°", locale.severity(alert.severity), alert.turbine_token, alert.max_temp, alert.angle),
+        format!("[{}] {}: {:.1} °C a {:.1}°", locale.severity(alert.severity), alert.turbine_token, alert.max_temp, alert.angle),
+    )
+}
+
+fn alert_vars(state: &AppState, alert: &AlertRecord, locale: Locale) -> TemplateVars {
+    HashMap::from([
+        ("turbine", alert.turbine_token.clone()),
+        ("max_temp", format!("{:.1}", alert.max_temp)),
+        ("angle", format!("{:.1}", alert.angle)),
+        ("severity", severity_name(alert.severity)),
+        ("level", alert.level.clone().unwrap_or_default()),
+        ("zone", alert.zone.clone().unwrap_or_default()),
+        ("time", format_time(alert.timestamp)),
+        ("dataset", alert.dataset_path.clone()),
+        ("alert_id", alert.id.clone()),
+        ("incident", alert.incident_id.clone().unwrap_or_default()),
+        ("site", String::new()),
+        ("link", capture_link(state, &alert.dataset_path)),
+        ("thumbnail", alert.links.as_ref().map(|l| l.thumbnail.clone()).unwrap_or_default()),
+        ("rules", alert.rules.join(", ")),
+        ("summary", alert_summary(alert, locale)),
+    ])
+}
+
+// Incidente: turbinas separadas por comas y los datos de la alerta más caliente
+fn incident_vars(state: &AppState, incident: &Incident, alerts: &[AlertRecord], locale: Locale) -> TemplateVars {
+    let hottest = alerts.iter().max_by(|a, b| a.max_temp.total_cmp(&b.max_temp));
+    let mut vars = match hottest {
+        Some(alert) => alert_vars(state, alert, locale),
+        None => HashMap::new(),
+    };
+    let (severity, turbines) = (locale.severity(incident.severity), incident.turbines.join(", "));
+    let hottest = hottest.map(|a| format!("{:.1} °C", a.max_temp)).unwrap_or_else(|| "-".into());
+    vars.insert("summary", locale.pick(
+        format!("[{}] Incident at {}: {} (hottest {})", severity, incident.site, turbines, hottest),
+        format!("[{}] Incidente en {}: {} (máxima {})", severity, incident.site, turbines, hottest),
+    ));
+    vars.insert("turbine", incident.turbines.join(", "));
+    vars.insert("severity", severity_name(incident.severity));
+    vars.insert("time", format_time(incident.started));
+    vars.insert("incident", incident.id.clone());
+    vars.insert("site", incident.site.clone());
+    vars
+}
+
+// Nombres de las variables usadas por una plantilla, en orden de aparición
+fn template_placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else { break };
+        names.push(rest[start + 2..start + 2 + end].trim());
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+pub fn render_template(template: &str, vars: &TemplateVars) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else { break };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + end].trim();
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+impl NotificationChannel {
+    pub fn validate(&self) -> Result<(), String> {
+        if let ChannelKind::Webhook { secret: Some(secret), .. } = &self.kind
+            && secret.trim().is_empty()
+        {
+            return Err(format!("channel '{}': empty webhook secret", self.name));
+        }
+        if let Some(quiet_hours) = &self.quiet_hours {
+            quiet_hours.validate().map_err(|e| format!("channel '{}': quiet_hours: {}", self.name, e))?;
+        }
+        let Some(template) = &self.template else { return Ok(()) };
+        if template.trim().is_empty() {
+            return Err(format!("channel '{}': empty template", self.name));
+        }
+        match template_placeholders(template).into_iter().find(|name| !TEMPLATE_VARS.contains(name)) {
+            Some(unknown) => Err(format!(
+                "channel '{}': unknown template variable '{{{{{}}}}}' (available: {})",
+                self.name,
+                unknown,
+                TEMPLATE_VARS.join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+// --- ENTREGAS CON REINTENTOS ---
+// Cada envío a un webhook se reintenta con espera exponencial (2 s, 4 s, 8 s... hasta
+// 5 min) para sobrevivir a caídas breves del destino. Si se agotan los intentos pasa a la
+// lista de entregas fallidas, persistida, desde donde se puede reenviar a mano.
+
+// Intentos totales antes de dar la entrega por fallida (~4 min de reintentos)
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 8;
+const BASE_BACKOFF_SEC: u64 = 2;
+const MAX_BACKOFF_SEC: u64 = 300;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+// Entregas fallidas que se conservan; se descartan las más antiguas
+pub const MAX_FAILED_DELIVERIES: usize = 500;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Delivery {
+    pub id: String,
+    // Canal de notificación de origen
+    pub channel: String,
+    pub url: String,
+    pub payload: serde_json::Value,
+    pub created: u64,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    // Cuándo se agotaron los reintentos
+    #[serde(default)]
+    pub failed_at: Option<u64>,
+}
+
+impl Delivery {
+    pub fn new(channel: &str, url: &str, payload: serde_json::Value) -> Self {
+        Delivery {
+            id: uuid::Uuid::new_v4().to_string(),
+            channel: channel.to_string(),
+            url: url.to_string(),
+            payload,
+            created: chrono::Utc::now().timestamp() as u64,
+            attempts: 0,
+            last_error: None,
+            failed_at: None,
+        }
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    Duration::from_secs((BASE_BACKOFF_SEC << exponent).min(MAX_BACKOFF_SEC))
+}
+
+// La clave de firma es la que tenga el canal al encolar la entrega (también al reenviar
+// a mano una fallida); no se guarda con las entregas fallidas
+pub async fn spawn_delivery(state: &AppState, delivery: Delivery) {
+    let secret = state.config.read().await.channels.iter()
+        .find(|c| c.name == delivery.channel)
+        .and_then(|c| match &c.kind {
+            ChannelKind::Webhook { secret, .. } => secret.clone(),
+            ChannelKind::Log => None,
+        });
+    tokio::spawn(deliver(state.http.clone(), state.secrets.clone(), state.failed_deliveries.clone(), secret, delivery));
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    secrets: Arc<SecretVault>,
+    failed: Arc<RwLock<Vec<Delivery>>>,
+    secret: Option<String>,
+    mut delivery: Delivery,
+) {
+    // La URL se guarda cifrada (puede llevar el token del webhook), igual que la clave de
+    // firma; sin poder descifrarlas no tiene sentido reintentar
+    let url = secrets.reveal(&delivery.url);
+    let secret = secret.as_deref().map(|s| secrets.reveal(s)).transpose();
+    let (url, secret) = match url.and_then(|url| secret.map(|secret| (url, secret))) {
+        Ok(revealed) => revealed,
+        Err(e) => {
+            delivery.last_error = Some(e.to_string());
+            return move_to_failed(&failed, delivery).await;
+        }
+    };
+    // Se firma el cuerpo exacto que se envía
+    let body = serde_json::to_vec(&delivery.payload).unwrap_or_default();
+    loop {
+        delivery.attempts += 1;
+        let mut request = client.post(&url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(secret) = &secret {
+            let timestamp = chrono::Utc::now().timestamp() as u64;
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, sign_payload(secret, timestamp, &body));
+        }
+        let result = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => {
+                if delivery.attempts > 1 {
+                    tracing::info!(channel = %delivery.channel, attempts = delivery.attempts, "📨 Notificación entregada tras reintentos");
+                }
+                return;
+            }
+            Err(e) => delivery.last_error = Some(e.to_string()),
+        }
+        if delivery.attempts >= MAX_DELIVERY_ATTEMPTS {
+            break;
+        }
+        let wait = backoff(delivery.attempts);
+        tracing::warn!(
+            channel = %delivery.channel,
+            attempt = delivery.attempts,
+            retry_in_sec = wait.as_secs(),
+            error = delivery.last_error.as_deref(),
+            "⚠️ Error enviando notificación por webhook, se reintentará"
+        );
+        tokio::time::sleep(wait).await;
+    }
+    move_to_failed(&failed, delivery).await;
+}
+
+async fn move_to_failed(failed: &RwLock<Vec<Delivery>>, mut delivery: Delivery) {
+    tracing::error!(
+        channel = %delivery.channel,
+        id = %delivery.id,
+        attempts = delivery.attempts,
+        error = delivery.last_error.as_deref(),
+        "❌ Notificación no entregada, movida a entregas fallidas"
+    );
+    record_fault(Fault::Notification);
+    delivery.failed_at = Some(chrono::Utc::now().timestamp() as u64);
+    let mut failed = failed.write().await;
+    failed.push(delivery);
+    let excess = failed.len().saturating_sub(MAX_FAILED_DELIVERIES);
+    failed.drain(..excess);
+    save_failed_deliveries(&failed);
+}
+
+// --- FIRMA DE LOS WEBHOOKS ---
+// Con `secret` en el canal, cada envío lleva X-Sentinel-Timestamp (segundos Unix del
+// intento) y X-Sentinel-Signature: "v1=" + HMAC-SHA256 en hexadecimal, con la clave del
+// canal, de "<timestamp>.<cuerpo>" (los bytes exactos del cuerpo). El receptor recalcula
+// la firma, la compara en tiempo constante y rechaza marcas de tiempo con más de
+// SIGNATURE_TOLERANCE_SEC de diferencia para evitar reenvíos. GET /api/webhooks/signing
+// describe el esquema y POST /api/webhooks/verify comprueba una firma recibida.
+
+pub const TIMESTAMP_HEADER: &str = "X-Sentinel-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Sentinel-Signature";
+pub const SIGNATURE_VERSION: &str = "v1";
+pub const SIGNATURE_TOLERANCE_SEC: u64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn payload_mac(secret: &str, timestamp: u64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC admite claves de cualquier longitud");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+// Valor de X-Sentinel-Signature
+pub fn sign_payload(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    format!("{}={:x}", SIGNATURE_VERSION, payload_mac(secret, timestamp, body).finalize().into_bytes())
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok()).collect()
+}
+
+// Comprueba una firma recibida; el error explica por qué no vale
+pub fn verify_signature(secret: &str, timestamp: u64, body: &[u8], signature: &str, now: u64) -> Result<(), &'static str> {
+    if now.abs_diff(timestamp) > SIGNATURE_TOLERANCE_SEC {
+        return Err("timestamp outside the tolerance window");
+    }
+    let digest = signature.trim()
+        .strip_prefix(SIGNATURE_VERSION)
+        .and_then(|rest| rest.strip_prefix('='))
+        .ok_or("unsupported signature version")?;
+    let digest = decode_hex(digest).ok_or("signature is not hexadecimal")?;
+    payload_mac(secret, timestamp, body).verify_slice(&digest).map_err(|_| "signature mismatch")
+}
@@ -0,0 +1,141 @@
+use crate::settings::ServerSettings;
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+// --- ALMACENAMIENTO DE OBJETOS (S3) ---
+// Con --s3-bucket y sus credenciales los robots pueden subir las capturas grandes
+// directamente al bucket (ver routes::direct_upload) con URLs prefirmadas: el servidor no
+// firma peticiones ni guarda nada en S3, solo genera las URLs (AWS Signature V4 en la
+// query string) y, al completarse la subida, descarga el objeto y lo borra. Sin
+// --s3-endpoint se usa AWS con direcciones virtual-hosted; con él (MinIO, Ceph...), el
+// bucket va en la ruta.
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(600);
+const DELETE_TIMEOUT: Duration = Duration::from_secs(30);
+// Validez de las URLs que usa el propio servidor para leer y borrar
+const INTERNAL_URL_EXPIRY_SEC: u64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub struct S3Bucket {
+    bucket: String,
+    region: String,
+    endpoint: Option<String>,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Bucket {
+    // None si las subidas directas no están configuradas
+    pub fn from_settings(settings: &ServerSettings) -> Option<S3Bucket> {
+        Some(S3Bucket {
+            bucket: settings.s3_bucket.clone()?,
+            region: settings.s3_region.clone(),
+            endpoint: settings.s3_endpoint.clone(),
+            access_key: settings.s3_access_key.clone()?,
+            secret_key: settings.s3_secret_key.clone()?,
+        })
+    }
+
+    // URL del objeto sin firmar
+    fn object_url(&self, key: &str) -> Result<Url, String> {
+        let url = match &self.endpoint {
+            Some(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), self.bucket, uri_encode(key, true)),
+            None => format!("https://{}.s3.{}.amazonaws.com/{}", self.bucket, self.region, uri_encode(key, true)),
+        };
+        Url::parse(&url).map_err(|e| format!("invalid S3 URL: {}", e))
+    }
+
+    // URL prefirmada para `method` ("PUT", "GET", "DELETE") válida `expires_sec` segundos
+    pub fn presign(&self, method: &str, key: &str, expires_sec: u64) -> Result<String, String> {
+        let url = self.object_url(key)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err("S3 URL without host".into()),
+        };
+        let datetime = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &datetime[..8];
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        // Los parámetros ya van en orden alfabético, como exige la forma canónica
+        let query = [
+            ("X-Amz-Algorithm", "AWS4-HMAC-SHA256".to_string()),
+            ("X-Amz-Credential", format!("{}/{}", self.access_key, scope)),
+            ("X-Amz-Date", datetime.clone()),
+            ("X-Amz-Expires", expires_sec.to_string()),
+            ("X-Amz-SignedHeaders", "host".to_string()),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, uri_encode(value, false)))
+        .collect::<Vec<_>>()
+        .join("&");
+        let canonical_request = format!("{}\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", method, url.path(), query, host);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+            datetime,
+            scope,
+            Sha256::digest(canonical_request.as_bytes())
+        );
+        let mut signing_key = hmac(format!("AWS4{}", self.secret_key).as_bytes(), date);
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac(&signing_key, part);
+        }
+        let signature: String = hmac(&signing_key, &string_to_sign).iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(format!("{}?{}&X-Amz-Signature={}", url, query, signature))
+    }
+
+    // Descarga un objeto; None si no existe (el robot aún no lo ha subido). Se rechaza
+    // si supera `max_bytes`
+    pub async fn fetch(&self, http: &reqwest::Client, key: &str, max_bytes: u64) -> Result<Option<Vec<u8>>, String> {
+        let url = self.presign("GET", key, INTERNAL_URL_EXPIRY_SEC)?;
+        let mut response = http.get(url).timeout(FETCH_TIMEOUT).send().await.map_err(|e| e.to_string())?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        response = response.error_for_status().map_err(|e| e.to_string())?;
+        if response.content_length().is_some_and(|len| len > max_bytes) {
+            return Err(format!("object larger than {} bytes", max_bytes));
+        }
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            if (data.len() + chunk.len()) as u64 > max_bytes {
+                return Err(format!("object larger than {} bytes", max_bytes));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        Ok(Some(data))
+    }
+
+    pub async fn delete(&self, http: &reqwest::Client, key: &str) -> Result<(), String> {
+        let url = self.presign("DELETE", key, INTERNAL_URL_EXPIRY_SEC)?;
+        http.delete(url)
+            .timeout(DELETE_TIMEOUT)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC admite claves de cualquier longitud");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Codificación de URI de SigV4: solo se dejan tal cual los caracteres no reservados (y
+// las barras de la ruta)
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,187 @@
+use crate::{
+    analysis::{
+        anomaly::MIN_HISTORY, blades::hottest_frame_blades, frame_shape, hottest_frame, image_quality::CaptureQuality, CaptureStats,
+        EvolutionPoint,
+    },
+    analyzers::find_analyzer,
+    error::AppError,
+    rules::{matching_rules, RuleFacts},
+    state::{AlertLinks, AlertRecord, AppState},
+    storage::lineage::record_derivation,
+    thresholds::{Evaluation, ThresholdLevel},
+    weather::AmbientReading,
+};
+use axum::body::Bytes;
+use std::collections::BTreeMap;
+
+// --- PIPELINE DE ANÁLISIS ---
+// Lo que se hace con cada captura ya guardada: la cadena de analizadores configurada
+// (estadísticas, anomalía, puntos calientes, cribado; ver crate::analyzers) y la
+// evaluación contra los umbrales vigentes en el instante de la captura y las reglas del
+// operador, con la alerta enriquecida (palas, clasificador). Lo comparten la subida de los robots y la
+// re-ingesta de capturas almacenadas (/api/admin/replay).
+
+pub struct CaptureInput {
+    pub turbine_token: String,
+    pub filename: String,
+    pub timestamp: u64,
+    pub angle: f32,
+    pub rotor_phase: Option<f32>,
+    pub camera_id: Option<String>,
+    pub ambient: Option<AmbientReading>,
+    pub data: Bytes,
+}
+
+#[derive(Default)]
+pub struct CaptureAnalysis {
+    pub stats: Option<CaptureStats>,
+    pub anomaly_score: Option<f32>,
+    // (alto, ancho) de los frames
+    pub shape: Option<(usize, usize)>,
+    // Puntos por frame si la pila trae ángulo o instante por frame (ya calibrados)
+    pub frames: Vec<EvolutionPoint>,
+    // Versión de calibración aplicada a las estadísticas (None = temperaturas en bruto)
+    pub calibration_version: Option<u64>,
+    // Calidad de imagen del frame más caliente (ver analysis::image_quality)
+    pub quality: Option<CaptureQuality>,
+    // Resultados de los analizadores que no tienen campo propio, por nombre; se guardan en
+    // el catálogo (CaptureRecord::analyses)
+    pub results: BTreeMap<String, serde_json::Value>,
+}
+
+pub enum Outcome {
+    // Por debajo de todos los umbrales de su zona y sin reglas cumplidas
+    Normal { zone: Option<String> },
+    // `level` es None si la alerta la crea solo una regla; `channels` reúne los del nivel
+    // y los de las reglas cumplidas
+    Alert { alert: Box<AlertRecord>, level: Option<ThresholdLevel>, channels: Vec<String> },
+}
+
+// Ejecuta los analizadores configurados, en orden (ver crate::analyzers)
+pub async fn analyze_capture(state: &AppState, input: &CaptureInput) -> Result<CaptureAnalysis, AppError> {
+    let names = state.config.read().await.analyzers.clone();
+    let mut analysis = CaptureAnalysis::default();
+    for name in &names {
+        match find_analyzer(name) {
+            Some(analyzer) => analyzer.analyze(state, input, &mut analysis).await?,
+            None => tracing::warn!(analyzer = %name, "⚠️ Analizador desconocido, se omite"),
+        }
+    }
+    Ok(analysis)
+}
+
+// Línea base de las reglas: media de las máximas del historial de la captura (misma
+// resolución)
+async fn capture_baseline(state: &AppState, input: &CaptureInput) -> Option<f32> {
+    let shape = frame_shape(&input.data);
+    let history = state.capture_history(&input.turbine_token, input.camera_id.as_deref(), input.angle, shape, input.timestamp).await;
+    (history.len() >= MIN_HISTORY).then(|| history.iter().map(|h| h.max_temp).sum::<f32>() / history.len() as f32)
+}
+
+// Escala de umbrales de la zona angular donde se tomó la captura (o la global),
+// ajustada por el perfil horario y el ambiente del sitio en ese instante, y reglas del
+// operador: la severidad final es la mayor entre el nivel y las reglas cumplidas
+pub async fn evaluate_capture(state: &AppState, input: &CaptureInput, max_temp: f32, analysis: &CaptureAnalysis) -> Result<Outcome, AppError> {
+    let anomaly_score = analysis.anomaly_score;
+    let at = chrono::DateTime::from_timestamp(input.timestamp as i64, 0).unwrap_or_else(chrono::Utc::now);
+    let config = state.config.read().await.clone();
+    let offset = state.turbine_temp_offset(&input.turbine_token).await;
+    let Evaluation { zone, level } = config
+        .effective_at(&at)
+        .shifted(offset)
+        .compensated(input.ambient.as_ref(), input.timestamp)
+        .evaluate(input.angle, max_temp);
+    let has_rules = state.rules.read().await.iter().any(|r| r.enabled);
+    let baseline = if has_rules { capture_baseline(state, input).await } else { None };
+    let facts = RuleFacts { max_temp, baseline, anomaly_score, angle: input.angle, at, config: &config };
+    let matched = matching_rules(&state.rules.read().await, &facts);
+    let severity = level.iter().map(|l| l.severity).chain(matched.iter().map(|r| r.severity)).max();
+    let Some(severity) = severity else {
+        return Ok(Outcome::Normal { zone });
+    };
+    let mut channels: Vec<String> = level.iter().flat_map(|l| l.channels.clone()).collect();
+    for channel in matched.iter().flat_map(|r| &r.channels) {
+        if !channels.contains(channel) {
+            channels.push(channel.clone());
+        }
+    }
+    let rule_names: Vec<String> = matched.iter().map(|r| r.name.clone()).collect();
+    if !rule_names.is_empty() {
+        tracing::info!(filename = %input.filename, rules = ?rule_names, "📐 Reglas de alerta cumplidas");
+    }
+
+    // Estadísticas por pala del frame más caliente, si hay máscara de rotor
+    let geometry = state.config.read().await.blade_geometry.clone();
+    let blade_imbalance = match geometry {
+        Some(geometry) => {
+            let data = input.data.clone();
+            let phase = input.rotor_phase.unwrap_or(geometry.rotor_phase_deg);
+            tokio::task::spawn_blocking(move || hottest_frame_blades(&data, &geometry, phase))
+                .await?
+                .and_then(|report| report.imbalance)
+        }
+        None => None,
+    };
+    // Tipo de fallo según el clasificador ONNX, si hay modelo configurado y la captura
+    // tiene calidad suficiente
+    let classifier_config = state.config.read().await.classifier.clone()
+        .filter(|_| config.usable_quality(analysis.quality.as_ref()));
+    let fault_prediction = match classifier_config {
+        Some(config) => {
+            let data = input.data.clone();
+            let classifier = state.classifier.clone();
+            let params = serde_json::json!({
+                "model_path": config.model_path,
+                "labels": config.labels,
+                "input_width": config.input_width,
+                "input_height": config.input_height,
+            });
+            let result = tokio::task::spawn_blocking(move || {
+                let (frame_index, frame) = hottest_frame(&data).ok_or("unreadable capture")?;
+                classifier.classify(&config, &frame).map(|prediction| (frame_index, prediction))
+            })
+            .await?;
+            match result {
+                Ok((frame_index, prediction)) => {
+                    let inputs = [(input.filename.as_str(), Some(frame_index))];
+                    record_derivation(state, "fault_classification", &inputs, params, None).await;
+                    Some(prediction)
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "⚠️ Error clasificando la captura");
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    // Frame más caliente, el que enlazan la miniatura y la matriz de la alerta
+    let data = input.data.clone();
+    let frame_index = tokio::task::spawn_blocking(move || hottest_frame(&data).map(|(index, _)| index)).await?;
+    let links = frame_index.map(|index| AlertLinks::new(state.settings.public_url.as_deref(), &input.filename, index));
+
+    let alert = Box::new(AlertRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: input.timestamp,
+        turbine_token: input.turbine_token.clone(),
+        max_temp,
+        angle: input.angle,
+        dataset_path: input.filename.clone(),
+        zone,
+        severity,
+        level: level.as_ref().map(|l| l.name.clone()),
+        ambient: input.ambient.clone(),
+        blade_imbalance,
+        fault_prediction,
+        incident_id: None,
+        starred: false,
+        frame_index,
+        links,
+        rules: rule_names,
+        confirmation: None,
+        work_order: None,
+        resolution: None,
+    });
+    Ok(Outcome::Alert { alert, level, channels })
+}
@@ -0,0 +1,126 @@
+use crate::{
+    analysis::with_frames,
+    i18n::Localized,
+    notify::{raise_device_alert, DeviceAlert},
+    state::AppState,
+    storage::quality::{record_quality_event, QualityEventKind},
+};
+use serde::{Deserialize, Serialize};
+
+// --- LÍMITES DE PLAUSIBILIDAD DE LAS TEMPERATURAS ---
+// Tras un fallo de firmware el robot puede mandar kelvin o cuentas crudas del ADC, que
+// envenenan las tendencias. Con temperature_bounds configurado, una captura con algún
+// frame fuera de [min_temp, max_temp] (°C) no entra en el catálogo ni en el pipeline de
+// alertas: pasa a la cuarentena (ver storage::quarantine) para revisarla, con un evento
+// de calidad de datos y, si se pide, un aviso a device_alert_channels.
+
+fn default_min_temp() -> f32 {
+    -40.0
+}
+
+fn default_max_temp() -> f32 {
+    400.0
+}
+
+fn default_alert() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TemperatureBounds {
+    #[serde(default = "default_min_temp")]
+    pub min_temp: f32,
+    #[serde(default = "default_max_temp")]
+    pub max_temp: f32,
+    // Además del evento de calidad, aviso a device_alert_channels
+    #[serde(default = "default_alert")]
+    pub alert: bool,
+}
+
+impl TemperatureBounds {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.min_temp.is_finite() || !self.max_temp.is_finite() || self.min_temp >= self.max_temp {
+            return Err("temperature_bounds: min_temp must be lower than max_temp".into());
+        }
+        Ok(())
+    }
+}
+
+// Frames fuera de los límites y los extremos que tenían
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Implausible {
+    pub frames: Vec<usize>,
+    pub total_frames: usize,
+    pub min_temp: f32,
+    pub max_temp: f32,
+}
+
+// Frames con algún píxel fuera de los límites (los NaN no cuentan); None si todo es plausible
+pub fn implausible_frames(bytes: &[u8], bounds: &TemperatureBounds) -> Option<Implausible> {
+    with_frames(bytes, |frames| {
+        let mut result = Implausible {
+            frames: Vec::new(),
+            total_frames: frames.len_of(ndarray::Axis(0)),
+            min_temp: f32::INFINITY,
+            max_temp: f32::NEG_INFINITY,
+        };
+        for (frame_index, frame) in frames.outer_iter().enumerate() {
+            let (min, max) = frame.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &v| (min.min(v), max.max(v)));
+            result.min_temp = result.min_temp.min(min);
+            result.max_temp = result.max_temp.max(max);
+            if min < bounds.min_temp || max > bounds.max_temp {
+                result.frames.push(frame_index);
+            }
+        }
+        result
+    })
+    .filter(|result| !result.frames.is_empty())
+}
+
+// Evento de calidad y aviso de una subida apartada por temperaturas implausibles
+pub async fn report_implausible(state: &AppState, turbine_token: &str, quarantine_id: &str, bounds: &TemperatureBounds, implausible: &Implausible) {
+    tracing::warn!(
+        %turbine_token,
+        %quarantine_id,
+        frames = implausible.frames.len(),
+        min_temp = implausible.min_temp,
+        max_temp = implausible.max_temp,
+        "🚧 Captura en cuarentena: temperaturas fuera de los límites plausibles"
+    );
+    record_quality_event(turbine_token, QualityEventKind::ImplausibleTemperature {
+        quarantine_id: quarantine_id.to_string(),
+        frames: implausible.frames.len(),
+        min_temp: implausible.min_temp,
+        max_temp: implausible.max_temp,
+    });
+    if bounds.alert {
+        raise_device_alert(state, DeviceAlert::new(
+            turbine_token,
+            "implausible_temperature",
+            Localized::new(
+                format!(
+                    "Upload {} from {} has {} of {} frames outside {:.0}..{:.0} °C (min {:.1}, max {:.1}); check the robot firmware and units",
+                    quarantine_id,
+                    turbine_token,
+                    implausible.frames.len(),
+                    implausible.total_frames,
+                    bounds.min_temp,
+                    bounds.max_temp,
+                    implausible.min_temp,
+                    implausible.max_temp,
+                ),
+                format!(
+                    "La subida {} de {} tiene {} de {} frames fuera de {:.0}..{:.0} °C (mín. {:.1}, máx. {:.1}); revisa el firmware y las unidades del robot",
+                    quarantine_id,
+                    turbine_token,
+                    implausible.frames.len(),
+                    implausible.total_frames,
+                    bounds.min_temp,
+                    bounds.max_temp,
+                    implausible.min_temp,
+                    implausible.max_temp,
+                ),
+            ),
+        )).await;
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{
+    i18n::Locale,
+    state::AppState,
+    storage::push::{save_push_registry, PushPreferences, PushRegistry},
+};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::{Mutex, RwLock};
+
+// --- PUSH A LA APP MÓVIL (FCM) ---
+// Con --fcm-credentials (JSON de cuenta de servicio de Firebase) las alertas críticas y
+// las desconexiones de turbinas se envían por Firebase Cloud Messaging (API HTTP v1) a
+// los dispositivos registrados de cada usuario, según sus preferencias. Es un aviso de
+// mejor esfuerzo: no se reintenta, y los tokens que FCM da por caducados se eliminan.
+
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".into()
+}
+
+// Campos usados del JSON de cuenta de servicio
+#[derive(Deserialize)]
+struct ServiceAccount {
+    project_id: String,
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct TokenClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+pub struct FcmClient {
+    account: ServiceAccount,
+    key: EncodingKey,
+    api_url: String,
+    // Token OAuth vigente y su caducidad
+    access_token: Mutex<Option<(String, u64)>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    // Para que la app abra la pantalla adecuada (tipo, turbina, id de alerta...)
+    pub data: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PushTopic {
+    CriticalAlert,
+    Offline,
+}
+
+impl PushTopic {
+    fn wanted_by(self, preferences: &PushPreferences) -> bool {
+        match self {
+            PushTopic::CriticalAlert => preferences.critical_alerts,
+            PushTopic::Offline => preferences.offline,
+        }
+    }
+}
+
+enum PushError {
+    // El token ya no es válido (app desinstalada...)
+    Unregistered,
+    Other(String),
+}
+
+impl FcmClient {
+    pub fn load(path: &std::path::Path, api_url: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let account: ServiceAccount = serde_json::from_str(&json).map_err(|e| format!("invalid service account: {}", e))?;
+        let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes()).map_err(|e| format!("invalid private_key: {}", e))?;
+        Ok(FcmClient {
+            account,
+            key,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            access_token: Mutex::new(None),
+        })
+    }
+
+    pub fn project_id(&self) -> &str {
+        &self.account.project_id
+    }
+
+    // Token OAuth de la cuenta de servicio, renovado un minuto antes de caducar
+    async fn access_token(&self, http: &reqwest::Client) -> Result<String, String> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut cached = self.access_token.lock().await;
+        if let Some((token, expires)) = cached.as_ref()
+            && *expires > now + 60
+        {
+            return Ok(token.clone());
+        }
+        let claims = TokenClaims {
+            iss: &self.account.client_email,
+            scope: FCM_SCOPE,
+            aud: &self.account.token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key).map_err(|e| e.to_string())?;
+        let response: TokenResponse = http.post(&self.account.token_uri)
+            .timeout(PUSH_TIMEOUT)
+            .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", assertion.as_str())])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        *cached = Some((response.access_token.clone(), now + response.expires_in));
+        Ok(response.access_token)
+    }
+
+    async fn send(&self, http: &reqwest::Client, device_token: &str, message: &PushMessage) -> Result<(), PushError> {
+        let access_token = self.access_token(http).await.map_err(PushError::Other)?;
+        let body = serde_json::json!({
+            "message": {
+                "token": device_token,
+                "notification": { "title": message.title, "body": message.body },
+                "data": message.data,
+                "android": { "priority": "high" },
+            }
+        });
+        let url = format!("{}/v1/projects/{}/messages:send", self.api_url, self.account.project_id);
+        let response = http.post(url)
+            .timeout(PUSH_TIMEOUT)
+            .bearer_auth(access_token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| PushError::Other(e.to_string()))?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            reqwest::StatusCode::NOT_FOUND => Err(PushError::Unregistered),
+            status => Err(PushError::Other(format!("{}: {}", status, response.text().await.unwrap_or_default()))),
+        }
+    }
+}
+
+// Envía el mensaje a los dispositivos de los usuarios que siguen la turbina y quieren
+// este tipo de aviso, redactado en el idioma de cada uno; los envíos se hacen en segundo
+// plano
+pub async fn push_to_subscribers(state: &AppState, turbine_token: &str, topic: PushTopic, message: impl Fn(Locale) -> PushMessage) {
+    let Some(fcm) = state.fcm.clone() else { return };
+    let mut tokens: HashMap<Locale, Vec<String>> = HashMap::new();
+    {
+        let registry = state.push.read().await;
+        for device in &registry.devices {
+            let preferences = registry.preferences_of(&device.user);
+            if topic.wanted_by(&preferences) && preferences.follows(turbine_token) {
+                tokens.entry(preferences.locale).or_default().push(device.token.clone());
+            }
+        }
+    }
+    for (locale, tokens) in tokens {
+        tokio::spawn(deliver(fcm.clone(), state.http.clone(), state.push.clone(), tokens, message(locale)));
+    }
+}
+
+async fn deliver(fcm: Arc<FcmClient>, http: reqwest::Client, registry: Arc<RwLock<PushRegistry>>, tokens: Vec<String>, message: PushMessage) {
+    let mut stale = Vec::new();
+    let mut sent = 0;
+    for token in tokens {
+        match fcm.send(&http, &token, &message).await {
+            Ok(()) => sent += 1,
+            Err(PushError::Unregistered) => stale.push(token),
+            Err(PushError::Other(e)) => tracing::warn!(error = %e, "⚠️ Error enviando notificación push"),
+        }
+    }
+    tracing::info!(sent, title = %message.title, "📱 Notificación push enviada");
+    if !stale.is_empty() {
+        tracing::info!(count = stale.len(), "📱 Se eliminan dispositivos push no registrados en FCM");
+        let mut registry = registry.write().await;
+        registry.devices.retain(|d| !stale.contains(&d.token));
+        save_push_registry(&registry);
+    }
+}
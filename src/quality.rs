@@ -0,0 +1,218 @@
+use crate::{
+    schedule::ScanWindow,
+    state::{AppState, ClockSkew, RemoteConfig},
+    storage::quality::{load_quality_events, QualityEvent, QualityEventKind},
+};
+use chrono::DateTime;
+use serde::Serialize;
+
+// --- INFORME DE CALIDAD DE DATOS ---
+// Completitud de los datos de una turbina en un periodo, para los auditores del
+// propietario: ventanas de escaneo sin capturas, huecos de heartbeat, desfase del reloj
+// del robot y subidas ilegibles o corruptas.
+//
+// La cadencia esperada se calcula con la configuración vigente de la turbina (intervalo
+// de escaneo y planificaciones); el periodo se divide en ranuras del doble del intervalo
+// (mínimo un minuto) y una ranura sin ninguna captura cuenta como perdida.
+
+// Una ranura cubre este múltiplo del intervalo de escaneo (margen para el barrido)
+const SLOT_FACTOR: u64 = 2;
+const MIN_SLOT_SEC: u64 = 60;
+// Periodos que se listan como máximo en cada apartado (los totales siempre son completos)
+const MAX_LISTED_SPANS: usize = 100;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Span {
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Serialize, Default)]
+pub struct ScanCoverage {
+    pub captures: usize,
+    pub expected_slots: u64,
+    pub missed_slots: u64,
+    // Capturas recibidas en las ranuras esperadas (1.0 = ninguna perdida)
+    pub completeness: Option<f64>,
+    pub missed_windows: Vec<Span>,
+    // Barridos con huecos de ángulo (solo con scan_coverage configurado)
+    pub incomplete_sweeps: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct HeartbeatQuality {
+    pub gaps: usize,
+    pub offline_sec: u64,
+    pub longest_gap_sec: u64,
+    pub currently_offline: bool,
+    pub gap_list: Vec<Span>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ClockQuality {
+    // Última medición (None si el robot nunca ha indicado su hora)
+    pub current: Option<ClockSkew>,
+    // Veces que el reloj pasó a estar desincronizado en el periodo
+    pub flagged_episodes: usize,
+    // Mayor desfase (en valor absoluto) registrado al cambiar de estado
+    pub max_offset_sec: Option<i64>,
+    // Capturas recibidas con el reloj desincronizado
+    pub captures_with_skew: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct UploadQuality {
+    // Subidas que no contenían frames legibles
+    pub corrupt_uploads: usize,
+    // Capturas guardadas que fallaron el escaneo de integridad
+    pub integrity_errors: usize,
+    pub corrupt_files: Vec<String>,
+    // Subidas rechazadas antes de guardarse (formato o resolución no válidos)
+    pub rejected_uploads: usize,
+    // Episodios en que el robot superó el límite de subidas por minuto
+    pub flood_episodes: usize,
+    // Subidas apartadas por temperaturas implausibles (ver crate::plausibility)
+    pub quarantined_uploads: usize,
+}
+
+#[derive(Serialize)]
+pub struct QualityReport {
+    pub turbine_token: String,
+    pub from: u64,
+    pub to: u64,
+    pub generated: u64,
+    pub scans: ScanCoverage,
+    pub heartbeats: HeartbeatQuality,
+    pub clock: ClockQuality,
+    pub uploads: UploadQuality,
+}
+
+// Intervalo de escaneo esperado en un instante (None = no se espera escanear)
+fn expected_interval(config: &RemoteConfig, turbine_token: &str, at: u64) -> Option<u64> {
+    if !config.system_enabled {
+        return None;
+    }
+    let at = DateTime::from_timestamp(at as i64, 0)?;
+    match config.scan_window(turbine_token, &at) {
+        ScanWindow::Unscheduled => Some(config.scan_wait_time_sec),
+        ScanWindow::Open(schedule) => Some(schedule.interval_sec.unwrap_or(config.scan_wait_time_sec)),
+        ScanWindow::Closed => None,
+    }
+}
+
+fn push_span(spans: &mut Vec<Span>, from: u64, to: u64) {
+    match spans.last_mut() {
+        Some(last) if last.to == from => last.to = to,
+        _ => spans.push(Span { from, to }),
+    }
+}
+
+// Ranuras esperadas y perdidas en [from, to); `captures` en orden cronológico
+fn scan_coverage(config: &RemoteConfig, turbine_token: &str, from: u64, to: u64, captures: &[u64]) -> ScanCoverage {
+    let mut coverage = ScanCoverage { captures: captures.len(), ..Default::default() };
+    let mut t = from;
+    while t < to {
+        let Some(interval) = expected_interval(config, turbine_token, t) else {
+            // Ventana cerrada: se vuelve a mirar al minuto siguiente
+            t = (t - t % 60 + 60).min(to);
+            continue;
+        };
+        let end = t.saturating_add(interval.saturating_mul(SLOT_FACTOR).max(MIN_SLOT_SEC)).min(to);
+        coverage.expected_slots += 1;
+        let first = captures.partition_point(|&c| c < t);
+        if captures.get(first).is_none_or(|&c| c >= end) {
+            coverage.missed_slots += 1;
+            push_span(&mut coverage.missed_windows, t, end);
+        }
+        t = end;
+    }
+    coverage.completeness = (coverage.expected_slots > 0)
+        .then(|| 1.0 - coverage.missed_slots as f64 / coverage.expected_slots as f64);
+    coverage.missed_windows.truncate(MAX_LISTED_SPANS);
+    coverage
+}
+
+fn heartbeat_quality(events: &[QualityEvent], last_heartbeat: Option<u64>, offline_after: u64, from: u64, to: u64, now: u64) -> HeartbeatQuality {
+    let mut gaps: Vec<Span> = events.iter()
+        .filter_map(|e| match e.kind {
+            QualityEventKind::HeartbeatGap { from: start } => Some(Span { from: start.max(from), to: e.timestamp }),
+            _ => None,
+        })
+        .collect();
+    // Hueco aún abierto si la turbina no reporta
+    let currently_offline = last_heartbeat.is_none_or(|t| now > t.saturating_add(offline_after));
+    if currently_offline
+        && let Some(last) = last_heartbeat
+        && last < to
+    {
+        gaps.push(Span { from: last.max(from), to: now.min(to) });
+    }
+    HeartbeatQuality {
+        gaps: gaps.len(),
+        offline_sec: gaps.iter().map(|g| g.to.saturating_sub(g.from)).sum(),
+        longest_gap_sec: gaps.iter().map(|g| g.to.saturating_sub(g.from)).max().unwrap_or(0),
+        currently_offline,
+        gap_list: gaps.into_iter().take(MAX_LISTED_SPANS).collect(),
+    }
+}
+
+pub async fn build_quality_report(state: &AppState, turbine_token: &str, from: u64, to: u64) -> std::io::Result<QualityReport> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let max_skew = state.settings.max_clock_skew_sec;
+    let mut captures = Vec::new();
+    let mut clock = ClockQuality { current: state.clock_skew.read().await.get(turbine_token).cloned(), ..Default::default() };
+    let mut uploads = UploadQuality::default();
+    let mut incomplete_sweeps = 0;
+    for record in state.catalog.read().await.iter().filter(|r| r.turbine_token == turbine_token && (from..=to).contains(&r.timestamp)) {
+        captures.push(record.timestamp);
+        if record.clock_skew_sec.is_some_and(|s| s.unsigned_abs() > max_skew) {
+            clock.captures_with_skew += 1;
+        }
+        if record.integrity_error.is_some() {
+            uploads.integrity_errors += 1;
+            uploads.corrupt_files.push(record.filename.clone());
+        }
+    }
+    captures.sort_unstable();
+
+    let token = turbine_token.to_string();
+    let events = tokio::task::spawn_blocking(move || load_quality_events(&token, from, to)).await??;
+    for event in &events {
+        match &event.kind {
+            QualityEventKind::ClockSkew { offset_sec, flagged } => {
+                clock.flagged_episodes += *flagged as usize;
+                if clock.max_offset_sec.is_none_or(|m| offset_sec.unsigned_abs() > m.unsigned_abs()) {
+                    clock.max_offset_sec = Some(*offset_sec);
+                }
+            }
+            QualityEventKind::CorruptUpload { filename } => {
+                uploads.corrupt_uploads += 1;
+                if !uploads.corrupt_files.contains(filename) {
+                    uploads.corrupt_files.push(filename.clone());
+                }
+            }
+            QualityEventKind::RejectedUpload { .. } => uploads.rejected_uploads += 1,
+            QualityEventKind::IngestFlood { .. } => uploads.flood_episodes += 1,
+            QualityEventKind::IncompleteScan { .. } => incomplete_sweeps += 1,
+            QualityEventKind::ImplausibleTemperature { .. } => uploads.quarantined_uploads += 1,
+            QualityEventKind::HeartbeatGap { .. } => {}
+        }
+    }
+    uploads.corrupt_files.truncate(MAX_LISTED_SPANS);
+
+    let last_heartbeat = state.turbine_status.read().await.get(turbine_token).map(|s| s.last_update);
+    let config = state.config_for_turbine(turbine_token).await;
+    let token = turbine_token.to_string();
+    let mut scans = tokio::task::spawn_blocking(move || scan_coverage(&config, &token, from, to, &captures)).await?;
+    scans.incomplete_sweeps = incomplete_sweeps;
+    Ok(QualityReport {
+        turbine_token: turbine_token.to_string(),
+        from,
+        to,
+        generated: now,
+        scans,
+        heartbeats: heartbeat_quality(&events, last_heartbeat, state.offline_after(turbine_token).await, from, to, now),
+        clock,
+        uploads,
+    })
+}
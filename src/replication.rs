@@ -0,0 +1,203 @@
+use crate::{
+    routes::replication::ReplicatedConfig,
+    state::{trim_alerts, AlertRecord, AppState},
+    storage::{
+        alert_log::append_alerts,
+        catalog::{save_catalog, sha256_hex, CaptureRecord},
+        encode_capture,
+        paths::{check_file_name, safe_resolve},
+        replication::{load_replication_cursor, save_replication_cursor, ReplicationCursor},
+        storage_root, write_durable,
+    },
+};
+use serde::de::DeserializeOwned;
+use std::{cmp::Reverse, collections::HashSet, sync::Arc, time::Duration};
+
+// --- INSTANCIA SECUNDARIA EN CALIENTE ---
+// Con --replicate-from (y el --replication-token de la primaria) esta instancia copia de
+// la primaria cada --replication-interval-sec lo nuevo desde su último punto (ver
+// storage::replication): la configuración si cambió de versión, las capturas por orden
+// de llegada, verificando su SHA-256 antes de catalogarlas, y las alertas. Si cae el
+// gateway del sitio basta con apuntar los robots y el dashboard a la secundaria, que tiene
+// el historial hasta la última sincronización. Las alertas copiadas no se evalúan ni se
+// notifican de nuevo: eso ya lo hizo la primaria.
+
+const REPLICATION_TIMEOUT: Duration = Duration::from_secs(120);
+// Capturas o alertas por petición
+const BATCH: usize = 500;
+const MIN_INTERVAL_SEC: u64 = 10;
+
+struct Primary {
+    http: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl Primary {
+    async fn get(&self, path: &str) -> Result<reqwest::Response, String> {
+        self.http.get(format!("{}{}", self.url, path))
+            .bearer_auth(&self.token)
+            .timeout(REPLICATION_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        self.get(path).await?
+            .error_for_status()
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Default)]
+struct SyncSummary {
+    config_version: Option<u64>,
+    captures: usize,
+    alerts: usize,
+}
+
+pub fn spawn_replication(state: &Arc<AppState>) {
+    let Some(url) = state.settings.replicate_from.as_deref() else { return };
+    let Some(token) = state.settings.replication_token.clone().filter(|t| !t.is_empty()) else {
+        tracing::warn!("⚠️ Replicación desactivada: --replicate-from necesita --replication-token");
+        return;
+    };
+    let primary = Primary { http: state.http.clone(), url: url.trim_end_matches('/').to_string(), token };
+    let interval = Duration::from_secs(state.settings.replication_interval_sec.max(MIN_INTERVAL_SEC));
+    tracing::info!(primary = %primary.url, "🔁 Instancia secundaria: replicando desde la primaria");
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut cursor = load_replication_cursor();
+        loop {
+            let result = sync(&state, &primary, &mut cursor).await;
+            // El punto avanza con lo que se llegó a copiar aunque la sincronización falle
+            save_replication_cursor(&cursor);
+            match result {
+                Ok(summary) if summary.config_version.is_none() && summary.captures == 0 && summary.alerts == 0 => {}
+                Ok(summary) => tracing::info!(
+                    config_version = summary.config_version,
+                    captures = summary.captures,
+                    alerts = summary.alerts,
+                    "🔁 Datos replicados desde la primaria"
+                ),
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error replicando desde la primaria, se reintentará"),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+async fn sync(state: &AppState, primary: &Primary, cursor: &mut ReplicationCursor) -> Result<SyncSummary, String> {
+    let mut summary = SyncSummary::default();
+    let replicated: ReplicatedConfig = primary.get_json("/api/replication/config").await?;
+    if replicated.version != cursor.config_version {
+        state.apply_config(replicated.config, None, "replication", None).await.map_err(|e| e.to_string())?;
+        cursor.config_version = replicated.version;
+        summary.config_version = Some(replicated.version);
+    }
+    summary.captures = sync_captures(state, primary, cursor).await?;
+    summary.alerts = sync_alerts(state, primary, cursor).await?;
+    cursor.last_sync = Some(chrono::Utc::now().timestamp() as u64);
+    Ok(summary)
+}
+
+// Descarga y guarda una captura; Ok(false) si ya no está en la primaria o no coincide
+// con su checksum (no se reintenta: se registra y se sigue con las demás)
+async fn fetch_capture(state: &AppState, primary: &Primary, record: &CaptureRecord) -> Result<bool, String> {
+    check_file_name(&record.filename).map_err(|e| format!("{}: {}", record.filename, e))?;
+    let response = primary.get(&format!("/api/replication/captures/{}", record.filename)).await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        tracing::warn!(filename = %record.filename, "⚠️ Replicación: la captura ya no está en la primaria");
+        return Ok(false);
+    }
+    let data = response.error_for_status()
+        .map_err(|e| e.to_string())?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?;
+    if sha256_hex(&data) != record.sha256 {
+        tracing::error!(filename = %record.filename, "❌ Replicación: checksum distinto del catálogo de la primaria, se descarta");
+        return Ok(false);
+    }
+    let path = safe_resolve(&storage_root(), &record.filename).map_err(|e| e.to_string())?;
+    let zstd_level = state.settings.zstd_level;
+    tokio::task::spawn_blocking(move || write_durable(&path, &encode_capture(&data, zstd_level)?))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// Añade las capturas copiadas al catálogo, que se mantiene en orden cronológico
+async fn catalog_copies(state: &AppState, copies: Vec<CaptureRecord>) {
+    if copies.is_empty() {
+        return;
+    }
+    let mut catalog = state.catalog.write().await;
+    catalog.extend(copies);
+    catalog.sort_by_key(|r| r.timestamp);
+    save_catalog(&catalog);
+}
+
+async fn sync_captures(state: &AppState, primary: &Primary, cursor: &mut ReplicationCursor) -> Result<usize, String> {
+    let mut copied = 0;
+    loop {
+        let mut path = format!("/api/replication/captures?since={}&limit={}", cursor.captures_since, BATCH);
+        if let Some(after) = &cursor.captures_after {
+            path.push_str(&format!("&after={}", after));
+        }
+        let records: Vec<CaptureRecord> = primary.get_json(&path).await?;
+        let full_batch = records.len() == BATCH;
+        let known: HashSet<String> = state.catalog.read().await.iter().map(|r| r.filename.clone()).collect();
+        let mut copies = Vec::new();
+        for record in records {
+            if !known.contains(&record.filename) {
+                match fetch_capture(state, primary, &record).await {
+                    // Guardada en la raíz aunque en la primaria esté archivada; la foto visible no se replica
+                    Ok(true) => copies.push(CaptureRecord { archived: false, photo: None, ..record.clone() }),
+                    Ok(false) => {}
+                    Err(e) => {
+                        catalog_copies(state, copies).await;
+                        return Err(e);
+                    }
+                }
+            }
+            cursor.captures_since = record.arrival();
+            cursor.captures_after = Some(record.filename);
+        }
+        copied += copies.len();
+        catalog_copies(state, copies).await;
+        if !full_batch {
+            return Ok(copied);
+        }
+    }
+}
+
+async fn sync_alerts(state: &AppState, primary: &Primary, cursor: &mut ReplicationCursor) -> Result<usize, String> {
+    let mut copied = 0;
+    loop {
+        let path = format!("/api/replication/alerts?since={}&limit={}", cursor.alerts_since, BATCH);
+        let alerts: Vec<AlertRecord> = primary.get_json(&path).await?;
+        let full_batch = alerts.len() == BATCH;
+        let start = cursor.alerts_since;
+        cursor.alerts_since = alerts.iter().map(|a| a.timestamp).max().unwrap_or(start).max(start);
+        // Las del segundo límite pueden repetirse; en el histórico gana la última línea de
+        // cada id, así que solo se filtran las que ya están en memoria
+        let mut recent = state.alerts.write().await;
+        let mut known: HashSet<String> = recent.iter().map(|a| a.id.clone()).collect();
+        let copies: Vec<AlertRecord> = alerts.into_iter().filter(|a| known.insert(a.id.clone())).collect();
+        append_alerts(&copies);
+        copied += copies.len();
+        recent.extend(copies);
+        recent.make_contiguous().sort_by_key(|a| Reverse(a.timestamp));
+        trim_alerts(&mut recent);
+        drop(recent);
+        if !full_batch || cursor.alerts_since == start {
+            return Ok(copied);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::state::AppState;
+
+// --- ENVÍO DE INFORMES POR CORREO ---
+// SMTP con TLS (--smtp-server "host" o "host:puerto", 465 por defecto) y el informe como
+// adjunto. Necesita la feature `smtp`; sin ella los informes solo se pueden guardar.
+
+pub struct Attachment {
+    pub name: String,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+pub async fn send_report(state: &AppState, to: &[String], subject: &str, body: &str, attachment: Attachment) -> Result<(), String> {
+    let Some(server) = state.settings.smtp_server.as_deref() else {
+        return Err("email delivery disabled: set SENTINEL_SMTP_SERVER".into());
+    };
+    smtp::send(state, server, to, subject, body, attachment).await
+}
+
+#[cfg(feature = "smtp")]
+mod smtp {
+    use super::Attachment;
+    use crate::state::AppState;
+    use lettre::{
+        message::{header::ContentType, Attachment as MailAttachment, Mailbox, MultiPart, SinglePart},
+        transport::smtp::authentication::Credentials,
+        AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    };
+
+    const DEFAULT_PORT: u16 = 465;
+
+    pub async fn send(
+        state: &AppState,
+        server: &str,
+        to: &[String],
+        subject: &str,
+        body: &str,
+        attachment: Attachment,
+    ) -> Result<(), String> {
+        let settings = &state.settings;
+        let (host, port) = match server.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| format!("invalid port in '{}'", server))?),
+            None => (server, DEFAULT_PORT),
+        };
+        let mut builder = Message::builder()
+            .from(settings.smtp_from.parse::<Mailbox>().map_err(|e| format!("invalid sender address: {}", e))?)
+            .subject(subject);
+        for address in to {
+            builder = builder.to(address.parse::<Mailbox>().map_err(|e| format!("invalid address '{}': {}", address, e))?);
+        }
+        let content_type = ContentType::parse(attachment.content_type).map_err(|e| e.to_string())?;
+        let message = builder
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(body.to_string()))
+                    .singlepart(MailAttachment::new(attachment.name).body(attachment.bytes, content_type)),
+            )
+            .map_err(|e| e.to_string())?;
+        let mut transport = AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(|e| e.to_string())?.port(port);
+        if let (Some(user), Some(password)) = (&settings.smtp_user, &settings.smtp_password) {
+            transport = transport.credentials(Credentials::new(user.clone(), password.clone()));
+        }
+        transport.build().send(message).await.map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "smtp"))]
+mod smtp {
+    use super::Attachment;
+    use crate::state::AppState;
+
+    pub async fn send(
+        _state: &AppState,
+        _server: &str,
+        _to: &[String],
+        _subject: &str,
+        _body: &str,
+        _attachment: Attachment,
+    ) -> Result<(), String> {
+        Err("server built without the `smtp` feature".into())
+    }
+}
@@ -0,0 +1,508 @@
+use crate::{
+    i18n::Locale,
+    schedule::{format_timestamp, parse_duration, CronSchedule},
+    sessions::{compare_sessions, session_coverage, session_of, AngleDelta},
+    state::AppState,
+    storage::{
+        alert_log::load_alert_log,
+        catalog::CaptureRecord,
+        lineage::record_derivation,
+        reports::write_report,
+    },
+    thresholds::Severity,
+    units::TempUnit,
+};
+use chrono::{Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+pub mod mail;
+pub mod pdf;
+
+// --- INFORMES PROGRAMADOS ---
+// Planificaciones cron (report_schedules en la configuración o /api/schedules) que
+// generan informes periódicos sin depender de un cron externo que llame a la API:
+//   - digest: capturas y alertas de cada turbina en el periodo (period, "24h" por defecto)
+//   - session: última sesión de escaneo de cada turbina en el periodo, con su cobertura
+//     y la comparación con la sesión anterior
+// Cada informe sale en JSON o en PDF y se guarda en cloud_storage/reports/, se envía por
+// correo (--smtp-server) o ambas cosas. El cron se evalúa en UTC, una vez por minuto;
+// las fechas del informe van en display_timezone, las temperaturas en display_units y el
+// texto del PDF y del correo en el idioma de la planificación (locale).
+
+// Diferencia máxima de ángulo para emparejar las sesiones, como en /api/sessions/compare
+const SESSION_ANGLE_TOLERANCE: f32 = 2.0;
+// Ángulos con mayor subida que se listan por turbina
+const REPORT_INCREASES: usize = 3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportKind {
+    Digest,
+    Session,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "application/json",
+            ReportFormat::Pdf => "application/pdf",
+        }
+    }
+}
+
+fn default_period() -> String {
+    "24h".into()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReportSchedule {
+    // Asignado por /api/schedules; las de la configuración se identifican por el nombre
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    pub name: String,
+    // Cron de 5 campos en UTC ("0 6 * * 1" = los lunes a las 6:00)
+    pub cron: String,
+    pub kind: ReportKind,
+    #[serde(default)]
+    pub format: ReportFormat,
+    // Turbinas incluidas; vacío = todas
+    #[serde(default)]
+    pub turbines: Vec<String>,
+    // Periodo que cubre el informe, hacia atrás desde su generación
+    #[serde(default = "default_period")]
+    pub period: String,
+    // Guardar en cloud_storage/reports/
+    #[serde(default = "default_true")]
+    pub store: bool,
+    // Destinatarios del correo (requiere --smtp-server)
+    #[serde(default)]
+    pub email: Vec<String>,
+    // Idioma del PDF y del correo (el JSON lleva siempre las mismas claves)
+    #[serde(default)]
+    pub locale: Locale,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl ReportSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("report schedule: name must not be empty".into());
+        }
+        self.cron.parse::<CronSchedule>().map_err(|e| format!("report schedule '{}': {}", self.name, e))?;
+        match parse_duration(&self.period) {
+            Ok(0) | Err(_) => return Err(format!("report schedule '{}': invalid period '{}'", self.name, self.period)),
+            Ok(_) => {}
+        }
+        if !self.store && self.email.is_empty() {
+            return Err(format!("report schedule '{}': set store or at least one email recipient", self.name));
+        }
+        if let Some(address) = self.email.iter().find(|a| !a.contains('@')) {
+            return Err(format!("report schedule '{}': invalid email address '{}'", self.name, address));
+        }
+        Ok(())
+    }
+
+    fn includes(&self, turbine_token: &str) -> bool {
+        self.turbines.is_empty() || self.turbines.iter().any(|t| t == turbine_token)
+    }
+}
+
+// --- CONTENIDO DE LOS INFORMES ---
+
+#[derive(Serialize)]
+pub struct DigestTurbine {
+    pub turbine_token: String,
+    pub captures: usize,
+    pub max_temp: Option<f32>,
+    pub alerts: usize,
+    pub critical_alerts: usize,
+}
+
+#[derive(Serialize)]
+pub struct CameraCoverage {
+    pub camera_id: Option<String>,
+    pub coverage: f32,
+    pub gaps: usize,
+}
+
+#[derive(Serialize)]
+pub struct SessionSummary {
+    pub turbine_token: String,
+    pub start: u64,
+    pub end: u64,
+    pub captures: usize,
+    pub max_temp: Option<f32>,
+    pub coverage: Vec<CameraCoverage>,
+    // Comparación con la sesión anterior (b − a), si la hay
+    pub previous_start: Option<u64>,
+    pub mean_max_delta: Option<f32>,
+    pub largest_increases: Vec<AngleDelta>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ReportBody {
+    Digest { turbines: Vec<DigestTurbine> },
+    Session { sessions: Vec<SessionSummary> },
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub schedule: String,
+    pub generated: u64,
+    pub from: u64,
+    pub to: u64,
+    pub timezone: String,
+    pub units: TempUnit,
+    pub locale: Locale,
+    #[serde(flatten)]
+    pub body: ReportBody,
+    // Capturas en las que se basa (para el linaje, no sale en el informe)
+    #[serde(skip)]
+    pub sources: Vec<String>,
+}
+
+fn raise_max(current: &mut Option<f32>, value: Option<f32>) {
+    if let Some(value) = value.filter(|v| v.is_finite())
+        && current.is_none_or(|m| value > m)
+    {
+        *current = Some(value);
+    }
+}
+
+async fn digest(
+    state: &AppState,
+    schedule: &ReportSchedule,
+    from: u64,
+    to: u64,
+    unit: TempUnit,
+    sources: &mut Vec<String>,
+) -> Result<ReportBody, String> {
+    let mut turbines: BTreeMap<String, DigestTurbine> = BTreeMap::new();
+    for record in state.catalog.read().await.iter() {
+        if record.timestamp < from || record.timestamp > to || !schedule.includes(&record.turbine_token) {
+            continue;
+        }
+        sources.push(record.filename.clone());
+        let turbine = turbines.entry(record.turbine_token.clone()).or_insert_with(|| empty_digest(&record.turbine_token));
+        turbine.captures += 1;
+        raise_max(&mut turbine.max_temp, record.max_temp.map(|t| unit.temp(t)));
+    }
+    let alerts = tokio::task::spawn_blocking(load_alert_log).await.map_err(|e| e.to_string())?.map_err(|e| e.to_string())?;
+    for alert in alerts.iter().filter(|a| a.timestamp >= from && a.timestamp <= to && schedule.includes(&a.turbine_token)) {
+        let turbine = turbines.entry(alert.turbine_token.clone()).or_insert_with(|| empty_digest(&alert.turbine_token));
+        turbine.alerts += 1;
+        if alert.severity == Severity::Critical {
+            turbine.critical_alerts += 1;
+        }
+        raise_max(&mut turbine.max_temp, Some(unit.temp(alert.max_temp)));
+    }
+    Ok(ReportBody::Digest { turbines: turbines.into_values().collect() })
+}
+
+fn empty_digest(turbine_token: &str) -> DigestTurbine {
+    DigestTurbine { turbine_token: turbine_token.to_string(), captures: 0, max_temp: None, alerts: 0, critical_alerts: 0 }
+}
+
+async fn sessions(
+    state: &AppState,
+    schedule: &ReportSchedule,
+    from: u64,
+    to: u64,
+    unit: TempUnit,
+    sources: &mut Vec<String>,
+) -> ReportBody {
+    let pan_step = state.config.read().await.pan_step_degrees;
+    let catalog = state.catalog.read().await;
+    // Última captura de cada turbina dentro del periodo
+    let mut latest: BTreeMap<&str, &CaptureRecord> = BTreeMap::new();
+    for record in catalog.iter().filter(|r| r.timestamp >= from && r.timestamp <= to && schedule.includes(&r.turbine_token)) {
+        let current = latest.entry(record.turbine_token.as_str()).or_insert(record);
+        if record.timestamp > current.timestamp {
+            *current = record;
+        }
+    }
+    let mut summaries = Vec::new();
+    for record in latest.into_values() {
+        let Some(session) = session_of(&catalog, &record.filename) else { continue };
+        let start = session.first().map_or(record.timestamp, |r| r.timestamp);
+        let previous = catalog.iter()
+            .filter(|r| r.turbine_token == record.turbine_token && r.timestamp < start)
+            .max_by_key(|r| r.timestamp)
+            .and_then(|r| session_of(&catalog, &r.filename));
+
+        let mut cameras: BTreeMap<Option<String>, Vec<CaptureRecord>> = BTreeMap::new();
+        for capture in &session {
+            cameras.entry(capture.camera_id.clone()).or_default().push(capture.clone());
+        }
+        let coverage = cameras.into_iter()
+            .map(|(camera_id, captures)| {
+                let coverage = session_coverage(&captures, pan_step, to);
+                CameraCoverage { camera_id, coverage: coverage.coverage, gaps: coverage.gaps.len() }
+            })
+            .collect();
+        sources.extend(session.iter().chain(previous.iter().flatten()).map(|r| r.filename.clone()));
+        let comparison = previous.as_ref().map(|previous| unit.comparison(compare_sessions(previous, &session, SESSION_ANGLE_TOLERANCE)));
+        let mut max_temp = None;
+        for capture in &session {
+            raise_max(&mut max_temp, capture.max_temp.map(|t| unit.temp(t)));
+        }
+        summaries.push(SessionSummary {
+            turbine_token: record.turbine_token.clone(),
+            start,
+            end: session.last().map_or(record.timestamp, |r| r.timestamp),
+            captures: session.len(),
+            max_temp,
+            coverage,
+            previous_start: previous.as_ref().and_then(|p| p.first()).map(|r| r.timestamp),
+            mean_max_delta: comparison.as_ref().and_then(|c| c.mean_max_delta),
+            largest_increases: comparison
+                .map(|c| c.largest_increases.into_iter().take(REPORT_INCREASES).collect())
+                .unwrap_or_default(),
+        });
+    }
+    ReportBody::Session { sessions: summaries }
+}
+
+pub async fn build_report(state: &AppState, schedule: &ReportSchedule, now: u64) -> Result<Report, String> {
+    let period = parse_duration(&schedule.period)?;
+    let from = now.saturating_sub(period);
+    let (tz, unit) = {
+        let config = state.config.read().await;
+        let tz = config.display_timezone.as_deref().and_then(|name| name.parse::<Tz>().ok()).unwrap_or(Tz::UTC);
+        (tz, config.display_units)
+    };
+    let mut sources = Vec::new();
+    let body = match schedule.kind {
+        ReportKind::Digest => digest(state, schedule, from, now, unit, &mut sources).await?,
+        ReportKind::Session => sessions(state, schedule, from, now, unit, &mut sources).await,
+    };
+    Ok(Report {
+        schedule: schedule.name.clone(),
+        generated: now,
+        from,
+        to: now,
+        timezone: tz.name().to_string(),
+        units: unit,
+        locale: schedule.locale,
+        body,
+        sources,
+    })
+}
+
+// --- TEXTO PARA EL PDF Y EL CORREO ---
+
+impl Report {
+    fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    pub fn title(&self) -> String {
+        let l = self.locale;
+        let kind = match self.body {
+            ReportBody::Digest { .. } => l.pick("Digest report", "Informe resumen"),
+            ReportBody::Session { .. } => l.pick("Scan sessions report", "Informe de sesiones de escaneo"),
+        };
+        format!("{}: {}", kind, self.schedule)
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let (tz, l) = (self.tz(), self.locale);
+        let time = |t: u64| format_timestamp(t, tz);
+        let symbol = match self.units {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        };
+        let temp = |t: Option<f32>| t.map_or("-".to_string(), |t| format!("{:.1} {}", t, symbol));
+        let (from, to) = (time(self.from), time(self.to));
+        let mut lines = vec![
+            l.pick(format!("Period: {} to {}", from, to), format!("Periodo: del {} al {}", from, to)),
+            String::new(),
+        ];
+        match &self.body {
+            ReportBody::Digest { turbines } => {
+                if turbines.is_empty() {
+                    lines.push(l.pick("No captures or alerts in this period.", "Sin capturas ni alertas en este periodo.").into());
+                }
+                let alerts: usize = turbines.iter().map(|t| t.alerts).sum();
+                let critical: usize = turbines.iter().map(|t| t.critical_alerts).sum();
+                let captures: usize = turbines.iter().map(|t| t.captures).sum();
+                lines.push(l.pick(
+                    format!("Alerts: {} ({} critical)", alerts, critical),
+                    format!("Alertas: {} ({} críticas)", alerts, critical),
+                ));
+                lines.push(l.pick(format!("Captures: {}", captures), format!("Capturas: {}", captures)));
+                lines.push(String::new());
+                for t in turbines {
+                    lines.push(l.pick(
+                        format!(
+                            "{}: {} captures, max {}, {} alerts ({} critical)",
+                            t.turbine_token,
+                            t.captures,
+                            temp(t.max_temp),
+                            t.alerts,
+                            t.critical_alerts
+                        ),
+                        format!(
+                            "{}: {} capturas, máx. {}, {} alertas ({} críticas)",
+                            t.turbine_token,
+                            t.captures,
+                            temp(t.max_temp),
+                            t.alerts,
+                            t.critical_alerts
+                        ),
+                    ));
+                }
+            }
+            ReportBody::Session { sessions } => {
+                if sessions.is_empty() {
+                    lines.push(l.pick("No scan sessions in this period.", "Sin sesiones de escaneo en este periodo.").into());
+                }
+                for s in sessions {
+                    let (start, end) = (time(s.start), time(s.end));
+                    lines.push(l.pick(
+                        format!("{}: session {} to {}", s.turbine_token, start, end),
+                        format!("{}: sesión del {} al {}", s.turbine_token, start, end),
+                    ));
+                    lines.push(l.pick(
+                        format!("  {} captures, max {}", s.captures, temp(s.max_temp)),
+                        format!("  {} capturas, máx. {}", s.captures, temp(s.max_temp)),
+                    ));
+                    for c in &s.coverage {
+                        let camera = c.camera_id.as_deref()
+                            .map(|id| l.pick(format!(" camera {}", id), format!(" cámara {}", id)))
+                            .unwrap_or_default();
+                        let percent = c.coverage * 100.0;
+                        lines.push(l.pick(
+                            format!("  Coverage{}: {:.0}% ({} gaps)", camera, percent, c.gaps),
+                            format!("  Cobertura{}: {:.0}% ({} huecos)", camera, percent, c.gaps),
+                        ));
+                    }
+                    match (s.previous_start.map(time), s.mean_max_delta) {
+                        (Some(previous), Some(delta)) => lines.push(l.pick(
+                            format!("  Mean change since session of {}: {:+.1} {}", previous, delta, symbol),
+                            format!("  Cambio medio desde la sesión del {}: {:+.1} {}", previous, delta, symbol),
+                        )),
+                        (Some(previous), None) => lines.push(l.pick(
+                            format!("  No matching angles with session of {}", previous),
+                            format!("  Ningún ángulo coincide con la sesión del {}", previous),
+                        )),
+                        (None, _) => lines.push(l.pick("  No previous session to compare", "  Sin sesión anterior con la que comparar").into()),
+                    }
+                    for d in &s.largest_increases {
+                        lines.push(l.pick(
+                            format!("  +{:.1} {} at {:.1}° ({})", d.max_delta, symbol, d.angle_b, d.filename_b),
+                            format!("  +{:.1} {} a {:.1}° ({})", d.max_delta, symbol, d.angle_b, d.filename_b),
+                        ));
+                    }
+                    lines.push(String::new());
+                }
+            }
+        }
+        lines
+    }
+}
+
+// --- EJECUCIÓN ---
+
+#[derive(Serialize)]
+pub struct GeneratedReport {
+    pub name: String,
+    pub stored: bool,
+    pub emailed: usize,
+}
+
+// Nombre del archivo: instante de generación y nombre de la planificación
+fn report_name(schedule: &ReportSchedule, now: u64, format: ReportFormat) -> String {
+    let slug: String = schedule.name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    let stamp = chrono::DateTime::from_timestamp(now as i64, 0).map(|t| t.format("%Y%m%d-%H%M").to_string()).unwrap_or_default();
+    format!("{}_{}.{}", stamp, slug, format.extension())
+}
+
+pub async fn run_schedule(state: &AppState, schedule: &ReportSchedule, now: u64) -> Result<GeneratedReport, String> {
+    let report = build_report(state, schedule, now).await?;
+    let bytes = match schedule.format {
+        ReportFormat::Json => serde_json::to_vec_pretty(&report).map_err(|e| e.to_string())?,
+        ReportFormat::Pdf => pdf::text_pdf(&report.title(), &report.lines()),
+    };
+    let name = report_name(schedule, now, schedule.format);
+    if schedule.store {
+        let (stored_name, stored_bytes) = (name.clone(), bytes.clone());
+        tokio::task::spawn_blocking(move || write_report(&stored_name, &stored_bytes))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        let inputs: Vec<(&str, Option<usize>)> = report.sources.iter().map(|filename| (filename.as_str(), None)).collect();
+        let params = serde_json::json!({
+            "schedule": schedule.name,
+            "kind": schedule.kind,
+            "format": schedule.format,
+            "from": report.from,
+            "to": report.to,
+        });
+        record_derivation(state, "report", &inputs, params, Some(name.clone())).await;
+    }
+    let emailed = if schedule.email.is_empty() {
+        0
+    } else {
+        let attachment = mail::Attachment { name: name.clone(), content_type: schedule.format.content_type(), bytes };
+        mail::send_report(state, &schedule.email, &report.title(), &report.lines().join("\n"), attachment).await?;
+        schedule.email.len()
+    };
+    tracing::info!(schedule = %schedule.name, report = %name, stored = schedule.store, emailed, "📑 Informe generado");
+    Ok(GeneratedReport { name, stored: schedule.store, emailed })
+}
+
+// Planificaciones activas: las de la configuración y las de /api/schedules
+pub async fn all_schedules(state: &AppState) -> Vec<ReportSchedule> {
+    let mut schedules = state.config.read().await.report_schedules.clone();
+    schedules.extend(state.report_schedules.read().await.iter().cloned());
+    schedules
+}
+
+// Comprueba las planificaciones al comienzo de cada minuto
+pub fn spawn_report_scheduler(state: &Arc<AppState>) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        loop {
+            let wait = 60 - Utc::now().second() as u64;
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+            let at = Utc::now();
+            let due = all_schedules(&state).await.into_iter()
+                .filter(|s| s.enabled && s.cron.parse::<CronSchedule>().is_ok_and(|cron| cron.matches(&at)));
+            for schedule in due {
+                let state = state.clone();
+                let now = at.timestamp() as u64;
+                tokio::spawn(async move {
+                    if let Err(e) = run_schedule(&state, &schedule, now).await {
+                        tracing::error!(schedule = %schedule.name, error = %e, "❌ Error generando informe programado");
+                    }
+                });
+            }
+        }
+    });
+}
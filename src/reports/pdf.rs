@@ -0,0 +1,88 @@
+// --- PDF DE TEXTO ---
+// PDF mínimo (1.4) con el informe como líneas de texto en Helvetica, sin dependencias:
+// lo justo para archivarlo o adjuntarlo a un correo. Los caracteres fuera de Latin-1 se
+// sustituyen por '?' (WinAnsiEncoding).
+
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 50;
+const FONT_SIZE: u32 = 10;
+const LINE_HEIGHT: u32 = 14;
+// Caracteres por línea antes de partirla
+const MAX_LINE_CHARS: usize = 95;
+
+fn escape(line: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => bytes.extend([b'\\', c as u8]),
+            c if (c as u32) < 256 && !c.is_control() => bytes.push(c as u32 as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+// Parte las líneas largas para que no se salgan de la página
+fn wrap(lines: &[String]) -> Vec<String> {
+    let mut wrapped = Vec::new();
+    for line in lines {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            wrapped.push(String::new());
+        }
+        for chunk in chars.chunks(MAX_LINE_CHARS) {
+            wrapped.push(chunk.iter().collect());
+        }
+    }
+    wrapped
+}
+
+pub fn text_pdf(title: &str, lines: &[String]) -> Vec<u8> {
+    let mut all = vec![title.to_string(), String::new()];
+    all.extend(wrap(lines));
+    let per_page = ((PAGE_HEIGHT - 2 * MARGIN) / LINE_HEIGHT) as usize;
+    let pages: Vec<&[String]> = all.chunks(per_page).collect();
+
+    // Objetos: 1 catálogo, 2 árbol de páginas, 3 fuente y, por página, página + contenido
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", 4 + 2 * i)).collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+    for (i, page) in pages.iter().enumerate() {
+        let mut content = format!("BT /F1 {} Tf {} TL {} {} Td\n", FONT_SIZE, LINE_HEIGHT, MARGIN, PAGE_HEIGHT - MARGIN).into_bytes();
+        for line in page.iter() {
+            content.push(b'(');
+            content.extend(escape(line));
+            content.extend(b") Tj T*\n");
+        }
+        content.extend(b"ET");
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            5 + 2 * i
+        ).into_bytes());
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).into_bytes());
+    pdf
+}
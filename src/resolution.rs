@@ -0,0 +1,125 @@
+use crate::{
+    alertmanager,
+    analysis::anomaly::ANGLE_TOLERANCE,
+    events::EventKind,
+    notify,
+    scada::OPEN_ALERT_WINDOW_SEC,
+    state::{AlertRecord, AppState},
+    storage::alert_log::append_alerts,
+    weather::AmbientReading,
+};
+use serde::{Deserialize, Serialize};
+
+// --- RESOLUCIÓN AUTOMÁTICA DE ALERTAS ---
+// Con auto_resolve en la configuración, una alerta de umbral abierta pasa a resuelta
+// cuando las últimas `consecutive_captures` capturas de la misma turbina, ángulo y cámara
+// posteriores a ella quedan por debajo del umbral menos `hysteresis` grados (la histéresis
+// evita que una temperatura que ronda el umbral abra y cierre alertas sin parar). Se
+// comprueba con cada captura normal. La resolución queda en la alerta y en el histórico,
+// se publica como evento, se envía a Alertmanager y, si hay `channels`, se notifica.
+
+fn default_consecutive_captures() -> usize {
+    3
+}
+
+fn default_hysteresis() -> f32 {
+    2.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AutoResolveConfig {
+    #[serde(default = "default_consecutive_captures")]
+    pub consecutive_captures: usize,
+    // Grados por debajo del umbral que deben bajar las capturas
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis: f32,
+    // Canales a los que se avisa de la resolución (vacío = sin aviso)
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+impl AutoResolveConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.consecutive_captures == 0 {
+            return Err("auto_resolve: consecutive_captures must be at least 1".into());
+        }
+        if !self.hysteresis.is_finite() || self.hysteresis < 0.0 {
+            return Err("auto_resolve: hysteresis must be a non-negative number".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertResolution {
+    pub timestamp: u64,
+    // Captura que completó la racha por debajo del umbral
+    pub dataset_path: String,
+    pub max_temp: f32,
+    // Umbral (ya con histéresis) que tuvieron que bajar las capturas
+    pub below_temp: f32,
+}
+
+impl AlertRecord {
+    // Disparada hace menos de OPEN_ALERT_WINDOW_SEC y sin resolver
+    pub fn is_open(&self, now: u64) -> bool {
+        self.resolution.is_none() && self.timestamp.saturating_add(OPEN_ALERT_WINDOW_SEC) >= now
+    }
+}
+
+// Captura normal recién catalogada; resuelve las alertas abiertas de su turbina y ángulo
+// que ya cumplen la racha
+pub async fn check_resolution(state: &AppState, turbine_token: &str, angle: f32, ambient: Option<&AmbientReading>, timestamp: u64) {
+    let config = state.config.read().await.clone();
+    let Some(auto_resolve) = config.auto_resolve.clone() else { return };
+    let at = chrono::DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_else(chrono::Utc::now);
+    let offset = state.turbine_temp_offset(turbine_token).await;
+    let below_temp = config.effective_at(&at).shifted(offset).compensated(ambient, timestamp).trigger_temp(angle) - auto_resolve.hysteresis;
+    let now = chrono::Utc::now().timestamp() as u64;
+
+    // Solo las de umbral: las de reglas o del correo no tienen un umbral al que volver
+    let candidates: Vec<AlertRecord> = state.alerts.read().await.iter()
+        .filter(|a| a.turbine_token == turbine_token && a.is_open(now) && a.level.is_some() && !a.dataset_path.is_empty())
+        .filter(|a| (a.angle - angle).abs() < ANGLE_TOLERANCE)
+        .cloned()
+        .collect();
+    for alert in candidates {
+        let catalog = state.catalog.read().await;
+        let camera_id = catalog.iter().find(|r| r.filename == alert.dataset_path).and_then(|r| r.camera_id.clone());
+        let streak: Vec<(String, Option<f32>)> = catalog.iter().rev()
+            .filter(|r| r.turbine_token == turbine_token && r.timestamp > alert.timestamp && r.camera_id == camera_id)
+            .filter(|r| r.angle.is_some_and(|a| (a - alert.angle).abs() < ANGLE_TOLERANCE))
+            .take(auto_resolve.consecutive_captures)
+            .map(|r| (r.filename.clone(), r.max_temp))
+            .collect();
+        drop(catalog);
+        if streak.len() < auto_resolve.consecutive_captures || !streak.iter().all(|(_, t)| t.is_some_and(|t| t < below_temp)) {
+            continue;
+        }
+        let (dataset_path, max_temp) = streak[0].clone();
+        let resolution = AlertResolution { timestamp: now, dataset_path, max_temp: max_temp.unwrap_or_default(), below_temp };
+        resolve_alert(state, &alert.id, resolution, &auto_resolve.channels).await;
+    }
+}
+
+async fn resolve_alert(state: &AppState, id: &str, resolution: AlertResolution, channels: &[String]) {
+    let resolved = {
+        let mut alerts = state.alerts.write().await;
+        let Some(alert) = alerts.iter_mut().find(|a| a.id == id && a.resolution.is_none()) else { return };
+        alert.resolution = Some(resolution);
+        append_alerts([&*alert]);
+        alert.clone()
+    };
+    tracing::info!(
+        alert_id = %resolved.id,
+        turbine_token = %resolved.turbine_token,
+        angle = resolved.angle,
+        dataset_path = resolved.resolution.as_ref().map(|r| r.dataset_path.as_str()),
+        "✅ Alerta resuelta: la temperatura volvió a la normalidad"
+    );
+    state.events.publish(&resolved.turbine_token, EventKind::AlertResolved { alert: resolved.clone() });
+    alertmanager::forward_alert(state, &resolved).await;
+    if !channels.is_empty() {
+        notify::notify_resolution(state, &resolved, channels).await;
+    }
+}
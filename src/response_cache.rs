@@ -0,0 +1,116 @@
+use crate::{error::AppError, storage::catalog::CaptureRecord};
+use axum::{
+    body::Bytes,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use lru::LruCache;
+use serde::Serialize;
+use std::{
+    future::Future,
+    hash::{DefaultHasher, Hash, Hasher},
+    num::NonZeroUsize,
+};
+use tokio::sync::Mutex;
+
+// --- CACHÉ DE RESPUESTAS DERIVADAS ---
+// Las respuestas que obligan a decodificar capturas enteras (evolución de una captura,
+// fotogramas clave y seguimiento de un punto caliente en una sesión) se guardan ya
+// serializadas en una LRU en memoria, para que varios dashboards mirando lo mismo no las
+// recalculen. La clave es el endpoint, la captura pedida y los parámetros ya resueltos
+// (unidades y zona horaria incluidas); cada entrada lleva la huella de las capturas del
+// catálogo de las que depende, y deja de valer en cuanto alguna cambia (re-ingesta,
+// re-análisis, purga, una captura nueva en la sesión...). Las que se vacían de golpe
+// (restauración, reconstrucción del catálogo) limpian la caché entera, como la de frames.
+// La cabecera X-Cache (hit/miss) indica de dónde salió cada respuesta.
+
+// Respuestas mayores no se guardan: no compensan la memoria que ocupan
+const MAX_CACHED_BODY: usize = 4 * 1024 * 1024;
+
+#[derive(Hash, PartialEq, Eq)]
+pub struct CacheKey {
+    endpoint: &'static str,
+    id: String,
+    params: String,
+}
+
+impl CacheKey {
+    pub fn new(endpoint: &'static str, id: &str, params: String) -> Self {
+        CacheKey { endpoint, id: id.to_string(), params }
+    }
+}
+
+struct CachedResponse {
+    fingerprint: u64,
+    body: Bytes,
+}
+
+// Huella de las capturas de las que depende una respuesta: cambia si alguna se añade,
+// se quita o cambia cualquiera de sus datos en el catálogo
+pub fn fingerprint<'a>(records: impl IntoIterator<Item = &'a CaptureRecord>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for record in records {
+        serde_json::to_vec(record).unwrap_or_default().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+pub struct ResponseCache {
+    // None con --response-cache-size 0 (caché desactivada)
+    entries: Option<Mutex<LruCache<CacheKey, CachedResponse>>>,
+}
+
+impl ResponseCache {
+    pub fn new(size: usize) -> Self {
+        ResponseCache { entries: NonZeroUsize::new(size).map(|size| Mutex::new(LruCache::new(size))) }
+    }
+
+    async fn get(&self, key: &CacheKey, fingerprint: u64) -> Option<Bytes> {
+        let mut entries = self.entries.as_ref()?.lock().await;
+        match entries.get(key) {
+            Some(cached) if cached.fingerprint == fingerprint => Some(cached.body.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn put(&self, key: CacheKey, fingerprint: u64, body: Bytes) {
+        let Some(entries) = &self.entries else { return };
+        if body.len() <= MAX_CACHED_BODY {
+            entries.lock().await.put(key, CachedResponse { fingerprint, body });
+        }
+    }
+
+    pub async fn clear(&self) {
+        if let Some(entries) = &self.entries {
+            entries.lock().await.clear();
+        }
+    }
+
+    // Respuesta JSON desde la caché o, si no está (o dejó de valer), calculada y guardada.
+    // Los errores no se guardan
+    pub async fn json<T: Serialize>(
+        &self,
+        key: CacheKey,
+        fingerprint: u64,
+        compute: impl Future<Output = Result<T, AppError>>,
+    ) -> Result<Response, AppError> {
+        if let Some(body) = self.get(&key, fingerprint).await {
+            return Ok(json_response(body, "hit"));
+        }
+        let body = Bytes::from(serde_json::to_vec(&compute.await?).map_err(|e| AppError::Internal(e.to_string()))?);
+        self.put(key, fingerprint, body.clone()).await;
+        Ok(json_response(body, "miss"))
+    }
+}
+
+fn json_response(body: Bytes, cache: &'static str) -> Response {
+    let headers = [
+        (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+        (header::HeaderName::from_static("x-cache"), HeaderValue::from_static(cache)),
+    ];
+    (headers, body).into_response()
+}
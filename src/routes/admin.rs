@@ -0,0 +1,490 @@
+use crate::{
+    analysis::pixel_units::PixelUnit,
+    error::AppError,
+    pipeline::{self, CaptureInput, Outcome},
+    routes::ingest::{ingest_capture, CaptureUpload},
+    schedule::parse_duration,
+    simulate::{synthetic_frame, Hotspot},
+    state::{trim_alerts, AlertRecord, AppState, LiveStatus, RemoteConfig},
+    storage::{
+        alert_log::{append_alerts, rewrite_alert_log},
+        archive::stored_path,
+        audit::{append_audit, AuditEntry},
+        backup::{build_backup, read_backup},
+        catalog::{rebuild_catalog, save_catalog, CaptureRecord},
+        import::{import_captures, ImportSummary},
+        paths::check_file_name,
+        read_capture,
+    },
+};
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use ndarray_npy::WriteNpyExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+
+// --- ADMINISTRACIÓN: RESPALDO Y RESTAURACIÓN ---
+
+// Autenticación de endpoints de administración mediante "Authorization: Bearer <token>"
+pub fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(expected) = state.settings.admin_token.as_deref().filter(|t| !t.is_empty()) else {
+        return Err(AppError::Forbidden("Admin API disabled: set SENTINEL_ADMIN_TOKEN"));
+    };
+    let provided = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided != Some(expected) {
+        return Err(AppError::Unauthorized("Invalid admin token"));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct BackupParams {
+    #[serde(default)]
+    include_captures: bool,
+}
+
+#[derive(Serialize)]
+pub struct RestoreSummary {
+    catalog_entries: usize,
+    alerts: usize,
+    captures: usize,
+}
+
+pub async fn backup_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<BackupParams>,
+) -> Result<Response, AppError> {
+    require_admin(&state, &headers)?;
+    let config = state.config.read().await.clone();
+    let alerts: Vec<AlertRecord> = state.alerts.read().await.iter().cloned().collect();
+    let catalog = state.catalog.read().await.clone();
+
+    let tarball = tokio::task::spawn_blocking(move || {
+        build_backup(config, alerts, catalog, params.include_captures)
+    })
+    .await??;
+
+    let filename = format!("sentinel_backup_{}.tar", chrono::Utc::now().timestamp());
+    tracing::info!(%filename, size_kb = tarball.len() / 1024, "🗄️ Respaldo generado");
+    let headers = [
+        (header::CONTENT_TYPE, "application/x-tar".to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+    ];
+    Ok((headers, tarball).into_response())
+}
+
+// Restaura un tar generado por /api/admin/backup (cuerpo binario de la petición)
+pub async fn restore_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<RestoreSummary>, AppError> {
+    require_admin(&state, &headers)?;
+    let contents = tokio::task::spawn_blocking(move || read_backup(&body))
+        .await?
+        .map_err(|e| {
+            tracing::warn!(error = %e, "❌ Respaldo inválido");
+            AppError::BadRequest(format!("Invalid backup: {}", e))
+        })?;
+
+    let mut summary = RestoreSummary { catalog_entries: 0, alerts: 0, captures: contents.captures };
+    if let Some(config) = contents.config {
+        state.apply_config(config, None, "restore", None).await
+            .map_err(|e| AppError::Internal(format!("could not save the restored configuration: {}", e)))?;
+    }
+    if let Some(alerts) = contents.alerts {
+        summary.alerts = alerts.len();
+        // El histórico en disco pasa a ser el del respaldo, como la memoria
+        let mut current = state.alerts.write().await;
+        let persisted = alerts.clone();
+        tokio::task::spawn_blocking(move || rewrite_alert_log(&persisted))
+            .await?
+            .map_err(|e| AppError::Internal(format!("could not write the restored alert log: {}", e)))?;
+        *current = alerts;
+    }
+    if let Some(catalog) = contents.catalog {
+        summary.catalog_entries = catalog.len();
+        let mut current = state.catalog.write().await;
+        save_catalog(&catalog);
+        *current = catalog;
+    }
+    // Los archivos restaurados pueden reemplazar capturas ya cacheadas
+    state.frame_cache.lock().await.clear();
+    state.response_cache.clear().await;
+    state.latest_frames.clear().await;
+    tracing::info!(
+        catalog_entries = summary.catalog_entries,
+        alerts = summary.alerts,
+        captures = summary.captures,
+        "♻️ Respaldo restaurado"
+    );
+    Ok(Json(summary))
+}
+
+// --- SECRETOS EN CLARO ---
+// Los endpoints de configuración devuelven los secretos enmascarados; este devuelve la
+// configuración vigente con la API key y las URLs de los webhooks descifradas, por si
+// hiciera falta recuperarlas. Cada consulta queda en la auditoría.
+pub async fn reveal_config_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<RemoteConfig>, AppError> {
+    require_admin(&state, &headers)?;
+    let config = state.secrets.reveal_config(&*state.config.read().await)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    append_audit(&AuditEntry::new("reveal_config_secrets", "config", serde_json::Value::Null));
+    tracing::warn!("🔓 Secretos de la configuración consultados en claro");
+    Ok(Json(config))
+}
+
+// --- RE-INGESTA (REPLAY) ---
+// Vuelve a pasar capturas almacenadas por el pipeline actual: recalcula estadísticas y
+// anomalía en el catálogo y, si se pide, genera las alertas que dispararían hoy (sin
+// notificar, son históricas). Útil tras cambiar umbrales o añadir análisis nuevos.
+
+#[derive(Deserialize)]
+pub struct ReplayParams {
+    turbine_token: Option<String>,
+    // Antigüedad máxima de las capturas ("30d", "12h"...); sin valor, todas
+    since: Option<String>,
+    #[serde(default)]
+    alerts: bool,
+}
+
+#[derive(Serialize)]
+pub struct ReplaySummary {
+    replayed: usize,
+    failed: usize,
+    alerts_created: usize,
+}
+
+pub async fn replay_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ReplayParams>,
+) -> Result<Json<ReplaySummary>, AppError> {
+    require_admin(&state, &headers)?;
+    let since = match params.since.as_deref() {
+        Some(since) => parse_duration(since).map_err(AppError::BadRequest)?,
+        None => u64::MAX,
+    };
+    let now = chrono::Utc::now().timestamp() as u64;
+
+    // Orden cronológico: el historial de anomalía de cada captura usa las ya recalculadas
+    let mut records: Vec<CaptureRecord> = state.catalog.read().await.iter()
+        .filter(|r| params.turbine_token.as_ref().is_none_or(|t| *t == r.turbine_token))
+        .filter(|r| r.timestamp.saturating_add(since) >= now)
+        .cloned()
+        .collect();
+    records.sort_by_key(|r| r.timestamp);
+    let alerted: HashSet<String> = state.alerts.read().await.iter().map(|a| a.dataset_path.clone()).collect();
+
+    let mut summary = ReplaySummary { replayed: 0, failed: 0, alerts_created: 0 };
+    let mut new_alerts = Vec::new();
+    for record in records {
+        // Lectura directa (también del archivo frío, sin restaurarlo)
+        let path = stored_path(&record);
+        let data = match tokio::task::spawn_blocking(move || read_capture(&path)).await? {
+            Ok(data) => Bytes::from(data),
+            Err(e) => {
+                tracing::warn!(filename = %record.filename, error = %e, "⚠️ Replay: captura ilegible");
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let input = CaptureInput {
+            turbine_token: record.turbine_token.clone(),
+            filename: record.filename.clone(),
+            timestamp: record.timestamp,
+            angle: record.angle.unwrap_or(0.0),
+            rotor_phase: None,
+            camera_id: record.camera_id.clone(),
+            ambient: record.ambient.clone(),
+            data,
+        };
+        let mut analysis = pipeline::analyze_capture(&state, &input).await?;
+        if let Some(entry) = state.catalog.write().await.iter_mut().find(|r| r.filename == record.filename) {
+            entry.max_temp = analysis.stats.map(|s| s.max_temp);
+            entry.avg_temp = analysis.stats.map(|s| s.avg_temp);
+            entry.anomaly_score = analysis.anomaly_score;
+            entry.calibration_version = analysis.calibration_version;
+            entry.quality = analysis.quality;
+            entry.analyses = std::mem::take(&mut analysis.results);
+        }
+        summary.replayed += 1;
+
+        if let (true, Some(stats)) = (params.alerts && !alerted.contains(&record.filename), analysis.stats)
+            && let Outcome::Alert { alert, .. } = pipeline::evaluate_capture(&state, &input, stats.max_temp, &analysis).await?
+        {
+            new_alerts.push(*alert);
+        }
+    }
+    save_catalog(&state.catalog.read().await);
+
+    if !new_alerts.is_empty() {
+        summary.alerts_created = new_alerts.len();
+        append_alerts(&new_alerts);
+        let mut alerts = state.alerts.write().await;
+        alerts.extend(new_alerts);
+        alerts.make_contiguous().sort_by_key(|a| std::cmp::Reverse(a.timestamp));
+        trim_alerts(&mut alerts);
+    }
+    tracing::info!(
+        replayed = summary.replayed,
+        failed = summary.failed,
+        alerts_created = summary.alerts_created,
+        "🔁 Re-ingesta completada"
+    );
+    Ok(Json(summary))
+}
+
+// --- IMPORTACIÓN MASIVA ---
+// Importa un directorio o un .tar del propio servidor (las capturas históricas son
+// demasiadas para subirlas por HTTP); ver storage::import para el formato.
+
+#[derive(Deserialize)]
+pub struct ImportRequest {
+    path: String,
+}
+
+pub async fn import_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ImportRequest>,
+) -> Result<Json<ImportSummary>, AppError> {
+    require_admin(&state, &headers)?;
+    let source = PathBuf::from(&request.path);
+    if !source.exists() {
+        return Err(AppError::BadRequest(format!("Import source not found: {}", request.path)));
+    }
+    tracing::info!(source = %request.path, "📥 Importando capturas históricas");
+
+    let worker = state.clone();
+    let summary = tokio::task::spawn_blocking(move || import_captures(&worker, &source))
+        .await?
+        .map_err(|e| AppError::BadRequest(format!("Import failed: {}", e)))?;
+    tracing::info!(
+        imported = summary.imported,
+        duplicates = summary.duplicates,
+        skipped = summary.skipped,
+        "📥 Importación completada"
+    );
+    Ok(Json(summary))
+}
+
+// --- REINICIO DE ESTADO ---
+// Recuperación sin reiniciar el proceso: olvidar el estado en vivo de una turbina, vaciar
+// cachés o reconstruir el catálogo escaneando el disco (conservando los metadatos de las
+// capturas que siguen ahí con el mismo contenido).
+
+#[derive(Deserialize)]
+pub struct ResetRequest {
+    // Token cuyo estado en vivo (heartbeat y refuerzo de escaneo) se borra
+    #[serde(default)]
+    live_status: Option<String>,
+    #[serde(default)]
+    caches: bool,
+    #[serde(default)]
+    rebuild_catalog: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct ResetSummary {
+    live_status_cleared: bool,
+    caches_flushed: bool,
+    // Entradas del catálogo antes y después de reconstruirlo
+    catalog_before: Option<usize>,
+    catalog_after: Option<usize>,
+}
+
+pub async fn reset_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ResetRequest>,
+) -> Result<Json<ResetSummary>, AppError> {
+    require_admin(&state, &headers)?;
+    let mut summary = ResetSummary::default();
+
+    if let Some(token) = &request.live_status {
+        let known = state.turbine_status.write().await.remove(token).is_some();
+        state.scan_boosts.write().await.remove(token);
+        state.adaptive_scans.write().await.remove(token);
+        let mut live = state.live_status.write().await;
+        if live.turbine_token == *token {
+            *live = LiveStatus::default();
+        }
+        summary.live_status_cleared = known;
+    }
+    if request.caches {
+        state.frame_cache.lock().await.clear();
+        state.response_cache.clear().await;
+        state.latest_frames.clear().await;
+        summary.caches_flushed = true;
+    }
+    if request.rebuild_catalog {
+        let mut rebuilt = tokio::task::spawn_blocking(rebuild_catalog).await?;
+        let mut catalog = state.catalog.write().await;
+        let previous: HashMap<&str, &CaptureRecord> = catalog.iter().map(|r| (r.filename.as_str(), r)).collect();
+        for record in &mut rebuilt {
+            if let Some(old) = previous.get(record.filename.as_str()).filter(|old| old.sha256 == record.sha256) {
+                record.ambient = old.ambient.clone();
+                record.angle = old.angle;
+                record.max_temp = old.max_temp;
+                record.avg_temp = old.avg_temp;
+                record.anomaly_score = old.anomaly_score;
+                record.quality = old.quality;
+                record.integrity_error = old.integrity_error.clone();
+                record.starred = old.starred;
+            }
+        }
+        summary.catalog_before = Some(catalog.len());
+        summary.catalog_after = Some(rebuilt.len());
+        save_catalog(&rebuilt);
+        *catalog = rebuilt;
+        // Los frames cacheados pueden ser de archivos que ya no están
+        state.frame_cache.lock().await.clear();
+        state.response_cache.clear().await;
+        state.latest_frames.clear().await;
+    }
+
+    append_audit(&AuditEntry::new(
+        "reset",
+        request.live_status.as_deref().unwrap_or("*"),
+        serde_json::json!({ "caches": request.caches, "rebuild_catalog": request.rebuild_catalog }),
+    ));
+    tracing::warn!(
+        live_status = request.live_status.as_deref(),
+        caches = request.caches,
+        rebuild_catalog = request.rebuild_catalog,
+        "🧹 Estado reiniciado"
+    );
+    Ok(Json(summary))
+}
+
+// --- GENERADOR DE CAPTURAS DE PRUEBA ---
+// Crea una captura sintética con un punto caliente gaussiano y la pasa por el mismo
+// camino que una subida real (catálogo, análisis, umbrales, alertas y notificaciones):
+// permite comprobar la instalación de punta a punta sin calentar nada.
+
+// Tamaño máximo del frame sintético por lado
+const MAX_SYNTHETIC_SIDE: usize = 1024;
+
+fn default_synthetic_token() -> String {
+    "SYNTH-TEST".into()
+}
+
+fn default_synthetic_width() -> usize {
+    32
+}
+
+fn default_synthetic_height() -> usize {
+    24
+}
+
+fn default_synthetic_ambient() -> f32 {
+    20.0
+}
+
+fn default_hotspot_radius() -> f32 {
+    2.0
+}
+
+#[derive(Deserialize)]
+pub struct GenerateParams {
+    #[serde(default = "default_synthetic_token")]
+    turbine_token: String,
+    #[serde(default = "default_synthetic_width")]
+    width: usize,
+    #[serde(default = "default_synthetic_height")]
+    height: usize,
+    #[serde(default = "default_synthetic_ambient")]
+    ambient: f32,
+    // Temperatura en el centro del punto caliente (sin él, solo fondo)
+    hotspot_temp: Option<f32>,
+    // Centro en píxeles (por defecto el centro del frame) y desviación típica
+    hotspot_x: Option<f32>,
+    hotspot_y: Option<f32>,
+    #[serde(default = "default_hotspot_radius")]
+    hotspot_radius: f32,
+    #[serde(default)]
+    angle: f32,
+    camera_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct GenerateSummary {
+    status: Cow<'static, str>,
+    filename: String,
+    max_temp: f32,
+    // Alerta creada por la captura, si ha superado algún umbral
+    alert_id: Option<String>,
+}
+
+pub async fn generate_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<GenerateParams>,
+) -> Result<Json<GenerateSummary>, AppError> {
+    require_admin(&state, &headers)?;
+    if !(1..=MAX_SYNTHETIC_SIDE).contains(&params.width) || !(1..=MAX_SYNTHETIC_SIDE).contains(&params.height) {
+        return Err(AppError::BadRequest(format!("width and height must be within 1..={}", MAX_SYNTHETIC_SIDE)));
+    }
+    check_file_name(&params.turbine_token).map_err(|e| AppError::BadRequest(format!("Invalid turbine_token: {}", e)))?;
+    let finite = [params.ambient, params.hotspot_radius, params.angle].into_iter()
+        .chain(params.hotspot_temp)
+        .chain(params.hotspot_x)
+        .chain(params.hotspot_y)
+        .all(f32::is_finite);
+    if !finite || params.hotspot_radius <= 0.0 {
+        return Err(AppError::BadRequest("Invalid synthetic frame parameters".into()));
+    }
+
+    let row = params.hotspot_y.unwrap_or((params.height / 2) as f32);
+    let hotspot = params.hotspot_temp.map(|temp| Hotspot {
+        row,
+        col: params.hotspot_x.unwrap_or((params.width / 2) as f32),
+        // El fondo sube 0,15 °C por fila: el pico queda en hotspot_temp
+        delta: temp - (params.ambient + row * 0.15),
+        sigma: params.hotspot_radius,
+    });
+    let frame = synthetic_frame(&mut fastrand::Rng::new(), params.height, params.width, params.ambient, hotspot.as_ref());
+    let max_temp = frame.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    let mut npy = Vec::new();
+    frame.write_npy(&mut npy).map_err(|e| AppError::Internal(e.to_string()))?;
+
+    let upload = CaptureUpload {
+        turbine_token: params.turbine_token.clone(),
+        angle: params.angle,
+        rotor_phase: None,
+        camera_id: params.camera_id.clone(),
+        source: None,
+        captured_at: None,
+        force: false,
+        receipt_id: None,
+        data: Bytes::from(npy),
+        photo: None,
+        pixel_unit: PixelUnit::Celsius,
+    };
+    let response = ingest_capture(&state, upload).await?;
+    tracing::info!(
+        turbine_token = %params.turbine_token,
+        filename = %response.filename,
+        max_temp,
+        alert = response.alert_id.is_some(),
+        "🧪 Captura sintética generada"
+    );
+    Ok(Json(GenerateSummary { status: response.status, filename: response.filename, max_temp, alert_id: response.alert_id }))
+}
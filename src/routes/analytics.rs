@@ -0,0 +1,308 @@
+use super::web::display_unit;
+use crate::{
+    analysis::forecast::{fit_linear, ForecastPoint, MIN_FORECAST_POINTS},
+    error::AppError,
+    schedule::parse_duration,
+    state::AppState,
+    storage::catalog::CaptureRecord,
+    thresholds::Severity,
+    units::TempUnit,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+// --- ANALÍTICA DE FLOTA: TENDENCIAS Y PRONÓSTICOS ---
+
+// Puntos máximos que devuelve un pronóstico
+const MAX_FORECAST_STEPS: usize = 500;
+
+fn default_horizon() -> String {
+    "24h".into()
+}
+
+fn default_window() -> String {
+    "7d".into()
+}
+
+fn default_steps() -> usize {
+    24
+}
+
+#[derive(Deserialize)]
+pub struct ForecastParams {
+    // Hasta dónde pronosticar y cuánta historia usar ("24h", "7d"...)
+    #[serde(default = "default_horizon")]
+    horizon: String,
+    #[serde(default = "default_window")]
+    window: String,
+    #[serde(default = "default_steps")]
+    steps: usize,
+}
+
+#[derive(Serialize)]
+pub struct ThresholdEta {
+    level: String,
+    severity: Severity,
+    min_temp: f32,
+    // Instante estimado en que la tendencia alcanza el umbral (None = no lo alcanza)
+    eta: Option<u64>,
+    hours_until: Option<f32>,
+}
+
+#[derive(Serialize)]
+pub struct Forecast {
+    turbine_token: String,
+    points_used: usize,
+    slope_per_hour: f32,
+    forecast: Vec<ForecastPoint>,
+    thresholds: Vec<ThresholdEta>,
+}
+
+// Pronóstico de la máxima de una turbina y tiempo estimado hasta cada umbral global
+pub async fn forecast_handler(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(params): Query<ForecastParams>,
+) -> Result<Json<Forecast>, AppError> {
+    let horizon = parse_duration(&params.horizon).map_err(AppError::BadRequest)?;
+    let window = parse_duration(&params.window).map_err(AppError::BadRequest)?;
+    let steps = params.steps.clamp(1, MAX_FORECAST_STEPS);
+    let now = chrono::Utc::now().timestamp() as u64;
+
+    let config = state.config.read().await.clone();
+    let series: Vec<(u64, f32)> = state.catalog.read().await.iter()
+        .filter(|r| r.turbine_token == token && r.timestamp.saturating_add(window) >= now)
+        .filter(|r| config.usable_quality(r.quality.as_ref()))
+        .filter_map(|r| Some((r.timestamp, r.max_temp?)))
+        .collect();
+    if series.is_empty() {
+        return Err(AppError::NotFound(format!("No recent captures for turbine '{}'", token)));
+    }
+    let Some(trend) = fit_linear(&series) else {
+        return Err(AppError::BadRequest(format!(
+            "At least {} recent captures are needed for a forecast", MIN_FORECAST_POINTS
+        )));
+    };
+
+    let forecast = (1..=steps as u64)
+        .map(|i| trend.predict(now.saturating_add(horizon.saturating_mul(i) / steps as u64)))
+        .collect();
+    let thresholds = config.global_levels().into_iter()
+        .map(|level| {
+            let eta = trend.time_to_threshold(level.min_temp, now);
+            ThresholdEta {
+                level: level.name,
+                severity: level.severity,
+                min_temp: level.min_temp,
+                eta,
+                hours_until: eta.map(|t| t.saturating_sub(now) as f32 / 3600.0),
+            }
+        })
+        .collect();
+
+    Ok(Json(Forecast {
+        turbine_token: token,
+        points_used: series.len(),
+        slope_per_hour: trend.slope_per_hour(),
+        forecast,
+        thresholds,
+    }))
+}
+
+// --- COMPARACIÓN ENTRE TURBINAS ---
+// GET /api/compare/turbines?tokens=a,b,c&metric=max_temp&window=30d: series de cada
+// turbina alineadas en los mismos intervalos (?interval=, por defecto el que da unos
+// COMPARE_POINTS puntos) y un resumen por turbina con su tendencia y cuánto se aparta de
+// la media de las demás, para ver qué unidad se calienta más que sus vecinas.
+
+const MAX_COMPARE_TURBINES: usize = 20;
+const MAX_COMPARE_WINDOW_SEC: u64 = 366 * 86400;
+// Intervalos de la serie cuando no se indica ?interval=
+const COMPARE_POINTS: u64 = 100;
+const MAX_COMPARE_POINTS: u64 = 2_000;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareMetric {
+    #[default]
+    MaxTemp,
+    AvgTemp,
+    AnomalyScore,
+}
+
+impl CompareMetric {
+    fn value(self, record: &CaptureRecord) -> Option<f32> {
+        match self {
+            CompareMetric::MaxTemp => record.max_temp,
+            CompareMetric::AvgTemp => record.avg_temp,
+            CompareMetric::AnomalyScore => record.anomaly_score,
+        }
+    }
+
+    fn is_temperature(self) -> bool {
+        self != CompareMetric::AnomalyScore
+    }
+
+    // Valor de un intervalo: la más alta de las máximas y de las anomalías, la media de las medias
+    fn aggregate(self, values: &[f32]) -> f32 {
+        match self {
+            CompareMetric::AvgTemp => values.iter().sum::<f32>() / values.len() as f32,
+            _ => values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        }
+    }
+}
+
+fn default_compare_window() -> String {
+    "30d".into()
+}
+
+#[derive(Deserialize)]
+pub struct CompareParams {
+    // Tokens separados por comas
+    tokens: String,
+    #[serde(default)]
+    metric: CompareMetric,
+    #[serde(default = "default_compare_window")]
+    window: String,
+    interval: Option<String>,
+    units: Option<TempUnit>,
+}
+
+#[derive(Serialize)]
+pub struct TurbineSummary {
+    captures: usize,
+    mean: Option<f32>,
+    min: Option<f32>,
+    max: Option<f32>,
+    latest: Option<f32>,
+    // Pendiente de la recta ajustada, por día (None con menos de MIN_FORECAST_POINTS capturas)
+    slope_per_day: Option<f32>,
+    // Su media menos la media de las demás turbinas
+    deviation: Option<f32>,
+}
+
+#[derive(Serialize)]
+pub struct TurbineSeries {
+    turbine_token: String,
+    // Un valor por intervalo de `timestamps` (null si no hubo capturas)
+    values: Vec<Option<f32>>,
+    summary: TurbineSummary,
+}
+
+#[derive(Serialize)]
+pub struct TurbineComparison {
+    metric: CompareMetric,
+    from: u64,
+    to: u64,
+    interval_sec: u64,
+    // Inicio de cada intervalo
+    timestamps: Vec<u64>,
+    turbines: Vec<TurbineSeries>,
+    // La de mayor desviación positiva respecto a las demás
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hottest: Option<String>,
+}
+
+pub async fn compare_turbines_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CompareParams>,
+) -> Result<Json<TurbineComparison>, AppError> {
+    let mut tokens: Vec<String> = Vec::new();
+    for token in params.tokens.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !tokens.iter().any(|t| t == token) {
+            tokens.push(token.to_string());
+        }
+    }
+    if !(2..=MAX_COMPARE_TURBINES).contains(&tokens.len()) {
+        return Err(AppError::BadRequest(format!("tokens must list between 2 and {} turbines", MAX_COMPARE_TURBINES)));
+    }
+    for token in &tokens {
+        if !state.knows_turbine(token).await {
+            return Err(AppError::NotFound(format!("Turbine '{}' not found", token)));
+        }
+    }
+    let window = parse_duration(&params.window).map_err(AppError::BadRequest)?;
+    if window == 0 || window > MAX_COMPARE_WINDOW_SEC {
+        return Err(AppError::BadRequest(format!("window must be between 1s and {} days", MAX_COMPARE_WINDOW_SEC / 86400)));
+    }
+    let interval = match params.interval.as_deref() {
+        Some(interval) => parse_duration(interval).map_err(AppError::BadRequest)?,
+        None => window.div_ceil(COMPARE_POINTS),
+    };
+    if interval == 0 || window.div_ceil(interval) > MAX_COMPARE_POINTS {
+        return Err(AppError::BadRequest(format!("interval must split the window into at most {} points", MAX_COMPARE_POINTS)));
+    }
+    let unit = display_unit(&state, params.units).await;
+    let metric = params.metric;
+    let convert = |value: f32| if metric.is_temperature() { unit.temp(value) } else { value };
+    let convert_delta = |value: f32| if metric.is_temperature() { unit.delta(value) } else { value };
+
+    let to = chrono::Utc::now().timestamp() as u64;
+    let from = to.saturating_sub(window);
+    let points = window.div_ceil(interval) as usize;
+    let mut samples: HashMap<&str, Vec<(u64, f32)>> = HashMap::new();
+    let config = state.config.read().await;
+    let catalog = state.catalog.read().await;
+    let usable = catalog.iter().filter(|r| config.usable_quality(r.quality.as_ref()));
+    for record in usable.filter(|r| r.timestamp >= from && r.timestamp <= to) {
+        if let Some(token) = tokens.iter().find(|t| **t == record.turbine_token)
+            && let Some(value) = metric.value(record)
+        {
+            samples.entry(token.as_str()).or_default().push((record.timestamp, value));
+        }
+    }
+
+    let means: Vec<Option<f32>> = tokens.iter()
+        .map(|t| samples.get(t.as_str()).map(|s| s.iter().map(|(_, v)| v).sum::<f32>() / s.len() as f32))
+        .collect();
+    let mut turbines = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let series = samples.get(token.as_str()).map(Vec::as_slice).unwrap_or_default();
+        let mut buckets: Vec<Vec<f32>> = vec![Vec::new(); points];
+        for &(timestamp, value) in series {
+            let index = (((timestamp - from) / interval) as usize).min(points - 1);
+            buckets[index].push(value);
+        }
+        let others: Vec<f32> = means.iter().enumerate().filter(|(j, _)| *j != i).filter_map(|(_, m)| *m).collect();
+        let deviation = means[i]
+            .filter(|_| !others.is_empty())
+            .map(|mean| mean - others.iter().sum::<f32>() / others.len() as f32);
+        let values = series.iter().map(|(_, v)| *v);
+        turbines.push(TurbineSeries {
+            turbine_token: token.clone(),
+            values: buckets.iter()
+                .map(|b| (!b.is_empty()).then(|| convert(metric.aggregate(b))))
+                .collect(),
+            summary: TurbineSummary {
+                captures: series.len(),
+                mean: means[i].map(convert),
+                min: values.clone().reduce(f32::min).map(convert),
+                max: values.reduce(f32::max).map(convert),
+                latest: series.last().map(|(_, v)| convert(*v)),
+                slope_per_day: fit_linear(series).map(|trend| convert_delta(trend.slope_per_hour() * 24.0)),
+                deviation: deviation.map(convert_delta),
+            },
+        });
+    }
+    drop(catalog);
+    drop(config);
+    let hottest = turbines.iter()
+        .filter_map(|t| Some((t, t.summary.deviation?)))
+        .filter(|(_, deviation)| *deviation > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(t, _)| t.turbine_token.clone());
+
+    Ok(Json(TurbineComparison {
+        metric,
+        from,
+        to,
+        interval_sec: interval,
+        timestamps: (0..points as u64).map(|i| from + i * interval).collect(),
+        turbines,
+        hottest,
+    }))
+}
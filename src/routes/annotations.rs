@@ -0,0 +1,116 @@
+use super::web::change_author;
+use crate::{
+    analysis::overlay::{parse_color, Shape},
+    error::AppError,
+    state::AppState,
+    storage::annotations::{save_annotations, Annotation},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- API DE ANOTACIONES ---
+// Marcas dibujadas por los usuarios sobre un frame, en píxeles del frame. Se devuelven
+// junto con la matriz (/api/matrix/:filename/:frame_index) y se pueden dibujar en la
+// imagen renderizada con /api/render/:filename/:frame_index?annotations=true.
+
+// Anotaciones por captura
+const MAX_ANNOTATIONS_PER_CAPTURE: usize = 500;
+
+#[derive(Deserialize)]
+pub struct NewAnnotation {
+    frame_index: usize,
+    // {"type": "rect", "x", "y", "width", "height"}, {"type": "arrow", "x1", "y1", "x2", "y2"}
+    // o {"type": "text", "x", "y", "text"}
+    #[serde(flatten)]
+    shape: Shape,
+    #[serde(default)]
+    color: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AnnotationParams {
+    // Solo las de un frame
+    frame: Option<usize>,
+}
+
+async fn check_capture(state: &AppState, filename: &str) -> Result<(), AppError> {
+    if !state.catalog.read().await.iter().any(|r| r.filename == filename) {
+        return Err(AppError::NotFound(format!("Capture '{}' not found", filename)));
+    }
+    Ok(())
+}
+
+// Anotaciones de un frame, en el orden en que se crearon
+pub(crate) async fn frame_annotations(state: &AppState, filename: &str, frame_index: usize) -> Vec<Annotation> {
+    state.annotations.read().await.iter()
+        .filter(|a| a.filename == filename && a.frame_index == frame_index)
+        .cloned()
+        .collect()
+}
+
+pub async fn list_annotations(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(params): Query<AnnotationParams>,
+) -> Result<Json<Vec<Annotation>>, AppError> {
+    check_capture(&state, &filename).await?;
+    let annotations = state.annotations.read().await.iter()
+        .filter(|a| a.filename == filename && params.frame.is_none_or(|f| a.frame_index == f))
+        .cloned()
+        .collect();
+    Ok(Json(annotations))
+}
+
+pub async fn create_annotation(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(filename): Path<String>,
+    Json(request): Json<NewAnnotation>,
+) -> Result<Json<Annotation>, AppError> {
+    check_capture(&state, &filename).await?;
+    request.shape.validate().map_err(AppError::BadRequest)?;
+    if let Some(color) = request.color.as_deref()
+        && parse_color(color).is_none()
+    {
+        return Err(AppError::BadRequest(format!("Invalid color '{}' (expected #rrggbb)", color)));
+    }
+    let annotation = Annotation {
+        id: uuid::Uuid::new_v4().to_string(),
+        filename,
+        frame_index: request.frame_index,
+        shape: request.shape,
+        color: request.color,
+        author: change_author(&headers),
+        created: chrono::Utc::now().timestamp() as u64,
+    };
+    let mut annotations = state.annotations.write().await;
+    if annotations.iter().filter(|a| a.filename == annotation.filename).count() >= MAX_ANNOTATIONS_PER_CAPTURE {
+        return Err(AppError::BadRequest(format!(
+            "Capture already has {} annotations",
+            MAX_ANNOTATIONS_PER_CAPTURE
+        )));
+    }
+    annotations.push(annotation.clone());
+    save_annotations(&annotations);
+    tracing::info!(filename = %annotation.filename, frame = annotation.frame_index, "✏️ Anotación creada");
+    Ok(Json(annotation))
+}
+
+pub async fn delete_annotation(
+    State(state): State<Arc<AppState>>,
+    Path((filename, id)): Path<(String, String)>,
+) -> Result<Json<&'static str>, AppError> {
+    let mut annotations = state.annotations.write().await;
+    let before = annotations.len();
+    annotations.retain(|a| !(a.filename == filename && a.id == id));
+    if annotations.len() == before {
+        return Err(AppError::NotFound(format!("Annotation '{}' not found", id)));
+    }
+    save_annotations(&annotations);
+    Ok(Json("Annotation removed"))
+}
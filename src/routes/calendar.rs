@@ -0,0 +1,118 @@
+use super::web::{display_tz, display_unit};
+use crate::{
+    error::AppError,
+    state::AppState,
+    storage::alert_log::load_alert_log,
+    thresholds::Severity,
+    units::TempUnit,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Datelike, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+// --- CALENDARIO DE CALOR ---
+// GET /api/calendar/:token?year=2026: máxima y número de alertas de cada día del año
+// para el calendario estilo GitHub del dashboard, donde se ven de un vistazo los patrones
+// estacionales. Los días se cuentan en la zona de ?tz= (o display_timezone); solo se
+// devuelven los días con capturas o alertas. La máxima del día es la mayor entre las
+// capturas (calibradas) y las alertas.
+
+#[derive(Deserialize)]
+pub struct CalendarParams {
+    // Por defecto, el año en curso
+    year: Option<i32>,
+    tz: Option<String>,
+    units: Option<TempUnit>,
+}
+
+#[derive(Serialize, Default)]
+pub struct CalendarDay {
+    // "2026-07-14"
+    pub date: String,
+    pub max_temp: Option<f32>,
+    pub captures: usize,
+    pub alerts: usize,
+    pub critical_alerts: usize,
+}
+
+#[derive(Serialize)]
+pub struct CalendarReport {
+    pub turbine_token: String,
+    pub year: i32,
+    pub timezone: String,
+    pub units: TempUnit,
+    pub max_temp: Option<f32>,
+    pub total_alerts: usize,
+    pub days: Vec<CalendarDay>,
+}
+
+// Día local de un instante, si cae dentro del año pedido
+fn local_day(timestamp: u64, tz: Tz, year: i32) -> Option<NaiveDate> {
+    let date = DateTime::from_timestamp(timestamp as i64, 0)?.with_timezone(&tz).date_naive();
+    (date.year() == year).then_some(date)
+}
+
+fn raise_max(current: &mut Option<f32>, value: f32) {
+    if value.is_finite() && current.is_none_or(|m| value > m) {
+        *current = Some(value);
+    }
+}
+
+pub async fn calendar_handler(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(params): Query<CalendarParams>,
+) -> Result<Json<CalendarReport>, AppError> {
+    if !state.knows_turbine(&token).await {
+        return Err(AppError::NotFound(format!("Turbine '{}' not found", token)));
+    }
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+    let unit = display_unit(&state, params.units).await;
+    let year = params.year.unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).year());
+    if !(1970..=9999).contains(&year) {
+        return Err(AppError::BadRequest(format!("Invalid year {}", year)));
+    }
+
+    let mut days: BTreeMap<NaiveDate, CalendarDay> = BTreeMap::new();
+    for record in state.catalog.read().await.iter().filter(|r| r.turbine_token == token) {
+        let Some(date) = local_day(record.timestamp, tz, year) else { continue };
+        let day = days.entry(date).or_default();
+        day.captures += 1;
+        if let Some(max_temp) = record.max_temp {
+            raise_max(&mut day.max_temp, max_temp);
+        }
+    }
+    // El histórico en disco incluye las alertas que ya no están en memoria
+    let alerts = tokio::task::spawn_blocking(load_alert_log).await??;
+    for alert in alerts.iter().filter(|a| a.turbine_token == token) {
+        let Some(date) = local_day(alert.timestamp, tz, year) else { continue };
+        let day = days.entry(date).or_default();
+        day.alerts += 1;
+        if alert.severity == Severity::Critical {
+            day.critical_alerts += 1;
+        }
+        raise_max(&mut day.max_temp, alert.max_temp);
+    }
+
+    let days: Vec<CalendarDay> = days.into_iter()
+        .map(|(date, day)| CalendarDay {
+            date: date.to_string(),
+            max_temp: day.max_temp.map(|t| unit.temp(t)),
+            ..day
+        })
+        .collect();
+    Ok(Json(CalendarReport {
+        turbine_token: token,
+        year,
+        timezone: tz.name().to_string(),
+        units: unit,
+        max_temp: days.iter().filter_map(|d| d.max_temp).reduce(f32::max),
+        total_alerts: days.iter().map(|d| d.alerts).sum(),
+        days,
+    }))
+}
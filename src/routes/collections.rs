@@ -0,0 +1,134 @@
+use crate::{
+    error::AppError,
+    state::AppState,
+    storage::collections::{save_collections, Collection},
+};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- API DE COLECCIONES ---
+// Las capturas de una colección se pueden exportar con /api/export/all?collection=<id>
+
+#[derive(Deserialize)]
+pub struct NewCollection {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    captures: Vec<String>,
+}
+
+// Capturas a añadir y a quitar de una colección
+#[derive(Deserialize)]
+pub struct CollectionUpdate {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+// Solo capturas que existen en el catálogo
+async fn check_captures(state: &AppState, captures: &[String]) -> Result<(), AppError> {
+    let catalog = state.catalog.read().await;
+    match captures.iter().find(|f| !catalog.iter().any(|r| r.filename == **f)) {
+        Some(missing) => Err(AppError::BadRequest(format!("Unknown capture '{}'", missing))),
+        None => Ok(()),
+    }
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Collection '{}' not found", id))
+}
+
+pub async fn list_collections(State(state): State<Arc<AppState>>) -> Json<Vec<Collection>> {
+    Json(state.collections.read().await.clone())
+}
+
+pub async fn get_collection(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Collection>, AppError> {
+    state.collections.read().await.iter()
+        .find(|c| c.id == id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+pub async fn create_collection(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<NewCollection>,
+) -> Result<Json<Collection>, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::BadRequest("name must not be empty".into()));
+    }
+    check_captures(&state, &request.captures).await?;
+    let now = chrono::Utc::now().timestamp() as u64;
+    let mut captures: Vec<String> = Vec::new();
+    for filename in request.captures {
+        if !captures.contains(&filename) {
+            captures.push(filename);
+        }
+    }
+    let collection = Collection {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name.trim().to_string(),
+        description: request.description,
+        captures,
+        created: now,
+        updated: now,
+    };
+    let mut collections = state.collections.write().await;
+    collections.push(collection.clone());
+    save_collections(&collections);
+    tracing::info!(id = %collection.id, name = %collection.name, "🗂️ Colección creada");
+    Ok(Json(collection))
+}
+
+pub async fn update_collection(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(update): Json<CollectionUpdate>,
+) -> Result<Json<Collection>, AppError> {
+    check_captures(&state, &update.add).await?;
+    let mut collections = state.collections.write().await;
+    let collection = collections.iter_mut().find(|c| c.id == id).ok_or_else(|| not_found(&id))?;
+    if let Some(name) = update.name.filter(|n| !n.trim().is_empty()) {
+        collection.name = name.trim().to_string();
+    }
+    if update.description.is_some() {
+        collection.description = update.description;
+    }
+    collection.captures.retain(|f| !update.remove.contains(f));
+    for filename in update.add {
+        if !collection.captures.contains(&filename) {
+            collection.captures.push(filename);
+        }
+    }
+    collection.updated = chrono::Utc::now().timestamp() as u64;
+    let updated = collection.clone();
+    save_collections(&collections);
+    Ok(Json(updated))
+}
+
+pub async fn delete_collection(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<&'static str>, AppError> {
+    let mut collections = state.collections.write().await;
+    let before = collections.len();
+    collections.retain(|c| c.id != id);
+    if collections.len() == before {
+        return Err(not_found(&id));
+    }
+    save_collections(&collections);
+    Ok(Json("Collection removed"))
+}
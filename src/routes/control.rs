@@ -0,0 +1,123 @@
+use super::devices::require_ingest_key;
+use crate::{
+    commands::{CommandStatus, RobotCommand},
+    error::AppError,
+    events::EventKind,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+// --- CONTROL MANUAL Y COMANDOS ---
+// El operador encola comandos para un robot (genéricos o los de control manual del
+// pan/escaneo); el robot los recoge con su long-poll en /ingest/commands/:token e informa
+// del avance en /ingest/commands/:token/:id.
+
+#[derive(Deserialize)]
+pub struct CommandRequest {
+    command: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+async fn enqueue(state: &AppState, token: &str, command: RobotCommand) -> Json<RobotCommand> {
+    state.commands.enqueue(token, command.clone()).await;
+    tracing::info!(turbine_token = %token, command = %command.command, id = %command.id, "📮 Comando encolado");
+    Json(command)
+}
+
+pub async fn enqueue_command(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Json(request): Json<CommandRequest>,
+) -> Result<Json<RobotCommand>, AppError> {
+    if request.command.trim().is_empty() {
+        return Err(AppError::BadRequest("command must not be empty".into()));
+    }
+    Ok(enqueue(&state, &token, RobotCommand::new(request.command.trim(), request.params)).await)
+}
+
+// Comandos recientes de la turbina con su estado
+pub async fn list_commands(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Json<Vec<RobotCommand>> {
+    Json(state.commands.recent(&token).await)
+}
+
+pub async fn get_command(
+    State(state): State<Arc<AppState>>,
+    Path((token, id)): Path<(String, String)>,
+) -> Result<Json<RobotCommand>, AppError> {
+    state.commands.get(&token, &id).await
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Command '{}' not found", id)))
+}
+
+#[derive(Deserialize)]
+pub struct GotoRequest {
+    angle: f32,
+}
+
+// Apuntar la cámara a un ángulo de pan concreto
+pub async fn goto_angle(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Json(request): Json<GotoRequest>,
+) -> Result<Json<RobotCommand>, AppError> {
+    if !request.angle.is_finite() {
+        return Err(AppError::BadRequest("angle must be a finite number".into()));
+    }
+    let angle = request.angle.rem_euclid(360.0);
+    Ok(enqueue(&state, &token, RobotCommand::new("goto_angle", json!({ "angle": angle }))).await)
+}
+
+pub async fn start_scan(State(state): State<Arc<AppState>>, Path(token): Path<String>) -> Json<RobotCommand> {
+    enqueue(&state, &token, RobotCommand::new("start_scan", serde_json::Value::Null)).await
+}
+
+pub async fn stop(State(state): State<Arc<AppState>>, Path(token): Path<String>) -> Json<RobotCommand> {
+    enqueue(&state, &token, RobotCommand::new("stop", serde_json::Value::Null)).await
+}
+
+pub async fn home(State(state): State<Arc<AppState>>, Path(token): Path<String>) -> Json<RobotCommand> {
+    enqueue(&state, &token, RobotCommand::new("home", serde_json::Value::Null)).await
+}
+
+// --- AVANCE INFORMADO POR EL ROBOT ---
+
+#[derive(Deserialize)]
+pub struct CommandUpdate {
+    status: CommandStatus,
+    #[serde(default)]
+    message: Option<String>,
+}
+
+pub async fn update_command(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((token, id)): Path<(String, String)>,
+    Json(update): Json<CommandUpdate>,
+) -> Result<Json<RobotCommand>, AppError> {
+    require_ingest_key(&state, &token, &headers).await?;
+    if matches!(update.status, CommandStatus::Pending | CommandStatus::Delivered) {
+        return Err(AppError::BadRequest("status must be acked, completed or failed".into()));
+    }
+    let command = state.commands.update(&token, &id, update.status, update.message).await
+        .ok_or_else(|| AppError::NotFound(format!("Command '{}' not found", id)))?;
+    match command.status {
+        CommandStatus::Failed => tracing::warn!(
+            turbine_token = %token, command = %command.command, message = command.message.as_deref(),
+            "⚠️ El robot no pudo ejecutar el comando"
+        ),
+        status => tracing::info!(turbine_token = %token, command = %command.command, ?status, "📬 Estado de comando actualizado"),
+    }
+    state.events.publish(&token, EventKind::Command { command: command.clone() });
+    Ok(Json(command))
+}
@@ -0,0 +1,499 @@
+use super::{admin::require_admin, web::change_author};
+use crate::{
+    analysis::{fusion::PhotoRegion, pixel_units::RawCountConversion},
+    calibration::{latest_calibrations, CameraCalibration, CorrectionPoint},
+    error::AppError,
+    schedule::parse_duration,
+    shadow::{device_shadow, DeviceShadow},
+    state::{AppState, ClockSkew, RATE_WINDOW_SEC},
+    storage::{
+        audit::{append_audit, AuditEntry},
+        cameras::valid_camera_id,
+        registry::{save_registry, TurbineInfo},
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// --- DISPOSITIVOS: CALIBRACIÓN DE CÁMARAS ---
+// Cada POST crea una versión nueva (las anteriores se conservan para explicar capturas
+// antiguas). El robot recibe las vigentes en el heartbeat y el servidor las aplica a
+// las estadísticas de las capturas que llegan después.
+
+#[derive(Deserialize)]
+pub struct NewCalibration {
+    #[serde(default)]
+    camera_id: Option<String>,
+    #[serde(default)]
+    intrinsics: Option<serde_json::Value>,
+    #[serde(default)]
+    correction: Vec<CorrectionPoint>,
+    #[serde(default)]
+    photo_region: Option<PhotoRegion>,
+    #[serde(default)]
+    raw_counts: Option<RawCountConversion>,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CameraParams {
+    #[serde(default)]
+    camera: Option<String>,
+}
+
+// Vigente de cada cámara del robot
+pub async fn get_calibration(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Json<Vec<CameraCalibration>> {
+    Json(latest_calibrations(&state.calibrations.read().await, &token))
+}
+
+pub async fn create_calibration(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<NewCalibration>,
+) -> Result<(StatusCode, Json<CameraCalibration>), AppError> {
+    if let Some(camera_id) = request.camera_id.as_deref().filter(|c| !valid_camera_id(c)) {
+        return Err(AppError::BadRequest(format!("Invalid camera_id '{}'", camera_id)));
+    }
+    if request.intrinsics.is_none() && request.correction.is_empty() && request.photo_region.is_none() && request.raw_counts.is_none() {
+        return Err(AppError::BadRequest(
+            "Calibration needs intrinsics, a correction curve, a photo region or a raw_counts conversion".into(),
+        ));
+    }
+    let calibration = CameraCalibration {
+        turbine_token: token,
+        camera_id: request.camera_id,
+        version: 0,
+        created: chrono::Utc::now().timestamp() as u64,
+        author: change_author(&headers),
+        intrinsics: request.intrinsics,
+        correction: request.correction,
+        photo_region: request.photo_region,
+        raw_counts: request.raw_counts,
+        notes: request.notes,
+    };
+    calibration.validate().map_err(AppError::BadRequest)?;
+    let calibration = state.add_calibration(calibration).await?;
+    Ok((StatusCode::CREATED, Json(calibration)))
+}
+
+// Todas las versiones de una cámara, la más reciente primero
+pub async fn get_calibration_history(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(params): Query<CameraParams>,
+) -> Json<Vec<CameraCalibration>> {
+    Json(state.calibrations.read().await.iter().rev()
+        .filter(|c| c.turbine_token == token && c.camera_id == params.camera)
+        .cloned()
+        .collect())
+}
+
+pub async fn get_calibration_version(
+    State(state): State<Arc<AppState>>,
+    Path((token, version)): Path<(String, u64)>,
+    Query(params): Query<CameraParams>,
+) -> Result<Json<CameraCalibration>, AppError> {
+    state.calibrations.read().await.iter()
+        .find(|c| c.turbine_token == token && c.camera_id == params.camera && c.version == version)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Calibration version {} not found", version)))
+}
+
+// --- DISPOSITIVOS: RELOJ ---
+
+#[derive(Deserialize)]
+pub struct ClockParams {
+    // Solo los robots con el reloj desincronizado
+    #[serde(default)]
+    flagged: bool,
+}
+
+// Último desfase medido de cada robot, el mayor primero
+pub async fn get_clock_skew(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ClockParams>,
+) -> Json<Vec<ClockSkew>> {
+    let mut skews: Vec<ClockSkew> = state.clock_skew.read().await.values()
+        .filter(|s| !params.flagged || s.flagged)
+        .cloned()
+        .collect();
+    skews.sort_by_key(|s| std::cmp::Reverse(s.offset_sec.unsigned_abs()));
+    Json(skews)
+}
+
+// --- DISPOSITIVOS: RITMO DE SUBIDAS ---
+
+#[derive(Serialize)]
+pub struct IngestRateSummary {
+    turbine_token: String,
+    // Subidas aceptadas en el último minuto
+    uploads_last_min: usize,
+    limit_per_min: u32,
+    flooding: bool,
+    flood_started: Option<u64>,
+    last_rejected: Option<u64>,
+    rejected: u64,
+}
+
+// Ritmo de subidas de cada robot, los que están inundando primero
+pub async fn get_ingest_rates(State(state): State<Arc<AppState>>) -> Json<Vec<IngestRateSummary>> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let mut rates: Vec<IngestRateSummary> = state.ingest_rates.read().await.values()
+        .map(|r| IngestRateSummary {
+            turbine_token: r.turbine_token.clone(),
+            uploads_last_min: r.recent.iter().filter(|&&t| t + RATE_WINDOW_SEC > now).count(),
+            limit_per_min: state.settings.ingest_rate_limit_per_min,
+            flooding: r.flood_started.is_some(),
+            flood_started: r.flood_started,
+            last_rejected: r.last_rejected,
+            rejected: r.rejected,
+        })
+        .collect();
+    rates.sort_by_key(|r| (!r.flooding, std::cmp::Reverse(r.uploads_last_min)));
+    Json(rates)
+}
+
+// --- DISPOSITIVOS: CLAVES DE INGESTA ---
+// Los robots con clave emitida deben enviarla en X-Ingest-Key en todas las llamadas a
+// /ingest; los que nunca han tenido una siguen aceptándose sin ella.
+
+pub const INGEST_KEY_HEADER: &str = "x-ingest-key";
+
+pub async fn require_ingest_key(state: &AppState, turbine_token: &str, headers: &HeaderMap) -> Result<(), AppError> {
+    let key = headers.get(INGEST_KEY_HEADER).and_then(|v| v.to_str().ok());
+    if state.ingest_key_valid(turbine_token, key).await == Some(false) {
+        tracing::warn!(%turbine_token, "🔒 Petición de ingesta con clave ausente o no válida");
+        return Err(AppError::Unauthorized("Invalid ingest key"));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct RotateParams {
+    // Validez de la clave anterior ("2h", "7d"; 0 la revoca ya). Por defecto
+    // --key-rotation-grace-sec
+    grace: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RotatedKey {
+    turbine_token: String,
+    // Solo se muestra ahora
+    key: String,
+    key_id: String,
+    created: u64,
+    // Hasta cuándo se acepta la clave anterior (None si no tenía)
+    previous_valid_until: Option<u64>,
+}
+
+pub async fn rotate_ingest_key(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+    Query(params): Query<RotateParams>,
+) -> Result<(StatusCode, Json<RotatedKey>), AppError> {
+    require_admin(&state, &headers)?;
+    if token.trim().is_empty() {
+        return Err(AppError::BadRequest("Invalid turbine token".into()));
+    }
+    let grace_sec = match params.grace.as_deref() {
+        Some(grace) => parse_duration(grace).map_err(AppError::BadRequest)?,
+        None => state.settings.key_rotation_grace_sec,
+    };
+    let (key, credential, previous_valid_until) = state.rotate_ingest_key(&token, grace_sec).await;
+    append_audit(&AuditEntry::new("rotate_ingest_key", &token, serde_json::json!({
+        "key_id": credential.key_id,
+        "previous_valid_until": previous_valid_until,
+    })));
+    tracing::info!(turbine_token = %token, key_id = %credential.key_id, ?previous_valid_until, "🔑 Clave de ingesta rotada");
+    Ok((StatusCode::CREATED, Json(RotatedKey {
+        turbine_token: token,
+        key,
+        key_id: credential.key_id,
+        created: credential.created,
+        previous_valid_until,
+    })))
+}
+
+// --- DISPOSITIVOS: SOMBRA ---
+// GET /api/devices/:token/shadow: lo deseado frente a lo notificado por el robot (ver
+// crate::shadow), para ver en el dashboard qué ajustes tiene aplicados y qué está pendiente.
+
+pub async fn get_shadow(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<DeviceShadow>, AppError> {
+    device_shadow(&state, &token).await
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Device '{}' not found", token)))
+}
+
+// --- DISPOSITIVOS: ALTA MASIVA ---
+// POST /api/devices/import: registra (o actualiza) un parque entero en una llamada durante
+// la puesta en marcha. El cuerpo es JSON (una lista de turbinas, o {"turbines": [...]}) o,
+// con Content-Type text/csv, un CSV con cabecera cuyas columnas son los campos del registro
+// (lat/lon valen por latitude/longitude; sensor_width y sensor_height dan la resolución).
+// Se valida todo antes de tocar nada: con cualquier fila errónea no se aplica ninguna, y
+// con dry_run=true solo se informa de lo que se haría. La importación real queda auditada.
+
+const MAX_IMPORT_TURBINES: usize = 5000;
+
+const TEXT_COLUMNS: [&str; 4] = ["token", "name", "site", "model"];
+const NUMBER_COLUMNS: [&str; 4] = ["latitude", "longitude", "hub_height_m", "temp_offset"];
+const INTEGER_COLUMNS: [&str; 4] = ["modbus_slot", "offline_after_sec", "sensor_width", "sensor_height"];
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum ImportAction {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+#[derive(Serialize)]
+pub struct ImportRow {
+    // Posición en el fichero (1 = primera turbina, sin contar la cabecera del CSV)
+    row: usize,
+    token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<ImportAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportReport {
+    dry_run: bool,
+    // false si alguna fila tiene error (o en dry_run): no se ha registrado nada
+    applied: bool,
+    created: usize,
+    updated: usize,
+    unchanged: usize,
+    errors: usize,
+    rows: Vec<ImportRow>,
+}
+
+// Registros de un CSV (RFC 4180: campos entre comillas con comas, saltos de línea y ""),
+// sin las líneas vacías
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let (mut records, mut record, mut field) = (Vec::new(), Vec::new(), String::new());
+    let (mut chars, mut quoted) = (text.trim_start_matches('\u{feff}').chars().peekable(), false);
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".into());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r: &Vec<String>| r.iter().any(|f| !f.trim().is_empty()));
+    Ok(records)
+}
+
+fn csv_column(header: &str) -> Result<&'static str, String> {
+    let name = header.trim().to_ascii_lowercase();
+    let name = match name.as_str() {
+        "lat" => "latitude",
+        "lon" | "lng" => "longitude",
+        other => other,
+    };
+    TEXT_COLUMNS.iter().chain(&NUMBER_COLUMNS).chain(&INTEGER_COLUMNS)
+        .find(|c| **c == name)
+        .copied()
+        .ok_or_else(|| format!("unknown column '{}'", header.trim()))
+}
+
+// Una fila del CSV como el JSON de una turbina; las celdas vacías son campos ausentes
+fn csv_turbine(columns: &[&'static str], record: &[String]) -> Result<serde_json::Value, String> {
+    if record.len() != columns.len() {
+        return Err(format!("expected {} fields, found {}", columns.len(), record.len()));
+    }
+    let mut turbine = serde_json::Map::new();
+    for (&column, value) in columns.iter().zip(record) {
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        let parsed = if TEXT_COLUMNS.contains(&column) {
+            serde_json::json!(value)
+        } else if NUMBER_COLUMNS.contains(&column) {
+            value.parse::<f64>().ok().filter(|v| v.is_finite()).map(|v| serde_json::json!(v))
+                .ok_or_else(|| format!("{}: '{}' is not a number", column, value))?
+        } else {
+            value.parse::<u64>().map(|v| serde_json::json!(v))
+                .map_err(|_| format!("{}: '{}' is not a non-negative integer", column, value))?
+        };
+        turbine.insert(column.to_string(), parsed);
+    }
+    match (turbine.remove("sensor_width"), turbine.remove("sensor_height")) {
+        (Some(width), Some(height)) => {
+            turbine.insert("sensor_resolution".into(), serde_json::json!({ "width": width, "height": height }));
+        }
+        (None, None) => {}
+        _ => return Err("sensor_width and sensor_height must be given together".into()),
+    }
+    Ok(serde_json::Value::Object(turbine))
+}
+
+// Cada elemento por separado, para señalar las filas que no son una turbina válida
+fn import_entries(headers: &HeaderMap, body: &str) -> Result<Vec<serde_json::Value>, AppError> {
+    let csv = headers.get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|c| c.starts_with("text/csv"));
+    if !csv {
+        let value: serde_json::Value = serde_json::from_str(body)
+            .map_err(|e| AppError::BadRequest(format!("Invalid JSON: {}", e)))?;
+        return match value {
+            serde_json::Value::Array(entries) => Ok(entries),
+            serde_json::Value::Object(mut object) => match object.remove("turbines") {
+                Some(serde_json::Value::Array(entries)) => Ok(entries),
+                _ => Err(AppError::BadRequest("Expected a list of turbines or {\"turbines\": [...]}".into())),
+            },
+            _ => Err(AppError::BadRequest("Expected a list of turbines or {\"turbines\": [...]}".into())),
+        };
+    }
+    let records = parse_csv(body).map_err(|e| AppError::BadRequest(format!("Invalid CSV: {}", e)))?;
+    let Some((header, rows)) = records.split_first() else {
+        return Err(AppError::BadRequest("Invalid CSV: missing header row".into()));
+    };
+    let columns = header.iter().map(|h| csv_column(h)).collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::BadRequest(format!("Invalid CSV: {}", e)))?;
+    if let Some(column) = columns.iter().enumerate().find(|(i, c)| columns[..*i].contains(c)).map(|(_, c)| c) {
+        return Err(AppError::BadRequest(format!("Invalid CSV: column '{}' is repeated", column)));
+    }
+    // Las filas mal formadas llegan como texto, para que fallen al convertirlas en turbina
+    Ok(rows.iter().map(|r| csv_turbine(&columns, r).unwrap_or_else(serde_json::Value::String)).collect())
+}
+
+pub async fn import_devices(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ImportParams>,
+    body: String,
+) -> Result<(StatusCode, Json<ImportReport>), AppError> {
+    require_admin(&state, &headers)?;
+    let entries = import_entries(&headers, &body)?;
+    if entries.is_empty() {
+        return Err(AppError::BadRequest("No turbines to import".into()));
+    }
+    if entries.len() > MAX_IMPORT_TURBINES {
+        return Err(AppError::BadRequest(format!("At most {} turbines per import", MAX_IMPORT_TURBINES)));
+    }
+
+    // El registro queda bloqueado de la validación a la escritura: nadie cambia un slot
+    // Modbus entre medias
+    let mut turbines = state.turbines.write().await;
+    let mut rows = Vec::with_capacity(entries.len());
+    let mut valid: Vec<TurbineInfo> = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.into_iter().enumerate() {
+        let parsed = match entry {
+            serde_json::Value::String(e) => Err(e),
+            entry => serde_json::from_value::<TurbineInfo>(entry).map_err(|e| e.to_string()),
+        };
+        let checked = parsed.and_then(|turbine| {
+            turbine.validate()?;
+            if valid.iter().any(|t| t.token == turbine.token) {
+                return Err(format!("turbine '{}' appears twice in the import", turbine.token));
+            }
+            if let Some(slot) = turbine.modbus_slot
+                && let Some(other) = valid.iter().find(|t| t.modbus_slot == Some(slot))
+            {
+                return Err(format!("modbus_slot {} is already used by turbine '{}'", slot, other.token));
+            }
+            Ok(turbine)
+        });
+        let row = i + 1;
+        match checked {
+            Ok(turbine) => {
+                let action = match turbines.iter().find(|t| t.token == turbine.token) {
+                    None => ImportAction::Created,
+                    Some(t) if serde_json::to_value(t).ok() == serde_json::to_value(&turbine).ok() => ImportAction::Unchanged,
+                    Some(_) => ImportAction::Updated,
+                };
+                rows.push(ImportRow { row, token: Some(turbine.token.clone()), action: Some(action), error: None });
+                valid.push(turbine);
+            }
+            Err(error) => rows.push(ImportRow { row, token: None, action: None, error: Some(error) }),
+        }
+    }
+    // Frente al registro cuentan solo las turbinas que no se importan: una existente que
+    // también viene en el fichero puede cederle su slot a otra fila
+    for row in rows.iter_mut().filter(|r| r.error.is_none()) {
+        let Some(turbine) = valid.iter().find(|t| Some(&t.token) == row.token.as_ref()) else { continue };
+        let Some(slot) = turbine.modbus_slot else { continue };
+        if let Some(other) = turbines.iter().find(|t| t.modbus_slot == Some(slot) && !valid.iter().any(|v| v.token == t.token)) {
+            row.error = Some(format!("modbus_slot {} is already used by turbine '{}'", slot, other.token));
+            row.action = None;
+        }
+    }
+
+    let count = |action: ImportAction| rows.iter().filter(|r| r.action == Some(action)).count();
+    let errors = rows.iter().filter(|r| r.error.is_some()).count();
+    let mut report = ImportReport {
+        dry_run: params.dry_run,
+        applied: false,
+        created: count(ImportAction::Created),
+        updated: count(ImportAction::Updated),
+        unchanged: count(ImportAction::Unchanged),
+        errors,
+        rows,
+    };
+    if errors > 0 {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(report)));
+    }
+    if params.dry_run {
+        return Ok((StatusCode::OK, Json(report)));
+    }
+
+    for turbine in valid {
+        match turbines.iter_mut().find(|t| t.token == turbine.token) {
+            Some(existing) => *existing = turbine,
+            None => turbines.push(turbine),
+        }
+    }
+    save_registry(&turbines);
+    drop(turbines);
+    report.applied = true;
+    append_audit(&AuditEntry::new("import_devices", "registry", serde_json::json!({
+        "author": change_author(&headers),
+        "created": report.created,
+        "updated": report.updated,
+        "unchanged": report.unchanged,
+    })));
+    tracing::info!(created = report.created, updated = report.updated, unchanged = report.unchanged, "🗺️ Turbinas importadas en bloque");
+    Ok((StatusCode::OK, Json(report)))
+}
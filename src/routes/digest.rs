@@ -0,0 +1,211 @@
+use super::web::{display_tz, display_unit};
+use crate::{
+    error::AppError,
+    schedule::{format_timestamp, parse_duration},
+    state::{AlertRecord, AppState},
+    storage::alert_log::alert_history,
+    thresholds::Severity,
+    units::TempUnit,
+};
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+// --- RESUMEN DE ALERTAS PARA BOTS DE CHAT ---
+// GET /api/alerts/digest?window=1h&group_by=turbine: las alertas de la última ventana
+// agrupadas (turbine, site, zone o severity) con una línea por grupo lista para publicar
+// ("T42: 6 alerts (2 distinct, 1 critical), peak 91.0 °C at 130°"), para que los bots que
+// publican en los canales de chat cada cierto tiempo no repitan la agrupación. Las
+// alertas repetidas del mismo punto cuentan una vez en `distinct`: las de una misma
+// incidencia o, sin incidencia, las de la misma turbina y sector de ANGLE_BUCKET_DEG
+// grados. Con ?format=text responde solo las líneas en texto plano; ?min_severity=critical
+// deja fuera los avisos. Temperaturas en ?units= y horas en ?tz=, como /api/alerts.
+
+const DEFAULT_WINDOW: &str = "1h";
+const MAX_WINDOW_SEC: u64 = 30 * 86_400;
+// Ancho del sector de ángulo que agrupa alertas repetidas sin incidencia
+const ANGLE_BUCKET_DEG: f32 = 5.0;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestGrouping {
+    #[default]
+    Turbine,
+    Site,
+    Zone,
+    Severity,
+}
+
+#[derive(Deserialize)]
+pub struct DigestParams {
+    window: Option<String>,
+    #[serde(default)]
+    group_by: DigestGrouping,
+    min_severity: Option<Severity>,
+    // json (por defecto) o text
+    format: Option<String>,
+    units: Option<TempUnit>,
+    tz: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DigestGroup {
+    key: String,
+    alerts: usize,
+    // Sin contar las repetidas del mismo punto
+    distinct: usize,
+    critical: usize,
+    // Turbinas afectadas (en una agrupación por turbina, solo ella)
+    turbines: Vec<String>,
+    peak_temp: f32,
+    peak_turbine: String,
+    peak_angle: f32,
+    peak_time: String,
+    first_time: String,
+    last_time: String,
+    line: String,
+}
+
+#[derive(Serialize)]
+pub struct AlertDigest {
+    from: String,
+    to: String,
+    window_sec: u64,
+    alerts: usize,
+    groups: Vec<DigestGroup>,
+    // Todas las líneas, listas para publicar
+    text: String,
+}
+
+// Marca de un punto para descartar las alertas repetidas
+fn dedup_key(alert: &AlertRecord) -> String {
+    match &alert.incident_id {
+        Some(incident) => format!("incident:{}", incident),
+        None => format!("{}@{}", alert.turbine_token, (alert.angle.rem_euclid(360.0) / ANGLE_BUCKET_DEG).floor() as i32),
+    }
+}
+
+fn group_line(key: &str, count: usize, distinct: usize, critical: usize, peak: &AlertRecord, unit: TempUnit, by: DigestGrouping) -> String {
+    let plural = if count == 1 { "alert" } else { "alerts" };
+    let mut details = vec![format!("{} distinct", distinct)];
+    if critical > 0 && by != DigestGrouping::Severity {
+        details.push(format!("{} critical", critical));
+    }
+    // Fuera de la agrupación por turbina, el pico dice en cuál fue
+    let location = match by {
+        DigestGrouping::Turbine => String::new(),
+        _ => format!(" on {}", peak.turbine_token),
+    };
+    format!(
+        "{}: {} {} ({}), peak {:.1} {}{} at {:.0}°",
+        key,
+        count,
+        plural,
+        details.join(", "),
+        unit.temp(peak.max_temp),
+        unit.symbol(),
+        location,
+        peak.angle,
+    )
+}
+
+pub async fn alert_digest_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DigestParams>,
+) -> Result<Response, AppError> {
+    let window_sec = parse_duration(params.window.as_deref().unwrap_or(DEFAULT_WINDOW)).map_err(AppError::BadRequest)?;
+    if !(1..=MAX_WINDOW_SEC).contains(&window_sec) {
+        return Err(AppError::BadRequest(format!("window must be at most {}s (30 days)", MAX_WINDOW_SEC)));
+    }
+    let text_only = match params.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "text" => true,
+        other => return Err(AppError::BadRequest(format!("Unknown digest format '{}' (json|text)", other))),
+    };
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+    let unit = display_unit(&state, params.units).await;
+    let now = chrono::Utc::now().timestamp() as u64;
+    let from = now.saturating_sub(window_sec);
+
+    // Las alertas en memoria bastan si cubren toda la ventana; si no, el histórico
+    let recent = {
+        let alerts = state.alerts.read().await;
+        alerts.iter().map(|a| a.timestamp).min().filter(|&oldest| oldest <= from)
+            .map(|_| alerts.iter().filter(|a| a.timestamp >= from).cloned().collect::<Vec<_>>())
+    };
+    let mut alerts = match recent {
+        Some(alerts) => alerts,
+        None => alert_history(&state).await?.into_iter().filter(|a| a.timestamp >= from).collect(),
+    };
+    alerts.retain(|a| params.min_severity.is_none_or(|min| a.severity >= min));
+    alerts.sort_by_key(|a| a.timestamp);
+
+    let sites: BTreeMap<String, String> = match params.group_by {
+        DigestGrouping::Site => state.turbines.read().await.iter()
+            .filter_map(|t| t.site.clone().map(|site| (t.token.clone(), site)))
+            .collect(),
+        _ => BTreeMap::new(),
+    };
+    let mut grouped: BTreeMap<String, Vec<&AlertRecord>> = BTreeMap::new();
+    for alert in &alerts {
+        let key = match params.group_by {
+            DigestGrouping::Turbine => alert.turbine_token.clone(),
+            DigestGrouping::Site => sites.get(&alert.turbine_token).cloned().unwrap_or_else(|| "no site".into()),
+            DigestGrouping::Zone => alert.zone.clone().unwrap_or_else(|| "no zone".into()),
+            DigestGrouping::Severity => match alert.severity {
+                Severity::Warning => "warning".into(),
+                Severity::Critical => "critical".into(),
+            },
+        };
+        grouped.entry(key).or_default().push(alert);
+    }
+
+    let mut groups: Vec<DigestGroup> = grouped.into_iter()
+        .filter_map(|(key, group)| {
+            let peak = *group.iter().max_by(|a, b| a.max_temp.total_cmp(&b.max_temp))?;
+            let distinct = group.iter().map(|a| dedup_key(a)).collect::<HashSet<_>>().len();
+            let critical = group.iter().filter(|a| a.severity == Severity::Critical).count();
+            let mut turbines: Vec<String> = group.iter().map(|a| a.turbine_token.clone()).collect();
+            turbines.sort();
+            turbines.dedup();
+            Some(DigestGroup {
+                line: group_line(&key, group.len(), distinct, critical, peak, unit, params.group_by),
+                alerts: group.len(),
+                distinct,
+                critical,
+                turbines,
+                peak_temp: unit.temp(peak.max_temp),
+                peak_turbine: peak.turbine_token.clone(),
+                peak_angle: peak.angle,
+                peak_time: format_timestamp(peak.timestamp, tz),
+                first_time: format_timestamp(group[0].timestamp, tz),
+                last_time: format_timestamp(group[group.len() - 1].timestamp, tz),
+                key,
+            })
+        })
+        .collect();
+    // Lo más grave arriba: primero los grupos con críticas, luego por temperatura
+    groups.sort_by(|a, b| b.critical.min(1).cmp(&a.critical.min(1)).then(b.peak_temp.total_cmp(&a.peak_temp)));
+
+    let text = groups.iter().map(|g| g.line.as_str()).collect::<Vec<_>>().join("\n");
+    if text_only {
+        return Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text).into_response());
+    }
+    Ok(Json(AlertDigest {
+        from: format_timestamp(from, tz),
+        to: format_timestamp(now, tz),
+        window_sec,
+        alerts: alerts.len(),
+        groups,
+        text,
+    })
+    .into_response())
+}
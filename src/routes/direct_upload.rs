@@ -0,0 +1,189 @@
+use super::{
+    devices::require_ingest_key,
+    ingest::{ingest_capture, CaptureUpload, UploadResponse},
+};
+use crate::{analysis::pixel_units::PixelUnit, error::AppError, objectstore::S3Bucket, state::AppState, storage::catalog::sha256_hex};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// --- SUBIDAS DIRECTAS A S3 ---
+// Para capturas de cientos de MB el robot puede evitar el enlace con el servidor:
+// 1. POST /ingest/upload/init con los mismos datos del formulario de /ingest/upload (sin
+//    el archivo) devuelve upload_id y una URL prefirmada a la que hace PUT de la captura.
+// 2. POST /ingest/upload/complete con upload_id: el servidor descarga el objeto del
+//    bucket, lo borra y lo procesa como una subida normal (catálogo, análisis, alertas);
+//    responde lo mismo que /ingest/upload, recibo incluido.
+// Las subidas pendientes viven en memoria: tras un reinicio complete devuelve 404 y el
+// robot vuelve a empezar (su copia local sigue ahí hasta tener el recibo).
+
+// Margen tras caducar la URL para llamar a complete (una subida lenta que empezó a tiempo)
+const COMPLETE_GRACE_SEC: u64 = 3600;
+
+#[derive(Clone, Debug)]
+pub struct DirectUpload {
+    pub turbine_token: String,
+    pub angle: f32,
+    pub rotor_phase: Option<f32>,
+    pub camera_id: Option<String>,
+    // SHA-256 declarado por el robot; se comprueba al completar
+    pub sha256: Option<String>,
+    pub pixel_unit: PixelUnit,
+    pub key: String,
+    pub expires_at: u64,
+}
+
+#[derive(Deserialize)]
+pub struct InitRequest {
+    turbine_token: String,
+    #[serde(default)]
+    angle: f32,
+    #[serde(default)]
+    rotor_phase: Option<f32>,
+    #[serde(default)]
+    camera_id: Option<String>,
+    #[serde(default)]
+    sha256: Option<String>,
+    #[serde(default)]
+    pixel_unit: PixelUnit,
+}
+
+#[derive(Serialize)]
+pub struct InitResponse {
+    upload_id: String,
+    method: &'static str,
+    url: String,
+    expires_at: u64,
+    max_bytes: u64,
+}
+
+#[derive(Deserialize)]
+pub struct CompleteRequest {
+    turbine_token: String,
+    upload_id: String,
+}
+
+fn bucket(state: &AppState) -> Result<S3Bucket, AppError> {
+    S3Bucket::from_settings(&state.settings)
+        .ok_or_else(|| AppError::NotFound("Direct uploads are not enabled on this server".into()))
+}
+
+pub async fn init_upload_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<InitRequest>,
+) -> Result<Json<InitResponse>, AppError> {
+    let bucket = bucket(&state)?;
+    require_ingest_key(&state, &request.turbine_token, &headers).await?;
+    state.admit_upload(&request.turbine_token).await
+        .map_err(|retry_after_sec| AppError::TooManyRequests { retry_after_sec })?;
+    if let Some(sha256) = &request.sha256
+        && (sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()))
+    {
+        return Err(AppError::BadRequest("sha256 must be 64 hex characters".into()));
+    }
+
+    let upload_id = uuid::Uuid::new_v4().to_string();
+    let key = format!("{}{}/{}.npz", state.settings.s3_prefix, request.turbine_token, upload_id);
+    let url = bucket.presign("PUT", &key, state.settings.s3_upload_expiry_sec).map_err(AppError::Internal)?;
+    let now = chrono::Utc::now().timestamp() as u64;
+    let expires_at = now + state.settings.s3_upload_expiry_sec;
+    tracing::info!(turbine_token = %request.turbine_token, %upload_id, "☁️ Subida directa a S3 iniciada");
+    let upload = DirectUpload {
+        turbine_token: request.turbine_token,
+        angle: request.angle,
+        rotor_phase: request.rotor_phase,
+        camera_id: request.camera_id.filter(|c| !c.is_empty()),
+        sha256: request.sha256.map(|s| s.to_ascii_lowercase()),
+        pixel_unit: request.pixel_unit,
+        key,
+        expires_at,
+    };
+    {
+        let mut pending = state.direct_uploads.write().await;
+        pending.retain(|_, u| u.expires_at + COMPLETE_GRACE_SEC >= now);
+        pending.insert(upload_id.clone(), upload);
+    }
+    Ok(Json(InitResponse {
+        upload_id,
+        method: "PUT",
+        url,
+        expires_at,
+        max_bytes: state.settings.s3_max_upload_mb * 1024 * 1024,
+    }))
+}
+
+pub async fn complete_upload_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CompleteRequest>,
+) -> Result<(StatusCode, Json<UploadResponse>), AppError> {
+    let bucket = bucket(&state)?;
+    require_ingest_key(&state, &request.turbine_token, &headers).await?;
+    let upload = state.direct_uploads.read().await.get(&request.upload_id)
+        .filter(|u| u.turbine_token == request.turbine_token)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Upload '{}' not found or expired", request.upload_id)))?;
+
+    // Misma cola y ranuras que /ingest/upload
+    let Ok(_admission) = state.upload_admission.clone().try_acquire_owned() else {
+        tracing::warn!("⏳ Cola de subidas llena, se rechaza la subida directa con 503.");
+        return Err(AppError::Busy { retry_after_sec: state.settings.upload_retry_after_sec });
+    };
+    let _slot = state.upload_slots.acquire().await
+        .map_err(|_| AppError::Internal("upload semaphore closed".into()))?;
+
+    let max_bytes = state.settings.s3_max_upload_mb * 1024 * 1024;
+    let data = match bucket.fetch(&state.http, &upload.key, max_bytes).await {
+        Ok(Some(data)) => data,
+        Ok(None) => return Err(AppError::BadRequest("Object not uploaded yet; PUT it to the presigned URL first".into())),
+        Err(e) => {
+            tracing::error!(upload_id = %request.upload_id, error = %e, "❌ Error descargando la subida directa de S3");
+            return Err(AppError::Internal(format!("could not fetch upload from object storage: {}", e)));
+        }
+    };
+    if let Some(expected) = &upload.sha256
+        && sha256_hex(&data) != *expected
+    {
+        // El objeto no sirve: se borra para que el robot lo suba de nuevo con otro init
+        state.direct_uploads.write().await.remove(&request.upload_id);
+        delete_object(&state, bucket, upload.key);
+        return Err(AppError::BadRequest("Uploaded object does not match the declared sha256".into()));
+    }
+
+    let capture = CaptureUpload {
+        turbine_token: upload.turbine_token,
+        angle: upload.angle,
+        rotor_phase: upload.rotor_phase,
+        camera_id: upload.camera_id,
+        source: None,
+        captured_at: None,
+        force: false,
+        receipt_id: None,
+        data: Bytes::from(data),
+        photo: None,
+        pixel_unit: upload.pixel_unit,
+    };
+    let response = ingest_capture(&state, capture).await?;
+    // Procesada (o en cuarentena, con su copia): el objeto del bucket ya no hace falta
+    state.direct_uploads.write().await.remove(&request.upload_id);
+    delete_object(&state, bucket, upload.key);
+    tracing::info!(upload_id = %request.upload_id, status = %response.status, "☁️ Subida directa completada");
+    Ok((response.status_code(), Json(response)))
+}
+
+// Borrado en segundo plano; si falla queda en el bucket (una regla de ciclo de vida sobre
+// --s3-prefix lo limpia)
+fn delete_object(state: &AppState, bucket: S3Bucket, key: String) {
+    let http = state.http.clone();
+    tokio::spawn(async move {
+        if let Err(e) = bucket.delete(&http, &key).await {
+            tracing::warn!(%key, error = %e, "⚠️ No se pudo borrar la subida directa del bucket");
+        }
+    });
+}
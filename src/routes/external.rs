@@ -0,0 +1,169 @@
+use super::ingest::{ingest_capture, read_part, CaptureUpload, UploadResponse};
+use crate::{analysis::pixel_units::PixelUnit, error::AppError, schedule::parse_timestamp, state::AppState};
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Multipart, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- INGESTA DE FUENTES EXTERNAS ---
+// POST /ingest/external: drones y cámaras de mano de las contratas alimentan el mismo
+// catálogo y la misma evaluación de alertas que los robots. Autenticación con
+// "Authorization: Bearer <SENTINEL_EXTERNAL_INGEST_TOKEN>"; la turbina debe estar
+// registrada. Dos formatos:
+//
+// - JSON (Content-Type: application/json):
+//   {"turbine_token": "T42", "source": "acme-drone-07", "data_base64": "<npy>",
+//    "angle": 90.0, "camera_id": "drone", "captured_at": 1760000000}
+// - multipart/form-data con los mismos campos de texto y el archivo en "dataset_file"
+//
+// La captura es un .npy float32 en °C (alto × ancho o frames × alto × ancho), igual que
+// la de los robots. angle (grados, por defecto 0), camera_id, captured_at (segundos
+// Unix o RFC 3339; por defecto, el instante de la subida) y pixel_unit (celsius, kelvin
+// o raw_counts; ver analysis::pixel_units) son opcionales. Los códigos
+// de respuesta son los de /ingest/upload.
+
+// Margen hacia el futuro que se admite en captured_at (relojes de campo sin sincronizar)
+const MAX_FUTURE_SKEW_SEC: u64 = 300;
+
+#[derive(Deserialize, Default)]
+pub struct ExternalCapture {
+    turbine_token: String,
+    source: String,
+    #[serde(default)]
+    data_base64: Option<String>,
+    #[serde(default)]
+    angle: Option<f32>,
+    #[serde(default)]
+    camera_id: Option<String>,
+    #[serde(default)]
+    captured_at: Option<String>,
+    #[serde(default)]
+    pixel_unit: PixelUnit,
+}
+
+// Nombre de la fuente tal como queda en el catálogo
+fn valid_source(source: &str) -> bool {
+    !source.is_empty()
+        && source.len() <= 64
+        && source.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn require_external_token(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(expected) = state.settings.external_ingest_token.as_deref().filter(|t| !t.is_empty()) else {
+        return Err(AppError::Forbidden("External ingestion disabled: set SENTINEL_EXTERNAL_INGEST_TOKEN"));
+    };
+    let provided = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided != Some(expected) {
+        return Err(AppError::Unauthorized("Invalid external ingest token"));
+    }
+    Ok(())
+}
+
+const EXTERNAL_ROUTE: &str = "/ingest/external";
+
+async fn read_multipart(state: &AppState, mut multipart: Multipart) -> Result<(ExternalCapture, Option<Bytes>), AppError> {
+    let mut capture = ExternalCapture::default();
+    let mut data = None;
+    while let Some(field) = read_part(state, EXTERNAL_ROUTE, multipart.next_field()).await? {
+        let name = field.name().unwrap_or_default().to_string();
+        if name == "dataset_file" {
+            data = Some(read_part(state, EXTERNAL_ROUTE, field.bytes()).await?);
+            continue;
+        }
+        let text = read_part(state, EXTERNAL_ROUTE, field.text()).await?;
+        match name.as_str() {
+            "turbine_token" => capture.turbine_token = text,
+            "source" => capture.source = text,
+            "angle" => {
+                let angle = text.parse().map_err(|_| AppError::BadRequest(format!("Invalid angle '{}'", text)))?;
+                capture.angle = Some(angle);
+            }
+            "camera_id" => capture.camera_id = Some(text).filter(|t| !t.is_empty()),
+            "captured_at" => capture.captured_at = Some(text).filter(|t| !t.is_empty()),
+            "pixel_unit" => capture.pixel_unit = PixelUnit::parse(&text).map_err(AppError::BadRequest)?,
+            _ => {}
+        }
+    }
+    Ok((capture, data))
+}
+
+pub async fn external_upload_handler(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+) -> Result<(StatusCode, Json<UploadResponse>), AppError> {
+    require_external_token(&state, request.headers())?;
+    let is_multipart = request.headers().get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("multipart/form-data"));
+    let (capture, data) = if is_multipart {
+        let multipart = Multipart::from_request(request, &state).await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+        read_multipart(&state, multipart).await?
+    } else {
+        let Json(capture) = Json::<ExternalCapture>::from_request(request, &state).await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+        let data = match capture.data_base64.as_deref() {
+            Some(encoded) => Some(Bytes::from(
+                STANDARD.decode(encoded.trim()).map_err(|e| AppError::BadRequest(format!("Invalid data_base64: {}", e)))?,
+            )),
+            None => None,
+        };
+        (capture, data)
+    };
+    tracing::Span::current().record("turbine_token", capture.turbine_token.as_str());
+
+    if !valid_source(&capture.source) {
+        return Err(AppError::BadRequest("source is required (letters, digits, '-', '_' and '.', up to 64)".into()));
+    }
+    if !state.turbines.read().await.iter().any(|t| t.token == capture.turbine_token) {
+        return Err(AppError::NotFound(format!("Turbine '{}' not registered", capture.turbine_token)));
+    }
+    let angle = capture.angle.unwrap_or(0.0);
+    if !angle.is_finite() {
+        return Err(AppError::BadRequest("Invalid angle".into()));
+    }
+    let now = chrono::Utc::now().timestamp() as u64;
+    let captured_at = capture.captured_at.as_deref()
+        .map(parse_timestamp)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+    if captured_at.is_some_and(|t| t > now + MAX_FUTURE_SKEW_SEC) {
+        return Err(AppError::BadRequest("captured_at is in the future".into()));
+    }
+    let Some(data) = data else {
+        return Err(AppError::BadRequest("Missing capture data (data_base64 or dataset_file)".into()));
+    };
+
+    // Mismas colas y límite por turbina que las subidas de los robots
+    let Ok(_admission) = state.upload_admission.clone().try_acquire_owned() else {
+        return Err(AppError::Busy { retry_after_sec: state.settings.upload_retry_after_sec });
+    };
+    let _slot = state.upload_slots.acquire().await
+        .map_err(|_| AppError::Internal("upload semaphore closed".into()))?;
+    state.admit_upload(&capture.turbine_token).await
+        .map_err(|retry_after_sec| AppError::TooManyRequests { retry_after_sec })?;
+
+    tracing::info!(turbine_token = %capture.turbine_token, source = %capture.source, "🛸 Captura de fuente externa recibida");
+    let upload = CaptureUpload {
+        turbine_token: capture.turbine_token,
+        angle,
+        rotor_phase: None,
+        camera_id: capture.camera_id,
+        source: Some(capture.source),
+        captured_at,
+        force: false,
+        receipt_id: None,
+        data,
+        photo: None,
+        pixel_unit: capture.pixel_unit,
+    };
+    let response = ingest_capture(&state, upload).await?;
+    Ok((response.status_code(), Json(response)))
+}
@@ -0,0 +1,520 @@
+use super::{
+    admin::require_admin,
+    web::{display_tz, display_unit},
+};
+use crate::{
+    alertmanager::{firing_alerts, AlertmanagerAlert},
+    error::AppError,
+    schedule::format_timestamp,
+    state::{AppState, LiveStatus},
+    storage::{
+        alert_log::purge_turbine_alerts,
+        annotations::save_annotations,
+        archive::{archive_dir, stored_path},
+        audit::{append_audit, AuditEntry},
+        cameras::{save_cameras, CameraInfo},
+        catalog::{parse_capture_name, save_catalog, CaptureRecord},
+        collections::save_collections,
+        lineage::purge_derivations,
+        paths::check_file_name,
+        photos::photo_capture,
+        quality::purge_turbine_quality_events,
+        quarantine::{remove_quarantined, save_quarantine},
+        registry::{save_registry, TurbineInfo},
+        sensors::{load_readings, purge_turbine_readings, ReadingFilter},
+        shares::{remove_share_image, save_shares},
+        storage_root,
+        timeline::purge_turbine_mode_changes,
+    },
+    thresholds::Severity,
+    units::TempUnit,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+
+// --- FLOTA: REGISTRO DE TURBINAS Y MAPA ---
+
+pub async fn list_turbines(State(state): State<Arc<AppState>>) -> Json<Vec<TurbineInfo>> {
+    Json(state.turbines.read().await.clone())
+}
+
+pub async fn get_turbine(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<TurbineInfo>, AppError> {
+    state.turbines.read().await.iter()
+        .find(|t| t.token == token)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Turbine '{}' not registered", token)))
+}
+
+// Alertas abiertas en el formato de la API v2 de Alertmanager (ver crate::alertmanager)
+pub async fn alertmanager_alerts(State(state): State<Arc<AppState>>) -> Json<Vec<AlertmanagerAlert>> {
+    Json(firing_alerts(&state).await)
+}
+
+// Cámaras vistas en el robot de una turbina, con su última resolución
+pub async fn list_cameras(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Json<Vec<CameraInfo>> {
+    Json(state.cameras.read().await.iter().filter(|c| c.turbine_token == token).cloned().collect())
+}
+
+// Alta o actualización (por token) de una turbina
+pub async fn upsert_turbine(
+    State(state): State<Arc<AppState>>,
+    Json(turbine): Json<TurbineInfo>,
+) -> Result<Json<TurbineInfo>, AppError> {
+    turbine.validate().map_err(AppError::BadRequest)?;
+    let mut turbines = state.turbines.write().await;
+    if let Some(slot) = turbine.modbus_slot
+        && let Some(other) = turbines.iter().find(|t| t.modbus_slot == Some(slot) && t.token != turbine.token)
+    {
+        return Err(AppError::BadRequest(format!("modbus_slot {} is already used by turbine '{}'", slot, other.token)));
+    }
+    match turbines.iter_mut().find(|t| t.token == turbine.token) {
+        Some(existing) => *existing = turbine.clone(),
+        None => turbines.push(turbine.clone()),
+    }
+    save_registry(&turbines);
+    tracing::info!(turbine_token = %turbine.token, name = %turbine.name, "🗺️ Turbina registrada");
+    Ok(Json(turbine))
+}
+
+pub async fn delete_turbine(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<&'static str>, AppError> {
+    let mut turbines = state.turbines.write().await;
+    let before = turbines.len();
+    turbines.retain(|t| t.token != token);
+    if turbines.len() == before {
+        return Err(AppError::NotFound(format!("Turbine '{}' not registered", token)));
+    }
+    save_registry(&turbines);
+    Ok(Json("Turbine removed"))
+}
+
+// --- PURGA DE DATOS DE UNA TURBINA ---
+// Baja de una turbina o de un cliente: borra sus capturas (también las archivadas y las
+// huérfanas que no estén en el catálogo), alertas, estado en vivo y entradas del catálogo,
+// sus enlaces compartidos, el linaje de sus capturas y su presencia en las colecciones.
+// El registro de la turbina se mantiene (se borra aparte con DELETE /api/turbines/:token).
+// Con dry_run=true solo informa de lo que se borraría; la purga real queda auditada.
+
+#[derive(Deserialize)]
+pub struct PurgeParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+pub struct PurgeSummary {
+    turbine_token: String,
+    dry_run: bool,
+    // Archivos borrados: capturas y sus fotos visibles
+    captures: usize,
+    bytes: u64,
+    alerts: usize,
+    incidents: usize,
+    sensor_readings: usize,
+    shares: usize,
+    // Derivaciones del linaje con alguna captura de la turbina como entrada
+    lineage_entries: usize,
+    // Capturas de la turbina quitadas de las colecciones (las colecciones se conservan)
+    collection_memberships: usize,
+}
+
+// Archivos de la turbina en disco: los del catálogo más los que solo existen en la carpeta,
+// y las fotos visibles de sus capturas
+fn turbine_files(token: &str, catalogued: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut files: HashSet<PathBuf> = catalogued.into_iter().filter(|p| p.exists()).collect();
+    for dir in [storage_root(), archive_dir()] {
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let capture = photo_capture(&name).unwrap_or(name);
+            if parse_capture_name(&capture).is_some_and(|(t, _)| t == token) {
+                files.insert(entry.path());
+            }
+        }
+    }
+    files.into_iter().collect()
+}
+
+pub async fn purge_turbine_data(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+    Query(params): Query<PurgeParams>,
+) -> Result<Json<PurgeSummary>, AppError> {
+    require_admin(&state, &headers)?;
+    check_file_name(&token).map_err(|e| AppError::BadRequest(format!("Invalid turbine token: {}", e)))?;
+
+    let (catalogued, mut captures): (Vec<PathBuf>, HashSet<String>) = state.catalog.read().await.iter()
+        .filter(|r| r.turbine_token == token)
+        .map(|r| (stored_path(r), r.filename.clone()))
+        .unzip();
+    let worker_token = token.clone();
+    let files = tokio::task::spawn_blocking(move || turbine_files(&worker_token, catalogued)).await?;
+    // Nombres de sus capturas, también las que ya no están en el catálogo ni en disco
+    let is_turbine_capture = |name: &str| parse_capture_name(name).is_some_and(|(t, _)| t == token);
+    captures.extend(files.iter().filter_map(|f| f.file_name()).map(|n| n.to_string_lossy().to_string()));
+    captures.extend(state.collections.read().await.iter().flat_map(|c| &c.captures).filter(|n| is_turbine_capture(n)).cloned());
+    captures.extend(state.lineage.read().await.iter().flat_map(|d| &d.inputs).filter(|i| is_turbine_capture(&i.filename)).map(|i| i.filename.clone()));
+    let filter = ReadingFilter { turbine_token: Some(token.clone()), ..Default::default() };
+    let sensor_readings = tokio::task::spawn_blocking(move || load_readings(&filter)).await??.len();
+    let mut summary = PurgeSummary {
+        turbine_token: token.clone(),
+        dry_run: params.dry_run,
+        captures: files.len(),
+        bytes: files.iter().filter_map(|f| f.metadata().ok()).map(|m| m.len()).sum(),
+        alerts: state.alerts.read().await.iter().filter(|a| a.turbine_token == token).count(),
+        incidents: state.incidents.read().await.iter().filter(|i| i.turbines.contains(&token)).count(),
+        sensor_readings,
+        shares: state.shares.read().await.iter().filter(|s| s.turbine_token == token).count(),
+        lineage_entries: state.lineage.read().await.iter().filter(|d| d.inputs.iter().any(|i| captures.contains(&i.filename))).count(),
+        collection_memberships: state.collections.read().await.iter()
+            .map(|c| c.captures.iter().filter(|n| captures.contains(*n)).count())
+            .sum(),
+    };
+    if params.dry_run {
+        return Ok(Json(summary));
+    }
+
+    // Primero el catálogo, para que ningún lector localice archivos a punto de borrarse
+    let purged_captures: HashSet<String> = {
+        let mut catalog = state.catalog.write().await;
+        let purged = catalog.iter().filter(|r| r.turbine_token == token).map(|r| r.filename.clone()).collect();
+        catalog.retain(|r| r.turbine_token != token);
+        save_catalog(&catalog);
+        purged
+    };
+    {
+        let mut annotations = state.annotations.write().await;
+        let before = annotations.len();
+        annotations.retain(|a| !purged_captures.contains(&a.filename));
+        if annotations.len() != before {
+            save_annotations(&annotations);
+        }
+    }
+    let deleted = tokio::task::spawn_blocking(move || {
+        files.iter()
+            .filter(|path| match std::fs::remove_file(path) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!(path = %path.display(), error = %e, "❌ Error borrando captura");
+                    false
+                }
+            })
+            .count()
+    })
+    .await?;
+    summary.captures = deleted;
+    state.frame_cache.lock().await.clear();
+    state.response_cache.clear().await;
+
+    let removed_alerts: HashSet<String> = {
+        let mut alerts = state.alerts.write().await;
+        let removed = alerts.iter().filter(|a| a.turbine_token == token).map(|a| a.id.clone()).collect();
+        alerts.retain(|a| a.turbine_token != token);
+        removed
+    };
+    {
+        let mut incidents = state.incidents.write().await;
+        for incident in incidents.iter_mut() {
+            incident.turbines.retain(|t| *t != token);
+            incident.alert_ids.retain(|id| !removed_alerts.contains(id));
+        }
+        incidents.retain(|i| !i.alert_ids.is_empty());
+    }
+    let worker_token = token.clone();
+    if let Err(e) = tokio::task::spawn_blocking(move || purge_turbine_alerts(&worker_token)).await? {
+        tracing::error!(error = %e, "❌ Error purgando el histórico de alertas");
+    }
+    let worker_token = token.clone();
+    match tokio::task::spawn_blocking(move || purge_turbine_readings(&worker_token)).await? {
+        Ok(removed) => summary.sensor_readings = removed,
+        Err(e) => tracing::error!(error = %e, "❌ Error purgando la telemetría de sensores"),
+    }
+    {
+        let mut cameras = state.cameras.write().await;
+        cameras.retain(|c| c.turbine_token != token);
+        save_cameras(&cameras);
+    }
+    let worker_token = token.clone();
+    if let Err(e) = tokio::task::spawn_blocking(move || purge_turbine_quality_events(&worker_token)).await? {
+        tracing::error!(error = %e, "❌ Error purgando los eventos de calidad de datos");
+    }
+    let quarantined: Vec<String> = {
+        let mut quarantine = state.quarantine.write().await;
+        let ids = quarantine.iter().filter(|e| e.turbine_token == token).map(|e| e.id.clone()).collect();
+        quarantine.retain(|e| e.turbine_token != token);
+        save_quarantine(&quarantine);
+        ids
+    };
+    for id in quarantined {
+        if let Err(e) = tokio::task::spawn_blocking(move || remove_quarantined(&id)).await? {
+            tracing::error!(error = %e, "❌ Error borrando una captura en cuarentena");
+        }
+    }
+    let worker_token = token.clone();
+    if let Err(e) = tokio::task::spawn_blocking(move || purge_turbine_mode_changes(&worker_token)).await? {
+        tracing::error!(error = %e, "❌ Error purgando la línea de tiempo de modos");
+    }
+    let shared: Vec<String> = {
+        let mut shares = state.shares.write().await;
+        let tokens = shares.iter().filter(|s| s.turbine_token == token).map(|s| s.token.clone()).collect();
+        shares.retain(|s| s.turbine_token != token);
+        if let Err(e) = save_shares(&shares) {
+            tracing::error!(error = %e, "❌ Error guardando los enlaces compartidos");
+        }
+        tokens
+    };
+    for share in shared {
+        if let Err(e) = tokio::task::spawn_blocking(move || remove_share_image(&share)).await? {
+            tracing::error!(error = %e, "❌ Error borrando la imagen de un enlace compartido");
+        }
+    }
+    state.lineage.write().await.retain(|d| !d.inputs.iter().any(|i| captures.contains(&i.filename)));
+    let purged_names = captures.clone();
+    if let Err(e) = tokio::task::spawn_blocking(move || purge_derivations(&purged_names)).await? {
+        tracing::error!(error = %e, "❌ Error purgando el linaje");
+    }
+    {
+        let mut collections = state.collections.write().await;
+        let now = chrono::Utc::now().timestamp() as u64;
+        for collection in collections.iter_mut().filter(|c| c.captures.iter().any(|n| captures.contains(n))) {
+            collection.captures.retain(|n| !captures.contains(n));
+            collection.updated = now;
+        }
+        if summary.collection_memberships > 0 {
+            save_collections(&collections);
+        }
+    }
+    state.clock_skew.write().await.remove(&token);
+    state.ingest_rates.write().await.remove(&token);
+    state.turbine_status.write().await.remove(&token);
+    state.scan_boosts.write().await.remove(&token);
+    state.adaptive_scans.write().await.remove(&token);
+    state.latest_frames.remove(&token).await;
+    {
+        let mut live = state.live_status.write().await;
+        if live.turbine_token == token {
+            *live = LiveStatus::default();
+        }
+    }
+
+    append_audit(&AuditEntry::new("purge_turbine_data", &token, serde_json::json!({
+        "captures": summary.captures,
+        "bytes": summary.bytes,
+        "alerts": summary.alerts,
+        "incidents": summary.incidents,
+        "shares": summary.shares,
+        "lineage_entries": summary.lineage_entries,
+        "collection_memberships": summary.collection_memberships,
+    })));
+    tracing::warn!(turbine_token = %token, captures = summary.captures, alerts = summary.alerts, "🗑️ Datos de la turbina purgados");
+    Ok(Json(summary))
+}
+
+// --- GEOJSON ---
+
+#[derive(Serialize)]
+pub struct FeatureCollection {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    features: Vec<Feature>,
+}
+
+#[derive(Serialize)]
+pub struct Feature {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    geometry: Point,
+    properties: TurbineProperties,
+}
+
+#[derive(Serialize)]
+pub struct Point {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    // GeoJSON: [longitud, latitud]
+    coordinates: [f64; 2],
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MapStatus {
+    Offline,
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl MapStatus {
+    fn color(&self) -> &'static str {
+        match self {
+            MapStatus::Offline => "#9e9e9e",
+            MapStatus::Normal => "#2e7d32",
+            MapStatus::Warning => "#f9a825",
+            MapStatus::Critical => "#c62828",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct TurbineProperties {
+    token: String,
+    name: String,
+    model: Option<String>,
+    hub_height_m: Option<f32>,
+    site: Option<String>,
+    status: MapStatus,
+    color: &'static str,
+    // Datos del último heartbeat (None si nunca ha reportado)
+    max_temp: Option<f32>,
+    last_update: Option<u64>,
+}
+
+// Gris si no reporta; si no, el nivel de umbral que alcanza su temperatura actual
+pub async fn live_map_status(
+    state: &AppState,
+    turbine_token: &str,
+    live: Option<&LiveStatus>,
+    now: &chrono::DateTime<chrono::Utc>,
+) -> MapStatus {
+    let offline_after = state.offline_after(turbine_token).await;
+    let Some(live) = live.filter(|l| now.timestamp() as u64 <= l.last_update.saturating_add(offline_after)) else {
+        return MapStatus::Offline;
+    };
+    let ambient = state.ambient_for_turbine(turbine_token).await;
+    let offset = state.turbine_temp_offset(turbine_token).await;
+    let evaluation = state.config.read().await
+        .effective_at(now)
+        .shifted(offset)
+        .compensated(ambient.as_ref(), now.timestamp() as u64)
+        .evaluate(live.current_angle, live.current_max_temp);
+    match evaluation.level.map(|l| l.severity) {
+        None => MapStatus::Normal,
+        Some(Severity::Warning) => MapStatus::Warning,
+        Some(Severity::Critical) => MapStatus::Critical,
+    }
+}
+
+// Turbinas registradas coloreadas según su último heartbeat: gris si no reporta,
+// y verde/amarillo/rojo según el nivel de umbral que alcanza su temperatura actual
+pub async fn map_handler(State(state): State<Arc<AppState>>) -> Json<FeatureCollection> {
+    let turbines = state.turbines.read().await.clone();
+    let statuses = state.turbine_status.read().await.clone();
+    let now = chrono::Utc::now();
+
+    let mut features = Vec::with_capacity(turbines.len());
+    for turbine in turbines {
+        let live = statuses.get(&turbine.token);
+        let status = live_map_status(&state, &turbine.token, live, &now).await;
+        features.push(Feature {
+            kind: "Feature",
+            geometry: Point { kind: "Point", coordinates: [turbine.longitude, turbine.latitude] },
+            properties: TurbineProperties {
+                color: status.color(),
+                status,
+                max_temp: live.map(|l| l.current_max_temp),
+                last_update: live.map(|l| l.last_update),
+                token: turbine.token,
+                name: turbine.name,
+                model: turbine.model,
+                hub_height_m: turbine.hub_height_m,
+                site: turbine.site,
+            },
+        });
+    }
+    Json(FeatureCollection { kind: "FeatureCollection", features })
+}
+
+// --- MAPA TÉRMICO ACTUAL DE UNA TURBINA ---
+// GET /api/latest/:token: la captura más reciente de cada tramo de ángulo de pan, para
+// montar la vista del mapa térmico actual con una sola petición. El tramo es por defecto
+// el paso de pan efectivo de la turbina (?bucket= en grados para agruparlos más) y con
+// ?camera= solo cuenta esa cámara; sin ella gana la más reciente de cualquier cámara.
+
+#[derive(Deserialize)]
+pub struct LatestParams {
+    bucket: Option<f32>,
+    camera: Option<String>,
+    units: Option<TempUnit>,
+    tz: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AngleLatest {
+    // Inicio del tramo, en grados
+    bucket: f32,
+    angle: f32,
+    filename: String,
+    timestamp: u64,
+    time: String,
+    max_temp: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    camera_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct LatestByAngle {
+    turbine_token: String,
+    bucket_degrees: f32,
+    buckets: Vec<AngleLatest>,
+}
+
+pub async fn latest_by_angle(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(params): Query<LatestParams>,
+) -> Result<Json<LatestByAngle>, AppError> {
+    let bucket_degrees = match params.bucket {
+        Some(bucket) => bucket,
+        None => state.config_for_turbine(&token).await.pan_step_degrees,
+    };
+    if bucket_degrees.is_nan() || bucket_degrees <= 0.0 || bucket_degrees > 360.0 {
+        return Err(AppError::BadRequest("bucket must be within (0, 360] degrees".into()));
+    }
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+    let unit = display_unit(&state, params.units).await;
+
+    let mut latest: BTreeMap<u32, &CaptureRecord> = BTreeMap::new();
+    let catalog = state.catalog.read().await;
+    let captures = catalog.iter()
+        .filter(|r| r.turbine_token == token)
+        .filter(|r| params.camera.is_none() || r.camera_id == params.camera);
+    for record in captures {
+        let Some(angle) = record.angle else { continue };
+        let index = (angle.rem_euclid(360.0) / bucket_degrees).floor() as u32;
+        let slot = latest.entry(index).or_insert(record);
+        if record.timestamp > slot.timestamp {
+            *slot = record;
+        }
+    }
+    let buckets = latest.into_iter()
+        .map(|(index, record)| AngleLatest {
+            bucket: index as f32 * bucket_degrees,
+            angle: record.angle.unwrap_or_default(),
+            filename: record.filename.clone(),
+            timestamp: record.timestamp,
+            time: format_timestamp(record.timestamp, tz),
+            max_temp: record.max_temp.map(|t| unit.temp(t)),
+            camera_id: record.camera_id.clone(),
+        })
+        .collect();
+    Ok(Json(LatestByAngle { turbine_token: token, bucket_degrees, buckets }))
+}
@@ -0,0 +1,48 @@
+use crate::{
+    error::AppError,
+    state::AppState,
+    storage::{
+        integrity::{integrity_report, IntegrityReport},
+        storage_root,
+        usage::{build_storage_report, low_disk, StorageReport},
+    },
+    watchdog::WatchdogStatus,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+// --- SALUD DEL SERVIDOR Y DEL ALMACENAMIENTO ---
+
+pub async fn storage_report_handler(State(state): State<Arc<AppState>>) -> Result<Json<StorageReport>, AppError> {
+    let report = tokio::task::spawn_blocking(move || build_storage_report(&state)).await?;
+    Ok(Json(report))
+}
+
+pub async fn integrity_report_handler(State(state): State<Arc<AppState>>) -> Json<IntegrityReport> {
+    Json(integrity_report(&state).await)
+}
+
+// Última comprobación del watchdog (ver crate::watchdog)
+pub async fn watchdog_status_handler(State(state): State<Arc<AppState>>) -> Json<WatchdogStatus> {
+    Json(state.watchdog.read().await.clone())
+}
+
+// Readiness: el almacenamiento debe existir y tener espacio libre suficiente (salvo en la
+// réplica de solo lectura, que no escribe y suele montar la copia llena o de solo lectura)
+pub async fn readyz_handler(State(state): State<Arc<AppState>>) -> Response {
+    let storage_ok = storage_root().is_dir();
+    let read_only = state.settings.read_only;
+    let low_disk = !read_only && low_disk(&state);
+    let ready = storage_ok && !low_disk;
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (
+        status,
+        Json(serde_json::json!({ "ready": ready, "storage_ok": storage_ok, "low_disk_warning": low_disk, "read_only": read_only })),
+    )
+        .into_response()
+}
@@ -0,0 +1,780 @@
+use super::{devices::require_ingest_key, quarantine::quarantine_upload};
+use crate::{
+    analysis::{
+        npy::validate_capture,
+        pixel_units::{capture_to_celsius, PixelUnit},
+    },
+    calibration::{calibration_at, CameraCalibration},
+    commands::{RobotCommand, MAX_WAIT_SEC},
+    error::AppError,
+    events::EventKind,
+    latest_frames::LatestFrame,
+    notify,
+    pipeline::{self, CaptureInput, Outcome},
+    plausibility::{implausible_frames, report_implausible},
+    resolution,
+    sessions,
+    shadow::ReportedState,
+    state::{AppState, LiveStatus, RemoteConfig, ScanBoost},
+    storage::{
+        cameras::valid_camera_id,
+        catalog::{capture_filename, sha256_hex, CaptureRecord},
+        encode_capture,
+        paths::safe_resolve,
+        photos::{photo_path, save_photo, PhotoFormat},
+        quality::{record_quality_event, QualityEventKind},
+        quarantine::QuarantineReason,
+        sensors::{append_readings, valid_sensor_name, SensorReading},
+        storage_root,
+        timeline::{record_mode_change, OFFLINE_MODE},
+        write_durable,
+    },
+    weather::{AmbientReading, AmbientSource},
+};
+use axum::{
+    body::Bytes,
+    extract::{multipart::MultipartError, Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, future::Future, sync::Arc, time::Duration};
+
+// Respuesta de subida para el robot. Qué debe hacer según el código:
+// - 201 "upload_success": guardada; puede borrar su copia local (tras comparar sha256)
+// - 200 "duplicate": ya estaba guardada (reintento de red); también puede borrarla
+// - 202 "quarantined": temperaturas implausibles (kelvin, cuentas del ADC...); se guardó
+//   en la cuarentena (quarantine_id) y no se reintenta, el problema está en el firmware
+//   (o falta indicar pixel_unit=kelvin|raw_counts en la subida)
+// Las tres llevan receipt_id, que solo se entrega con la captura y su entrada del catálogo
+// (o de la cuarentena) ya en disco (fsync). Un robot que acumula capturas sin conexión
+// guarda el recibo y, ante la duda (respuesta perdida, reinicio), pregunta por él en
+// GET /ingest/receipt/:id antes de borrar su copia local.
+// - 400: captura o formulario no válidos; reintentar no sirve, hay que revisar el firmware
+//   (una captura no válida queda en la cuarentena). También un cuerpo cortado a mitad
+// - 408: el robot dejó de enviar el cuerpo (conexión inestable); reintentar
+// - 413: subida mayor que el límite del servidor; no reintentar tal cual
+// - 401/403: clave de ingesta no válida; no reintentar hasta tener la clave correcta
+// - 429 y 503: límite de subidas o cola llena; reintentar pasado Retry-After
+// - 500: el servidor no pudo guardarla; reintentar más tarde conservando la copia local
+#[derive(Serialize, Deserialize)]
+pub struct UploadResponse {
+    pub status: Cow<'static, str>,
+    pub filename: String,
+    pub size_bytes: u64,
+    // SHA-256 del contenido recibido, para que el robot compruebe que llegó entero
+    pub sha256: String,
+    // Alerta creada por la captura, si ha superado algún umbral
+    pub alert_id: Option<String>,
+    // Id en la cuarentena de una subida apartada (sin nombre de archivo en el catálogo)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantine_id: Option<String>,
+    // Recibo duradero de la subida (None solo si no se pudo asignar a un duplicado antiguo)
+    pub receipt_id: Option<String>,
+}
+
+impl UploadResponse {
+    pub fn status_code(&self) -> StatusCode {
+        match self.status.as_ref() {
+            "duplicate" => StatusCode::OK,
+            "quarantined" => StatusCode::ACCEPTED,
+            _ => StatusCode::CREATED,
+        }
+    }
+}
+
+// --- API ROBOT (CORE) ---
+
+// Heartbeat del robot. Si indica la versión de configuración que tiene aplicada recibe
+// solo un acuse con la versión vigente (y vuelve a pedir /ingest/config/:token cuando
+// cambie); los robots antiguos, sin versión, siguen recibiendo la configuración completa.
+// Las calibraciones de cámara van en el propio acuse cuando la versión que indica el
+// robot no es la vigente. El acuse lleva la hora del servidor y, si el robot manda la
+// suya (robot_time), se anota el desfase de su reloj. Las versiones y `reported` (sus
+// ajustes aplicados, opcional) quedan en la sombra del dispositivo (ver crate::shadow).
+#[derive(Serialize, Deserialize)]
+pub struct HeartbeatPayload {
+    #[serde(flatten)]
+    pub status: LiveStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibration_version: Option<String>,
+    // Hora del reloj del robot al enviar el heartbeat (segundos Unix)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robot_time: Option<u64>,
+    // Ajustes tal como los tiene aplicados el robot, con las claves de la configuración
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reported: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HeartbeatAck {
+    pub ack: bool,
+    pub config_version: String,
+    pub config_changed: bool,
+    #[serde(default)]
+    pub calibration_version: String,
+    // Solo si el robot tiene otra versión
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibrations: Option<Vec<CameraCalibration>>,
+    #[serde(default)]
+    pub server_time: u64,
+}
+
+// Configuración completa para el robot con su versión
+#[derive(Serialize, Deserialize)]
+pub struct RobotConfig {
+    pub config_version: String,
+    pub config: RemoteConfig,
+}
+
+pub async fn heartbeat_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<HeartbeatPayload>
+) -> Result<Response, AppError> {
+    tracing::Span::current().record("turbine_token", payload.status.turbine_token.as_str());
+    let turbine_token = payload.status.turbine_token.clone();
+    require_ingest_key(&state, &turbine_token, &headers).await?;
+    let offline_after = state.offline_after(&turbine_token).await;
+    {
+        let mut status = state.live_status.write().await;
+        *status = payload.status;
+        status.last_update = chrono::Utc::now().timestamp() as u64;
+        status.is_online = true;
+        let previous = state.turbine_status.write().await.insert(turbine_token.clone(), status.clone());
+        // Hueco de heartbeats: la turbina estuvo desconectada hasta ahora
+        let offline_since = previous.as_ref()
+            .map(|p| p.last_update.saturating_add(offline_after))
+            .filter(|&since| status.last_update > since);
+        if let (Some(previous), Some(offline_since)) = (&previous, offline_since) {
+            record_quality_event(&turbine_token, QualityEventKind::HeartbeatGap { from: previous.last_update });
+            record_mode_change(&turbine_token, offline_since, OFFLINE_MODE);
+        }
+        // Línea de tiempo: solo los cambios de modo (y la vuelta tras un hueco)
+        if offline_since.is_some() || previous.as_ref().is_none_or(|p| p.mode != status.mode) {
+            record_mode_change(&turbine_token, status.last_update, &status.mode);
+        }
+    }
+    if let Some(robot_time) = payload.robot_time {
+        state.record_clock_skew(&turbine_token, robot_time).await;
+    }
+    let (config, config_version) = state.robot_config(&turbine_token).await;
+    // Los robots sin versión reciben la configuración completa: la vigente es la aplicada
+    if payload.config_version.is_none() {
+        state.record_served_config(&turbine_token, &config_version, &config).await;
+    }
+    let reported = ReportedState {
+        timestamp: chrono::Utc::now().timestamp() as u64,
+        config_version: Some(payload.config_version.clone().unwrap_or_else(|| config_version.clone())),
+        calibration_version: payload.calibration_version.clone(),
+        settings: payload.reported,
+    };
+    state.record_reported_state(&turbine_token, reported).await;
+    match payload.config_version {
+        Some(applied) => {
+            let config_changed = applied != config_version;
+            let (calibrations, calibration_version) = state.robot_calibrations(&turbine_token).await;
+            let calibrations = (payload.calibration_version.as_ref() != Some(&calibration_version)).then_some(calibrations);
+            Ok(Json(HeartbeatAck {
+                ack: true,
+                config_version,
+                config_changed,
+                calibration_version,
+                calibrations,
+                server_time: chrono::Utc::now().timestamp() as u64,
+            }).into_response())
+        }
+        None => Ok(Json(config).into_response()),
+    }
+}
+
+// Hora del servidor para que el robot sincronice su reloj. Con turbine_token y
+// robot_time se anota además el desfase del robot.
+#[derive(Deserialize)]
+pub struct TimeParams {
+    turbine_token: Option<String>,
+    robot_time: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct ServerTime {
+    server_time: u64,
+    server_time_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset_sec: Option<i64>,
+}
+
+pub async fn time_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<TimeParams>,
+) -> Result<Json<ServerTime>, AppError> {
+    let offset_sec = match (params.turbine_token.as_deref().filter(|t| !t.is_empty()), params.robot_time) {
+        (Some(token), Some(robot_time)) => {
+            require_ingest_key(&state, token, &headers).await?;
+            Some(state.record_clock_skew(token, robot_time).await.offset_sec)
+        }
+        _ => None,
+    };
+    let now = chrono::Utc::now();
+    Ok(Json(ServerTime { server_time: now.timestamp() as u64, server_time_ms: now.timestamp_millis(), offset_sec }))
+}
+
+// Long-poll de comandos: responde en cuanto haya alguno o, si no, al vencer la espera
+#[derive(Deserialize)]
+pub struct CommandPollParams {
+    #[serde(default)]
+    wait: u64,
+}
+
+pub async fn poll_commands_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(turbine_token): Path<String>,
+    Query(params): Query<CommandPollParams>,
+) -> Result<Json<Vec<RobotCommand>>, AppError> {
+    tracing::Span::current().record("turbine_token", turbine_token.as_str());
+    require_ingest_key(&state, &turbine_token, &headers).await?;
+    let wait = Duration::from_secs(params.wait.min(MAX_WAIT_SEC));
+    let commands = state.commands.wait_for(&turbine_token, wait).await;
+    if !commands.is_empty() {
+        tracing::info!(%turbine_token, count = commands.len(), "📨 Comandos entregados al robot");
+    }
+    Ok(Json(commands))
+}
+
+pub async fn robot_config_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(turbine_token): Path<String>,
+) -> Result<Json<RobotConfig>, AppError> {
+    require_ingest_key(&state, &turbine_token, &headers).await?;
+    let (config, config_version) = state.robot_config(&turbine_token).await;
+    state.record_served_config(&turbine_token, &config_version, &config).await;
+    Ok(Json(RobotConfig { config_version, config }))
+}
+
+// Lectura de un sensor de ambiente local, identificada por el sitio
+#[derive(Deserialize)]
+pub struct AmbientPayload {
+    site: String,
+    ambient_temp: f32,
+    #[serde(default)]
+    wind_speed: Option<f32>,
+}
+
+pub async fn ambient_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AmbientPayload>
+) -> Result<Json<AmbientReading>, AppError> {
+    if !state.config.read().await.weather_sites.iter().any(|s| s.name == payload.site) {
+        return Err(AppError::BadRequest(format!("unknown site '{}'", payload.site)));
+    }
+    if !payload.ambient_temp.is_finite() || payload.wind_speed.is_some_and(|w| !w.is_finite() || w < 0.0) {
+        return Err(AppError::BadRequest("invalid ambient reading".into()));
+    }
+    let reading = AmbientReading {
+        ambient_temp: payload.ambient_temp,
+        wind_speed: payload.wind_speed,
+        source: AmbientSource::Sensor,
+        timestamp: chrono::Utc::now().timestamp() as u64,
+    };
+    tracing::debug!(site = %payload.site, ambient_temp = reading.ambient_temp, "🌤️ Lectura de ambiente recibida");
+    state.ambient.write().await.insert(payload.site, reading.clone());
+    Ok(Json(reading))
+}
+
+// Lote de lecturas de sensores no térmicos de una turbina (vibración, acústica...).
+// Sin timestamp se toma el de llegada; con él, se corrige con el desfase del reloj del robot.
+#[derive(Deserialize)]
+pub struct TelemetryBatch {
+    turbine_token: String,
+    readings: Vec<TelemetryReading>,
+}
+
+#[derive(Deserialize)]
+pub struct TelemetryReading {
+    sensor: String,
+    value: f64,
+    #[serde(default)]
+    timestamp: Option<u64>,
+    #[serde(default)]
+    unit: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TelemetryAck {
+    accepted: usize,
+}
+
+// Lecturas que se admiten en un solo lote
+const MAX_TELEMETRY_BATCH: usize = 5_000;
+
+pub async fn telemetry_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(batch): Json<TelemetryBatch>
+) -> Result<Json<TelemetryAck>, AppError> {
+    tracing::Span::current().record("turbine_token", batch.turbine_token.as_str());
+    if batch.turbine_token.is_empty() {
+        return Err(AppError::BadRequest("turbine_token is required".into()));
+    }
+    require_ingest_key(&state, &batch.turbine_token, &headers).await?;
+    if batch.readings.len() > MAX_TELEMETRY_BATCH {
+        return Err(AppError::BadRequest(format!("at most {} readings per batch", MAX_TELEMETRY_BATCH)));
+    }
+    let now = chrono::Utc::now().timestamp() as u64;
+    let offset = state.clock_skew.read().await.get(&batch.turbine_token).map_or(0, |s| s.offset_sec);
+    let readings = batch.readings.into_iter()
+        .map(|r| {
+            if !valid_sensor_name(&r.sensor) {
+                return Err(AppError::BadRequest(format!("invalid sensor name '{}'", r.sensor)));
+            }
+            if !r.value.is_finite() {
+                return Err(AppError::BadRequest(format!("invalid value for sensor '{}'", r.sensor)));
+            }
+            Ok(SensorReading {
+                turbine_token: batch.turbine_token.clone(),
+                sensor: r.sensor,
+                timestamp: r.timestamp.map_or(now, |t| t.saturating_add_signed(-offset)),
+                value: r.value,
+                unit: r.unit,
+            })
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+    let accepted = readings.len();
+    if accepted > 0 {
+        tokio::task::spawn_blocking(move || append_readings(&readings)).await??;
+    }
+    tracing::debug!(turbine_token = %batch.turbine_token, accepted, "📳 Telemetría de sensores recibida");
+    Ok(Json(TelemetryAck { accepted }))
+}
+
+pub async fn upload_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    multipart: Multipart
+) -> Result<(StatusCode, Json<UploadResponse>), AppError> {
+    // Backpressure: si la cola está llena respondemos 503 para que el robot reintente más tarde
+    let Ok(_admission) = state.upload_admission.clone().try_acquire_owned() else {
+        tracing::warn!("⏳ Cola de subidas llena, se rechaza la subida con 503.");
+        return Err(AppError::Busy { retry_after_sec: state.settings.upload_retry_after_sec });
+    };
+    let _slot = state.upload_slots.acquire().await
+        .map_err(|_| AppError::Internal("upload semaphore closed".into()))?;
+
+    process_upload(&state, &headers, multipart).await
+}
+
+// Captura recibida, ya separada del formulario multipart
+pub struct CaptureUpload {
+    pub turbine_token: String,
+    pub angle: f32,
+    pub rotor_phase: Option<f32>,
+    pub camera_id: Option<String>,
+    // Fuente externa (ver routes::external); None = el robot de la turbina
+    pub source: Option<String>,
+    // Instante de la toma si no es el de la subida (fuentes externas y cuarentena)
+    pub captured_at: Option<u64>,
+    // Aceptada a mano desde la cuarentena: sin validar el formato ni los límites
+    pub force: bool,
+    // Recibo ya entregado al robot (el id de la cuarentena de una subida aceptada)
+    pub receipt_id: Option<String>,
+    pub data: Bytes,
+    // Foto visible de la misma posición (ver storage::photos)
+    pub photo: Option<Bytes>,
+    // Unidad de los píxeles de `data` (ver analysis::pixel_units)
+    pub pixel_unit: PixelUnit,
+}
+
+// Lee un paso del formulario de una subida (el siguiente campo o su contenido) con tiempo
+// límite. Un fallo responde 400 (mal formado o cortado), 408 (el robot dejó de enviar) o
+// 413 (demasiado grande) y se cuenta en /metrics (sentinel_upload_errors_total), para que
+// los problemas de conectividad en campo se vean sin mirar los logs
+pub(crate) async fn read_part<T>(
+    state: &AppState,
+    route: &'static str,
+    read: impl Future<Output = Result<T, MultipartError>>,
+) -> Result<T, AppError> {
+    let timeout_sec = state.settings.upload_read_timeout_sec;
+    let error = match tokio::time::timeout(Duration::from_secs(timeout_sec), read).await {
+        Ok(Ok(value)) => return Ok(value),
+        Ok(Err(e)) => AppError::from(e),
+        Err(_) => AppError::RequestTimeout(format!("Upload body stalled for more than {}s", timeout_sec)),
+    };
+    let kind = match &error {
+        AppError::PayloadTooLarge(_) => "too_large",
+        AppError::RequestTimeout(_) => "timeout",
+        _ => "malformed",
+    };
+    state.metrics.record_upload_error(route, kind).await;
+    tracing::warn!(route, kind, error = %error, "📡 Formulario de subida ilegible o interrumpido");
+    Err(error)
+}
+
+const UPLOAD_ROUTE: &str = "/ingest/upload";
+
+async fn process_upload(state: &AppState, headers: &HeaderMap, mut multipart: Multipart) -> Result<(StatusCode, Json<UploadResponse>), AppError> {
+    let mut turbine_token = String::new();
+    let mut angle = 0.0;
+    let mut rotor_phase = None;
+    let mut camera_id = None;
+    let mut data = None;
+    let mut photo = None;
+    let mut pixel_unit = PixelUnit::Celsius;
+
+    while let Some(field) = read_part(state, UPLOAD_ROUTE, multipart.next_field()).await? {
+        let name = field.name().unwrap_or_default().to_string();
+
+        if name == "turbine_token" {
+            let txt = read_part(state, UPLOAD_ROUTE, field.text()).await?;
+            tracing::Span::current().record("turbine_token", txt.as_str());
+            turbine_token = txt;
+        } else if name == "angle" {
+            angle = read_part(state, UPLOAD_ROUTE, field.text()).await?.parse().unwrap_or(0.0);
+        } else if name == "rotor_phase" {
+            rotor_phase = read_part(state, UPLOAD_ROUTE, field.text()).await?.parse::<f32>().ok();
+        } else if name == "camera_id" {
+            camera_id = Some(read_part(state, UPLOAD_ROUTE, field.text()).await?).filter(|t| !t.is_empty());
+        } else if name == "pixel_unit" {
+            pixel_unit = PixelUnit::parse(&read_part(state, UPLOAD_ROUTE, field.text()).await?).map_err(AppError::BadRequest)?;
+        } else if name == "dataset_file" {
+            data = Some(read_part(state, UPLOAD_ROUTE, field.bytes()).await?);
+        } else if name == "photo" {
+            photo = Some(read_part(state, UPLOAD_ROUTE, field.bytes()).await?).filter(|p| !p.is_empty());
+        }
+    }
+
+    require_ingest_key(state, &turbine_token, headers).await?;
+    // Límite por robot: cuenta también las subidas repetidas que acaban deduplicadas
+    state.admit_upload(&turbine_token).await
+        .map_err(|retry_after_sec| AppError::TooManyRequests { retry_after_sec })?;
+
+    let Some(data) = data else {
+        return Err(AppError::BadRequest("Missing dataset_file".into()));
+    };
+    let upload = CaptureUpload { turbine_token, angle, rotor_phase, camera_id, source: None, captured_at: None, force: false, receipt_id: None, data, photo, pixel_unit };
+    let response = ingest_capture(state, upload).await?;
+    Ok((response.status_code(), Json(response)))
+}
+
+// Comprobaciones previas al almacenamiento. Lo que no sea una matriz térmica válida (o no
+// tenga la resolución declarada del robot) se rechaza con 400; lo que tenga temperaturas
+// imposibles se responde como "quarantined". En ambos casos la subida queda en la
+// cuarentena para revisarla.
+async fn screen_upload(state: &AppState, upload: &CaptureUpload) -> Result<Option<UploadResponse>, AppError> {
+    let turbine_token = &upload.turbine_token;
+    let expected = state.turbines.read().await.iter()
+        .find(|t| t.token == *turbine_token)
+        .and_then(|t| t.declared_resolution(upload.camera_id.as_deref()));
+    if let Err(reason) = validate_capture(&upload.data, expected) {
+        tracing::warn!(%turbine_token, %reason, "❌ Subida rechazada: captura no válida");
+        // Sin poder guardarla se rechaza igual: el robot no debe reintentar una captura no válida
+        let quarantine_id = quarantine_upload(state, upload, QuarantineReason::Invalid { message: reason.clone() }).await
+            .inspect_err(|e| tracing::error!(error = %e, "❌ Error guardando la subida en cuarentena"))
+            .ok();
+        record_quality_event(turbine_token, QualityEventKind::RejectedUpload { reason: reason.clone(), quarantine_id });
+        return Err(AppError::BadRequest(format!("Invalid capture: {}", reason)));
+    }
+
+    let Some(bounds) = state.config.read().await.temperature_bounds.clone() else {
+        return Ok(None);
+    };
+    let (data, worker_bounds) = (upload.data.clone(), bounds.clone());
+    let Some(implausible) = tokio::task::spawn_blocking(move || implausible_frames(&data, &worker_bounds)).await? else {
+        return Ok(None);
+    };
+    let quarantine_id = quarantine_upload(state, upload, QuarantineReason::Implausible(implausible.clone())).await?;
+    report_implausible(state, turbine_token, &quarantine_id, &bounds, &implausible).await;
+    Ok(Some(UploadResponse {
+        status: "quarantined".into(),
+        filename: String::new(),
+        size_bytes: upload.data.len() as u64,
+        sha256: sha256_hex(&upload.data),
+        alert_id: None,
+        receipt_id: Some(quarantine_id.clone()),
+        quarantine_id: Some(quarantine_id),
+    }))
+}
+
+// Píxeles de una subida en kelvin o en cuentas, pasados a °C. Las cuentas necesitan la
+// conversión de la calibración de la cámara vigente en el instante de la toma
+async fn pixels_to_celsius(state: &AppState, upload: &CaptureUpload) -> Result<Bytes, AppError> {
+    let conversion = match upload.pixel_unit {
+        PixelUnit::RawCounts => {
+            let at = upload.captured_at.unwrap_or_else(|| chrono::Utc::now().timestamp() as u64);
+            let calibrations = state.calibrations.read().await;
+            let calibration = calibration_at(&calibrations, &upload.turbine_token, upload.camera_id.as_deref(), at);
+            let Some(conversion) = calibration.and_then(|c| c.raw_counts) else {
+                return Err(AppError::BadRequest(
+                    "pixel_unit=raw_counts needs a raw_counts conversion in the camera calibration".into(),
+                ));
+            };
+            Some(conversion)
+        }
+        _ => None,
+    };
+    let (data, unit) = (upload.data.clone(), upload.pixel_unit);
+    match tokio::task::spawn_blocking(move || capture_to_celsius(&data, unit, conversion.as_ref())).await? {
+        Ok(converted) => Ok(Bytes::from(converted)),
+        Err(reason) => {
+            tracing::warn!(turbine_token = %upload.turbine_token, ?unit, %reason, "❌ Subida rechazada: no se pudo convertir a °C");
+            Err(AppError::BadRequest(format!("Invalid capture: {}", reason)))
+        }
+    }
+}
+
+// Guarda, cataloga, analiza y evalúa una captura como si la hubiera subido un robot
+// (también lo usa el generador de capturas sintéticas de /api/admin/generate). Lo que no
+// llega en °C se convierte antes de nada; la respuesta lleva el sha256 de lo recibido y
+// el catálogo (y el recibo) el de la captura ya convertida, que es la que se guarda
+pub async fn ingest_capture(state: &AppState, mut upload: CaptureUpload) -> Result<UploadResponse, AppError> {
+    if let Some(camera) = upload.camera_id.as_deref().filter(|c| !valid_camera_id(c)) {
+        return Err(AppError::BadRequest(format!("Invalid camera_id '{}' (letters, digits and '-')", camera)));
+    }
+    if upload.pixel_unit == PixelUnit::Celsius {
+        return store_capture(state, upload).await;
+    }
+    let received_sha256 = sha256_hex(&upload.data);
+    upload.data = pixels_to_celsius(state, &upload).await?;
+    let mut response = store_capture(state, upload).await?;
+    response.sha256 = received_sha256;
+    Ok(response)
+}
+
+async fn store_capture(state: &AppState, upload: CaptureUpload) -> Result<UploadResponse, AppError> {
+    if upload.photo.as_deref().is_some_and(|p| PhotoFormat::detect(p).is_none()) {
+        return Err(AppError::BadRequest("Invalid photo: expected a JPEG or PNG image".into()));
+    }
+    if !upload.force
+        && let Some(response) = screen_upload(state, &upload).await?
+    {
+        return Ok(response);
+    }
+    let CaptureUpload { turbine_token, angle, rotor_phase, camera_id, source, captured_at, force: _, receipt_id, data, photo, pixel_unit } = upload;
+    let digest = sha256_hex(&data);
+
+    // Deduplicación: un reintento de red con el mismo contenido reutiliza el archivo existente
+    if let Some(existing) = state.find_duplicate(&turbine_token, &digest).await {
+        tracing::info!(%turbine_token, filename = %existing, "♻️ Captura duplicada, se reutiliza el archivo existente");
+        let alert_id = state.alerts.read().await.iter()
+            .find(|a| a.dataset_path == existing)
+            .map(|a| a.id.clone());
+        // El robot puede borrar su copia igual: la captura ya estaba en disco
+        let receipt_id = state.capture_receipt(&existing).await
+            .inspect_err(|e| tracing::error!(filename = %existing, error = %e, "❌ Error guardando el recibo de una captura duplicada"))
+            .ok()
+            .flatten();
+        return Ok(UploadResponse {
+            status: "duplicate".into(),
+            filename: existing,
+            size_bytes: data.len() as u64,
+            sha256: digest,
+            alert_id,
+            quarantine_id: None,
+            receipt_id,
+        });
+    }
+
+    let timestamp = captured_at.map_or_else(|| chrono::Utc::now().timestamp(), |t| t as i64);
+
+    let file_saved_name = capture_filename(&turbine_token, timestamp as u64, camera_id.as_deref());
+    let filepath = safe_resolve(&storage_root(), &file_saved_name)
+        .map_err(|e| AppError::BadRequest(format!("Invalid turbine_token: {}", e)))?;
+    // Una toma con fecha propia puede coincidir con otra del mismo segundo: no se pisa
+    if captured_at.is_some() && state.catalog.read().await.iter().any(|r| r.filename == file_saved_name) {
+        return Err(AppError::BadRequest(format!(
+            "A capture for this turbine and second already exists ({}); use a different camera_id", file_saved_name
+        )));
+    }
+
+    let (worker_data, worker_path, zstd_level) = (data.clone(), filepath.clone(), state.settings.zstd_level);
+    let write_result = tokio::task::spawn_blocking(move || {
+        write_durable(&worker_path, &encode_capture(&worker_data, zstd_level)?)
+    }).await?;
+    if let Err(e) = write_result {
+        tracing::error!(path = %filepath.display(), error = %e, "❌ Error escribiendo archivo");
+        return Err(AppError::Internal(format!("could not store {}: {}", file_saved_name, e)));
+    }
+    tracing::info!(path = %filepath.display(), "💾 Archivo recibido y guardado");
+    // Sin la foto la captura térmica vale igual: un fallo al guardarla no frena la subida
+    let photo = match photo {
+        Some(photo) => {
+            let capture = file_saved_name.clone();
+            tokio::task::spawn_blocking(move || save_photo(&capture, &photo)).await?
+                .inspect_err(|e| tracing::warn!(filename = %file_saved_name, error = %e, "⚠️ Error guardando la foto visible de la captura"))
+                .ok()
+        }
+        None => None,
+    };
+
+    let input = CaptureInput {
+        turbine_token: turbine_token.clone(),
+        filename: file_saved_name.clone(),
+        timestamp: timestamp as u64,
+        angle,
+        rotor_phase,
+        camera_id: camera_id.clone(),
+        // El ambiente actual solo vale para lo que se acaba de tomar
+        ambient: match captured_at {
+            None => state.ambient_for_turbine(&turbine_token).await,
+            Some(_) => None,
+        },
+        data,
+    };
+    let mut analysis = pipeline::analyze_capture(state, &input).await?;
+    if analysis.stats.is_none() {
+        tracing::warn!(filename = %file_saved_name, "⚠️ Captura ilegible: no contiene frames térmicos");
+        record_quality_event(&turbine_token, QualityEventKind::CorruptUpload { filename: file_saved_name.clone() });
+    }
+
+    let from_robot = source.is_none();
+    let receipt_id = receipt_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let catalogued = state.add_capture(CaptureRecord {
+        filename: file_saved_name.clone(),
+        turbine_token: turbine_token.clone(),
+        timestamp: timestamp as u64,
+        sha256: digest.clone(),
+        size_bytes: input.data.len() as u64,
+        archived: false,
+        integrity_error: None,
+        ambient: input.ambient.clone(),
+        angle: Some(angle),
+        max_temp: analysis.stats.map(|s| s.max_temp),
+        avg_temp: analysis.stats.map(|s| s.avg_temp),
+        anomaly_score: analysis.anomaly_score,
+        starred: false,
+        camera_id: camera_id.clone(),
+        calibration_version: analysis.calibration_version,
+        // El desfase anotado es el del reloj del robot
+        clock_skew_sec: match source {
+            None => state.clock_skew.read().await.get(&turbine_token).map(|s| s.offset_sec),
+            Some(_) => None,
+        },
+        source,
+        width: analysis.shape.map(|(_, width)| width),
+        height: analysis.shape.map(|(height, _)| height),
+        frames: std::mem::take(&mut analysis.frames),
+        received: Some(chrono::Utc::now().timestamp() as u64),
+        receipt_id: Some(receipt_id.clone()),
+        analyses: std::mem::take(&mut analysis.results),
+        photo: photo.clone(),
+        quality: analysis.quality,
+        pixel_unit: Some(pixel_unit).filter(|u| *u != PixelUnit::Celsius),
+    }).await;
+    if let Err(e) = catalogued {
+        // Sin entrada en el catálogo no hay recibo: el robot conserva su copia y reintenta
+        tracing::error!(filename = %file_saved_name, error = %e, "❌ Error guardando el catálogo, se descarta la subida");
+        let _ = tokio::fs::remove_file(&filepath).await;
+        if let Some(path) = photo.as_deref().and_then(|photo| photo_path(photo).ok()) {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        return Err(AppError::Internal(format!("could not catalog {}: {}", file_saved_name, e)));
+    }
+    if state.latest_frames.enabled() {
+        let (worker_name, worker_data) = (file_saved_name.clone(), input.data.clone());
+        let latest = tokio::task::spawn_blocking(move || LatestFrame::decode(&worker_name, timestamp as u64, &worker_data)).await?;
+        if let Some(latest) = latest {
+            state.latest_frames.push(&turbine_token, latest).await;
+        }
+    }
+    state.events.publish(&turbine_token, EventKind::Upload {
+        filename: file_saved_name.clone(),
+        camera_id: camera_id.clone(),
+        angle,
+        max_temp: analysis.stats.map(|s| s.max_temp),
+        size_bytes: input.data.len() as u64,
+    });
+    if let (Some(camera), Some(shape)) = (camera_id.as_deref(), analysis.shape) {
+        state.record_camera(&turbine_token, camera, shape, timestamp as u64).await;
+    }
+    if from_robot {
+        sessions::check_scan_coverage(state, &turbine_token, &file_saved_name).await;
+    }
+
+    let max_temp = analysis.stats.map_or(0.0, |s| s.max_temp);
+    let mut alert_id = None;
+    match pipeline::evaluate_capture(state, &input, max_temp, &analysis).await? {
+        Outcome::Alert { alert, level, channels } => {
+            alert_id = Some(alert.id.clone());
+            if let Some(level) = level
+                && let Some(scan_wait_time_sec) = level.boost_scan_wait_sec
+            {
+                let now = chrono::Utc::now().timestamp() as u64;
+                state.scan_boosts.write().await.insert(
+                    turbine_token.clone(),
+                    ScanBoost { scan_wait_time_sec, until: now + level.boost_duration_sec },
+                );
+            }
+            notify::raise_alert(state, *alert, &channels).await;
+        }
+        Outcome::Normal { zone } => {
+            // Una captura normal cancela el refuerzo de escaneo de la turbina
+            state.scan_boosts.write().await.remove(&turbine_token);
+            tracing::info!(max_temp, zone = zone.as_deref(), "🌡️ Captura bajo el umbral de su zona, sin alerta");
+            resolution::check_resolution(state, &turbine_token, angle, input.ambient.as_ref(), timestamp as u64).await;
+        }
+    }
+    Ok(UploadResponse {
+        status: "upload_success".into(),
+        filename: file_saved_name,
+        size_bytes: input.data.len() as u64,
+        sha256: digest,
+        alert_id,
+        quarantine_id: None,
+        receipt_id: Some(receipt_id),
+    })
+}
+
+// Estado de un recibo de subida. "stored": la captura está en el catálogo (aunque ya se
+// haya archivado); "quarantined": apartada en la cuarentena, tampoco hay que reenviarla.
+// Un 404 significa que el servidor no la tiene: el robot conserva su copia y la reenvía.
+#[derive(Serialize, Deserialize)]
+pub struct ReceiptStatus {
+    pub receipt_id: String,
+    pub status: Cow<'static, str>,
+    pub turbine_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub received: Option<u64>,
+}
+
+pub async fn receipt_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(receipt_id): Path<String>,
+) -> Result<Json<ReceiptStatus>, AppError> {
+    let stored = state.catalog.read().await.iter()
+        .find(|r| r.receipt_id.as_deref() == Some(receipt_id.as_str()))
+        .map(|r| ReceiptStatus {
+            receipt_id: receipt_id.clone(),
+            status: "stored".into(),
+            turbine_token: r.turbine_token.clone(),
+            filename: Some(r.filename.clone()),
+            sha256: r.sha256.clone(),
+            size_bytes: r.size_bytes,
+            received: r.received,
+        });
+    let found = match stored {
+        Some(status) => Some(status),
+        None => state.quarantine.read().await.iter()
+            .find(|e| e.id == receipt_id)
+            .map(|e| ReceiptStatus {
+                receipt_id: receipt_id.clone(),
+                status: "quarantined".into(),
+                turbine_token: e.turbine_token.clone(),
+                filename: None,
+                sha256: e.sha256.clone(),
+                size_bytes: e.size_bytes,
+                received: Some(e.received),
+            }),
+    };
+    let status = found.ok_or_else(|| AppError::NotFound(format!("Receipt '{}' not found", receipt_id)))?;
+    require_ingest_key(&state, &status.turbine_token, &headers).await?;
+    Ok(Json(status))
+}
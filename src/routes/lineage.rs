@@ -0,0 +1,37 @@
+use crate::{error::AppError, state::AppState, storage::lineage::Derivation};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Serialize;
+use std::{cmp::Reverse, sync::Arc};
+
+// --- LINAJE DE UN ARCHIVO ---
+// GET /api/files/:filename/derived: de dónde sale un archivo (para los informes, la
+// derivación que lo generó y sus capturas) y qué se ha derivado de él. Las capturas crudas
+// no tienen origen: son el final de la cadena.
+
+#[derive(Serialize)]
+pub struct FileLineage {
+    filename: String,
+    // Contenido actual, si es una captura del catálogo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    derived_from: Vec<Derivation>,
+    derived: Vec<Derivation>,
+}
+
+pub async fn file_lineage_handler(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+) -> Result<Json<FileLineage>, AppError> {
+    let sha256 = state.catalog.read().await.iter().find(|r| r.filename == filename).map(|r| r.sha256.clone());
+    let lineage = state.lineage.read().await;
+    let derived_from: Vec<Derivation> = lineage.iter().filter(|d| d.output.as_deref() == Some(filename.as_str())).cloned().collect();
+    let mut derived: Vec<Derivation> = lineage.iter().filter(|d| d.uses(&filename)).cloned().collect();
+    if sha256.is_none() && derived_from.is_empty() && derived.is_empty() {
+        return Err(AppError::NotFound(format!("No lineage recorded for '{}'", filename)));
+    }
+    derived.sort_by_key(|d| Reverse(d.created));
+    Ok(Json(FileLineage { filename, sha256, derived_from, derived }))
+}
@@ -0,0 +1,151 @@
+use super::devices::require_ingest_key;
+use crate::{error::AppError, state::AppState};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::HeaderMap,
+    response::Response,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+// --- CONSOLA DE LOGS DE LOS ROBOTS ---
+// El robot envía sus líneas de log en lotes a POST /ingest/logs y los dashboards las
+// siguen en vivo por /ws/logs/:token (?level=warn para ver solo avisos y errores), sin
+// SSH por la VPN durante la puesta en marcha. Como el stream de frames, es un relé: las
+// líneas no se guardan y una consola que se conecta tarde solo ve las siguientes.
+
+// Líneas que se admiten en un solo lote
+const MAX_LOG_BATCH: usize = 1_000;
+// Las líneas más largas se recortan
+const MAX_LOG_LINE_CHARS: usize = 4_096;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    #[serde(alias = "warning")]
+    Warn,
+    Error,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RobotLogLine {
+    pub turbine_token: String,
+    pub timestamp: u64,
+    pub level: LogLevel,
+    // Módulo o proceso del robot que la escribió
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct LogBatch {
+    turbine_token: String,
+    lines: Vec<LogLineInput>,
+}
+
+#[derive(Deserialize)]
+pub struct LogLineInput {
+    // Reloj del robot; sin él, el de llegada
+    #[serde(default)]
+    timestamp: Option<u64>,
+    #[serde(default)]
+    level: LogLevel,
+    #[serde(default)]
+    target: Option<String>,
+    message: String,
+}
+
+#[derive(Serialize)]
+pub struct LogAck {
+    accepted: usize,
+}
+
+pub async fn ingest_logs_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(batch): Json<LogBatch>,
+) -> Result<Json<LogAck>, AppError> {
+    tracing::Span::current().record("turbine_token", batch.turbine_token.as_str());
+    if batch.turbine_token.is_empty() {
+        return Err(AppError::BadRequest("turbine_token is required".into()));
+    }
+    require_ingest_key(&state, &batch.turbine_token, &headers).await?;
+    if batch.lines.len() > MAX_LOG_BATCH {
+        return Err(AppError::BadRequest(format!("at most {} lines per batch", MAX_LOG_BATCH)));
+    }
+    let now = chrono::Utc::now().timestamp() as u64;
+    let offset = state.clock_skew.read().await.get(&batch.turbine_token).map_or(0, |s| s.offset_sec);
+    let accepted = batch.lines.len();
+    for line in batch.lines {
+        let line = RobotLogLine {
+            turbine_token: batch.turbine_token.clone(),
+            timestamp: line.timestamp.map_or(now, |t| t.saturating_add_signed(-offset)),
+            level: line.level,
+            target: line.target,
+            message: line.message.chars().take(MAX_LOG_LINE_CHARS).collect(),
+        };
+        // Sin consolas abiertas el envío falla; no es un error
+        let _ = state.robot_logs.send(line);
+    }
+    Ok(Json(LogAck { accepted }))
+}
+
+#[derive(Deserialize)]
+pub struct LogTailParams {
+    // Nivel mínimo (por defecto todos)
+    #[serde(default)]
+    level: Option<LogLevel>,
+}
+
+pub async fn tail_logs_handler(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(params): Query<LogTailParams>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let receiver = state.robot_logs.subscribe();
+    let min_level = params.level.unwrap_or(LogLevel::Trace);
+    ws.on_upgrade(move |socket| forward_log_lines(receiver, token, min_level, socket))
+}
+
+async fn forward_log_lines(mut receiver: Receiver<RobotLogLine>, token: String, min_level: LogLevel, mut socket: WebSocket) {
+    tracing::info!(turbine_token = %token, level = ?min_level, "🖥️ Consola de logs abierta");
+    loop {
+        tokio::select! {
+            line = receiver.recv() => match line {
+                Ok(line) => {
+                    if line.turbine_token != token || line.level < min_level {
+                        continue;
+                    }
+                    let Ok(json) = serde_json::to_string(&line) else { continue };
+                    if socket.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                // Una consola lenta pierde líneas; se le avisa de cuántas
+                Err(RecvError::Lagged(skipped)) => {
+                    let notice = serde_json::json!({ "turbine_token": token, "skipped": skipped });
+                    if socket.send(Message::Text(notice.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+    tracing::info!(turbine_token = %token, "🖥️ Consola de logs cerrada");
+}
@@ -0,0 +1,124 @@
+use super::{
+    fleet::{live_map_status, MapStatus},
+    web::display_unit,
+};
+use crate::{
+    analysis::{hottest_frame, thumbnail::thumbnail_png},
+    error::AppError,
+    scada::OPEN_ALERT_WINDOW_SEC,
+    state::AppState,
+    storage::{archive::stored_path, catalog::CaptureRecord, lineage::record_derivation, read_capture},
+    units::TempUnit,
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, sync::Arc};
+
+// --- RESUMEN PARA LA APP MÓVIL ---
+// Una sola respuesta pequeña para la pantalla de inicio: estado y temperatura de cada
+// turbina, alertas sin leer y una miniatura de su última captura. Los campos vacíos se
+// omiten para ahorrar bytes con mala cobertura.
+
+// Lado mayor de las miniaturas, en píxeles
+const THUMBNAIL_SIDE: usize = 32;
+
+#[derive(Deserialize)]
+pub struct OverviewParams {
+    // Última vez que el usuario vio las alertas (segundos Unix); por defecto, las abiertas
+    since: Option<u64>,
+    // false para omitir las miniaturas
+    thumbnails: Option<bool>,
+    units: Option<TempUnit>,
+}
+
+#[derive(Serialize)]
+pub struct MobileTurbine {
+    token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    status: MapStatus,
+    // Temperatura máxima actual (del último heartbeat), a una décima
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temp: Option<f32>,
+    #[serde(skip_serializing_if = "is_zero")]
+    unread: u32,
+    // Última captura: instante y miniatura PNG como data URI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capture_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+#[derive(Serialize)]
+pub struct MobileOverview {
+    generated: u64,
+    turbines: Vec<MobileTurbine>,
+}
+
+// Miniatura de la captura (de la caché si ya se generó)
+async fn capture_thumbnail(state: &Arc<AppState>, record: &CaptureRecord) -> Option<String> {
+    if let Some(cached) = state.thumbnail_cache.lock().await.get(&record.sha256).cloned() {
+        return Some(cached);
+    }
+    let path = stored_path(record);
+    let (frame_index, encoded) = tokio::task::spawn_blocking(move || {
+        let data = read_capture(&path).ok()?;
+        let (frame_index, frame) = hottest_frame(&data)?;
+        let png = thumbnail_png(&frame, THUMBNAIL_SIDE).ok()?;
+        Some((frame_index, format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png))))
+    })
+    .await
+    .ok()
+    .flatten()?;
+    let params = serde_json::json!({ "palette": "ironbow", "max_side": THUMBNAIL_SIDE });
+    record_derivation(state, "thumbnail", &[(&record.filename, Some(frame_index))], params, None).await;
+    state.thumbnail_cache.lock().await.put(record.sha256.clone(), encoded.clone());
+    Some(encoded)
+}
+
+pub async fn mobile_overview(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<OverviewParams>,
+) -> Result<Json<MobileOverview>, AppError> {
+    let now = chrono::Utc::now();
+    let since = params.since.unwrap_or((now.timestamp() as u64).saturating_sub(OPEN_ALERT_WINDOW_SEC));
+    let unit = display_unit(&state, params.units).await;
+    let registered = state.turbines.read().await.clone();
+    let statuses = state.turbine_status.read().await.clone();
+
+    // Registradas más las que solo han enviado heartbeat
+    let tokens: BTreeSet<String> = registered.iter().map(|t| t.token.clone()).chain(statuses.keys().cloned()).collect();
+    let mut turbines = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let live = statuses.get(&token);
+        let unread = state.alerts.read().await.iter()
+            .filter(|a| a.turbine_token == token && a.timestamp > since)
+            .count() as u32;
+        let latest = state.catalog.read().await.iter()
+            .filter(|r| r.turbine_token == token && r.integrity_error.is_none())
+            .max_by_key(|r| r.timestamp)
+            .cloned();
+        let thumbnail = match (&latest, params.thumbnails.unwrap_or(true)) {
+            (Some(record), true) => capture_thumbnail(&state, record).await,
+            _ => None,
+        };
+        turbines.push(MobileTurbine {
+            name: registered.iter().find(|t| t.token == token).map(|t| t.name.clone()),
+            status: live_map_status(&state, &token, live, &now).await,
+            temp: live.map(|l| (unit.temp(l.current_max_temp) * 10.0).round() / 10.0),
+            unread,
+            capture_time: latest.map(|r| r.timestamp),
+            thumbnail,
+            token,
+        });
+    }
+    Ok(Json(MobileOverview { generated: now.timestamp() as u64, turbines }))
+}
@@ -0,0 +1,222 @@
+use crate::{graphql, metrics, state::AppState, telemetry};
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post, put},
+    Router,
+};
+use std::sync::Arc;
+use tower_http::cors::{Any, CorsLayer};
+
+pub mod admin;
+pub mod analytics;
+pub mod annotations;
+pub mod calendar;
+pub mod collections;
+pub mod control;
+pub mod devices;
+pub mod digest;
+pub mod direct_upload;
+pub mod external;
+pub mod fleet;
+pub mod health;
+pub mod ingest;
+pub mod lineage;
+pub mod logs;
+pub mod mobile;
+pub mod notifications;
+pub mod preferences;
+pub mod push;
+pub mod quality;
+pub mod quarantine;
+pub mod read_only;
+pub mod replay;
+pub mod replication;
+pub mod reports;
+pub mod rules;
+pub mod sensors;
+pub mod sessions;
+pub mod share;
+pub mod status;
+pub mod stream;
+pub mod timeline;
+pub mod web;
+
+// Router completo del servidor (API web, administración e ingesta de los robots)
+pub fn build_router(state: Arc<AppState>) -> Router {
+    // CORS Permisivo
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    let read_only = state.settings.read_only;
+    let router = Router::new()
+        // --- API WEB ---
+        .route("/api/live", get(web::get_live_status))
+        .route("/api/live/stream", get(stream::live_stream_handler))
+        .route("/api/live/frames/:token", get(web::get_live_frames))
+        .route("/ws/logs/:token", get(logs::tail_logs_handler))
+        .route("/ws/replay/:session_id", get(replay::replay_session_handler))
+        .route("/api/config", get(web::get_config).post(web::update_config))
+        .route("/api/config/effective", get(web::get_effective_config))
+        .route("/api/config/history", get(web::get_config_history))
+        .route("/api/config/history/:version", get(web::get_config_version))
+        .route("/api/config/rollback/:version", post(web::rollback_config))
+        .route("/api/alerts", get(web::get_alerts))
+        .route("/api/alerts/digest", get(digest::alert_digest_handler))
+        .route("/api/alerts/export", get(web::export_alerts_handler))
+        .route("/api/alerts/:id/star", put(web::star_alert).delete(web::star_alert))
+        .route("/api/alerts/:id/confirm", put(web::confirm_alert))
+        .route("/api/incidents", get(web::get_incidents))
+        .route("/api/incidents/:id/timeline", get(web::incident_timeline))
+        .route("/api/rules", get(rules::list_rules).post(rules::create_rule))
+        .route("/api/rules/:id", get(rules::get_rule).put(rules::update_rule).delete(rules::delete_rule))
+        .route("/api/schedules", get(reports::list_schedules).post(reports::create_schedule))
+        .route(
+            "/api/schedules/:id",
+            get(reports::get_schedule).put(reports::update_schedule).delete(reports::delete_schedule),
+        )
+        .route("/api/schedules/:id/run", post(reports::run_schedule_handler))
+        .route("/api/reports", get(reports::list_reports_handler))
+        .route("/api/reports/:name", get(reports::download_report_handler))
+        .route("/api/notifications/failed", get(notifications::list_failed))
+        .route("/api/notifications/failed/:id", delete(notifications::discard_failed))
+        .route("/api/notifications/failed/:id/retry", post(notifications::retry_failed))
+        .route("/api/webhooks/signing", get(notifications::signing_scheme))
+        .route("/api/webhooks/verify", post(notifications::verify_webhook))
+        .route("/api/push/devices", get(push::list_devices).post(push::register_device))
+        .route("/api/push/devices/:token", delete(push::unregister_device))
+        .route("/api/push/preferences/:user", get(push::get_preferences).put(push::update_preferences))
+        .route(
+            "/api/preferences",
+            get(preferences::get_preferences).put(preferences::update_preferences).delete(preferences::delete_preferences),
+        )
+        .route("/api/files", get(web::list_files_handler))
+        .route(
+            "/api/files/:filename/annotations",
+            get(annotations::list_annotations).post(annotations::create_annotation),
+        )
+        .route("/api/files/:filename/annotations/:id", delete(annotations::delete_annotation))
+        .route("/api/files/:filename/derived", get(lineage::file_lineage_handler))
+        .route("/api/captures", get(web::list_captures_handler))
+        .route("/api/captures/:filename/star", put(web::star_capture).delete(web::star_capture))
+        .route("/api/collections", get(collections::list_collections).post(collections::create_collection))
+        .route(
+            "/api/collections/:id",
+            get(collections::get_collection)
+                .patch(collections::update_collection)
+                .delete(collections::delete_collection),
+        )
+        .route("/api/turbines", get(fleet::list_turbines).post(fleet::upsert_turbine))
+        .route("/api/turbines/:token", get(fleet::get_turbine).delete(fleet::delete_turbine))
+        .route("/api/turbines/:token/data", delete(fleet::purge_turbine_data))
+        .route("/api/turbines/:token/cameras", get(fleet::list_cameras))
+        .route("/api/devices/clock", get(devices::get_clock_skew))
+        .route("/api/devices/ingest-rates", get(devices::get_ingest_rates))
+        .route("/api/devices/import", post(devices::import_devices))
+        .route("/api/devices/:token/rotate-key", post(devices::rotate_ingest_key))
+        .route("/api/devices/:token/shadow", get(devices::get_shadow))
+        .route("/api/devices/:token/calibration", get(devices::get_calibration).post(devices::create_calibration))
+        .route("/api/devices/:token/calibration/history", get(devices::get_calibration_history))
+        .route("/api/devices/:token/calibration/:version", get(devices::get_calibration_version))
+        .route("/api/commands/:token", get(control::list_commands).post(control::enqueue_command))
+        .route("/api/commands/:token/:id", get(control::get_command))
+        .route("/api/control/:token/goto", post(control::goto_angle))
+        .route("/api/control/:token/scan", post(control::start_scan))
+        .route("/api/control/:token/stop", post(control::stop))
+        .route("/api/control/:token/home", post(control::home))
+        .route("/api/map", get(fleet::map_handler))
+        .route("/api/latest/:token", get(fleet::latest_by_angle))
+        .route("/api/mobile/overview", get(mobile::mobile_overview))
+        .route("/api/alertmanager/alerts", get(fleet::alertmanager_alerts))
+        .route("/api/telemetry", get(sensors::get_telemetry))
+        .route("/api/quality/:token", get(quality::quality_report_handler))
+        .route("/api/quarantine", get(quarantine::list_quarantine))
+        .route("/api/quarantine/:id", get(quarantine::get_quarantined).delete(quarantine::purge_quarantined))
+        .route("/api/quarantine/:id/data", get(quarantine::download_quarantined))
+        .route("/api/quarantine/:id/accept", post(quarantine::accept_quarantined))
+        .route("/api/replication/config", get(replication::replication_config))
+        .route("/api/replication/captures", get(replication::replication_captures))
+        .route("/api/replication/captures/:filename", get(replication::replication_capture_data))
+        .route("/api/replication/alerts", get(replication::replication_alerts))
+        .route("/api/timeline/:token", get(timeline::timeline_handler))
+        .route("/api/calendar/:token", get(calendar::calendar_handler))
+        .route("/api/forecast/:token", get(analytics::forecast_handler))
+        .route("/api/compare/turbines", get(analytics::compare_turbines_handler))
+        .route("/api/sessions/compare", get(sessions::compare_sessions_handler))
+        .route("/api/sessions/coverage", get(sessions::session_coverage_handler))
+        .route("/api/sessions/current/:token", get(sessions::current_session_handler))
+        .route("/api/sessions/:id/keyframes", get(sessions::session_keyframes_handler))
+        .route("/api/sessions/:id/track", get(sessions::session_track_handler))
+        .route("/api/sessions/:id/package", get(sessions::session_package_handler))
+        .route("/api/evolution/:filename", get(web::get_evolution_data))
+        .route("/api/storage", get(health::storage_report_handler))
+        .route("/api/storage/integrity", get(health::integrity_report_handler))
+        .route("/readyz", get(health::readyz_handler))
+        .route("/api/watchdog", get(health::watchdog_status_handler))
+        .route("/status", get(status::status_handler))
+        .route("/metrics", get(metrics::metrics_handler))
+
+        // --- NUEVOS ENDPOINTS SOLICITADOS ---
+        // Descarga de archivos forzada
+        .route("/api/download/:filename", get(web::download_file_handler))
+        // Foto visible tomada junto a la captura térmica
+        .route("/api/photo/:filename", get(web::get_photo_handler))
+        // Térmico superpuesto a la foto visible (?alpha=0.5)
+        .route("/api/fusion/:filename", get(web::get_fusion_handler))
+        // Exportación completa (tar en streaming)
+        .route("/api/export/all", get(web::export_all_handler))
+        // Obtención de matriz cruda para visualización térmica
+        .route("/api/matrix/:filename/:frame_index", get(web::get_matrix_handler))
+        // Varios frames de una vez (?frames=5..20&stride=2)
+        .route("/api/matrix/:filename", get(web::get_matrix_range_handler))
+        // Frame renderizado como PNG (?max_side= para miniaturas)
+        .route("/api/render/:filename/:frame_index", get(web::get_render_handler))
+        // Instantánea de un frame con enlace público que caduca
+        .route("/api/share", post(share::create_share))
+        .route("/api/share/:token", delete(share::revoke_share))
+        .route("/share/:token", get(share::view_share))
+        .route("/share/:token/image.png", get(share::share_image))
+        // Arrays con nombre de las capturas npz (ángulos e instantes por frame)
+        .route("/api/arrays/:filename", get(web::list_arrays_handler))
+        .route("/api/arrays/:filename/:name", get(web::get_array_handler))
+        // Estadísticas por pala de un frame
+        .route("/api/blades/:filename/:frame_index", get(web::get_blades_handler))
+        // Diferencia entre dos frames, alineados entre sí
+        .route("/api/diff", get(web::get_diff_handler))
+
+        // --- ADMINISTRACIÓN ---
+        .route("/api/admin/backup", post(admin::backup_handler))
+        .route("/api/admin/restore", post(admin::restore_handler).layer(DefaultBodyLimit::disable()))
+        .route("/api/admin/replay", post(admin::replay_handler))
+        .route("/api/admin/import", post(admin::import_handler))
+        .route("/api/admin/reset", post(admin::reset_handler))
+        .route("/api/admin/generate", post(admin::generate_handler))
+        .route("/api/admin/config/reveal", post(admin::reveal_config_handler))
+
+        // --- API ROBOT (CORE) ---
+        .route("/ingest/heartbeat", post(ingest::heartbeat_handler))
+        .route("/ingest/time", get(ingest::time_handler))
+        .route("/ingest/config/:token", get(ingest::robot_config_handler))
+        .route("/ingest/commands/:token", get(ingest::poll_commands_handler))
+        .route("/ingest/commands/:token/:id", post(control::update_command))
+        .route("/ingest/upload", post(ingest::upload_handler))
+        .route("/ingest/upload/init", post(direct_upload::init_upload_handler))
+        .route("/ingest/upload/complete", post(direct_upload::complete_upload_handler))
+        .route("/ingest/receipt/:id", get(ingest::receipt_handler))
+        .route("/ingest/external", post(external::external_upload_handler))
+        .route("/ingest/ambient", post(ingest::ambient_handler))
+        .route("/ingest/telemetry", post(ingest::telemetry_handler))
+        .route("/ingest/logs", post(logs::ingest_logs_handler))
+        .route("/ingest/stream", get(stream::ingest_stream_handler))
+
+        // --- GRAPHQL ---
+        .merge(graphql::router(state.clone()))
+
+        .layer(middleware::from_fn_with_state(state.clone(), metrics::track_metrics))
+        .with_state(state);
+    let router = if read_only { router.layer(middleware::from_fn(read_only::reject_writes)) } else { router };
+
+    telemetry::with_request_tracing(router).layer(cors)
+}
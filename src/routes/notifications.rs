@@ -0,0 +1,123 @@
+use crate::{
+    error::AppError,
+    notify::{
+        spawn_delivery, verify_signature, ChannelKind, Delivery, SIGNATURE_HEADER, SIGNATURE_TOLERANCE_SEC,
+        SIGNATURE_VERSION, TIMESTAMP_HEADER,
+    },
+    state::AppState,
+    storage::deliveries::save_failed_deliveries,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// --- ENTREGAS FALLIDAS (DEAD LETTER) ---
+// Notificaciones que agotaron los reintentos automáticos: se listan, se reenvían a mano
+// (vuelven a la cola con los reintentos desde cero) o se descartan.
+
+pub async fn list_failed(State(state): State<Arc<AppState>>) -> Json<Vec<Delivery>> {
+    // La más reciente primero
+    Json(state.failed_deliveries.read().await.iter().rev().cloned().collect())
+}
+
+async fn take_failed(state: &AppState, id: &str) -> Result<Delivery, AppError> {
+    let mut failed = state.failed_deliveries.write().await;
+    let index = failed.iter()
+        .position(|d| d.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Failed delivery '{}' not found", id)))?;
+    let delivery = failed.remove(index);
+    save_failed_deliveries(&failed);
+    Ok(delivery)
+}
+
+pub async fn retry_failed(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, Json<Delivery>), AppError> {
+    let mut delivery = take_failed(&state, &id).await?;
+    delivery.attempts = 0;
+    delivery.failed_at = None;
+    tracing::info!(%id, channel = %delivery.channel, "📨 Reenvío manual de notificación");
+    spawn_delivery(&state, delivery.clone()).await;
+    Ok((StatusCode::ACCEPTED, Json(delivery)))
+}
+
+pub async fn discard_failed(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let delivery = take_failed(&state, &id).await?;
+    tracing::info!(%id, channel = %delivery.channel, "🗑️ Notificación fallida descartada");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// --- VERIFICACIÓN DE FIRMAS ---
+// GET /api/webhooks/signing: receta para que un receptor compruebe las firmas (ver
+// notify::FIRMA DE LOS WEBHOOKS). POST /api/webhooks/verify: comprueba una firma recibida
+// con la clave del canal indicado, para depurar la integración sin compartir la clave.
+
+#[derive(Serialize)]
+pub struct SigningScheme {
+    algorithm: &'static str,
+    timestamp_header: &'static str,
+    signature_header: &'static str,
+    // Formato de la cabecera de firma y del texto firmado
+    signature_format: String,
+    signed_payload: &'static str,
+    tolerance_sec: u64,
+    // Canales que firman sus envíos
+    signed_channels: Vec<String>,
+}
+
+pub async fn signing_scheme(State(state): State<Arc<AppState>>) -> Json<SigningScheme> {
+    let signed_channels = state.config.read().await.channels.iter()
+        .filter(|c| matches!(c.kind, ChannelKind::Webhook { secret: Some(_), .. }))
+        .map(|c| c.name.clone())
+        .collect();
+    Json(SigningScheme {
+        algorithm: "HMAC-SHA256",
+        timestamp_header: TIMESTAMP_HEADER,
+        signature_header: SIGNATURE_HEADER,
+        signature_format: format!("{}=<lowercase hex digest>", SIGNATURE_VERSION),
+        signed_payload: "<timestamp>.<raw request body>",
+        tolerance_sec: SIGNATURE_TOLERANCE_SEC,
+        signed_channels,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct VerifyRequest {
+    channel: String,
+    timestamp: u64,
+    signature: String,
+    // Cuerpo recibido, tal cual
+    body: String,
+}
+
+#[derive(Serialize)]
+pub struct VerifyResult {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+}
+
+pub async fn verify_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<VerifyRequest>,
+) -> Result<Json<VerifyResult>, AppError> {
+    let secret = state.config.read().await.channels.iter()
+        .find(|c| c.name == request.channel)
+        .ok_or_else(|| AppError::NotFound(format!("Channel '{}' not found", request.channel)))
+        .and_then(|c| match &c.kind {
+            ChannelKind::Webhook { secret: Some(secret), .. } => Ok(secret.clone()),
+            _ => Err(AppError::BadRequest(format!("Channel '{}' does not sign its deliveries", request.channel))),
+        })?;
+    let secret = state.secrets.reveal(&secret).map_err(|e| AppError::Internal(e.to_string()))?;
+    let now = chrono::Utc::now().timestamp() as u64;
+    let result = verify_signature(&secret, request.timestamp, request.body.as_bytes(), &request.signature, now);
+    Ok(Json(VerifyResult { valid: result.is_ok(), reason: result.err() }))
+}
@@ -0,0 +1,96 @@
+use super::web::change_author;
+use crate::{
+    error::AppError,
+    schedule::parse_timezone,
+    state::AppState,
+    storage::preferences::{save_preferences, UserPreferences},
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::{collections::HashSet, sync::Arc};
+
+// --- API DE PREFERENCIAS ---
+// GET/PUT/DELETE /api/preferences leen, sustituyen o borran las preferencias del usuario
+// que hace la petición, identificado por la cabecera X-User que envía el dashboard (la
+// misma que firma los cambios de configuración). Sin preferencias guardadas se devuelven
+// vacías: el dashboard aplica sus valores por defecto.
+
+const MAX_PINNED_TURBINES: usize = 200;
+const MAX_PALETTE_LEN: usize = 64;
+// Tamaño máximo de los ajustes libres (JSON serializado)
+const MAX_EXTRA_BYTES: usize = 16 * 1024;
+
+fn request_user(headers: &HeaderMap) -> Result<String, AppError> {
+    change_author(headers)
+        .map(|user| user.trim().to_string())
+        .filter(|user| !user.is_empty())
+        .ok_or(AppError::Unauthorized("Missing X-User header"))
+}
+
+fn validate(preferences: &UserPreferences) -> Result<(), String> {
+    if let Some(palette) = &preferences.palette
+        && (palette.trim().is_empty() || palette.len() > MAX_PALETTE_LEN)
+    {
+        return Err(format!("palette must be between 1 and {} characters", MAX_PALETTE_LEN));
+    }
+    if let Some(tz) = &preferences.timezone {
+        parse_timezone(tz).map_err(|e| format!("timezone: {}", e))?;
+    }
+    if preferences.pinned_turbines.len() > MAX_PINNED_TURBINES {
+        return Err(format!("at most {} pinned turbines", MAX_PINNED_TURBINES));
+    }
+    if preferences.pinned_turbines.iter().any(|t| t.trim().is_empty()) {
+        return Err("pinned_turbines cannot contain empty tokens".into());
+    }
+    let extra_len = serde_json::to_vec(&preferences.extra).map_or(0, |json| json.len());
+    if extra_len > MAX_EXTRA_BYTES {
+        return Err(format!("extra settings exceed {} bytes", MAX_EXTRA_BYTES));
+    }
+    Ok(())
+}
+
+pub async fn get_preferences(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<UserPreferences>, AppError> {
+    let user = request_user(&headers)?;
+    let preferences = state.preferences.read().await.iter()
+        .find(|p| p.user == user)
+        .cloned()
+        .unwrap_or_else(|| UserPreferences::new(&user));
+    Ok(Json(preferences))
+}
+
+pub async fn update_preferences(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut preferences): Json<UserPreferences>,
+) -> Result<Json<UserPreferences>, AppError> {
+    preferences.user = request_user(&headers)?;
+    validate(&preferences).map_err(AppError::BadRequest)?;
+    let mut seen = HashSet::new();
+    preferences.pinned_turbines.retain(|t| seen.insert(t.clone()));
+    preferences.updated = chrono::Utc::now().timestamp() as u64;
+    let mut stored = state.preferences.write().await;
+    stored.retain(|p| p.user != preferences.user);
+    stored.push(preferences.clone());
+    save_preferences(&stored);
+    Ok(Json(preferences))
+}
+
+pub async fn delete_preferences(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, AppError> {
+    let user = request_user(&headers)?;
+    let mut stored = state.preferences.write().await;
+    let before = stored.len();
+    stored.retain(|p| p.user != user);
+    if stored.len() != before {
+        save_preferences(&stored);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
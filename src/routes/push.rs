@@ -0,0 +1,96 @@
+use crate::{
+    error::AppError,
+    state::AppState,
+    storage::push::{save_push_registry, PushDevice, PushPreferences},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- REGISTRO DE DISPOSITIVOS PUSH ---
+// La app móvil registra su token FCM al iniciar sesión (y lo borra al cerrarla); cada
+// usuario elige qué avisos recibe y de qué turbinas.
+
+#[derive(Deserialize)]
+pub struct DeviceRegistration {
+    token: String,
+    user: String,
+    #[serde(default)]
+    platform: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceParams {
+    #[serde(default)]
+    user: Option<String>,
+}
+
+pub async fn list_devices(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DeviceParams>,
+) -> Json<Vec<PushDevice>> {
+    Json(state.push.read().await.devices.iter()
+        .filter(|d| params.user.as_ref().is_none_or(|u| *u == d.user))
+        .cloned()
+        .collect())
+}
+
+// Alta o reasignación (por token) de un dispositivo
+pub async fn register_device(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DeviceRegistration>,
+) -> Result<(StatusCode, Json<PushDevice>), AppError> {
+    if request.token.trim().is_empty() || request.user.trim().is_empty() {
+        return Err(AppError::BadRequest("token and user are required".into()));
+    }
+    let device = PushDevice {
+        token: request.token.trim().to_string(),
+        user: request.user.trim().to_string(),
+        platform: request.platform,
+        registered: chrono::Utc::now().timestamp() as u64,
+    };
+    let mut registry = state.push.write().await;
+    registry.devices.retain(|d| d.token != device.token);
+    registry.devices.push(device.clone());
+    save_push_registry(&registry);
+    tracing::info!(user = %device.user, platform = device.platform.as_deref(), "📱 Dispositivo push registrado");
+    Ok((StatusCode::CREATED, Json(device)))
+}
+
+pub async fn unregister_device(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let mut registry = state.push.write().await;
+    let before = registry.devices.len();
+    registry.devices.retain(|d| d.token != token);
+    if registry.devices.len() == before {
+        return Err(AppError::NotFound("Push device not registered".into()));
+    }
+    save_push_registry(&registry);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_preferences(
+    State(state): State<Arc<AppState>>,
+    Path(user): Path<String>,
+) -> Json<PushPreferences> {
+    Json(state.push.read().await.preferences_of(&user))
+}
+
+pub async fn update_preferences(
+    State(state): State<Arc<AppState>>,
+    Path(user): Path<String>,
+    Json(mut preferences): Json<PushPreferences>,
+) -> Json<PushPreferences> {
+    preferences.user = user;
+    let mut registry = state.push.write().await;
+    registry.preferences.retain(|p| p.user != preferences.user);
+    registry.preferences.push(preferences.clone());
+    save_push_registry(&registry);
+    Json(preferences)
+}
@@ -0,0 +1,59 @@
+use crate::{
+    error::AppError,
+    quality::{build_quality_report, QualityReport},
+    schedule::{parse_duration, parse_timestamp},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- INFORME DE CALIDAD DE DATOS ---
+// GET /api/quality/:token?from=&to= (o ?window=30d hasta ahora). Por defecto, los
+// últimos 7 días.
+
+const DEFAULT_WINDOW_SEC: u64 = 7 * 86400;
+// Periodo máximo de un informe
+const MAX_PERIOD_SEC: u64 = 92 * 86400;
+
+#[derive(Deserialize)]
+pub struct PeriodParams {
+    // Segundos Unix o RFC 3339
+    from: Option<String>,
+    to: Option<String>,
+    // Duración hacia atrás desde `to` ("24h", "30d") si no se indica `from`
+    window: Option<String>,
+}
+
+pub async fn quality_report_handler(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Json<QualityReport>, AppError> {
+    if !state.knows_turbine(&token).await {
+        return Err(AppError::NotFound(format!("Turbine '{}' not found", token)));
+    }
+    let (from, to) = parse_period(&params, DEFAULT_WINDOW_SEC, MAX_PERIOD_SEC)?;
+    Ok(Json(build_quality_report(&state, &token, from, to).await?))
+}
+
+// Periodo (from, to) de los parámetros, validado; también lo usa la línea de tiempo
+pub(crate) fn parse_period(params: &PeriodParams, default_window_sec: u64, max_period_sec: u64) -> Result<(u64, u64), AppError> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let to = params.to.as_deref().map(parse_timestamp).transpose().map_err(AppError::BadRequest)?.unwrap_or(now);
+    let from = match (params.from.as_deref(), params.window.as_deref()) {
+        (Some(from), _) => parse_timestamp(from).map_err(AppError::BadRequest)?,
+        (None, Some(window)) => to.saturating_sub(parse_duration(window).map_err(AppError::BadRequest)?),
+        (None, None) => to.saturating_sub(default_window_sec),
+    };
+    if from >= to {
+        return Err(AppError::BadRequest("'from' must be before 'to'".into()));
+    }
+    if to - from > max_period_sec {
+        return Err(AppError::BadRequest(format!("The period can span at most {} days", max_period_sec / 86400)));
+    }
+    Ok((from, to))
+}
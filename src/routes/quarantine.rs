@@ -0,0 +1,165 @@
+use super::{
+    admin::require_admin,
+    ingest::{ingest_capture, CaptureUpload, UploadResponse},
+};
+use crate::{
+    analysis::pixel_units::PixelUnit,
+    error::AppError,
+    state::AppState,
+    storage::{
+        audit::{append_audit, AuditEntry},
+        catalog::sha256_hex,
+        encode_capture,
+        quarantine::{read_quarantined, remove_quarantined, save_quarantine, try_save_quarantine, write_quarantined, QuarantineReason, QuarantinedUpload},
+    },
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::{cmp::Reverse, sync::Arc};
+
+// --- REVISIÓN DE LA CUARENTENA ---
+// GET /api/quarantine lista las subidas apartadas con su motivo (?turbine_token= para una
+// turbina) y GET /api/quarantine/:id/data descarga su contenido para inspeccionarlo.
+// Con el token de administración, POST /api/quarantine/:id/accept la ingiere igualmente
+// (sin validar el formato ni los límites de temperatura) y DELETE /api/quarantine/:id
+// la borra. Ambas quedan en la auditoría.
+
+// Aparta una subida rechazada; devuelve su id en la cuarentena
+pub async fn quarantine_upload(state: &AppState, upload: &CaptureUpload, reason: QuarantineReason) -> Result<String, AppError> {
+    let entry = QuarantinedUpload {
+        id: uuid::Uuid::new_v4().to_string(),
+        turbine_token: upload.turbine_token.clone(),
+        angle: upload.angle,
+        rotor_phase: upload.rotor_phase,
+        camera_id: upload.camera_id.clone(),
+        source: upload.source.clone(),
+        captured_at: upload.captured_at,
+        received: chrono::Utc::now().timestamp() as u64,
+        sha256: sha256_hex(&upload.data),
+        size_bytes: upload.data.len() as u64,
+        reason,
+    };
+    let (id, data, zstd_level) = (entry.id.clone(), upload.data.clone(), state.settings.zstd_level);
+    tokio::task::spawn_blocking(move || write_quarantined(&id, &encode_capture(&data, zstd_level)?)).await??;
+    // Su id es el recibo de la subida: el índice tiene que estar en disco antes de darlo
+    let mut quarantine = state.quarantine.write().await;
+    quarantine.push(entry.clone());
+    if let Err(e) = try_save_quarantine(&quarantine) {
+        quarantine.pop();
+        return Err(e.into());
+    }
+    tracing::info!(id = %entry.id, turbine_token = %entry.turbine_token, "🚧 Subida apartada en cuarentena");
+    Ok(entry.id)
+}
+
+#[derive(Deserialize)]
+pub struct QuarantineParams {
+    turbine_token: Option<String>,
+}
+
+pub async fn list_quarantine(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<QuarantineParams>,
+) -> Json<Vec<QuarantinedUpload>> {
+    let mut entries: Vec<QuarantinedUpload> = state.quarantine.read().await.iter()
+        .filter(|e| params.turbine_token.as_ref().is_none_or(|t| e.turbine_token == *t))
+        .cloned()
+        .collect();
+    entries.sort_by_key(|e| Reverse(e.received));
+    Json(entries)
+}
+
+async fn find_entry(state: &AppState, id: &str) -> Result<QuarantinedUpload, AppError> {
+    state.quarantine.read().await.iter()
+        .find(|e| e.id == id)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Quarantined upload '{}' not found", id)))
+}
+
+pub async fn get_quarantined(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<QuarantinedUpload>, AppError> {
+    find_entry(&state, &id).await.map(Json)
+}
+
+pub async fn download_quarantined(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    let entry = find_entry(&state, &id).await?;
+    let data = tokio::task::spawn_blocking(move || read_quarantined(&entry.id)).await??;
+    let headers = [
+        (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"quarantine_{}.npz\"", id)),
+    ];
+    Ok((headers, data).into_response())
+}
+
+// Quita la entrada del índice y su archivo
+async fn discard(state: &AppState, id: &str) -> Result<(), AppError> {
+    {
+        let mut quarantine = state.quarantine.write().await;
+        quarantine.retain(|e| e.id != id);
+        save_quarantine(&quarantine);
+    }
+    let id = id.to_string();
+    tokio::task::spawn_blocking(move || remove_quarantined(&id)).await??;
+    Ok(())
+}
+
+pub async fn accept_quarantined(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<UploadResponse>, AppError> {
+    require_admin(&state, &headers)?;
+    let entry = find_entry(&state, &id).await?;
+    let worker_id = entry.id.clone();
+    let data = tokio::task::spawn_blocking(move || read_quarantined(&worker_id)).await??;
+    let upload = CaptureUpload {
+        turbine_token: entry.turbine_token.clone(),
+        angle: entry.angle,
+        rotor_phase: entry.rotor_phase,
+        camera_id: entry.camera_id.clone(),
+        source: entry.source.clone(),
+        // La toma es de cuando llegó, no de ahora
+        captured_at: Some(entry.captured_at.unwrap_or(entry.received)),
+        force: true,
+        // El robot ya tiene el id de la cuarentena como recibo
+        receipt_id: Some(entry.id.clone()),
+        data: Bytes::from(data),
+        photo: None,
+        // Lo apartado ya se convirtió a °C al recibirlo
+        pixel_unit: PixelUnit::Celsius,
+    };
+    let response = ingest_capture(&state, upload).await?;
+    discard(&state, &id).await?;
+    append_audit(&AuditEntry::new("accept_quarantined", &id, serde_json::json!({
+        "turbine_token": entry.turbine_token,
+        "filename": response.filename,
+        "reason": entry.reason,
+    })));
+    Ok(Json(response))
+}
+
+pub async fn purge_quarantined(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<&'static str>, AppError> {
+    require_admin(&state, &headers)?;
+    let entry = find_entry(&state, &id).await?;
+    discard(&state, &id).await?;
+    append_audit(&AuditEntry::new("purge_quarantined", &id, serde_json::json!({
+        "turbine_token": entry.turbine_token,
+        "reason": entry.reason,
+    })));
+    Ok(Json("Quarantined upload removed"))
+}
@@ -0,0 +1,25 @@
+use crate::error::AppError;
+use axum::{extract::Request, http::Method, middleware::Next, response::Response};
+
+// --- MODO SOLO LECTURA ---
+// Con --read-only el servidor sirve las API de lectura desde una copia montada de
+// cloud_storage (la instantánea del almacenamiento de la instancia viva), para desviar a
+// ella el tráfico pesado de analítica sin tocar el pipeline en vivo. Se rechaza toda la
+// ingesta (/ingest/...) y cualquier otra petición que no sea GET, salvo las consultas que
+// van por POST sin escribir nada. Las tareas de fondo que escriben no se arrancan (ver
+// main.rs) y las capturas archivadas se leen del archivo sin restaurarlas.
+
+// Consultas por POST que no modifican nada
+const READ_ONLY_POSTS: [&str; 3] = ["/api/graphql", "/api/webhooks/verify", "/api/admin/backup"];
+
+pub async fn reject_writes(request: Request, next: Next) -> Result<Response, AppError> {
+    let path = request.uri().path();
+    let method = request.method();
+    let read = *method == Method::GET
+        || *method == Method::HEAD
+        || (*method == Method::POST && READ_ONLY_POSTS.contains(&path));
+    if path.starts_with("/ingest/") || !read {
+        return Err(AppError::Forbidden("Read-only replica: ingest and changes are disabled"));
+    }
+    Ok(next.run(request).await)
+}
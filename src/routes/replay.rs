@@ -0,0 +1,333 @@
+use super::web::display_unit;
+use crate::{
+    analysis::{
+        frame_data,
+        npz::frame_axes,
+        registration::downsample,
+        thumbnail::{encode_png, render_rgb},
+        with_frames, ThermalFrameData,
+    },
+    error::AppError,
+    sessions::{session_info, session_of, without_angle, SessionInfo},
+    state::AppState,
+    storage::{archive::locate_capture, catalog::CaptureRecord, open_capture},
+    units::TempUnit,
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    response::Response,
+};
+use ndarray::Axis;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+// --- REPRODUCCIÓN DE SESIONES ---
+// /ws/replay/:session_id (id = cualquier captura de la sesión) reproduce los frames de un
+// escaneo pasado en orden, como un vídeo, en lugar de abrirlos uno a uno. El ritmo es el
+// de la toma (instantes de cada frame o de cada captura) acelerado por ?speed= ("4x" o
+// "4", de 0.25 a 32; 1 por defecto), con cada paso entre 1 y 10 s de tiempo de sesión
+// para que las pausas largas no detengan la reproducción. Parámetros:
+// - format=matrix (por defecto): cada frame es un mensaje JSON con la matriz reducida
+//   (lado mayor ?max_side=, 80 por defecto) en las unidades pedidas (?units=)
+// - format=png: el mensaje JSON del frame va seguido de un mensaje binario con el PNG
+//   (paleta ironbow, ?max_side=, 320 por defecto)
+// - camera=: solo los frames de esa cámara
+// Primero llega {"type": "session"} con las capturas de la sesión y después
+// {"type": "frame"} de cada frame ({"type": "skipped"} si una captura no se puede leer)
+// hasta {"type": "end"}. El dashboard controla la reproducción con mensajes JSON:
+// {"action": "pause"}, {"action": "play"}, {"action": "speed", "speed": 8} y
+// {"action": "seek", "capture": 12} (posición en la lista de capturas) para saltar.
+
+const DEFAULT_MATRIX_SIDE: usize = 80;
+const DEFAULT_PNG_SIDE: usize = 320;
+const MAX_REPLAY_SIDE: usize = 640;
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 32.0;
+// Tiempo de sesión entre dos frames, antes de aplicar la velocidad
+const MIN_STEP_SEC: f32 = 1.0;
+const MAX_STEP_SEC: f32 = 10.0;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayFormat {
+    #[default]
+    Matrix,
+    Png,
+}
+
+#[derive(Deserialize)]
+pub struct ReplayParams {
+    speed: Option<String>,
+    #[serde(default)]
+    format: ReplayFormat,
+    max_side: Option<usize>,
+    camera: Option<String>,
+    units: Option<TempUnit>,
+}
+
+// "4x", "4" o "0.5x"
+fn parse_speed(speed: &str) -> Result<f32, String> {
+    let value = speed.trim().trim_end_matches(['x', 'X']).parse::<f32>()
+        .map_err(|_| format!("Invalid speed '{}' (e.g. 4x)", speed))?;
+    if !(MIN_SPEED..=MAX_SPEED).contains(&value) {
+        return Err(format!("speed must be between {}x and {}x", MIN_SPEED, MAX_SPEED));
+    }
+    Ok(value)
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ReplayControl {
+    Play,
+    Pause,
+    Speed { speed: f32 },
+    Seek { capture: usize },
+}
+
+#[derive(Serialize)]
+struct ReplayCapture {
+    filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    camera_id: Option<String>,
+    timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    angle: Option<f32>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ReplayEvent<'a> {
+    Session { session: SessionInfo, captures: Vec<ReplayCapture>, speed: f32, format: ReplayFormat },
+    Frame {
+        capture: usize,
+        filename: &'a str,
+        frame_index: usize,
+        frames: usize,
+        timestamp: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        angle: Option<f32>,
+        // Del frame completo (la matriz reducida promedia los picos)
+        min_temp: f32,
+        max_temp: f32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        matrix: Option<&'a ThermalFrameData>,
+    },
+    Skipped { capture: usize, filename: &'a str },
+    Playing { speed: f32 },
+    Paused,
+    End,
+    Error { message: String },
+}
+
+// Frame listo para enviar
+struct PreparedFrame {
+    frame_index: usize,
+    // Segundos Unix, con decimales si la pila trae el instante de cada frame
+    timestamp: f64,
+    angle: Option<f32>,
+    min_temp: f32,
+    max_temp: f32,
+    matrix: Option<ThermalFrameData>,
+    png: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Copy)]
+struct RenderOptions {
+    format: ReplayFormat,
+    max_side: usize,
+    unit: TempUnit,
+}
+
+// Frames de una captura, reducidos o renderizados (en el pool bloqueante); None si no se
+// puede leer
+fn prepare_capture(state: &AppState, record: &CaptureRecord, options: RenderOptions) -> Option<Vec<PreparedFrame>> {
+    let bytes = locate_capture(state, &record.filename).and_then(|path| open_capture(&path)).ok()?;
+    let axes = frame_axes(&bytes);
+    with_frames(&bytes, |frames| {
+        frames.axis_iter(Axis(0)).enumerate()
+            .map(|(frame_index, frame)| {
+                let full = frame_data(frame);
+                let (rows, cols) = frame.dim();
+                let scale = (options.max_side as f32 / rows.max(cols) as f32).min(1.0);
+                let target = (((rows as f32 * scale).round() as usize).max(1), ((cols as f32 * scale).round() as usize).max(1));
+                let small = downsample(frame, target);
+                let (matrix, png) = match options.format {
+                    ReplayFormat::Matrix => {
+                        let matrix = ThermalFrameData { min_temp: full.min_temp, max_temp: full.max_temp, ..frame_data(small.view()) };
+                        (Some(options.unit.frame(matrix)), None)
+                    }
+                    ReplayFormat::Png => (None, encode_png(&render_rgb(&small, options.max_side)).ok()),
+                };
+                PreparedFrame {
+                    frame_index,
+                    timestamp: axes.timestamp(frame_index).unwrap_or(record.timestamp as f64),
+                    angle: axes.angle(frame_index).or(record.angle),
+                    min_temp: options.unit.temp(full.min_temp),
+                    max_temp: options.unit.temp(full.max_temp),
+                    matrix,
+                    png,
+                }
+            })
+            .collect()
+    })
+}
+
+async fn send_event(socket: &mut WebSocket, event: &ReplayEvent<'_>) -> bool {
+    let Ok(json) = serde_json::to_string(event) else { return false };
+    socket.send(Message::Text(json)).await.is_ok()
+}
+
+pub async fn replay_session_handler(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Query(params): Query<ReplayParams>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let speed = params.speed.as_deref().map_or(Ok(1.0), parse_speed).map_err(AppError::BadRequest)?;
+    let default_side = match params.format {
+        ReplayFormat::Matrix => DEFAULT_MATRIX_SIDE,
+        ReplayFormat::Png => DEFAULT_PNG_SIDE,
+    };
+    let max_side = params.max_side.unwrap_or(default_side);
+    if !(1..=MAX_REPLAY_SIDE).contains(&max_side) {
+        return Err(AppError::BadRequest(format!("max_side must be between 1 and {}", MAX_REPLAY_SIDE)));
+    }
+    let unit = display_unit(&state, params.units).await;
+    let mut session = {
+        let catalog = state.catalog.read().await;
+        session_of(&catalog, &session_id).ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", session_id)))?
+    };
+    if let Some(camera) = &params.camera {
+        session.retain(|r| r.camera_id.as_ref() == Some(camera));
+        if session.is_empty() {
+            return Err(AppError::NotFound(format!("No captures from camera '{}' in this session", camera)));
+        }
+    }
+    let options = RenderOptions { format: params.format, max_side, unit };
+    Ok(ws.on_upgrade(move |socket| replay_session(state, session, options, speed, socket)))
+}
+
+async fn replay_session(state: Arc<AppState>, session: Vec<CaptureRecord>, options: RenderOptions, mut speed: f32, mut socket: WebSocket) {
+    let info = session_info(&session, without_angle(&session));
+    tracing::info!(turbine_token = %info.turbine_token, captures = session.len(), speed, "⏯️ Reproducción de sesión abierta");
+    let captures = session.iter()
+        .map(|r| ReplayCapture { filename: r.filename.clone(), camera_id: r.camera_id.clone(), timestamp: r.timestamp, angle: r.angle })
+        .collect();
+    if !send_event(&mut socket, &ReplayEvent::Session { session: info, captures, speed, format: options.format }).await {
+        return;
+    }
+
+    // Posición (captura, frame), frames de la captura en curso y momento del siguiente envío
+    let (mut capture, mut frame) = (0usize, 0usize);
+    let mut loaded: Option<(usize, Vec<PreparedFrame>)> = None;
+    let mut paused = false;
+    let mut next_at = Instant::now();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_at), if !paused => {
+                if capture >= session.len() {
+                    paused = true;
+                    if !send_event(&mut socket, &ReplayEvent::End).await {
+                        break;
+                    }
+                    continue;
+                }
+                if loaded.as_ref().is_none_or(|(index, _)| *index != capture) {
+                    let (worker_state, record) = (state.clone(), session[capture].clone());
+                    let frames = tokio::task::spawn_blocking(move || prepare_capture(&worker_state, &record, options)).await.ok().flatten();
+                    match frames.filter(|f| !f.is_empty()) {
+                        Some(frames) => loaded = Some((capture, frames)),
+                        None => {
+                            let skipped = ReplayEvent::Skipped { capture, filename: &session[capture].filename };
+                            if !send_event(&mut socket, &skipped).await {
+                                break;
+                            }
+                            (capture, frame) = (capture + 1, 0);
+                            continue;
+                        }
+                    }
+                }
+                let Some((_, frames)) = &loaded else { continue };
+                let current = &frames[frame];
+                let event = ReplayEvent::Frame {
+                    capture,
+                    filename: &session[capture].filename,
+                    frame_index: current.frame_index,
+                    frames: frames.len(),
+                    timestamp: current.timestamp as u64,
+                    angle: current.angle,
+                    min_temp: current.min_temp,
+                    max_temp: current.max_temp,
+                    matrix: current.matrix.as_ref(),
+                };
+                if !send_event(&mut socket, &event).await {
+                    break;
+                }
+                if let Some(png) = &current.png
+                    && socket.send(Message::Binary(png.clone())).await.is_err()
+                {
+                    break;
+                }
+                // Siguiente frame y espera según el tiempo de sesión que los separa
+                let next_timestamp = match frames.get(frame + 1) {
+                    Some(next) => {
+                        frame += 1;
+                        Some(next.timestamp)
+                    }
+                    None => {
+                        (capture, frame) = (capture + 1, 0);
+                        session.get(capture).map(|r| r.timestamp as f64)
+                    }
+                };
+                let step = next_timestamp.map_or(MIN_STEP_SEC, |t| ((t - current.timestamp) as f32).clamp(MIN_STEP_SEC, MAX_STEP_SEC));
+                next_at = Instant::now() + Duration::from_secs_f32(step / speed);
+            }
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => continue,
+                };
+                let event = match serde_json::from_str::<ReplayControl>(&text) {
+                    Ok(ReplayControl::Pause) => {
+                        paused = true;
+                        ReplayEvent::Paused
+                    }
+                    Ok(ReplayControl::Play) => {
+                        // Tras el final, play vuelve a empezar
+                        if capture >= session.len() {
+                            (capture, frame) = (0, 0);
+                        }
+                        paused = false;
+                        next_at = Instant::now();
+                        ReplayEvent::Playing { speed }
+                    }
+                    Ok(ReplayControl::Speed { speed: requested }) if (MIN_SPEED..=MAX_SPEED).contains(&requested) => {
+                        speed = requested;
+                        ReplayEvent::Playing { speed }
+                    }
+                    Ok(ReplayControl::Speed { .. }) => ReplayEvent::Error {
+                        message: format!("speed must be between {}x and {}x", MIN_SPEED, MAX_SPEED),
+                    },
+                    Ok(ReplayControl::Seek { capture: target }) if target < session.len() => {
+                        (capture, frame) = (target, 0);
+                        next_at = Instant::now();
+                        continue;
+                    }
+                    Ok(ReplayControl::Seek { .. }) => ReplayEvent::Error {
+                        message: format!("capture must be below {}", session.len()),
+                    },
+                    Err(e) => ReplayEvent::Error { message: format!("Invalid control message: {}", e) },
+                };
+                if !send_event(&mut socket, &event).await {
+                    break;
+                }
+            }
+        }
+    }
+    tracing::info!(captures = session.len(), "⏯️ Reproducción de sesión cerrada");
+}
@@ -0,0 +1,122 @@
+use crate::{
+    error::AppError,
+    state::{AlertRecord, AppState, RemoteConfig},
+    storage::{alert_log::load_alert_log, archive::stored_path, catalog::CaptureRecord, read_capture},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// --- API DE REPLICACIÓN (INSTANCIA PRIMARIA) ---
+// Con --replication-token, la instancia secundaria (ver crate::replication) pide aquí lo
+// nuevo desde su último punto: capturas por orden de llegada, su contenido original y las
+// alertas, además de la configuración vigente. Los secretos de la configuración viajan
+// tal como se guardan: cifrados si hay clave maestra (la secundaria necesita la misma para
+// usarlos), en claro si no.
+
+const DEFAULT_BATCH: usize = 500;
+const MAX_BATCH: usize = 5000;
+
+fn require_replication_token(state: &AppState, headers: &HeaderMap) -> Result<(), AppError> {
+    let Some(expected) = state.settings.replication_token.as_deref().filter(|t| !t.is_empty()) else {
+        return Err(AppError::Forbidden("Replication disabled: set SENTINEL_REPLICATION_TOKEN"));
+    };
+    let provided = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided != Some(expected) {
+        return Err(AppError::Unauthorized("Invalid replication token"));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplicatedConfig {
+    pub version: u64,
+    pub config: RemoteConfig,
+}
+
+pub async fn replication_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<ReplicatedConfig>, AppError> {
+    require_replication_token(&state, &headers)?;
+    let version = state.config_history.read().await.last().map_or(0, |v| v.version);
+    let config = state.config.read().await.clone();
+    Ok(Json(ReplicatedConfig { version, config }))
+}
+
+#[derive(Deserialize)]
+pub struct SinceParams {
+    #[serde(default)]
+    since: u64,
+    // Con since, última captura ya copiada de ese segundo: se devuelven las posteriores
+    #[serde(default)]
+    after: Option<String>,
+    limit: Option<usize>,
+}
+
+impl SinceParams {
+    fn limit(&self) -> Result<usize, AppError> {
+        let limit = self.limit.unwrap_or(DEFAULT_BATCH);
+        if !(1..=MAX_BATCH).contains(&limit) {
+            return Err(AppError::BadRequest(format!("limit must be between 1 and {}", MAX_BATCH)));
+        }
+        Ok(limit)
+    }
+}
+
+// Capturas que llegaron en `since` o después, por orden de llegada y nombre; con `after`,
+// solo las posteriores a (since, after), para recorrer aunque un lote entero llegue en el
+// mismo segundo (sin él el límite va incluido y la secundaria descarta las que ya tiene)
+pub async fn replication_captures(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<SinceParams>,
+) -> Result<Json<Vec<CaptureRecord>>, AppError> {
+    require_replication_token(&state, &headers)?;
+    let limit = params.limit()?;
+    let mut records: Vec<CaptureRecord> = state.catalog.read().await.iter()
+        .filter(|r| match &params.after {
+            Some(after) => (r.arrival(), &r.filename) > (params.since, after),
+            None => r.arrival() >= params.since,
+        })
+        .cloned()
+        .collect();
+    records.sort_by(|a, b| (a.arrival(), &a.filename).cmp(&(b.arrival(), &b.filename)));
+    records.truncate(limit);
+    Ok(Json(records))
+}
+
+// Contenido original (descomprimido) de una captura, sin restaurarla si está archivada
+pub async fn replication_capture_data(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(filename): Path<String>,
+) -> Result<Response, AppError> {
+    require_replication_token(&state, &headers)?;
+    let record = state.catalog.read().await.iter()
+        .find(|r| r.filename == filename)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", filename)))?;
+    let data = tokio::task::spawn_blocking(move || read_capture(&stored_path(&record))).await??;
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], data).into_response())
+}
+
+pub async fn replication_alerts(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<SinceParams>,
+) -> Result<Json<Vec<AlertRecord>>, AppError> {
+    require_replication_token(&state, &headers)?;
+    let limit = params.limit()?;
+    let mut alerts = tokio::task::spawn_blocking(load_alert_log).await??;
+    alerts.retain(|a| a.timestamp >= params.since);
+    alerts.truncate(limit);
+    Ok(Json(alerts))
+}
@@ -0,0 +1,194 @@
+use crate::{
+    error::AppError,
+    i18n::Locale,
+    reports::{all_schedules, run_schedule, GeneratedReport, ReportFormat, ReportKind, ReportSchedule},
+    state::AppState,
+    storage::reports::{list_reports, read_report, save_report_schedules, StoredReport},
+};
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- API DE INFORMES PROGRAMADOS ---
+// /api/schedules lista las planificaciones de la configuración (identificadas por nombre,
+// solo lectura aquí) y las creadas por la API, que se pueden editar y borrar.
+// POST /api/schedules/:id/run genera el informe en el momento. Los informes guardados se
+// listan en /api/reports y se descargan en /api/reports/:name.
+
+#[derive(Deserialize)]
+pub struct NewSchedule {
+    name: String,
+    cron: String,
+    kind: ReportKind,
+    #[serde(default)]
+    format: ReportFormat,
+    #[serde(default)]
+    turbines: Vec<String>,
+    #[serde(default)]
+    period: Option<String>,
+    #[serde(default)]
+    store: Option<bool>,
+    #[serde(default)]
+    email: Vec<String>,
+    #[serde(default)]
+    locale: Locale,
+    #[serde(default)]
+    enabled: Option<bool>,
+}
+
+// Campos a cambiar; los ausentes se conservan
+#[derive(Deserialize)]
+pub struct ScheduleUpdate {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    cron: Option<String>,
+    #[serde(default)]
+    kind: Option<ReportKind>,
+    #[serde(default)]
+    format: Option<ReportFormat>,
+    #[serde(default)]
+    turbines: Option<Vec<String>>,
+    #[serde(default)]
+    period: Option<String>,
+    #[serde(default)]
+    store: Option<bool>,
+    #[serde(default)]
+    email: Option<Vec<String>>,
+    #[serde(default)]
+    locale: Option<Locale>,
+    #[serde(default)]
+    enabled: Option<bool>,
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Schedule '{}' not found", id))
+}
+
+// Los nombres no se repiten entre la configuración y la API (dan nombre a los informes)
+async fn check_unique_name(state: &AppState, schedule: &ReportSchedule) -> Result<(), AppError> {
+    let taken = all_schedules(state).await.iter().any(|s| s.name == schedule.name && (s.id.is_empty() || s.id != schedule.id));
+    if taken {
+        return Err(AppError::BadRequest(format!("A schedule named '{}' already exists", schedule.name)));
+    }
+    Ok(())
+}
+
+pub async fn list_schedules(State(state): State<Arc<AppState>>) -> Json<Vec<ReportSchedule>> {
+    Json(all_schedules(&state).await)
+}
+
+// Por id (las de la API) o por nombre (las de la configuración)
+async fn find_schedule(state: &AppState, id: &str) -> Result<ReportSchedule, AppError> {
+    all_schedules(state).await.into_iter()
+        .find(|s| if s.id.is_empty() { s.name == id } else { s.id == id })
+        .ok_or_else(|| not_found(id))
+}
+
+pub async fn get_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ReportSchedule>, AppError> {
+    find_schedule(&state, &id).await.map(Json)
+}
+
+pub async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<NewSchedule>,
+) -> Result<Json<ReportSchedule>, AppError> {
+    let schedule = ReportSchedule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name.trim().to_string(),
+        cron: request.cron,
+        kind: request.kind,
+        format: request.format,
+        turbines: request.turbines,
+        period: request.period.unwrap_or_else(|| "24h".into()),
+        store: request.store.unwrap_or(true),
+        email: request.email,
+        locale: request.locale,
+        enabled: request.enabled.unwrap_or(true),
+    };
+    schedule.validate().map_err(AppError::BadRequest)?;
+    check_unique_name(&state, &schedule).await?;
+    let mut schedules = state.report_schedules.write().await;
+    schedules.push(schedule.clone());
+    save_report_schedules(&schedules);
+    tracing::info!(id = %schedule.id, name = %schedule.name, cron = %schedule.cron, "📑 Informe programado creado");
+    Ok(Json(schedule))
+}
+
+pub async fn update_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(update): Json<ScheduleUpdate>,
+) -> Result<Json<ReportSchedule>, AppError> {
+    let mut updated = state.report_schedules.read().await.iter()
+        .find(|s| s.id == id)
+        .cloned()
+        .ok_or_else(|| not_found(&id))?;
+    if let Some(name) = update.name {
+        updated.name = name.trim().to_string();
+    }
+    updated.cron = update.cron.unwrap_or(updated.cron);
+    updated.kind = update.kind.unwrap_or(updated.kind);
+    updated.format = update.format.unwrap_or(updated.format);
+    updated.turbines = update.turbines.unwrap_or(updated.turbines);
+    updated.period = update.period.unwrap_or(updated.period);
+    updated.store = update.store.unwrap_or(updated.store);
+    updated.email = update.email.unwrap_or(updated.email);
+    updated.locale = update.locale.unwrap_or(updated.locale);
+    updated.enabled = update.enabled.unwrap_or(updated.enabled);
+    updated.validate().map_err(AppError::BadRequest)?;
+    check_unique_name(&state, &updated).await?;
+    let mut schedules = state.report_schedules.write().await;
+    let schedule = schedules.iter_mut().find(|s| s.id == id).ok_or_else(|| not_found(&id))?;
+    *schedule = updated.clone();
+    save_report_schedules(&schedules);
+    Ok(Json(updated))
+}
+
+pub async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<&'static str>, AppError> {
+    let mut schedules = state.report_schedules.write().await;
+    let before = schedules.len();
+    schedules.retain(|s| s.id != id);
+    if schedules.len() == before {
+        return Err(not_found(&id));
+    }
+    save_report_schedules(&schedules);
+    Ok(Json("Schedule removed"))
+}
+
+pub async fn run_schedule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<GeneratedReport>, AppError> {
+    let schedule = find_schedule(&state, &id).await?;
+    let now = chrono::Utc::now().timestamp() as u64;
+    let report = run_schedule(&state, &schedule, now).await.map_err(AppError::Internal)?;
+    Ok(Json(report))
+}
+
+pub async fn list_reports_handler() -> Result<Json<Vec<StoredReport>>, AppError> {
+    Ok(Json(tokio::task::spawn_blocking(list_reports).await?))
+}
+
+pub async fn download_report_handler(Path(name): Path<String>) -> Result<Response, AppError> {
+    let worker_name = name.clone();
+    let bytes = tokio::task::spawn_blocking(move || read_report(&worker_name)).await??;
+    let format = if name.ends_with(".pdf") { ReportFormat::Pdf } else { ReportFormat::Json };
+    let headers = [
+        (header::CONTENT_TYPE, format.content_type().to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name)),
+    ];
+    Ok((headers, Body::from(bytes)).into_response())
+}
@@ -0,0 +1,130 @@
+use crate::{
+    error::AppError,
+    rules::{AlertRule, RuleCondition},
+    state::AppState,
+    storage::rules::save_rules,
+    thresholds::Severity,
+};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- API DE REGLAS DE ALERTA ---
+// Se comprueban contra la configuración vigente (zonas y canales) al crearlas o editarlas;
+// se aplican a las subidas a partir de ese momento.
+
+#[derive(Deserialize)]
+pub struct NewRule {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    enabled: Option<bool>,
+    conditions: Vec<RuleCondition>,
+    severity: Severity,
+    #[serde(default)]
+    channels: Vec<String>,
+}
+
+// Campos a cambiar; los ausentes se conservan
+#[derive(Deserialize)]
+pub struct RuleUpdate {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    enabled: Option<bool>,
+    #[serde(default)]
+    conditions: Option<Vec<RuleCondition>>,
+    #[serde(default)]
+    severity: Option<Severity>,
+    #[serde(default)]
+    channels: Option<Vec<String>>,
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Rule '{}' not found", id))
+}
+
+pub async fn list_rules(State(state): State<Arc<AppState>>) -> Json<Vec<AlertRule>> {
+    Json(state.rules.read().await.clone())
+}
+
+pub async fn get_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AlertRule>, AppError> {
+    state.rules.read().await.iter()
+        .find(|r| r.id == id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| not_found(&id))
+}
+
+pub async fn create_rule(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<NewRule>,
+) -> Result<Json<AlertRule>, AppError> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let rule = AlertRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request.name.trim().to_string(),
+        description: request.description,
+        enabled: request.enabled.unwrap_or(true),
+        conditions: request.conditions,
+        severity: request.severity,
+        channels: request.channels,
+        created: now,
+        updated: now,
+    };
+    rule.validate(&*state.config.read().await).map_err(AppError::BadRequest)?;
+    let mut rules = state.rules.write().await;
+    rules.push(rule.clone());
+    save_rules(&rules);
+    tracing::info!(id = %rule.id, name = %rule.name, "📐 Regla de alerta creada");
+    Ok(Json(rule))
+}
+
+pub async fn update_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(update): Json<RuleUpdate>,
+) -> Result<Json<AlertRule>, AppError> {
+    let config = state.config.read().await.clone();
+    let mut rules = state.rules.write().await;
+    let rule = rules.iter_mut().find(|r| r.id == id).ok_or_else(|| not_found(&id))?;
+    let mut updated = rule.clone();
+    if let Some(name) = update.name {
+        updated.name = name.trim().to_string();
+    }
+    if update.description.is_some() {
+        updated.description = update.description;
+    }
+    updated.enabled = update.enabled.unwrap_or(updated.enabled);
+    updated.conditions = update.conditions.unwrap_or(updated.conditions);
+    updated.severity = update.severity.unwrap_or(updated.severity);
+    updated.channels = update.channels.unwrap_or(updated.channels);
+    updated.validate(&config).map_err(AppError::BadRequest)?;
+    updated.updated = chrono::Utc::now().timestamp() as u64;
+    *rule = updated.clone();
+    save_rules(&rules);
+    Ok(Json(updated))
+}
+
+pub async fn delete_rule(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<&'static str>, AppError> {
+    let mut rules = state.rules.write().await;
+    let before = rules.len();
+    rules.retain(|r| r.id != id);
+    if rules.len() == before {
+        return Err(not_found(&id));
+    }
+    save_rules(&rules);
+    Ok(Json("Rule removed"))
+}
@@ -0,0 +1,74 @@
+use super::web::{display_tz, Timed};
+use crate::{
+    error::AppError,
+    schedule::{format_timestamp, parse_timestamp},
+    state::AppState,
+    storage::{
+        alert_log::load_alert_log,
+        sensors::{load_readings, ReadingFilter, SensorReading, MAX_QUERY_READINGS},
+    },
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// --- CONSULTA DE TELEMETRÍA ---
+// Series de sensores no térmicos por turbina y sensor. Con ?alert=<id> devuelve las
+// lecturas de la turbina de la alerta en una ventana alrededor de ella, para ver si la
+// anomalía térmica coincide con un pico de vibración.
+
+// Segundos antes y después de la alerta que se consultan por defecto
+const DEFAULT_ALERT_WINDOW_SEC: u64 = 300;
+
+#[derive(Deserialize)]
+pub struct TelemetryParams {
+    turbine_token: Option<String>,
+    sensor: Option<String>,
+    // Segundos Unix o RFC 3339, ambos inclusivos
+    from: Option<String>,
+    to: Option<String>,
+    alert: Option<String>,
+    window: Option<u64>,
+    tz: Option<String>,
+}
+
+pub async fn get_telemetry(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TelemetryParams>,
+) -> Result<Json<Vec<Timed<SensorReading>>>, AppError> {
+    let bound = |value: Option<&str>| value.map(parse_timestamp).transpose().map_err(AppError::BadRequest);
+    let mut filter = ReadingFilter {
+        turbine_token: params.turbine_token,
+        sensor: params.sensor,
+        from: bound(params.from.as_deref())?.unwrap_or(0),
+        to: bound(params.to.as_deref())?.unwrap_or(u64::MAX),
+    };
+    if let Some(id) = params.alert {
+        let in_memory = state.alerts.read().await.iter().find(|a| a.id == id).cloned();
+        let alert = match in_memory {
+            Some(alert) => alert,
+            None => tokio::task::spawn_blocking(load_alert_log).await??
+                .into_iter()
+                .find(|a| a.id == id)
+                .ok_or_else(|| AppError::NotFound(format!("Alert '{}' not found", id)))?,
+        };
+        let window = params.window.unwrap_or(DEFAULT_ALERT_WINDOW_SEC);
+        filter.turbine_token = Some(alert.turbine_token);
+        filter.from = filter.from.max(alert.timestamp.saturating_sub(window));
+        filter.to = filter.to.min(alert.timestamp.saturating_add(window));
+    }
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+
+    let mut readings = tokio::task::spawn_blocking(move || load_readings(&filter)).await??;
+    // Si hay demasiadas se devuelven las más recientes
+    if readings.len() > MAX_QUERY_READINGS {
+        tracing::warn!(total = readings.len(), returned = MAX_QUERY_READINGS, "⚠️ Consulta de telemetría truncada");
+        readings.drain(..readings.len() - MAX_QUERY_READINGS);
+    }
+    Ok(Json(readings.into_iter()
+        .map(|r| Timed { time: format_timestamp(r.timestamp, tz), item: r })
+        .collect()))
+}
@@ -0,0 +1,420 @@
+use super::web::display_unit;
+use crate::{
+    analysis::{
+        hottest_frame,
+        keyframes::{frame_features, select_keyframes, Candidate, KeyframeReason},
+        tracking::{frame_blobs, hottest_blob, nearest_blob, track_summary, Blob, TrackSummary, DEFAULT_MAX_DISTANCE, MAX_MISSES},
+        with_frames,
+    },
+    error::AppError,
+    response_cache::{fingerprint, CacheKey},
+    sessions::{
+        camera_session, compare_sessions, current_session, session_coverage, session_info, session_of, session_progress,
+        without_angle, SessionComparison, SessionCoverage, SessionInfo, SessionProgress,
+    },
+    state::{AlertRecord, AppState},
+    storage::{
+        archive::locate_capture,
+        lineage::record_derivation,
+        open_capture,
+        package::{write_package, PACKAGE_FORMAT, PACKAGE_VERSION},
+    },
+    units::TempUnit,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+// --- COMPARACIÓN DE SESIONES ---
+// GET /api/sessions/compare?a=&b=: cada sesión se indica con cualquiera de sus capturas
+// (nombre de archivo). Flujo típico: verificar una reparación comparando el escaneo
+// anterior (a) con el posterior (b).
+//
+// GET /api/sessions/coverage?capture= (o ?turbine_token= para la última sesión): ángulos
+// sin capturar en la sesión, con las capturas de la misma cámara que la indicada.
+//
+// GET /api/sessions/:id/keyframes (id = cualquier captura de la sesión): los N frames más
+// informativos de la sesión (ver analysis::keyframes), para no procesar cientos de
+// frames casi iguales al generar informes o analizarlos con IA.
+//
+// GET /api/sessions/:id/track: trayectoria de un punto caliente por la sesión (ver más
+// abajo).
+//
+// GET /api/sessions/:id/package: la inspección en un zip estructurado (ver más abajo).
+//
+// GET /api/sessions/current/:token: progreso del barrido que está haciendo la turbina
+// (frames recibidos, último ángulo, tiempo transcurrido y fin estimado), para seguirlo en
+// directo; 404 si no hay ninguna sesión abierta.
+
+// Diferencia máxima de ángulo (grados) para considerar dos capturas del mismo punto
+const DEFAULT_ANGLE_TOLERANCE: f32 = 2.0;
+const MAX_ANGLE_TOLERANCE: f32 = 30.0;
+
+#[derive(Deserialize)]
+pub struct CompareParams {
+    a: String,
+    b: String,
+    tolerance: Option<f32>,
+    units: Option<TempUnit>,
+}
+
+pub async fn compare_sessions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CompareParams>,
+) -> Result<Json<SessionComparison>, AppError> {
+    let tolerance = params.tolerance.unwrap_or(DEFAULT_ANGLE_TOLERANCE);
+    if !(0.0..=MAX_ANGLE_TOLERANCE).contains(&tolerance) {
+        return Err(AppError::BadRequest(format!("tolerance must be between 0 and {} degrees", MAX_ANGLE_TOLERANCE)));
+    }
+    let unit = display_unit(&state, params.units).await;
+    let (a, b) = {
+        let catalog = state.catalog.read().await;
+        let find = |filename: &str| {
+            session_of(&catalog, filename).ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", filename)))
+        };
+        (find(&params.a)?, find(&params.b)?)
+    };
+    Ok(Json(unit.comparison(compare_sessions(&a, &b, tolerance))))
+}
+
+#[derive(Deserialize)]
+pub struct CoverageParams {
+    capture: Option<String>,
+    turbine_token: Option<String>,
+}
+
+pub async fn session_coverage_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CoverageParams>,
+) -> Result<Json<SessionCoverage>, AppError> {
+    let (turbine_token, session) = {
+        let catalog = state.catalog.read().await;
+        let anchor = match (&params.capture, &params.turbine_token) {
+            (Some(filename), _) => catalog.iter().find(|r| r.filename == *filename)
+                .ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", filename)))?,
+            (None, Some(token)) => catalog.iter().filter(|r| r.turbine_token == *token).max_by_key(|r| r.timestamp)
+                .ok_or_else(|| AppError::NotFound(format!("No captures for turbine '{}'", token)))?,
+            (None, None) => return Err(AppError::BadRequest("capture or turbine_token is required".into())),
+        };
+        let session = camera_session(&catalog, &anchor.filename).unwrap_or_default();
+        (anchor.turbine_token.clone(), session)
+    };
+    let pan_step_degrees = state.config_for_turbine(&turbine_token).await.pan_step_degrees;
+    if pan_step_degrees.is_nan() || pan_step_degrees <= 0.0 {
+        return Err(AppError::BadRequest("pan_step_degrees must be positive to compute coverage".into()));
+    }
+    let now = chrono::Utc::now().timestamp() as u64;
+    Ok(Json(session_coverage(&session, pan_step_degrees, now)))
+}
+
+pub async fn current_session_handler(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<SessionProgress>, AppError> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let session = {
+        let catalog = state.catalog.read().await;
+        current_session(&catalog, &token, now)
+            .ok_or_else(|| AppError::NotFound(format!("No scan in progress for turbine '{}'", token)))?
+    };
+    // La espera entre ángulos es la efectiva de la turbina (planificaciones, boost)
+    let config = state.config_for_turbine(&token).await;
+    if config.pan_step_degrees.is_nan() || config.pan_step_degrees <= 0.0 {
+        return Err(AppError::BadRequest("pan_step_degrees must be positive to estimate progress".into()));
+    }
+    Ok(Json(session_progress(&session, config.pan_step_degrees, config.scan_wait_time_sec, now)))
+}
+
+const DEFAULT_KEYFRAMES: usize = 10;
+const MAX_KEYFRAMES: usize = 100;
+// Grados sobre la mediana de las máximas de la sesión para contar como punto caliente
+const DEFAULT_HOTSPOT_MARGIN: f32 = 5.0;
+
+#[derive(Deserialize)]
+pub struct KeyframeParams {
+    count: Option<usize>,
+    hotspot_margin: Option<f32>,
+    units: Option<TempUnit>,
+}
+
+#[derive(Serialize)]
+pub struct Keyframe {
+    filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    camera_id: Option<String>,
+    frame_index: usize,
+    // Instante del frame si la pila lo trae, si no el de la captura
+    timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    angle: Option<f32>,
+    max_temp: f32,
+    avg_temp: f32,
+    // Posición del máximo (fila, columna) en fracción del alto y del ancho
+    hotspot: (f32, f32),
+    // Diferencia media absoluta con el frame anterior
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change: Option<f32>,
+    reasons: Vec<KeyframeReason>,
+}
+
+#[derive(Serialize)]
+pub struct KeyframeSelection {
+    session: SessionInfo,
+    // Frames recorridos y capturas que no se pudieron leer
+    frames: usize,
+    unreadable: usize,
+    keyframes: Vec<Keyframe>,
+}
+
+pub async fn session_keyframes_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<KeyframeParams>,
+) -> Result<Response, AppError> {
+    let count = params.count.unwrap_or(DEFAULT_KEYFRAMES);
+    if !(1..=MAX_KEYFRAMES).contains(&count) {
+        return Err(AppError::BadRequest(format!("count must be between 1 and {}", MAX_KEYFRAMES)));
+    }
+    let unit = display_unit(&state, params.units).await;
+    // El margen llega en las unidades pedidas
+    let hotspot_margin = params.hotspot_margin.map_or(DEFAULT_HOTSPOT_MARGIN, |m| m / unit.delta(1.0));
+    if hotspot_margin.is_nan() || hotspot_margin < 0.0 {
+        return Err(AppError::BadRequest("hotspot_margin must not be negative".into()));
+    }
+    let session = {
+        let catalog = state.catalog.read().await;
+        session_of(&catalog, &id).ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", id)))?
+    };
+
+    let key = CacheKey::new("keyframes", &id, format!("{}|{}|{:?}", count, hotspot_margin, unit));
+    let fingerprint = fingerprint(&session);
+    let worker_state = state.clone();
+    let selection = async move {
+        let selection = tokio::task::spawn_blocking(move || {
+            // Cada cámara es una secuencia propia, con su último frame visto
+            let mut sequences: HashMap<Option<String>, (usize, Option<ndarray::Array2<f32>>)> = HashMap::new();
+            let mut candidates: Vec<Candidate> = Vec::new();
+            let mut sources: Vec<usize> = Vec::new();
+            let mut unreadable = 0;
+            for (capture, record) in session.iter().enumerate() {
+                let next_sequence = sequences.len();
+                let (sequence, previous) = sequences.entry(record.camera_id.clone()).or_insert((next_sequence, None));
+                let features = locate_capture(&worker_state, &record.filename)
+                    .and_then(|path| open_capture(&path))
+                    .ok()
+                    .and_then(|bytes| frame_features(&bytes, previous));
+                let Some(features) = features else {
+                    unreadable += 1;
+                    continue;
+                };
+                for features in features {
+                    let angle = record.frames.get(features.frame_index).and_then(|p| p.angle).or(record.angle);
+                    candidates.push(Candidate { features, angle, sequence: *sequence });
+                    sources.push(capture);
+                }
+            }
+            let keyframes = select_keyframes(&candidates, count, hotspot_margin).into_iter()
+                .map(|(i, reasons)| {
+                    let (record, candidate) = (&session[sources[i]], &candidates[i]);
+                    let features = &candidate.features;
+                    let frame_time = record.frames.get(features.frame_index).and_then(|p| p.timestamp);
+                    Keyframe {
+                        filename: record.filename.clone(),
+                        camera_id: record.camera_id.clone(),
+                        frame_index: features.frame_index,
+                        timestamp: frame_time.map_or(record.timestamp, |t| t as u64),
+                        angle: candidate.angle,
+                        max_temp: unit.temp(features.max_temp),
+                        avg_temp: unit.temp(features.avg_temp),
+                        hotspot: features.hotspot,
+                        change: features.change_prev.map(|c| unit.delta(c)),
+                        reasons,
+                    }
+                })
+                .collect();
+            KeyframeSelection { session: session_info(&session, without_angle(&session)), frames: candidates.len(), unreadable, keyframes }
+        })
+        .await?;
+        Ok(selection)
+    };
+    state.response_cache.json(key, fingerprint, selection).await
+}
+
+// GET /api/sessions/:id/track (id = la captura donde está el punto caliente): sigue la
+// mancha del punto más caliente de uno de sus frames (?frame=, por defecto el más
+// caliente) por los frames siguientes de la misma cámara (ver analysis::tracking), con su
+// trayectoria y su historial de temperatura, para distinguir un reflejo que se desplaza
+// de un fallo fijo en un componente.
+
+// Frames que se recorren como máximo desde el de partida
+const MAX_TRACK_FRAMES: usize = 5_000;
+
+#[derive(Deserialize)]
+pub struct TrackParams {
+    frame: Option<usize>,
+    // Salto máximo del centroide entre frames (fracción de la diagonal)
+    max_distance: Option<f32>,
+    units: Option<TempUnit>,
+}
+
+#[derive(Serialize)]
+pub struct TrackPoint {
+    filename: String,
+    frame_index: usize,
+    timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    angle: Option<f32>,
+    #[serde(flatten)]
+    blob: Blob,
+}
+
+#[derive(Serialize)]
+pub struct HotspotTrack {
+    session: SessionInfo,
+    // Se dejó de ver antes del final de la sesión
+    lost: bool,
+    summary: TrackSummary,
+    trajectory: Vec<TrackPoint>,
+}
+
+pub async fn session_track_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<TrackParams>,
+) -> Result<Response, AppError> {
+    let max_distance = params.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE);
+    if !(max_distance > 0.0 && max_distance <= 1.0) {
+        return Err(AppError::BadRequest("max_distance must be within (0, 1]".into()));
+    }
+    let unit = display_unit(&state, params.units).await;
+    let session = {
+        let catalog = state.catalog.read().await;
+        camera_session(&catalog, &id).ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", id)))?
+    };
+    let seed = session.iter().position(|r| r.filename == id).unwrap_or_default();
+
+    let key = CacheKey::new("track", &id, format!("{:?}|{}|{:?}", params.frame, max_distance, unit));
+    let fingerprint = fingerprint(&session);
+    let worker_state = state.clone();
+    state.response_cache.json(key, fingerprint, async move {
+        let mut track = tokio::task::spawn_blocking(move || {
+            let mut trajectory: Vec<TrackPoint> = Vec::new();
+            let (mut visited, mut misses, mut missed, mut lost) = (0, 0, 0, false);
+            'captures: for (position, record) in session.iter().enumerate().skip(seed) {
+                let blobs_per_frame = locate_capture(&worker_state, &record.filename)
+                    .and_then(|path| open_capture(&path))
+                    .ok()
+                    .and_then(|bytes| {
+                        let first = match position == seed {
+                            true => params.frame.or_else(|| hottest_frame(&bytes).map(|(i, _)| i)).unwrap_or(0),
+                            false => 0,
+                        };
+                        with_frames(&bytes, |frames| {
+                            frames.outer_iter().enumerate().skip(first).map(|(i, f)| (i, frame_blobs(f))).collect::<Vec<_>>()
+                        })
+                    });
+                let Some(blobs_per_frame) = blobs_per_frame else {
+                    if position == seed {
+                        return Err(AppError::BadRequest(format!("Capture '{}' is unreadable", record.filename)));
+                    }
+                    continue;
+                };
+                for (frame_index, blobs) in blobs_per_frame {
+                    let blob = match trajectory.last() {
+                        None => hottest_blob(&blobs)
+                            .ok_or_else(|| AppError::NotFound(format!("No hotspot in frame {} of '{}'", frame_index, record.filename)))?,
+                        Some(last) => match nearest_blob(&blobs, last.blob.centroid, max_distance) {
+                            Some(blob) => blob,
+                            None => {
+                                missed += 1;
+                                misses += 1;
+                                if misses > MAX_MISSES {
+                                    lost = true;
+                                    break 'captures;
+                                }
+                                continue;
+                            }
+                        },
+                    };
+                    misses = 0;
+                    let frame_time = record.frames.get(frame_index).and_then(|p| p.timestamp);
+                    trajectory.push(TrackPoint {
+                        filename: record.filename.clone(),
+                        frame_index,
+                        timestamp: frame_time.map_or(record.timestamp, |t| t as u64),
+                        angle: record.frames.get(frame_index).and_then(|p| p.angle).or(record.angle),
+                        blob: blob.clone(),
+                    });
+                    visited += 1;
+                    if visited >= MAX_TRACK_FRAMES {
+                        break 'captures;
+                    }
+                }
+                if trajectory.is_empty() {
+                    return Err(AppError::NotFound(format!("Frame {} not found in '{}'", params.frame.unwrap_or(0), id)));
+                }
+            }
+            let blobs: Vec<&Blob> = trajectory.iter().map(|p| &p.blob).collect();
+            let summary = track_summary(&blobs, missed).ok_or_else(|| AppError::Internal("empty track".into()))?;
+            let info = session_info(&session, without_angle(&session));
+            Ok(HotspotTrack { session: info, lost, summary, trajectory })
+        })
+        .await??;
+
+        for point in track.trajectory.iter_mut() {
+            point.blob.max_temp = unit.temp(point.blob.max_temp);
+            point.blob.mean_temp = unit.temp(point.blob.mean_temp);
+        }
+        let summary = &mut track.summary;
+        (summary.min_temp, summary.max_temp, summary.mean_temp) = (unit.temp(summary.min_temp), unit.temp(summary.max_temp), unit.temp(summary.mean_temp));
+        summary.temp_std = unit.delta(summary.temp_std);
+        Ok(track)
+    })
+    .await
+}
+
+// GET /api/sessions/:id/package (id = cualquier captura de la sesión): la inspección
+// completa en un zip estructurado para los sistemas de gestión de activos (ver
+// storage::package)
+
+// Capturas como máximo en un paquete (se genera en memoria)
+const MAX_PACKAGE_CAPTURES: usize = 500;
+
+pub async fn session_package_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    let session = {
+        let catalog = state.catalog.read().await;
+        session_of(&catalog, &id).ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", id)))?
+    };
+    if session.len() > MAX_PACKAGE_CAPTURES {
+        return Err(AppError::BadRequest(format!(
+            "Session has {} captures; packages hold at most {}", session.len(), MAX_PACKAGE_CAPTURES
+        )));
+    }
+    let turbine_token = session.first().map(|r| r.turbine_token.clone()).unwrap_or_default();
+    let turbine = state.turbines.read().await.iter().find(|t| t.token == turbine_token).cloned();
+    let alerts: Vec<AlertRecord> = state.alerts.read().await.iter()
+        .filter(|a| session.iter().any(|r| r.filename == a.dataset_path))
+        .cloned()
+        .collect();
+
+    let inputs: Vec<String> = session.iter().map(|r| r.filename.clone()).collect();
+    let package_name = format!("inspection_{}_{}.zip", turbine_token, session.first().map_or(0, |r| r.timestamp));
+    let zip = tokio::task::spawn_blocking(move || write_package(&session, turbine, &alerts)).await??;
+    let inputs: Vec<(&str, Option<usize>)> = inputs.iter().map(|f| (f.as_str(), None)).collect();
+    let params = serde_json::json!({ "format": PACKAGE_FORMAT, "version": PACKAGE_VERSION });
+    record_derivation(&state, "inspection_package", &inputs, params, None).await;
+    tracing::info!(%turbine_token, captures = inputs.len(), size_bytes = zip.len(), "📦 Paquete de inspección generado");
+    let headers = [
+        (header::CONTENT_TYPE, "application/zip".to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", package_name)),
+    ];
+    Ok((headers, zip).into_response())
+}
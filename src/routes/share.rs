@@ -0,0 +1,226 @@
+use super::web::{change_author, display_unit, load_frame};
+use crate::{
+    analysis::thumbnail::{encode_png, render_rgb},
+    error::AppError,
+    schedule::parse_duration,
+    state::AppState,
+    storage::{
+        audit::{append_audit, AuditEntry},
+        shares::{read_share_image, remove_share_image, save_shares, write_share_image, SharedSnapshot},
+    },
+    units::TempUnit,
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// --- ENLACES PARA COMPARTIR UN FRAME ---
+// POST /api/share fija un frame (imagen ironbow y estadísticas) en una instantánea
+// inmutable y devuelve un enlace público /share/:token que caduca (24 h por defecto,
+// expires_in hasta 7 días), para pegarlo en el chat con el fabricante sin darle acceso a
+// la API. El visor es una página HTML con la imagen incrustada, sin nombres de archivo ni
+// tokens de turbina; /share/:token/image.png sirve solo la imagen. DELETE
+// /api/share/:token revoca el enlace antes de tiempo. Las instantáneas caducadas se
+// borran al crear otras.
+
+const DEFAULT_SHARE_TTL: &str = "24h";
+const MAX_SHARE_TTL_SEC: u64 = 7 * 86_400;
+// Lado mayor de la imagen compartida
+const SHARE_RENDER_SIDE: usize = 640;
+const MAX_NOTE_CHARS: usize = 500;
+
+#[derive(Deserialize)]
+pub struct ShareRequest {
+    filename: String,
+    #[serde(default)]
+    frame_index: usize,
+    // "2h", "3d"...
+    #[serde(default)]
+    expires_in: Option<String>,
+    #[serde(default)]
+    units: Option<TempUnit>,
+    // Texto que acompaña a la imagen en el visor
+    #[serde(default)]
+    note: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ShareLink {
+    token: String,
+    // Absoluta si hay --public-url
+    url: String,
+    expires_at: u64,
+}
+
+fn share_url(state: &AppState, token: &str) -> String {
+    let base = state.settings.public_url.as_deref().unwrap_or("").trim_end_matches('/');
+    format!("{}/share/{}", base, token)
+}
+
+pub async fn create_share(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ShareRequest>,
+) -> Result<(StatusCode, Json<ShareLink>), AppError> {
+    let ttl = parse_duration(request.expires_in.as_deref().unwrap_or(DEFAULT_SHARE_TTL)).map_err(AppError::BadRequest)?;
+    if !(1..=MAX_SHARE_TTL_SEC).contains(&ttl) {
+        return Err(AppError::BadRequest(format!("expires_in must be at most {}s (7 days)", MAX_SHARE_TTL_SEC)));
+    }
+    let note = request.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if note.as_ref().is_some_and(|n| n.chars().count() > MAX_NOTE_CHARS) {
+        return Err(AppError::BadRequest(format!("note must be at most {} characters", MAX_NOTE_CHARS)));
+    }
+    let record = state.catalog.read().await.iter()
+        .find(|r| r.filename == request.filename)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", request.filename)))?;
+    let units = display_unit(&state, request.units).await;
+
+    let (worker_state, filename, frame_index) = (state.clone(), request.filename.clone(), request.frame_index);
+    let (frame, png) = tokio::task::spawn_blocking(move || {
+        let frame = load_frame(&worker_state, &filename, frame_index)?;
+        let matrix = Array2::from_shape_vec((frame.height, frame.width), frame.pixels.clone())
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let png = encode_png(&render_rgb(&matrix, SHARE_RENDER_SIDE))
+            .map_err(|e| AppError::Internal(format!("PNG encoding failed: {}", e)))?;
+        Ok::<_, AppError>((frame, png))
+    })
+    .await??;
+    let finite: Vec<f32> = frame.pixels.iter().copied().filter(|v| v.is_finite()).collect();
+    let avg_temp = if finite.is_empty() { 0.0 } else { finite.iter().sum::<f32>() / finite.len() as f32 };
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    let snapshot = SharedSnapshot {
+        token: uuid::Uuid::new_v4().simple().to_string(),
+        created: now,
+        expires_at: now + ttl,
+        author: change_author(&headers),
+        turbine_token: record.turbine_token.clone(),
+        filename: record.filename.clone(),
+        frame_index,
+        captured: frame.timestamp.map_or(record.timestamp, |t| t as u64),
+        angle: frame.angle.or(record.angle),
+        min_temp: frame.min_temp,
+        max_temp: frame.max_temp,
+        avg_temp,
+        units,
+        width: frame.width,
+        height: frame.height,
+        note,
+    };
+    let token = snapshot.token.clone();
+    let worker_token = token.clone();
+    tokio::task::spawn_blocking(move || write_share_image(&worker_token, &png)).await??;
+
+    // Se guardan las vigentes y se borran las imágenes de las caducadas
+    let expired = {
+        let mut shares = state.shares.write().await;
+        let (expired, mut active): (Vec<SharedSnapshot>, Vec<SharedSnapshot>) = shares.drain(..).partition(|s| s.expires_at <= now);
+        active.push(snapshot.clone());
+        *shares = active;
+        if let Err(e) = save_shares(&shares) {
+            shares.retain(|s| s.token != token);
+            let _ = remove_share_image(&token);
+            return Err(AppError::Internal(format!("could not save the share link: {}", e)));
+        }
+        expired
+    };
+    for share in expired {
+        let _ = remove_share_image(&share.token);
+    }
+    append_audit(&AuditEntry::new("create_share", &token, serde_json::json!({
+        "filename": snapshot.filename,
+        "frame_index": snapshot.frame_index,
+        "expires_at": snapshot.expires_at,
+        "author": snapshot.author,
+    })));
+    tracing::info!(filename = %snapshot.filename, frame_index, expires_at = snapshot.expires_at, "🔗 Enlace público de un frame creado");
+    Ok((StatusCode::CREATED, Json(ShareLink { url: share_url(&state, &token), token, expires_at: snapshot.expires_at })))
+}
+
+pub async fn revoke_share(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<StatusCode, AppError> {
+    {
+        let mut shares = state.shares.write().await;
+        let before = shares.len();
+        shares.retain(|s| s.token != token);
+        if shares.len() == before {
+            return Err(AppError::NotFound(format!("Share link '{}' not found", token)));
+        }
+        save_shares(&shares)?;
+    }
+    let worker_token = token.clone();
+    tokio::task::spawn_blocking(move || remove_share_image(&worker_token)).await??;
+    append_audit(&AuditEntry::new("revoke_share", &token, serde_json::json!({})));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Instantánea vigente; las caducadas y las revocadas responden igual, 404
+async fn active_share(state: &AppState, token: &str) -> Result<SharedSnapshot, AppError> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    state.shares.read().await.iter()
+        .find(|s| s.token == token && s.expires_at > now)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound("Share link not found or expired".into()))
+}
+
+// Las instantáneas no cambian, pero el enlace caduca: sin caché compartida ni indexado
+fn share_headers() -> [(header::HeaderName, &'static str); 2] {
+    [(header::CACHE_CONTROL, "private, no-store"), (header::HeaderName::from_static("x-robots-tag"), "noindex")]
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn share_html(share: &SharedSnapshot, png: &[u8]) -> String {
+    let (unit, symbol) = (share.units, share.units.symbol());
+    let captured = chrono::DateTime::from_timestamp(share.captured as i64, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    let angle = share.angle.map(|a| format!(" · angle {:.1}°", a)).unwrap_or_default();
+    let note = share.note.as_deref().map(|n| format!("<p>{}</p>", escape_html(n))).unwrap_or_default();
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Thermal snapshot</title>\
+         <meta name=\"robots\" content=\"noindex\"></head>\
+         <body style=\"font-family:sans-serif;margin:1em\">\
+         <h2>Thermal snapshot</h2><p>{captured}{angle}</p>\
+         <img alt=\"thermal frame\" style=\"max-width:100%;image-rendering:pixelated\" src=\"data:image/png;base64,{image}\">\
+         <p>max {max:.1} {symbol} · avg {avg:.1} {symbol} · min {min:.1} {symbol} ({width}×{height} px)</p>\
+         {note}<p style=\"color:#777\">Link expires {expires}</p></body></html>",
+        captured = captured,
+        angle = angle,
+        image = STANDARD.encode(png),
+        max = unit.temp(share.max_temp),
+        avg = unit.temp(share.avg_temp),
+        min = unit.temp(share.min_temp),
+        symbol = symbol,
+        width = share.width,
+        height = share.height,
+        note = note,
+        expires = chrono::DateTime::from_timestamp(share.expires_at as i64, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default(),
+    )
+}
+
+pub async fn view_share(State(state): State<Arc<AppState>>, Path(token): Path<String>) -> Result<Response, AppError> {
+    let share = active_share(&state, &token).await?;
+    let png = tokio::task::spawn_blocking(move || read_share_image(&token)).await??;
+    Ok((share_headers(), Html(share_html(&share, &png))).into_response())
+}
+
+pub async fn share_image(State(state): State<Arc<AppState>>, Path(token): Path<String>) -> Result<Response, AppError> {
+    active_share(&state, &token).await?;
+    let png = tokio::task::spawn_blocking(move || read_share_image(&token)).await??;
+    Ok((share_headers(), [(header::CONTENT_TYPE, "image/png")], png).into_response())
+}
@@ -0,0 +1,156 @@
+use super::web::display_tz;
+use crate::{
+    error::AppError,
+    state::{AppState, RATE_WINDOW_SEC},
+    storage::alert_log::load_alert_log,
+    thresholds::Severity,
+};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use chrono::DateTime;
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, VecDeque},
+    sync::Arc,
+};
+
+// --- PÁGINA DE ESTADO PÚBLICA ---
+// GET /status (solo con --status-page): salud de la flota a grandes rasgos para
+// incrustarla en la intranet del propietario, sin autenticación. Solo lleva recuentos
+// (turbinas conectadas, alertas de hoy): ni tokens, ni nombres de archivo, ni
+// temperaturas. Todas las consultas comparten --status-rate-limit-per-min y la respuesta
+// se reutiliza durante CACHE_SEC. Con Accept: text/html devuelve una página mínima.
+
+// Segundos durante los que se sirve la misma respuesta
+const CACHE_SEC: u64 = 30;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FleetHealth {
+    // Todas las turbinas conectadas
+    Operational,
+    // Alguna desconectada
+    Degraded,
+    // Ninguna conectada
+    Down,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FleetStatus {
+    pub status: FleetHealth,
+    pub turbines: usize,
+    pub online: usize,
+    // Día en curso en display_timezone
+    pub alerts_today: usize,
+    pub critical_alerts_today: usize,
+    pub generated: u64,
+}
+
+// Consultas recientes a /status y su última respuesta
+#[derive(Default, Debug)]
+pub struct StatusPageCache {
+    recent: VecDeque<u64>,
+    cached: Option<FleetStatus>,
+}
+
+async fn fleet_status(state: &AppState, now: u64) -> Result<FleetStatus, AppError> {
+    let statuses = state.turbine_status.read().await.clone();
+    let tokens: BTreeSet<String> = state.turbines.read().await.iter()
+        .map(|t| t.token.clone())
+        .chain(statuses.keys().cloned())
+        .collect();
+    let mut online = 0;
+    for token in &tokens {
+        let offline_after = state.offline_after(token).await;
+        if statuses.get(token).is_some_and(|live| now <= live.last_update.saturating_add(offline_after)) {
+            online += 1;
+        }
+    }
+
+    // El histórico en disco: en memoria solo quedan las últimas alertas
+    let tz = display_tz(state, None).await?;
+    let local_date = |timestamp: u64| DateTime::from_timestamp(timestamp as i64, 0).map(|t| t.with_timezone(&tz).date_naive());
+    let today = local_date(now);
+    let alerts = tokio::task::spawn_blocking(load_alert_log).await??;
+    let today_alerts: Vec<_> = alerts.iter().filter(|a| local_date(a.timestamp) == today).collect();
+
+    let status = match online {
+        _ if online == tokens.len() => FleetHealth::Operational,
+        0 => FleetHealth::Down,
+        _ => FleetHealth::Degraded,
+    };
+    Ok(FleetStatus {
+        status,
+        turbines: tokens.len(),
+        online,
+        alerts_today: today_alerts.len(),
+        critical_alerts_today: today_alerts.iter().filter(|a| a.severity == Severity::Critical).count(),
+        generated: now,
+    })
+}
+
+fn status_html(status: &FleetStatus) -> String {
+    let (label, color) = match status.status {
+        FleetHealth::Operational => ("Operational", "#2e7d32"),
+        FleetHealth::Degraded => ("Degraded", "#f9a825"),
+        FleetHealth::Down => ("Down", "#c62828"),
+    };
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Fleet status</title>\
+         <meta http-equiv=\"refresh\" content=\"{refresh}\"></head>\
+         <body style=\"font-family:sans-serif;margin:1em\">\
+         <h2 style=\"color:{color}\">{label}</h2>\
+         <p>{online} of {turbines} turbines online</p>\
+         <p>{alerts} alerts today ({critical} critical)</p></body></html>",
+        refresh = CACHE_SEC,
+        color = color,
+        label = label,
+        online = status.online,
+        turbines = status.turbines,
+        alerts = status.alerts_today,
+        critical = status.critical_alerts_today,
+    )
+}
+
+pub async fn status_handler(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Result<Response, AppError> {
+    if !state.settings.status_page {
+        return Err(AppError::NotFound("Status page disabled".into()));
+    }
+    let now = chrono::Utc::now().timestamp() as u64;
+    // El candado se mantiene al recalcular: las consultas simultáneas esperan la misma respuesta
+    let mut page = state.status_page.lock().await;
+    let limit = state.settings.status_rate_limit_per_min as usize;
+    if limit > 0 {
+        while page.recent.front().is_some_and(|&t| t + RATE_WINDOW_SEC <= now) {
+            page.recent.pop_front();
+        }
+        if page.recent.len() >= limit {
+            let retry_after_sec = page.recent.front().map_or(RATE_WINDOW_SEC, |&t| t + RATE_WINDOW_SEC - now).max(1);
+            return Err(AppError::TooManyRequests { retry_after_sec });
+        }
+        page.recent.push_back(now);
+    }
+    let status = match page.cached.clone().filter(|s| now < s.generated + CACHE_SEC) {
+        Some(status) => status,
+        None => {
+            let status = fleet_status(&state, now).await?;
+            page.cached = Some(status.clone());
+            status
+        }
+    };
+    drop(page);
+
+    let wants_html = headers.get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+    let cache_control = [(header::CACHE_CONTROL, format!("public, max-age={}", CACHE_SEC))];
+    Ok(if wants_html {
+        (cache_control, Html(status_html(&status))).into_response()
+    } else {
+        (cache_control, Json(status)).into_response()
+    })
+}
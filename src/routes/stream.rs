@@ -0,0 +1,114 @@
+use super::devices::require_ingest_key;
+use crate::{
+    analysis::{extract_frame, ThermalFrameData},
+    error::AppError,
+    state::{AppState, LiveFrame},
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::HeaderMap,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+// --- STREAM EN VIVO ---
+// El robot abre /ingest/stream y envía frames npy (2D f32) como mensajes binarios
+// durante el escaneo; el servidor los reenvía como JSON a los dashboards suscritos
+// en /api/live/stream. Es un relé: nada de esto se guarda en disco.
+
+// Tamaño máximo de un frame en vivo
+const MAX_LIVE_FRAME_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct StreamParams {
+    turbine_token: Option<String>,
+}
+
+// Mensaje que reciben los dashboards
+#[derive(Serialize)]
+struct LiveFramePayload<'a> {
+    turbine_token: &'a str,
+    timestamp: u64,
+    #[serde(flatten)]
+    frame: &'a ThermalFrameData,
+}
+
+pub async fn ingest_stream_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<StreamParams>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let Some(turbine_token) = params.turbine_token.filter(|t| !t.is_empty()) else {
+        return Err(AppError::BadRequest("turbine_token is required".into()));
+    };
+    tracing::Span::current().record("turbine_token", turbine_token.as_str());
+    require_ingest_key(&state, &turbine_token, &headers).await?;
+    Ok(ws
+        .max_message_size(MAX_LIVE_FRAME_BYTES)
+        .on_upgrade(move |socket| relay_robot_frames(state, turbine_token, socket)))
+}
+
+async fn relay_robot_frames(state: Arc<AppState>, turbine_token: String, mut socket: WebSocket) {
+    tracing::info!(%turbine_token, "📡 Stream en vivo abierto");
+    while let Some(Ok(message)) = socket.recv().await {
+        let data = match message {
+            Message::Binary(data) => data,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let frame = tokio::task::spawn_blocking(move || extract_frame(&data, 0)).await;
+        let Ok(Ok(frame)) = frame else {
+            tracing::warn!(%turbine_token, "⚠️ Frame en vivo ilegible, se descarta");
+            continue;
+        };
+        let payload = LiveFramePayload {
+            turbine_token: &turbine_token,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            frame: &frame,
+        };
+        let Ok(json) = serde_json::to_string(&payload) else { continue };
+        // Sin dashboards suscritos el envío falla; no es un error
+        let _ = state.live_frames.send(LiveFrame { turbine_token: turbine_token.clone(), json: json.into() });
+    }
+    tracing::info!(%turbine_token, "📡 Stream en vivo cerrado");
+}
+
+// Suscripción de un dashboard (opcionalmente filtrada por turbina)
+pub async fn live_stream_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<StreamParams>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let receiver = state.live_frames.subscribe();
+    ws.on_upgrade(move |socket| forward_live_frames(receiver, params.turbine_token, socket))
+}
+
+async fn forward_live_frames(mut receiver: Receiver<LiveFrame>, filter: Option<String>, mut socket: WebSocket) {
+    loop {
+        tokio::select! {
+            frame = receiver.recv() => match frame {
+                Ok(frame) => {
+                    if filter.as_ref().is_some_and(|token| *token != frame.turbine_token) {
+                        continue;
+                    }
+                    if socket.send(Message::Text(frame.json.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                // Un dashboard lento pierde frames intermedios en lugar de frenar al resto
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use super::quality::{parse_period, PeriodParams};
+use crate::{
+    error::AppError,
+    state::AppState,
+    timeline::{build_timeline, TimelineReport},
+};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use std::sync::Arc;
+
+// --- LÍNEA DE TIEMPO DE MODOS ---
+// GET /api/timeline/:token?from=&to= (o ?window=7d hasta ahora). Por defecto, las
+// últimas 24 horas.
+
+const DEFAULT_WINDOW_SEC: u64 = 86400;
+// Periodo máximo de una línea de tiempo
+const MAX_PERIOD_SEC: u64 = 31 * 86400;
+
+pub async fn timeline_handler(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Json<TimelineReport>, AppError> {
+    if !state.knows_turbine(&token).await {
+        return Err(AppError::NotFound(format!("Turbine '{}' not found", token)));
+    }
+    let (from, to) = parse_period(&params, DEFAULT_WINDOW_SEC, MAX_PERIOD_SEC)?;
+    Ok(Json(build_timeline(&state, &token, from, to).await?))
+}
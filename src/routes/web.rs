@@ -0,0 +1,1244 @@
+use super::annotations::frame_annotations;
+use crate::{
+    analysis::{
+        blades::{blade_report, BladeReport},
+        encoding::{MatrixEncoding, MatrixFrame, MAX_PRECISION},
+        evolution_points, extract_frame, frame_data, frame_matrix, frame_range,
+        fusion::{decode_photo, fit_photo, fuse},
+        hottest_frame,
+        npz::{array_names, frame_axes, is_npz, read_named_array, NamedArray, FRAMES_ARRAY},
+        registration::{aligned_difference, downsample, estimate_translation, Alignment, DEFAULT_MAX_SHIFT},
+        overlay::{parse_color, Canvas, DEFAULT_COLOR},
+        thumbnail::{encode_png, render_rgb},
+        FrameError, ThermalFrameData,
+    },
+    calibration::calibration_at,
+    error::AppError,
+    incidents::{build_incident_timeline, Incident, IncidentTimeline},
+    latest_frames::LatestFrameStats,
+    response_cache::{fingerprint, CacheKey},
+    state::{AlertRecord, AppState, LiveStatus, RemoteConfig},
+    schedule::{format_timestamp, parse_duration, parse_timestamp, parse_timezone},
+    secrets::{redact_config, restore_redacted},
+    storage::{
+        alert_log::{alert_history, append_alerts, write_alerts},
+        annotations::Annotation,
+        archive::locate_capture,
+        catalog::{capture_camera, save_catalog, CaptureRecord},
+        config_history::{redacted_history, ConfigChange, ConfigVersion},
+        export::{write_export, ChannelWriter},
+        lineage::record_derivation,
+        open_capture,
+        paths::{check_file_name, safe_resolve},
+        photos::{photo_path, PhotoFormat},
+        read_capture, storage_root,
+    },
+    thresholds::Severity,
+    units::TempUnit,
+    workorders::{create_work_order, Confirmation},
+};
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, Method},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono_tz::Tz;
+use ndarray_npy::WriteNpyExt;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, io::Write, path::PathBuf, sync::Arc};
+use tokio_stream::wrappers::ReceiverStream;
+
+// --- FECHAS Y UNIDADES PEDIDAS ---
+
+#[derive(Deserialize)]
+pub struct DisplayParams {
+    // Zona IANA ("Europe/Madrid"); por defecto display_timezone de la configuración o UTC
+    tz: Option<String>,
+    // c o f; por defecto display_units de la configuración
+    units: Option<TempUnit>,
+}
+
+// Registro tal cual más su instante en ISO 8601 con desplazamiento
+#[derive(Serialize)]
+pub struct Timed<T> {
+    #[serde(flatten)]
+    pub item: T,
+    pub time: String,
+}
+
+pub async fn display_tz(state: &AppState, requested: Option<&str>) -> Result<Tz, AppError> {
+    match requested {
+        Some(name) => parse_timezone(name).map_err(AppError::BadRequest),
+        None => Ok(state.config.read().await.display_timezone.as_deref()
+            .and_then(|name| parse_timezone(name).ok())
+            .unwrap_or(Tz::UTC)),
+    }
+}
+
+pub async fn display_unit(state: &AppState, requested: Option<TempUnit>) -> TempUnit {
+    match requested {
+        Some(unit) => unit,
+        None => state.config.read().await.display_units,
+    }
+}
+
+// --- LISTADOS EN NDJSON ---
+// Con decenas de miles de filas, /api/files y /api/alerts pueden responder en NDJSON
+// (un objeto JSON por línea) si el cliente lo pide con Accept: application/x-ndjson (o
+// application/jsonl). Las filas se serializan a medida que se envían, sin construir en
+// memoria el array JSON completo; sin esa cabecera la respuesta sigue siendo un array.
+
+pub fn wants_ndjson(headers: &HeaderMap) -> bool {
+    headers.get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| {
+            accept.split(',')
+                .filter_map(|media| media.split(';').next())
+                .any(|media| matches!(media.trim(), "application/x-ndjson" | "application/jsonl"))
+        })
+}
+
+pub fn ndjson_response<T: Serialize + Send + 'static>(rows: Vec<T>) -> Response {
+    let (tx, rx) = tokio::sync::mpsc::channel(4);
+    tokio::task::spawn_blocking(move || {
+        let error_tx = tx.clone();
+        let mut writer = ChannelWriter::new(tx);
+        let result = rows.iter()
+            .try_for_each(|row| {
+                serde_json::to_writer(&mut writer, row)?;
+                writer.write_all(b"\n")
+            })
+            .and_then(|_| writer.flush());
+        match result {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                tracing::debug!("Listado NDJSON cancelado por el cliente");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "❌ Error enviando un listado NDJSON");
+                let _ = error_tx.blocking_send(Err(e));
+            }
+        }
+    });
+    ([(header::CONTENT_TYPE, "application/x-ndjson")], Body::from_stream(ReceiverStream::new(rx))).into_response()
+}
+
+// Estructura para listar archivos
+#[derive(Serialize)]
+pub struct FileEntry {
+    name: String,
+    size_kb: u64,
+    date: String,
+    #[serde(rename = "type")]
+    file_type: String,
+    // Resolución de las capturas catalogadas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<usize>,
+}
+
+// --- HANDLERS NUEVOS Y MODIFICADOS ---
+
+#[derive(Deserialize)]
+pub struct DownloadParams {
+    // true = mostrar en el navegador (logs de texto, imágenes) en vez de descargar
+    #[serde(default)]
+    inline: bool,
+}
+
+// Tipo MIME por el contenido (bytes mágicos) y, si no lo delata, por la extensión.
+// Las capturas .npz suelen ser en realidad un .npy sin comprimir.
+fn content_type_for(filename: &str, data: &[u8]) -> &'static str {
+    if data.starts_with(b"\x93NUMPY") {
+        return "application/x-npy";
+    }
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return "image/png";
+    }
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return "image/jpeg";
+    }
+    if data.starts_with(b"PK\x03\x04") {
+        return "application/zip";
+    }
+    let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("txt" | "log") => "text/plain; charset=utf-8",
+        Some("json") => "application/json",
+        Some("csv") => "text/csv; charset=utf-8",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+// Log del robot (.txt en la raíz del almacenamiento); no están en el catálogo
+fn locate_robot_log(filename: &str) -> std::io::Result<PathBuf> {
+    if check_file_name(filename).is_err() {
+        return Err(std::io::ErrorKind::NotFound.into());
+    }
+    let path = safe_resolve(&storage_root(), filename)?;
+    if !path.is_file() {
+        return Err(std::io::ErrorKind::NotFound.into());
+    }
+    Ok(path)
+}
+
+// 1. NUEVO: Descarga de archivos (capturas, logs, imágenes generadas)
+pub async fn download_file_handler(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(params): Query<DownloadParams>,
+) -> Result<Response, AppError> {
+    // Leemos el archivo en el pool bloqueante (restaurándolo del archivo frío si hace
+    // falta); solo se sirven capturas del catálogo y logs del robot, el resto da 404
+    let worker_filename = filename.clone();
+    let file_bytes = tokio::task::spawn_blocking(move || {
+        let path = if worker_filename.ends_with(".txt") {
+            locate_robot_log(&worker_filename)
+        } else {
+            locate_capture(&state, &worker_filename)
+        };
+        path.and_then(|path| read_capture(&path))
+    })
+    .await??;
+
+    let content_type = content_type_for(&filename, &file_bytes);
+    // Convertimos bytes a Body de Axum
+    let body = Body::from(file_bytes);
+
+    // Adjunto (descarga) por defecto; inline para previsualizar en el navegador
+    let disposition = if params.inline { "inline" } else { "attachment" };
+    let headers = [
+        (header::CONTENT_TYPE, content_type),
+        (header::CONTENT_DISPOSITION, &format!("{}; filename=\"{}\"", disposition, filename)),
+        // Que el navegador no reinterprete un log como HTML
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+    ];
+
+    Ok((headers, body).into_response())
+}
+
+// Foto visible de una captura (ver storage::photos), para mostrarla junto a la térmica
+pub async fn get_photo_handler(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+) -> Result<Response, AppError> {
+    let photo = state.catalog.read().await.iter()
+        .find(|r| r.filename == filename)
+        .ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", filename)))?
+        .photo.clone()
+        .ok_or_else(|| AppError::NotFound(format!("Capture '{}' has no photo", filename)))?;
+    let format = PhotoFormat::of_name(&photo)
+        .ok_or_else(|| AppError::Internal(format!("unexpected photo name '{}'", photo)))?;
+    let bytes = tokio::task::spawn_blocking(move || read_capture(&photo_path(&photo)?)).await??;
+    let headers = [
+        (header::CONTENT_TYPE, format.content_type()),
+        (header::CACHE_CONTROL, "private, max-age=86400"),
+    ];
+    Ok((headers, bytes).into_response())
+}
+
+// Codificación de los píxeles en las respuestas JSON de /api/matrix (ver
+// analysis::encoding); sin ella, el array `pixels` completo
+#[derive(Deserialize)]
+pub struct EncodingParams {
+    encoding: Option<MatrixEncoding>,
+    precision: Option<u32>,
+}
+
+impl EncodingParams {
+    fn encoder(&self) -> Result<impl Fn(ThermalFrameData) -> MatrixFrame, AppError> {
+        if let Some(precision) = self.precision.filter(|p| *p > MAX_PRECISION) {
+            return Err(AppError::BadRequest(format!("precision must be at most {} (got {})", MAX_PRECISION, precision)));
+        }
+        let (encoding, precision) = (self.encoding.unwrap_or(MatrixEncoding::Dense), self.precision);
+        Ok(move |frame| encoding.encode(frame, precision))
+    }
+}
+
+// Frame con las anotaciones de los usuarios sobre él
+#[derive(Serialize)]
+pub struct AnnotatedFrame {
+    #[serde(flatten)]
+    frame: MatrixFrame,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+// 2. NUEVO: Obtener Matriz Cruda (JSON)
+// Devuelve los datos necesarios para que el frontend dibuje el mapa de calor
+pub async fn get_matrix_handler(
+    State(state): State<Arc<AppState>>,
+    Path((filename, frame_index)): Path<(String, usize)>,
+    Query(params): Query<DisplayParams>,
+    Query(encoding): Query<EncodingParams>,
+) -> Result<Json<AnnotatedFrame>, AppError> {
+    let encode = encoding.encoder()?;
+    let unit = display_unit(&state, params.units).await;
+    let annotations = frame_annotations(&state, &filename, frame_index).await;
+    // Los últimos frames de cada turbina ya están en memoria
+    if let Some(frame) = state.latest_frames.find(&filename, frame_index).await {
+        return Ok(Json(AnnotatedFrame { frame: encode(unit.frame(frame)), annotations }));
+    }
+    let key = (filename.clone(), frame_index);
+    // La caché guarda siempre Celsius
+    let cached = state.frame_cache.lock().await.get(&key).cloned();
+    if let Some(frame) = cached {
+        return Ok(Json(AnnotatedFrame { frame: encode(unit.frame(frame)), annotations }));
+    }
+
+    // Decodificación y estadísticas en el pool bloqueante para no frenar el runtime
+    let worker_state = state.clone();
+    let frame = tokio::task::spawn_blocking(move || load_frame(&worker_state, &filename, frame_index)).await??;
+
+    state.frame_cache.lock().await.put(key, frame.clone());
+    Ok(Json(AnnotatedFrame { frame: encode(unit.frame(frame)), annotations }))
+}
+
+// Varios frames en una respuesta, para recorrer una secuencia sin una petición por frame
+#[derive(Deserialize)]
+pub struct FrameRangeParams {
+    // "5..20" (20 excluido), "5..=20", "5..", "..20" o un único índice
+    frames: Option<String>,
+    stride: Option<usize>,
+    // "json" (por defecto) o "npy": pila N×H×W en binario con los índices en X-Frame-Indices
+    // (?encoding= solo se aplica en JSON)
+    format: Option<String>,
+    units: Option<TempUnit>,
+}
+
+#[derive(Serialize)]
+pub struct FrameRange {
+    indices: Vec<usize>,
+    frames: Vec<MatrixFrame>,
+}
+
+// Máximo de frames por respuesta
+const MAX_RANGE_FRAMES: usize = 256;
+
+// Rango semiabierto [inicio, fin); sin fin = hasta el último frame
+fn parse_frame_range(s: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("invalid frame range '{}'", s);
+    let index = |v: &str| v.trim().parse::<usize>().map_err(|_| invalid());
+    match s.split_once("..") {
+        None => index(s).map(|i| (i, i + 1)),
+        Some((start, end)) => {
+            let start = if start.is_empty() { 0 } else { index(start)? };
+            let end = match end.strip_prefix('=') {
+                Some(end) => index(end)?.checked_add(1).ok_or_else(invalid)?,
+                None if end.is_empty() => usize::MAX,
+                None => index(end)?,
+            };
+            Ok((start, end))
+        }
+    }
+}
+
+pub async fn get_matrix_range_handler(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(params): Query<FrameRangeParams>,
+    Query(encoding): Query<EncodingParams>,
+) -> Result<Response, AppError> {
+    let encode = encoding.encoder()?;
+    let (start, end) = parse_frame_range(params.frames.as_deref().unwrap_or(".."))
+        .map_err(AppError::BadRequest)?;
+    let stride = params.stride.unwrap_or(1);
+    if stride == 0 {
+        return Err(AppError::BadRequest("stride must be positive".into()));
+    }
+    let binary = match params.format.as_deref() {
+        None | Some("json") => false,
+        Some("npy") => true,
+        Some(other) => return Err(AppError::BadRequest(format!("Unknown format '{}'", other))),
+    };
+    let unit = display_unit(&state, params.units).await;
+
+    let (indices, mut stack, axes) = tokio::task::spawn_blocking(move || {
+        let capture = open_capture(&locate_capture(&state, &filename)?)?;
+        // El tope se aplica antes de copiar: nunca se carga más de MAX_RANGE_FRAMES
+        let end = end.min(start.saturating_add(MAX_RANGE_FRAMES.saturating_mul(stride)));
+        let (indices, stack) = frame_range(&capture, start, end, stride).map_err(|e| frame_error(&filename, e))?;
+        Ok::<_, AppError>((indices, stack, frame_axes(&capture)))
+    })
+    .await??;
+    if unit != TempUnit::Celsius {
+        stack.mapv_inplace(|t| unit.temp(t));
+    }
+
+    if binary {
+        let mut npy = Vec::new();
+        stack.write_npy(&mut npy).map_err(|e| AppError::Internal(e.to_string()))?;
+        let indices = indices.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
+        let headers = [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (header::HeaderName::from_static("x-frame-indices"), indices),
+        ];
+        return Ok((headers, npy).into_response());
+    }
+    let frames = stack.outer_iter().zip(&indices)
+        .map(|(matrix, &i)| encode(ThermalFrameData { angle: axes.angle(i), timestamp: axes.timestamp(i), ..frame_data(matrix) }))
+        .collect();
+    Ok(Json(FrameRange { indices, frames }).into_response())
+}
+
+// Arrays con nombre de una captura npz (frames, angles, timestamps...). Un npy suelto
+// solo tiene los frames
+pub async fn list_arrays_handler(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+) -> Result<Json<Vec<String>>, AppError> {
+    let names = tokio::task::spawn_blocking(move || {
+        let capture = open_capture(&locate_capture(&state, &filename)?)?;
+        match is_npz(&capture) {
+            true => array_names(&capture).map_err(AppError::BadRequest),
+            false => Ok(vec![FRAMES_ARRAY.to_string()]),
+        }
+    })
+    .await??;
+    Ok(Json(names))
+}
+
+// Valores que se devuelven como máximo por JSON; los frames se piden con /api/matrix
+const MAX_ARRAY_VALUES: usize = 1_000_000;
+
+pub async fn get_array_handler(
+    State(state): State<Arc<AppState>>,
+    Path((filename, name)): Path<(String, String)>,
+) -> Result<Json<NamedArray>, AppError> {
+    let array = tokio::task::spawn_blocking(move || {
+        let capture = open_capture(&locate_capture(&state, &filename)?)?;
+        if !is_npz(&capture) {
+            return Err(match name == FRAMES_ARRAY {
+                true => AppError::BadRequest(format!("{} is a plain npy capture; read its frames with /api/matrix", filename)),
+                false => AppError::NotFound(format!("{} has no array '{}'", filename, name)),
+            });
+        }
+        if !array_names(&capture).map_err(AppError::BadRequest)?.contains(&name) {
+            return Err(AppError::NotFound(format!("{} has no array '{}'", filename, name)));
+        }
+        read_named_array(&capture, &name).map_err(AppError::BadRequest)
+    })
+    .await??;
+    if array.values.len() > MAX_ARRAY_VALUES {
+        return Err(AppError::BadRequest(format!(
+            "Array '{}' has {} values (max {}); read frames with /api/matrix",
+            array.name,
+            array.values.len(),
+            MAX_ARRAY_VALUES
+        )));
+    }
+    Ok(Json(array))
+}
+
+pub(crate) fn load_frame(state: &AppState, filename: &str, frame_index: usize) -> Result<ThermalFrameData, AppError> {
+    // Abrir archivo (restaurándolo del archivo frío si hace falta)
+    let path = locate_capture(state, filename)?;
+    let capture = open_capture(&path)?;
+
+    extract_frame(&capture, frame_index).map_err(|e| frame_error(filename, e))
+}
+
+// Frame como PNG con la paleta ironbow, a tamaño completo o reducido con ?max_side=
+// (las miniaturas de las alertas apuntan aquí); con ?annotations=true lleva dibujadas
+// las anotaciones de los usuarios
+#[derive(Deserialize)]
+pub struct RenderParams {
+    max_side: Option<usize>,
+    #[serde(default)]
+    annotations: bool,
+}
+
+// Lado mayor de las imágenes renderizadas
+const MAX_RENDER_SIDE: usize = 1024;
+
+pub async fn get_render_handler(
+    State(state): State<Arc<AppState>>,
+    Path((filename, frame_index)): Path<(String, usize)>,
+    Query(params): Query<RenderParams>,
+) -> Result<Response, AppError> {
+    let max_side = params.max_side.unwrap_or(MAX_RENDER_SIDE).clamp(1, MAX_RENDER_SIDE);
+    let annotations = if params.annotations {
+        frame_annotations(&state, &filename, frame_index).await
+    } else {
+        Vec::new()
+    };
+    let annotation_ids: Vec<String> = annotations.iter().map(|a| a.id.clone()).collect();
+    let (worker_state, worker_filename) = (state.clone(), filename.clone());
+    let png = tokio::task::spawn_blocking(move || {
+        let capture = open_capture(&locate_capture(&worker_state, &worker_filename)?)?;
+        let frame = frame_matrix(&capture, frame_index).map_err(|e| frame_error(&worker_filename, e))?;
+        let mut image = render_rgb(&frame, max_side);
+        let mut canvas = Canvas { pixels: &mut image.pixels, width: image.width, height: image.height };
+        for annotation in &annotations {
+            let color = annotation.color.as_deref().and_then(parse_color).unwrap_or(DEFAULT_COLOR);
+            canvas.draw(&annotation.shape, color, image.scale);
+        }
+        encode_png(&image).map_err(|e| AppError::Internal(format!("PNG encoding failed: {}", e)))
+    })
+    .await??;
+    let operation = if max_side < MAX_RENDER_SIDE { "thumbnail" } else { "render" };
+    let params = serde_json::json!({ "palette": "ironbow", "max_side": max_side, "annotations": annotation_ids });
+    record_derivation(&state, operation, &[(&filename, Some(frame_index))], params, None).await;
+    Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response())
+}
+
+// Frame térmico fundido con la foto visible de la captura (ver analysis::fusion), en PNG:
+// ?alpha= es la opacidad del térmico, ?frame= el frame (por defecto el más caliente) y
+// ?max_side= reduce la foto
+#[derive(Deserialize)]
+pub struct FusionParams {
+    alpha: Option<f32>,
+    frame: Option<usize>,
+    max_side: Option<usize>,
+}
+
+const DEFAULT_FUSION_ALPHA: f32 = 0.5;
+// Lado mayor de las imágenes fundidas (las fotos suelen ser mucho mayores que el frame)
+const MAX_FUSION_SIDE: usize = 2048;
+
+pub async fn get_fusion_handler(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(params): Query<FusionParams>,
+) -> Result<Response, AppError> {
+    let alpha = params.alpha.unwrap_or(DEFAULT_FUSION_ALPHA);
+    if !(0.0..=1.0).contains(&alpha) {
+        return Err(AppError::BadRequest("alpha must be between 0 and 1".into()));
+    }
+    let max_side = params.max_side.unwrap_or(MAX_FUSION_SIDE).clamp(1, MAX_FUSION_SIDE);
+    let record = state.catalog.read().await.iter()
+        .find(|r| r.filename == filename)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", filename)))?;
+    let photo = record.photo.clone()
+        .ok_or_else(|| AppError::NotFound(format!("Capture '{}' has no photo", filename)))?;
+    let region = calibration_at(&state.calibrations.read().await, &record.turbine_token, record.camera_id.as_deref(), record.timestamp)
+        .and_then(|c| c.photo_region)
+        .unwrap_or_default();
+
+    let (worker_state, worker_filename) = (state.clone(), filename.clone());
+    let (png, frame_index) = tokio::task::spawn_blocking(move || {
+        let capture = open_capture(&locate_capture(&worker_state, &worker_filename)?)?;
+        let (frame_index, frame) = match params.frame {
+            Some(index) => (index, frame_matrix(&capture, index).map_err(|e| frame_error(&worker_filename, e))?),
+            None => hottest_frame(&capture).ok_or_else(|| frame_error(&worker_filename, FrameError::Unreadable))?,
+        };
+        let photo = decode_photo(&read_capture(&photo_path(&photo)?)?)
+            .map_err(|e| AppError::Internal(format!("photo of {} is unreadable: {}", worker_filename, e)))?;
+        let mut image = fit_photo(photo, max_side);
+        fuse(&mut image, &frame, region, alpha);
+        encode_png(&image)
+            .map(|png| (png, frame_index))
+            .map_err(|e| AppError::Internal(format!("PNG encoding failed: {}", e)))
+    })
+    .await??;
+    let params = serde_json::json!({ "palette": "ironbow", "alpha": alpha, "max_side": max_side, "photo_region": region });
+    record_derivation(&state, "fusion", &[(&filename, Some(frame_index))], params, None).await;
+    Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response())
+}
+
+fn frame_error(filename: &str, error: FrameError) -> AppError {
+    match error {
+        FrameError::Unreadable => AppError::Internal(format!("{} is not a readable thermal matrix", filename)),
+        FrameError::OutOfRange => AppError::BadRequest("Frame index out of range".into()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BladeParams {
+    // Fase del rotor en la captura; por defecto la de la configuración
+    rotor_phase: Option<f32>,
+}
+
+// Estadísticas por pala (buje, palas y fondo) de un frame según la máscara del rotor
+pub async fn get_blades_handler(
+    State(state): State<Arc<AppState>>,
+    Path((filename, frame_index)): Path<(String, usize)>,
+    Query(params): Query<BladeParams>,
+) -> Result<Json<BladeReport>, AppError> {
+    let Some(geometry) = state.config.read().await.blade_geometry.clone() else {
+        return Err(AppError::BadRequest("blade_geometry is not configured".into()));
+    };
+    let phase = params.rotor_phase.unwrap_or(geometry.rotor_phase_deg);
+    let lineage_params = serde_json::json!({ "geometry": geometry, "rotor_phase": phase });
+    let (worker_state, worker_filename) = (state.clone(), filename.clone());
+    let report = tokio::task::spawn_blocking(move || {
+        let path = locate_capture(&worker_state, &worker_filename)?;
+        let capture = open_capture(&path)?;
+        blade_report(&capture, frame_index, &geometry, phase).map_err(|e| frame_error(&worker_filename, e))
+    })
+    .await??;
+    record_derivation(&state, "blades", &[(&filename, Some(frame_index))], lineage_params, None).await;
+    Ok(Json(report))
+}
+
+fn default_register() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct DiffParams {
+    // Captura de referencia y captura a comparar (pueden ser la misma)
+    a: String,
+    b: String,
+    #[serde(default)]
+    a_frame: usize,
+    #[serde(default)]
+    b_frame: usize,
+    // Alinear antes de restar para compensar la deriva del pan/tilt
+    #[serde(default = "default_register")]
+    register: bool,
+    max_shift: Option<usize>,
+    // Con resoluciones distintas, reducir el frame mayor a la resolución del menor en
+    // vez de rechazar la comparación
+    #[serde(default)]
+    resample: bool,
+}
+
+// Frame reducido antes de comparar ("a" o "b") y sus resoluciones (ancho × alto)
+#[derive(Serialize)]
+pub struct Resampled {
+    frame: &'static str,
+    from: String,
+    to: String,
+}
+
+fn resolution(frame: &ndarray::Array2<f32>) -> String {
+    let (rows, cols) = frame.dim();
+    format!("{}x{}", cols, rows)
+}
+
+#[derive(Serialize)]
+pub struct FrameDiff {
+    // Dimensiones de la zona solapada tras alinear
+    width: usize,
+    height: usize,
+    alignment: Option<Alignment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resampled: Option<Resampled>,
+    min_delta: f32,
+    max_delta: f32,
+    // b − a, aplanado fila por fila
+    pixels: Vec<f32>,
+}
+
+// Diferencia entre dos frames (típicamente dos escaneos del mismo ángulo)
+pub async fn get_diff_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DiffParams>,
+) -> Result<Json<FrameDiff>, AppError> {
+    let inputs = [(params.a.clone(), params.a_frame), (params.b.clone(), params.b_frame)];
+    let worker_state = state.clone();
+    let diff = tokio::task::spawn_blocking(move || {
+        let load = |filename: &str, frame_index: usize| -> Result<_, AppError> {
+            let capture = open_capture(&locate_capture(&worker_state, filename)?)?;
+            frame_matrix(&capture, frame_index).map_err(|e| frame_error(filename, e))
+        };
+        let mut reference = load(&params.a, params.a_frame)?;
+        let mut moving = load(&params.b, params.b_frame)?;
+        let mut resampled = None;
+        if reference.dim() != moving.dim() {
+            let (resolution_a, resolution_b) = (resolution(&reference), resolution(&moving));
+            if !params.resample {
+                return Err(AppError::BadRequest(format!(
+                    "Frames have different resolutions (a: {}, b: {}); pass resample=true to compare at the lower resolution",
+                    resolution_a, resolution_b,
+                )));
+            }
+            // Se reduce el de más píxeles a la resolución del otro
+            let (larger, target, name) = if reference.len() > moving.len() {
+                (&mut reference, moving.dim(), "a")
+            } else {
+                (&mut moving, reference.dim(), "b")
+            };
+            if larger.nrows() < target.0 || larger.ncols() < target.1 {
+                return Err(AppError::BadRequest(format!(
+                    "Frames have incompatible resolutions (a: {}, b: {}); neither contains the other",
+                    resolution_a, resolution_b,
+                )));
+            }
+            let (from, to) = if name == "a" { (resolution_a, resolution_b) } else { (resolution_b, resolution_a) };
+            *larger = downsample(larger.view(), target);
+            resampled = Some(Resampled { frame: name, from, to });
+        }
+
+        let alignment = params.register.then(|| {
+            estimate_translation(reference.view(), moving.view(), params.max_shift.unwrap_or(DEFAULT_MAX_SHIFT))
+        });
+        let delta = aligned_difference(reference.view(), moving.view(), alignment.unwrap_or_default());
+        let (height, width) = delta.dim();
+        Ok(FrameDiff {
+            width,
+            height,
+            alignment,
+            resampled,
+            min_delta: delta.fold(f32::INFINITY, |a, &b| a.min(b)),
+            max_delta: delta.fold(f32::NEG_INFINITY, |a, &b| a.max(b)),
+            pixels: delta.as_standard_layout().into_owned().into_raw_vec(),
+        })
+    })
+    .await??;
+    let lineage_params = serde_json::json!({ "alignment": diff.alignment, "resampled": diff.resampled });
+    let inputs = inputs.each_ref().map(|(filename, frame_index)| (filename.as_str(), Some(*frame_index)));
+    record_derivation(&state, "diff", &inputs, lineage_params, None).await;
+    Ok(Json(diff))
+}
+
+// --- HANDLERS EXISTENTES ---
+
+#[derive(Deserialize)]
+pub struct FilesParams {
+    tz: Option<String>,
+    // Solo las capturas de esta cámara (según el nombre del archivo)
+    camera: Option<String>,
+}
+
+pub async fn list_files_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<FilesParams>,
+) -> Result<Response, AppError> {
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+    let shapes: HashMap<String, (usize, usize)> = state.catalog.read().await.iter()
+        .filter_map(|r| Some((r.filename.clone(), r.shape()?)))
+        .collect();
+    let mut files = Vec::new();
+
+    if let Ok(entries) = std::fs::read_dir(storage_root()) {
+        for entry in entries.flatten() {
+            if let Ok(metadata) = entry.metadata()
+                && metadata.is_file()
+            {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if params.camera.is_some() && capture_camera(&name) != params.camera {
+                    continue;
+                }
+                if name.ends_with(".npz") || name.ends_with(".txt") {
+                    let date: chrono::DateTime<chrono::Utc> = metadata.modified()
+                        .unwrap_or(std::time::SystemTime::now())
+                        .into();
+
+                    let shape = shapes.get(&name);
+                    files.push((date, FileEntry {
+                        width: shape.map(|(_, width)| *width),
+                        height: shape.map(|(height, _)| *height),
+                        name: name.clone(),
+                        size_kb: metadata.len() / 1024,
+                        date: format_timestamp(date.timestamp() as u64, tz),
+                        file_type: if name.contains("log") { "log".to_string() } else { "capture".to_string() },
+                    }));
+                }
+            }
+        }
+    }
+    // Con desplazamiento las cadenas ya no ordenan cronológicamente
+    files.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+    let files: Vec<FileEntry> = files.into_iter().map(|(_, entry)| entry).collect();
+    if wants_ndjson(&headers) {
+        return Ok(ndjson_response(files));
+    }
+    Ok(Json(files).into_response())
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSort {
+    #[default]
+    Timestamp,
+    AnomalyScore,
+    MaxTemp,
+}
+
+fn default_captures_limit() -> usize {
+    100
+}
+
+#[derive(Deserialize)]
+pub struct CapturesParams {
+    turbine_token: Option<String>,
+    #[serde(default)]
+    sort: CaptureSort,
+    // Por defecto de mayor a menor
+    #[serde(default)]
+    ascending: bool,
+    #[serde(default = "default_captures_limit")]
+    limit: usize,
+    // Solo las marcadas con estrella
+    #[serde(default)]
+    starred: bool,
+    camera: Option<String>,
+    tz: Option<String>,
+    units: Option<TempUnit>,
+}
+
+// Capturas del catálogo ordenables por fecha, anomalía o temperatura. Las que no tienen
+// el campo de orden (capturas antiguas) van siempre al final.
+pub async fn list_captures_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CapturesParams>,
+) -> Result<Json<Vec<Timed<CaptureRecord>>>, AppError> {
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+    let unit = display_unit(&state, params.units).await;
+    let mut records: Vec<CaptureRecord> = state.catalog.read().await.iter()
+        .filter(|r| params.turbine_token.as_ref().is_none_or(|t| *t == r.turbine_token))
+        .filter(|r| !params.starred || r.starred)
+        .filter(|r| params.camera.is_none() || r.camera_id == params.camera)
+        .cloned()
+        .collect();
+    let key = |r: &CaptureRecord| match params.sort {
+        CaptureSort::Timestamp => Some(r.timestamp as f64),
+        CaptureSort::AnomalyScore => r.anomaly_score.map(f64::from),
+        CaptureSort::MaxTemp => r.max_temp.map(f64::from),
+    };
+    records.sort_by(|a, b| match (key(a), key(b)) {
+        (Some(x), Some(y)) if params.ascending => x.total_cmp(&y),
+        (Some(x), Some(y)) => y.total_cmp(&x),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    records.truncate(params.limit);
+    Ok(Json(records.into_iter()
+        .map(|r| Timed { time: format_timestamp(r.timestamp, tz), item: unit.capture(r) })
+        .collect()))
+}
+
+// Estado en vivo con las estadísticas del último frame de la turbina, si está en memoria
+#[derive(Serialize)]
+pub struct LiveView {
+    #[serde(flatten)]
+    status: LiveStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latest_frame: Option<LatestFrameStats>,
+}
+
+pub async fn get_live_status(State(state): State<Arc<AppState>>) -> Json<LiveView> {
+    let mut status = state.live_status.read().await.clone();
+    let now = chrono::Utc::now().timestamp() as u64;
+    if now > status.last_update.saturating_add(state.offline_after(&status.turbine_token).await) {
+        status.is_online = false;
+        status.mode = "Lost Connection".to_string();
+    }
+    let latest_frame = state.latest_frames.newest(&status.turbine_token).await.map(|f| f.stats());
+    Json(LiveView { status, latest_frame })
+}
+
+// Frame reciente de una turbina servido desde memoria (ver crate::latest_frames)
+#[derive(Serialize)]
+pub struct RecentFrame {
+    filename: String,
+    frame_index: usize,
+    // Instante de la captura (el frame puede traer el suyo en `timestamp`)
+    captured: u64,
+    #[serde(flatten)]
+    frame: MatrixFrame,
+}
+
+// Últimos frames de una turbina, del más nuevo al más antiguo, para el mapa de calor en vivo
+pub async fn get_live_frames(
+    State(state): State<Arc<AppState>>,
+    Path(turbine_token): Path<String>,
+    Query(params): Query<DisplayParams>,
+    Query(encoding): Query<EncodingParams>,
+) -> Result<Json<Vec<RecentFrame>>, AppError> {
+    let encode = encoding.encoder()?;
+    let unit = display_unit(&state, params.units).await;
+    let frames = state.latest_frames.for_turbine(&turbine_token).await;
+    Ok(Json(frames.into_iter()
+        .map(|f| RecentFrame { filename: f.filename, frame_index: f.frame_index, captured: f.timestamp, frame: encode(unit.frame(f.frame)) })
+        .collect()))
+}
+
+// Los secretos se devuelven enmascarados (ver secrets::redact_config)
+pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<RemoteConfig> {
+    Json(redact_config(&*state.config.read().await))
+}
+
+// Configuración que se aplica ahora mismo (umbrales desplazados por el perfil horario activo)
+#[derive(Serialize)]
+pub struct EffectiveConfig {
+    active_profile: Option<String>,
+    config: RemoteConfig,
+}
+
+pub async fn get_effective_config(State(state): State<Arc<AppState>>) -> Json<EffectiveConfig> {
+    let now = chrono::Utc::now();
+    let config = state.config.read().await;
+    Json(EffectiveConfig {
+        active_profile: config.active_profile(&now).map(|p| p.name.clone()),
+        config: redact_config(&config.effective_at(&now)),
+    })
+}
+
+// Autor de un cambio de configuración: el dashboard envía el usuario en X-User
+pub(crate) fn change_author(headers: &HeaderMap) -> Option<String> {
+    headers.get("x-user").and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+pub async fn update_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut new_conf): Json<RemoteConfig>
+) -> Result<Json<&'static str>, AppError> {
+    // Los secretos que llegan enmascarados conservan el valor actual
+    let current = state.config.read().await.clone();
+    restore_redacted(&mut new_conf, &current).map_err(AppError::BadRequest)?;
+    new_conf.validate().map_err(AppError::BadRequest)?;
+    // Imprimir si se actualizó la Key
+    if let Some(ref key) = new_conf.gemini_api_key
+        && !key.is_empty()
+        && current.gemini_api_key.as_ref() != Some(key)
+    {
+        tracing::info!("🔑 Gemini API Key actualizada.");
+    }
+    state.apply_config(new_conf, change_author(&headers), "update", None).await
+        .map_err(|e| AppError::Internal(format!("could not save the configuration: {}", e)))?;
+    Ok(Json("Config updated successfully"))
+}
+
+// Historial de versiones, la más reciente primero (sin la configuración completa)
+#[derive(Serialize)]
+pub struct ConfigVersionSummary {
+    version: u64,
+    timestamp: u64,
+    author: Option<String>,
+    source: String,
+    rollback_of: Option<u64>,
+    changes: Vec<ConfigChange>,
+}
+
+pub async fn get_config_history(State(state): State<Arc<AppState>>) -> Json<Vec<ConfigVersionSummary>> {
+    let history = redacted_history(&state.config_history.read().await);
+    Json(history.into_iter().rev().map(|v| ConfigVersionSummary {
+        version: v.version,
+        timestamp: v.timestamp,
+        author: v.author,
+        source: v.source,
+        rollback_of: v.rollback_of,
+        changes: v.changes,
+    }).collect())
+}
+
+pub async fn get_config_version(
+    State(state): State<Arc<AppState>>,
+    Path(version): Path<u64>,
+) -> Result<Json<ConfigVersion>, AppError> {
+    redacted_history(&state.config_history.read().await).into_iter()
+        .find(|v| v.version == version)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Config version {} not found", version)))
+}
+
+// Vuelve a aplicar una versión anterior; el rollback queda como una versión nueva
+#[derive(Serialize)]
+pub struct RollbackResult {
+    version: u64,
+    rollback_of: u64,
+}
+
+pub async fn rollback_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(version): Path<u64>,
+) -> Result<Json<RollbackResult>, AppError> {
+    let target = state.config_history.read().await.iter()
+        .find(|v| v.version == version)
+        .map(|v| v.config.clone())
+        .ok_or_else(|| AppError::NotFound(format!("Config version {} not found", version)))?;
+    // Una versión antigua puede no pasar las validaciones actuales
+    target.validate().map_err(AppError::BadRequest)?;
+    let new_version = state.apply_config(target, change_author(&headers), "rollback", Some(version)).await
+        .map_err(|e| AppError::Internal(format!("could not save the configuration: {}", e)))?;
+    tracing::warn!(rollback_of = version, version = new_version, "⏪ Configuración revertida");
+    Ok(Json(RollbackResult { version: new_version, rollback_of: version }))
+}
+
+#[derive(Deserialize)]
+pub struct AlertsParams {
+    #[serde(default)]
+    starred: bool,
+    tz: Option<String>,
+    units: Option<TempUnit>,
+}
+
+pub async fn get_alerts(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<AlertsParams>,
+) -> Result<Response, AppError> {
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+    let unit = display_unit(&state, params.units).await;
+    let alerts: Vec<Timed<AlertRecord>> = state.alerts.read().await.iter()
+        .filter(|a| !params.starred || a.starred)
+        .map(|a| Timed { time: format_timestamp(a.timestamp, tz), item: unit.alert(a.clone()) })
+        .collect();
+    if wants_ndjson(&headers) {
+        return Ok(ndjson_response(alerts));
+    }
+    Ok(Json(alerts).into_response())
+}
+
+// Exportación del histórico completo de alertas para informes y análisis fuera de línea
+#[derive(Deserialize)]
+pub struct AlertExportParams {
+    // csv (por defecto) o jsonl
+    #[serde(default)]
+    format: Option<String>,
+    // Segundos Unix o RFC 3339, ambos inclusivos
+    from: Option<String>,
+    to: Option<String>,
+    // Zona de la columna datetime del CSV
+    tz: Option<String>,
+}
+
+pub async fn export_alerts_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AlertExportParams>,
+) -> Result<Response, AppError> {
+    let csv = match params.format.as_deref().unwrap_or("csv") {
+        "csv" => true,
+        "jsonl" => false,
+        other => return Err(AppError::BadRequest(format!("Unknown export format '{}' (csv|jsonl)", other))),
+    };
+    let bound = |value: Option<&str>| value.map(parse_timestamp).transpose().map_err(AppError::BadRequest);
+    let from = bound(params.from.as_deref())?.unwrap_or(0);
+    let to = bound(params.to.as_deref())?.unwrap_or(u64::MAX);
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+
+    let mut alerts = alert_history(&state).await?;
+    alerts.retain(|a| (from..=to).contains(&a.timestamp));
+    alerts.sort_by_key(|a| a.timestamp);
+
+    let (tx, rx) = tokio::sync::mpsc::channel(4);
+    tokio::task::spawn_blocking(move || {
+        let error_tx = tx.clone();
+        match write_alerts(&alerts, csv, tz, ChannelWriter::new(tx)) {
+            Ok(exported) => tracing::info!(exported, "📤 Alertas exportadas"),
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                tracing::warn!("⚠️ Exportación de alertas cancelada por el cliente");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "❌ Error exportando alertas");
+                let _ = error_tx.blocking_send(Err(e));
+            }
+        }
+    });
+
+    let (content_type, extension) = match csv {
+        true => ("text/csv; charset=utf-8", "csv"),
+        false => ("application/x-ndjson", "jsonl"),
+    };
+    let filename = format!("sentinel_alerts_{}.{}", chrono::Utc::now().timestamp(), extension);
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+    ];
+    Ok((headers, Body::from_stream(ReceiverStream::new(rx))).into_response())
+}
+
+// --- ESTRELLAS ---
+// PUT marca y DELETE desmarca una captura o una alerta
+
+pub async fn star_capture(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    method: Method,
+) -> Result<Json<CaptureRecord>, AppError> {
+    let mut catalog = state.catalog.write().await;
+    let record = catalog.iter_mut()
+        .find(|r| r.filename == filename)
+        .ok_or_else(|| AppError::NotFound(format!("Capture '{}' not found", filename)))?;
+    record.starred = method == Method::PUT;
+    let record = record.clone();
+    save_catalog(&catalog);
+    tracing::info!(%filename, starred = record.starred, "⭐ Captura marcada");
+    Ok(Json(record))
+}
+
+pub async fn star_alert(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    method: Method,
+) -> Result<Json<AlertRecord>, AppError> {
+    let mut alerts = state.alerts.write().await;
+    let alert = alerts.iter_mut()
+        .find(|a| a.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Alert '{}' not found", id)))?;
+    alert.starred = method == Method::PUT;
+    append_alerts([&*alert]);
+    tracing::info!(%id, starred = alert.starred, "⭐ Alerta marcada");
+    Ok(Json(alert.clone()))
+}
+
+// Confirma una alerta; si es crítica y hay work_orders, abre el ticket de mantenimiento.
+// Repetirla no cambia la confirmación y reintenta el ticket si no se pudo abrir.
+pub async fn confirm_alert(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<AlertRecord>, AppError> {
+    let alert = {
+        let mut alerts = state.alerts.write().await;
+        let alert = alerts.iter_mut()
+            .find(|a| a.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Alert '{}' not found", id)))?;
+        if alert.confirmation.is_none() {
+            alert.confirmation = Some(Confirmation {
+                by: change_author(&headers),
+                at: chrono::Utc::now().timestamp() as u64,
+            });
+            append_alerts([&*alert]);
+            tracing::info!(%id, by = alert.confirmation.as_ref().and_then(|c| c.by.as_deref()), "✔️ Alerta confirmada");
+        }
+        alert.clone()
+    };
+    if alert.severity != Severity::Critical || alert.work_order.is_some() {
+        return Ok(Json(alert));
+    }
+    let order = create_work_order(&state, &alert).await.map_err(|e| {
+        tracing::error!(%id, error = %e, "❌ No se pudo abrir la orden de trabajo");
+        AppError::Internal(format!("Alert confirmed, but the work order could not be created: {}", e))
+    })?;
+    let Some(order) = order else {
+        return Ok(Json(alert));
+    };
+    tracing::info!(%id, key = %order.key, system = %order.system, "🛠️ Orden de trabajo abierta");
+    let mut alerts = state.alerts.write().await;
+    let alert = alerts.iter_mut()
+        .find(|a| a.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Alert '{}' not found", id)))?;
+    alert.work_order = Some(order);
+    append_alerts([&*alert]);
+    Ok(Json(alert.clone()))
+}
+
+pub async fn get_incidents(State(state): State<Arc<AppState>>) -> Json<Vec<Incident>> {
+    Json(state.incidents.read().await.iter().cloned().collect())
+}
+
+// Margen por defecto y máximo alrededor del incidente en su línea de tiempo
+const TIMELINE_MARGIN_SEC: u64 = 3600;
+const MAX_TIMELINE_MARGIN_SEC: u64 = 7 * 86400;
+
+#[derive(Deserialize)]
+pub struct TimelineParams {
+    // Cuánto antes y después del incidente ("30m", "2h"...)
+    margin: Option<String>,
+}
+
+pub async fn incident_timeline(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<TimelineParams>,
+) -> Result<Json<IncidentTimeline>, AppError> {
+    let margin = match params.margin.as_deref() {
+        Some(margin) => parse_duration(margin).map_err(AppError::BadRequest)?,
+        None => TIMELINE_MARGIN_SEC,
+    };
+    if margin > MAX_TIMELINE_MARGIN_SEC {
+        return Err(AppError::BadRequest(format!("margin must be at most {}s", MAX_TIMELINE_MARGIN_SEC)));
+    }
+    let timeline = build_incident_timeline(&state, &id, margin).await?
+        .ok_or_else(|| AppError::NotFound(format!("Incident '{}' not found", id)))?;
+    Ok(Json(timeline))
+}
+
+pub async fn get_evolution_data(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    Query(params): Query<DisplayParams>,
+) -> Result<Response, AppError> {
+    let tz = display_tz(&state, params.tz.as_deref()).await?;
+    let unit = display_unit(&state, params.units).await;
+    // Los frames sin instante propio muestran el de la captura
+    let record = state.catalog.read().await.iter().find(|r| r.filename == filename).cloned();
+    let capture_time = record.as_ref().map(|r| r.timestamp);
+    let key = CacheKey::new("evolution", &filename, format!("{:?}|{}", unit, tz));
+    let cache_state = state.clone();
+    cache_state.response_cache.json(key, fingerprint(&record), async move {
+        // Lectura y recorrido de frames en el pool bloqueante
+        let points = tokio::task::spawn_blocking(move || {
+            locate_capture(&state, &filename)
+                .and_then(|path| open_capture(&path))
+                .map(|capture| evolution_points(&capture))
+                .unwrap_or_default()
+        })
+        .await
+        .unwrap_or_default();
+        Ok(points.into_iter()
+            .map(|p| {
+                let time = p.timestamp.map(|t| t as u64).or(capture_time).map(|t| format_timestamp(t, tz)).unwrap_or_default();
+                Timed { item: unit.evolution(p), time }
+            })
+            .collect::<Vec<_>>())
+    })
+    .await
+}
+
+// Exportación completa: tar en streaming con las capturas filtradas y su manifiesto
+#[derive(Deserialize)]
+pub struct ExportParams {
+    turbine: Option<String>,
+    // Solo las capturas de esta colección
+    collection: Option<String>,
+    camera: Option<String>,
+    // Segundos Unix o RFC 3339, ambos inclusivos
+    from: Option<String>,
+    to: Option<String>,
+}
+
+pub async fn export_all_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, AppError> {
+    let bound = |value: Option<&str>| value.map(parse_timestamp).transpose().map_err(AppError::BadRequest);
+    let from = bound(params.from.as_deref())?.unwrap_or(0);
+    let to = bound(params.to.as_deref())?.unwrap_or(u64::MAX);
+
+    let members = match &params.collection {
+        Some(id) => Some(
+            state.collections.read().await.iter()
+                .find(|c| c.id == *id)
+                .map(|c| c.captures.clone())
+                .ok_or_else(|| AppError::NotFound(format!("Collection '{}' not found", id)))?,
+        ),
+        None => None,
+    };
+    let records: Vec<CaptureRecord> = state.catalog.read().await.iter()
+        .filter(|r| members.as_ref().is_none_or(|m| m.contains(&r.filename)))
+        .filter(|r| params.turbine.as_ref().is_none_or(|t| *t == r.turbine_token))
+        .filter(|r| params.camera.is_none() || r.camera_id == params.camera)
+        .filter(|r| (from..=to).contains(&r.timestamp))
+        .cloned()
+        .collect();
+    if records.is_empty() {
+        return Err(AppError::NotFound("No captures match the export filter".into()));
+    }
+
+    let (tx, rx) = tokio::sync::mpsc::channel(4);
+    let total = records.len();
+    tokio::task::spawn_blocking(move || {
+        let error_tx = tx.clone();
+        match write_export(&records, ChannelWriter::new(tx)) {
+            Ok(exported) => tracing::info!(exported, total, "📦 Exportación completada"),
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                tracing::warn!("⚠️ Exportación cancelada por el cliente");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "❌ Error generando exportación");
+                // El cliente ve la descarga cortada en lugar de un tar truncado "válido"
+                let _ = error_tx.blocking_send(Err(e));
+            }
+        }
+    });
+
+    let filename = format!("sentinel_export_{}.tar", chrono::Utc::now().timestamp());
+    let headers = [
+        (header::CONTENT_TYPE, "application/x-tar".to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+    ];
+    Ok((headers, Body::from_stream(ReceiverStream::new(rx))).into_response())
+}
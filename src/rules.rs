@@ -0,0 +1,150 @@
+use crate::{
+    schedule::CronSchedule,
+    state::RemoteConfig,
+    thresholds::Severity,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// --- REGLAS DE ALERTA DEL OPERADOR ---
+// Además de la escala de umbrales, el operador puede definir reglas (/api/rules): una
+// lista de condiciones que deben cumplirse todas sobre la captura recién subida. Cada
+// regla que se cumple puede subir la severidad de la alerta y añadir canales de
+// notificación; una regla puede crear la alerta aunque no se alcance ningún umbral.
+//
+// max_temp es la máxima calibrada, sin el desplazamiento de perfiles ni la compensación
+// ambiental de los umbrales. La línea base es la media de las máximas del historial de
+// la misma turbina, cámara y ángulo (la del z-score de anomalía).
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Comparison {
+    fn holds(self, actual: f32, value: f32) -> bool {
+        match self {
+            Comparison::Gt => actual > value,
+            Comparison::Gte => actual >= value,
+            Comparison::Lt => actual < value,
+            Comparison::Lte => actual <= value,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum RuleCondition {
+    MaxTemp { op: Comparison, value: f32 },
+    // max_temp menos la línea base (no se cumple sin historial suficiente)
+    DeltaOverBaseline { op: Comparison, value: f32 },
+    // Z-score de anomalía (no se cumple sin historial suficiente)
+    AnomalyScore { op: Comparison, value: f32 },
+    // Ángulo dentro de una zona de umbrales de la configuración (por nombre)
+    AngleZone { zone: String },
+    // Instante de la captura dentro de un horario cron (UTC, como los perfiles)
+    TimeOfDay { schedule: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Todas deben cumplirse
+    pub conditions: Vec<RuleCondition>,
+    pub severity: Severity,
+    // Nombres de canales de notificación (ver RemoteConfig::channels)
+    #[serde(default)]
+    pub channels: Vec<String>,
+    pub created: u64,
+    pub updated: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+// Datos de la captura sobre los que se evalúan las reglas
+pub struct RuleFacts<'a> {
+    pub max_temp: f32,
+    pub baseline: Option<f32>,
+    pub anomaly_score: Option<f32>,
+    pub angle: f32,
+    pub at: DateTime<Utc>,
+    pub config: &'a RemoteConfig,
+}
+
+impl RuleCondition {
+    fn matches(&self, facts: &RuleFacts) -> bool {
+        match self {
+            RuleCondition::MaxTemp { op, value } => op.holds(facts.max_temp, *value),
+            RuleCondition::DeltaOverBaseline { op, value } => {
+                facts.baseline.is_some_and(|baseline| op.holds(facts.max_temp - baseline, *value))
+            }
+            RuleCondition::AnomalyScore { op, value } => facts.anomaly_score.is_some_and(|score| op.holds(score, *value)),
+            RuleCondition::AngleZone { zone } => {
+                facts.config.zones.iter().any(|z| z.name == *zone && z.contains(facts.angle))
+            }
+            RuleCondition::TimeOfDay { schedule } => {
+                schedule.parse::<CronSchedule>().is_ok_and(|s| s.matches(&facts.at))
+            }
+        }
+    }
+
+    fn validate(&self, config: &RemoteConfig) -> Result<(), String> {
+        match self {
+            RuleCondition::MaxTemp { value, .. }
+            | RuleCondition::DeltaOverBaseline { value, .. }
+            | RuleCondition::AnomalyScore { value, .. } => {
+                if !value.is_finite() {
+                    return Err("condition value must be a finite number".into());
+                }
+            }
+            RuleCondition::AngleZone { zone } => {
+                if !config.zones.iter().any(|z| z.name == *zone) {
+                    return Err(format!("unknown zone '{}'", zone));
+                }
+            }
+            RuleCondition::TimeOfDay { schedule } => {
+                schedule.parse::<CronSchedule>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AlertRule {
+    pub fn matches(&self, facts: &RuleFacts) -> bool {
+        self.enabled && self.conditions.iter().all(|c| c.matches(facts))
+    }
+
+    // Comprobación al crear o editar la regla, contra la configuración vigente
+    pub fn validate(&self, config: &RemoteConfig) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name must not be empty".into());
+        }
+        if self.conditions.is_empty() {
+            return Err(format!("rule '{}': at least one condition is required", self.name));
+        }
+        for condition in &self.conditions {
+            condition.validate(config).map_err(|e| format!("rule '{}': {}", self.name, e))?;
+        }
+        if let Some(missing) = self.channels.iter().find(|c| !config.channels.iter().any(|ch| ch.name == **c)) {
+            return Err(format!("rule '{}': unknown channel '{}'", self.name, missing));
+        }
+        Ok(())
+    }
+}
+
+// Reglas que se cumplen para una captura, en el orden en que están definidas
+pub fn matching_rules(rules: &[AlertRule], facts: &RuleFacts) -> Vec<AlertRule> {
+    rules.iter().filter(|r| r.matches(facts)).cloned().collect()
+}
@@ -0,0 +1,55 @@
+use crate::{
+    state::AppState,
+    thresholds::Severity,
+};
+
+// --- FOTO DE LA FLOTA PARA LA PLANTA ---
+// Valores clave de cada turbina (en línea, temperatura actual y alertas abiertas) tal
+// como los consumen los sistemas de planta que no hablan REST; hoy, el mapa de registros
+// Modbus (ver modbus.rs).
+
+// Una alerta cuenta como abierta durante este tiempo tras dispararse, salvo que se
+// resuelva antes (ver crate::resolution)
+pub const OPEN_ALERT_WINDOW_SEC: u64 = 24 * 3600;
+
+// Estado de una turbina tal como lo ven la planta y sus PLC
+pub struct TurbineSnapshot {
+    pub token: String,
+    pub online: bool,
+    // Del último heartbeat (None si nunca ha reportado)
+    pub current_max_temp: Option<f32>,
+    pub last_update: Option<u64>,
+    pub open_alerts: u32,
+    // Severidad más alta entre las alertas abiertas
+    pub open_severity: Option<Severity>,
+}
+
+// Turbinas conocidas (registradas o que han enviado heartbeat) ordenadas por token
+pub async fn fleet_snapshot(state: &AppState) -> Vec<TurbineSnapshot> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let statuses = state.turbine_status.read().await.clone();
+    let timeouts = state.offline_timeouts().await;
+    let mut tokens: Vec<String> = state.turbines.read().await.iter().map(|t| t.token.clone()).collect();
+    tokens.extend(statuses.keys().cloned());
+    tokens.sort();
+    tokens.dedup();
+
+    let alerts = state.alerts.read().await;
+    tokens.into_iter()
+        .map(|token| {
+            let live = statuses.get(&token);
+            let open: Vec<Severity> = alerts.iter()
+                .filter(|a| a.turbine_token == token && a.is_open(now))
+                .map(|a| a.severity)
+                .collect();
+            TurbineSnapshot {
+                online: live.is_some_and(|l| timeouts.is_online(&token, l.last_update, now)),
+                current_max_temp: live.map(|l| l.current_max_temp),
+                last_update: live.map(|l| l.last_update),
+                open_alerts: open.len() as u32,
+                open_severity: open.into_iter().max(),
+                token,
+            }
+        })
+        .collect()
+}
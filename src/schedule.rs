@@ -0,0 +1,203 @@
+use crate::state::RemoteConfig;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// --- HORARIOS TIPO CRON ---
+// Expresión de 5 campos "minuto hora día-del-mes mes día-de-la-semana" con soporte
+// para `*`, valores, rangos `a-b`, listas `a,b` y pasos `*/n` o `a-b/n`.
+// Se usa como ventana: un instante "coincide" si cumple los cinco campos, p. ej.
+// "* 22-23,0-5 * * *" es toda la noche y "* 11-15 * 6-8 *" el mediodía de verano.
+// Se evalúa en UTC (las horas de silencio de los canales pueden llevar su propia zona).
+// El día de la semana va de 0 (domingo) a 6; 7 también es domingo.
+// Aquí vive también el parser de duraciones ("24h") que usan los parámetros de consulta.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+}
+
+// Convierte un campo en una máscara de bits con los valores permitidos
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step in '{}'", part))?;
+                if step == 0 {
+                    return Err(format!("step must be positive in '{}'", part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let a = a.parse().map_err(|_| format!("invalid value in '{}'", part))?;
+            let b = b.parse().map_err(|_| format!("invalid value in '{}'", part))?;
+            (a, b)
+        } else {
+            let value = range.parse().map_err(|_| format!("invalid value in '{}'", part))?;
+            (value, value)
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("'{}' out of range {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(format!("expected 5 fields in schedule '{}'", s));
+        };
+        let mut days_of_week = parse_field(days_of_week, 0, 7)?;
+        // 7 es otra forma de escribir domingo
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+        Ok(CronSchedule {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week,
+        })
+    }
+}
+
+// Duración con sufijo de unidad ("90s", "15m", "24h", "7d"); sin sufijo son segundos
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => s.split_at(pos),
+        None => (s, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("invalid duration unit in '{}'", s)),
+    };
+    let value: u64 = value.parse().map_err(|_| format!("invalid duration '{}'", s))?;
+    value.checked_mul(multiplier).ok_or_else(|| format!("duration '{}' too large", s))
+}
+
+// Instante absoluto: segundos Unix o RFC 3339 ("2024-01-31T15:45:00Z")
+pub fn parse_timestamp(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if let Ok(seconds) = s.parse::<u64>() {
+        return Ok(seconds);
+    }
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .and_then(|t| u64::try_from(t.timestamp()).ok())
+        .ok_or_else(|| format!("invalid timestamp '{}'", s))
+}
+
+// --- ZONA HORARIA DE LAS RESPUESTAS ---
+// Internamente todo son segundos Unix en UTC; la API los presenta además como ISO 8601
+// con el desplazamiento de la zona pedida (?tz=Europe/Madrid) o de display_timezone.
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim().parse::<Tz>().map_err(|_| format!("unknown timezone '{}'", name))
+}
+
+pub fn format_timestamp(timestamp: u64, tz: Tz) -> String {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|t| t.with_timezone(&tz).to_rfc3339())
+        .unwrap_or_default()
+}
+
+impl CronSchedule {
+    // Acepta cualquier instante con fecha y hora: en UTC o ya pasado a otra zona
+    pub fn matches(&self, at: &(impl Datelike + Timelike)) -> bool {
+        let has = |mask: u64, value: u32| mask & (1 << value) != 0;
+        has(self.minutes, at.minute())
+            && has(self.hours, at.hour())
+            && has(self.days_of_month, at.day())
+            && has(self.months, at.month())
+            && has(self.days_of_week, at.weekday().num_days_from_sunday())
+    }
+}
+
+// --- PLANIFICACIÓN DE ESCANEOS ---
+// Cadencia de escaneo gestionada desde el servidor: cada planificación es una ventana cron
+// con su intervalo entre escaneos, para todas las turbinas o para una lista. Una turbina
+// con planificaciones solo escanea mientras alguna de sus ventanas está abierta (gana la
+// primera de la lista); fuera de ellas recibe system_enabled = false en su heartbeat.
+// Las turbinas sin planificación siguen con scan_wait_time_sec.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScanSchedule {
+    pub name: String,
+    // Turbinas a las que aplica; vacío = todas
+    #[serde(default)]
+    pub turbines: Vec<String>,
+    // Ventana cron durante la que se escanea ("* 6-20 * * *")
+    pub window: String,
+    // Espera entre escaneos dentro de la ventana (None = scan_wait_time_sec)
+    #[serde(default)]
+    pub interval_sec: Option<u64>,
+}
+
+impl ScanSchedule {
+    pub fn applies_to(&self, turbine_token: &str) -> bool {
+        self.turbines.is_empty() || self.turbines.iter().any(|t| t == turbine_token)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.window.parse::<CronSchedule>().map_err(|e| format!("scan schedule '{}': {}", self.name, e))?;
+        if self.interval_sec == Some(0) {
+            return Err(format!("scan schedule '{}': interval_sec must be positive", self.name));
+        }
+        Ok(())
+    }
+}
+
+pub enum ScanWindow<'a> {
+    // La turbina no tiene planificaciones
+    Unscheduled,
+    Open(&'a ScanSchedule),
+    Closed,
+}
+
+impl RemoteConfig {
+    pub fn scan_window(&self, turbine_token: &str, at: &DateTime<Utc>) -> ScanWindow<'_> {
+        let mut schedules = self.scan_schedules.iter().filter(|s| s.applies_to(turbine_token)).peekable();
+        if schedules.peek().is_none() {
+            return ScanWindow::Unscheduled;
+        }
+        schedules
+            .find(|s| s.window.parse::<CronSchedule>().is_ok_and(|w| w.matches(at)))
+            .map_or(ScanWindow::Closed, ScanWindow::Open)
+    }
+
+    // Aplica la ventana de escaneo vigente a la configuración de una turbina
+    pub fn scheduled_for(mut self, turbine_token: &str, at: &DateTime<Utc>) -> RemoteConfig {
+        match self.scan_window(turbine_token, at) {
+            ScanWindow::Unscheduled => {}
+            ScanWindow::Open(schedule) => {
+                if let Some(interval) = schedule.interval_sec {
+                    self.scan_wait_time_sec = interval;
+                }
+            }
+            ScanWindow::Closed => self.system_enabled = false,
+        }
+        self
+    }
+}
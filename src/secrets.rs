@@ -0,0 +1,322 @@
+use crate::{
+    notify::ChannelKind,
+    settings::ServerSettings,
+    state::RemoteConfig,
+    storage::{
+        catalog::sha256_hex,
+        config_history::{config_diff, rewrite_config_history},
+        deliveries::save_failed_deliveries,
+        PersistedData,
+    },
+};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{collections::HashMap, path::Path};
+
+// --- SECRETOS CIFRADOS EN REPOSO ---
+// La API key de Gemini, las URLs de los webhooks (que suelen llevar el token en la
+// propia URL) y sus claves de firma, y la credencial de Jira o ServiceNow se guardan cifradas en el historial
+// de configuración y en las entregas fallidas. Cifrado de sobre: cada secreto se cifra
+// con una clave de datos aleatoria (AES-256-GCM) y esa clave se cifra con la clave maestra, que llega por entorno
+// (SENTINEL_MASTER_KEY) o en un fichero montado por el KMS o el gestor de secretos
+// (SENTINEL_MASTER_KEY_FILE) y nunca se escribe en disco. El texto plano solo existe en
+// memoria, justo antes de usar la integración.
+//
+// Formato: "enc:v1:<id de clave>:<nonce|clave de datos cifrada>:<nonce|secreto cifrado>",
+// en base64. Sin clave maestra los secretos se guardan en claro, como antes.
+
+const PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug)]
+pub enum SecretError {
+    // Hay un secreto cifrado pero el servidor no tiene clave maestra
+    NoMasterKey,
+    // Cifrado con otra clave maestra
+    WrongKey(String),
+    Malformed,
+    // AES-GCM no pudo cifrar el secreto
+    Encryption,
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::NoMasterKey => write!(f, "secret is encrypted but no master key is configured"),
+            SecretError::WrongKey(kid) => write!(f, "secret was encrypted with another master key ({})", kid),
+            SecretError::Malformed => write!(f, "malformed encrypted secret"),
+            SecretError::Encryption => write!(f, "could not encrypt the secret"),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+pub struct SecretVault {
+    master: Option<Aes256Gcm>,
+    // Huella de la clave maestra, para reconocer secretos cifrados con otra
+    key_id: String,
+}
+
+impl SecretVault {
+    pub fn disabled() -> Self {
+        SecretVault { master: None, key_id: String::new() }
+    }
+
+    pub fn from_settings(settings: &ServerSettings) -> Result<Self, String> {
+        let Some(key) = load_key(settings.master_key.as_deref(), settings.master_key_file.as_deref())? else {
+            return Ok(Self::disabled());
+        };
+        Ok(SecretVault {
+            master: Some(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))),
+            key_id: sha256_hex(&key)[..8].to_string(),
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.master.is_some()
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    // Cifra un secreto; vacío, ya cifrado o sin clave maestra se devuelve tal cual
+    pub fn seal(&self, plaintext: &str) -> Result<String, SecretError> {
+        let Some(master) = &self.master else { return Ok(plaintext.to_string()) };
+        if plaintext.is_empty() || is_sealed(plaintext) {
+            return Ok(plaintext.to_string());
+        }
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let (key_nonce, value_nonce) = (Aes256Gcm::generate_nonce(OsRng), Aes256Gcm::generate_nonce(OsRng));
+        let wrapped = master.encrypt(&key_nonce, data_key.as_slice()).map_err(|_| SecretError::Encryption)?;
+        let ciphertext = Aes256Gcm::new(&data_key).encrypt(&value_nonce, plaintext.as_bytes()).map_err(|_| SecretError::Encryption)?;
+        let join = |nonce: &[u8], data: &[u8]| STANDARD.encode([nonce, data].concat());
+        Ok(format!("{}{}:{}:{}", PREFIX, self.key_id, join(&key_nonce, &wrapped), join(&value_nonce, &ciphertext)))
+    }
+
+    // Descifra un secreto; los que están en claro se devuelven tal cual
+    pub fn reveal(&self, value: &str) -> Result<String, SecretError> {
+        let Some(rest) = value.strip_prefix(PREFIX) else { return Ok(value.to_string()) };
+        let master = self.master.as_ref().ok_or(SecretError::NoMasterKey)?;
+        let mut parts = rest.split(':');
+        let (Some(kid), Some(wrapped), Some(ciphertext), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(SecretError::Malformed);
+        };
+        if kid != self.key_id {
+            return Err(SecretError::WrongKey(kid.to_string()));
+        }
+        let open = |cipher: &Aes256Gcm, encoded: &str| -> Result<Vec<u8>, SecretError> {
+            let data = STANDARD.decode(encoded).map_err(|_| SecretError::Malformed)?;
+            if data.len() <= NONCE_LEN {
+                return Err(SecretError::Malformed);
+            }
+            let (nonce, data) = data.split_at(NONCE_LEN);
+            cipher.decrypt(Nonce::from_slice(nonce), data).map_err(|_| SecretError::Malformed)
+        };
+        let data_key = open(master, wrapped)?;
+        if data_key.len() != 32 {
+            return Err(SecretError::Malformed);
+        }
+        let plaintext = open(&Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key)), ciphertext)?;
+        String::from_utf8(plaintext).map_err(|_| SecretError::Malformed)
+    }
+
+    // Cifra los secretos en claro de una configuración. `known` relaciona textos planos
+    // con su versión cifrada, para que un secreto que no cambia conserve el mismo cifrado
+    // (y no aparezca como cambio en el historial)
+    pub fn seal_config(&self, config: &mut RemoteConfig, known: &mut HashMap<String, String>) -> Result<(), SecretError> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        for secret in config_secrets(config) {
+            if secret.is_empty() || is_sealed(secret) {
+                continue;
+            }
+            let sealed = match known.get(secret.as_str()) {
+                Some(sealed) => sealed.clone(),
+                None => self.seal(secret)?,
+            };
+            known.insert(secret.clone(), sealed.clone());
+            *secret = sealed;
+        }
+        Ok(())
+    }
+
+    // Configuración con todos los secretos en claro (solo para el endpoint de administración)
+    pub fn reveal_config(&self, config: &RemoteConfig) -> Result<RemoteConfig, SecretError> {
+        let mut config = config.clone();
+        for secret in config_secrets(&mut config) {
+            *secret = self.reveal(secret)?;
+        }
+        Ok(config)
+    }
+
+    // Textos planos de los secretos cifrados de una configuración (para `seal_config`)
+    pub fn known_secrets(&self, config: &RemoteConfig) -> HashMap<String, String> {
+        let mut config = config.clone();
+        config_secrets(&mut config).into_iter()
+            .filter(|secret| is_sealed(secret))
+            .filter_map(|sealed| self.reveal(sealed).ok().map(|plain| (plain, sealed.clone())))
+            .collect()
+    }
+}
+
+fn decode_key(text: &str) -> Result<Vec<u8>, String> {
+    let key = STANDARD.decode(text.trim()).map_err(|e| format!("invalid base64 key: {}", e))?;
+    if key.len() != 32 {
+        return Err(format!("key must be 32 bytes, got {}", key.len()));
+    }
+    Ok(key)
+}
+
+// Clave de 32 bytes en base64 (entorno) o en un fichero (binaria o base64); None sin
+// ninguna de las dos. La usan la clave maestra y la de las capturas (storage::crypto)
+pub fn load_key(encoded: Option<&str>, file: Option<&Path>) -> Result<Option<Vec<u8>>, String> {
+    match (encoded, file) {
+        (Some(encoded), _) => decode_key(encoded).map(Some),
+        (None, Some(path)) => {
+            let raw = std::fs::read(path).map_err(|e| format!("cannot read key file {}: {}", path.display(), e))?;
+            match raw.len() {
+                32 => Ok(Some(raw)),
+                _ => decode_key(std::str::from_utf8(&raw).map_err(|_| "key file must hold 32 bytes or base64")?).map(Some),
+            }
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+// Campos secretos de la configuración: la API key, las URLs y las claves de firma de los
+// webhooks y la credencial del sistema de órdenes de trabajo
+pub fn config_secrets(config: &mut RemoteConfig) -> Vec<&mut String> {
+    let mut secrets: Vec<&mut String> = config.gemini_api_key.iter_mut().collect();
+    for channel in &mut config.channels {
+        if let ChannelKind::Webhook { url, secret } = &mut channel.kind {
+            secrets.push(url);
+            secrets.extend(secret.as_mut());
+        }
+    }
+    if let Some(work_orders) = &mut config.work_orders {
+        secrets.push(work_orders.secret_mut());
+    }
+    secrets
+}
+
+// --- REDACCIÓN EN LOS ENDPOINTS DE LECTURA ---
+// La configuración que se devuelve al dashboard lleva este marcador en lugar de cada
+// secreto. Al guardar, un secreto con el marcador significa "conservar el actual", así
+// que el dashboard puede reenviar la configuración leída sin borrar la API key.
+pub const REDACTED: &str = "***";
+
+pub fn redact_config(config: &RemoteConfig) -> RemoteConfig {
+    let mut config = config.clone();
+    for secret in config_secrets(&mut config).into_iter().filter(|s| !s.is_empty()) {
+        *secret = REDACTED.to_string();
+    }
+    config
+}
+
+// Sustituye los marcadores de una configuración recibida por los valores actuales: la API
+// key tal cual y la URL y la clave de firma de cada webhook por las del canal con el
+// mismo nombre
+pub fn restore_redacted(config: &mut RemoteConfig, current: &RemoteConfig) -> Result<(), String> {
+    if config.gemini_api_key.as_deref() == Some(REDACTED) {
+        config.gemini_api_key = current.gemini_api_key.clone();
+    }
+    if let Some(work_orders) = &mut config.work_orders
+        && *work_orders.secret_mut() == REDACTED
+    {
+        let mut previous = current.work_orders.clone()
+            .filter(|c| std::mem::discriminant(&c.system) == std::mem::discriminant(&work_orders.system))
+            .ok_or("work_orders: no current credential to keep")?;
+        *work_orders.secret_mut() = std::mem::take(previous.secret_mut());
+    }
+    for channel in &mut config.channels {
+        let ChannelKind::Webhook { url, secret } = &mut channel.kind else { continue };
+        let previous = current.channels.iter()
+            .filter(|c| c.name == channel.name)
+            .find_map(|c| match &c.kind {
+                ChannelKind::Webhook { url, secret } => Some((url.clone(), secret.clone())),
+                ChannelKind::Log => None,
+            });
+        if url == REDACTED {
+            *url = previous.as_ref()
+                .map(|(url, _)| url.clone())
+                .ok_or_else(|| format!("channel '{}': no current webhook URL to keep", channel.name))?;
+        }
+        if let Some(secret) = secret.as_mut().filter(|s| s.as_str() == REDACTED) {
+            *secret = previous.and_then(|(_, secret)| secret)
+                .ok_or_else(|| format!("channel '{}': no current webhook secret to keep", channel.name))?;
+        }
+    }
+    Ok(())
+}
+
+// --- MIGRACIÓN AL ARRANCAR ---
+// Con clave maestra, los secretos que sigan en claro en el historial de configuración o
+// en las entregas fallidas se cifran y se reescriben los ficheros. Las diferencias de
+// cada versión se recalculan para que no quede ningún secreto en claro en ellas.
+pub fn seal_stored_secrets(vault: &SecretVault, data: &mut PersistedData) {
+    if !vault.enabled() {
+        if data.config_history.last().is_some_and(|v| config_secrets(&mut v.config.clone()).iter().any(|s| is_sealed(s))) {
+            tracing::warn!("⚠️ La configuración tiene secretos cifrados pero no hay clave maestra; las integraciones no podrán usarlos");
+        }
+        return;
+    }
+
+    let mut known = HashMap::new();
+    let mut sealed = 0;
+    for version in &mut data.config_history {
+        let before = config_secrets(&mut version.config).into_iter().filter(|s| !s.is_empty() && !is_sealed(s)).count();
+        if let Err(e) = vault.seal_config(&mut version.config, &mut known) {
+            tracing::error!(error = %e, version = version.version, "❌ No se pudieron cifrar los secretos del historial de configuración");
+            return;
+        }
+        sealed += before;
+    }
+    if sealed > 0 {
+        let mut previous = RemoteConfig::default();
+        for version in &mut data.config_history {
+            version.changes = config_diff(&previous, &version.config);
+            previous = version.config.clone();
+        }
+        match rewrite_config_history(&data.config_history) {
+            Ok(()) => tracing::info!(secrets = sealed, key_id = vault.key_id(), "🔐 Secretos del historial de configuración cifrados"),
+            Err(e) => tracing::error!(error = %e, "❌ Error reescribiendo el historial de configuración cifrado"),
+        }
+    }
+
+    let mut deliveries = 0;
+    for delivery in data.failed_deliveries.iter_mut().filter(|d| !is_sealed(&d.url)) {
+        let sealed = match known.get(&delivery.url) {
+            Some(sealed) => sealed.clone(),
+            None => match vault.seal(&delivery.url) {
+                Ok(sealed) => sealed,
+                Err(e) => {
+                    tracing::error!(error = %e, "❌ No se pudo cifrar la URL de una entrega fallida");
+                    continue;
+                }
+            },
+        };
+        delivery.url = sealed;
+        deliveries += 1;
+    }
+    if deliveries > 0 {
+        save_failed_deliveries(&data.failed_deliveries);
+        tracing::info!(deliveries, "🔐 URLs de entregas fallidas cifradas");
+    }
+
+    if let Some(latest) = data.config_history.last() {
+        let mut config = latest.config.clone();
+        if let Some(e) = config_secrets(&mut config).into_iter().find_map(|s| vault.reveal(s).err()) {
+            tracing::error!(error = %e, "❌ Hay secretos de la configuración que no se pueden descifrar con esta clave maestra");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::settings::ServerSettings;
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+};
+use tokio::task::JoinSet;
+
+// --- LISTENERS ---
+// El servidor puede escuchar en varias direcciones a la vez (IPv4 o IPv6), cada una
+// en HTTP o HTTPS, p. ej. HTTP en localhost para el proxy y HTTPS hacia fuera:
+//   --listen 127.0.0.1:8080 --listen https://[::]:8443
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListenAddr {
+    pub addr: SocketAddr,
+    pub tls: bool,
+}
+
+impl FromStr for ListenAddr {
+    type Err = String;
+
+    // Acepta "ip:puerto", "[ipv6]:puerto" y opcionalmente el prefijo http:// o https://
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tls, rest) = if let Some(rest) = s.strip_prefix("https://") {
+            (true, rest)
+        } else {
+            (false, s.strip_prefix("http://").unwrap_or(s))
+        };
+        let addr = rest.trim_end_matches('/').parse::<SocketAddr>()
+            .map_err(|e| format!("invalid listen address '{}': {}", s, e))?;
+        Ok(ListenAddr { addr, tls })
+    }
+}
+
+impl std::fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}://{}", if self.tls { "https" } else { "http" }, self.addr)
+    }
+}
+
+impl ListenAddr {
+    // URL para conectarse a este listener desde la propia máquina
+    pub fn local_url(&self) -> String {
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        ListenAddr { addr, tls: self.tls }.to_string()
+    }
+}
+
+// Levanta todos los listeners configurados y espera hasta que alguno termine con error
+pub async fn serve(app: Router, settings: &ServerSettings) -> io::Result<()> {
+    let tls_config = if settings.listen.iter().any(|l| l.tls) {
+        let (Some(cert), Some(key)) = (&settings.tls_cert, &settings.tls_key) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "https listeners require --tls-cert and --tls-key",
+            ));
+        };
+        Some(RustlsConfig::from_pem_file(cert, key).await?)
+    } else {
+        None
+    };
+
+    let mut servers = JoinSet::new();
+    for listen in &settings.listen {
+        let app = app.clone();
+        match &tls_config {
+            Some(config) if listen.tls => {
+                let server = axum_server::bind_rustls(listen.addr, config.clone());
+                servers.spawn(async move { server.serve(app.into_make_service()).await });
+            }
+            _ => {
+                let listener = tokio::net::TcpListener::bind(listen.addr).await?;
+                servers.spawn(async move { axum::serve(listener, app).await });
+            }
+        }
+        tracing::info!(%listen, "☁️ GSU Sentinel Cloud escuchando");
+    }
+
+    while let Some(result) = servers.join_next().await {
+        result.map_err(io::Error::other)??;
+    }
+    Ok(())
+}
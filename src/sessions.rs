@@ -0,0 +1,548 @@
+use crate::{
+    i18n::Localized,
+    notify::{raise_device_alert, DeviceAlert},
+    state::AppState,
+    storage::{
+        catalog::CaptureRecord,
+        quality::{record_quality_event, QualityEventKind},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+// --- COMPARACIÓN DE SESIONES DE ESCANEO ---
+// Una sesión son las capturas consecutivas de una turbina sin pausas mayores que
+// SESSION_GAP_SEC. Para verificar una reparación se comparan dos sesiones (antes y
+// después) emparejando las capturas de la misma cámara por ángulo, con la tolerancia
+// indicada, y calculando la diferencia de temperaturas (b − a) en cada ángulo.
+//
+// La cobertura de una sesión compara los ángulos recibidos de una cámara con el barrido
+// completo que implica pan_step_degrees: un salto entre ángulos consecutivos mayor que
+// el paso (con margen) es un hueco sin ver, p. ej. por un motor de giro atascado.
+//
+// Si el robot sube una pila de frames con un ángulo por frame (arrays "angles" del npz),
+// cada frame cuenta como un ángulo propio en vez de asumir un ángulo por archivo.
+
+// Una pausa mayor entre capturas de la misma turbina cierra la sesión de escaneo
+pub const SESSION_GAP_SEC: u64 = 1800;
+// Ángulos que se destacan en el resumen como los de mayor subida
+const MAX_LARGEST_INCREASES: usize = 10;
+// Un salto de más de este múltiplo del paso de giro cuenta como hueco
+const GAP_STEP_FACTOR: f32 = 1.5;
+
+fn default_min_coverage() -> f32 {
+    0.95
+}
+
+fn default_alert() -> bool {
+    true
+}
+
+// Verificación de la cobertura de cada barrido al recibir las capturas (None = desactivada)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CoverageCheck {
+    // Fracción mínima de los 360° vista en un barrido
+    #[serde(default = "default_min_coverage")]
+    pub min_coverage: f32,
+    // Además del evento de calidad, aviso a device_alert_channels
+    #[serde(default = "default_alert")]
+    pub alert: bool,
+}
+
+impl CoverageCheck {
+    pub fn validate(&self, pan_step_degrees: f32) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.min_coverage) {
+            return Err("scan_coverage: min_coverage must be between 0 and 1".into());
+        }
+        if pan_step_degrees.is_nan() || pan_step_degrees <= 0.0 || pan_step_degrees > 360.0 {
+            return Err("scan_coverage: pan_step_degrees must be within (0, 360]".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionInfo {
+    pub turbine_token: String,
+    pub start: u64,
+    pub end: u64,
+    pub capture_count: usize,
+    // Capturas sin ángulo o sin estadísticas, que no se pueden emparejar
+    pub without_angle: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AngleDelta {
+    pub camera_id: Option<String>,
+    pub angle_a: f32,
+    pub angle_b: f32,
+    pub filename_a: String,
+    pub filename_b: String,
+    pub max_temp_a: f32,
+    pub max_temp_b: f32,
+    // b − a
+    pub max_delta: f32,
+    pub avg_delta: Option<f32>,
+    // Frame dentro de la captura, si el ángulo viene de una pila con ángulo por frame
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_a: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_b: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SessionComparison {
+    pub a: SessionInfo,
+    pub b: SessionInfo,
+    pub angle_tolerance: f32,
+    pub matched: usize,
+    // Ángulos de una sesión sin pareja en la otra
+    pub unmatched_a: Vec<f32>,
+    pub unmatched_b: Vec<f32>,
+    pub mean_max_delta: Option<f32>,
+    // Ángulos con mayor subida de la máxima, de mayor a menor (solo subidas)
+    pub largest_increases: Vec<AngleDelta>,
+    // Todos los ángulos emparejados, por cámara y ángulo
+    pub deltas: Vec<AngleDelta>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CoverageGap {
+    // Últimos ángulos vistos a cada lado del hueco (en sentido de giro)
+    pub from: f32,
+    pub to: f32,
+    pub missing_degrees: f32,
+}
+
+#[derive(Serialize)]
+pub struct SessionCoverage {
+    pub session: SessionInfo,
+    pub camera_id: Option<String>,
+    pub pan_step_degrees: f32,
+    // Ángulos de un barrido completo y ángulos distintos recibidos
+    pub expected_angles: usize,
+    pub received_angles: usize,
+    // Fracción de los 360° vista (0..1)
+    pub coverage: f32,
+    pub complete: bool,
+    // La sesión sigue abierta y aún no suma un barrido completo: los huecos pueden llenarse
+    pub in_progress: bool,
+    pub gaps: Vec<CoverageGap>,
+}
+
+// Capturas de la sesión a la que pertenece `filename`, en orden cronológico
+pub fn session_of(catalog: &[CaptureRecord], filename: &str) -> Option<Vec<CaptureRecord>> {
+    let anchor = catalog.iter().find(|r| r.filename == filename)?;
+    let mut captures: Vec<&CaptureRecord> = catalog.iter().filter(|r| r.turbine_token == anchor.turbine_token).collect();
+    captures.sort_by_key(|r| r.timestamp);
+    let position = captures.iter().position(|r| r.filename == filename)?;
+    let mut start = position;
+    while start > 0 && captures[start].timestamp <= captures[start - 1].timestamp.saturating_add(SESSION_GAP_SEC) {
+        start -= 1;
+    }
+    let mut end = position;
+    while end + 1 < captures.len() && captures[end + 1].timestamp <= captures[end].timestamp.saturating_add(SESSION_GAP_SEC) {
+        end += 1;
+    }
+    Some(captures[start..=end].iter().map(|r| (*r).clone()).collect())
+}
+
+pub fn session_info(captures: &[CaptureRecord], without_angle: usize) -> SessionInfo {
+    SessionInfo {
+        turbine_token: captures.first().map(|r| r.turbine_token.clone()).unwrap_or_default(),
+        start: captures.first().map_or(0, |r| r.timestamp),
+        end: captures.last().map_or(0, |r| r.timestamp),
+        capture_count: captures.len(),
+        without_angle,
+    }
+}
+
+// Ángulos de una captura: los de cada frame si los trae, si no el de la captura
+fn capture_angles(record: &CaptureRecord) -> Vec<f32> {
+    let frame_angles: Vec<f32> = record.frames.iter().filter_map(|p| p.angle).collect();
+    if frame_angles.is_empty() { record.angle.into_iter().collect() } else { frame_angles }
+}
+
+fn angle_count(captures: &[CaptureRecord]) -> usize {
+    captures.iter().map(|r| capture_angles(r).len()).sum()
+}
+
+pub fn without_angle(captures: &[CaptureRecord]) -> usize {
+    captures.iter().filter(|r| capture_angles(r).is_empty()).count()
+}
+
+// Un ángulo emparejable: una captura entera o un frame de una pila
+struct AnglePoint<'a> {
+    record: &'a CaptureRecord,
+    frame_index: Option<usize>,
+    angle: f32,
+    max_temp: f32,
+    avg_temp: Option<f32>,
+}
+
+// Puntos emparejables de una sesión: con ángulo y máxima. Si se repite un ángulo
+// (mismo valor y cámara), cuenta el punto más caliente.
+fn angle_points(captures: &[CaptureRecord]) -> (Vec<AnglePoint<'_>>, usize) {
+    let mut points: Vec<AnglePoint> = Vec::new();
+    let mut without_angle = 0;
+    for record in captures {
+        let candidates: Vec<AnglePoint> = if record.frames.iter().any(|p| p.angle.is_some()) {
+            record.frames.iter()
+                .filter_map(|p| p.angle.map(|angle| AnglePoint {
+                    record,
+                    frame_index: Some(p.frame_index),
+                    angle,
+                    max_temp: p.max_temp,
+                    avg_temp: Some(p.avg_temp),
+                }))
+                .collect()
+        } else if let (Some(angle), Some(max_temp)) = (record.angle, record.max_temp) {
+            vec![AnglePoint { record, frame_index: None, angle, max_temp, avg_temp: record.avg_temp }]
+        } else {
+            Vec::new()
+        };
+        if candidates.is_empty() {
+            without_angle += 1;
+        }
+        for candidate in candidates {
+            match points.iter_mut().find(|p| p.record.camera_id == record.camera_id && p.angle == candidate.angle) {
+                Some(point) if point.max_temp < candidate.max_temp => *point = candidate,
+                Some(_) => {}
+                None => points.push(candidate),
+            }
+        }
+    }
+    (points, without_angle)
+}
+
+pub fn compare_sessions(a: &[CaptureRecord], b: &[CaptureRecord], angle_tolerance: f32) -> SessionComparison {
+    let (points_a, without_a) = angle_points(a);
+    let (points_b, without_b) = angle_points(b);
+
+    // Emparejamiento voraz: primero las parejas más cercanas en ángulo
+    let mut candidates: Vec<(f32, usize, usize)> = Vec::new();
+    for (i, pa) in points_a.iter().enumerate() {
+        for (j, pb) in points_b.iter().enumerate() {
+            let distance = (pa.angle - pb.angle).abs();
+            if pa.record.camera_id == pb.record.camera_id && distance <= angle_tolerance {
+                candidates.push((distance, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let (mut used_a, mut used_b) = (vec![false; points_a.len()], vec![false; points_b.len()]);
+    let mut deltas = Vec::new();
+    for (_, i, j) in candidates {
+        if used_a[i] || used_b[j] {
+            continue;
+        }
+        used_a[i] = true;
+        used_b[j] = true;
+        let (pa, pb) = (&points_a[i], &points_b[j]);
+        deltas.push(AngleDelta {
+            camera_id: pa.record.camera_id.clone(),
+            angle_a: pa.angle,
+            angle_b: pb.angle,
+            filename_a: pa.record.filename.clone(),
+            filename_b: pb.record.filename.clone(),
+            max_temp_a: pa.max_temp,
+            max_temp_b: pb.max_temp,
+            max_delta: pb.max_temp - pa.max_temp,
+            avg_delta: pa.avg_temp.zip(pb.avg_temp).map(|(avg_a, avg_b)| avg_b - avg_a),
+            frame_a: pa.frame_index,
+            frame_b: pb.frame_index,
+        });
+    }
+    deltas.sort_by(|x, y| x.camera_id.cmp(&y.camera_id).then(x.angle_a.total_cmp(&y.angle_a)));
+
+    let unmatched = |points: &[AnglePoint], used: &[bool]| -> Vec<f32> {
+        let mut angles: Vec<f32> = points.iter().zip(used).filter(|(_, used)| !**used).map(|(p, _)| p.angle).collect();
+        angles.sort_by(f32::total_cmp);
+        angles
+    };
+    let mut largest_increases: Vec<AngleDelta> = deltas.iter().filter(|d| d.max_delta > 0.0).cloned().collect();
+    largest_increases.sort_by(|x, y| y.max_delta.total_cmp(&x.max_delta));
+    largest_increases.truncate(MAX_LARGEST_INCREASES);
+
+    SessionComparison {
+        a: session_info(a, without_a),
+        b: session_info(b, without_b),
+        angle_tolerance,
+        matched: deltas.len(),
+        unmatched_a: unmatched(&points_a, &used_a),
+        unmatched_b: unmatched(&points_b, &used_b),
+        mean_max_delta: (!deltas.is_empty()).then(|| deltas.iter().map(|d| d.max_delta).sum::<f32>() / deltas.len() as f32),
+        largest_increases,
+        deltas,
+    }
+}
+
+fn expected_angles(pan_step_degrees: f32) -> usize {
+    (360.0 / pan_step_degrees).ceil().max(1.0) as usize
+}
+
+// Huecos del barrido, recorriendo los ángulos distintos en círculo
+fn coverage_gaps(captures: &[CaptureRecord], pan_step_degrees: f32) -> (usize, Vec<CoverageGap>) {
+    let mut angles: Vec<f32> = captures.iter().flat_map(capture_angles).map(|a| a.rem_euclid(360.0)).collect();
+    angles.sort_by(f32::total_cmp);
+    angles.dedup_by(|a, b| (*a - *b).abs() < 0.01);
+    if angles.is_empty() {
+        return (0, vec![CoverageGap { from: 0.0, to: 360.0, missing_degrees: 360.0 }]);
+    }
+    let gaps = angles.iter().enumerate()
+        .filter_map(|(i, &from)| {
+            let to = angles.get(i + 1).copied().unwrap_or(angles[0] + 360.0);
+            (to - from > pan_step_degrees * GAP_STEP_FACTOR).then(|| CoverageGap {
+                from,
+                to: to.rem_euclid(360.0),
+                missing_degrees: to - from - pan_step_degrees,
+            })
+        })
+        .collect();
+    (angles.len(), gaps)
+}
+
+// Cobertura de las capturas de una cámara en una sesión
+pub fn session_coverage(captures: &[CaptureRecord], pan_step_degrees: f32, now: u64) -> SessionCoverage {
+    let (received_angles, gaps) = coverage_gaps(captures, pan_step_degrees);
+    let missing: f32 = gaps.iter().map(|g| g.missing_degrees).sum();
+    let expected_angles = expected_angles(pan_step_degrees);
+    let open = captures.last().is_some_and(|r| now <= r.timestamp.saturating_add(SESSION_GAP_SEC));
+    SessionCoverage {
+        session: session_info(captures, without_angle(captures)),
+        camera_id: captures.first().and_then(|r| r.camera_id.clone()),
+        pan_step_degrees,
+        expected_angles,
+        received_angles,
+        coverage: ((360.0 - missing) / 360.0).clamp(0.0, 1.0),
+        complete: gaps.is_empty(),
+        in_progress: open && angle_count(captures) < expected_angles,
+        gaps,
+    }
+}
+
+// Capturas de la misma cámara que `filename` en su sesión
+pub fn camera_session(catalog: &[CaptureRecord], filename: &str) -> Option<Vec<CaptureRecord>> {
+    let camera_id = catalog.iter().find(|r| r.filename == filename)?.camera_id.clone();
+    let mut session = session_of(catalog, filename)?;
+    session.retain(|r| r.camera_id == camera_id);
+    Some(session)
+}
+
+// Capturas finales de una sesión que suman al menos `angles` ángulos
+fn trailing_angles(session: &[CaptureRecord], angles: usize) -> Vec<CaptureRecord> {
+    let mut count = 0;
+    let start = session.iter().rposition(|r| {
+        count += capture_angles(r).len();
+        count >= angles
+    });
+    session[start.unwrap_or(0)..].to_vec()
+}
+
+// Al recibir una captura del robot se verifica el barrido que cierra: cada vez que la
+// sesión completa un barrido (pan_step_degrees implica cuántos ángulos son, contando
+// cada frame de una pila con ángulo por frame) y, al abrir una sesión nueva, el tramo
+// final de la anterior. Un barrido por debajo de min_coverage deja un evento de calidad
+// y, si se pide, un aviso de dispositivo.
+pub async fn check_scan_coverage(state: &AppState, turbine_token: &str, filename: &str) {
+    let config = state.config_for_turbine(turbine_token).await;
+    let Some(check) = config.scan_coverage.clone() else {
+        return;
+    };
+    let sweep_len = expected_angles(config.pan_step_degrees);
+    let mut sweeps = Vec::new();
+    {
+        let catalog = state.catalog.read().await;
+        // Las capturas de fuentes externas no forman parte del barrido del robot
+        let robot_session = |filename: &str| {
+            camera_session(&catalog, filename).map(|mut session| {
+                session.retain(|r| r.source.is_none());
+                session
+            })
+        };
+        let Some(session) = robot_session(filename).filter(|s| !s.is_empty()) else {
+            return;
+        };
+        if session.len() == 1 {
+            // Sesión nueva: se revisa lo que quedó sin revisar de la anterior
+            let first = &session[0];
+            let previous = catalog.iter()
+                .filter(|r| r.turbine_token == turbine_token && r.camera_id == first.camera_id && r.source.is_none())
+                .filter(|r| r.timestamp < first.timestamp)
+                .max_by_key(|r| r.timestamp)
+                .and_then(|r| robot_session(&r.filename));
+            if let Some(previous) = previous {
+                let remainder = angle_count(&previous) % sweep_len;
+                if remainder != 0 {
+                    sweeps.push(trailing_angles(&previous, remainder));
+                }
+            }
+        }
+        // La última captura (una pila puede traer varios ángulos) completa un barrido
+        let total = angle_count(&session);
+        let before = total - session.last().map_or(0, |r| capture_angles(r).len());
+        if total / sweep_len > before / sweep_len {
+            sweeps.push(trailing_angles(&session, sweep_len));
+        }
+    }
+    let now = chrono::Utc::now().timestamp() as u64;
+    for sweep in sweeps {
+        let coverage = session_coverage(&sweep, config.pan_step_degrees, now);
+        if coverage.coverage < check.min_coverage {
+            report_incomplete_scan(state, turbine_token, &check, coverage).await;
+        }
+    }
+}
+
+async fn report_incomplete_scan(state: &AppState, turbine_token: &str, check: &CoverageCheck, coverage: SessionCoverage) {
+    let camera_id = coverage.camera_id.clone();
+    tracing::warn!(
+        %turbine_token,
+        camera_id = camera_id.as_deref(),
+        coverage = coverage.coverage,
+        gaps = coverage.gaps.len(),
+        "🧭 Barrido incompleto: hay ángulos sin capturar"
+    );
+    record_quality_event(turbine_token, QualityEventKind::IncompleteScan {
+        session_start: coverage.session.start,
+        camera_id: camera_id.clone(),
+        coverage: coverage.coverage,
+        gaps: coverage.gaps.len(),
+    });
+    if check.alert {
+        let largest = coverage.gaps.iter().max_by(|a, b| a.missing_degrees.total_cmp(&b.missing_degrees));
+        let camera = camera_id.as_deref();
+        raise_device_alert(state, DeviceAlert::new(
+            turbine_token,
+            "incomplete_scan",
+            Localized::new(
+                format!(
+                    "Scan of {}{} covered {:.0}% of the rotation ({} gaps{}); check the pan motor",
+                    turbine_token,
+                    camera.map(|c| format!(" camera {}", c)).unwrap_or_default(),
+                    coverage.coverage * 100.0,
+                    coverage.gaps.len(),
+                    largest.map(|g| format!(", largest {:.1}°-{:.1}°", g.from, g.to)).unwrap_or_default(),
+                ),
+                format!(
+                    "El barrido de {}{} cubrió el {:.0}% de la rotación ({} huecos{}); revisa el motor de giro",
+                    turbine_token,
+                    camera.map(|c| format!(" cámara {}", c)).unwrap_or_default(),
+                    coverage.coverage * 100.0,
+                    coverage.gaps.len(),
+                    largest.map(|g| format!(", el mayor {:.1}°-{:.1}°", g.from, g.to)).unwrap_or_default(),
+                ),
+            ),
+        )).await;
+    }
+}
+
+// --- PROGRESO DEL BARRIDO EN CURSO ---
+// Mientras la sesión sigue abierta (última captura hace menos de SESSION_GAP_SEC), cuánto
+// lleva el barrido actual de cada cámara y cuándo se espera que termine: los ángulos que
+// faltan para el barrido completo de pan_step_degrees, a uno por scan_wait_time_sec
+// desde la última captura. Solo cuentan las capturas del robot.
+
+#[derive(Serialize)]
+pub struct CameraProgress {
+    pub camera_id: Option<String>,
+    pub captures: usize,
+    // Frames recibidos (un frame por ángulo en las pilas con ángulo por frame)
+    pub frames: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_angle: Option<f32>,
+    pub sweeps_completed: usize,
+    // Ángulos del barrido actual y los de un barrido completo
+    pub sweep_angles: usize,
+    pub expected_angles: usize,
+    // Fracción del barrido actual (0..1)
+    pub progress: f32,
+    pub estimated_completion: u64,
+}
+
+#[derive(Serialize)]
+pub struct SessionProgress {
+    pub turbine_token: String,
+    pub start: u64,
+    pub last_capture: u64,
+    pub elapsed_sec: u64,
+    pub captures: usize,
+    pub frames_received: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_angle: Option<f32>,
+    pub pan_step_degrees: f32,
+    pub scan_wait_time_sec: u64,
+    // La cámara que más tarde termine
+    pub estimated_completion: u64,
+    pub estimated_remaining_sec: u64,
+    pub cameras: Vec<CameraProgress>,
+}
+
+// Frames de una captura: uno por ángulo, o uno si no trae ángulo
+fn frame_count(record: &CaptureRecord) -> usize {
+    capture_angles(record).len().max(1)
+}
+
+fn last_angle(captures: &[CaptureRecord]) -> Option<f32> {
+    captures.iter().rev().find_map(|r| capture_angles(r).last().copied())
+}
+
+// Capturas del robot en la sesión abierta de la turbina; None si no hay ninguna en curso
+pub fn current_session(catalog: &[CaptureRecord], turbine_token: &str, now: u64) -> Option<Vec<CaptureRecord>> {
+    let latest = catalog.iter()
+        .filter(|r| r.turbine_token == turbine_token && r.source.is_none())
+        .max_by_key(|r| r.timestamp)
+        .filter(|r| now <= r.timestamp.saturating_add(SESSION_GAP_SEC))?;
+    let mut session = session_of(catalog, &latest.filename)?;
+    session.retain(|r| r.source.is_none());
+    Some(session)
+}
+
+pub fn session_progress(session: &[CaptureRecord], pan_step_degrees: f32, scan_wait_time_sec: u64, now: u64) -> SessionProgress {
+    let sweep_len = expected_angles(pan_step_degrees);
+    let mut by_camera: Vec<(Option<String>, Vec<CaptureRecord>)> = Vec::new();
+    for record in session {
+        match by_camera.iter_mut().find(|(camera_id, _)| *camera_id == record.camera_id) {
+            Some((_, captures)) => captures.push(record.clone()),
+            None => by_camera.push((record.camera_id.clone(), vec![record.clone()])),
+        }
+    }
+    let cameras: Vec<CameraProgress> = by_camera.into_iter()
+        .map(|(camera_id, captures)| {
+            let frames: usize = captures.iter().map(frame_count).sum();
+            // Un barrido recién completado se muestra entero hasta que llegue el siguiente ángulo
+            let sweep_angles = match frames % sweep_len {
+                0 => sweep_len,
+                partial => partial,
+            };
+            let last_capture = captures.last().map_or(0, |r| r.timestamp);
+            let remaining = (sweep_len - sweep_angles) as u64;
+            CameraProgress {
+                sweeps_completed: frames / sweep_len,
+                last_angle: last_angle(&captures),
+                captures: captures.len(),
+                frames,
+                sweep_angles,
+                expected_angles: sweep_len,
+                progress: sweep_angles as f32 / sweep_len as f32,
+                estimated_completion: last_capture.saturating_add(remaining.saturating_mul(scan_wait_time_sec)),
+                camera_id,
+            }
+        })
+        .collect();
+    let start = session.first().map_or(now, |r| r.timestamp);
+    let estimated_completion = cameras.iter().map(|c| c.estimated_completion).max().unwrap_or(now);
+    SessionProgress {
+        turbine_token: session.first().map(|r| r.turbine_token.clone()).unwrap_or_default(),
+        start,
+        last_capture: session.last().map_or(start, |r| r.timestamp),
+        elapsed_sec: now.saturating_sub(start),
+        captures: session.len(),
+        frames_received: session.iter().map(frame_count).sum(),
+        last_angle: last_angle(session),
+        pan_step_degrees,
+        scan_wait_time_sec,
+        estimated_completion,
+        estimated_remaining_sec: estimated_completion.saturating_sub(now),
+        cameras,
+    }
+}
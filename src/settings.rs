@@ -0,0 +1,195 @@
+use crate::{server::ListenAddr, telemetry::LogFormat};
+use clap::Parser;
+use std::{net::SocketAddr, path::PathBuf};
+
+// Parámetros del servidor (línea de comandos o variables de entorno)
+#[derive(Parser, Clone, Debug)]
+pub struct ServerSettings {
+    // Direcciones de escucha (repetible o separadas por comas), con prefijo https:// para TLS
+    #[arg(long, env = "SENTINEL_LISTEN", value_delimiter = ',', default_value = "0.0.0.0:8080")]
+    pub listen: Vec<ListenAddr>,
+    // Certificado y clave PEM para los listeners https://
+    #[arg(long, env = "SENTINEL_TLS_CERT")]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(long, env = "SENTINEL_TLS_KEY")]
+    pub tls_key: Option<PathBuf>,
+    // Subidas procesadas en paralelo (escritura a disco + estadísticas)
+    #[arg(long, env = "SENTINEL_UPLOAD_CONCURRENCY", default_value_t = 4)]
+    pub upload_concurrency: usize,
+    // Subidas adicionales que pueden esperar turno antes de responder 503
+    #[arg(long, env = "SENTINEL_UPLOAD_QUEUE", default_value_t = 16)]
+    pub upload_queue: usize,
+    // Segundos sugeridos al robot en Retry-After cuando la cola está llena
+    #[arg(long, env = "SENTINEL_UPLOAD_RETRY_AFTER", default_value_t = 10)]
+    pub upload_retry_after_sec: u64,
+    // Segundos máximos para recibir cada campo del formulario de una subida (408 si se
+    // corta la conexión del robot a mitad)
+    #[arg(long, env = "SENTINEL_UPLOAD_READ_TIMEOUT", default_value_t = 120)]
+    pub upload_read_timeout_sec: u64,
+    // Nivel de compresión zstd de las capturas almacenadas (0 = sin comprimir)
+    #[arg(long, env = "SENTINEL_ZSTD_LEVEL", default_value_t = 3)]
+    pub zstd_level: i32,
+    // Días tras los cuales una captura pasa al archivo frío (0 = desactivado)
+    #[arg(long, env = "SENTINEL_ARCHIVE_AFTER_DAYS", default_value_t = 0)]
+    pub archive_after_days: u64,
+    // Token para los endpoints /api/admin (sin token la API de administración queda desactivada)
+    #[arg(long, env = "SENTINEL_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+    // Token de las fuentes externas (drones, cámaras de mano) para /ingest/external (sin
+    // token el endpoint queda desactivado)
+    #[arg(long, env = "SENTINEL_EXTERNAL_INGEST_TOKEN", hide_env_values = true)]
+    pub external_ingest_token: Option<String>,
+    // Espacio libre mínimo en disco antes de marcar advertencia (y /readyz en 503)
+    #[arg(long, env = "SENTINEL_MIN_FREE_DISK_MB", default_value_t = 1024)]
+    pub min_free_disk_mb: u64,
+    // Horas entre escaneos de integridad de las capturas (0 = desactivado)
+    #[arg(long, env = "SENTINEL_INTEGRITY_SCAN_HOURS", default_value_t = 24)]
+    pub integrity_scan_hours: u64,
+    // Frames decodificados que se mantienen en la caché LRU del endpoint de matriz
+    #[arg(long, env = "SENTINEL_FRAME_CACHE_SIZE", default_value_t = 64)]
+    pub frame_cache_size: usize,
+    // Respuestas derivadas (evolución, fotogramas clave, seguimiento) que se mantienen en
+    // la caché de respuestas (0 = desactivada)
+    #[arg(long, env = "SENTINEL_RESPONSE_CACHE_SIZE", default_value_t = 128)]
+    pub response_cache_size: usize,
+    // Frames recientes por turbina que se mantienen decodificados en memoria para la vista
+    // en vivo (0 = desactivado)
+    #[arg(long, env = "SENTINEL_LATEST_FRAMES", default_value_t = 4)]
+    pub latest_frames: usize,
+    // Formato de los logs: texto legible o JSON estructurado (una línea por evento)
+    #[arg(long, env = "SENTINEL_LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+    // Minutos entre consultas del clima para los sitios con coordenadas (0 = desactivado)
+    #[arg(long, env = "SENTINEL_WEATHER_POLL_MINUTES", default_value_t = 15)]
+    pub weather_poll_minutes: u64,
+    // Endpoint compatible con la API "forecast" de Open-Meteo
+    #[arg(long, env = "SENTINEL_WEATHER_API_URL", default_value = "https://api.open-meteo.com/v1/forecast")]
+    pub weather_api_url: String,
+    // Robots simulados que envían heartbeats y capturas sintéticas a este mismo servidor
+    // (demo y pruebas de carga sin hardware; 0 = desactivado)
+    #[arg(long, env = "SENTINEL_SIMULATE", default_value_t = 0)]
+    pub simulate: usize,
+    // URL pública del servidor para los enlaces de las notificaciones ({{link}})
+    #[arg(long, env = "SENTINEL_PUBLIC_URL")]
+    pub public_url: Option<String>,
+    // Dirección del listener Modbus TCP para los PLC de planta (sin ella, desactivado)
+    #[arg(long, env = "SENTINEL_MODBUS_LISTEN")]
+    pub modbus_listen: Option<SocketAddr>,
+    // Brokers de Kafka ("host:puerto,host:puerto") para publicar los eventos (sin ellos, desactivado)
+    #[arg(long, env = "SENTINEL_KAFKA_BROKERS")]
+    pub kafka_brokers: Option<String>,
+    #[arg(long, env = "SENTINEL_KAFKA_TOPIC", default_value = "sentinel-events")]
+    pub kafka_topic: String,
+    // Servidor NATS para publicar los eventos (sin él, desactivado)
+    #[arg(long, env = "SENTINEL_NATS_URL")]
+    pub nats_url: Option<String>,
+    #[arg(long, env = "SENTINEL_NATS_SUBJECT_PREFIX", default_value = "sentinel")]
+    pub nats_subject_prefix: String,
+    // Subject del que se aceptan comandos para los robots (admite comodines)
+    #[arg(long, env = "SENTINEL_NATS_COMMAND_SUBJECT")]
+    pub nats_command_subject: Option<String>,
+    // JSON de la cuenta de servicio de Firebase para las notificaciones push (sin él, desactivadas)
+    #[arg(long, env = "SENTINEL_FCM_CREDENTIALS")]
+    pub fcm_credentials: Option<PathBuf>,
+    #[arg(long, env = "SENTINEL_FCM_API_URL", default_value = "https://fcm.googleapis.com")]
+    pub fcm_api_url: String,
+    // Desfase de reloj de un robot (segundos) a partir del cual se marca como desincronizado
+    #[arg(long, env = "SENTINEL_MAX_CLOCK_SKEW_SEC", default_value_t = 30)]
+    pub max_clock_skew_sec: u64,
+    // Segundos que la clave de ingesta anterior sigue valiendo tras una rotación
+    #[arg(long, env = "SENTINEL_KEY_ROTATION_GRACE_SEC", default_value_t = 86400)]
+    pub key_rotation_grace_sec: u64,
+    // Segundos sin heartbeat tras los que un robot se considera desconectado (sin él, tres
+    // veces su espera entre escaneos; el registro de turbinas puede fijar uno por robot)
+    #[arg(long, env = "SENTINEL_OFFLINE_AFTER_SEC")]
+    pub offline_after_sec: Option<u64>,
+    // Subidas por minuto que acepta cada robot; por encima se responde 429 (0 = sin límite)
+    #[arg(long, env = "SENTINEL_INGEST_RATE_LIMIT_PER_MIN", default_value_t = 20)]
+    pub ingest_rate_limit_per_min: u32,
+    // Servidor IMAP ("host" o "host:puerto", TLS) de la pasarela de correo (sin él, desactivada)
+    #[arg(long, env = "SENTINEL_IMAP_SERVER")]
+    pub imap_server: Option<String>,
+    #[arg(long, env = "SENTINEL_IMAP_USER")]
+    pub imap_user: Option<String>,
+    #[arg(long, env = "SENTINEL_IMAP_PASSWORD", hide_env_values = true)]
+    pub imap_password: Option<String>,
+    #[arg(long, env = "SENTINEL_IMAP_FOLDER", default_value = "INBOX")]
+    pub imap_folder: String,
+    // Segundos entre revisiones del buzón (mínimo 10)
+    #[arg(long, env = "SENTINEL_IMAP_POLL_SEC", default_value_t = 60)]
+    pub imap_poll_sec: u64,
+    // Clave maestra (32 bytes en base64) para cifrar los secretos guardados en disco; sin
+    // ella se guardan en claro
+    #[arg(long, env = "SENTINEL_MASTER_KEY", hide_env_values = true)]
+    pub master_key: Option<String>,
+    // Alternativa: fichero con la clave maestra (el que monta el KMS o el gestor de secretos)
+    #[arg(long, env = "SENTINEL_MASTER_KEY_FILE")]
+    pub master_key_file: Option<PathBuf>,
+    // Clave (32 bytes en base64) para cifrar las capturas guardadas en disco (ver
+    // storage::crypto); sin ella se guardan en claro
+    #[arg(long, env = "SENTINEL_CAPTURE_KEY", hide_env_values = true)]
+    pub capture_key: Option<String>,
+    // Alternativa: fichero con la clave de las capturas (el que monta el KMS)
+    #[arg(long, env = "SENTINEL_CAPTURE_KEY_FILE")]
+    pub capture_key_file: Option<PathBuf>,
+    // Servidor SMTP ("host" o "host:puerto", TLS) para enviar los informes programados (sin
+    // él, los informes solo se guardan)
+    #[arg(long, env = "SENTINEL_SMTP_SERVER")]
+    pub smtp_server: Option<String>,
+    #[arg(long, env = "SENTINEL_SMTP_USER")]
+    pub smtp_user: Option<String>,
+    #[arg(long, env = "SENTINEL_SMTP_PASSWORD", hide_env_values = true)]
+    pub smtp_password: Option<String>,
+    #[arg(long, env = "SENTINEL_SMTP_FROM", default_value = "GCU Sentinel <sentinel@localhost>")]
+    pub smtp_from: String,
+    // Página de estado pública en /status, sin autenticación y sin datos sensibles
+    #[arg(long, env = "SENTINEL_STATUS_PAGE", default_value_t = false)]
+    pub status_page: bool,
+    // Consultas por minuto que atiende /status entre todos los clientes; por encima, 429
+    #[arg(long, env = "SENTINEL_STATUS_RATE_LIMIT_PER_MIN", default_value_t = 60)]
+    pub status_rate_limit_per_min: u32,
+    // Token de la API de replicación (/api/replication) en la instancia primaria, y con el
+    // que se autentica la secundaria (sin él no se replica)
+    #[arg(long, env = "SENTINEL_REPLICATION_TOKEN", hide_env_values = true)]
+    pub replication_token: Option<String>,
+    // URL de la instancia primaria: esta instancia queda como secundaria en caliente y
+    // copia de ella capturas, alertas y configuración
+    #[arg(long, env = "SENTINEL_REPLICATE_FROM")]
+    pub replicate_from: Option<String>,
+    // Segundos entre sincronizaciones con la primaria (mínimo 10)
+    #[arg(long, env = "SENTINEL_REPLICATION_INTERVAL_SEC", default_value_t = 60)]
+    pub replication_interval_sec: u64,
+    // Alertmanager al que se envían las alertas abiertas (su URL base, sin /api/v2/alerts;
+    // sin ella solo se exponen en /api/alertmanager/alerts)
+    #[arg(long, env = "SENTINEL_ALERTMANAGER_URL")]
+    pub alertmanager_url: Option<String>,
+    // Segundos entre reenvíos de las alertas abiertas (mínimo 15)
+    #[arg(long, env = "SENTINEL_ALERTMANAGER_INTERVAL_SEC", default_value_t = 60)]
+    pub alertmanager_interval_sec: u64,
+    // Bucket S3 (o compatible) para las subidas directas de los robots (ver objectstore);
+    // hacen falta también --s3-access-key y --s3-secret-key
+    #[arg(long, env = "SENTINEL_S3_BUCKET")]
+    pub s3_bucket: Option<String>,
+    #[arg(long, env = "SENTINEL_S3_REGION", default_value = "us-east-1")]
+    pub s3_region: String,
+    // Endpoint de un servicio compatible con S3 (MinIO, Ceph...); sin él, AWS
+    #[arg(long, env = "SENTINEL_S3_ENDPOINT")]
+    pub s3_endpoint: Option<String>,
+    #[arg(long, env = "SENTINEL_S3_ACCESS_KEY")]
+    pub s3_access_key: Option<String>,
+    #[arg(long, env = "SENTINEL_S3_SECRET_KEY", hide_env_values = true)]
+    pub s3_secret_key: Option<String>,
+    // Prefijo de las claves de las subidas directas en el bucket
+    #[arg(long, env = "SENTINEL_S3_PREFIX", default_value = "sentinel-uploads/")]
+    pub s3_prefix: String,
+    // Segundos de validez de la URL de subida que recibe el robot
+    #[arg(long, env = "SENTINEL_S3_UPLOAD_EXPIRY_SEC", default_value_t = 900)]
+    pub s3_upload_expiry_sec: u64,
+    // Tamaño máximo de una subida directa
+    #[arg(long, env = "SENTINEL_S3_MAX_UPLOAD_MB", default_value_t = 1024)]
+    pub s3_max_upload_mb: u64,
+    // Réplica de solo lectura sobre una copia montada de cloud_storage: sin ingesta, sin
+    // cambios por la API y sin tareas de fondo que escriban (ver routes::read_only)
+    #[arg(long, env = "SENTINEL_READ_ONLY", default_value_t = false)]
+    pub read_only: bool,
+}
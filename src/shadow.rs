@@ -0,0 +1,146 @@
+use crate::{
+    calibration::CameraCalibration,
+    commands::{CommandStatus, RobotCommand},
+    state::{AppState, LiveStatus, RemoteConfig},
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, VecDeque};
+
+// --- SOMBRA DE LOS DISPOSITIVOS ---
+// Como las device shadows de AWS IoT: de cada robot, lo deseado (la configuración que le
+// corresponde ahora, sus calibraciones y los comandos que aún no ha terminado) frente a lo
+// último que ha notificado en el heartbeat (versiones aplicadas, estado y, si lo envía,
+// `reported` con sus ajustes tal como los tiene). `delta` son los ajustes deseados que
+// difieren de los aplicados: se comparan con lo notificado o, si el robot solo manda la
+// versión, con la configuración que se le entregó con esa versión (se recuerdan las
+// últimas SERVED_VERSIONS de cada robot). Vive en memoria, como el estado de las turbinas.
+
+const SERVED_VERSIONS: usize = 8;
+
+#[derive(Default, Clone, Debug)]
+pub struct ShadowRecord {
+    pub reported: Option<ReportedState>,
+    // Configuraciones entregadas al robot por versión, la más reciente al final
+    served: VecDeque<(String, Value)>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ReportedState {
+    pub timestamp: u64,
+    pub config_version: Option<String>,
+    pub calibration_version: Option<String>,
+    // Ajustes aplicados en el robot, con las mismas claves que la configuración
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings: Option<Map<String, Value>>,
+}
+
+#[derive(Serialize)]
+pub struct DesiredState {
+    pub config_version: String,
+    pub config: RemoteConfig,
+    pub calibration_version: String,
+    pub calibrations: Vec<CameraCalibration>,
+    // Encolados, entregados o en ejecución
+    pub pending_commands: Vec<RobotCommand>,
+}
+
+#[derive(Serialize)]
+pub struct SettingDelta {
+    pub desired: Value,
+    // None si el robot no lo tiene
+    pub reported: Option<Value>,
+}
+
+#[derive(Serialize)]
+pub struct ShadowDelta {
+    pub config_pending: bool,
+    pub calibration_pending: bool,
+    // Ajustes que difieren; None si no se sabe qué tiene aplicado el robot (no ha
+    // notificado sus ajustes y su versión ya no se recuerda)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings: Option<BTreeMap<String, SettingDelta>>,
+    pub pending_commands: usize,
+}
+
+#[derive(Serialize)]
+pub struct DeviceShadow {
+    pub turbine_token: String,
+    pub desired: DesiredState,
+    pub reported: Option<ReportedState>,
+    // Último heartbeat
+    pub status: Option<LiveStatus>,
+    pub delta: ShadowDelta,
+    pub in_sync: bool,
+}
+
+impl AppState {
+    // Configuración entregada al robot (heartbeat sin versión o /ingest/config/:token)
+    pub async fn record_served_config(&self, turbine_token: &str, version: &str, config: &RemoteConfig) {
+        let mut shadows = self.shadows.write().await;
+        let served = &mut shadows.entry(turbine_token.to_string()).or_default().served;
+        if served.back().is_some_and(|(v, _)| v == version) {
+            return;
+        }
+        served.retain(|(v, _)| v != version);
+        served.push_back((version.to_string(), serde_json::to_value(config).unwrap_or_default()));
+        while served.len() > SERVED_VERSIONS {
+            served.pop_front();
+        }
+    }
+
+    pub async fn record_reported_state(&self, turbine_token: &str, reported: ReportedState) {
+        self.shadows.write().await.entry(turbine_token.to_string()).or_default().reported = Some(reported);
+    }
+}
+
+// Claves de primer nivel de `desired` cuyo valor no coincide con el de `applied`
+fn settings_delta(desired: &Value, applied: &Map<String, Value>) -> BTreeMap<String, SettingDelta> {
+    let Some(desired) = desired.as_object() else { return BTreeMap::new() };
+    desired.iter()
+        .filter(|(key, value)| applied.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), SettingDelta { desired: value.clone(), reported: applied.get(key).cloned() }))
+        .collect()
+}
+
+// None si el robot no tiene sombra ni heartbeat ni está en el registro
+pub async fn device_shadow(state: &AppState, turbine_token: &str) -> Option<DeviceShadow> {
+    let record = state.shadows.read().await.get(turbine_token).cloned();
+    let status = state.turbine_status.read().await.get(turbine_token).cloned();
+    let registered = state.turbines.read().await.iter().any(|t| t.token == turbine_token);
+    if record.is_none() && status.is_none() && !registered {
+        return None;
+    }
+    let record = record.unwrap_or_default();
+
+    let (config, config_version) = state.robot_config(turbine_token).await;
+    let (calibrations, calibration_version) = state.robot_calibrations(turbine_token).await;
+    let pending_commands: Vec<RobotCommand> = state.commands.recent(turbine_token).await.into_iter()
+        .filter(|c| matches!(c.status, CommandStatus::Pending | CommandStatus::Delivered | CommandStatus::Acked))
+        .collect();
+
+    let reported = record.reported.clone();
+    let config_pending = reported.as_ref().is_none_or(|r| r.config_version.as_deref() != Some(config_version.as_str()));
+    let calibration_pending = reported.as_ref().is_none_or(|r| r.calibration_version.as_deref() != Some(calibration_version.as_str()));
+    let applied = reported.as_ref().and_then(|r| {
+        r.settings.clone().or_else(|| {
+            let version = r.config_version.as_deref()?;
+            record.served.iter().find(|(v, _)| v == version).and_then(|(_, c)| c.as_object().cloned())
+        })
+    });
+    let desired_json = serde_json::to_value(&config).unwrap_or_default();
+    let settings = applied.map(|applied| settings_delta(&desired_json, &applied));
+
+    let in_sync = !config_pending
+        && !calibration_pending
+        && pending_commands.is_empty()
+        && settings.as_ref().is_none_or(BTreeMap::is_empty);
+    Some(DeviceShadow {
+        turbine_token: turbine_token.to_string(),
+        delta: ShadowDelta { config_pending, calibration_pending, settings, pending_commands: pending_commands.len() },
+        desired: DesiredState { config_version, config, calibration_version, calibrations, pending_commands },
+        reported,
+        status,
+        in_sync,
+    })
+}
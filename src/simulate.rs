@@ -0,0 +1,184 @@
+use crate::{
+    routes::ingest::{HeartbeatAck, HeartbeatPayload, RobotConfig},
+    settings::ServerSettings,
+    state::{LiveStatus, RemoteConfig},
+};
+use ndarray::Array2;
+use ndarray_npy::WriteNpyExt;
+use reqwest::multipart::{Form, Part};
+use std::time::Duration;
+use tokio::time::Instant;
+
+// --- MODO SIMULACIÓN ---
+// Robots falsos (SIM-001, SIM-002...) que se comportan como los reales contra el propio
+// servidor: heartbeat cada segundo, una captura sintética cada scan_wait_time_sec
+// avanzando el pan, y de vez en cuando un punto caliente inyectado para que salten las
+// alertas. Sirve para enseñar el dashboard y para pruebas de carga sin hardware.
+
+const FRAME_ROWS: usize = 24;
+const FRAME_COLS: usize = 32;
+// Probabilidad de que una captura lleve un punto caliente
+const HOTSPOT_PROBABILITY: f64 = 0.05;
+
+pub fn spawn_simulated_robots(settings: &ServerSettings) {
+    if settings.simulate == 0 {
+        return;
+    }
+    // Los certificados TLS suelen ser autofirmados: los robots simulados van por HTTP
+    let Some(listen) = settings.listen.iter().find(|l| !l.tls) else {
+        tracing::warn!("⚠️ La simulación necesita un listener HTTP; no se lanzan robots simulados");
+        return;
+    };
+    let base_url = listen.local_url();
+    tracing::info!(robots = settings.simulate, %base_url, "🤖 Modo simulación activo");
+    let client = reqwest::Client::new();
+    for i in 1..=settings.simulate {
+        let robot = SimulatedRobot::new(format!("SIM-{:03}", i), base_url.clone(), client.clone());
+        tokio::spawn(robot.run());
+    }
+}
+
+struct SimulatedRobot {
+    token: String,
+    base_url: String,
+    client: reqwest::Client,
+    rng: fastrand::Rng,
+    angle: f32,
+    // Temperatura de fondo propia de cada robot
+    ambient: f32,
+    last_max_temp: f32,
+    // Última configuración recibida; solo se vuelve a pedir cuando cambia su versión
+    config: Option<RobotConfig>,
+}
+
+impl SimulatedRobot {
+    fn new(token: String, base_url: String, client: reqwest::Client) -> Self {
+        let mut rng = fastrand::Rng::new();
+        let ambient = 12.0 + rng.f32() * 10.0;
+        SimulatedRobot {
+            angle: rng.f32() * 360.0,
+            token,
+            base_url,
+            client,
+            rng,
+            ambient,
+            last_max_temp: ambient,
+            config: None,
+        }
+    }
+
+    async fn run(mut self) {
+        // Escalonar los robots y dar tiempo a que el servidor abra los listeners
+        tokio::time::sleep(Duration::from_millis(1000 + self.rng.u64(0..2000))).await;
+        let mut next_upload = Instant::now();
+        loop {
+            match self.heartbeat().await {
+                Ok(config) if config.system_enabled && Instant::now() >= next_upload => {
+                    if let Err(e) = self.upload().await {
+                        tracing::warn!(turbine_token = %self.token, error = %e, "⚠️ Robot simulado: error subiendo captura");
+                    }
+                    self.angle = (self.angle + config.pan_step_degrees).rem_euclid(360.0);
+                    next_upload = Instant::now() + Duration::from_secs(config.scan_wait_time_sec.max(1));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::debug!(turbine_token = %self.token, error = %e, "Robot simulado: heartbeat fallido"),
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    async fn heartbeat(&mut self) -> reqwest::Result<RemoteConfig> {
+        let status = LiveStatus {
+            last_update: 0,
+            turbine_token: self.token.clone(),
+            mode: "Scanning".into(),
+            current_angle: self.angle,
+            current_max_temp: self.last_max_temp,
+            is_online: true,
+        };
+        let heartbeat = HeartbeatPayload {
+            status,
+            config_version: Some(self.config.as_ref().map_or_else(String::new, |c| c.config_version.clone())),
+            calibration_version: None,
+            robot_time: Some(chrono::Utc::now().timestamp() as u64),
+            reported: None,
+        };
+        let ack: HeartbeatAck = self.client.post(format!("{}/ingest/heartbeat", self.base_url))
+            .json(&heartbeat)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let config = match self.config.take() {
+            Some(config) if !ack.config_changed => config,
+            _ => self.client.get(format!("{}/ingest/config/{}", self.base_url, self.token))
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?,
+        };
+        Ok(self.config.insert(config).config.clone())
+    }
+
+    async fn upload(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let frame = self.synthetic_frame();
+        self.last_max_temp = frame.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let mut npy = Vec::new();
+        frame.write_npy(&mut npy)?;
+
+        let form = Form::new()
+            .text("turbine_token", self.token.clone())
+            .text("angle", self.angle.to_string())
+            .part("dataset_file", Part::bytes(npy).file_name("capture.npy"));
+        self.client.post(format!("{}/ingest/upload", self.base_url))
+            .multipart(form)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    // Fondo con un punto caliente gaussiano ocasional
+    fn synthetic_frame(&mut self) -> Array2<f32> {
+        let hotspot = (self.rng.f64() < HOTSPOT_PROBABILITY).then(|| {
+            let hotspot = Hotspot {
+                row: self.rng.usize(2..FRAME_ROWS - 2) as f32,
+                col: self.rng.usize(2..FRAME_COLS - 2) as f32,
+                delta: 30.0 + self.rng.f32() * 50.0,
+                sigma: std::f32::consts::SQRT_2,
+            };
+            tracing::debug!(turbine_token = %self.token, peak = hotspot.delta, "Robot simulado: punto caliente inyectado");
+            hotspot
+        });
+        synthetic_frame(&mut self.rng, FRAME_ROWS, FRAME_COLS, self.ambient, hotspot.as_ref())
+    }
+}
+
+// --- FRAMES SINTÉTICOS ---
+// Los usan los robots simulados y /api/admin/generate.
+
+// Punto caliente gaussiano: `delta` grados sobre el fondo en el centro
+pub struct Hotspot {
+    pub row: f32,
+    pub col: f32,
+    pub delta: f32,
+    // Desviación típica en píxeles
+    pub sigma: f32,
+}
+
+// Fondo con gradiente vertical (cielo más frío arriba) y ruido, más el punto caliente
+pub fn synthetic_frame(rng: &mut fastrand::Rng, rows: usize, cols: usize, ambient: f32, hotspot: Option<&Hotspot>) -> Array2<f32> {
+    let mut frame = Array2::from_shape_fn((rows, cols), |(r, _)| {
+        ambient + r as f32 * 0.15 + (rng.f32() - 0.5)
+    });
+    if let Some(hotspot) = hotspot {
+        let spread = 2.0 * hotspot.sigma * hotspot.sigma;
+        for ((r, c), value) in frame.indexed_iter_mut() {
+            let d2 = (r as f32 - hotspot.row).powi(2) + (c as f32 - hotspot.col).powi(2);
+            *value += hotspot.delta * (-d2 / spread).exp();
+        }
+    }
+    frame
+}
@@ -0,0 +1,921 @@
+use crate::{
+    adaptive_scan::AdaptiveScanConfig,
+    analysis::{
+        anomaly::{ANGLE_TOLERANCE, ANOMALY_WINDOW},
+        blades::{BladeGeometry, BladeImbalance},
+        CaptureStats, ThermalFrameData,
+    },
+    analyzers::default_analyzers,
+    calibration::{latest_calibrations, CameraCalibration},
+    commands::CommandQueue,
+    email::EmailRule,
+    events::{EventKind, EventSink},
+    i18n::Localized,
+    latest_frames::LatestFrames,
+    incidents::{CorrelationConfig, Incident},
+    inference::{Classifier, ClassifierConfig, FaultPrediction},
+    metrics::Metrics,
+    schedule::ScanSchedule,
+    notify::{raise_device_alert, Delivery, DeviceAlert, HeldNotification, NotificationChannel},
+    plausibility::TemperatureBounds,
+    push::FcmClient,
+    reports::ReportSchedule,
+    resolution::{AlertResolution, AutoResolveConfig},
+    response_cache::ResponseCache,
+    routes::{direct_upload::DirectUpload, logs::RobotLogLine, status::StatusPageCache},
+    rules::AlertRule,
+    secrets::{SecretError, SecretVault},
+    sessions::CoverageCheck,
+    settings::ServerSettings,
+    shadow::ShadowRecord,
+    storage::{
+        alert_log::append_alerts,
+        annotations::Annotation,
+        calibrations::append_calibration,
+        cameras::{save_cameras, CameraInfo},
+        catalog::{save_catalog, sha256_hex, try_save_catalog, CaptureRecord},
+        collections::Collection,
+        config_history::{append_config_version, config_diff, ConfigVersion},
+        credentials::{generate_credential, save_credentials, IngestCredential},
+        integrity::IntegrityScanSummary,
+        lineage::Derivation,
+        preferences::UserPreferences,
+        quarantine::QuarantinedUpload,
+        shares::SharedSnapshot,
+        push::PushRegistry,
+        quality::{record_quality_event, QualityEventKind},
+        registry::TurbineInfo,
+        PersistedData,
+    },
+    thresholds::{Severity, ThresholdLevel, ThresholdProfile, ThresholdZone},
+    units::TempUnit,
+    watchdog::{WatchdogConfig, WatchdogStatus},
+    weather::{AmbientCompensation, AmbientReading, WeatherSite},
+    workorders::{Confirmation, WorkOrder, WorkOrderConfig},
+};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroUsize,
+    sync::Arc,
+};
+use tokio::sync::{broadcast, Mutex, RwLock, Semaphore};
+
+// --- ESTRUCTURAS DE DATOS ---
+
+// 1. Configuración (Actualizada con Gemini API Key)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemoteConfig {
+    pub max_temp_trigger: f32,
+    pub scan_wait_time_sec: u64,
+    pub system_enabled: bool,
+    pub pan_step_degrees: f32,
+    // Campo opcional para la API Key de Gemini
+    pub gemini_api_key: Option<String>,
+    // Zonas por rango de ángulo con su propio umbral (p. ej. la góndola a 90° es más caliente)
+    #[serde(default)]
+    pub zones: Vec<ThresholdZone>,
+    // Escala de niveles (warning/critical...); vacía = un único nivel en max_temp_trigger
+    #[serde(default)]
+    pub levels: Vec<ThresholdLevel>,
+    // Canales de notificación disponibles para los niveles
+    #[serde(default)]
+    pub channels: Vec<NotificationChannel>,
+    // Perfiles horarios que desplazan los umbrales (noche, verano...)
+    #[serde(default)]
+    pub profiles: Vec<ThresholdProfile>,
+    // Sitios con su fuente de temperatura ambiente
+    #[serde(default)]
+    pub weather_sites: Vec<WeatherSite>,
+    // Desplazamiento de umbrales según ambiente y viento (None = sin compensar)
+    #[serde(default)]
+    pub ambient_compensation: Option<AmbientCompensation>,
+    // Máscara del rotor para las estadísticas por pala (None = sin segmentar)
+    #[serde(default)]
+    pub blade_geometry: Option<BladeGeometry>,
+    // Modelo ONNX que clasifica el tipo de fallo de cada alerta (None = desactivado)
+    #[serde(default)]
+    pub classifier: Option<ClassifierConfig>,
+    // Agrupación de alertas simultáneas de un mismo sitio (None = desactivada)
+    #[serde(default)]
+    pub correlation: Option<CorrelationConfig>,
+    // Ventanas e intervalos de escaneo por turbina (vacío = escaneo continuo)
+    #[serde(default)]
+    pub scan_schedules: Vec<ScanSchedule>,
+    // Zona horaria IANA de las fechas de la API cuando no se pasa ?tz= (None = UTC)
+    #[serde(default)]
+    pub display_timezone: Option<String>,
+    // Unidad de temperatura de las respuestas cuando no se pasa ?units=
+    #[serde(default)]
+    pub display_units: TempUnit,
+    // Canales para los avisos operativos de los robots (inundación de subidas...)
+    #[serde(default)]
+    pub device_alert_channels: Vec<String>,
+    // Reglas de la pasarela de correo (ver crate::email)
+    #[serde(default)]
+    pub email_rules: Vec<EmailRule>,
+    // Verificación de la cobertura angular de cada barrido (None = desactivada)
+    #[serde(default)]
+    pub scan_coverage: Option<CoverageCheck>,
+    // Límites de temperatura plausibles al recibir capturas (ver crate::plausibility)
+    #[serde(default)]
+    pub temperature_bounds: Option<TemperatureBounds>,
+    // Tickets en Jira o ServiceNow al confirmar alertas críticas (ver crate::workorders)
+    #[serde(default)]
+    pub work_orders: Option<WorkOrderConfig>,
+    // Informes programados (ver crate::reports); también se crean por /api/schedules
+    #[serde(default)]
+    pub report_schedules: Vec<ReportSchedule>,
+    // Resolución automática de alertas al normalizarse la temperatura (None = desactivada)
+    #[serde(default)]
+    pub auto_resolve: Option<AutoResolveConfig>,
+    // Escaneo más frecuente en turbinas con alertas abiertas (None = desactivado)
+    #[serde(default)]
+    pub adaptive_scan: Option<AdaptiveScanConfig>,
+    // Puntuación de calidad de imagen (0-1) por debajo de la cual una captura no cuenta para
+    // líneas base, tendencias ni el clasificador (None = todas cuentan)
+    #[serde(default)]
+    pub min_capture_quality: Option<f32>,
+    // Analizadores que se ejecutan con cada captura, en orden (ver crate::analyzers)
+    #[serde(default = "default_analyzers")]
+    pub analyzers: Vec<String>,
+    // Avisos de fallos del propio servidor (ver crate::watchdog; None = solo log y /metrics)
+    #[serde(default)]
+    pub watchdog: Option<WatchdogConfig>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        RemoteConfig {
+            max_temp_trigger: 50.0,
+            scan_wait_time_sec: 5,
+            system_enabled: true,
+            pan_step_degrees: 0.5,
+            gemini_api_key: Some("".to_string()), // Inicializar vacío
+            zones: Vec::new(),
+            levels: Vec::new(),
+            channels: Vec::new(),
+            profiles: Vec::new(),
+            weather_sites: Vec::new(),
+            ambient_compensation: None,
+            blade_geometry: None,
+            classifier: None,
+            correlation: None,
+            scan_schedules: Vec::new(),
+            display_timezone: None,
+            display_units: TempUnit::Celsius,
+            device_alert_channels: Vec::new(),
+            email_rules: Vec::new(),
+            scan_coverage: None,
+            temperature_bounds: None,
+            work_orders: None,
+            report_schedules: Vec::new(),
+            auto_resolve: None,
+            adaptive_scan: None,
+            min_capture_quality: None,
+            analyzers: default_analyzers(),
+            watchdog: None,
+        }
+    }
+}
+
+// 2. Estado en Vivo
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LiveStatus {
+    pub last_update: u64,
+    pub turbine_token: String,
+    pub mode: String,
+    pub current_angle: f32,
+    pub current_max_temp: f32,
+    pub is_online: bool,
+}
+
+impl Default for LiveStatus {
+    fn default() -> Self {
+        LiveStatus {
+            last_update: 0,
+            turbine_token: "Waiting...".into(),
+            mode: "Offline".into(),
+            current_angle: 0.0,
+            current_max_temp: 0.0,
+            is_online: false,
+        }
+    }
+}
+
+// 3. Registro de Alerta
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertRecord {
+    pub id: String,
+    pub timestamp: u64,
+    pub turbine_token: String,
+    pub max_temp: f32,
+    pub angle: f32,
+    pub dataset_path: String,
+    // Zona angular cuyo umbral disparó la alerta (None = umbral global)
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+    // Nombre del nivel de umbral alcanzado
+    #[serde(default)]
+    pub level: Option<String>,
+    // Ambiente del sitio en el momento de la captura
+    #[serde(default)]
+    pub ambient: Option<AmbientReading>,
+    // Pala notablemente más caliente que las demás en el frame de la alerta
+    #[serde(default)]
+    pub blade_imbalance: Option<BladeImbalance>,
+    // Tipo de fallo estimado por el clasificador ONNX
+    #[serde(default)]
+    pub fault_prediction: Option<FaultPrediction>,
+    // Incidente multi-turbina al que pertenece
+    #[serde(default)]
+    pub incident_id: Option<String>,
+    // Marcada como evidencia clave: no se descarta al recortar el historial
+    #[serde(default)]
+    pub starred: bool,
+    // Frame más caliente de la captura, el que muestran los enlaces
+    #[serde(default)]
+    pub frame_index: Option<usize>,
+    #[serde(default)]
+    pub links: Option<AlertLinks>,
+    // Reglas del operador que se cumplieron (ver crate::rules)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<String>,
+    // Confirmada por un operador (PUT /api/alerts/:id/confirm)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation: Option<Confirmation>,
+    // Ticket de mantenimiento abierto al confirmarla
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_order: Option<WorkOrder>,
+    // Resuelta sola al volver la temperatura a la normalidad (ver crate::resolution)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<AlertResolution>,
+}
+
+// Lado mayor de la miniatura enlazada desde las alertas, en píxeles
+pub const ALERT_THUMBNAIL_SIDE: usize = 64;
+
+// Enlaces directos a la evidencia de una alerta (absolutos si hay --public-url), para
+// mostrar la imagen en las notificaciones y en el listado sin más consultas
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertLinks {
+    pub thumbnail: String,
+    // PNG del frame a tamaño completo
+    pub render: String,
+    // Matriz cruda en JSON
+    pub matrix: String,
+    pub download: String,
+}
+
+impl AlertLinks {
+    pub fn new(public_url: Option<&str>, filename: &str, frame_index: usize) -> Self {
+        let base = public_url.unwrap_or("").trim_end_matches('/');
+        AlertLinks {
+            thumbnail: format!("{}/api/render/{}/{}?max_side={}", base, filename, frame_index, ALERT_THUMBNAIL_SIDE),
+            render: format!("{}/api/render/{}/{}", base, filename, frame_index),
+            matrix: format!("{}/api/matrix/{}/{}", base, filename, frame_index),
+            download: format!("{}/api/download/{}", base, filename),
+        }
+    }
+}
+
+// Refuerzo temporal del escaneo de una turbina tras una alerta
+#[derive(Clone, Debug)]
+pub struct ScanBoost {
+    pub scan_wait_time_sec: u64,
+    pub until: u64,
+}
+
+// Desfase del reloj de un robot respecto al del servidor, medido en el último heartbeat
+// (o en /ingest/time) que indicó la hora del robot
+#[derive(Serialize, Clone, Debug)]
+pub struct ClockSkew {
+    pub turbine_token: String,
+    // Reloj del robot menos el del servidor (positivo = adelantado)
+    pub offset_sec: i64,
+    pub measured: u64,
+    // El desfase supera --max-clock-skew-sec
+    pub flagged: bool,
+}
+
+// Subidas recientes de un robot, para el límite por dispositivo
+#[derive(Serialize, Clone, Debug)]
+pub struct IngestRate {
+    pub turbine_token: String,
+    // Instantes de las subidas aceptadas en el último minuto
+    #[serde(skip)]
+    pub recent: VecDeque<u64>,
+    // Inicio del episodio de inundación en curso (None = dentro del límite)
+    pub flood_started: Option<u64>,
+    pub last_rejected: Option<u64>,
+    // Subidas rechazadas en el episodio en curso
+    pub rejected: u64,
+}
+
+// 4. Frame en vivo ya serializado, listo para reenviar a los dashboards
+#[derive(Clone, Debug)]
+pub struct LiveFrame {
+    pub turbine_token: String,
+    pub json: Arc<str>,
+}
+
+// 5. Estado Global
+// Locks de tokio (sin envenenamiento ante pánicos). Los handlers async usan .await y
+// las tareas del pool bloqueante las variantes blocking_*; nunca se mezclan.
+pub struct AppState {
+    pub settings: ServerSettings,
+    pub config: RwLock<RemoteConfig>,
+    pub live_status: RwLock<LiveStatus>,
+    pub alerts: RwLock<VecDeque<AlertRecord>>,
+    pub catalog: RwLock<Vec<CaptureRecord>>,
+    pub integrity: RwLock<IntegrityScanSummary>,
+    // Caché LRU de frames ya decodificados, por (archivo, índice de frame)
+    pub frame_cache: Mutex<LruCache<(String, usize), ThermalFrameData>>,
+    // Miniaturas PNG en base64 por SHA-256 de la captura
+    pub thumbnail_cache: Mutex<LruCache<String, String>>,
+    // Respuestas ya serializadas de los endpoints caros (ver response_cache)
+    pub response_cache: ResponseCache,
+    // Últimos frames decodificados de cada turbina (ver crate::latest_frames)
+    pub latest_frames: LatestFrames,
+    // Admisión (en proceso + en cola) y ranuras de procesamiento de subidas
+    pub upload_admission: Arc<Semaphore>,
+    pub upload_slots: Arc<Semaphore>,
+    // Contadores y latencias por ruta para /metrics
+    pub metrics: Metrics,
+    // Relé de frames en vivo (robot -> dashboards)
+    pub live_frames: broadcast::Sender<LiveFrame>,
+    // Líneas de log de los robots para las consolas en vivo (ver routes::logs)
+    pub robot_logs: broadcast::Sender<RobotLogLine>,
+    // Subidas directas a S3 pendientes de completar, por upload_id
+    pub direct_uploads: RwLock<HashMap<String, DirectUpload>>,
+    // Refuerzos de escaneo activos por turbina
+    pub scan_boosts: RwLock<HashMap<String, ScanBoost>>,
+    // Espera adaptativa aplicada ahora a cada turbina (ver crate::adaptive_scan)
+    pub adaptive_scans: RwLock<HashMap<String, u64>>,
+    // Cliente HTTP compartido para notificaciones salientes
+    pub http: reqwest::Client,
+    // Última lectura de ambiente por sitio
+    pub ambient: RwLock<HashMap<String, AmbientReading>>,
+    // Registro de turbinas (metadatos y ubicación)
+    pub turbines: RwLock<Vec<TurbineInfo>>,
+    // Último heartbeat de cada turbina
+    pub turbine_status: RwLock<HashMap<String, LiveStatus>>,
+    // Sesión del clasificador ONNX
+    pub classifier: Arc<Classifier>,
+    // Incidentes de alertas correlacionadas, el más reciente primero
+    pub incidents: RwLock<VecDeque<Incident>>,
+    // Comandos pendientes de entregar a cada robot
+    pub commands: CommandQueue,
+    // Todas las versiones de la configuración, la vigente al final
+    pub config_history: RwLock<Vec<ConfigVersion>>,
+    // Agrupaciones de capturas creadas por los usuarios
+    pub collections: RwLock<Vec<Collection>>,
+    // Reglas de alerta definidas por el operador
+    pub rules: RwLock<Vec<AlertRule>>,
+    // Anotaciones dibujadas sobre los frames
+    pub annotations: RwLock<Vec<Annotation>>,
+    // Informes programados creados por /api/schedules
+    pub report_schedules: RwLock<Vec<ReportSchedule>>,
+    // Derivaciones registradas (ver storage::lineage)
+    pub lineage: RwLock<Vec<Derivation>>,
+    // Subidas rechazadas pendientes de revisión (ver storage::quarantine)
+    pub quarantine: RwLock<Vec<QuarantinedUpload>>,
+    // Preferencias del dashboard de cada usuario
+    pub preferences: RwLock<Vec<UserPreferences>>,
+    // Instantáneas de frames con enlace público (ver routes::share)
+    pub shares: RwLock<Vec<SharedSnapshot>>,
+    // Notificaciones que agotaron sus reintentos (compartido con las tareas de envío)
+    pub failed_deliveries: Arc<RwLock<Vec<Delivery>>>,
+    // Avisos retenidos por las horas de silencio de su canal, pendientes del resumen
+    pub held_notifications: RwLock<Vec<HeldNotification>>,
+    // Cámaras vistas en cada robot con su última resolución
+    pub cameras: RwLock<Vec<CameraInfo>>,
+    // Todas las versiones de calibración de todas las cámaras
+    pub calibrations: RwLock<Vec<CameraCalibration>>,
+    // Publicación de eventos hacia Kafka/NATS (inactiva si no hay destinos)
+    pub events: EventSink,
+    // Dispositivos móviles y preferencias push (compartido con las tareas de envío)
+    pub push: Arc<RwLock<PushRegistry>>,
+    // Cliente de Firebase Cloud Messaging, si hay credenciales
+    pub fcm: Option<Arc<FcmClient>>,
+    // Sombra de cada robot: lo que ha notificado y las configuraciones que se le entregaron
+    pub shadows: RwLock<HashMap<String, ShadowRecord>>,
+    // Último desfase de reloj medido en cada robot
+    pub clock_skew: RwLock<HashMap<String, ClockSkew>>,
+    // Claves de ingesta de los robots (solo hashes)
+    pub credentials: RwLock<Vec<IngestCredential>>,
+    // Clave maestra de los secretos de la configuración (compartida con las tareas de envío)
+    pub secrets: Arc<SecretVault>,
+    // Ritmo de subidas de cada robot (límite por dispositivo)
+    pub ingest_rates: RwLock<HashMap<String, IngestRate>>,
+    // Límite y caché de /status
+    pub status_page: Mutex<StatusPageCache>,
+    // Resultado de la última comprobación del watchdog
+    pub watchdog: RwLock<WatchdogStatus>,
+}
+
+// --- ESTADO COMPARTIDO ---
+// Helpers que encapsulan los patrones de actualización con un único lock por operación.
+
+// Alertas que se conservan en memoria
+pub const MAX_ALERTS: usize = 50;
+
+// Recorta el historial (más reciente primero) a MAX_ALERTS descartando las alertas más
+// antiguas sin estrella; las marcadas se conservan siempre
+pub fn trim_alerts(alerts: &mut VecDeque<AlertRecord>) {
+    let mut excess = alerts.len().saturating_sub(MAX_ALERTS);
+    let mut index = alerts.len();
+    while excess > 0 && index > 0 {
+        index -= 1;
+        if !alerts[index].starred {
+            alerts.remove(index);
+            excess -= 1;
+        }
+    }
+}
+
+// Plazo mínimo sin heartbeat tras el que una turbina se considera desconectada
+pub const MIN_OFFLINE_AFTER_SEC: u64 = 5;
+
+// Esperas entre escaneos sin heartbeat que se toleran antes de darla por desconectada
+const HEARTBEAT_MISSES: u64 = 3;
+
+// Plazos de desconexión de la flota, tomados de una vez para recorrer varias turbinas.
+// Por orden: el de la turbina en el registro (offline_after_sec), el global
+// (--offline-after-sec) o, sin ninguno, HEARTBEAT_MISSES veces su espera entre escaneos
+// (la mayor de sus planificaciones), nunca por debajo de MIN_OFFLINE_AFTER_SEC
+pub struct OfflineTimeouts {
+    global: Option<u64>,
+    overrides: HashMap<String, u64>,
+    scan_wait_time_sec: u64,
+    schedules: Vec<ScanSchedule>,
+}
+
+impl OfflineTimeouts {
+    pub fn for_turbine(&self, turbine_token: &str) -> u64 {
+        if let Some(&sec) = self.overrides.get(turbine_token) {
+            return sec;
+        }
+        if let Some(sec) = self.global {
+            return sec;
+        }
+        let interval = self.schedules.iter()
+            .filter(|s| s.applies_to(turbine_token))
+            .filter_map(|s| s.interval_sec)
+            .fold(self.scan_wait_time_sec, u64::max);
+        interval.saturating_mul(HEARTBEAT_MISSES).max(MIN_OFFLINE_AFTER_SEC)
+    }
+
+    pub fn is_online(&self, turbine_token: &str, last_update: u64, now: u64) -> bool {
+        now <= last_update.saturating_add(self.for_turbine(turbine_token))
+    }
+}
+
+// Miniaturas que se mantienen en memoria
+const THUMBNAIL_CACHE_SIZE: usize = 256;
+
+// Frames en vivo en cola por dashboard antes de descartar los más antiguos
+const LIVE_FRAME_BUFFER: usize = 16;
+// Líneas de log en cola por consola antes de que pierda las más antiguas
+const ROBOT_LOG_BUFFER: usize = 1024;
+
+// Ventana del límite de subidas por robot
+pub const RATE_WINDOW_SEC: u64 = 60;
+
+// Segundos sin subidas rechazadas tras los que termina un episodio de inundación
+const FLOOD_CLEAR_SEC: u64 = 600;
+
+impl AppState {
+    // Estado inicial a partir de los parámetros y lo cargado de disco
+    pub fn new(settings: ServerSettings, data: PersistedData, secrets: SecretVault) -> Self {
+        let PersistedData { catalog, turbines, config_history, collections, failed_deliveries, held_notifications, cameras, calibrations, push, credentials, rules, annotations, report_schedules, lineage, quarantine, preferences, shares } = data;
+        let fcm = settings.fcm_credentials.as_deref().and_then(|path| {
+            FcmClient::load(path, &settings.fcm_api_url)
+                .inspect(|fcm| tracing::info!(project = fcm.project_id(), "📱 Notificaciones push de FCM activas"))
+                .inspect_err(|e| tracing::error!(error = %e, "❌ No se pudieron cargar las credenciales de FCM"))
+                .ok()
+                .map(Arc::new)
+        });
+        let config = config_history.last().map(|v| v.config.clone()).unwrap_or_default();
+        AppState {
+            upload_admission: Arc::new(Semaphore::new(settings.upload_concurrency + settings.upload_queue)),
+            upload_slots: Arc::new(Semaphore::new(settings.upload_concurrency.max(1))),
+            frame_cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(settings.frame_cache_size).unwrap_or(NonZeroUsize::MIN),
+            )),
+            thumbnail_cache: Mutex::new(LruCache::new(NonZeroUsize::new(THUMBNAIL_CACHE_SIZE).unwrap_or(NonZeroUsize::MIN))),
+            response_cache: ResponseCache::new(settings.response_cache_size),
+            latest_frames: LatestFrames::new(settings.latest_frames),
+            settings,
+            config: RwLock::new(config),
+            live_status: RwLock::new(LiveStatus::default()),
+            alerts: RwLock::new(VecDeque::new()),
+            catalog: RwLock::new(catalog),
+            integrity: RwLock::new(IntegrityScanSummary::default()),
+            metrics: Metrics::default(),
+            live_frames: broadcast::channel(LIVE_FRAME_BUFFER).0,
+            robot_logs: broadcast::channel(ROBOT_LOG_BUFFER).0,
+            direct_uploads: RwLock::new(HashMap::new()),
+            scan_boosts: RwLock::new(HashMap::new()),
+            adaptive_scans: RwLock::new(HashMap::new()),
+            http: reqwest::Client::new(),
+            ambient: RwLock::new(HashMap::new()),
+            turbines: RwLock::new(turbines),
+            turbine_status: RwLock::new(HashMap::new()),
+            classifier: Arc::new(Classifier::default()),
+            incidents: RwLock::new(VecDeque::new()),
+            commands: CommandQueue::default(),
+            config_history: RwLock::new(config_history),
+            collections: RwLock::new(collections),
+            rules: RwLock::new(rules),
+            annotations: RwLock::new(annotations),
+            report_schedules: RwLock::new(report_schedules),
+            lineage: RwLock::new(lineage),
+            quarantine: RwLock::new(quarantine),
+            preferences: RwLock::new(preferences),
+            shares: RwLock::new(shares),
+            failed_deliveries: Arc::new(RwLock::new(failed_deliveries)),
+            held_notifications: RwLock::new(held_notifications),
+            cameras: RwLock::new(cameras),
+            calibrations: RwLock::new(calibrations),
+            events: EventSink::default(),
+            push: Arc::new(RwLock::new(push)),
+            fcm,
+            shadows: RwLock::new(HashMap::new()),
+            clock_skew: RwLock::new(HashMap::new()),
+            credentials: RwLock::new(credentials),
+            secrets: Arc::new(secrets),
+            ingest_rates: RwLock::new(HashMap::new()),
+            status_page: Mutex::new(StatusPageCache::default()),
+            watchdog: RwLock::new(WatchdogStatus { healthy: true, ..WatchdogStatus::default() }),
+        }
+    }
+
+    // Sustituye la configuración registrando la nueva versión; devuelve su número
+    // (el de la vigente si no cambia nada). Si un secreto no se puede cifrar no se guarda
+    // nada
+    pub async fn apply_config(&self, mut config: RemoteConfig, author: Option<String>, source: &str, rollback_of: Option<u64>) -> Result<u64, SecretError> {
+        let mut current = self.config.write().await;
+        let mut history = self.config_history.write().await;
+        // Los secretos en claro se cifran antes de guardarse; los que no cambian
+        // conservan su cifrado
+        self.secrets.seal_config(&mut config, &mut self.secrets.known_secrets(&current))
+            .inspect_err(|e| tracing::error!(error = %e, source, "❌ No se pudieron cifrar los secretos de la configuración"))?;
+        let last = history.last().map_or(0, |v| v.version);
+        let changes = config_diff(&current, &config);
+        if changes.is_empty() {
+            return Ok(last);
+        }
+        let version = ConfigVersion {
+            version: last + 1,
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            author,
+            source: source.to_string(),
+            rollback_of,
+            changes,
+            config: config.clone(),
+        };
+        append_config_version(&version);
+        tracing::info!(version = version.version, source, changes = version.changes.len(), "🗃️ Nueva versión de configuración");
+        *current = config;
+        history.push(version);
+        Ok(last + 1)
+    }
+
+    // API key de Gemini en claro, solo para usarla en el momento
+    pub async fn gemini_api_key(&self) -> Option<String> {
+        let key = self.config.read().await.gemini_api_key.clone().filter(|k| !k.is_empty())?;
+        self.secrets.reveal(&key)
+            .inspect_err(|e| tracing::error!(error = %e, "❌ No se pudo descifrar la API key de Gemini"))
+            .ok()
+    }
+
+    // Inserta una alerta al frente y recorta el historial (el completo queda en disco)
+    pub async fn push_alert(&self, alert: AlertRecord) {
+        append_alerts([&alert]);
+        self.events.publish(&alert.turbine_token, EventKind::Alert { alert: alert.clone() });
+        let mut alerts = self.alerts.write().await;
+        alerts.push_front(alert);
+        trim_alerts(&mut alerts);
+    }
+
+    // Configuración que debe recibir una turbina en su heartbeat: la efectiva (perfil
+    // horario, ambiente de su sitio y ventana de escaneo), con la espera entre escaneos
+    // reducida si tiene un refuerzo activo o alertas abiertas (escaneo adaptativo)
+    pub async fn config_for_turbine(&self, turbine_token: &str) -> RemoteConfig {
+        let ambient = self.ambient_for_turbine(turbine_token).await;
+        let offset = self.turbine_temp_offset(turbine_token).await;
+        let now = chrono::Utc::now();
+        let mut config = self.config.read().await
+            .effective_at(&now)
+            .shifted(offset)
+            .compensated(ambient.as_ref(), now.timestamp() as u64)
+            .scheduled_for(turbine_token, &now);
+        let now = now.timestamp() as u64;
+        {
+            let mut boosts = self.scan_boosts.write().await;
+            boosts.retain(|_, boost| boost.until > now);
+            if let Some(boost) = boosts.get(turbine_token) {
+                config.scan_wait_time_sec = config.scan_wait_time_sec.min(boost.scan_wait_time_sec);
+            }
+        }
+        if let Some(wait) = self.adaptive_scan_wait(config.adaptive_scan.as_ref(), turbine_token, now).await {
+            config.scan_wait_time_sec = config.scan_wait_time_sec.min(wait);
+        }
+        config
+    }
+
+    // Lo que necesita el robot de su configuración: sin la API key, los canales de
+    // notificación ni las integraciones del servidor (pueden llevar secretos) y solo con
+    // sus propias planificaciones, más una versión (hash del contenido) para que los
+    // heartbeats solo confirmen si cambió
+    pub async fn robot_config(&self, turbine_token: &str) -> (RemoteConfig, String) {
+        let mut config = self.config_for_turbine(turbine_token).await;
+        config.gemini_api_key = None;
+        config.channels.clear();
+        config.work_orders = None;
+        config.report_schedules.clear();
+        config.scan_schedules.retain(|s| s.applies_to(turbine_token));
+        let json = serde_json::to_vec(&config).unwrap_or_default();
+        let version = sha256_hex(&json)[..16].to_string();
+        (config, version)
+    }
+
+    // Calibraciones vigentes de las cámaras del robot con su versión (hash del conjunto,
+    // como la de la configuración)
+    pub async fn robot_calibrations(&self, turbine_token: &str) -> (Vec<CameraCalibration>, String) {
+        let calibrations = latest_calibrations(&self.calibrations.read().await, turbine_token);
+        let json = serde_json::to_vec(&calibrations).unwrap_or_default();
+        let version = sha256_hex(&json)[..16].to_string();
+        (calibrations, version)
+    }
+
+    // Busca una captura de la misma turbina con idéntico contenido
+    pub async fn find_duplicate(&self, turbine_token: &str, sha256: &str) -> Option<String> {
+        self.catalog.read().await.iter()
+            .find(|r| r.turbine_token == turbine_token && r.sha256 == sha256)
+            .map(|r| r.filename.clone())
+    }
+
+    // Estadísticas de las últimas capturas de la turbina (y cámara) al mismo ángulo
+    // anteriores a `before`
+    pub async fn capture_history(
+        &self,
+        turbine_token: &str,
+        camera_id: Option<&str>,
+        angle: f32,
+        shape: Option<(usize, usize)>,
+        before: u64,
+    ) -> Vec<CaptureStats> {
+        let config = self.config.read().await;
+        let catalog = self.catalog.read().await;
+        let mut history: Vec<CaptureStats> = catalog.iter().rev()
+            .filter(|r| r.turbine_token == turbine_token && r.timestamp < before)
+            .filter(|r| config.usable_quality(r.quality.as_ref()))
+            .filter(|r| r.camera_id.as_deref() == camera_id)
+            // Otra resolución tiene otras máximas y medias (un sensor de 32×24 promedia los
+            // puntos calientes): no sirve de referencia
+            .filter(|r| shape.is_none_or(|s| r.shape().is_none_or(|rs| rs == s)))
+            .filter(|r| r.angle.is_some_and(|a| (a - angle).abs() < ANGLE_TOLERANCE))
+            .filter_map(|r| Some(CaptureStats { max_temp: r.max_temp?, avg_temp: r.avg_temp? }))
+            .take(ANOMALY_WINDOW)
+            .collect();
+        history.reverse();
+        history
+    }
+
+    // Añade una captura al catálogo y lo persiste
+    // Actualiza la resolución y actividad de la cámara que tomó una captura
+    pub async fn record_camera(&self, turbine_token: &str, camera_id: &str, (height, width): (usize, usize), timestamp: u64) {
+        let mut cameras = self.cameras.write().await;
+        match cameras.iter_mut().find(|c| c.turbine_token == turbine_token && c.camera_id == camera_id) {
+            Some(camera) => {
+                if (camera.width, camera.height) != (width, height) {
+                    tracing::warn!(
+                        %turbine_token,
+                        %camera_id,
+                        previous = %format!("{}x{}", camera.width, camera.height),
+                        current = %format!("{}x{}", width, height),
+                        "📷 Cambio de resolución de la cámara"
+                    );
+                }
+                camera.width = width;
+                camera.height = height;
+                camera.last_seen = timestamp;
+                camera.captures += 1;
+            }
+            None => {
+                tracing::info!(%turbine_token, %camera_id, width, height, "📷 Nueva cámara registrada");
+                cameras.push(CameraInfo {
+                    turbine_token: turbine_token.to_string(),
+                    camera_id: camera_id.to_string(),
+                    width,
+                    height,
+                    first_seen: timestamp,
+                    last_seen: timestamp,
+                    captures: 1,
+                });
+            }
+        }
+        save_cameras(&cameras);
+    }
+
+    // Registra una nueva versión de calibración numerándola tras la última de su cámara
+    pub async fn add_calibration(&self, mut calibration: CameraCalibration) -> std::io::Result<CameraCalibration> {
+        let mut calibrations = self.calibrations.write().await;
+        calibration.version = calibrations.iter()
+            .filter(|c| c.turbine_token == calibration.turbine_token && c.camera_id == calibration.camera_id)
+            .map(|c| c.version)
+            .max()
+            .unwrap_or(0) + 1;
+        append_calibration(&calibration)?;
+        tracing::info!(
+            turbine_token = %calibration.turbine_token,
+            camera_id = calibration.camera_id.as_deref().unwrap_or("-"),
+            version = calibration.version,
+            "🎯 Nueva calibración de cámara"
+        );
+        calibrations.push(calibration.clone());
+        Ok(calibration)
+    }
+
+    // Turbina registrada, que ha enviado heartbeats o que tiene capturas
+    pub async fn knows_turbine(&self, turbine_token: &str) -> bool {
+        self.turbines.read().await.iter().any(|t| t.token == turbine_token)
+            || self.turbine_status.read().await.contains_key(turbine_token)
+            || self.catalog.read().await.iter().any(|r| r.turbine_token == turbine_token)
+    }
+
+    pub async fn offline_timeouts(&self) -> OfflineTimeouts {
+        let (scan_wait_time_sec, schedules) = {
+            let config = self.config.read().await;
+            (config.scan_wait_time_sec, config.scan_schedules.clone())
+        };
+        OfflineTimeouts {
+            global: self.settings.offline_after_sec,
+            overrides: self.turbines.read().await.iter()
+                .filter_map(|t| Some((t.token.clone(), t.offline_after_sec?)))
+                .collect(),
+            scan_wait_time_sec,
+            schedules,
+        }
+    }
+
+    // Desplazamiento de los umbrales propio de la turbina (ver TurbineInfo::temp_offset)
+    pub async fn turbine_temp_offset(&self, turbine_token: &str) -> f32 {
+        self.turbines.read().await.iter()
+            .find(|t| t.token == turbine_token)
+            .and_then(|t| t.temp_offset)
+            .unwrap_or(0.0)
+    }
+
+    // Plazo de desconexión de una turbina (ver OfflineTimeouts)
+    pub async fn offline_after(&self, turbine_token: &str) -> u64 {
+        self.offline_timeouts().await.for_turbine(turbine_token)
+    }
+
+    // Anota el desfase entre la hora que indica el robot y la del servidor, avisando
+    // cuando pasa a superar (o vuelve a estar dentro de) --max-clock-skew-sec
+    pub async fn record_clock_skew(&self, turbine_token: &str, robot_time: u64) -> ClockSkew {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let offset_sec = robot_time as i64 - now as i64;
+        let flagged = offset_sec.unsigned_abs() > self.settings.max_clock_skew_sec;
+        let skew = ClockSkew { turbine_token: turbine_token.to_string(), offset_sec, measured: now, flagged };
+        let previous = self.clock_skew.write().await.insert(turbine_token.to_string(), skew.clone());
+        let was_flagged = previous.is_some_and(|p| p.flagged);
+        if flagged != was_flagged {
+            if flagged {
+                tracing::warn!(%turbine_token, offset_sec, "🕰️ Reloj del robot desincronizado");
+            } else {
+                tracing::info!(%turbine_token, offset_sec, "🕰️ Reloj del robot sincronizado de nuevo");
+            }
+            record_quality_event(turbine_token, QualityEventKind::ClockSkew { offset_sec, flagged });
+        }
+        skew
+    }
+
+    // Límite de subidas por robot: ventana deslizante de un minuto con las aceptadas. Por
+    // encima de --ingest-rate-limit-per-min devuelve los segundos hasta que quede hueco.
+    // La primera subida rechazada abre un episodio de inundación (log, evento de calidad y
+    // aviso a device_alert_channels), que se cierra tras FLOOD_CLEAR_SEC sin rechazos
+    pub async fn admit_upload(&self, turbine_token: &str) -> Result<(), u64> {
+        let limit = self.settings.ingest_rate_limit_per_min as usize;
+        if limit == 0 {
+            return Ok(());
+        }
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut rates = self.ingest_rates.write().await;
+        let rate = rates.entry(turbine_token.to_string()).or_insert_with(|| IngestRate {
+            turbine_token: turbine_token.to_string(),
+            recent: VecDeque::new(),
+            flood_started: None,
+            last_rejected: None,
+            rejected: 0,
+        });
+        while rate.recent.front().is_some_and(|&t| t + RATE_WINDOW_SEC <= now) {
+            rate.recent.pop_front();
+        }
+        if rate.recent.len() < limit {
+            if let Some(started) = rate.flood_started
+                && rate.last_rejected.is_some_and(|t| now >= t + FLOOD_CLEAR_SEC)
+            {
+                tracing::info!(%turbine_token, started, rejected = rate.rejected, "🌊 Fin de la inundación de subidas del robot");
+                rate.flood_started = None;
+                rate.rejected = 0;
+            }
+            rate.recent.push_back(now);
+            return Ok(());
+        }
+
+        rate.last_rejected = Some(now);
+        rate.rejected += 1;
+        let retry_after_sec = rate.recent.front().map_or(RATE_WINDOW_SEC, |&t| t + RATE_WINDOW_SEC - now).max(1);
+        if rate.flood_started.is_some() {
+            return Err(retry_after_sec);
+        }
+        rate.flood_started = Some(now);
+        drop(rates);
+        tracing::warn!(%turbine_token, limit_per_min = limit, "🌊 Inundación de subidas: el robot supera el límite y se rechazan con 429");
+        record_quality_event(turbine_token, QualityEventKind::IngestFlood { limit_per_min: limit as u32 });
+        raise_device_alert(self, DeviceAlert::new(
+            turbine_token,
+            "ingest_flood",
+            Localized::new(
+                format!("Robot {} exceeds {} uploads per minute; uploads are being throttled", turbine_token, limit),
+                format!("El robot {} supera las {} subidas por minuto; se están limitando sus subidas", turbine_token, limit),
+            ),
+        )).await;
+        Err(retry_after_sec)
+    }
+
+    // Emite una clave de ingesta nueva para el robot; las vigentes pasan a caducar al
+    // terminar el periodo de gracia (grace_sec = 0 las revoca ya). Devuelve la clave en
+    // claro, su credencial y hasta cuándo vale la anterior
+    pub async fn rotate_ingest_key(&self, turbine_token: &str, grace_sec: u64) -> (String, IngestCredential, Option<u64>) {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let grace_end = now.saturating_add(grace_sec);
+        let mut credentials = self.credentials.write().await;
+        credentials.retain(|c| c.valid_at(now));
+        let mut previous_valid_until = None;
+        for credential in credentials.iter_mut().filter(|c| c.turbine_token == turbine_token) {
+            let expires = credential.expires.map_or(grace_end, |e| e.min(grace_end));
+            credential.expires = Some(expires);
+            previous_valid_until = previous_valid_until.max(Some(expires));
+        }
+        credentials.retain(|c| c.valid_at(now));
+        let (key, credential) = generate_credential(turbine_token, now);
+        credentials.push(credential.clone());
+        save_credentials(&credentials);
+        (key, credential, previous_valid_until)
+    }
+
+    // None si el robot no tiene claves (robots anteriores a las credenciales: se aceptan
+    // sin clave); si las tiene, si la indicada es una de las válidas
+    pub async fn ingest_key_valid(&self, turbine_token: &str, key: Option<&str>) -> Option<bool> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let credentials = self.credentials.read().await;
+        let mut own = credentials.iter().filter(|c| c.turbine_token == turbine_token).peekable();
+        own.peek()?;
+        Some(key.is_some_and(|key| own.any(|c| c.valid_at(now) && c.matches(key))))
+    }
+
+    // Cataloga una captura nueva; si el catálogo no llega a disco la entrada se retira y
+    // la subida falla, porque su recibo solo vale con la entrada guardada
+    pub async fn add_capture(&self, record: CaptureRecord) -> std::io::Result<()> {
+        let mut catalog = self.catalog.write().await;
+        catalog.push(record);
+        try_save_catalog(&catalog).inspect_err(|_| {
+            catalog.pop();
+        })
+    }
+
+    // Recibo de una captura ya catalogada; a las anteriores a los recibos se les asigna uno
+    pub async fn capture_receipt(&self, filename: &str) -> std::io::Result<Option<String>> {
+        let mut catalog = self.catalog.write().await;
+        let Some(index) = catalog.iter().position(|r| r.filename == filename) else {
+            return Ok(None);
+        };
+        if let Some(receipt_id) = &catalog[index].receipt_id {
+            return Ok(Some(receipt_id.clone()));
+        }
+        let receipt_id = uuid::Uuid::new_v4().to_string();
+        catalog[index].receipt_id = Some(receipt_id.clone());
+        if let Err(e) = try_save_catalog(&catalog) {
+            catalog[index].receipt_id = None;
+            return Err(e);
+        }
+        Ok(Some(receipt_id))
+    }
+
+    // Modifica una captura del catálogo desde el pool bloqueante y lo persiste
+    pub fn update_capture_blocking(&self, filename: &str, update: impl FnOnce(&mut CaptureRecord)) {
+        let mut catalog = self.catalog.blocking_write();
+        if let Some(record) = catalog.iter_mut().find(|r| r.filename == filename) {
+            update(record);
+        }
+        save_catalog(&catalog);
+    }
+}
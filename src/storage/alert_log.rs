@@ -0,0 +1,154 @@
+use super::{append_jsonl, purge_jsonl, rewrite_jsonl, storage_root};
+use crate::{
+    schedule::format_timestamp,
+    state::{AlertRecord, AppState},
+};
+use chrono_tz::Tz;
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+
+// --- HISTÓRICO COMPLETO DE ALERTAS ---
+// En memoria solo se guardan las últimas MAX_ALERTS; aquí se añade una línea JSON por
+// alerta creada o modificada (estrella...) en cloud_storage/alerts.jsonl. Al leerlo gana
+// la última línea de cada id. Es la fuente de /api/alerts/export. Solo se reescribe al
+// purgar los datos de una turbina o al restaurar un respaldo.
+
+pub fn alert_log_path() -> PathBuf {
+    storage_root().join("alerts.jsonl")
+}
+
+pub fn append_alerts<'a>(alerts: impl IntoIterator<Item = &'a AlertRecord>) {
+    if let Err(e) = append_jsonl(&alert_log_path(), alerts) {
+        tracing::error!(error = %e, "❌ Error escribiendo el histórico de alertas");
+    }
+}
+
+// Histórico en orden cronológico con la última versión de cada alerta
+pub fn load_alert_log() -> std::io::Result<Vec<AlertRecord>> {
+    let file = match std::fs::File::open(alert_log_path()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut alerts: Vec<AlertRecord> = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Una línea a medias (corte durante la escritura) no invalida el resto
+        let Ok(alert) = serde_json::from_str::<AlertRecord>(&line) else {
+            tracing::warn!("⚠️ Línea ilegible en el histórico de alertas");
+            continue;
+        };
+        match index.get(&alert.id) {
+            Some(&i) => alerts[i] = alert,
+            None => {
+                index.insert(alert.id.clone(), alerts.len());
+                alerts.push(alert);
+            }
+        }
+    }
+    alerts.sort_by_key(|a| a.timestamp);
+    Ok(alerts)
+}
+
+// Histórico completo: lo que hay en memoria es la versión más reciente (incidentes
+// asignados después...) y cubre las alertas anteriores a que existiera el histórico en disco
+pub async fn alert_history(state: &AppState) -> std::io::Result<Vec<AlertRecord>> {
+    let mut alerts = tokio::task::spawn_blocking(load_alert_log).await.map_err(std::io::Error::other)??;
+    for alert in state.alerts.read().await.iter() {
+        match alerts.iter_mut().find(|a| a.id == alert.id) {
+            Some(stored) => *stored = alert.clone(),
+            None => alerts.push(alert.clone()),
+        }
+    }
+    Ok(alerts)
+}
+
+// Sustituye el histórico completo (alertas de un respaldo restaurado), en orden
+// cronológico
+pub fn rewrite_alert_log<'a>(alerts: impl IntoIterator<Item = &'a AlertRecord>) -> std::io::Result<()> {
+    let mut alerts: Vec<&AlertRecord> = alerts.into_iter().collect();
+    alerts.sort_by_key(|a| a.timestamp);
+    rewrite_jsonl(&alert_log_path(), alerts)
+}
+
+// Quita del histórico las alertas de una turbina; devuelve cuántas líneas se borraron
+pub fn purge_turbine_alerts(turbine_token: &str) -> std::io::Result<usize> {
+    purge_jsonl(&alert_log_path(), |a: &AlertRecord| a.turbine_token == turbine_token)
+}
+
+// --- FORMATOS DE EXPORTACIÓN ---
+
+pub const CSV_HEADER: &str = "id,timestamp,datetime,turbine_token,max_temp,angle,dataset_path,zone,severity,level,\
+ambient_temp,wind_speed,ambient_source,blade,blade_delta_temp,blade_summary,fault_label,fault_confidence,\
+incident_id,starred,rules";
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Valor serializado como texto plano (enums en minúsculas, sin comillas)
+fn plain<T: serde::Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(serde_json::Value::Null) | Err(_) => String::new(),
+        Ok(other) => other.to_string(),
+    }
+}
+
+pub fn csv_row(alert: &AlertRecord, tz: Tz) -> String {
+    let opt = |v: Option<String>| v.unwrap_or_default();
+    let datetime = format_timestamp(alert.timestamp, tz);
+    let fields = [
+        alert.id.clone(),
+        alert.timestamp.to_string(),
+        datetime,
+        alert.turbine_token.clone(),
+        alert.max_temp.to_string(),
+        alert.angle.to_string(),
+        alert.dataset_path.clone(),
+        opt(alert.zone.clone()),
+        plain(&alert.severity),
+        opt(alert.level.clone()),
+        opt(alert.ambient.as_ref().map(|a| a.ambient_temp.to_string())),
+        opt(alert.ambient.as_ref().and_then(|a| a.wind_speed).map(|w| w.to_string())),
+        opt(alert.ambient.as_ref().map(|a| plain(&a.source))),
+        opt(alert.blade_imbalance.as_ref().map(|b| b.blade.clone())),
+        opt(alert.blade_imbalance.as_ref().map(|b| b.delta_temp.to_string())),
+        opt(alert.blade_imbalance.as_ref().map(|b| b.summary.clone())),
+        opt(alert.fault_prediction.as_ref().map(|f| f.label.clone())),
+        opt(alert.fault_prediction.as_ref().map(|f| f.confidence.to_string())),
+        opt(alert.incident_id.clone()),
+        alert.starred.to_string(),
+        alert.rules.join(";"),
+    ];
+    fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",")
+}
+
+// Escribe las alertas en CSV (con cabecera, fechas en `tz`) o JSON-lines; devuelve
+// cuántas se exportaron
+pub fn write_alerts<W: Write>(alerts: &[AlertRecord], csv: bool, tz: Tz, mut writer: W) -> std::io::Result<usize> {
+    if csv {
+        writeln!(writer, "{}", CSV_HEADER)?;
+    }
+    for alert in alerts {
+        if csv {
+            writeln!(writer, "{}", csv_row(alert, tz))?;
+        } else {
+            serde_json::to_writer(&mut writer, alert)?;
+            writer.write_all(b"\n")?;
+        }
+    }
+    writer.flush()?;
+    Ok(alerts.len())
+}
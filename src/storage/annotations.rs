@@ -0,0 +1,39 @@
+use super::{save_json, storage_root};
+use crate::analysis::overlay::Shape;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- ANOTACIONES DE LOS USUARIOS ---
+// Rectángulos, flechas y textos dibujados sobre un frame de una captura, en
+// cloud_storage/annotations.json.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Annotation {
+    pub id: String,
+    pub filename: String,
+    pub frame_index: usize,
+    #[serde(flatten)]
+    pub shape: Shape,
+    // "#rrggbb" (por defecto, cian)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    // Usuario del dashboard (X-User)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub created: u64,
+}
+
+pub fn annotations_path() -> PathBuf {
+    storage_root().join("annotations.json")
+}
+
+pub fn load_annotations() -> Vec<Annotation> {
+    std::fs::read_to_string(annotations_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_annotations(annotations: &[Annotation]) {
+    save_json(&annotations_path(), annotations, "anotaciones");
+}
@@ -0,0 +1,103 @@
+use super::{
+    catalog::{save_catalog, CaptureRecord},
+    encode_capture,
+    paths::{check_file_name, safe_resolve},
+    read_capture, storage_root, write_durable,
+};
+use crate::state::AppState;
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+// --- ARCHIVO FRÍO ---
+// Las capturas antiguas se recomprimen al máximo y se mueven a cloud_storage/archive.
+// Cualquier lectura de una captura archivada la restaura bajo demanda.
+
+const ARCHIVE_ZSTD_LEVEL: i32 = 19;
+
+pub fn archive_dir() -> PathBuf {
+    storage_root().join("archive")
+}
+
+// Ruta real en disco de una captura según su estado en el catálogo
+pub fn stored_path(record: &CaptureRecord) -> PathBuf {
+    if record.archived {
+        archive_dir().join(&record.filename)
+    } else {
+        storage_root().join(&record.filename)
+    }
+}
+
+pub fn archive_old_captures(state: &AppState) {
+    let max_age = Duration::from_secs(state.settings.archive_after_days * 86400);
+    let cutoff = (chrono::Utc::now().timestamp() as u64).saturating_sub(max_age.as_secs());
+    let cutoff_time = SystemTime::now() - max_age;
+
+    let candidates: Vec<String> = state.catalog.blocking_read().iter()
+        .filter(|r| !r.archived && !r.starred && r.timestamp < cutoff)
+        .map(|r| r.filename.clone())
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+    if let Err(e) = std::fs::create_dir_all(archive_dir()) {
+        tracing::error!(error = %e, "❌ Error creando carpeta de archivo");
+        return;
+    }
+
+    let mut archived = Vec::new();
+    for filename in candidates {
+        let path = storage_root().join(&filename);
+        // Una captura restaurada recientemente no se vuelve a archivar hasta que pase el plazo
+        let recently_restored = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .map(|t| t > cutoff_time)
+            .unwrap_or(false);
+        if recently_restored {
+            continue;
+        }
+        let result = read_capture(&path)
+            .and_then(|data| encode_capture(&data, ARCHIVE_ZSTD_LEVEL))
+            .and_then(|encoded| write_durable(&archive_dir().join(&filename), &encoded))
+            .and_then(|_| std::fs::remove_file(&path));
+        match result {
+            Ok(()) => archived.push(filename),
+            Err(e) => tracing::error!(%filename, error = %e, "❌ Error archivando captura"),
+        }
+    }
+
+    let mut catalog = state.catalog.blocking_write();
+    for record in catalog.iter_mut().filter(|r| archived.contains(&r.filename)) {
+        record.archived = true;
+    }
+    save_catalog(&catalog);
+    tracing::info!(archived = archived.len(), "🧊 Capturas movidas al archivo frío");
+}
+
+// Devuelve la ruta de una captura; si está archivada la restaura bajo demanda. El nombre
+// viene del usuario: solo se aceptan capturas del catálogo, nunca los índices ni las
+// subcarpetas de la raíz (404 aunque existan). Solo desde el pool bloqueante.
+pub fn locate_capture(state: &AppState, filename: &str) -> std::io::Result<PathBuf> {
+    if check_file_name(filename).is_err() || !state.catalog.blocking_read().iter().any(|r| r.filename == filename) {
+        return Err(std::io::ErrorKind::NotFound.into());
+    }
+    let path = safe_resolve(&storage_root(), filename)?;
+    if path.is_file() {
+        return Ok(path);
+    }
+    let archived_path = safe_resolve(&archive_dir(), filename)?;
+    if !archived_path.is_file() {
+        return Err(std::io::ErrorKind::NotFound.into());
+    }
+    // La réplica de solo lectura la sirve desde el archivo
+    if state.settings.read_only {
+        return Ok(archived_path);
+    }
+    let data = read_capture(&archived_path)?;
+    write_durable(&path, &encode_capture(&data, state.settings.zstd_level)?)?;
+    std::fs::remove_file(&archived_path)?;
+    state.update_capture_blocking(filename, |r| r.archived = false);
+    tracing::info!(%filename, "📦 Captura restaurada desde el archivo frío");
+    Ok(path)
+}
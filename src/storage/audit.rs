@@ -0,0 +1,40 @@
+use super::{append_jsonl, storage_root};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- REGISTRO DE AUDITORÍA ---
+// Operaciones administrativas destructivas o sensibles, una línea JSON por evento en
+// cloud_storage/audit.jsonl. Solo se añade: nunca se reescribe ni se purga.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    // Identificador de la operación ("purge_turbine_data"...)
+    pub action: String,
+    // Sobre qué se hizo (token de turbina, archivo...)
+    pub target: String,
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
+
+impl AuditEntry {
+    pub fn new(action: &str, target: &str, details: serde_json::Value) -> Self {
+        AuditEntry {
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            action: action.to_string(),
+            target: target.to_string(),
+            details,
+        }
+    }
+}
+
+pub fn audit_path() -> PathBuf {
+    storage_root().join("audit.jsonl")
+}
+
+pub fn append_audit(entry: &AuditEntry) {
+    match append_jsonl(&audit_path(), [entry]) {
+        Ok(()) => tracing::info!(action = %entry.action, target = %entry.target, "📝 Auditoría registrada"),
+        Err(e) => tracing::error!(error = %e, action = %entry.action, "❌ Error escribiendo auditoría"),
+    }
+}
@@ -0,0 +1,127 @@
+use super::{
+    archive::{archive_dir, stored_path},
+    catalog::{parse_capture_name, CaptureRecord},
+    paths::{check_file_name, safe_resolve},
+    storage_root, sync_dir, write_durable,
+};
+use crate::state::{AlertRecord, RemoteConfig};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    io::Cursor,
+    path::PathBuf,
+};
+
+// --- RESPALDO Y RESTAURACIÓN ---
+
+// Contenido de un respaldo ya leído; las capturas ya están en su sitio en disco
+#[derive(Default)]
+pub struct BackupContents {
+    pub config: Option<RemoteConfig>,
+    pub alerts: Option<VecDeque<AlertRecord>>,
+    pub catalog: Option<Vec<CaptureRecord>>,
+    pub captures: usize,
+}
+
+fn append_tar_bytes(builder: &mut tar::Builder<Vec<u8>>, name: &str, data: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data)
+}
+
+// Estructura del tar: config.json, alerts.json, catalog.json y opcionalmente
+// captures/<archivo> y archive/<archivo> con los bytes tal cual están en disco
+pub fn build_backup(
+    config: RemoteConfig,
+    alerts: Vec<AlertRecord>,
+    catalog: Vec<CaptureRecord>,
+    include_captures: bool,
+) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    append_tar_bytes(&mut builder, "config.json", &serde_json::to_vec_pretty(&config)?)?;
+    append_tar_bytes(&mut builder, "alerts.json", &serde_json::to_vec_pretty(&alerts)?)?;
+    append_tar_bytes(&mut builder, "catalog.json", &serde_json::to_vec_pretty(&catalog)?)?;
+
+    if include_captures {
+        for record in &catalog {
+            let prefix = if record.archived { "archive" } else { "captures" };
+            let path = stored_path(record);
+            if path.exists() {
+                builder.append_path_with_name(&path, format!("{}/{}", prefix, record.filename))?;
+            }
+        }
+    }
+    builder.into_inner()
+}
+
+// Carpeta donde se descomprimen las capturas de un respaldo antes de darlo por bueno (en
+// la misma raíz, para moverlas con un rename); se borra al terminar, vaya bien o no
+struct Staging(PathBuf);
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn invalid(e: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+}
+
+// Lee y valida todo el respaldo antes de tocar nada: las capturas se preparan en una
+// carpeta temporal y solo se mueven a su sitio si todas las entradas son válidas
+pub fn read_backup(data: &[u8]) -> std::io::Result<BackupContents> {
+    let staging = Staging(storage_root().join(format!(".restore-{}", uuid::Uuid::new_v4().simple())));
+    let mut contents = BackupContents::default();
+    // (carpeta del tar, nombre) de cada captura preparada
+    let mut staged: BTreeSet<(&'static str, String)> = BTreeSet::new();
+    let mut archive = tar::Archive::new(Cursor::new(data));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut bytes)?;
+
+        match name.as_str() {
+            "config.json" => {
+                let config: RemoteConfig = serde_json::from_slice(&bytes)?;
+                config.validate().map_err(|e| invalid(format!("config.json: {}", e)))?;
+                contents.config = Some(config);
+            }
+            "alerts.json" => contents.alerts = Some(serde_json::from_slice(&bytes)?),
+            "catalog.json" => contents.catalog = Some(serde_json::from_slice(&bytes)?),
+            _ => {
+                let (folder, filename) = match name.split_once('/') {
+                    Some(("captures", f)) => ("captures", f),
+                    Some(("archive", f)) => ("archive", f),
+                    _ => continue,
+                };
+                // Solo aceptamos nombres de captura válidos (sin rutas anidadas)
+                if parse_capture_name(filename).is_none() || check_file_name(filename).is_err() {
+                    continue;
+                }
+                let dir = staging.0.join(folder);
+                std::fs::create_dir_all(&dir)?;
+                write_durable(&safe_resolve(&dir, filename)?, &bytes)?;
+                staged.insert((folder, filename.to_string()));
+            }
+        }
+    }
+
+    // Todo el respaldo es válido: las capturas pasan a su sitio
+    for (folder, filename) in &staged {
+        let dir = if *folder == "archive" { archive_dir() } else { storage_root() };
+        std::fs::create_dir_all(&dir)?;
+        std::fs::rename(staging.0.join(folder).join(filename), safe_resolve(&dir, filename)?)?;
+    }
+    if staged.iter().any(|(folder, _)| *folder == "captures") {
+        sync_dir(&storage_root())?;
+    }
+    if staged.iter().any(|(folder, _)| *folder == "archive") {
+        sync_dir(&archive_dir())?;
+    }
+    contents.captures = staged.len();
+    Ok(contents)
+}
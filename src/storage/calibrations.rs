@@ -0,0 +1,34 @@
+use super::{append_jsonl, storage_root};
+use crate::calibration::CameraCalibration;
+use std::{
+    io::BufRead,
+    path::PathBuf,
+};
+
+// --- HISTORIAL DE CALIBRACIONES ---
+// Todas las versiones de todas las cámaras, una línea JSON por versión en
+// cloud_storage/calibrations.jsonl. Solo se añade: las versiones antiguas explican las
+// temperaturas corregidas de capturas antiguas.
+
+pub fn calibrations_path() -> PathBuf {
+    storage_root().join("calibrations.jsonl")
+}
+
+pub fn load_calibrations() -> Vec<CameraCalibration> {
+    let Ok(file) = std::fs::File::open(calibrations_path()) else { return Vec::new() };
+    std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| match serde_json::from_str(&line) {
+            Ok(calibration) => Some(calibration),
+            Err(e) => {
+                tracing::warn!(error = %e, "⚠️ Línea inválida en el historial de calibraciones");
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn append_calibration(calibration: &CameraCalibration) -> std::io::Result<()> {
+    append_jsonl(&calibrations_path(), [calibration])
+}
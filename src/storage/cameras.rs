@@ -0,0 +1,41 @@
+use super::{save_json, storage_root};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- CÁMARAS POR ROBOT ---
+// Un robot puede llevar varias cámaras térmicas (p. ej. la normal y una gran angular);
+// cada subida indica la suya con camera_id. De cada (turbina, cámara) se guarda la
+// resolución detectada en la última captura y su actividad, en cloud_storage/cameras.json.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CameraInfo {
+    pub turbine_token: String,
+    pub camera_id: String,
+    // Resolución de la última captura recibida
+    pub width: usize,
+    pub height: usize,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    #[serde(default)]
+    pub captures: u64,
+}
+
+// Va en el nombre del archivo: sin '_' (separa token y timestamp) ni '.'
+pub fn valid_camera_id(camera_id: &str) -> bool {
+    !camera_id.is_empty() && camera_id.len() <= 32 && camera_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+pub fn cameras_path() -> PathBuf {
+    storage_root().join("cameras.json")
+}
+
+pub fn load_cameras() -> Vec<CameraInfo> {
+    std::fs::read_to_string(cameras_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_cameras(cameras: &[CameraInfo]) {
+    save_json(&cameras_path(), cameras, "cámaras");
+}
@@ -0,0 +1,199 @@
+use super::{archive::archive_dir, photos::find_photo, read_capture, storage_root, try_save_json};
+use crate::{
+    analysis::{frame_points, frame_shape, image_quality::CaptureQuality, pixel_units::PixelUnit, EvolutionPoint},
+    watchdog::{record_fault, Fault},
+    weather::AmbientReading,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::PathBuf};
+
+// --- CATÁLOGO DE CAPTURAS ---
+
+// Registro de captura en el catálogo (índice de archivos almacenados)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaptureRecord {
+    pub filename: String,
+    pub turbine_token: String,
+    pub timestamp: u64,
+    // Hash SHA-256 del contenido, usado para detectar subidas duplicadas
+    pub sha256: String,
+    pub size_bytes: u64,
+    // true si la captura fue movida al archivo frío (cloud_storage/archive)
+    #[serde(default)]
+    pub archived: bool,
+    // Error detectado por el escaneo de integridad (None = captura verificada o sin verificar)
+    #[serde(default)]
+    pub integrity_error: Option<String>,
+    // Ambiente del sitio cuando se recibió la captura
+    #[serde(default)]
+    pub ambient: Option<AmbientReading>,
+    // Ángulo y estadísticas de la captura (ausentes en capturas anteriores a su registro)
+    #[serde(default)]
+    pub angle: Option<f32>,
+    #[serde(default)]
+    pub max_temp: Option<f32>,
+    #[serde(default)]
+    pub avg_temp: Option<f32>,
+    // Z-score frente al historial de la misma turbina y ángulo (None = historial insuficiente)
+    #[serde(default)]
+    pub anomaly_score: Option<f32>,
+    // Evidencia clave marcada por un usuario: no se archiva
+    #[serde(default)]
+    pub starred: bool,
+    // Cámara del robot que tomó la captura (None = cámara única o robot antiguo)
+    #[serde(default)]
+    pub camera_id: Option<String>,
+    // Versión de calibración aplicada a max_temp/avg_temp (None = sin corregir)
+    #[serde(default)]
+    pub calibration_version: Option<u64>,
+    // Desfase del reloj del robot al recibir la captura (robot - servidor, en segundos)
+    #[serde(default)]
+    pub clock_skew_sec: Option<i64>,
+    // Fuente externa (dron, cámara de mano de una contrata) si no la subió el robot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    // Resolución de los frames (ausente en capturas ilegibles o anteriores a su registro;
+    // el escaneo de integridad la completa)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
+    // Estadísticas por frame de las pilas con ángulo o instante por frame (vacío en el resto)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frames: Vec<EvolutionPoint>,
+    // Cuándo llegó al servidor (ausente en capturas anteriores a su registro). Las
+    // capturas diferidas llegan con un timestamp antiguo; la replicación avanza por este
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received: Option<u64>,
+    // Recibo entregado al robot al guardarla (ver GET /ingest/receipt/:id); ausente en
+    // capturas anteriores a los recibos, importadas o reconstruidas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt_id: Option<String>,
+    // Resultados de los analizadores sin campo propio (puntos calientes, cribado...), por
+    // nombre (ver crate::analyzers)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub analyses: BTreeMap<String, serde_json::Value>,
+    // Foto visible de la misma posición, si el robot la envió (ver storage::photos)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photo: Option<String>,
+    // Calidad de imagen calculada al recibirla (ausente en capturas anteriores o
+    // reconstruidas); ver analysis::image_quality
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<CaptureQuality>,
+    // Unidad en que llegaron los píxeles si no era °C; lo guardado ya está convertido
+    // (ver analysis::pixel_units)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_unit: Option<PixelUnit>,
+}
+
+impl CaptureRecord {
+    // Orden de llegada: received, o el timestamp si no se registró
+    pub fn arrival(&self) -> u64 {
+        self.received.unwrap_or(self.timestamp)
+    }
+
+    // (alto, ancho), como frame_shape
+    pub fn shape(&self) -> Option<(usize, usize)> {
+        self.height.zip(self.width)
+    }
+}
+
+pub fn catalog_path() -> PathBuf {
+    storage_root().join("catalog.json")
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// Extrae (turbine_token, timestamp) de un nombre "capture_{token}_{timestamp}.npz" o,
+// con cámara, "capture_{token}_{timestamp}.{camera_id}.npz"
+pub fn parse_capture_name(name: &str) -> Option<(String, u64)> {
+    let stem = name.strip_prefix("capture_")?.strip_suffix(".npz")?;
+    let (token, rest) = stem.rsplit_once('_')?;
+    let timestamp = rest.split_once('.').map_or(rest, |(timestamp, _)| timestamp);
+    Some((token.to_string(), timestamp.parse().ok()?))
+}
+
+pub fn capture_camera(name: &str) -> Option<String> {
+    let stem = name.strip_prefix("capture_")?.strip_suffix(".npz")?;
+    let (_, rest) = stem.rsplit_once('_')?;
+    rest.split_once('.').map(|(_, camera)| camera.to_string())
+}
+
+pub fn capture_filename(turbine_token: &str, timestamp: u64, camera_id: Option<&str>) -> String {
+    match camera_id {
+        Some(camera) => format!("capture_{}_{}.{}.npz", turbine_token, timestamp, camera),
+        None => format!("capture_{}_{}.npz", turbine_token, timestamp),
+    }
+}
+
+// Carga el catálogo desde disco, o lo reconstruye escaneando la carpeta si no existe
+pub fn load_catalog() -> Vec<CaptureRecord> {
+    if let Ok(txt) = std::fs::read_to_string(catalog_path())
+        && let Ok(records) = serde_json::from_str(&txt)
+    {
+        return records;
+    }
+    let records = rebuild_catalog();
+    save_catalog(&records);
+    records
+}
+
+pub fn rebuild_catalog() -> Vec<CaptureRecord> {
+    let mut records = Vec::new();
+    for (dir, archived) in [(storage_root(), false), (archive_dir(), true)] {
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+        for entry in entries.flatten() {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let Some((turbine_token, timestamp)) = parse_capture_name(&filename) else { continue };
+            let Ok(data) = read_capture(&entry.path()) else { continue };
+            let shape = frame_shape(&data);
+            let photo = find_photo(&filename);
+            records.push(CaptureRecord {
+                camera_id: capture_camera(&filename),
+                sha256: sha256_hex(&data),
+                size_bytes: data.len() as u64,
+                filename,
+                turbine_token,
+                timestamp,
+                archived,
+                integrity_error: None,
+                ambient: None,
+                angle: None,
+                max_temp: None,
+                avg_temp: None,
+                anomaly_score: None,
+                starred: false,
+                calibration_version: None,
+                clock_skew_sec: None,
+                source: None,
+                width: shape.map(|(_, width)| width),
+                height: shape.map(|(height, _)| height),
+                frames: frame_points(&data),
+                received: None,
+                receipt_id: None,
+                analyses: BTreeMap::new(),
+                photo,
+                quality: None,
+                pixel_unit: None,
+            });
+        }
+    }
+    records.sort_by_key(|r| r.timestamp);
+    records
+}
+
+// Guarda el catálogo; un error solo se registra (ver try_save_catalog)
+pub fn save_catalog(records: &[CaptureRecord]) {
+    if let Err(e) = try_save_catalog(records) {
+        tracing::error!(path = %catalog_path().display(), error = %e, "❌ Error guardando catálogo");
+    }
+}
+
+// Escritura atómica y duradera (ver super::write_durable) para no corromper el catálogo:
+// el recibo de una subida solo se entrega cuando su entrada está en disco
+pub fn try_save_catalog(records: &[CaptureRecord]) -> std::io::Result<()> {
+    try_save_json(&catalog_path(), records).inspect_err(|_| record_fault(Fault::Catalog))
+}
@@ -0,0 +1,36 @@
+use super::{save_json, storage_root};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- COLECCIONES ---
+// Carpetas virtuales: grupos arbitrarios de capturas ("T42 investigación rodamiento")
+// con un id que se puede compartir. Solo guardan nombres de archivo; las capturas no se
+// mueven. Se guardan en cloud_storage/collections.json.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Nombres de archivo de las capturas, en el orden en que se añadieron
+    #[serde(default)]
+    pub captures: Vec<String>,
+    pub created: u64,
+    pub updated: u64,
+}
+
+pub fn collections_path() -> PathBuf {
+    storage_root().join("collections.json")
+}
+
+pub fn load_collections() -> Vec<Collection> {
+    std::fs::read_to_string(collections_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_collections(collections: &[Collection]) {
+    save_json(&collections_path(), collections, "colecciones");
+}
@@ -0,0 +1,231 @@
+use super::{append_jsonl, rewrite_jsonl, storage_root};
+use crate::{
+    secrets::{config_secrets, redact_config, REDACTED},
+    state::RemoteConfig,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    io::BufRead,
+    path::PathBuf,
+};
+
+// --- HISTORIAL DE CONFIGURACIÓN ---
+// Cada cambio de RemoteConfig (edición, restauración de respaldo o rollback) se guarda
+// como una versión numerada en cloud_storage/config_history.jsonl, con autor, fecha y
+// diferencias respecto a la anterior. Al arrancar se aplica la última versión.
+
+// Marca temporal de los secretos al buscar sus rutas; no puede ser un valor real
+const SECRET_MARK: &str = "\u{0}secret";
+
+// Rutas de los campos secretos de una configuración ("channels.0.secret"), sacadas de la
+// misma lista que usa la redacción (secrets::config_secrets)
+fn secret_paths(config: &RemoteConfig) -> HashSet<String> {
+    let mut marked = config.clone();
+    for secret in config_secrets(&mut marked) {
+        *secret = SECRET_MARK.to_string();
+    }
+    let mut paths = HashSet::new();
+    if let Ok(value) = serde_json::to_value(&marked) {
+        collect_marked("", &value, &mut paths);
+    }
+    paths
+}
+
+fn collect_marked(path: &str, value: &Value, paths: &mut HashSet<String>) {
+    match value {
+        Value::String(s) if s == SECRET_MARK => {
+            paths.insert(path.to_string());
+        }
+        Value::Object(map) => map.iter().for_each(|(k, v)| collect_marked(&child_path(path, k), v, paths)),
+        Value::Array(items) => items.iter().enumerate().for_each(|(i, v)| collect_marked(&child_path(path, &i.to_string()), v, paths)),
+        _ => {}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConfigChange {
+    // Ruta del campo ("levels.0.min_temp")
+    pub path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConfigVersion {
+    pub version: u64,
+    pub timestamp: u64,
+    // Quién hizo el cambio (cabecera X-User del dashboard), si se sabe
+    #[serde(default)]
+    pub author: Option<String>,
+    // "update", "restore" o "rollback"
+    pub source: String,
+    // Versión restaurada, si es un rollback
+    #[serde(default)]
+    pub rollback_of: Option<u64>,
+    pub changes: Vec<ConfigChange>,
+    pub config: RemoteConfig,
+}
+
+pub fn history_path() -> PathBuf {
+    storage_root().join("config_history.jsonl")
+}
+
+pub fn load_config_history() -> Vec<ConfigVersion> {
+    let Ok(file) = std::fs::File::open(history_path()) else { return Vec::new() };
+    std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| match serde_json::from_str(&line) {
+            Ok(version) => Some(version),
+            Err(e) => {
+                tracing::warn!(error = %e, "⚠️ Línea inválida en el historial de configuración");
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn append_config_version(version: &ConfigVersion) {
+    if let Err(e) = append_jsonl(&history_path(), [version]) {
+        tracing::error!(error = %e, version = version.version, "❌ Error guardando versión de configuración");
+    }
+}
+
+// Reescribe el historial completo (al cifrar los secretos de versiones antiguas)
+pub fn rewrite_config_history(history: &[ConfigVersion]) -> std::io::Result<()> {
+    rewrite_jsonl(&history_path(), history)
+}
+
+// Campos que cambian entre dos configuraciones (los secretos aparecen enmascarados)
+pub fn config_diff(old: &RemoteConfig, new: &RemoteConfig) -> Vec<ConfigChange> {
+    let (Ok(old_value), Ok(new_value)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut changes = Vec::new();
+    diff_values("", &old_value, &new_value, &mut changes);
+    mask_changes(&mut changes, old, new);
+    changes
+}
+
+// Historial tal como lo ve la API: cada configuración redactada y las diferencias
+// enmascaradas de nuevo, por si se guardaron antes de cubrir todos los secretos
+pub fn redacted_history(history: &[ConfigVersion]) -> Vec<ConfigVersion> {
+    let initial = RemoteConfig::default();
+    let mut previous = &initial;
+    history.iter().map(|version| {
+        let mut redacted = ConfigVersion { config: redact_config(&version.config), ..version.clone() };
+        mask_changes(&mut redacted.changes, previous, &version.config);
+        previous = &version.config;
+        redacted
+    }).collect()
+}
+
+// Enmascara en las diferencias los secretos de cualquiera de las dos configuraciones
+fn mask_changes(changes: &mut [ConfigChange], old: &RemoteConfig, new: &RemoteConfig) {
+    let mut secrets = secret_paths(old);
+    secrets.extend(secret_paths(new));
+    for change in changes {
+        change.old = mask(&change.path, &change.old, &secrets);
+        change.new = mask(&change.path, &change.new, &secrets);
+    }
+}
+
+// Enmascara los secretos de un valor, también los anidados (un canal añadido entero)
+fn mask(path: &str, value: &Value, secrets: &HashSet<String>) -> Value {
+    match value {
+        Value::String(s) if !s.is_empty() && secrets.contains(path) => Value::String(REDACTED.into()),
+        Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), mask(&child_path(path, k), v, secrets))).collect()),
+        Value::Array(items) => Value::Array(items.iter().enumerate().map(|(i, v)| mask(&child_path(path, &i.to_string()), v, secrets)).collect()),
+        other => other.clone(),
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<ConfigChange>) {
+    let child = |key: &str| child_path(path, key);
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, old_value) in a {
+                diff_values(&child(key), old_value, b.get(key).unwrap_or(&Value::Null), changes);
+            }
+            for (key, new_value) in b.iter().filter(|(k, _)| !a.contains_key(*k)) {
+                diff_values(&child(key), &Value::Null, new_value, changes);
+            }
+        }
+        // Listas de igual longitud se comparan elemento a elemento; si no, entera
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (old_value, new_value)) in a.iter().zip(b).enumerate() {
+                diff_values(&child(&i.to_string()), old_value, new_value, changes);
+            }
+        }
+        _ if old != new => changes.push(ConfigChange { path: path.to_string(), old: old.clone(), new: new.clone() }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn with_channels(channels: Value) -> RemoteConfig {
+        RemoteConfig { channels: serde_json::from_value(channels).unwrap(), ..RemoteConfig::default() }
+    }
+
+    fn leaks(changes: &[ConfigChange], secret: &str) -> bool {
+        serde_json::to_string(changes).unwrap().contains(secret)
+    }
+
+    #[test]
+    fn masks_webhook_signing_secrets() {
+        let old = with_channels(json!([{ "name": "ops", "type": "webhook", "url": "https://hooks.example/T0", "secret": "whsec_OLD" }]));
+        let new = with_channels(json!([{ "name": "ops", "type": "webhook", "url": "https://hooks.example/T0", "secret": "whsec_PLAINSIGNING" }]));
+        let changes = config_diff(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "channels.0.secret");
+        assert_eq!((changes[0].old.as_str(), changes[0].new.as_str()), (Some(REDACTED), Some(REDACTED)));
+
+        // Un canal añadido entero se compara como lista completa
+        let changes = config_diff(&RemoteConfig::default(), &new);
+        assert!(!leaks(&changes, "whsec_PLAINSIGNING") && !leaks(&changes, "hooks.example"));
+    }
+
+    fn with_work_orders(work_orders: Value) -> RemoteConfig {
+        RemoteConfig { work_orders: Some(serde_json::from_value(work_orders).unwrap()), ..RemoteConfig::default() }
+    }
+
+    #[test]
+    fn masks_work_order_credentials() {
+        let jira = with_work_orders(json!({ "type": "jira", "base_url": "https://acme.atlassian.net", "project_key": "MNT", "user": "bot@acme.com", "api_token": "ATATT-jira-token" }));
+        let servicenow = with_work_orders(json!({ "type": "servicenow", "instance_url": "https://acme.service-now.com", "user": "sentinel", "password": "sn-password" }));
+        let rotated = with_work_orders(json!({ "type": "servicenow", "instance_url": "https://acme.service-now.com", "user": "sentinel", "password": "sn-password-2" }));
+
+        let changes = config_diff(&RemoteConfig::default(), &jira);
+        assert!(!changes.is_empty() && !leaks(&changes, "ATATT-jira-token"));
+        let changes = config_diff(&jira, &servicenow);
+        assert!(!leaks(&changes, "ATATT-jira-token") && !leaks(&changes, "sn-password"));
+        let changes = config_diff(&servicenow, &rotated);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "work_orders.password");
+        assert_eq!((changes[0].old.as_str(), changes[0].new.as_str()), (Some(REDACTED), Some(REDACTED)));
+
+        // Diferencias guardadas en claro: el historial de la API las enmascara igual
+        let stored = ConfigVersion {
+            version: 1,
+            timestamp: 0,
+            author: None,
+            source: "update".into(),
+            rollback_of: None,
+            changes: vec![ConfigChange { path: "work_orders.api_token".into(), old: Value::Null, new: json!("ATATT-jira-token") }],
+            config: jira,
+        };
+        let history = redacted_history(&[stored]);
+        assert!(!leaks(&history[0].changes, "ATATT-jira-token"));
+        assert!(!serde_json::to_string(&history[0].config).unwrap().contains("ATATT-jira-token"));
+    }
+}
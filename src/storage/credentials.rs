@@ -0,0 +1,59 @@
+use super::{catalog::sha256_hex, save_json, storage_root};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- CREDENCIALES DE INGESTA ---
+// Claves con las que cada robot se identifica en /ingest (cabecera X-Ingest-Key). Solo se
+// guarda su SHA-256, en cloud_storage/credentials.json; la clave en claro se muestra una
+// única vez al emitirla. Al rotar, las anteriores siguen valiendo durante un periodo de
+// gracia para que el robot pueda recoger la nueva sin cortes.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IngestCredential {
+    pub turbine_token: String,
+    // Primeros caracteres de la clave, para reconocerla en listados y logs
+    pub key_id: String,
+    pub key_sha256: String,
+    pub created: u64,
+    // Fin del periodo de gracia de una clave sustituida (None = vigente)
+    #[serde(default)]
+    pub expires: Option<u64>,
+}
+
+impl IngestCredential {
+    pub fn valid_at(&self, now: u64) -> bool {
+        self.expires.is_none_or(|e| now < e)
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        sha256_hex(key.as_bytes()) == self.key_sha256
+    }
+}
+
+// Clave aleatoria nueva y su credencial
+pub fn generate_credential(turbine_token: &str, now: u64) -> (String, IngestCredential) {
+    let key = format!("sk_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let credential = IngestCredential {
+        turbine_token: turbine_token.to_string(),
+        key_id: key[..11].to_string(),
+        key_sha256: sha256_hex(key.as_bytes()),
+        created: now,
+        expires: None,
+    };
+    (key, credential)
+}
+
+pub fn credentials_path() -> PathBuf {
+    storage_root().join("credentials.json")
+}
+
+pub fn load_credentials() -> Vec<IngestCredential> {
+    std::fs::read_to_string(credentials_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_credentials(credentials: &[IngestCredential]) {
+    save_json(&credentials_path(), credentials, "credenciales de ingesta");
+}
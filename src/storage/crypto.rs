@@ -0,0 +1,121 @@
+use super::{
+    archive::archive_dir,
+    catalog::{parse_capture_name, sha256_hex},
+    quarantine::quarantine_dir,
+    storage_root, write_durable,
+};
+use crate::{secrets::load_key, settings::ServerSettings};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use std::{io, path::Path, sync::OnceLock};
+
+// --- CIFRADO DE LAS CAPTURAS EN REPOSO ---
+// Con --capture-key (o --capture-key-file, el fichero que monta el KMS o el gestor de
+// secretos) las capturas se guardan cifradas con AES-256-GCM: las nuevas, las que se
+// archivan o restauran, las de la cuarentena y las replicadas o importadas. El cifrado
+// va dentro de encode_capture / read_capture / open_capture, así que descargas, matrices,
+// análisis y exportaciones ven siempre el contenido original; las copias de seguridad
+// llevan los archivos tal cual, cifrados. Se cifra después de comprimir. Las capturas ya
+// guardadas en claro se siguen leyendo; el subcomando encrypt-storage las cifra.
+//
+// Formato: "SNTENC1\0" | id de la clave (8 bytes, su huella) | nonce (12) | datos cifrados
+
+const MAGIC: &[u8; 8] = b"SNTENC1\0";
+const KEY_ID_LEN: usize = 8;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + KEY_ID_LEN + NONCE_LEN;
+
+struct CaptureKey {
+    cipher: Aes256Gcm,
+    key_id: String,
+}
+
+// Una sola clave por proceso, fijada al arrancar
+static CAPTURE_KEY: OnceLock<CaptureKey> = OnceLock::new();
+
+// Carga la clave de las capturas; devuelve su id, o None si no hay (se guardan en claro)
+pub fn init_capture_key(settings: &ServerSettings) -> Result<Option<String>, String> {
+    let Some(key) = load_key(settings.capture_key.as_deref(), settings.capture_key_file.as_deref())? else {
+        return Ok(None);
+    };
+    let key_id = sha256_hex(&key)[..KEY_ID_LEN].to_string();
+    let capture_key = CaptureKey { cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)), key_id: key_id.clone() };
+    CAPTURE_KEY.set(capture_key).map_err(|_| "capture key already initialized".to_string())?;
+    Ok(Some(key_id))
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+// Cifra una captura ya codificada; sin clave se devuelve tal cual
+pub fn encrypt(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    let Some(key) = CAPTURE_KEY.get() else { return Ok(data) };
+    let nonce = Aes256Gcm::generate_nonce(OsRng);
+    // Nunca se guarda una cabecera sin datos: si el cifrado falla, falla la escritura
+    let ciphertext = key.cipher.encrypt(&nonce, data.as_slice())
+        .map_err(|_| io::Error::other("capture encryption failed"))?;
+    Ok([MAGIC.as_slice(), key.key_id.as_bytes(), nonce.as_slice(), &ciphertext].concat())
+}
+
+pub fn decrypt(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < HEADER_LEN || !is_encrypted(data) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an encrypted capture"));
+    }
+    let key = CAPTURE_KEY.get()
+        .ok_or_else(|| io::Error::other("capture is encrypted but no capture key is configured"))?;
+    let (key_id, rest) = data[MAGIC.len()..].split_at(KEY_ID_LEN);
+    if key_id != key.key_id.as_bytes() {
+        return Err(io::Error::other(format!(
+            "capture was encrypted with another key ({})",
+            String::from_utf8_lossy(key_id)
+        )));
+    }
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    key.cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "capture failed authentication (corrupt or tampered)"))
+}
+
+// Cifra un archivo en claro en su sitio; false si ya estaba cifrado
+fn encrypt_file(path: &Path) -> io::Result<bool> {
+    let data = std::fs::read(path)?;
+    if is_encrypted(&data) {
+        return Ok(false);
+    }
+    write_durable(path, &encrypt(data)?)?;
+    Ok(true)
+}
+
+// Subcomando de migración: cifra las capturas guardadas en claro (activas, archivadas y
+// en cuarentena)
+pub fn encrypt_storage() {
+    if CAPTURE_KEY.get().is_none() {
+        tracing::warn!("⚠️ Sin --capture-key no hay nada que cifrar.");
+        return;
+    }
+    let (mut encrypted, mut failed) = (0, 0);
+    for (dir, quarantine) in [(storage_root(), false), (archive_dir(), false), (quarantine_dir(), true)] {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let capture = match quarantine {
+                true => filename.ends_with(".npz"),
+                false => parse_capture_name(&filename).is_some(),
+            };
+            if !capture {
+                continue;
+            }
+            match encrypt_file(&entry.path()) {
+                Ok(true) => encrypted += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    failed += 1;
+                    tracing::error!(path = %entry.path().display(), error = %e, "❌ Error cifrando captura");
+                }
+            }
+        }
+    }
+    tracing::info!(encrypted, failed, "🔐 Capturas cifradas");
+}
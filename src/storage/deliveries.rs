@@ -0,0 +1,23 @@
+use super::{save_json, storage_root};
+use crate::notify::Delivery;
+use std::path::PathBuf;
+
+// --- ENTREGAS FALLIDAS ---
+// Notificaciones que agotaron sus reintentos, pendientes de reenvío manual desde
+// /api/notifications/failed. Se guardan en cloud_storage/failed_deliveries.json para que
+// un reinicio no las pierda.
+
+pub fn failed_deliveries_path() -> PathBuf {
+    storage_root().join("failed_deliveries.json")
+}
+
+pub fn load_failed_deliveries() -> Vec<Delivery> {
+    std::fs::read_to_string(failed_deliveries_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_failed_deliveries(deliveries: &[Delivery]) {
+    save_json(&failed_deliveries_path(), deliveries, "entregas fallidas");
+}
@@ -0,0 +1,81 @@
+use super::{archive::stored_path, catalog::CaptureRecord, read_capture};
+use axum::body::Bytes;
+use std::io::Write;
+use tokio::sync::mpsc::Sender;
+
+// --- EXPORTACIÓN COMPLETA EN TAR ---
+// El tar se genera en el pool bloqueante y se envía por trozos al cuerpo de la respuesta
+// a medida que se escribe: nunca se guarda el archivo entero en disco ni en memoria.
+// Estructura: manifest.json (registros del catálogo exportados) y captures/<archivo> con
+// los bytes originales (sin zstd) de cada captura.
+
+// Tamaño de los trozos que se envían al cliente
+const CHUNK_BYTES: usize = 256 * 1024;
+
+// Writer que reenvía lo escrito por un canal; si el cliente corta la descarga el envío
+// falla y el tar se aborta con BrokenPipe
+pub struct ChannelWriter {
+    tx: Sender<std::io::Result<Bytes>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    pub fn new(tx: Sender<std::io::Result<Bytes>>) -> Self {
+        ChannelWriter { tx, buffer: Vec::with_capacity(CHUNK_BYTES) }
+    }
+
+    fn send_buffer(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_BYTES)));
+        self.tx.blocking_send(Ok(chunk))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "export client disconnected"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= CHUNK_BYTES {
+            self.send_buffer()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.send_buffer()
+    }
+}
+
+fn append_bytes<W: Write>(builder: &mut tar::Builder<W>, name: &str, mtime: u64, data: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data)
+}
+
+// Escribe el tar completo; las capturas ilegibles se omiten (ya constan en el manifiesto
+// con su error de integridad, si lo hay) para no cortar una exportación larga
+pub fn write_export<W: Write>(records: &[CaptureRecord], writer: W) -> std::io::Result<usize> {
+    let mut builder = tar::Builder::new(writer);
+    let now = chrono::Utc::now().timestamp() as u64;
+    append_bytes(&mut builder, "manifest.json", now, &serde_json::to_vec_pretty(records)?)?;
+
+    let mut exported = 0;
+    for record in records {
+        let data = match read_capture(&stored_path(record)) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(filename = %record.filename, error = %e, "⚠️ Exportación: captura ilegible, se omite");
+                continue;
+            }
+        };
+        append_bytes(&mut builder, &format!("captures/{}", record.filename), record.timestamp, &data)?;
+        exported += 1;
+    }
+    builder.into_inner()?.flush()?;
+    Ok(exported)
+}
@@ -0,0 +1,23 @@
+use super::{save_json, storage_root};
+use crate::notify::HeldNotification;
+use std::path::PathBuf;
+
+// --- NOTIFICACIONES RETENIDAS ---
+// Avisos no críticos retenidos durante las horas de silencio de su canal, pendientes del
+// resumen que se envía al terminar (ver notify::HORAS DE SILENCIO). Se guardan en
+// cloud_storage/held_notifications.json para que un reinicio no se los salte.
+
+pub fn held_notifications_path() -> PathBuf {
+    storage_root().join("held_notifications.json")
+}
+
+pub fn load_held_notifications() -> Vec<HeldNotification> {
+    std::fs::read_to_string(held_notifications_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_held_notifications(held: &[HeldNotification]) {
+    save_json(&held_notifications_path(), held, "notificaciones retenidas");
+}
@@ -0,0 +1,235 @@
+use super::{
+    cameras::valid_camera_id,
+    catalog::{capture_filename, sha256_hex, CaptureRecord},
+    encode_capture, is_zstd,
+    paths::{check_file_name, safe_resolve},
+    storage_root, write_durable,
+};
+use crate::{
+    analysis::{capture_stats, frame_points, frame_shape, image_quality::capture_quality},
+    state::AppState,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    path::Path,
+};
+
+// --- IMPORTACIÓN MASIVA DE CAPTURAS HISTÓRICAS ---
+// Ingesta directa (sin pasar por /ingest/upload) de un directorio o un .tar con capturas
+// de sistemas anteriores. Turbina y timestamp salen de un manifest.json en la raíz o, si
+// no está el archivo en él, del nombre: "[capture_]{token}_{timestamp}.npz|.npy" con el
+// timestamp en segundos Unix o como 20240131T154500 (UTC). Se calculan las estadísticas
+// básicas; anomalías y alertas se recalculan después con /api/admin/replay.
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Deserialize, Clone)]
+pub struct ManifestEntry {
+    // Ruta relativa dentro del directorio o del tar
+    pub file: String,
+    pub turbine_token: String,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub angle: Option<f32>,
+    #[serde(default)]
+    pub camera_id: Option<String>,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct ImportSummary {
+    pub imported: usize,
+    // Mismo contenido ya presente en el catálogo
+    pub duplicates: usize,
+    // Sin turbina/timestamp reconocibles, ilegibles o en conflicto con una captura existente
+    pub skipped: usize,
+}
+
+// Extrae (turbine_token, timestamp) de un nombre de archivo histórico
+pub fn parse_import_name(name: &str) -> Option<(String, u64)> {
+    let name = name.rsplit('/').next()?;
+    let stem = name.strip_suffix(".npz").or_else(|| name.strip_suffix(".npy"))?;
+    let stem = stem.strip_prefix("capture_").unwrap_or(stem);
+    let (token, timestamp) = stem.rsplit_once('_')?;
+    if token.is_empty() {
+        return None;
+    }
+    let timestamp = match timestamp.parse::<u64>() {
+        Ok(ts) if timestamp.len() <= 10 => ts,
+        _ => chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%dT%H%M%S")
+            .or_else(|_| chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d%H%M%S"))
+            .ok()?
+            .and_utc()
+            .timestamp()
+            .try_into()
+            .ok()?,
+    };
+    Some((token.to_string(), timestamp))
+}
+
+// Recorre el origen entregando (ruta relativa, bytes) de cada archivo
+fn for_each_file(source: &Path, mut visit: impl FnMut(String, Vec<u8>)) -> std::io::Result<()> {
+    if source.is_dir() {
+        let mut pending = vec![source.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)?.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(source) else { continue };
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                match std::fs::read(&path) {
+                    Ok(data) => visit(relative, data),
+                    Err(e) => tracing::warn!(path = %path.display(), error = %e, "⚠️ Importación: archivo ilegible"),
+                }
+            }
+        }
+    } else {
+        let mut archive = tar::Archive::new(std::fs::File::open(source)?);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            visit(name, data);
+        }
+    }
+    Ok(())
+}
+
+fn read_manifest(source: &Path) -> std::io::Result<HashMap<String, ManifestEntry>> {
+    let mut manifest = None;
+    if source.is_dir() {
+        if let Ok(data) = std::fs::read(source.join(MANIFEST_NAME)) {
+            manifest = Some(data);
+        }
+    } else {
+        for_each_file(source, |name, data| {
+            if name == MANIFEST_NAME {
+                manifest = Some(data);
+            }
+        })?;
+    }
+    let Some(data) = manifest else { return Ok(HashMap::new()) };
+    let entries: Vec<ManifestEntry> = serde_json::from_slice(&data)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("manifest.json: {}", e)))?;
+    Ok(entries.into_iter().map(|e| (e.file.trim_start_matches("./").to_string(), e)).collect())
+}
+
+// Se llama desde el pool bloqueante; el catálogo se actualiza una sola vez al final
+pub fn import_captures(state: &AppState, source: &Path) -> std::io::Result<ImportSummary> {
+    let manifest = read_manifest(source)?;
+    let (mut known, mut names): (HashSet<(String, String)>, HashSet<String>) = state.catalog.blocking_read().iter()
+        .map(|r| ((r.turbine_token.clone(), r.sha256.clone()), r.filename.clone()))
+        .unzip();
+
+    let mut summary = ImportSummary::default();
+    let mut records: Vec<CaptureRecord> = Vec::new();
+    for_each_file(source, |name, data| {
+        if name == MANIFEST_NAME {
+            return;
+        }
+        let (turbine_token, timestamp, angle, camera_id) = match manifest.get(&name) {
+            Some(entry) => (entry.turbine_token.clone(), entry.timestamp, entry.angle, entry.camera_id.clone()),
+            None => match parse_import_name(&name) {
+                Some((token, timestamp)) => (token, timestamp, None, None),
+                None => {
+                    tracing::debug!(file = %name, "Importación: nombre sin turbina/timestamp, se omite");
+                    summary.skipped += 1;
+                    return;
+                }
+            },
+        };
+        if check_file_name(&turbine_token).is_err()
+            || camera_id.as_deref().is_some_and(|c| !valid_camera_id(c))
+        {
+            summary.skipped += 1;
+            return;
+        }
+        // Se guarda siempre descomprimido en memoria para el hash y las estadísticas
+        let data = match is_zstd(&data) {
+            true => match zstd::decode_all(&data[..]) {
+                Ok(decoded) => decoded,
+                Err(_) => {
+                    summary.skipped += 1;
+                    return;
+                }
+            },
+            false => data,
+        };
+        let Some(stats) = capture_stats(&data) else {
+            tracing::warn!(file = %name, "⚠️ Importación: captura ilegible, se omite");
+            summary.skipped += 1;
+            return;
+        };
+        let shape = frame_shape(&data);
+        let sha256 = sha256_hex(&data);
+        if !known.insert((turbine_token.clone(), sha256.clone())) {
+            summary.duplicates += 1;
+            return;
+        }
+
+        let filename = capture_filename(&turbine_token, timestamp, camera_id.as_deref());
+        let Ok(path) = safe_resolve(&storage_root(), &filename) else {
+            summary.skipped += 1;
+            return;
+        };
+        if path.exists() || !names.insert(filename.clone()) {
+            // Misma turbina y segundo con otro contenido: no se pisa la captura existente
+            tracing::warn!(file = %name, %filename, "⚠️ Importación: ya existe una captura con ese nombre");
+            summary.skipped += 1;
+            return;
+        }
+        let result = encode_capture(&data, state.settings.zstd_level)
+            .and_then(|encoded| write_durable(&path, &encoded));
+        if let Err(e) = result {
+            tracing::error!(path = %path.display(), error = %e, "❌ Error escribiendo captura importada");
+            summary.skipped += 1;
+            return;
+        }
+        records.push(CaptureRecord {
+            filename,
+            turbine_token,
+            timestamp,
+            sha256,
+            size_bytes: data.len() as u64,
+            archived: false,
+            integrity_error: None,
+            ambient: None,
+            angle,
+            max_temp: Some(stats.max_temp),
+            avg_temp: Some(stats.avg_temp),
+            anomaly_score: None,
+            starred: false,
+            camera_id,
+            calibration_version: None,
+            clock_skew_sec: None,
+            source: None,
+            width: shape.map(|(_, width)| width),
+            height: shape.map(|(height, _)| height),
+            frames: frame_points(&data),
+            received: Some(chrono::Utc::now().timestamp() as u64),
+            receipt_id: None,
+            analyses: Default::default(),
+            photo: None,
+            quality: capture_quality(&data),
+            pixel_unit: None,
+        });
+        summary.imported += 1;
+    })?;
+
+    if !records.is_empty() {
+        // El historial por turbina asume el catálogo en orden cronológico
+        let mut catalog = state.catalog.blocking_write();
+        catalog.extend(records);
+        catalog.sort_by_key(|r| r.timestamp);
+        super::catalog::save_catalog(&catalog);
+    }
+    Ok(summary)
+}
@@ -0,0 +1,92 @@
+use super::{
+    archive::stored_path,
+    catalog::{save_catalog, sha256_hex, CaptureRecord},
+    read_capture,
+};
+use crate::{analysis::frame_shape, state::AppState};
+use serde::Serialize;
+use std::collections::HashMap;
+
+// --- ESCANEO DE INTEGRIDAD ---
+// Relee cada captura, la descomprime y compara su SHA-256 con el del catálogo.
+// Detecta archivos truncados o corruptos (p. ej. tras un corte de energía). De paso
+// completa la resolución de las capturas catalogadas antes de registrarla.
+
+#[derive(Serialize, Clone, Default)]
+pub struct IntegrityScanSummary {
+    pub last_scan: Option<u64>,
+    pub checked: usize,
+}
+
+#[derive(Serialize)]
+pub struct IntegrityIssue {
+    pub filename: String,
+    pub turbine_token: String,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct IntegrityReport {
+    pub last_scan: Option<u64>,
+    pub checked: usize,
+    pub corrupt: Vec<IntegrityIssue>,
+}
+
+// Devuelve (alto, ancho) de los frames si la captura está bien
+pub fn verify_capture(record: &CaptureRecord) -> Result<Option<(usize, usize)>, String> {
+    let data = read_capture(&stored_path(record)).map_err(|e| format!("unreadable: {}", e))?;
+    if sha256_hex(&data) != record.sha256 {
+        return Err(format!("checksum mismatch ({} bytes on disk, {} expected)", data.len(), record.size_bytes));
+    }
+    Ok(frame_shape(&data))
+}
+
+pub fn run_integrity_scan(state: &AppState) {
+    let records = state.catalog.blocking_read().clone();
+    let results: HashMap<String, Result<Option<(usize, usize)>, String>> = records.iter()
+        .map(|r| (r.filename.clone(), verify_capture(r)))
+        .collect();
+    let corrupt = results.values().filter(|r| r.is_err()).count();
+
+    {
+        let mut catalog = state.catalog.blocking_write();
+        for record in catalog.iter_mut() {
+            match results.get(&record.filename) {
+                Some(Ok(shape)) => {
+                    record.integrity_error = None;
+                    if record.shape().is_none()
+                        && let Some((height, width)) = *shape
+                    {
+                        record.width = Some(width);
+                        record.height = Some(height);
+                    }
+                }
+                Some(Err(error)) => record.integrity_error = Some(error.clone()),
+                None => {}
+            }
+        }
+        save_catalog(&catalog);
+    }
+    *state.integrity.blocking_write() = IntegrityScanSummary {
+        last_scan: Some(chrono::Utc::now().timestamp() as u64),
+        checked: records.len(),
+    };
+    if corrupt > 0 {
+        tracing::warn!(corrupt, checked = records.len(), "⚠️ Escaneo de integridad con capturas corruptas");
+    } else {
+        tracing::info!(checked = records.len(), "✅ Escaneo de integridad completado");
+    }
+}
+
+// Informe con el resultado del último escaneo y las capturas marcadas como corruptas
+pub async fn integrity_report(state: &AppState) -> IntegrityReport {
+    let summary = state.integrity.read().await.clone();
+    let corrupt = state.catalog.read().await.iter()
+        .filter_map(|r| r.integrity_error.as_ref().map(|error| IntegrityIssue {
+            filename: r.filename.clone(),
+            turbine_token: r.turbine_token.clone(),
+            error: error.clone(),
+        }))
+        .collect();
+    IntegrityReport { last_scan: summary.last_scan, checked: summary.checked, corrupt }
+}
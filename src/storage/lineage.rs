@@ -0,0 +1,110 @@
+use super::{append_jsonl, catalog::sha256_hex, purge_jsonl, storage_root};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    io::BufRead,
+    path::PathBuf,
+};
+
+// --- LINAJE DE LOS ARTEFACTOS DERIVADOS ---
+// Qué operación produjo cada artefacto derivado de las capturas (renders y miniaturas,
+// diferencias, estadísticas por pala, clasificaciones, informes), con sus capturas de
+// entrada (y el sha256 que tenían) y los parámetros, una línea JSON por derivación en
+// cloud_storage/lineage.jsonl. Lo que se genera bajo demanda se registra una sola vez por
+// combinación de operación, entradas y parámetros.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LineageInput {
+    pub filename: String,
+    // Contenido de la captura en el momento de la derivación
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_index: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Derivation {
+    // Hash de la operación, las entradas, los parámetros y la salida
+    pub id: String,
+    // "render", "thumbnail", "diff", "blades", "fault_classification", "report"
+    pub operation: String,
+    pub inputs: Vec<LineageInput>,
+    #[serde(default)]
+    pub params: serde_json::Value,
+    // Archivo generado, si se guarda (informes)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    pub created: u64,
+}
+
+impl Derivation {
+    pub fn uses(&self, filename: &str) -> bool {
+        self.inputs.iter().any(|input| input.filename == filename)
+    }
+}
+
+pub fn lineage_path() -> PathBuf {
+    storage_root().join("lineage.jsonl")
+}
+
+pub fn load_lineage() -> Vec<Derivation> {
+    let Ok(file) = std::fs::File::open(lineage_path()) else {
+        return Vec::new();
+    };
+    std::io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        // Una línea a medias (corte durante la escritura) no invalida el resto
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+// Quita del registro las derivaciones que usan alguna de `captures`; devuelve cuántas
+pub fn purge_derivations(captures: &HashSet<String>) -> std::io::Result<usize> {
+    purge_jsonl(&lineage_path(), |d: &Derivation| d.inputs.iter().any(|input| captures.contains(&input.filename)))
+}
+
+fn append_derivation(derivation: &Derivation) {
+    if let Err(e) = append_jsonl(&lineage_path(), [derivation]) {
+        tracing::error!(error = %e, operation = %derivation.operation, "❌ Error escribiendo el linaje");
+    }
+}
+
+// Registra una derivación (si no estaba ya); las entradas son (captura, frame)
+pub async fn record_derivation(
+    state: &AppState,
+    operation: &str,
+    inputs: &[(&str, Option<usize>)],
+    params: serde_json::Value,
+    output: Option<String>,
+) {
+    let inputs: Vec<LineageInput> = {
+        let catalog = state.catalog.read().await;
+        inputs.iter()
+            .map(|&(filename, frame_index)| LineageInput {
+                filename: filename.to_string(),
+                sha256: catalog.iter().find(|r| r.filename == filename).map(|r| r.sha256.clone()),
+                frame_index,
+            })
+            .collect()
+    };
+    let key = serde_json::json!([operation, inputs, params, output]).to_string();
+    let id = sha256_hex(key.as_bytes())[..16].to_string();
+    let mut lineage = state.lineage.write().await;
+    if lineage.iter().any(|d| d.id == id) {
+        return;
+    }
+    let derivation = Derivation {
+        id,
+        operation: operation.to_string(),
+        inputs,
+        params,
+        output,
+        created: chrono::Utc::now().timestamp() as u64,
+    };
+    append_derivation(&derivation);
+    lineage.push(derivation);
+}
@@ -0,0 +1,350 @@
+use crate::{
+    calibration::CameraCalibration,
+    notify::{Delivery, HeldNotification},
+    reports::ReportSchedule,
+    rules::AlertRule,
+    state::AppState,
+    watchdog::{record_fault, Fault},
+};
+use annotations::Annotation;
+use cameras::CameraInfo;
+use catalog::CaptureRecord;
+use collections::Collection;
+use config_history::ConfigVersion;
+use credentials::IngestCredential;
+use lineage::Derivation;
+use memmap2::Mmap;
+use preferences::UserPreferences;
+use push::PushRegistry;
+use quarantine::QuarantinedUpload;
+use shares::SharedSnapshot;
+use registry::TurbineInfo;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::File,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+pub mod alert_log;
+pub mod annotations;
+pub mod archive;
+pub mod audit;
+pub mod backup;
+pub mod calibrations;
+pub mod cameras;
+pub mod catalog;
+pub mod collections;
+pub mod config_history;
+pub mod credentials;
+pub mod crypto;
+pub mod deliveries;
+pub mod export;
+pub mod held;
+pub mod import;
+pub mod integrity;
+pub mod lineage;
+pub mod package;
+pub mod paths;
+pub mod photos;
+pub mod preferences;
+pub mod push;
+pub mod quality;
+pub mod quarantine;
+pub mod registry;
+pub mod replication;
+pub mod reports;
+pub mod rules;
+pub mod sensors;
+pub mod shares;
+pub mod timeline;
+pub mod usage;
+
+// --- CAPA DE ALMACENAMIENTO ---
+// Las capturas pueden estar comprimidas con zstd y cifradas (ver crypto); la lectura
+// detecta el formato por los bytes mágicos, así que los endpoints siempre ven el
+// contenido original.
+
+// Carpeta raíz de las capturas, el catálogo y el archivo frío
+pub const STORAGE_ROOT: &str = "cloud_storage";
+
+pub fn storage_root() -> PathBuf {
+    PathBuf::from(STORAGE_ROOT)
+}
+
+// Todo lo que se restaura de disco al arrancar
+#[derive(Default)]
+pub struct PersistedData {
+    pub catalog: Vec<CaptureRecord>,
+    pub turbines: Vec<TurbineInfo>,
+    pub config_history: Vec<ConfigVersion>,
+    pub collections: Vec<Collection>,
+    pub failed_deliveries: Vec<Delivery>,
+    pub held_notifications: Vec<HeldNotification>,
+    pub cameras: Vec<CameraInfo>,
+    pub calibrations: Vec<CameraCalibration>,
+    pub push: PushRegistry,
+    pub credentials: Vec<IngestCredential>,
+    pub rules: Vec<AlertRule>,
+    pub annotations: Vec<Annotation>,
+    pub report_schedules: Vec<ReportSchedule>,
+    pub lineage: Vec<Derivation>,
+    pub quarantine: Vec<QuarantinedUpload>,
+    pub preferences: Vec<UserPreferences>,
+    pub shares: Vec<SharedSnapshot>,
+}
+
+impl PersistedData {
+    pub fn load() -> Self {
+        PersistedData {
+            catalog: catalog::load_catalog(),
+            turbines: registry::load_registry(),
+            config_history: config_history::load_config_history(),
+            collections: collections::load_collections(),
+            failed_deliveries: deliveries::load_failed_deliveries(),
+            held_notifications: held::load_held_notifications(),
+            cameras: cameras::load_cameras(),
+            calibrations: calibrations::load_calibrations(),
+            push: push::load_push_registry(),
+            credentials: credentials::load_credentials(),
+            rules: rules::load_rules(),
+            annotations: annotations::load_annotations(),
+            report_schedules: reports::load_report_schedules(),
+            lineage: lineage::load_lineage(),
+            quarantine: quarantine::load_quarantine(),
+            preferences: preferences::load_preferences(),
+            shares: shares::load_shares(),
+        }
+    }
+}
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+pub fn is_zstd(data: &[u8]) -> bool {
+    data.starts_with(&ZSTD_MAGIC)
+}
+
+// Lee una captura devolviendo siempre los bytes originales (descifrados y descomprimidos)
+pub fn read_capture(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut data = std::fs::read(path)?;
+    if crypto::is_encrypted(&data) {
+        data = crypto::decrypt(&data)?;
+    }
+    if is_zstd(&data) {
+        zstd::decode_all(Cursor::new(data))
+    } else {
+        Ok(data)
+    }
+}
+
+// Captura abierta para lectura de frames: mapeada en memoria si está sin comprimir ni
+// cifrar, o descifrada y descomprimida en RAM
+pub enum CaptureBytes {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl std::ops::Deref for CaptureBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            CaptureBytes::Mapped(mmap) => mmap,
+            CaptureBytes::Owned(data) => data,
+        }
+    }
+}
+
+pub fn open_capture(path: &Path) -> std::io::Result<CaptureBytes> {
+    let file = File::open(path)?;
+    // SAFETY: las capturas nunca se modifican in situ; se escriben una vez o se
+    // reemplazan atómicamente con rename, así que el mapeo no cambia bajo nuestros pies.
+    let mmap = unsafe { Mmap::map(&file)? };
+    if crypto::is_encrypted(&mmap) {
+        let data = crypto::decrypt(&mmap)?;
+        return Ok(CaptureBytes::Owned(if is_zstd(&data) { zstd::decode_all(&data[..])? } else { data }));
+    }
+    if is_zstd(&mmap) {
+        Ok(CaptureBytes::Owned(zstd::decode_all(&mmap[..])?))
+    } else {
+        Ok(CaptureBytes::Mapped(mmap))
+    }
+}
+
+// Codifica una captura para disco según el nivel configurado (0 = sin comprimir), cifrada
+// si hay clave de capturas
+pub fn encode_capture(data: &[u8], zstd_level: i32) -> std::io::Result<Vec<u8>> {
+    let encoded = if zstd_level == 0 {
+        data.to_vec()
+    } else {
+        zstd::encode_all(data, zstd_level)?
+    };
+    crypto::encrypt(encoded)
+}
+
+// Escritura atómica y duradera: el contenido (fsync) y la entrada del directorio (fsync
+// de la carpeta tras el rename) están en disco al volver, aunque se corte la luz después.
+// La usan las capturas, los informes y todos los índices JSON (ver save_json); los fallos
+// cuentan para el watchdog.
+pub fn write_durable(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let result = write_and_sync(path, bytes);
+    if result.is_err() {
+        record_fault(Fault::DiskWrite);
+    }
+    result
+}
+
+fn write_and_sync(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    sync_dir(path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new(".")))
+}
+
+// fsync de una carpeta: deja en disco las entradas creadas o renombradas en ella
+pub fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+// --- ÍNDICES JSON Y REGISTROS JSONL ---
+// Todo lo que se persiste en JSON pasa por aquí: los índices completos se reescriben con
+// write_durable y las líneas de los registros se añaden con fsync, de modo que nada se
+// pierde en un corte de luz y todos los fallos cuentan para el watchdog.
+
+pub fn try_save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> std::io::Result<()> {
+    let json = serde_json::to_vec_pretty(value).map_err(std::io::Error::other)?;
+    write_durable(path, &json)
+}
+
+// Como try_save_json, pero un error solo se registra; `what` lo identifica en el log
+// ("registro de turbinas"...)
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) {
+    if let Err(e) = try_save_json(path, value) {
+        tracing::error!(path = %path.display(), error = %e, "❌ Error guardando {}", what);
+    }
+}
+
+// Añade una línea JSON por elemento al final del registro
+pub fn append_jsonl<'a, T: Serialize + 'a>(path: &Path, items: impl IntoIterator<Item = &'a T>) -> std::io::Result<()> {
+    let mut lines = Vec::new();
+    for item in items {
+        serde_json::to_writer(&mut lines, item)?;
+        lines.push(b'\n');
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let result = std::fs::OpenOptions::new().create(true).append(true).open(path)
+        .and_then(|mut file| {
+            file.write_all(&lines)?;
+            file.sync_data()
+        });
+    if result.is_err() {
+        record_fault(Fault::DiskWrite);
+    }
+    result
+}
+
+// Reescribe el registro completo
+pub fn rewrite_jsonl<'a, T: Serialize + 'a>(path: &Path, items: impl IntoIterator<Item = &'a T>) -> std::io::Result<()> {
+    let mut content = Vec::new();
+    for item in items {
+        serde_json::to_writer(&mut content, item)?;
+        content.push(b'\n');
+    }
+    write_durable(path, &content)
+}
+
+// Quita del registro las líneas que cumplen `remove` (las ilegibles se conservan);
+// devuelve cuántas se borraron
+pub fn purge_jsonl<T: DeserializeOwned>(path: &Path, remove: impl Fn(&T) -> bool) -> std::io::Result<usize> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut removed = 0;
+    let mut kept = String::with_capacity(content.len());
+    for line in content.lines() {
+        if serde_json::from_str::<T>(line).is_ok_and(|item| remove(&item)) {
+            removed += 1;
+        } else {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    if removed > 0 {
+        write_durable(path, kept.as_bytes())?;
+    }
+    Ok(removed)
+}
+
+// Subcomando de migración: comprime las capturas existentes en el sitio
+pub fn compress_storage(zstd_level: i32) {
+    if zstd_level == 0 {
+        tracing::warn!("⚠️ El nivel zstd es 0, no hay nada que comprimir.");
+        return;
+    }
+    let (mut compressed, mut saved_bytes) = (0, 0u64);
+    if let Ok(entries) = std::fs::read_dir(storage_root()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let filename = entry.file_name().to_string_lossy().to_string();
+            if catalog::parse_capture_name(&filename).is_none() {
+                continue;
+            }
+            let Ok(data) = std::fs::read(&path) else { continue };
+            // Las cifradas ya se comprimieron (o no) al guardarlas
+            if is_zstd(&data) || crypto::is_encrypted(&data) {
+                continue;
+            }
+            let result = encode_capture(&data, zstd_level)
+                .and_then(|encoded| write_durable(&path, &encoded).map(|_| encoded.len() as u64));
+            match result {
+                Ok(size) => {
+                    compressed += 1;
+                    saved_bytes += (data.len() as u64).saturating_sub(size);
+                }
+                Err(e) => tracing::error!(path = %path.display(), error = %e, "❌ Error comprimiendo captura"),
+            }
+        }
+    }
+    tracing::info!(compressed, saved_kb = saved_bytes / 1024, "🗜️ Capturas comprimidas");
+}
+
+// Tareas periódicas de mantenimiento (archivado e integridad) en el pool bloqueante
+pub fn spawn_maintenance_tasks(state: &Arc<AppState>) {
+    // Tarea periódica de archivado de capturas antiguas
+    if state.settings.archive_after_days > 0 {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                let state = state.clone();
+                let _ = tokio::task::spawn_blocking(move || archive::archive_old_captures(&state)).await;
+            }
+        });
+    }
+
+    // Escaneo periódico de integridad (checksums de las capturas almacenadas)
+    if state.settings.integrity_scan_hours > 0 {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let period = Duration::from_secs(state.settings.integrity_scan_hours * 3600);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let state = state.clone();
+                let _ = tokio::task::spawn_blocking(move || integrity::run_integrity_scan(&state)).await;
+            }
+        });
+    }
+}
@@ -0,0 +1,195 @@
+use super::{
+    archive::stored_path,
+    catalog::CaptureRecord,
+    photos::photo_path,
+    read_capture,
+    registry::TurbineInfo,
+};
+use crate::{
+    analysis::{
+        hottest_frame,
+        thumbnail::{encode_png, render_rgb},
+        tracking::{frame_blobs, hottest_blob, Blob},
+    },
+    state::AlertRecord,
+};
+use chrono::{Datelike, Timelike};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Write},
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+// --- PAQUETE ESTRUCTURADO DE UNA INSPECCIÓN ---
+// Una sesión de escaneo (ver crate::sessions) empaquetada para los sistemas de gestión
+// de activos: un zip con manifest.json (turbina, sesión, y por captura sus metadatos,
+// estadísticas, punto caliente, análisis y alertas) y en images/ el frame más caliente de
+// cada captura en PNG (paleta ironbow) y su foto visible, si la hay. Las rutas de las
+// imágenes en el manifiesto son relativas al zip. Temperaturas en °C; las del punto
+// caliente salen del frame en bruto, sin calibrar, igual que el analizador de hotspots.
+
+pub const PACKAGE_FORMAT: &str = "sentinel-inspection";
+pub const PACKAGE_VERSION: u32 = 1;
+// Lado mayor de las imágenes térmicas del paquete
+const PACKAGE_RENDER_SIDE: usize = 1024;
+
+#[derive(Serialize)]
+pub struct PackageManifest {
+    pub format: &'static str,
+    pub version: u32,
+    pub generated: u64,
+    pub inspection: Inspection,
+    pub captures: Vec<PackagedCapture>,
+}
+
+#[derive(Serialize)]
+pub struct Inspection {
+    // Primera captura de la sesión: identifica la inspección de forma estable
+    pub id: String,
+    pub turbine_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turbine: Option<TurbineInfo>,
+    pub start: u64,
+    pub end: u64,
+    pub capture_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_temp: Option<f32>,
+    pub alert_count: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct PackageImages {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thermal: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PackagedCapture {
+    pub filename: String,
+    pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub angle: Option<f32>,
+    pub sha256: String,
+    pub size_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_temp: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_temp: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anomaly_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calibration_version: Option<u64>,
+    // Frame más caliente, el de la imagen térmica y el punto caliente
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hotspot: Option<Blob>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub analyses: BTreeMap<String, serde_json::Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<AlertRecord>,
+    pub images: PackageImages,
+    // Por qué falta la imagen térmica o la foto (captura o foto ilegible)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+fn zip_time(timestamp: u64) -> zip::DateTime {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .and_then(|t| {
+            let (year, month, day) = (t.year() as u16, t.month() as u8, t.day() as u8);
+            zip::DateTime::from_date_and_time(year, month, day, t.hour() as u8, t.minute() as u8, t.second() as u8).ok()
+        })
+        .unwrap_or_default()
+}
+
+fn add_file(zip: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, timestamp: u64, method: CompressionMethod, data: &[u8]) -> std::io::Result<()> {
+    let options = SimpleFileOptions::default().compression_method(method).last_modified_time(zip_time(timestamp));
+    zip.start_file(name, options).map_err(std::io::Error::other)?;
+    zip.write_all(data)
+}
+
+// Imagen térmica y punto caliente del frame más caliente de una captura
+fn thermal_image(record: &CaptureRecord) -> Result<(usize, Vec<u8>, Option<Blob>), String> {
+    let data = read_capture(&stored_path(record)).map_err(|e| e.to_string())?;
+    let (frame_index, frame) = hottest_frame(&data).ok_or("not a readable thermal matrix")?;
+    let png = encode_png(&render_rgb(&frame, PACKAGE_RENDER_SIDE)).map_err(|e| e.to_string())?;
+    let hotspot = hottest_blob(&frame_blobs(frame.view())).cloned();
+    Ok((frame_index, png, hotspot))
+}
+
+// Genera el zip de la sesión (en el pool bloqueante). Una captura o foto ilegible no
+// corta el paquete: queda anotada en `errors` de su captura
+pub fn write_package(
+    session: &[CaptureRecord],
+    turbine: Option<TurbineInfo>,
+    alerts: &[AlertRecord],
+) -> std::io::Result<Vec<u8>> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut captures = Vec::with_capacity(session.len());
+    for record in session {
+        let stem = record.filename.strip_suffix(".npz").unwrap_or(&record.filename);
+        let (mut images, mut errors, mut frame_index, mut hotspot) = (PackageImages::default(), Vec::new(), None, None);
+        match thermal_image(record) {
+            Ok((index, png, blob)) => {
+                let name = format!("images/{}.thermal.png", stem);
+                add_file(&mut zip, &name, record.timestamp, CompressionMethod::Stored, &png)?;
+                (images.thermal, frame_index, hotspot) = (Some(name), Some(index), blob);
+            }
+            Err(e) => errors.push(format!("thermal image: {}", e)),
+        }
+        if let Some(photo) = &record.photo {
+            match photo_path(photo).and_then(|path| read_capture(&path)) {
+                Ok(data) => {
+                    let name = format!("images/{}", photo);
+                    add_file(&mut zip, &name, record.timestamp, CompressionMethod::Stored, &data)?;
+                    images.photo = Some(name);
+                }
+                Err(e) => errors.push(format!("photo: {}", e)),
+            }
+        }
+        captures.push(PackagedCapture {
+            filename: record.filename.clone(),
+            timestamp: record.timestamp,
+            camera_id: record.camera_id.clone(),
+            angle: record.angle,
+            sha256: record.sha256.clone(),
+            size_bytes: record.size_bytes,
+            max_temp: record.max_temp,
+            avg_temp: record.avg_temp,
+            anomaly_score: record.anomaly_score,
+            calibration_version: record.calibration_version,
+            frame_index,
+            hotspot,
+            analyses: record.analyses.clone(),
+            alerts: alerts.iter().filter(|a| a.dataset_path == record.filename).cloned().collect(),
+            images,
+            errors,
+        });
+    }
+
+    let manifest = PackageManifest {
+        format: PACKAGE_FORMAT,
+        version: PACKAGE_VERSION,
+        generated: now,
+        inspection: Inspection {
+            id: session.first().map(|r| r.filename.clone()).unwrap_or_default(),
+            turbine_token: session.first().map(|r| r.turbine_token.clone()).unwrap_or_default(),
+            turbine,
+            start: session.first().map_or(0, |r| r.timestamp),
+            end: session.last().map_or(0, |r| r.timestamp),
+            capture_count: session.len(),
+            max_temp: session.iter().filter_map(|r| r.max_temp).reduce(f32::max),
+            alert_count: alerts.len(),
+        },
+        captures,
+    };
+    add_file(&mut zip, "manifest.json", now, CompressionMethod::Deflated, &serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(zip.finish().map_err(std::io::Error::other)?.into_inner())
+}
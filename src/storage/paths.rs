@@ -0,0 +1,193 @@
+use std::path::{Component, Path, PathBuf};
+
+// --- RUTAS DE USUARIO ---
+// Todo nombre de archivo que llega de fuera (URL, formulario, manifiesto) se resuelve
+// con safe_resolve antes de tocar el disco: solo rutas relativas sin "..", sin
+// caracteres de control ni unicode que imite separadores o puntos, y cuyo destino real
+// (resolviendo enlaces simbólicos) quede dentro de la raíz. Admite subcarpetas.
+
+#[derive(Debug)]
+pub enum PathError {
+    Empty,
+    Absolute,
+    // Componentes "." o ".."
+    Traversal,
+    InvalidCharacter(char),
+    // Un enlace simbólico lleva fuera de la raíz
+    OutsideRoot,
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "empty path"),
+            PathError::Absolute => write!(f, "absolute paths are not allowed"),
+            PathError::Traversal => write!(f, "'.' and '..' components are not allowed"),
+            PathError::InvalidCharacter(c) => write!(f, "invalid character U+{:04X}", *c as u32),
+            PathError::OutsideRoot => write!(f, "path resolves outside the storage directory"),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+// Los handlers que trabajan con io::Result la ven como entrada inválida (400)
+impl From<PathError> for std::io::Error {
+    fn from(e: PathError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e)
+    }
+}
+
+// Caracteres que algunos sistemas o visores convierten en '/', '\' o '.' (formas de ancho
+// completo, barras de división...) o que esconden el nombre real (bidi, ancho cero)
+fn is_deceptive(c: char) -> bool {
+    matches!(c,
+        '\\'
+        | '\u{2024}' | '\u{2044}' | '\u{2215}' | '\u{29F8}' | '\u{29F9}'
+        | '\u{FE52}' | '\u{FF0E}' | '\u{FF0F}' | '\u{FF3C}'
+        | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{2069}' | '\u{FEFF}'
+    ) || c.is_control()
+}
+
+// Comprobaciones sin tocar el disco
+fn check_relative(user_path: &str) -> Result<&Path, PathError> {
+    if user_path.is_empty() {
+        return Err(PathError::Empty);
+    }
+    if let Some(c) = user_path.chars().find(|&c| is_deceptive(c)) {
+        return Err(PathError::InvalidCharacter(c));
+    }
+    let path = Path::new(user_path);
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => {}
+            Component::CurDir | Component::ParentDir => return Err(PathError::Traversal),
+            Component::RootDir | Component::Prefix(_) => return Err(PathError::Absolute),
+        }
+    }
+    // "a/./b" pierde el "." al descomponerse; se rechaza igual
+    if user_path.split('/').any(|part| part == "." || part == "..") {
+        return Err(PathError::Traversal);
+    }
+    Ok(path)
+}
+
+// Nombre de un único archivo (o token usado para nombrar archivos): como una ruta
+// segura pero sin subcarpetas
+pub fn check_file_name(name: &str) -> Result<(), PathError> {
+    check_relative(name)?;
+    if name.contains('/') {
+        return Err(PathError::Traversal);
+    }
+    Ok(())
+}
+
+// Ruta dentro de `root` para una ruta recibida del usuario. El archivo puede no existir
+// todavía: se comprueba el antecesor existente más profundo (solo metadatos, sin leer
+// el contenido).
+pub fn safe_resolve(root: &Path, user_path: &str) -> Result<PathBuf, PathError> {
+    let path = root.join(check_relative(user_path)?);
+    // Sin raíz en disco no hay enlaces que seguir
+    let Ok(canonical_root) = root.canonicalize() else { return Ok(path) };
+    let inside = path.ancestors()
+        .find(|p| p.symlink_metadata().is_ok())
+        .and_then(|existing| existing.canonicalize().ok())
+        .is_some_and(|resolved| resolved.starts_with(&canonical_root));
+    if !inside {
+        return Err(PathError::OutsideRoot);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Raíz temporal propia de cada prueba; se borra al terminar
+    struct TempRoot(PathBuf);
+
+    impl TempRoot {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("sentinel-paths-{}", uuid::Uuid::new_v4().simple()));
+            std::fs::create_dir_all(&root).unwrap();
+            TempRoot(root)
+        }
+    }
+
+    impl Drop for TempRoot {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn rejects_traversal() {
+        let root = TempRoot::new();
+        for path in ["..", "../etc/passwd", "a/../../b", "a/./b", "./a", "a/.", "a/.."] {
+            assert!(matches!(safe_resolve(&root.0, path), Err(PathError::Traversal)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn rejects_absolute_and_empty() {
+        let root = TempRoot::new();
+        assert!(matches!(safe_resolve(&root.0, "/etc/passwd"), Err(PathError::Absolute)));
+        assert!(matches!(safe_resolve(&root.0, ""), Err(PathError::Empty)));
+        assert!(matches!(check_file_name("/etc/passwd"), Err(PathError::Absolute)));
+    }
+
+    #[test]
+    fn rejects_deceptive_unicode() {
+        let root = TempRoot::new();
+        let cases = [
+            // Barra y punto de ancho completo
+            "..\u{FF0F}etc\u{FF0F}passwd",
+            "\u{FF0E}\u{FF0E}/x",
+            // Barra de división y barra invertida
+            "a\u{2215}b",
+            "a\\b",
+            // Bidi: "cod.npz" que se ve al revés
+            "x\u{202E}zpn.doc",
+            "x\u{2066}y",
+            // Ancho cero y BOM
+            "a\u{200B}b.npz",
+            "\u{FEFF}a.npz",
+            // Control
+            "a\nb",
+            "a\0b",
+        ];
+        for path in cases {
+            assert!(matches!(safe_resolve(&root.0, path), Err(PathError::InvalidCharacter(_))), "{:?}", path);
+            assert!(check_file_name(path).is_err(), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn accepts_plain_names_and_subfolders() {
+        let root = TempRoot::new();
+        assert_eq!(safe_resolve(&root.0, "T42_1700000000.npz").unwrap(), root.0.join("T42_1700000000.npz"));
+        assert_eq!(safe_resolve(&root.0, "archive/T42.npz").unwrap(), root.0.join("archive/T42.npz"));
+        assert!(safe_resolve(&root.0, "térmica_ñ.npz").is_ok());
+        assert!(check_file_name("T42_1700000000.npz").is_ok());
+        assert!(matches!(check_file_name("archive/T42.npz"), Err(PathError::Traversal)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_outside_root() {
+        let root = TempRoot::new();
+        let outside = TempRoot::new();
+        std::fs::write(outside.0.join("secret"), b"x").unwrap();
+        std::os::unix::fs::symlink(&outside.0, root.0.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.0.join("secret"), root.0.join("secret.npz")).unwrap();
+        assert!(matches!(safe_resolve(&root.0, "escape/secret"), Err(PathError::OutsideRoot)));
+        assert!(matches!(safe_resolve(&root.0, "escape/new.npz"), Err(PathError::OutsideRoot)));
+        assert!(matches!(safe_resolve(&root.0, "secret.npz"), Err(PathError::OutsideRoot)));
+
+        // Un enlace que se queda dentro es válido
+        std::fs::create_dir(root.0.join("real")).unwrap();
+        std::os::unix::fs::symlink(root.0.join("real"), root.0.join("alias")).unwrap();
+        assert!(safe_resolve(&root.0, "alias/file.npz").is_ok());
+    }
+}
@@ -0,0 +1,82 @@
+use super::{crypto, paths::safe_resolve, storage_root, write_durable};
+use std::{io, path::PathBuf};
+
+// --- FOTOS VISIBLES DE LAS CAPTURAS ---
+// El robot puede enviar junto a cada captura térmica la foto RGB de la misma posición
+// (campo multipart `photo`). Se guarda al lado de la captura, con su nombre terminado en
+// .photo.jpg o .photo.png en lugar de .npz (así no se confunde con una captura), cifrada
+// como ellas si hay clave, y el catálogo la enlaza en `photo`. GET /api/photo/:filename la
+// sirve para poner la vista térmica y la visible lado a lado en los informes.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhotoFormat {
+    Jpeg,
+    Png,
+}
+
+impl PhotoFormat {
+    // Por los bytes mágicos; None si no es JPEG ni PNG
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(PhotoFormat::Jpeg)
+        } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(PhotoFormat::Png)
+        } else {
+            None
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            PhotoFormat::Jpeg => "jpg",
+            PhotoFormat::Png => "png",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            PhotoFormat::Jpeg => "image/jpeg",
+            PhotoFormat::Png => "image/png",
+        }
+    }
+
+    pub fn of_name(photo: &str) -> Option<Self> {
+        match photo.rsplit_once('.')?.1 {
+            "jpg" => Some(PhotoFormat::Jpeg),
+            "png" => Some(PhotoFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+pub fn photo_filename(capture: &str, format: PhotoFormat) -> String {
+    format!("{}.photo.{}", capture.strip_suffix(".npz").unwrap_or(capture), format.extension())
+}
+
+// Captura a la que pertenece una foto guardada
+pub fn photo_capture(photo: &str) -> Option<String> {
+    let stem = photo.strip_suffix(".photo.jpg").or_else(|| photo.strip_suffix(".photo.png"))?;
+    Some(format!("{}.npz", stem))
+}
+
+// El nombre viene del catálogo, que puede haberse importado o replicado: se resuelve
+// como cualquier ruta de usuario
+pub fn photo_path(photo: &str) -> io::Result<PathBuf> {
+    Ok(safe_resolve(&storage_root(), photo)?)
+}
+
+// Foto ya guardada de una captura (al reconstruir el catálogo)
+pub fn find_photo(capture: &str) -> Option<String> {
+    [PhotoFormat::Jpeg, PhotoFormat::Png].into_iter()
+        .map(|format| photo_filename(capture, format))
+        .find(|photo| photo_path(photo).is_ok_and(|path| path.exists()))
+}
+
+// Guarda la foto de una captura; devuelve su nombre
+pub fn save_photo(capture: &str, data: &[u8]) -> io::Result<String> {
+    let format = PhotoFormat::detect(data)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "photo is not a JPEG or PNG image"))?;
+    let photo = photo_filename(capture, format);
+    write_durable(&photo_path(&photo)?, &crypto::encrypt(data.to_vec())?)?;
+    Ok(photo)
+}
@@ -0,0 +1,52 @@
+use super::{save_json, storage_root};
+use crate::units::TempUnit;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- PREFERENCIAS DEL DASHBOARD ---
+// Ajustes de cada usuario (paleta, unidades, zona horaria, turbinas fijadas...) para que le
+// sigan de un navegador a otro en lugar de vivir en localStorage. Se guardan en
+// cloud_storage/preferences.json.
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UserPreferences {
+    // Lo fija el servidor (cabecera X-User)
+    #[serde(default)]
+    pub user: String,
+    // Paleta por defecto de las imágenes térmicas ("ironbow"...)
+    #[serde(default)]
+    pub palette: Option<String>,
+    #[serde(default)]
+    pub units: Option<TempUnit>,
+    // Zona IANA ("Europe/Madrid")
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub pinned_turbines: Vec<String>,
+    // Otros ajustes del dashboard, que el servidor guarda sin interpretar
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub updated: u64,
+}
+
+impl UserPreferences {
+    pub fn new(user: &str) -> Self {
+        UserPreferences { user: user.to_string(), ..Default::default() }
+    }
+}
+
+pub fn preferences_path() -> PathBuf {
+    storage_root().join("preferences.json")
+}
+
+pub fn load_preferences() -> Vec<UserPreferences> {
+    std::fs::read_to_string(preferences_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_preferences(preferences: &[UserPreferences]) {
+    save_json(&preferences_path(), preferences, "preferencias de usuario");
+}
@@ -0,0 +1,87 @@
+use super::{save_json, storage_root};
+use crate::i18n::Locale;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- DISPOSITIVOS MÓVILES Y PREFERENCIAS PUSH ---
+// Tokens FCM de la app móvil de cada usuario y qué avisos quiere recibir, en
+// cloud_storage/push.json.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PushDevice {
+    // Token de registro FCM del dispositivo
+    pub token: String,
+    pub user: String,
+    // "android", "ios"... (informativo)
+    #[serde(default)]
+    pub platform: Option<String>,
+    pub registered: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_locale() -> Locale {
+    Locale::Es
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PushPreferences {
+    // En la ruta al actualizarlas
+    #[serde(default)]
+    pub user: String,
+    #[serde(default = "default_true")]
+    pub critical_alerts: bool,
+    #[serde(default = "default_true")]
+    pub offline: bool,
+    // Turbinas que le interesan (vacío = todas)
+    #[serde(default)]
+    pub turbines: Vec<String>,
+    // Idioma de los avisos; en español si no se indica, como antes de poder elegirlo
+    #[serde(default = "default_locale")]
+    pub locale: Locale,
+}
+
+impl PushPreferences {
+    pub fn new(user: &str) -> Self {
+        PushPreferences { user: user.to_string(), critical_alerts: true, offline: true, turbines: Vec::new(), locale: default_locale() }
+    }
+
+    pub fn follows(&self, turbine_token: &str) -> bool {
+        self.turbines.is_empty() || self.turbines.iter().any(|t| t == turbine_token)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PushRegistry {
+    #[serde(default)]
+    pub devices: Vec<PushDevice>,
+    // Usuarios sin entrada usan las preferencias por defecto
+    #[serde(default)]
+    pub preferences: Vec<PushPreferences>,
+}
+
+impl PushRegistry {
+    pub fn preferences_of(&self, user: &str) -> PushPreferences {
+        self.preferences.iter()
+            .find(|p| p.user == user)
+            .cloned()
+            .unwrap_or_else(|| PushPreferences::new(user))
+    }
+}
+
+pub fn push_path() -> PathBuf {
+    storage_root().join("push.json")
+}
+
+pub fn load_push_registry() -> PushRegistry {
+    std::fs::read_to_string(push_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_push_registry(registry: &PushRegistry) {
+    save_json(&push_path(), registry, "dispositivos push");
+}
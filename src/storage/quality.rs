@@ -0,0 +1,85 @@
+use super::{append_jsonl, purge_jsonl, storage_root};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::BufRead,
+    path::PathBuf,
+};
+
+// --- EVENTOS DE CALIDAD DE DATOS ---
+// Huecos de heartbeat, cambios de sincronización del reloj, subidas ilegibles,
+// rechazadas o en cuarentena e inundaciones de subidas de cada robot, en cloud_storage/quality_events.jsonl. Son la base del informe de completitud
+// (/api/quality/:token) que se entrega a los auditores del propietario.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QualityEventKind {
+    // Sin heartbeats desde `from` hasta el instante del evento
+    HeartbeatGap { from: u64 },
+    // El reloj del robot pasa a estar desincronizado (o vuelve a estar en hora)
+    ClockSkew { offset_sec: i64, flagged: bool },
+    // Subida que no se pudo leer como captura térmica
+    CorruptUpload { filename: String },
+    // Subida rechazada antes de guardarla (npy inválido o resolución distinta de la declarada)
+    RejectedUpload {
+        reason: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        quarantine_id: Option<String>,
+    },
+    // El robot supera el límite de subidas por minuto (inicio del episodio)
+    IngestFlood { limit_per_min: u32 },
+    // Barrido con ángulos sin capturar (ver crate::sessions)
+    IncompleteScan { session_start: u64, camera_id: Option<String>, coverage: f32, gaps: usize },
+    // Captura apartada en cuarentena por temperaturas fuera de temperature_bounds
+    ImplausibleTemperature { quarantine_id: String, frames: usize, min_temp: f32, max_temp: f32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QualityEvent {
+    pub turbine_token: String,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub kind: QualityEventKind,
+}
+
+pub fn quality_events_path() -> PathBuf {
+    storage_root().join("quality_events.jsonl")
+}
+
+pub fn append_quality_event(event: &QualityEvent) -> std::io::Result<()> {
+    append_jsonl(&quality_events_path(), [event])
+}
+
+// Eventos de una turbina entre dos instantes (inclusivos), en orden cronológico
+pub fn load_quality_events(turbine_token: &str, from: u64, to: u64) -> std::io::Result<Vec<QualityEvent>> {
+    let file = match std::fs::File::open(quality_events_path()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut events = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let Ok(event) = serde_json::from_str::<QualityEvent>(&line?) else { continue };
+        if event.turbine_token == turbine_token && (from..=to).contains(&event.timestamp) {
+            events.push(event);
+        }
+    }
+    events.sort_by_key(|e| e.timestamp);
+    Ok(events)
+}
+
+// Quita los eventos de una turbina; devuelve cuántos se borraron
+pub fn purge_turbine_quality_events(turbine_token: &str) -> std::io::Result<usize> {
+    purge_jsonl(&quality_events_path(), |e: &QualityEvent| e.turbine_token == turbine_token)
+}
+
+// Registra un evento con la hora actual; un fallo de escritura solo se anota en el log
+pub fn record_quality_event(turbine_token: &str, kind: QualityEventKind) {
+    let event = QualityEvent {
+        turbine_token: turbine_token.to_string(),
+        timestamp: chrono::Utc::now().timestamp() as u64,
+        kind,
+    };
+    if let Err(e) = append_quality_event(&event) {
+        tracing::error!(%turbine_token, error = %e, "❌ Error guardando evento de calidad de datos");
+    }
+}
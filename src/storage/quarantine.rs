@@ -0,0 +1,86 @@
+use super::{storage_root, try_save_json, write_durable};
+use crate::plausibility::Implausible;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- CUARENTENA ---
+// Subidas rechazadas (no válidas o con temperaturas implausibles, ver crate::plausibility)
+// que se guardan en vez de descartarse: el contenido en cloud_storage/quarantine/<id>.npz
+// y los datos de la subida y el motivo en cloud_storage/quarantine.json. No se catalogan
+// ni se archivan; un administrador las acepta o las borra tras revisarlas (/api/quarantine).
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QuarantineReason {
+    // No es una matriz térmica válida o no tiene la resolución declarada del robot
+    Invalid { message: String },
+    // Frames fuera de temperature_bounds
+    Implausible(Implausible),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QuarantinedUpload {
+    pub id: String,
+    pub turbine_token: String,
+    pub angle: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotor_phase: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captured_at: Option<u64>,
+    pub received: u64,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub reason: QuarantineReason,
+}
+
+pub fn quarantine_dir() -> PathBuf {
+    storage_root().join("quarantine")
+}
+
+pub fn quarantine_index_path() -> PathBuf {
+    storage_root().join("quarantine.json")
+}
+
+// Los ids son uuid generados por el servidor, nunca rutas del usuario
+fn quarantined_path(id: &str) -> PathBuf {
+    quarantine_dir().join(format!("{}.npz", id))
+}
+
+pub fn load_quarantine() -> Vec<QuarantinedUpload> {
+    std::fs::read_to_string(quarantine_index_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_quarantine(entries: &[QuarantinedUpload]) {
+    if let Err(e) = try_save_quarantine(entries) {
+        tracing::error!(path = %quarantine_index_path().display(), error = %e, "❌ Error guardando el índice de la cuarentena");
+    }
+}
+
+// Escritura duradera, igual que el catálogo: el id de la cuarentena sirve de recibo
+pub fn try_save_quarantine(entries: &[QuarantinedUpload]) -> std::io::Result<()> {
+    try_save_json(&quarantine_index_path(), entries)
+}
+
+pub fn write_quarantined(id: &str, bytes: &[u8]) -> std::io::Result<()> {
+    std::fs::create_dir_all(quarantine_dir())?;
+    write_durable(&quarantined_path(id), bytes)
+}
+
+// Contenido original (descomprimido) de una subida en cuarentena
+pub fn read_quarantined(id: &str) -> std::io::Result<Vec<u8>> {
+    super::read_capture(&quarantined_path(id))
+}
+
+pub fn remove_quarantined(id: &str) -> std::io::Result<()> {
+    match std::fs::remove_file(quarantined_path(id)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,101 @@
+use super::{save_json, storage_root};
+use crate::modbus::MAX_MODBUS_SLOT;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+
+// --- REGISTRO DE TURBINAS ---
+// Metadatos de cada turbina de la flota (ubicación, modelo...), independientes de que
+// haya enviado capturas. Se guarda en cloud_storage/turbines.json.
+
+// Desplazamiento máximo de los umbrales de una turbina
+pub const MAX_TEMP_OFFSET: f32 = 100.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TurbineInfo {
+    // Mismo token con el que la turbina envía heartbeats y capturas
+    pub token: String,
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub model: Option<String>,
+    // Altura del buje en metros
+    #[serde(default)]
+    pub hub_height_m: Option<f32>,
+    // Nombre del sitio/parque al que pertenece
+    #[serde(default)]
+    pub site: Option<String>,
+    // Bloque de registros Modbus de la turbina (None = no se expone por Modbus)
+    #[serde(default)]
+    pub modbus_slot: Option<u16>,
+    // Resolución declarada de la cámara térmica: las subidas con otra se rechazan
+    // (None = se acepta cualquiera)
+    #[serde(default)]
+    pub sensor_resolution: Option<SensorResolution>,
+    // Resolución de cada cámara adicional por camera_id (las que no están usan la anterior)
+    #[serde(default)]
+    pub camera_resolutions: HashMap<String, SensorResolution>,
+    // Segundos sin heartbeat tras los que se considera desconectada (None = el global o
+    // el derivado de su espera entre escaneos)
+    #[serde(default)]
+    pub offline_after_sec: Option<u64>,
+    // Grados que se desplazan todos sus umbrales (global, zonas y niveles), como un perfil
+    // horario: positivo para un modelo que trabaja más caliente (None = sin desplazar)
+    #[serde(default)]
+    pub temp_offset: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SensorResolution {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl TurbineInfo {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.token.trim().is_empty() {
+            return Err("token must not be empty".into());
+        }
+        if !(-90.0..=90.0).contains(&self.latitude) || !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(format!("turbine '{}': invalid coordinates", self.token));
+        }
+        if self.hub_height_m.is_some_and(|h| !h.is_finite() || h < 0.0) {
+            return Err(format!("turbine '{}': invalid hub_height_m", self.token));
+        }
+        if self.modbus_slot.is_some_and(|s| s > MAX_MODBUS_SLOT) {
+            return Err(format!("turbine '{}': modbus_slot must be at most {}", self.token, MAX_MODBUS_SLOT));
+        }
+        if self.temp_offset.is_some_and(|o| !o.is_finite() || o.abs() > MAX_TEMP_OFFSET) {
+            return Err(format!("turbine '{}': temp_offset must be within ±{} degrees", self.token, MAX_TEMP_OFFSET));
+        }
+        if self.offline_after_sec == Some(0) {
+            return Err(format!("turbine '{}': offline_after_sec must be positive", self.token));
+        }
+        if self.sensor_resolution.iter().chain(self.camera_resolutions.values()).any(|r| r.width == 0 || r.height == 0) {
+            return Err(format!("turbine '{}': sensor resolution must not be empty", self.token));
+        }
+        Ok(())
+    }
+
+    // Alto y ancho que deben tener las capturas de una cámara, si están declarados
+    pub fn declared_resolution(&self, camera_id: Option<&str>) -> Option<(usize, usize)> {
+        camera_id.and_then(|c| self.camera_resolutions.get(c))
+            .or(self.sensor_resolution.as_ref())
+            .map(|r| (r.height, r.width))
+    }
+}
+
+pub fn registry_path() -> PathBuf {
+    storage_root().join("turbines.json")
+}
+
+pub fn load_registry() -> Vec<TurbineInfo> {
+    std::fs::read_to_string(registry_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_registry(turbines: &[TurbineInfo]) {
+    save_json(&registry_path(), turbines, "registro de turbinas");
+}
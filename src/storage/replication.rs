@@ -0,0 +1,41 @@
+use super::{save_json, storage_root};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- PUNTO DE REPLICACIÓN ---
+// Hasta dónde ha copiado la instancia secundaria de la primaria, en
+// cloud_storage/replication.json, para continuar tras un reinicio sin volver a pedirlo todo.
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ReplicationCursor {
+    // Instante de llegada (CaptureRecord::arrival) de la última captura copiada
+    #[serde(default)]
+    pub captures_since: u64,
+    // Nombre de esa captura: el punto es (llegada, nombre), porque pueden llegar muchas
+    // en el mismo segundo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captures_after: Option<String>,
+    // Timestamp de la última alerta copiada
+    #[serde(default)]
+    pub alerts_since: u64,
+    // Versión de configuración de la primaria aplicada por última vez
+    #[serde(default)]
+    pub config_version: u64,
+    #[serde(default)]
+    pub last_sync: Option<u64>,
+}
+
+pub fn replication_path() -> PathBuf {
+    storage_root().join("replication.json")
+}
+
+pub fn load_replication_cursor() -> ReplicationCursor {
+    std::fs::read_to_string(replication_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_replication_cursor(cursor: &ReplicationCursor) {
+    save_json(&replication_path(), cursor, "el punto de replicación");
+}
@@ -0,0 +1,68 @@
+use super::{paths::check_file_name, save_json, storage_root, write_durable};
+use crate::reports::ReportSchedule;
+use serde::Serialize;
+use std::path::PathBuf;
+
+// --- INFORMES PROGRAMADOS ---
+// Planificaciones creadas por /api/schedules (ver crate::reports), en
+// cloud_storage/report_schedules.json, e informes generados, en cloud_storage/reports/.
+
+pub fn schedules_path() -> PathBuf {
+    storage_root().join("report_schedules.json")
+}
+
+pub fn reports_dir() -> PathBuf {
+    storage_root().join("reports")
+}
+
+pub fn load_report_schedules() -> Vec<ReportSchedule> {
+    std::fs::read_to_string(schedules_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_report_schedules(schedules: &[ReportSchedule]) {
+    save_json(&schedules_path(), schedules, "planificaciones de informes");
+}
+
+pub fn write_report(name: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    check_file_name(name)?;
+    let dir = reports_dir();
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(name);
+    write_durable(&path, bytes)?;
+    Ok(path)
+}
+
+#[derive(Serialize)]
+pub struct StoredReport {
+    pub name: String,
+    pub size_bytes: u64,
+    pub created: u64,
+}
+
+// Informes guardados, del más reciente al más antiguo
+pub fn list_reports() -> Vec<StoredReport> {
+    let Ok(entries) = std::fs::read_dir(reports_dir()) else { return Vec::new() };
+    let mut reports: Vec<StoredReport> = entries.flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".tmp") {
+                return None;
+            }
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            let created = metadata.modified().ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            Some(StoredReport { name, size_bytes: metadata.len(), created })
+        })
+        .collect();
+    reports.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| b.name.cmp(&a.name)));
+    reports
+}
+
+pub fn read_report(name: &str) -> std::io::Result<Vec<u8>> {
+    check_file_name(name)?;
+    std::fs::read(reports_dir().join(name))
+}
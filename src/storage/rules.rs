@@ -0,0 +1,21 @@
+use super::{save_json, storage_root};
+use crate::rules::AlertRule;
+use std::path::PathBuf;
+
+// --- REGLAS DE ALERTA ---
+// Reglas definidas por el operador (ver crate::rules), en cloud_storage/alert_rules.json.
+
+pub fn rules_path() -> PathBuf {
+    storage_root().join("alert_rules.json")
+}
+
+pub fn load_rules() -> Vec<AlertRule> {
+    std::fs::read_to_string(rules_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_rules(rules: &[AlertRule]) {
+    save_json(&rules_path(), rules, "reglas de alerta");
+}
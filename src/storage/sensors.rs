@@ -0,0 +1,85 @@
+use super::{append_jsonl, purge_jsonl, storage_root};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::BufRead,
+    path::PathBuf,
+};
+
+// --- TELEMETRÍA NO TÉRMICA ---
+// Series de otros sensores de la turbina (RMS de vibración, nivel acústico...) que los
+// robots envían junto a las capturas. Se guardan como una línea JSON por lectura en
+// cloud_storage/sensor_readings.jsonl para cruzarlas con las anomalías térmicas.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SensorReading {
+    pub turbine_token: String,
+    // "vibration_rms", "acoustic_db"...
+    pub sensor: String,
+    pub timestamp: u64,
+    pub value: f64,
+    #[serde(default)]
+    pub unit: Option<String>,
+}
+
+// Lecturas que se devuelven como máximo en una consulta
+pub const MAX_QUERY_READINGS: usize = 10_000;
+
+pub fn valid_sensor_name(sensor: &str) -> bool {
+    !sensor.is_empty()
+        && sensor.len() <= 64
+        && sensor.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+pub fn sensor_readings_path() -> PathBuf {
+    storage_root().join("sensor_readings.jsonl")
+}
+
+pub fn append_readings(readings: &[SensorReading]) -> std::io::Result<()> {
+    append_jsonl(&sensor_readings_path(), readings)
+}
+
+// Criterios de consulta; los límites de tiempo son inclusivos
+pub struct ReadingFilter {
+    pub turbine_token: Option<String>,
+    pub sensor: Option<String>,
+    pub from: u64,
+    pub to: u64,
+}
+
+impl Default for ReadingFilter {
+    fn default() -> Self {
+        ReadingFilter { turbine_token: None, sensor: None, from: 0, to: u64::MAX }
+    }
+}
+
+impl ReadingFilter {
+    fn matches(&self, reading: &SensorReading) -> bool {
+        self.turbine_token.as_ref().is_none_or(|t| *t == reading.turbine_token)
+            && self.sensor.as_ref().is_none_or(|s| *s == reading.sensor)
+            && (self.from..=self.to).contains(&reading.timestamp)
+    }
+}
+
+// Lecturas que cumplen el filtro en orden cronológico
+pub fn load_readings(filter: &ReadingFilter) -> std::io::Result<Vec<SensorReading>> {
+    let file = match std::fs::File::open(sensor_readings_path()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut readings = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        // Una línea a medias (corte durante la escritura) no invalida el resto
+        let Ok(reading) = serde_json::from_str::<SensorReading>(&line?) else { continue };
+        if filter.matches(&reading) {
+            readings.push(reading);
+        }
+    }
+    readings.sort_by_key(|r| r.timestamp);
+    Ok(readings)
+}
+
+// Quita las lecturas de una turbina; devuelve cuántas se borraron
+pub fn purge_turbine_readings(turbine_token: &str) -> std::io::Result<usize> {
+    purge_jsonl(&sensor_readings_path(), |r: &SensorReading| r.turbine_token == turbine_token)
+}
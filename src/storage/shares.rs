@@ -0,0 +1,78 @@
+use super::{crypto, read_capture, storage_root, try_save_json, write_durable};
+use crate::units::TempUnit;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// --- ENLACES COMPARTIDOS ---
+// Instantáneas inmutables de un frame (imagen renderizada y estadísticas) que se pueden
+// ver sin acceso a la API durante un tiempo limitado (ver routes::share). La imagen en
+// cloud_storage/shares/<token>.png (cifrada con la clave de las capturas, si la hay) y
+// los datos en cloud_storage/shares.json; no dependen de la captura, que puede
+// archivarse o borrarse después.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SharedSnapshot {
+    pub token: String,
+    pub created: u64,
+    pub expires_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub turbine_token: String,
+    pub filename: String,
+    pub frame_index: usize,
+    // Instante y ángulo de la toma
+    pub captured: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub angle: Option<f32>,
+    // Estadísticas del frame en °C
+    pub min_temp: f32,
+    pub max_temp: f32,
+    pub avg_temp: f32,
+    // Unidades en que se muestran en el visor
+    pub units: TempUnit,
+    pub width: usize,
+    pub height: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+pub fn shares_dir() -> PathBuf {
+    storage_root().join("shares")
+}
+
+pub fn shares_index_path() -> PathBuf {
+    storage_root().join("shares.json")
+}
+
+// Los tokens los genera el servidor, nunca son rutas del usuario
+fn share_image_path(token: &str) -> PathBuf {
+    shares_dir().join(format!("{}.png", token))
+}
+
+pub fn load_shares() -> Vec<SharedSnapshot> {
+    std::fs::read_to_string(shares_index_path())
+        .ok()
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_shares(shares: &[SharedSnapshot]) -> std::io::Result<()> {
+    try_save_json(&shares_index_path(), shares)
+}
+
+pub fn write_share_image(token: &str, png: &[u8]) -> std::io::Result<()> {
+    std::fs::create_dir_all(shares_dir())?;
+    write_durable(&share_image_path(token), &crypto::encrypt(png.to_vec())?)
+}
+
+// Las imágenes anteriores al cifrado se siguen leyendo en claro
+pub fn read_share_image(token: &str) -> std::io::Result<Vec<u8>> {
+    read_capture(&share_image_path(token))
+}
+
+pub fn remove_share_image(token: &str) -> std::io::Result<()> {
+    match std::fs::remove_file(share_image_path(token)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,72 @@
+use super::{append_jsonl, purge_jsonl, storage_root};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::BufRead,
+    path::PathBuf,
+};
+
+// --- LÍNEA DE TIEMPO DE MODOS ---
+// Cada cambio de modo que anuncian los heartbeats (Scanning, Idle, Alarm...) y los
+// periodos sin conexión ("Offline"), en cloud_storage/mode_timeline.jsonl. Es la base
+// de la franja de actividad por robot (/api/timeline/:token).
+
+// Modo que se anota cuando el robot deja de enviar heartbeats
+pub const OFFLINE_MODE: &str = "Offline";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModeChange {
+    pub turbine_token: String,
+    pub timestamp: u64,
+    pub mode: String,
+}
+
+pub fn mode_timeline_path() -> PathBuf {
+    storage_root().join("mode_timeline.jsonl")
+}
+
+pub fn append_mode_change(change: &ModeChange) -> std::io::Result<()> {
+    append_jsonl(&mode_timeline_path(), [change])
+}
+
+// Cambios de una turbina entre dos instantes (inclusivos), en orden cronológico. Incluye
+// además el último cambio anterior a `from`, que da el modo al inicio del periodo.
+pub fn load_mode_changes(turbine_token: &str, from: u64, to: u64) -> std::io::Result<Vec<ModeChange>> {
+    let file = match std::fs::File::open(mode_timeline_path()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut before: Option<ModeChange> = None;
+    let mut changes = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let Ok(change) = serde_json::from_str::<ModeChange>(&line?) else { continue };
+        if change.turbine_token != turbine_token || change.timestamp > to {
+            continue;
+        }
+        if change.timestamp < from {
+            if before.as_ref().is_none_or(|b| change.timestamp >= b.timestamp) {
+                before = Some(change);
+            }
+        } else {
+            changes.push(change);
+        }
+    }
+    changes.sort_by_key(|c| c.timestamp);
+    if let Some(before) = before {
+        changes.insert(0, before);
+    }
+    Ok(changes)
+}
+
+// Quita los cambios de una turbina; devuelve cuántos se borraron
+pub fn purge_turbine_mode_changes(turbine_token: &str) -> std::io::Result<usize> {
+    purge_jsonl(&mode_timeline_path(), |c: &ModeChange| c.turbine_token == turbine_token)
+}
+
+// Registra un cambio de modo; un fallo de escritura solo se anota en el log
+pub fn record_mode_change(turbine_token: &str, timestamp: u64, mode: &str) {
+    let change = ModeChange { turbine_token: turbine_token.to_string(), timestamp, mode: mode.to_string() };
+    if let Err(e) = append_mode_change(&change) {
+        tracing::error!(%turbine_token, error = %e, "❌ Error guardando cambio de modo");
+    }
+}
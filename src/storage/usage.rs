@@ -0,0 +1,72 @@
+use super::{archive::stored_path, catalog::parse_capture_name, storage_root};
+use crate::state::AppState;
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+// --- USO DE DISCO Y SALUD DEL ALMACENAMIENTO ---
+
+#[derive(Serialize)]
+pub struct StorageReport {
+    pub disk_total_bytes: u64,
+    pub disk_free_bytes: u64,
+    // Bytes en disco de las capturas activas (sin contar subcarpetas)
+    pub captures_bytes: u64,
+    // Bytes por subcarpeta de cloud_storage (archive, ...)
+    pub folders: BTreeMap<String, u64>,
+    pub per_turbine_bytes: BTreeMap<String, u64>,
+    pub low_disk_warning: bool,
+}
+
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else { return 0 };
+    entries.flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(m) if m.is_dir() => dir_size(&entry.path()),
+            Ok(m) => m.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+// true si el espacio libre está por debajo del mínimo configurado (o no se puede medir)
+pub fn low_disk(state: &AppState) -> bool {
+    fs2::available_space(storage_root())
+        .map(|free| free < state.settings.min_free_disk_mb * 1024 * 1024)
+        .unwrap_or(true)
+}
+
+pub fn build_storage_report(state: &AppState) -> StorageReport {
+    let root = storage_root();
+    let disk_total_bytes = fs2::total_space(&root).unwrap_or(0);
+    let disk_free_bytes = fs2::available_space(&root).unwrap_or(0);
+
+    let mut captures_bytes = 0;
+    let mut folders = BTreeMap::new();
+    if let Ok(entries) = std::fs::read_dir(&root) {
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else { continue };
+            if metadata.is_dir() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                folders.insert(name, dir_size(&entry.path()));
+            } else if parse_capture_name(&entry.file_name().to_string_lossy()).is_some() {
+                captures_bytes += metadata.len();
+            }
+        }
+    }
+
+    // Uso por turbina según el tamaño real en disco (comprimido o archivado)
+    let mut per_turbine_bytes = BTreeMap::new();
+    for record in state.catalog.blocking_read().iter() {
+        let size = std::fs::metadata(stored_path(record)).map(|m| m.len()).unwrap_or(0);
+        *per_turbine_bytes.entry(record.turbine_token.clone()).or_insert(0) += size;
+    }
+
+    StorageReport {
+        disk_total_bytes,
+        disk_free_bytes,
+        captures_bytes,
+        folders,
+        per_turbine_bytes,
+        low_disk_warning: disk_free_bytes < state.settings.min_free_disk_mb * 1024 * 1024,
+    }
+}
@@ -0,0 +1,74 @@
+use axum::{body::Body, extract::MatchedPath, http::Request, response::Response, Router};
+use std::time::Duration;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use tracing::{field, Span};
+use tracing_subscriber::EnvFilter;
+
+// --- LOGS Y TRAZAS ---
+// Todos los eventos pasan por tracing. En modo json cada línea es un objeto con los
+// campos del evento y del span de la petición (método, ruta, request id, turbina),
+// listo para ingerirse en Loki/ELK sin parsear texto libre.
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+// Inicializa el subscriber global. El nivel se controla con RUST_LOG (por defecto info).
+pub fn init_logging(format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+    }
+}
+
+// Span por petición: los handlers de ingesta completan `turbine_token` con
+// `Span::current().record(...)` en cuanto lo conocen.
+fn make_request_span(request: &Request<Body>) -> Span {
+    let path = request.extensions().get::<MatchedPath>()
+        .map(|p| p.as_str().to_owned())
+        .unwrap_or_else(|| request.uri().path().to_owned());
+    let request_id = request.headers().get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %path,
+        request_id = %request_id,
+        turbine_token = field::Empty,
+    )
+}
+
+fn log_response(response: &Response, latency: Duration, _span: &Span) {
+    tracing::info!(
+        status = response.status().as_u16(),
+        latency_ms = latency.as_secs_f64() * 1000.0,
+        "request completed"
+    );
+}
+
+// Añade request id (x-request-id, generado si el cliente no lo envía) y el log por petición
+pub fn with_request_tracing(router: Router) -> Router {
+    router
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_request_span)
+                .on_request(())
+                .on_response(log_response),
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
@@ -0,0 +1,278 @@
+use crate::{
+    analyzers::validate_analyzers,
+    schedule::{parse_timezone, CronSchedule},
+    state::RemoteConfig,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// --- UMBRALES ---
+// Cada captura se evalúa contra una escala ordenada de niveles (p. ej. warning a 50°C,
+// critical a 70°C). La escala puede depender del ángulo: una zona angular define la suya
+// propia o, en su forma simple, un único umbral.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Warning,
+    Critical,
+}
+
+fn default_boost_duration_sec() -> u64 {
+    1800
+}
+
+// Nivel de umbral: severidad de la alerta, canales a notificar y refuerzo del escaneo
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThresholdLevel {
+    pub name: String,
+    pub severity: Severity,
+    pub min_temp: f32,
+    // Nombres de canales de notificación (ver RemoteConfig::channels)
+    #[serde(default)]
+    pub channels: Vec<String>,
+    // Si se define, el heartbeat de la turbina afectada devuelve esta espera entre
+    // escaneos durante boost_duration_sec
+    #[serde(default)]
+    pub boost_scan_wait_sec: Option<u64>,
+    #[serde(default = "default_boost_duration_sec")]
+    pub boost_duration_sec: u64,
+}
+
+impl ThresholdLevel {
+    // Nivel implícito de la configuración simple (un único max_temp_trigger)
+    fn single(min_temp: f32) -> Self {
+        ThresholdLevel {
+            name: "trigger".into(),
+            severity: Severity::Warning,
+            min_temp,
+            channels: Vec::new(),
+            boost_scan_wait_sec: None,
+            boost_duration_sec: default_boost_duration_sec(),
+        }
+    }
+}
+
+// Zona angular con umbral propio. Si angle_min > angle_max el rango cruza 0°
+// (p. ej. 350° -> 10°).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThresholdZone {
+    pub name: String,
+    pub angle_min: f32,
+    pub angle_max: f32,
+    pub max_temp_trigger: f32,
+    // Escala propia de la zona; vacía = un único nivel en max_temp_trigger
+    #[serde(default)]
+    pub levels: Vec<ThresholdLevel>,
+}
+
+impl ThresholdZone {
+    pub fn contains(&self, angle: f32) -> bool {
+        let angle = angle.rem_euclid(360.0);
+        if self.angle_min <= self.angle_max {
+            (self.angle_min..=self.angle_max).contains(&angle)
+        } else {
+            angle >= self.angle_min || angle <= self.angle_max
+        }
+    }
+}
+
+// Perfil horario: mientras su horario coincide, todos los umbrales (global, zonas y
+// niveles) se desplazan `temp_offset` grados. Negativo endurece (noche), positivo
+// relaja (mediodía de verano). Si coinciden varios, gana el primero de la lista.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThresholdProfile {
+    pub name: String,
+    pub schedule: String,
+    pub temp_offset: f32,
+}
+
+impl ThresholdProfile {
+    fn is_active(&self, at: &DateTime<Utc>) -> bool {
+        self.schedule.parse::<CronSchedule>().is_ok_and(|s| s.matches(at))
+    }
+}
+
+// Resultado de evaluar una captura
+pub struct Evaluation {
+    pub zone: Option<String>,
+    // Nivel más alto alcanzado (None = por debajo de todos)
+    pub level: Option<ThresholdLevel>,
+}
+
+impl RemoteConfig {
+    pub fn active_profile(&self, at: &DateTime<Utc>) -> Option<&ThresholdProfile> {
+        self.profiles.iter().find(|p| p.is_active(at))
+    }
+
+    // Configuración con el desplazamiento del perfil activo ya aplicado a todos los umbrales
+    pub fn effective_at(&self, at: &DateTime<Utc>) -> RemoteConfig {
+        let offset = self.active_profile(at).map(|p| p.temp_offset).unwrap_or(0.0);
+        self.shifted(offset)
+    }
+
+    // Copia con todos los umbrales (global, zonas y niveles) desplazados `offset` grados
+    pub fn shifted(&self, offset: f32) -> RemoteConfig {
+        let mut config = self.clone();
+        if offset == 0.0 {
+            return config;
+        }
+        config.max_temp_trigger += offset;
+        for level in &mut config.levels {
+            level.min_temp += offset;
+        }
+        for zone in &mut config.zones {
+            zone.max_temp_trigger += offset;
+            for level in &mut zone.levels {
+                level.min_temp += offset;
+            }
+        }
+        config
+    }
+
+    // Escala global, la que se aplica fuera de las zonas
+    pub fn global_levels(&self) -> Vec<ThresholdLevel> {
+        if self.levels.is_empty() {
+            vec![ThresholdLevel::single(self.max_temp_trigger)]
+        } else {
+            self.levels.clone()
+        }
+    }
+
+    // Escala aplicable a un ángulo: la de la primera zona que lo contiene o la global
+    fn levels_for_angle(&self, angle: f32) -> (Option<&ThresholdZone>, Vec<ThresholdLevel>) {
+        let zone = self.zones.iter().find(|z| z.contains(angle));
+        let levels = match zone {
+            Some(zone) if !zone.levels.is_empty() => zone.levels.clone(),
+            Some(zone) => vec![ThresholdLevel::single(zone.max_temp_trigger)],
+            None => self.global_levels(),
+        };
+        (zone, levels)
+    }
+
+    // Temperatura del nivel más bajo de la escala de un ángulo: por debajo no hay alerta
+    pub fn trigger_temp(&self, angle: f32) -> f32 {
+        let (_, levels) = self.levels_for_angle(angle);
+        levels.iter().map(|l| l.min_temp).fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn evaluate(&self, angle: f32, max_temp: f32) -> Evaluation {
+        let (zone, levels) = self.levels_for_angle(angle);
+        let level = levels.into_iter()
+            .filter(|l| max_temp >= l.min_temp)
+            .max_by(|a, b| a.min_temp.total_cmp(&b.min_temp));
+        Evaluation { zone: zone.map(|z| z.name.clone()), level }
+    }
+
+    // Comprobación de la configuración recibida desde el dashboard
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_levels("global", &self.levels)?;
+        for zone in &self.zones {
+            let in_range = |a: f32| (0.0..=360.0).contains(&a);
+            if !in_range(zone.angle_min) || !in_range(zone.angle_max) {
+                return Err(format!("zone '{}': angles must be within 0..=360", zone.name));
+            }
+            if !zone.max_temp_trigger.is_finite() {
+                return Err(format!("zone '{}': invalid max_temp_trigger", zone.name));
+            }
+            self.validate_levels(&zone.name, &zone.levels)?;
+        }
+        if let Some(compensation) = &self.ambient_compensation
+            && ![compensation.reference_temp, compensation.temp_factor, compensation.wind_factor].iter().all(|v| v.is_finite())
+        {
+            return Err("ambient_compensation: invalid factors".into());
+        }
+        if let Some(geometry) = &self.blade_geometry {
+            geometry.validate()?;
+        }
+        if let Some(classifier) = &self.classifier {
+            classifier.validate()?;
+        }
+        if let Some(correlation) = &self.correlation {
+            correlation.validate()?;
+        }
+        for site in &self.weather_sites {
+            if site.latitude.is_some_and(|lat| !(-90.0..=90.0).contains(&lat))
+                || site.longitude.is_some_and(|lon| !(-180.0..=180.0).contains(&lon))
+            {
+                return Err(format!("site '{}': invalid coordinates", site.name));
+            }
+        }
+        for profile in &self.profiles {
+            profile.schedule.parse::<CronSchedule>()
+                .map_err(|e| format!("profile '{}': {}", profile.name, e))?;
+            if !profile.temp_offset.is_finite() {
+                return Err(format!("profile '{}': invalid temp_offset", profile.name));
+            }
+        }
+        for schedule in &self.scan_schedules {
+            schedule.validate()?;
+        }
+        for channel in &self.channels {
+            channel.validate()?;
+        }
+        if let Some(missing) = self.device_alert_channels.iter().find(|c| !self.channels.iter().any(|ch| ch.name == **c)) {
+            return Err(format!("device_alert_channels: unknown channel '{}'", missing));
+        }
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.validate()?;
+            if let Some(missing) = watchdog.channels.iter().find(|c| !self.channels.iter().any(|ch| ch.name == **c)) {
+                return Err(format!("watchdog: unknown channel '{}'", missing));
+            }
+        }
+        for rule in &self.email_rules {
+            rule.validate(self)?;
+        }
+        if let Some(check) = &self.scan_coverage {
+            check.validate(self.pan_step_degrees)?;
+        }
+        if let Some(bounds) = &self.temperature_bounds {
+            bounds.validate()?;
+        }
+        if let Some(work_orders) = &self.work_orders {
+            work_orders.validate()?;
+        }
+        validate_analyzers(&self.analyzers)?;
+        if let Some(auto_resolve) = &self.auto_resolve {
+            auto_resolve.validate()?;
+            if let Some(missing) = auto_resolve.channels.iter().find(|c| !self.channels.iter().any(|ch| ch.name == **c)) {
+                return Err(format!("auto_resolve: unknown channel '{}'", missing));
+            }
+        }
+        if let Some(adaptive_scan) = &self.adaptive_scan {
+            adaptive_scan.validate()?;
+        }
+        if let Some(min) = self.min_capture_quality
+            && !(0.0..=1.0).contains(&min)
+        {
+            return Err("min_capture_quality must be between 0 and 1".into());
+        }
+        for (i, schedule) in self.report_schedules.iter().enumerate() {
+            schedule.validate()?;
+            if self.report_schedules[..i].iter().any(|s| s.name == schedule.name) {
+                return Err(format!("report schedule '{}' is defined twice", schedule.name));
+            }
+        }
+        if let Some(tz) = &self.display_timezone {
+            parse_timezone(tz).map_err(|e| format!("display_timezone: {}", e))?;
+        }
+        Ok(())
+    }
+
+    // Los niveles deben ir en orden estrictamente creciente y citar canales existentes
+    fn validate_levels(&self, scope: &str, levels: &[ThresholdLevel]) -> Result<(), String> {
+        for (i, level) in levels.iter().enumerate() {
+            if !level.min_temp.is_finite() {
+                return Err(format!("{}: level '{}' has an invalid min_temp", scope, level.name));
+            }
+            if i > 0 && level.min_temp <= levels[i - 1].min_temp {
+                return Err(format!("{}: levels must be ordered by increasing min_temp", scope));
+            }
+            if let Some(missing) = level.channels.iter().find(|c| !self.channels.iter().any(|ch| ch.name == **c)) {
+                return Err(format!("{}: level '{}' references unknown channel '{}'", scope, level.name, missing));
+            }
+        }
+        Ok(())
+    }
+}
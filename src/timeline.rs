@@ -0,0 +1,98 @@
+use crate::{
+    state::AppState,
+    storage::timeline::{load_mode_changes, ModeChange, OFFLINE_MODE},
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// --- LÍNEA DE TIEMPO DE MODOS ---
+// Actividad de un robot en un periodo como tramos consecutivos de un mismo modo, para
+// la franja tipo Gantt del dashboard. Los tramos se reconstruyen con los cambios de modo
+// anotados en cada heartbeat; si el robot no reporta ahora, el último tramo termina
+// cuando venció su tiempo sin conexión y sigue un tramo "Offline" abierto.
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ModeSegment {
+    pub mode: String,
+    pub from: u64,
+    pub to: u64,
+    // El tramo sigue en curso (llega hasta ahora)
+    pub ongoing: bool,
+}
+
+#[derive(Serialize)]
+pub struct TimelineReport {
+    pub turbine_token: String,
+    pub from: u64,
+    pub to: u64,
+    pub current_mode: Option<String>,
+    pub segments: Vec<ModeSegment>,
+    // Segundos del periodo en cada modo
+    pub totals_sec: BTreeMap<String, u64>,
+}
+
+// Tramos entre `from` y `end` a partir de los cambios ordenados; une tramos contiguos
+// del mismo modo y descarta los de duración nula
+fn build_segments(changes: &[ModeChange], from: u64, end: u64, now: u64) -> Vec<ModeSegment> {
+    let mut segments: Vec<ModeSegment> = Vec::new();
+    for (i, change) in changes.iter().enumerate() {
+        let start = change.timestamp.max(from);
+        let stop = changes.get(i + 1).map_or(end, |next| next.timestamp.min(end));
+        if stop <= start {
+            continue;
+        }
+        match segments.last_mut() {
+            Some(last) if last.mode == change.mode && last.to == start => last.to = stop,
+            _ => segments.push(ModeSegment { mode: change.mode.clone(), from: start, to: stop, ongoing: false }),
+        }
+    }
+    if let Some(last) = segments.last_mut() {
+        last.ongoing = last.to == now;
+    }
+    segments
+}
+
+pub async fn build_timeline(state: &AppState, turbine_token: &str, from: u64, to: u64) -> std::io::Result<TimelineReport> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let offline_after = state.offline_after(turbine_token).await;
+    let last_status = state.turbine_status.read().await.get(turbine_token).cloned();
+
+    let token = turbine_token.to_string();
+    let mut changes = tokio::task::spawn_blocking(move || load_mode_changes(&token, from, to)).await??;
+
+    // Sin heartbeats recientes (o ninguno desde el arranque): el robot está sin conexión
+    // desde que venció su plazo tras el último dato conocido
+    let last_seen = match &last_status {
+        Some(status) => Some(status.last_update),
+        None => changes.last().map(|c| c.timestamp),
+    };
+    let online = last_status.as_ref().is_some_and(|s| now <= s.last_update.saturating_add(offline_after));
+    if !online
+        && let Some(last_seen) = last_seen
+    {
+        let offline_since = last_seen.saturating_add(offline_after).min(now);
+        let already_offline = changes.last().is_some_and(|c| c.mode == OFFLINE_MODE);
+        if !already_offline && offline_since <= to {
+            changes.push(ModeChange { turbine_token: turbine_token.to_string(), timestamp: offline_since, mode: OFFLINE_MODE.into() });
+        }
+    }
+
+    let segments = build_segments(&changes, from, to.min(now), now);
+    let mut totals_sec = BTreeMap::new();
+    for segment in &segments {
+        *totals_sec.entry(segment.mode.clone()).or_insert(0) += segment.to - segment.from;
+    }
+    let current_mode = match (online, &last_status) {
+        (true, Some(status)) => Some(status.mode.clone()),
+        _ if last_seen.is_some() => Some(OFFLINE_MODE.to_string()),
+        _ => None,
+    };
+    Ok(TimelineReport {
+        turbine_token: turbine_token.to_string(),
+        from,
+        to,
+        current_mode,
+        segments,
+        totals_sec,
+    })
+}
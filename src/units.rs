@@ -0,0 +1,96 @@
+use crate::{
+    analysis::{EvolutionPoint, ThermalFrameData},
+    sessions::{AngleDelta, SessionComparison},
+    state::AlertRecord,
+    storage::catalog::CaptureRecord,
+};
+use serde::{Deserialize, Serialize};
+
+// --- UNIDADES DE TEMPERATURA ---
+// Todo se almacena y se evalúa en grados Celsius; la conversión se hace solo al responder
+// (?units=f o display_units de la configuración). Las diferencias de temperatura se
+// escalan sin desplazar el cero.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TempUnit {
+    #[default]
+    #[serde(alias = "c")]
+    Celsius,
+    #[serde(alias = "f")]
+    Fahrenheit,
+}
+
+impl TempUnit {
+    pub fn temp(self, celsius: f32) -> f32 {
+        match self {
+            TempUnit::Celsius => celsius,
+            TempUnit::Fahrenheit => celsius * 1.8 + 32.0,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+
+    pub fn delta(self, celsius: f32) -> f32 {
+        match self {
+            TempUnit::Celsius => celsius,
+            TempUnit::Fahrenheit => celsius * 1.8,
+        }
+    }
+
+    pub fn frame(self, mut frame: ThermalFrameData) -> ThermalFrameData {
+        if self != TempUnit::Celsius {
+            frame.min_temp = self.temp(frame.min_temp);
+            frame.max_temp = self.temp(frame.max_temp);
+            frame.pixels.iter_mut().for_each(|p| *p = self.temp(*p));
+        }
+        frame
+    }
+
+    pub fn evolution(self, mut point: EvolutionPoint) -> EvolutionPoint {
+        point.max_temp = self.temp(point.max_temp);
+        point.avg_temp = self.temp(point.avg_temp);
+        point
+    }
+
+    pub fn alert(self, mut alert: AlertRecord) -> AlertRecord {
+        alert.max_temp = self.temp(alert.max_temp);
+        if let Some(ambient) = alert.ambient.as_mut() {
+            ambient.ambient_temp = self.temp(ambient.ambient_temp);
+        }
+        if let Some(imbalance) = alert.blade_imbalance.as_mut() {
+            imbalance.delta_temp = self.delta(imbalance.delta_temp);
+        }
+        alert
+    }
+
+    fn angle_delta(self, mut delta: AngleDelta) -> AngleDelta {
+        delta.max_temp_a = self.temp(delta.max_temp_a);
+        delta.max_temp_b = self.temp(delta.max_temp_b);
+        delta.max_delta = self.delta(delta.max_delta);
+        delta.avg_delta = delta.avg_delta.map(|d| self.delta(d));
+        delta
+    }
+
+    pub fn comparison(self, mut comparison: SessionComparison) -> SessionComparison {
+        comparison.mean_max_delta = comparison.mean_max_delta.map(|d| self.delta(d));
+        comparison.largest_increases = comparison.largest_increases.into_iter().map(|d| self.angle_delta(d)).collect();
+        comparison.deltas = comparison.deltas.into_iter().map(|d| self.angle_delta(d)).collect();
+        comparison
+    }
+
+    pub fn capture(self, mut record: CaptureRecord) -> CaptureRecord {
+        record.max_temp = record.max_temp.map(|t| self.temp(t));
+        record.avg_temp = record.avg_temp.map(|t| self.temp(t));
+        record.frames = record.frames.into_iter().map(|p| self.evolution(p)).collect();
+        if let Some(ambient) = record.ambient.as_mut() {
+            ambient.ambient_temp = self.temp(ambient.ambient_temp);
+        }
+        record
+    }
+}
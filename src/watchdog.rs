@@ -0,0 +1,252 @@
+use crate::{
+    i18n::Localized,
+    notify::{raise_health_alert, HealthAlert},
+    state::AppState,
+    thresholds::Severity,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+// --- VIGILANCIA DEL PROPIO SERVIDOR ---
+// Un sistema de monitorización que se degrada en silencio deja de avisar justo cuando
+// hace falta. El watchdog mide el retraso del bucle de eventos (cuánto se pasa de hora un
+// sleep corto) y cuenta los fallos internos que de otro modo solo quedan en el log:
+// escrituras en disco fallidas (todas pasan por storage::write_durable o
+// storage::append_jsonl), errores guardando el catálogo de capturas (la base de datos
+// del servidor) y notificaciones que agotaron sus reintentos. Cada
+// CHECK_INTERVAL_SEC compara con la comprobación anterior y, con watchdog en la
+// configuración, avisa por sus canales de cada tipo de fallo nuevo (como mucho una vez
+// cada repeat_after_sec) y de su recuperación tras RECOVERY_CHECKS comprobaciones
+// limpias. Los contadores salen también en /metrics y el estado en GET /api/watchdog.
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+const CHECK_INTERVAL_SEC: u64 = 60;
+// Comprobaciones seguidas sin fallos para dar un problema por resuelto
+const RECOVERY_CHECKS: u32 = 5;
+// Retraso del bucle tolerado sin watchdog en la configuración
+const DEFAULT_MAX_LOOP_LAG_MS: u64 = 500;
+
+fn default_max_loop_lag_ms() -> u64 {
+    DEFAULT_MAX_LOOP_LAG_MS
+}
+
+fn default_repeat_after_sec() -> u64 {
+    3600
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WatchdogConfig {
+    // Canales de los avisos internos (los mismos de las alertas térmicas)
+    pub channels: Vec<String>,
+    // Retraso de una sonda del bucle de eventos a partir del cual cuenta como fallo
+    #[serde(default = "default_max_loop_lag_ms")]
+    pub max_loop_lag_ms: u64,
+    // Segundos mínimos entre dos avisos del mismo tipo de fallo
+    #[serde(default = "default_repeat_after_sec")]
+    pub repeat_after_sec: u64,
+}
+
+impl WatchdogConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_loop_lag_ms == 0 {
+            return Err("watchdog: max_loop_lag_ms must be positive".into());
+        }
+        if self.repeat_after_sec < CHECK_INTERVAL_SEC {
+            return Err(format!("watchdog: repeat_after_sec must be at least {}", CHECK_INTERVAL_SEC));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    EventLoopLag,
+    DiskWrite,
+    Catalog,
+    Notification,
+}
+
+const FAULTS: [Fault; 4] = [Fault::EventLoopLag, Fault::DiskWrite, Fault::Catalog, Fault::Notification];
+
+// Fallos de cada tipo desde el arranque, indexados por Fault; son globales porque los
+// registran funciones de almacenamiento que no reciben el estado
+static FAULT_COUNTS: [AtomicU64; FAULTS.len()] = [const { AtomicU64::new(0) }; FAULTS.len()];
+
+impl Fault {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Fault::EventLoopLag => "event_loop_lag",
+            Fault::DiskWrite => "disk_write",
+            Fault::Catalog => "catalog",
+            Fault::Notification => "notification",
+        }
+    }
+
+    // Perder datos es más grave que ir lento o no poder avisar
+    fn severity(self) -> Severity {
+        match self {
+            Fault::DiskWrite | Fault::Catalog => Severity::Critical,
+            Fault::EventLoopLag | Fault::Notification => Severity::Warning,
+        }
+    }
+
+    fn describe(self, new: u64, worst_lag_ms: u64, max_lag_ms: u64) -> Localized {
+        match self {
+            Fault::EventLoopLag => Localized::new(
+                format!("Server event loop lagging: {} probes over {} ms since the last check (worst {} ms)", new, max_lag_ms, worst_lag_ms),
+                format!("Bucle de eventos del servidor retrasado: {} sondas por encima de {} ms desde la última comprobación (peor {} ms)", new, max_lag_ms, worst_lag_ms),
+            ),
+            Fault::DiskWrite => Localized::new(
+                format!("{} failed disk writes on the server since the last check", new),
+                format!("{} escrituras en disco fallidas en el servidor desde la última comprobación", new),
+            ),
+            Fault::Catalog => Localized::new(
+                format!("{} failed saves of the capture catalog since the last check", new),
+                format!("{} errores guardando el catálogo de capturas desde la última comprobación", new),
+            ),
+            Fault::Notification => Localized::new(
+                format!("{} notifications undelivered after all retries since the last check", new),
+                format!("{} notificaciones no entregadas tras agotar los reintentos desde la última comprobación", new),
+            ),
+        }
+    }
+
+    fn recovered(self) -> Localized {
+        let (en, es) = match self {
+            Fault::EventLoopLag => ("event loop lag", "retraso del bucle de eventos"),
+            Fault::DiskWrite => ("disk write failures", "escrituras en disco fallidas"),
+            Fault::Catalog => ("capture catalog errors", "errores del catálogo de capturas"),
+            Fault::Notification => ("undelivered notifications", "notificaciones no entregadas"),
+        };
+        Localized::new(
+            format!("Server recovered: no {} in the last {} checks", en, RECOVERY_CHECKS),
+            format!("Servidor recuperado: sin {} en las últimas {} comprobaciones", es, RECOVERY_CHECKS),
+        )
+    }
+}
+
+pub fn record_fault(fault: Fault) {
+    FAULT_COUNTS[fault as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn fault_counts() -> BTreeMap<Fault, u64> {
+    FAULTS.iter().map(|&f| (f, FAULT_COUNTS[f as usize].load(Ordering::Relaxed))).collect()
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DegradedFault {
+    pub fault: Fault,
+    // Primera comprobación en la que apareció
+    pub since: u64,
+    // Fallos nuevos en la última comprobación
+    pub new_faults: u64,
+    // Comprobaciones seguidas sin fallos nuevos
+    pub clean_checks: u32,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct WatchdogStatus {
+    pub checked_at: u64,
+    pub healthy: bool,
+    // Peor retraso del bucle de eventos en la última ventana
+    pub loop_lag_ms: u64,
+    // Totales desde el arranque
+    pub faults: BTreeMap<Fault, u64>,
+    pub degraded: Vec<DegradedFault>,
+}
+
+// Sonda del bucle de eventos cada PROBE_INTERVAL y comprobación cada CHECK_INTERVAL_SEC
+pub fn spawn_watchdog(state: &Arc<AppState>) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut previous = fault_counts();
+        let mut degraded: BTreeMap<Fault, DegradedFault> = BTreeMap::new();
+        let mut last_alerted: HashMap<Fault, u64> = HashMap::new();
+        let mut worst_lag = Duration::ZERO;
+        let mut last_check = Instant::now();
+        loop {
+            let start = Instant::now();
+            tokio::time::sleep(PROBE_INTERVAL).await;
+            // Lo que tarda en volver a ejecutarse la tarea tras vencer el sleep
+            let lag = start.elapsed().saturating_sub(PROBE_INTERVAL);
+            worst_lag = worst_lag.max(lag);
+            let max_lag_ms = state.config.read().await.watchdog.as_ref().map_or(DEFAULT_MAX_LOOP_LAG_MS, |w| w.max_loop_lag_ms);
+            if lag.as_millis() as u64 > max_lag_ms {
+                record_fault(Fault::EventLoopLag);
+            }
+            if last_check.elapsed() < Duration::from_secs(CHECK_INTERVAL_SEC) {
+                continue;
+            }
+            last_check = Instant::now();
+            let current = fault_counts();
+            check(&state, &previous, &current, worst_lag.as_millis() as u64, &mut degraded, &mut last_alerted).await;
+            previous = current;
+            worst_lag = Duration::ZERO;
+        }
+    });
+}
+
+async fn check(
+    state: &AppState,
+    previous: &BTreeMap<Fault, u64>,
+    current: &BTreeMap<Fault, u64>,
+    worst_lag_ms: u64,
+    degraded: &mut BTreeMap<Fault, DegradedFault>,
+    last_alerted: &mut HashMap<Fault, u64>,
+) {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let config = state.config.read().await.watchdog.clone();
+    let max_lag_ms = config.as_ref().map_or(DEFAULT_MAX_LOOP_LAG_MS, |w| w.max_loop_lag_ms);
+    let mut alerts = Vec::new();
+    for fault in FAULTS {
+        let new = current[&fault].saturating_sub(previous.get(&fault).copied().unwrap_or(0));
+        if new == 0 {
+            let recovered = degraded.get_mut(&fault).is_some_and(|d| {
+                d.new_faults = 0;
+                d.clean_checks += 1;
+                d.clean_checks >= RECOVERY_CHECKS
+            });
+            if recovered {
+                degraded.remove(&fault);
+                tracing::info!(fault = fault.as_str(), "✅ El servidor se ha recuperado de un fallo interno");
+                // Solo se avisa de la recuperación de lo que se llegó a avisar
+                if last_alerted.remove(&fault).is_some() {
+                    alerts.push(HealthAlert::new(fault.as_str(), Severity::Warning, true, fault.recovered()));
+                }
+            }
+            continue;
+        }
+        let message = fault.describe(new, worst_lag_ms, max_lag_ms);
+        tracing::error!(fault = fault.as_str(), new, message = %message.es, "🩺 Servidor degradado");
+        let entry = degraded.entry(fault).or_insert(DegradedFault { fault, since: now, new_faults: 0, clean_checks: 0 });
+        entry.new_faults = new;
+        entry.clean_checks = 0;
+        let Some(config) = &config else { continue };
+        if last_alerted.get(&fault).is_some_and(|&at| now < at.saturating_add(config.repeat_after_sec)) {
+            continue;
+        }
+        last_alerted.insert(fault, now);
+        alerts.push(HealthAlert::new(fault.as_str(), fault.severity(), false, message));
+    }
+
+    *state.watchdog.write().await = WatchdogStatus {
+        checked_at: now,
+        healthy: degraded.values().all(|d| d.new_faults == 0),
+        loop_lag_ms: worst_lag_ms,
+        faults: current.clone(),
+        degraded: degraded.values().cloned().collect(),
+    };
+    if let Some(config) = &config {
+        for alert in alerts {
+            raise_health_alert(state, &config.channels, alert).await;
+        }
+    }
+}
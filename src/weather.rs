@@ -0,0 +1,155 @@
+use crate::state::{AppState, RemoteConfig};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+// --- CLIMA Y TEMPERATURA AMBIENTE ---
+// Cada sitio (grupo de turbinas) tiene una lectura de ambiente que llega por dos vías:
+// consultando una API meteorológica (Open-Meteo, sin clave) con sus coordenadas, o
+// empujada por un sensor local a /ingest/ambient. La lectura se guarda con cada
+// captura y alerta y desplaza los umbrales según la compensación configurada.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WeatherSite {
+    pub name: String,
+    // Tokens de las turbinas del sitio
+    pub turbines: Vec<String>,
+    // Coordenadas para la API meteorológica (sin ellas solo se usan sensores locales)
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AmbientSource {
+    Api,
+    Sensor,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AmbientReading {
+    pub ambient_temp: f32,
+    // m/s
+    #[serde(default)]
+    pub wind_speed: Option<f32>,
+    pub source: AmbientSource,
+    pub timestamp: u64,
+}
+
+fn default_reference_temp() -> f32 {
+    15.0
+}
+
+fn default_max_age_sec() -> u64 {
+    7200
+}
+
+// Compensación: los umbrales suben `temp_factor` grados por cada grado de ambiente sobre
+// la referencia y bajan `wind_factor` grados por cada m/s de viento (el viento enfría la
+// superficie y enmascara puntos calientes).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AmbientCompensation {
+    #[serde(default = "default_reference_temp")]
+    pub reference_temp: f32,
+    #[serde(default)]
+    pub temp_factor: f32,
+    #[serde(default)]
+    pub wind_factor: f32,
+    // Lecturas más antiguas no se usan para compensar
+    #[serde(default = "default_max_age_sec")]
+    pub max_age_sec: u64,
+}
+
+impl AmbientCompensation {
+    pub fn offset(&self, reading: &AmbientReading, now: u64) -> f32 {
+        if now.saturating_sub(reading.timestamp) > self.max_age_sec {
+            return 0.0;
+        }
+        let temp = (reading.ambient_temp - self.reference_temp) * self.temp_factor;
+        let wind = reading.wind_speed.unwrap_or(0.0) * self.wind_factor;
+        temp - wind
+    }
+}
+
+impl RemoteConfig {
+    pub fn site_for_turbine(&self, turbine_token: &str) -> Option<&WeatherSite> {
+        self.weather_sites.iter().find(|s| s.turbines.iter().any(|t| t == turbine_token))
+    }
+
+    // Umbrales desplazados según la lectura de ambiente (sin cambios si no hay
+    // compensación configurada o la lectura falta o está caducada)
+    pub fn compensated(&self, ambient: Option<&AmbientReading>, now: u64) -> RemoteConfig {
+        let offset = match (&self.ambient_compensation, ambient) {
+            (Some(compensation), Some(reading)) => compensation.offset(reading, now),
+            _ => 0.0,
+        };
+        self.shifted(offset)
+    }
+}
+
+impl AppState {
+    // Última lectura de ambiente del sitio al que pertenece la turbina
+    pub async fn ambient_for_turbine(&self, turbine_token: &str) -> Option<AmbientReading> {
+        let site = self.config.read().await.site_for_turbine(turbine_token)?.name.clone();
+        self.ambient.read().await.get(&site).cloned()
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenMeteoResponse {
+    current: OpenMeteoCurrent,
+}
+
+#[derive(Deserialize)]
+struct OpenMeteoCurrent {
+    temperature_2m: f32,
+    wind_speed_10m: Option<f32>,
+}
+
+async fn fetch_site_weather(state: &AppState, latitude: f64, longitude: f64) -> Result<AmbientReading, reqwest::Error> {
+    let response: OpenMeteoResponse = state.http
+        .get(&state.settings.weather_api_url)
+        .query(&[
+            ("latitude", latitude.to_string()),
+            ("longitude", longitude.to_string()),
+            ("current", "temperature_2m,wind_speed_10m".to_string()),
+            ("wind_speed_unit", "ms".to_string()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(AmbientReading {
+        ambient_temp: response.current.temperature_2m,
+        wind_speed: response.current.wind_speed_10m,
+        source: AmbientSource::Api,
+        timestamp: chrono::Utc::now().timestamp() as u64,
+    })
+}
+
+// Consulta periódica de la API para los sitios con coordenadas
+pub fn spawn_weather_poller(state: &Arc<AppState>) {
+    if state.settings.weather_poll_minutes == 0 {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(state.settings.weather_poll_minutes * 60));
+        loop {
+            interval.tick().await;
+            let sites: Vec<WeatherSite> = state.config.read().await.weather_sites.clone();
+            for site in sites {
+                let (Some(latitude), Some(longitude)) = (site.latitude, site.longitude) else { continue };
+                match fetch_site_weather(&state, latitude, longitude).await {
+                    Ok(reading) => {
+                        tracing::debug!(site = %site.name, ambient_temp = reading.ambient_temp, "🌤️ Clima actualizado");
+                        state.ambient.write().await.insert(site.name, reading);
+                    }
+                    Err(e) => tracing::warn!(site = %site.name, error = %e, "⚠️ Error consultando el clima"),
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,320 @@
+use crate::{
+    schedule::format_timestamp,
+    state::{AlertRecord, AppState},
+    storage::alert_log::append_alerts,
+};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+// --- ÓRDENES DE TRABAJO (JIRA / SERVICENOW) ---
+// Al confirmar una alerta crítica (PUT /api/alerts/:id/confirm) se abre un ticket en el
+// sistema de mantenimiento de work_orders y su clave queda en la alerta, así el despacho
+// no depende de copiar datos desde el dashboard. Un sondeo periódico trae el estado de
+// los tickets abiertos y marca la orden como resuelta cuando el ticket se cierra.
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn default_sync_interval_sec() -> u64 {
+    300
+}
+
+fn default_issue_type() -> String {
+    "Task".into()
+}
+
+fn default_table() -> String {
+    "incident".into()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorkOrderConfig {
+    #[serde(flatten)]
+    pub system: WorkOrderSystem,
+    // Cada cuánto se consulta el estado de los tickets abiertos
+    #[serde(default = "default_sync_interval_sec")]
+    pub sync_interval_sec: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum WorkOrderSystem {
+    // Jira Cloud: usuario (email) + API token
+    Jira {
+        base_url: String,
+        project_key: String,
+        #[serde(default = "default_issue_type")]
+        issue_type: String,
+        user: String,
+        api_token: String,
+    },
+    // ServiceNow: usuario y contraseña de integración, tabla de la Table API
+    ServiceNow {
+        instance_url: String,
+        #[serde(default = "default_table")]
+        table: String,
+        user: String,
+        password: String,
+    },
+}
+
+impl WorkOrderConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let (url, required) = match &self.system {
+            WorkOrderSystem::Jira { base_url, project_key, issue_type, user, .. } => {
+                (base_url, vec![("project_key", project_key), ("issue_type", issue_type), ("user", user)])
+            }
+            WorkOrderSystem::ServiceNow { instance_url, table, user, .. } => {
+                (instance_url, vec![("table", table), ("user", user)])
+            }
+        };
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("work_orders: invalid URL '{}'", url));
+        }
+        if let Some((field, _)) = required.iter().find(|(_, value)| value.trim().is_empty()) {
+            return Err(format!("work_orders: {} must not be empty", field));
+        }
+        if self.sync_interval_sec < 60 {
+            return Err("work_orders: sync_interval_sec must be at least 60".into());
+        }
+        Ok(())
+    }
+
+    // Credencial del sistema (se guarda cifrada, ver crate::secrets)
+    pub fn secret_mut(&mut self) -> &mut String {
+        match &mut self.system {
+            WorkOrderSystem::Jira { api_token, .. } => api_token,
+            WorkOrderSystem::ServiceNow { password, .. } => password,
+        }
+    }
+}
+
+// Ticket abierto para una alerta
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorkOrder {
+    // "jira" o "servicenow"
+    pub system: String,
+    // Clave visible ("MNT-123", "INC0010042")
+    pub key: String,
+    // Id interno del sistema (id de la issue o sys_id)
+    pub id: String,
+    // Enlace al ticket
+    pub url: String,
+    // Último estado conocido en el sistema externo
+    pub status: String,
+    pub created: u64,
+    pub synced: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<u64>,
+}
+
+// Quién confirmó la alerta y cuándo
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Confirmation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub by: Option<String>,
+    pub at: u64,
+}
+
+// Configuración con la credencial en claro, solo para usarla en el momento
+async fn work_order_config(state: &AppState) -> Result<Option<WorkOrderConfig>, String> {
+    let Some(mut config) = state.config.read().await.work_orders.clone() else {
+        return Ok(None);
+    };
+    let secret = config.secret_mut();
+    *secret = state.secrets.reveal(secret).map_err(|e| e.to_string())?;
+    Ok(Some(config))
+}
+
+fn ticket_summary(alert: &AlertRecord) -> String {
+    format!("Critical thermal alert on {}: {:.1} °C at {:.1}°", alert.turbine_token, alert.max_temp, alert.angle)
+}
+
+fn ticket_description(alert: &AlertRecord) -> String {
+    let mut lines = vec![
+        format!("Turbine: {}", alert.turbine_token),
+        format!("Time: {}", format_timestamp(alert.timestamp, chrono_tz::UTC)),
+        format!("Max temperature: {:.1} °C", alert.max_temp),
+        format!("Angle: {:.1}°", alert.angle),
+        format!("Severity: {:?}", alert.severity),
+        format!("Alert id: {}", alert.id),
+    ];
+    if let Some(level) = &alert.level {
+        lines.push(format!("Level: {}", level));
+    }
+    if let Some(zone) = &alert.zone {
+        lines.push(format!("Zone: {}", zone));
+    }
+    if let Some(prediction) = &alert.fault_prediction {
+        lines.push(format!("Predicted fault: {} ({:.0}%)", prediction.label, prediction.confidence * 100.0));
+    }
+    if let Some(links) = &alert.links {
+        lines.push(format!("Image: {}", links.render));
+        lines.push(format!("Data: {}", links.download));
+    }
+    lines.join("\n")
+}
+
+async fn send_json(request: reqwest::RequestBuilder) -> Result<serde_json::Value, String> {
+    request.timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn text_field<'a>(value: &'a serde_json::Value, pointer: &str) -> Result<&'a str, String> {
+    value.pointer(pointer).and_then(|v| v.as_str()).ok_or_else(|| format!("unexpected response: missing {}", pointer))
+}
+
+// Abre el ticket de una alerta; Ok(None) si no hay sistema configurado
+pub async fn create_work_order(state: &AppState, alert: &AlertRecord) -> Result<Option<WorkOrder>, String> {
+    let Some(config) = work_order_config(state).await? else {
+        return Ok(None);
+    };
+    let now = chrono::Utc::now().timestamp() as u64;
+    let order = match &config.system {
+        WorkOrderSystem::Jira { base_url, project_key, issue_type, user, api_token } => {
+            let base = base_url.trim_end_matches('/');
+            let body = serde_json::json!({
+                "fields": {
+                    "project": { "key": project_key },
+                    "issuetype": { "name": issue_type },
+                    "summary": ticket_summary(alert),
+                    "description": ticket_description(alert),
+                    "labels": ["thermal-alert"],
+                }
+            });
+            let request = state.http.post(format!("{}/rest/api/2/issue", base)).basic_auth(user, Some(api_token)).json(&body);
+            let response = send_json(request).await?;
+            let key = text_field(&response, "/key")?.to_string();
+            WorkOrder {
+                system: "jira".into(),
+                id: text_field(&response, "/id")?.to_string(),
+                url: format!("{}/browse/{}", base, key),
+                key,
+                status: "Open".into(),
+                created: now,
+                synced: now,
+                resolved_at: None,
+            }
+        }
+        WorkOrderSystem::ServiceNow { instance_url, table, user, password } => {
+            let base = instance_url.trim_end_matches('/');
+            let body = serde_json::json!({
+                "short_description": ticket_summary(alert),
+                "description": ticket_description(alert),
+                "correlation_id": alert.id,
+                "impact": "2",
+                "urgency": "1",
+            });
+            let request = state.http.post(format!("{}/api/now/table/{}", base, table)).basic_auth(user, Some(password)).json(&body);
+            let response = send_json(request).await?;
+            let sys_id = text_field(&response, "/result/sys_id")?.to_string();
+            WorkOrder {
+                system: "servicenow".into(),
+                key: text_field(&response, "/result/number")?.to_string(),
+                url: format!("{}/nav_to.do?uri={}.do?sys_id={}", base, table, sys_id),
+                id: sys_id,
+                status: "New".into(),
+                created: now,
+                synced: now,
+                resolved_at: None,
+            }
+        }
+    };
+    Ok(Some(order))
+}
+
+// Estados de incident de ServiceNow; 6, 7 y 8 cierran el ticket
+fn servicenow_state(code: &str) -> (&'static str, bool) {
+    match code {
+        "1" => ("New", false),
+        "2" => ("In Progress", false),
+        "3" => ("On Hold", false),
+        "6" => ("Resolved", true),
+        "7" => ("Closed", true),
+        "8" => ("Canceled", true),
+        _ => ("Unknown", false),
+    }
+}
+
+// Estado actual del ticket y si ya está resuelto
+async fn fetch_status(state: &AppState, config: &WorkOrderConfig, order: &WorkOrder) -> Result<(String, bool), String> {
+    match &config.system {
+        WorkOrderSystem::Jira { base_url, user, api_token, .. } => {
+            let url = format!("{}/rest/api/2/issue/{}?fields=status", base_url.trim_end_matches('/'), order.id);
+            let response = send_json(state.http.get(url).basic_auth(user, Some(api_token))).await?;
+            let status = text_field(&response, "/fields/status/name")?.to_string();
+            let done = response.pointer("/fields/status/statusCategory/key").and_then(|v| v.as_str()) == Some("done");
+            Ok((status, done))
+        }
+        WorkOrderSystem::ServiceNow { instance_url, table, user, password } => {
+            let url = format!(
+                "{}/api/now/table/{}/{}?sysparm_fields=state",
+                instance_url.trim_end_matches('/'),
+                table,
+                order.id
+            );
+            let response = send_json(state.http.get(url).basic_auth(user, Some(password))).await?;
+            let (status, done) = servicenow_state(text_field(&response, "/result/state")?);
+            Ok((status.to_string(), done))
+        }
+    }
+}
+
+// Una pasada por los tickets abiertos de las alertas en memoria; devuelve cuántos se cerraron
+pub async fn sync_work_orders(state: &AppState) -> Result<usize, String> {
+    let Some(config) = work_order_config(state).await? else {
+        return Ok(0);
+    };
+    let open: Vec<(String, WorkOrder)> = state.alerts.read().await.iter()
+        .filter_map(|a| a.work_order.clone().map(|order| (a.id.clone(), order)))
+        .filter(|(_, order)| order.resolved_at.is_none())
+        .collect();
+    let mut resolved = 0;
+    for (alert_id, order) in open {
+        let (status, done) = match fetch_status(state, &config, &order).await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!(key = %order.key, error = %e, "⚠️ Error consultando la orden de trabajo");
+                continue;
+            }
+        };
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut alerts = state.alerts.write().await;
+        let Some(alert) = alerts.iter_mut().find(|a| a.id == alert_id) else { continue };
+        let Some(current) = alert.work_order.as_mut() else { continue };
+        current.synced = now;
+        if current.status == status && !done {
+            continue;
+        }
+        current.status = status;
+        if done {
+            current.resolved_at = Some(now);
+            resolved += 1;
+            tracing::info!(%alert_id, key = %current.key, status = %current.status, "✅ Orden de trabajo cerrada");
+        }
+        append_alerts([&*alert]);
+    }
+    Ok(resolved)
+}
+
+// Sondeo periódico del estado de los tickets (el intervalo se relee en cada vuelta)
+pub fn spawn_work_order_sync(state: &Arc<AppState>) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        loop {
+            let interval = state.config.read().await.work_orders.as_ref().map_or(default_sync_interval_sec(), |c| c.sync_interval_sec);
+            tokio::time::sleep(Duration::from_secs(interval.max(60))).await;
+            match sync_work_orders(&state).await {
+                Ok(0) => {}
+                Ok(resolved) => tracing::info!(resolved, "🛠️ Órdenes de trabajo sincronizadas"),
+                Err(e) => tracing::warn!(error = %e, "⚠️ Error sincronizando órdenes de trabajo"),
+            }
+        }
+    });
+}
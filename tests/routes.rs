@@ -0,0 +1,367 @@
+// Pruebas de las rutas HTTP contra el router completo, sin levantar el servidor.
+// El almacenamiento es relativo al directorio actual: todo el binario de pruebas trabaja
+// en una carpeta temporal propia.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use clap::Parser;
+use gcu_sentinel_cloud::{
+    build_router,
+    secrets::SecretVault,
+    state::{AlertRecord, RemoteConfig},
+    storage::{
+        catalog::CaptureRecord,
+        config_history::ConfigVersion,
+        crypto,
+        shares::{shares_dir, write_share_image, SharedSnapshot},
+        storage_root, PersistedData,
+    },
+    AppState, ServerSettings,
+};
+use std::sync::{Arc, OnceLock};
+use tower::ServiceExt;
+
+fn storage() -> std::path::PathBuf {
+    static ROOT: OnceLock<std::path::PathBuf> = OnceLock::new();
+    let dir = ROOT.get_or_init(|| {
+        let dir = std::env::temp_dir().join(format!("sentinel-routes-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+        std::fs::create_dir_all(storage_root()).unwrap();
+        // Con clave de capturas, como en producción: lo que se cifra se comprueba en disco
+        let settings = ServerSettings::parse_from(["gcu_sentinel_cloud", "--capture-key", CAPTURE_KEY]);
+        crypto::init_capture_key(&settings).unwrap();
+        dir
+    });
+    dir.join(storage_root())
+}
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+const CAPTURE_KEY: &str = "c2VudGluZWwtdGVzdC1jYXB0dXJlLWtleS0zMmJ5dGU=";
+
+// Estado vacío salvo lo que ponga `setup`
+fn state(setup: impl FnOnce(&mut PersistedData)) -> Arc<AppState> {
+    storage();
+    let settings = ServerSettings::parse_from(["gcu_sentinel_cloud", "--admin-token", ADMIN_TOKEN]);
+    let mut data = PersistedData::default();
+    setup(&mut data);
+    Arc::new(AppState::new(settings, data, SecretVault::disabled()))
+}
+
+fn app(setup: impl FnOnce(&mut PersistedData)) -> Router {
+    build_router(state(setup))
+}
+
+fn capture(filename: &str) -> CaptureRecord {
+    serde_json::from_value(serde_json::json!({
+        "filename": filename,
+        "turbine_token": "T1",
+        "timestamp": 1_700_000_000,
+        "sha256": "",
+        "size_bytes": 0,
+    }))
+    .unwrap()
+}
+
+async fn get(app: Router, uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    (status, to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec())
+}
+
+#[tokio::test]
+async fn download_serves_catalogued_captures_and_robot_logs() {
+    let root = storage();
+    std::fs::write(root.join("capture_T1_1700000000.npz"), b"capture bytes").unwrap();
+    std::fs::write(root.join("robot_log_T1.txt"), b"log line").unwrap();
+    let app = app(|data| data.catalog = vec![capture("capture_T1_1700000000.npz")]);
+
+    let (status, body) = get(app.clone(), "/api/download/capture_T1_1700000000.npz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, b"capture bytes");
+    let (status, body) = get(app, "/api/download/robot_log_T1.txt").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, b"log line");
+}
+
+#[tokio::test]
+async fn download_rejects_storage_indexes() {
+    let root = storage();
+    std::fs::write(root.join("config_history.jsonl"), br#"{"config":{"gemini_api_key":"AIza-secret"}}"#).unwrap();
+    std::fs::write(root.join("credentials.json"), b"[]").unwrap();
+    // Un archivo en disco que el catálogo no conoce tampoco se sirve
+    std::fs::write(root.join("capture_T1_1600000000.npz"), b"stray").unwrap();
+    let app = app(|_| {});
+
+    for uri in ["/api/download/config_history.jsonl", "/api/download/credentials.json", "/api/download/capture_T1_1600000000.npz"] {
+        let (status, body) = get(app.clone(), uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+        assert!(!String::from_utf8_lossy(&body).contains("AIza-secret"), "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn config_history_redacts_stored_diffs() {
+    // Versión guardada antes de enmascarar todos los secretos en las diferencias
+    let channels = serde_json::json!([{ "name": "ops", "type": "webhook", "url": "https://hooks.example/T0", "secret": "whsec_PLAINSIGNING" }]);
+    let version = ConfigVersion {
+        version: 1,
+        timestamp: 1_700_000_000,
+        author: None,
+        source: "update".into(),
+        rollback_of: None,
+        changes: serde_json::from_value(serde_json::json!([
+            { "path": "gemini_api_key", "old": null, "new": "AIza-secret" },
+            { "path": "channels", "old": [], "new": channels },
+        ]))
+        .unwrap(),
+        config: RemoteConfig {
+            gemini_api_key: Some("AIza-secret".into()),
+            channels: serde_json::from_value(channels).unwrap(),
+            ..RemoteConfig::default()
+        },
+    };
+    let app = app(|data| data.config_history = vec![version]);
+
+    for uri in ["/api/config/history", "/api/config/history/1"] {
+        let (status, body) = get(app.clone(), uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        let body = String::from_utf8(body).unwrap();
+        for secret in ["AIza-secret", "hooks.example", "whsec_PLAINSIGNING"] {
+            assert!(!body.contains(secret), "{} leaks {}", uri, secret);
+        }
+    }
+}
+
+#[tokio::test]
+async fn capture_routes_reject_encoded_subfolders() {
+    let root = storage();
+    for folder in ["quarantine", "shares", "reports", "archive"] {
+        std::fs::create_dir_all(root.join(folder)).unwrap();
+    }
+    std::fs::write(root.join("archive/capture_T1_1700000000.npz"), b"archived").unwrap();
+    std::fs::write(root.join("shares/note.txt"), b"share").unwrap();
+    // Ni siquiera un registro del catálogo con subcarpeta o que nombre una carpeta
+    let app = app(|data| data.catalog = vec![capture("archive/capture_T1_1700000000.npz"), capture("quarantine")]);
+
+    for uri in [
+        "/api/download/quarantine%2F",
+        "/api/download/quarantine",
+        "/api/download/archive%2Fcapture_T1_1700000000.npz",
+        "/api/download/shares%2Fnote.txt",
+        "/api/download/reports%2F..%2Fcatalog.json",
+        "/api/matrix/archive%2Fcapture_T1_1700000000.npz/0",
+        "/api/render/quarantine%2F/0",
+        "/api/arrays/archive%2Fcapture_T1_1700000000.npz",
+    ] {
+        let (status, _) = get(app.clone(), uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn graphql_lists_alerts_newest_first() {
+    let state = state(|data| data.catalog = vec![capture("capture_T1_1700000000.npz")]);
+    // Como raise_alert: cada alerta nueva va al principio
+    for (id, timestamp) in [("a1", 1_700_000_000), ("a2", 1_700_000_060), ("a3", 1_700_000_120)] {
+        let alert: AlertRecord = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "timestamp": timestamp,
+            "turbine_token": "T1",
+            "max_temp": 80.0,
+            "angle": 0.0,
+            "dataset_path": "capture_T1_1700000000.npz",
+        }))
+        .unwrap();
+        state.alerts.write().await.push_front(alert);
+    }
+    let query = r#"{ alerts { id } turbine(token: "T1") { alerts { id } } capture(filename: "capture_T1_1700000000.npz") { alerts { id } } }"#;
+    let request = Request::post("/api/graphql")
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::json!({ "query": query }).to_string()))
+        .unwrap();
+    let response = build_router(state).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+    let newest_first = serde_json::json!([{ "id": "a3" }, { "id": "a2" }, { "id": "a1" }]);
+    assert_eq!(body["data"]["alerts"], newest_first, "{}", body);
+    assert_eq!(body["data"]["turbine"]["alerts"], newest_first, "{}", body);
+    assert_eq!(body["data"]["capture"]["alerts"], newest_first, "{}", body);
+}
+
+fn tarball(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, *data).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+async fn restore(app: Router, backup: Vec<u8>) -> StatusCode {
+    let request = Request::post("/api/admin/restore")
+        .header("authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .body(Body::from(backup))
+        .unwrap();
+    app.oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn restore_validates_before_writing_and_persists_alerts() {
+    let root = storage();
+    let staging_left = || std::fs::read_dir(&root).unwrap().flatten().any(|e| e.file_name().to_string_lossy().starts_with(".restore-"));
+
+    // Una entrada inválida después de una captura: no se escribe nada
+    let broken = tarball(&[("captures/capture_T8_1700000000.npz", b"partial"), ("alerts.json", b"not json")]);
+    assert_eq!(restore(app(|_| {}), broken).await, StatusCode::BAD_REQUEST);
+    assert!(!root.join("capture_T8_1700000000.npz").exists());
+    assert!(!staging_left());
+
+    let alerts = serde_json::json!([
+        { "id": "r2", "timestamp": 1_700_000_060, "turbine_token": "T9", "max_temp": 90.0, "angle": 0.0, "dataset_path": "capture_T9_1700000000.npz" },
+        { "id": "r1", "timestamp": 1_700_000_000, "turbine_token": "T9", "max_temp": 85.0, "angle": 0.0, "dataset_path": "capture_T9_1700000000.npz" },
+    ]);
+    let backup = tarball(&[
+        ("alerts.json", alerts.to_string().as_bytes()),
+        ("captures/capture_T9_1700000000.npz", b"restored"),
+    ]);
+    assert_eq!(restore(app(|_| {}), backup).await, StatusCode::OK);
+    assert_eq!(std::fs::read(root.join("capture_T9_1700000000.npz")).unwrap(), b"restored");
+    assert!(!staging_left());
+    let log = std::fs::read_to_string(root.join("alerts.jsonl")).unwrap();
+    let ids: Vec<String> = log.lines()
+        .map(|line| serde_json::from_str::<AlertRecord>(line).unwrap().id)
+        .collect();
+    assert_eq!(ids, ["r1", "r2"]);
+}
+
+fn share(token: &str, turbine_token: &str) -> SharedSnapshot {
+    serde_json::from_value(serde_json::json!({
+        "token": token,
+        "created": 1_700_000_000,
+        "expires_at": u64::MAX,
+        "turbine_token": turbine_token,
+        "filename": format!("capture_{}_1700000000.npz", turbine_token),
+        "frame_index": 0,
+        "captured": 1_700_000_000,
+        "min_temp": 20.0,
+        "max_temp": 80.0,
+        "avg_temp": 40.0,
+        "units": "celsius",
+        "width": 2,
+        "height": 2,
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn share_images_are_encrypted_at_rest() {
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nshare-image";
+    storage();
+    write_share_image("s-encrypted", PNG).unwrap();
+    let on_disk = std::fs::read(shares_dir().join("s-encrypted.png")).unwrap();
+    assert!(crypto::is_encrypted(&on_disk));
+    assert!(!on_disk.windows(PNG.len()).any(|w| w == PNG));
+
+    let app = app(|data| data.shares = vec![share("s-encrypted", "T3")]);
+    let (status, body) = get(app, "/share/s-encrypted/image.png").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, PNG);
+}
+
+async fn purge(app: Router, uri: &str) -> serde_json::Value {
+    let request = Request::delete(uri)
+        .header("authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn purge_removes_shares_lineage_and_collection_memberships() {
+    storage();
+    let state = state(|data| {
+        data.shares = vec![share("s-t5", "T5"), share("s-t6", "T6")];
+        data.lineage = serde_json::from_value(serde_json::json!([
+            { "id": "d1", "operation": "render", "inputs": [{ "filename": "capture_T5_1700000000.npz" }], "created": 1 },
+            { "id": "d2", "operation": "render", "inputs": [{ "filename": "capture_T6_1700000000.npz" }], "created": 1 },
+        ]))
+        .unwrap();
+        data.collections = serde_json::from_value(serde_json::json!([{
+            "id": "c1",
+            "name": "Palas",
+            "captures": ["capture_T5_1700000000.npz", "capture_T5_1700000060.npz", "capture_T6_1700000000.npz"],
+            "created": 1,
+            "updated": 1,
+        }]))
+        .unwrap();
+    });
+
+    let dry = purge(build_router(state.clone()), "/api/turbines/T5/data?dry_run=true").await;
+    assert_eq!(dry["shares"], 1);
+    assert_eq!(dry["lineage_entries"], 1);
+    assert_eq!(dry["collection_memberships"], 2);
+    assert_eq!(state.shares.read().await.len(), 2);
+
+    let done = purge(build_router(state.clone()), "/api/turbines/T5/data").await;
+    assert_eq!(done["shares"], 1);
+    assert_eq!(done["lineage_entries"], 1);
+    assert_eq!(done["collection_memberships"], 2);
+    let shares: Vec<String> = state.shares.read().await.iter().map(|s| s.token.clone()).collect();
+    assert_eq!(shares, ["s-t6"]);
+    let lineage: Vec<String> = state.lineage.read().await.iter().map(|d| d.id.clone()).collect();
+    assert_eq!(lineage, ["d2"]);
+    assert_eq!(state.collections.read().await[0].captures, ["capture_T6_1700000000.npz"]);
+}
+
+// Cabecera npy v1 alineada a 64 bytes, como la escribe numpy
+fn npy(values: &[f32], shape: (usize, usize)) -> Vec<u8> {
+    let header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}", shape.0, shape.1);
+    let header = format!("{:<width$}\n", header, width = 117);
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+    bytes
+}
+
+async fn upload(app: Router, turbine_token: &str, dataset: Vec<u8>) -> (StatusCode, String) {
+    const BOUNDARY: &str = "sentinel-test-boundary";
+    let mut body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"turbine_token\"\r\n\r\n{t}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"angle\"\r\n\r\n0\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"dataset_file\"; filename=\"capture.npy\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n",
+        b = BOUNDARY,
+        t = turbine_token,
+    )
+    .into_bytes();
+    body.extend(dataset);
+    body.extend(format!("\r\n--{}--\r\n", BOUNDARY).into_bytes());
+    let request = Request::post("/ingest/upload")
+        .header("content-type", format!("multipart/form-data; boundary={}", BOUNDARY))
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    (status, String::from_utf8_lossy(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).to_string())
+}
+
+#[tokio::test]
+async fn upload_rejects_captures_without_finite_values() {
+    let (status, body) = upload(app(|_| {}), "T7", npy(&[f32::NAN; 4], (2, 2))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("no finite temperature values"), "{}", body);
+
+    let (status, _) = upload(app(|_| {}), "T7", npy(&[f32::NAN, 21.5, f32::NAN, 22.0], (2, 2))).await;
+    assert_eq!(status, StatusCode::CREATED);
+}